@@ -0,0 +1,18 @@
+import { readFileSync, writeFileSync } from "fs";
+import { join } from "path";
+
+function embedAsBase64(path: string): string {
+  const buffer = readFileSync(path);
+  return buffer.toString("base64");
+}
+
+const DRAWLIST_WASM_BASE64 = embedAsBase64(join(import.meta.dirname, "..", "pkg", "drawlist_bg.wasm"));
+
+const output = `// AUTO-GENERATED FILE - DO NOT EDIT
+// Generated by: bun run gen
+
+export const DRAWLIST_WASM_BASE64 = "${DRAWLIST_WASM_BASE64}";
+`;
+
+writeFileSync(join(import.meta.dirname, "..", "ts", "gen.embedded.ts"), output);
+console.log("Generated ts/gen.embedded.ts");
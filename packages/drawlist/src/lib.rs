@@ -0,0 +1,900 @@
+//! WASM draw list builder for Glade.
+//!
+//! Bakes a static screen in one call: lays out a tree of boxes with the
+//! layout engine, measuring text leaves with the shaper and SVG leaves at
+//! their native size, then tessellates each SVG at its laid out size and
+//! lays out each text block at its width. The result is a flat list of
+//! draw items in painter's order, ready for a renderer.
+
+use layout::{ClipRect, LayoutError, LayoutId, Matrix2D, StyleInput};
+use serde::{Deserialize, Serialize};
+use shaper::{FontStyleInput, LayoutOptions, LayoutResult, MetricsError, TextShaper};
+use std::collections::HashMap;
+use svg::{
+    ParseOptions, PathDataIssue, PathError, SvgTessellator, TessellateOptions, TessellatedMesh,
+};
+use taffy::Size;
+use wasm_bindgen::prelude::*;
+
+/// Taffy rounds boxes to whole pixels, so a box sized to its measured text
+/// can come out narrower than the text by up to a pixel. Text wraps this
+/// much past its box rather than breaking its last word onto a new line.
+const WRAP_SLACK: f32 = 1.0;
+
+/// A screen to bake: a layout tree laid out in a viewport.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DrawScene {
+    pub width: f32,
+    pub height: f32,
+    pub root: SceneNode,
+}
+
+/// A box in the scene's layout tree.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneNode {
+    pub style: StyleInput,
+    /// Packed 0xRRGGBBAA fill for the node's border box
+    pub background: Option<u32>,
+    /// What the node draws in its content box, under its children. A node
+    /// with content and no children is measured from its content.
+    pub content: Option<NodeContent>,
+    pub children: Vec<SceneNode>,
+}
+
+/// What a leaf of the scene draws.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NodeContent {
+    /// An SVG registered with `register_svg`, stretched to the content box.
+    /// Without a size from its style, the leaf takes the SVG's native size,
+    /// or keeps its aspect ratio when only one side is known.
+    Svg { name: String },
+    /// A block of text, wrapped to the content box
+    Text {
+        text: String,
+        font_size: f32,
+        line_height: f32,
+        #[serde(default)]
+        style: FontStyleInput,
+        /// Packed 0xRRGGBBAA, or None for the host's default text color
+        #[serde(default)]
+        color: Option<u32>,
+        /// Options for `layout_text`. Draw runs are always filled.
+        #[serde(default)]
+        options: LayoutOptions,
+    },
+}
+
+/// One thing to draw. Items come in painter's order: a node's background,
+/// then its content, then its children, depth first.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DrawItem {
+    /// Clip the items after this one to `clip`, until the next `Clip`.
+    /// Items before the first `Clip` are unclipped.
+    Clip { clip: ClipRect },
+    /// A filled rectangle, from `SceneNode::background`
+    Rect {
+        /// Index of the node in the scene, depth first
+        node: usize,
+        width: f32,
+        height: f32,
+        /// Packed 0xRRGGBBAA
+        color: u32,
+        /// Maps the rect, which starts at the origin, to the viewport
+        transform: Matrix2D,
+    },
+    /// A fill or stroke of an SVG leaf, tessellated at the content box size
+    Mesh {
+        node: usize,
+        /// Resolved RGBA color, or None for a transparent paint
+        color: Option<[u8; 4]>,
+        stroke: bool,
+        mesh: Box<TessellatedMesh>,
+        /// Maps mesh vertices, which start at the content box origin, to
+        /// the viewport
+        transform: Matrix2D,
+    },
+    /// Glyphs of one line of a text leaf that share a font and color
+    Glyphs {
+        node: usize,
+        cosmic_font_id: u64,
+        font_size: f32,
+        /// Packed 0xRRGGBBAA, or None for the host's default text color
+        color: Option<u32>,
+        glyphs: Vec<DrawGlyph>,
+        /// Maps glyph origins, relative to the content box, to the viewport
+        transform: Matrix2D,
+    },
+}
+
+/// A glyph to draw at its baseline origin.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DrawGlyph {
+    pub glyph_id: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A baked screen from `build_draw_list`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DrawList {
+    pub items: Vec<DrawItem>,
+    /// Paths of SVG leaves that could not be drawn, in node order
+    pub errors: Vec<SvgPathError>,
+}
+
+/// A path of an SVG leaf that could not be tessellated. The rest of the
+/// SVG still draws.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SvgPathError {
+    pub node: usize,
+    pub error: PathError,
+}
+
+/// Why a scene could not be baked.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawListError {
+    /// An SVG leaf names an SVG that was never registered
+    UnknownSvg(String),
+    /// An SVG could not be parsed for registering
+    InvalidSvg {
+        name: String,
+        issue: Box<PathDataIssue>,
+    },
+    Layout(LayoutError),
+    /// A text leaf's font size or line height cannot be laid out
+    Text {
+        node: usize,
+        error: MetricsError,
+    },
+}
+
+impl std::fmt::Display for DrawListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawListError::UnknownSvg(name) => write!(f, "Unknown SVG: {}", name),
+            DrawListError::InvalidSvg { name, issue } => {
+                write!(f, "Invalid SVG {}: {}", name, issue.message)
+            }
+            DrawListError::Layout(error) => write!(f, "Layout failed: {}", error),
+            DrawListError::Text { node, error } => {
+                write!(f, "Invalid text in node {}: {}", node, error)
+            }
+        }
+    }
+}
+
+impl From<LayoutError> for DrawListError {
+    fn from(error: LayoutError) -> Self {
+        DrawListError::Layout(error)
+    }
+}
+
+/// An SVG registered for SVG leaves, with its native size.
+struct SvgAsset {
+    content: String,
+    width: f32,
+    height: f32,
+}
+
+/// Bakes scenes into draw lists, keeping fonts and SVGs between calls.
+#[wasm_bindgen]
+pub struct DrawListBuilder {
+    shaper: TextShaper,
+    tessellator: SvgTessellator,
+    svgs: HashMap<String, SvgAsset>,
+}
+
+impl Default for DrawListBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl DrawListBuilder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            shaper: TextShaper::new(),
+            tessellator: SvgTessellator::new(),
+            svgs: HashMap::new(),
+        }
+    }
+
+    /// Register a font for text leaves, under its own family name, or
+    /// `name` when it has none.
+    #[wasm_bindgen]
+    pub fn register_font(&mut self, name: &str, font_data: &[u8]) -> Result<u32, JsValue> {
+        let id = self.shaper.register_font_with_name(name, font_data)?;
+        Ok(id.id())
+    }
+
+    /// Register an SVG document for SVG leaves to draw by `name`, replacing
+    /// any registered under it before.
+    #[wasm_bindgen]
+    pub fn register_svg(&mut self, name: &str, svg_content: &str) -> Result<(), JsValue> {
+        self.register_svg_native(name, svg_content)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Lay out and draw a `DrawScene`, returning a `DrawList`.
+    #[wasm_bindgen]
+    pub fn build_draw_list(&mut self, scene_js: JsValue) -> Result<JsValue, JsValue> {
+        let scene: DrawScene = serde_wasm_bindgen::from_value(scene_js)
+            .map_err(|e| JsValue::from_str(&format!("Invalid scene: {}", e)))?;
+        let list = self
+            .build_draw_list_native(&scene)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_wasm_bindgen::to_value(&list)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+}
+
+impl DrawListBuilder {
+    /// The shaper text leaves are laid out with, for registering fonts
+    /// natively.
+    pub fn shaper_mut(&mut self) -> &mut TextShaper {
+        &mut self.shaper
+    }
+
+    /// Native counterpart of `register_svg`.
+    pub fn register_svg_native(
+        &mut self,
+        name: &str,
+        svg_content: &str,
+    ) -> Result<(), DrawListError> {
+        let parsed = self
+            .tessellator
+            .parse_svg_native(svg_content, &ParseOptions::default())
+            .map_err(|issue| DrawListError::InvalidSvg {
+                name: name.to_string(),
+                issue: Box::new(issue),
+            })?;
+        self.svgs.insert(
+            name.to_string(),
+            SvgAsset {
+                content: svg_content.to_string(),
+                width: parsed.width,
+                height: parsed.height,
+            },
+        );
+        Ok(())
+    }
+
+    /// Native counterpart of `build_draw_list`.
+    pub fn build_draw_list_native(&mut self, scene: &DrawScene) -> Result<DrawList, DrawListError> {
+        let mut nodes = Vec::new();
+        flatten(&scene.root, &mut nodes);
+        for node in &nodes {
+            if let Some(NodeContent::Svg { name }) = &node.content {
+                if !self.svgs.contains_key(name) {
+                    return Err(DrawListError::UnknownSvg(name.clone()));
+                }
+            }
+        }
+
+        let mut engine = layout::TaffyLayoutEngine::new();
+        let mut ids = vec![None; nodes.len()];
+        let root = build_tree(&mut engine, &scene.root, 0, &mut ids)?;
+        let ids: Vec<LayoutId> = ids.into_iter().flatten().collect();
+        let shaper = &mut self.shaper;
+        let svgs = &self.svgs;
+        engine.compute_layout_with_measure_native(&root, scene.width, scene.height, |request| {
+            let node = nodes[request.measure_id as usize];
+            match &node.content {
+                Some(NodeContent::Text {
+                    text,
+                    font_size,
+                    line_height,
+                    style,
+                    ..
+                }) => {
+                    let max_width = request
+                        .known_width
+                        .or(Some(request.available_width).filter(|width| width.is_finite()));
+                    shaper
+                        .measure_text_native(text, *font_size, *line_height, max_width, style)
+                        .map_or(Size::ZERO, |measured| Size {
+                            width: request.known_width.unwrap_or(measured.width),
+                            height: request.known_height.unwrap_or(measured.height),
+                        })
+                }
+                Some(NodeContent::Svg { name }) => {
+                    let asset = &svgs[name];
+                    svg_size(asset, request.known_width, request.known_height)
+                }
+                None => Size::ZERO,
+            }
+        })?;
+
+        let layouts = engine.absolute_layouts(&root)?;
+        let index_of: HashMap<u64, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (id.id(), index))
+            .collect();
+        let mut items = Vec::new();
+        let mut errors = Vec::new();
+        let mut clip = ClipRect::UNBOUNDED;
+        for layout in &layouts {
+            let index = index_of[&layout.id];
+            let node = nodes[index];
+            if layout.fully_clipped || (node.background.is_none() && node.content.is_none()) {
+                continue;
+            }
+            if layout.clip != clip {
+                clip = layout.clip;
+                items.push(DrawItem::Clip { clip });
+            }
+            let border_box = translated(&layout.matrix, layout.x, layout.y);
+            if let Some(color) = node.background {
+                items.push(DrawItem::Rect {
+                    node: index,
+                    width: layout.width,
+                    height: layout.height,
+                    color,
+                    transform: border_box,
+                });
+            }
+            let insets = engine.content_insets_native(&ids[index])?;
+            let content_box = ContentBox {
+                transform: translated(&border_box, insets.left, insets.top),
+                width: (layout.width - insets.left - insets.right).max(0.0),
+                height: (layout.height - insets.top - insets.bottom).max(0.0),
+            };
+            match &node.content {
+                Some(NodeContent::Svg { name }) => draw_svg(
+                    &mut self.tessellator,
+                    index,
+                    &self.svgs[name].content,
+                    &content_box,
+                    &mut items,
+                    &mut errors,
+                ),
+                Some(NodeContent::Text {
+                    text,
+                    font_size,
+                    line_height,
+                    style,
+                    color,
+                    options,
+                }) => {
+                    let options = LayoutOptions {
+                        draw_runs: true,
+                        ..options.clone()
+                    };
+                    let laid_out = self
+                        .shaper
+                        .layout_text_native(
+                            text,
+                            *font_size,
+                            *line_height,
+                            content_box.width + WRAP_SLACK,
+                            style,
+                            &options,
+                        )
+                        .map_err(|error| DrawListError::Text { node: index, error })?;
+                    draw_text(
+                        index,
+                        *font_size,
+                        *color,
+                        &laid_out,
+                        &content_box,
+                        &mut items,
+                    );
+                }
+                None => {}
+            }
+        }
+        Ok(DrawList { items, errors })
+    }
+}
+
+/// Where a leaf's content goes: its content box, mapped to the viewport.
+struct ContentBox {
+    transform: Matrix2D,
+    width: f32,
+    height: f32,
+}
+
+/// Tessellate an SVG leaf at its content box size.
+fn draw_svg(
+    tessellator: &mut SvgTessellator,
+    node: usize,
+    svg_content: &str,
+    content_box: &ContentBox,
+    items: &mut Vec<DrawItem>,
+    errors: &mut Vec<SvgPathError>,
+) {
+    let (assets, path_errors) = tessellator.tessellate_svg_assets_checked(
+        svg_content,
+        content_box.width,
+        content_box.height,
+        &TessellateOptions::default(),
+    );
+    items.extend(assets.into_iter().map(|asset| DrawItem::Mesh {
+        node,
+        color: asset.color,
+        stroke: asset.stroke,
+        mesh: Box::new(asset.mesh),
+        transform: content_box.transform,
+    }));
+    errors.extend(
+        path_errors
+            .into_iter()
+            .map(|error| SvgPathError { node, error }),
+    );
+}
+
+/// Emit the draw runs of a text leaf laid out in its content box.
+fn draw_text(
+    node: usize,
+    font_size: f32,
+    color: Option<u32>,
+    laid_out: &LayoutResult,
+    content_box: &ContentBox,
+    items: &mut Vec<DrawItem>,
+) {
+    for line in &laid_out.lines {
+        items.extend(line.draw_runs.iter().map(|run| {
+            DrawItem::Glyphs {
+                node,
+                cosmic_font_id: run.cosmic_font_id,
+                font_size,
+                color: run.color.or(color),
+                glyphs: line.glyphs[run.glyph_start..run.glyph_end]
+                    .iter()
+                    .map(|glyph| DrawGlyph {
+                        glyph_id: glyph.glyph_id,
+                        x: glyph.x,
+                        y: line.y + glyph.y,
+                    })
+                    .collect(),
+                transform: content_box.transform,
+            }
+        }));
+    }
+}
+
+/// The scene's nodes, depth first, which is the order they are indexed in.
+fn flatten<'a>(node: &'a SceneNode, nodes: &mut Vec<&'a SceneNode>) {
+    nodes.push(node);
+    for child in &node.children {
+        flatten(child, nodes);
+    }
+}
+
+/// Create the layout nodes for `node`, which has depth-first index `index`,
+/// and its subtree, recording each node's id in `ids`.
+fn build_tree(
+    engine: &mut layout::TaffyLayoutEngine,
+    node: &SceneNode,
+    index: usize,
+    ids: &mut [Option<LayoutId>],
+) -> Result<LayoutId, LayoutError> {
+    let id = if node.content.is_some() && node.children.is_empty() {
+        engine.new_measurable_leaf_native(&node.style, index as u64)?
+    } else {
+        let mut children = Vec::with_capacity(node.children.len());
+        let mut next = index + 1;
+        for child in &node.children {
+            children.push(build_tree(engine, child, next, ids)?.id());
+            next += subtree_len(child);
+        }
+        engine.new_with_children_native(&node.style, &children)?
+    };
+    ids[index] = Some(id);
+    Ok(id)
+}
+
+fn subtree_len(node: &SceneNode) -> usize {
+    1 + node.children.iter().map(subtree_len).sum::<usize>()
+}
+
+/// An SVG leaf's size: its native size, with a known side scaling the
+/// other to keep its aspect ratio.
+fn svg_size(asset: &SvgAsset, known_width: Option<f32>, known_height: Option<f32>) -> Size<f32> {
+    let aspect = if asset.width > 0.0 && asset.height > 0.0 {
+        asset.height / asset.width
+    } else {
+        1.0
+    };
+    match (known_width, known_height) {
+        (Some(width), Some(height)) => Size { width, height },
+        (Some(width), None) => Size {
+            width,
+            height: width * aspect,
+        },
+        (None, Some(height)) => Size {
+            width: height / aspect,
+            height,
+        },
+        (None, None) => Size {
+            width: asset.width,
+            height: asset.height,
+        },
+    }
+}
+
+/// `matrix` applied after a translation by `(x, y)`.
+fn translated(matrix: &Matrix2D, x: f32, y: f32) -> Matrix2D {
+    let [a, b, c, d, e, f] = *matrix;
+    [a, b, c, d, a * x + c * y + e, b * x + d * y + f]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svg::TessellationStage;
+
+    const INTER: &[u8] = include_bytes!("../../../assets/InterVariable.ttf");
+
+    const ICON: &str = r##"<svg viewBox="0 0 24 24"><rect width="24" height="24" fill="#3366cc"/><circle cx="12" cy="12" r="6" fill="#ffffff"/></svg>"##;
+
+    fn text(text: &str, font_size: f32) -> Option<NodeContent> {
+        Some(NodeContent::Text {
+            text: text.to_string(),
+            font_size,
+            line_height: font_size * 1.25,
+            style: FontStyleInput {
+                family: Some("Inter".to_string()),
+                ..Default::default()
+            },
+            color: Some(0x111111ff),
+            options: LayoutOptions::default(),
+        })
+    }
+
+    fn style(json: &str) -> StyleInput {
+        serde_json::from_str(json).unwrap()
+    }
+
+    /// Each item's kind and node, with `usize::MAX` for clips.
+    fn kinds(list: &DrawList) -> Vec<(&'static str, usize)> {
+        list.items
+            .iter()
+            .map(|item| match item {
+                DrawItem::Clip { .. } => ("clip", usize::MAX),
+                DrawItem::Rect { node, .. } => ("rect", *node),
+                DrawItem::Mesh { node, .. } => ("mesh", *node),
+                DrawItem::Glyphs { node, .. } => ("glyphs", *node),
+            })
+            .collect()
+    }
+
+    fn rect(json: &str, background: u32) -> SceneNode {
+        SceneNode {
+            style: style(json),
+            background: Some(background),
+            ..Default::default()
+        }
+    }
+
+    fn svg(name: &str) -> Option<NodeContent> {
+        Some(NodeContent::Svg {
+            name: name.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_card_bakes_to_an_ordered_draw_list() {
+        let mut builder = DrawListBuilder::new();
+        builder
+            .shaper_mut()
+            .register_font_with_name("Inter", INTER)
+            .unwrap();
+        builder.register_svg_native("icon", ICON).unwrap();
+
+        let body = "Cards combine an icon, a title and a body that wraps onto \
+                    several lines when it is longer than the card is wide.";
+        let scene = DrawScene {
+            width: 800.0,
+            height: 600.0,
+            root: SceneNode {
+                style: style(
+                    r#"{"display": "flex", "flex_direction": "column", "width": 240,
+                        "padding_top": 16, "padding_right": 16, "padding_bottom": 16,
+                        "padding_left": 16, "gap": 8}"#,
+                ),
+                background: Some(0xffffffff),
+                children: vec![
+                    SceneNode {
+                        style: style(
+                            r#"{"display": "flex", "flex_direction": "row", "gap": 8,
+                                "align_items": "center"}"#,
+                        ),
+                        children: vec![
+                            SceneNode {
+                                style: style(r#"{"width": 20}"#),
+                                content: Some(NodeContent::Svg {
+                                    name: "icon".to_string(),
+                                }),
+                                ..Default::default()
+                            },
+                            SceneNode {
+                                content: text("Title", 16.0),
+                                ..Default::default()
+                            },
+                        ],
+                        ..Default::default()
+                    },
+                    SceneNode {
+                        content: text(body, 12.0),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        };
+        let list = builder.build_draw_list_native(&scene).unwrap();
+
+        // Background, two icon fills, the title's line, then one run per body line
+        let kinds = kinds(&list);
+        let body_lines = kinds.iter().filter(|kind| **kind == ("glyphs", 4)).count();
+        assert!(body_lines >= 3, "{:?}", kinds);
+        let mut expected = vec![("rect", 0), ("mesh", 2), ("mesh", 2), ("glyphs", 3)];
+        expected.extend(std::iter::repeat_n(("glyphs", 4), body_lines));
+        assert_eq!(kinds, expected);
+
+        let DrawItem::Rect {
+            width, transform, ..
+        } = &list.items[0]
+        else {
+            unreachable!()
+        };
+        assert_eq!((*width, transform[4], transform[5]), (240.0, 0.0, 0.0));
+
+        // The icon is 20px square at the padding edge, centered on the title
+        let DrawItem::Mesh {
+            mesh,
+            color,
+            transform,
+            ..
+        } = &list.items[1]
+        else {
+            unreachable!()
+        };
+        assert_eq!(*color, Some([0x33, 0x66, 0xcc, 0xff]));
+        assert_eq!(transform[4], 16.0);
+        assert!((mesh.bounds.max_x - 20.0).abs() < 1e-3);
+        assert!((mesh.bounds.max_y - 20.0).abs() < 1e-3);
+        let icon_top = transform[5];
+
+        let DrawItem::Glyphs {
+            glyphs,
+            transform,
+            color,
+            ..
+        } = &list.items[3]
+        else {
+            unreachable!()
+        };
+        assert_eq!(glyphs.len(), 5);
+        assert_eq!(*color, Some(0x111111ff));
+        assert_eq!(transform[4], 16.0 + 20.0 + 8.0);
+        assert_eq!(transform[5], 16.0);
+        assert!(glyphs.windows(2).all(|pair| pair[0].x < pair[1].x));
+
+        // The header is as tall as the title, which sets where the body starts
+        let DrawItem::Glyphs { transform, .. } = &list.items[4] else {
+            unreachable!()
+        };
+        let header_height = transform[5] - 16.0 - 8.0;
+        assert!(header_height > 20.0);
+        assert!((icon_top - (16.0 + (header_height - 20.0) / 2.0)).abs() < 1.0);
+
+        // Body lines stack below the header, each within the content width
+        let mut last_y = f32::MIN;
+        for item in &list.items[4..] {
+            let DrawItem::Glyphs {
+                glyphs, transform, ..
+            } = item
+            else {
+                unreachable!()
+            };
+            assert_eq!(
+                (transform[4], transform[5]),
+                (16.0, 16.0 + header_height + 8.0)
+            );
+            assert!(glyphs[0].y > last_y);
+            last_y = glyphs[0].y;
+            let right = glyphs.last().unwrap().x;
+            assert!(right < 240.0 - 32.0, "{}", right);
+        }
+    }
+
+    #[test]
+    fn test_unknown_svg_is_an_error() {
+        let mut builder = DrawListBuilder::new();
+        builder.register_svg_native("icon", ICON).unwrap();
+        let scene = DrawScene {
+            width: 100.0,
+            height: 100.0,
+            root: SceneNode {
+                children: vec![
+                    SceneNode {
+                        content: svg("icon"),
+                        ..Default::default()
+                    },
+                    SceneNode {
+                        content: svg("missing"),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            builder.build_draw_list_native(&scene),
+            Err(DrawListError::UnknownSvg("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_clip_items_mark_entering_and_leaving_a_clipping_box() {
+        let mut builder = DrawListBuilder::new();
+        let scene = DrawScene {
+            width: 200.0,
+            height: 200.0,
+            root: SceneNode {
+                style: style(r#"{"display": "flex", "flex_direction": "column"}"#),
+                children: vec![
+                    SceneNode {
+                        style: style(
+                            r#"{"display": "flex", "flex_direction": "column",
+                                "width": 50, "height": 40, "overflow": "hidden"}"#,
+                        ),
+                        background: Some(0xeeeeeeff),
+                        children: vec![
+                            rect(
+                                r#"{"width": 80, "height": 30, "flex_shrink": 0}"#,
+                                0xff0000ff,
+                            ),
+                            rect(
+                                r#"{"width": 80, "height": 30, "flex_shrink": 0}"#,
+                                0x00ff00ff,
+                            ),
+                        ],
+                        ..Default::default()
+                    },
+                    rect(r#"{"width": 20, "height": 20}"#, 0x0000ffff),
+                ],
+                ..Default::default()
+            },
+        };
+        let list = builder.build_draw_list_native(&scene).unwrap();
+        assert_eq!(
+            kinds(&list),
+            vec![
+                ("rect", 1),
+                ("clip", usize::MAX),
+                ("rect", 2),
+                ("rect", 3),
+                ("clip", usize::MAX),
+                ("rect", 4),
+            ]
+        );
+        let clips: Vec<ClipRect> = list
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                DrawItem::Clip { clip } => Some(*clip),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            clips,
+            vec![
+                ClipRect {
+                    min_x: 0.0,
+                    min_y: 0.0,
+                    max_x: 50.0,
+                    max_y: 40.0,
+                },
+                ClipRect::UNBOUNDED,
+            ]
+        );
+    }
+
+    /// The content box an SVG leaf was sized to, from its background.
+    fn svg_leaf_size(builder: &mut DrawListBuilder, leaf_style: &str) -> (f32, f32) {
+        let scene = DrawScene {
+            width: 400.0,
+            height: 400.0,
+            root: SceneNode {
+                style: style(r#"{"display": "flex", "align_items": "start"}"#),
+                children: vec![SceneNode {
+                    style: style(leaf_style),
+                    background: Some(0xffffffff),
+                    content: svg("wide"),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        };
+        let list = builder.build_draw_list_native(&scene).unwrap();
+        let DrawItem::Rect { width, height, .. } = list.items[0] else {
+            unreachable!()
+        };
+        (width, height)
+    }
+
+    const WIDE: &str = r##"<svg width="40" height="10" viewBox="0 0 40 10"><rect width="40" height="10" fill="#000000"/></svg>"##;
+
+    #[test]
+    fn test_svg_leaf_takes_its_native_size() {
+        let mut builder = DrawListBuilder::new();
+        builder.register_svg_native("wide", WIDE).unwrap();
+        assert_eq!(svg_leaf_size(&mut builder, "{}"), (40.0, 10.0));
+    }
+
+    #[test]
+    fn test_svg_leaf_keeps_its_aspect_from_a_known_width() {
+        let mut builder = DrawListBuilder::new();
+        builder.register_svg_native("wide", WIDE).unwrap();
+        assert_eq!(
+            svg_leaf_size(&mut builder, r#"{"width": 80}"#),
+            (80.0, 20.0)
+        );
+    }
+
+    #[test]
+    fn test_svg_leaf_keeps_its_aspect_from_a_known_height() {
+        let mut builder = DrawListBuilder::new();
+        builder.register_svg_native("wide", WIDE).unwrap();
+        assert_eq!(
+            svg_leaf_size(&mut builder, r#"{"height": 30}"#),
+            (120.0, 30.0)
+        );
+    }
+
+    #[test]
+    fn test_node_with_content_draws_its_children_over_it() {
+        let mut builder = DrawListBuilder::new();
+        builder.register_svg_native("icon", ICON).unwrap();
+        let scene = DrawScene {
+            width: 100.0,
+            height: 100.0,
+            root: SceneNode {
+                style: style(r#"{"width": 48, "height": 48}"#),
+                content: svg("icon"),
+                children: vec![rect(r#"{"width": 10, "height": 10}"#, 0xff0000ff)],
+                ..Default::default()
+            },
+        };
+        let list = builder.build_draw_list_native(&scene).unwrap();
+        assert_eq!(kinds(&list), vec![("mesh", 0), ("mesh", 0), ("rect", 1)]);
+        let DrawItem::Mesh { mesh, .. } = &list.items[0] else {
+            unreachable!()
+        };
+        assert!((mesh.bounds.max_x - 48.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_svg_path_errors_are_reported_on_the_draw_list() {
+        let mut builder = DrawListBuilder::new();
+        let broken = r##"<svg viewBox="0 0 10 10"><path d="M0 0 L10 0 L10 10 Z"/><path d="M1 1 L9 1 L9 9 Z" transform="scale(1e38)"/></svg>"##;
+        builder.register_svg_native("broken", broken).unwrap();
+        let scene = DrawScene {
+            width: 100.0,
+            height: 100.0,
+            root: SceneNode {
+                style: style(r#"{"width": 10, "height": 10}"#),
+                content: svg("broken"),
+                ..Default::default()
+            },
+        };
+        let list = builder.build_draw_list_native(&scene).unwrap();
+        // The overflowing path is reported and the other still draws
+        assert_eq!(kinds(&list), vec![("mesh", 0)]);
+        let failed: Vec<_> = list
+            .errors
+            .iter()
+            .map(|error| (error.node, error.error.path_index, error.error.stage))
+            .collect();
+        assert_eq!(failed, vec![(0, 1, TessellationStage::Fill)]);
+    }
+}
@@ -0,0 +1,280 @@
+/**
+ * @glade/drawlist - WASM draw list builder
+ *
+ * Bakes a static screen in one call: lays out a tree of boxes, measures and
+ * lays out its text, tessellates its SVGs at their laid out size, and returns
+ * a flat list of draw items in painter's order.
+ */
+
+import { type ClipRect, type StyleInput, styleToWasm } from "@glade/layout";
+import { log } from "@glade/logging";
+import {
+  type FontStyleOptions,
+  type LayoutOptions,
+  layoutOptionsToWasm,
+  styleToWasm as fontStyleToWasm,
+} from "@glade/shaper";
+import {
+  convertMesh,
+  type PathDataIssue,
+  type PathError,
+  type RawMesh,
+  type TessellatedMesh,
+  type TessellationStage,
+} from "@glade/svg";
+import { base64ToBytes, formatBytes } from "@glade/utils";
+
+import {
+  DrawListBuilder as WasmDrawListBuilder,
+  type InitOutput,
+  initSync,
+} from "../pkg/drawlist";
+import { DRAWLIST_WASM_BASE64 } from "./gen.embedded";
+
+export class DrawListBuilder extends WasmDrawListBuilder {
+  readonly module: InitOutput;
+
+  constructor(module: InitOutput) {
+    super();
+    this.module = module;
+  }
+}
+
+/**
+ * Create a new draw list builder. Register fonts and SVGs on it before
+ * building draw lists.
+ */
+export function createDrawListBuilder(): DrawListBuilder {
+  const wasmBytes = base64ToBytes(DRAWLIST_WASM_BASE64);
+  log.info(`draw list embedded WASM binary is ${formatBytes(wasmBytes.byteLength)}`);
+  const module = initSync({ module: wasmBytes });
+  return new DrawListBuilder(module);
+}
+
+export type { InitOutput };
+
+/**
+ * Row-major 2D affine matrix [a, b, c, d, e, f], mapping (x, y) to
+ * (a * x + c * y + e, b * x + d * y + f).
+ */
+export type Matrix2D = [number, number, number, number, number, number];
+
+/**
+ * What a leaf of the scene draws.
+ */
+export type NodeContent =
+  /**
+   * An SVG registered with registerSvg, stretched to the content box. Without
+   * a size from its style, the leaf takes the SVG's native size, or keeps its
+   * aspect ratio when only one side is known.
+   */
+  | { kind: "svg"; name: string }
+  /** A block of text, wrapped to the content box */
+  | {
+      kind: "text";
+      text: string;
+      fontSize: number;
+      lineHeight: number;
+      style?: FontStyleOptions;
+      /** Packed 0xRRGGBBAA, or undefined for the host's default text color */
+      color?: number;
+      options?: LayoutOptions;
+    };
+
+/**
+ * A box in the scene's layout tree. Its content draws under its children; a
+ * node with content and no children is measured from its content.
+ */
+export interface SceneNode {
+  style?: StyleInput;
+  /** Packed 0xRRGGBBAA fill for the node's border box */
+  background?: number;
+  content?: NodeContent;
+  children?: SceneNode[];
+}
+
+/**
+ * A screen to bake: a layout tree laid out in a viewport.
+ */
+export interface DrawScene {
+  width: number;
+  height: number;
+  root: SceneNode;
+}
+
+/**
+ * A glyph to draw at its baseline origin.
+ */
+export interface DrawGlyph {
+  glyphId: number;
+  x: number;
+  y: number;
+}
+
+/**
+ * One thing to draw, in painter's order: a node's background, then its
+ * content, then its children, depth first. `node` is the node's index in the
+ * scene, depth first.
+ */
+export type DrawItem =
+  /** Clip the items after this one to clip, until the next clip item */
+  | { kind: "clip"; clip: ClipRect }
+  /** A filled rectangle; transform maps it from the origin to the viewport */
+  | {
+      kind: "rect";
+      node: number;
+      width: number;
+      height: number;
+      color: number;
+      transform: Matrix2D;
+    }
+  /** A fill or stroke of an SVG leaf, tessellated at the content box size */
+  | {
+      kind: "mesh";
+      node: number;
+      /** Resolved RGBA color, or undefined for a transparent paint */
+      color?: [number, number, number, number];
+      stroke: boolean;
+      mesh: TessellatedMesh;
+      transform: Matrix2D;
+    }
+  /** Glyphs of one line of a text leaf that share a font and color */
+  | {
+      kind: "glyphs";
+      node: number;
+      cosmicFontId: number;
+      fontSize: number;
+      /** Packed 0xRRGGBBAA, or undefined for the host's default text color */
+      color?: number;
+      glyphs: DrawGlyph[];
+      transform: Matrix2D;
+    };
+
+/** A path of an SVG leaf that could not be tessellated */
+export interface SvgPathError {
+  node: number;
+  error: PathError;
+}
+
+/**
+ * A baked screen. Paths of SVG leaves that failed to tessellate are left out
+ * of items and listed in errors; the rest of each SVG still draws.
+ */
+export interface DrawList {
+  items: DrawItem[];
+  errors: SvgPathError[];
+}
+
+function contentToWasm(content: NodeContent): Record<string, unknown> {
+  if (content.kind === "svg") {
+    return content;
+  }
+  return {
+    kind: "text",
+    text: content.text,
+    font_size: content.fontSize,
+    line_height: content.lineHeight,
+    style: fontStyleToWasm(content.style ?? {}),
+    color: content.color,
+    options: layoutOptionsToWasm(content.options ?? {}),
+  };
+}
+
+function nodeToWasm(node: SceneNode): Record<string, unknown> {
+  // Only send set fields; serde rejects an explicit undefined
+  const wasmNode: Record<string, unknown> = {};
+  if (node.style !== undefined) {
+    wasmNode.style = styleToWasm(node.style);
+  }
+  if (node.background !== undefined) {
+    wasmNode.background = node.background;
+  }
+  if (node.content !== undefined) {
+    wasmNode.content = contentToWasm(node.content);
+  }
+  if (node.children !== undefined) {
+    wasmNode.children = node.children.map(nodeToWasm);
+  }
+  return wasmNode;
+}
+
+type RawDrawItem =
+  | { kind: "clip"; clip: ClipRect }
+  | {
+      kind: "rect";
+      node: number;
+      width: number;
+      height: number;
+      color: number;
+      transform: Matrix2D;
+    }
+  | {
+      kind: "mesh";
+      node: number;
+      color?: [number, number, number, number];
+      stroke: boolean;
+      mesh: RawMesh;
+      transform: Matrix2D;
+    }
+  | {
+      kind: "glyphs";
+      node: number;
+      cosmic_font_id: number;
+      font_size: number;
+      color?: number;
+      glyphs: { glyph_id: number; x: number; y: number }[];
+      transform: Matrix2D;
+    };
+
+function convertItem(item: RawDrawItem): DrawItem {
+  switch (item.kind) {
+    case "clip":
+    case "rect":
+      return item;
+    case "mesh":
+      return { ...item, mesh: convertMesh(item.mesh) };
+    case "glyphs":
+      return {
+        kind: "glyphs",
+        node: item.node,
+        cosmicFontId: item.cosmic_font_id,
+        fontSize: item.font_size,
+        color: item.color,
+        glyphs: item.glyphs.map((glyph) => ({ glyphId: glyph.glyph_id, x: glyph.x, y: glyph.y })),
+        transform: item.transform,
+      };
+  }
+}
+
+interface RawSvgPathError {
+  node: number;
+  error: {
+    path_index: number;
+    stage: TessellationStage;
+    message: string;
+    issue?: PathDataIssue;
+  };
+}
+
+/**
+ * Lay out, shape and tessellate a scene into draw items.
+ */
+export function buildDrawList(builder: DrawListBuilder, scene: DrawScene): DrawList {
+  const result = builder.build_draw_list({
+    width: scene.width,
+    height: scene.height,
+    root: nodeToWasm(scene.root),
+  }) as { items: RawDrawItem[]; errors: RawSvgPathError[] };
+  return {
+    items: result.items.map(convertItem),
+    errors: result.errors.map(({ node, error }) => ({
+      node,
+      error: {
+        pathIndex: error.path_index,
+        stage: error.stage,
+        message: error.message,
+        issue: error.issue,
+      },
+    })),
+  };
+}
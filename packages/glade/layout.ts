@@ -9,6 +9,8 @@
 
 import {
   createLayoutEngine,
+  type EdgeSizes,
+  type ExclusionRect,
   type LayoutId as WasmLayoutId,
   type MeasureCallback,
   type StyleInput,
@@ -44,6 +46,15 @@ export function maxContent(): AvailableSpaceValue {
   return { type: "max-content" };
 }
 
+function unscaleEdges(edges: EdgeSizes, scaleFactor: number): EdgeSizes {
+  return {
+    top: edges.top / scaleFactor,
+    right: edges.right / scaleFactor,
+    bottom: edges.bottom / scaleFactor,
+    left: edges.left / scaleFactor,
+  };
+}
+
 /**
  * Glade layout engine wrapping Taffy WASM.
  */
@@ -158,7 +169,9 @@ export class GladeLayoutEngine {
       knownW: number,
       knownH: number,
       availW: number,
-      availH: number
+      availH: number,
+      padding: EdgeSizes,
+      border: EdgeSizes
     ) => {
       // Convert from scaled to logical coordinates for measurement
       const result = measureCallback(
@@ -166,7 +179,9 @@ export class GladeLayoutEngine {
         Number.isNaN(knownW) ? knownW : knownW / sf,
         Number.isNaN(knownH) ? knownH : knownH / sf,
         Number.isFinite(availW) ? availW / sf : availW,
-        Number.isFinite(availH) ? availH / sf : availH
+        Number.isFinite(availH) ? availH / sf : availH,
+        unscaleEdges(padding, sf),
+        unscaleEdges(border, sf)
       );
       // Convert result back to scaled coordinates
       return {
@@ -216,6 +231,45 @@ export class GladeLayoutEngine {
     return bounds;
   }
 
+  /**
+   * Set rectangles, relative to the container, that its content flows
+   * around. Measure callbacks query availableRect to wrap around them.
+   */
+  setExclusions(containerId: LayoutId, rects: Bounds[]): void {
+    const wasmId = this.layoutIdToWasmId.get(containerId);
+    if (!wasmId) {
+      throw new Error(`Unknown layout ID: ${containerId}`);
+    }
+    const sf = this.scaleFactor;
+    const scaled: ExclusionRect[] = rects.map((rect) => ({
+      x: rect.x * sf,
+      y: rect.y * sf,
+      width: rect.width * sf,
+      height: rect.height * sf,
+    }));
+    this.engine.set_exclusions(wasmId, scaled);
+  }
+
+  /**
+   * The widest span of a child left free by its parent's exclusions over a
+   * horizontal band measured from the child's top, relative to the child.
+   * Uses the most recent layout.
+   */
+  availableRect(childId: LayoutId, bandY: number, bandHeight: number): Bounds {
+    const wasmId = this.layoutIdToWasmId.get(childId);
+    if (!wasmId) {
+      throw new Error(`Unknown layout ID: ${childId}`);
+    }
+    const sf = this.scaleFactor;
+    const rect = this.engine.available_rect(wasmId, bandY * sf, bandHeight * sf);
+    return {
+      x: rect.x / sf,
+      y: rect.y / sf,
+      width: rect.width / sf,
+      height: rect.height / sf,
+    };
+  }
+
   /**
    * Clear all layout nodes and reset state.
    */
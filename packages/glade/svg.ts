@@ -9,6 +9,7 @@
  * - High-quality tessellation with fine tolerance
  */
 
+import { log } from "@glade/logging";
 import {
   createSvgTessellator,
   parseSvg as parseSvgWasm,
@@ -223,8 +224,16 @@ export class SvgElement extends GladeElement<SvgRequestState, SvgPrepaintState>
     let cachedPaths = globalSvgCache.get(this.svgContent, displayWidth, displayHeight);
 
     if (!cachedPaths) {
-      const meshes = tessellateSvg(getTessellator(), this.svgContent, displayWidth, displayHeight);
-      cachedPaths = meshes.map(meshToCachedPath);
+      const { meshes, errors } = tessellateSvg(
+        getTessellator(),
+        this.svgContent,
+        displayWidth,
+        displayHeight
+      );
+      for (const error of errors) {
+        log.warn(`SVG path ${error.pathIndex} failed to ${error.stage}: ${error.message}`);
+      }
+      cachedPaths = meshes.map((entry) => meshToCachedPath(entry.mesh));
       globalSvgCache.set(this.svgContent, displayWidth, displayHeight, cachedPaths);
     }
 
@@ -1865,9 +1865,10 @@ function layoutDecoratedLines(
         // want here since baselineY calculation already adds lineHeight.
         const firstLine = layoutResult.lines[0];
         const firstLineY = firstLine ? firstLine.y : 0;
+        // Carets go after trailing spaces, so keep their width
         lines = layoutResult.lines.map((line) => ({
           glyphs: line.glyphs,
-          width: line.width,
+          width: line.widthWithTrailingWhitespace,
           y: line.y - firstLineY,
           lineHeight: line.lineHeight,
         }));
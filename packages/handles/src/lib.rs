@@ -0,0 +1,248 @@
+//! Generation-tagged handles for values freed from JS.
+//!
+//! JS holds handles as plain numbers, so a handle can outlive its value and
+//! be passed back after its slot has been reused. Each slot counts how
+//! often it has been freed, and a handle carries the count from when it
+//! was issued: a handle to a freed value never reaches the slot's next
+//! value. The layout engine keeps registered styles this way, and the svg
+//! tessellator keeps progressive tessellations.
+
+/// Handles keep their generation below this, so that a handle stays under
+/// 2^53 and survives a round trip through a JS number.
+const GENERATION_LIMIT: u32 = 1 << 21;
+
+/// A handle that names no live value: its value was freed, or the registry
+/// never issued it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaleHandle {
+    pub handle: u64,
+    /// The handle was issued and its value has since been freed
+    pub freed: bool,
+}
+
+impl std::fmt::Display for StaleHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (index, generation) = split(self.handle);
+        let reason = if self.freed { "freed" } else { "never issued" };
+        write!(
+            f,
+            "Stale handle {} (slot {}, generation {}, {})",
+            self.handle, index, generation, reason
+        )
+    }
+}
+
+fn split(handle: u64) -> (u32, u32) {
+    (handle as u32, (handle >> 32) as u32)
+}
+
+struct Slot<T> {
+    /// Times the slot has been freed
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Values addressed by handles that go stale when the value is freed.
+/// Freed slots are reused, except that a slot freed `GENERATION_LIMIT`
+/// times is retired, so no handle is ever issued twice.
+pub struct HandleRegistry<T> {
+    slots: Vec<Slot<T>>,
+    /// Indices of empty slots, reused last freed first
+    free: Vec<u32>,
+    len: usize,
+    double_frees: u64,
+}
+
+impl<T> Default for HandleRegistry<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            double_frees: 0,
+        }
+    }
+}
+
+impl<T> HandleRegistry<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a value, returning its handle. Handles of a fresh registry
+    /// count up from zero.
+    pub fn insert(&mut self, value: T) -> u64 {
+        self.len += 1;
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.value = Some(value);
+            return u64::from(slot.generation) << 32 | u64::from(index);
+        }
+        let index = u32::try_from(self.slots.len()).expect("handle registry is full");
+        self.slots.push(Slot {
+            generation: 0,
+            value: Some(value),
+        });
+        u64::from(index)
+    }
+
+    /// The live slot `handle` names, or why there is none.
+    fn slot_index(&self, handle: u64) -> Result<usize, StaleHandle> {
+        let (index, generation) = split(handle);
+        match self.slots.get(index as usize) {
+            Some(slot) if slot.generation == generation && slot.value.is_some() => {
+                Ok(index as usize)
+            }
+            // Each free moves the slot's generation past the handle's
+            Some(slot) if slot.generation > generation => Err(StaleHandle {
+                handle,
+                freed: true,
+            }),
+            _ => Err(StaleHandle {
+                handle,
+                freed: false,
+            }),
+        }
+    }
+
+    pub fn get(&self, handle: u64) -> Result<&T, StaleHandle> {
+        let index = self.slot_index(handle)?;
+        Ok(self.slots[index].value.as_ref().unwrap())
+    }
+
+    pub fn get_mut(&mut self, handle: u64) -> Result<&mut T, StaleHandle> {
+        let index = self.slot_index(handle)?;
+        Ok(self.slots[index].value.as_mut().unwrap())
+    }
+
+    pub fn contains(&self, handle: u64) -> bool {
+        self.slot_index(handle).is_ok()
+    }
+
+    /// Free a value, returning it. Freeing a stale handle, such as one
+    /// already freed, does nothing but count it in `double_frees`.
+    pub fn remove(&mut self, handle: u64) -> Option<T> {
+        let Ok(index) = self.slot_index(handle) else {
+            self.double_frees += 1;
+            return None;
+        };
+        let slot = &mut self.slots[index];
+        let value = slot.value.take();
+        slot.generation += 1;
+        if slot.generation < GENERATION_LIMIT {
+            self.free.push(index as u32);
+        }
+        self.len -= 1;
+        value
+    }
+
+    /// Free every value. Their handles go stale as if each were removed.
+    pub fn clear(&mut self) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.value.take().is_some() {
+                slot.generation += 1;
+                if slot.generation < GENERATION_LIMIT {
+                    self.free.push(index as u32);
+                }
+            }
+        }
+        self.len = 0;
+    }
+
+    /// Number of live values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of frees of stale handles, which point at a host freeing a
+    /// value twice or freeing one it never got.
+    pub fn double_frees(&self) -> u64 {
+        self.double_frees
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handles_go_stale_when_freed_and_double_frees_are_counted() {
+        let mut registry: HandleRegistry<u32> = HandleRegistry::new();
+        let mut live: Vec<(u64, u32)> = Vec::new();
+        let mut freed: Vec<u64> = Vec::new();
+        // Inserts and frees picked from a fixed linear congruential sequence
+        let mut seed: u32 = 987654321;
+        let mut next = |bound: usize| {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 8) as usize % bound
+        };
+        for value in 0..20_000u32 {
+            match next(3) {
+                0 | 1 => {
+                    let handle = registry.insert(value);
+                    assert!(live.iter().all(|(other, _)| *other != handle));
+                    assert!(!freed.contains(&handle), "handle {} reissued", handle);
+                    live.push((handle, value));
+                }
+                _ if !live.is_empty() => {
+                    let (handle, value) = live.swap_remove(next(live.len()));
+                    assert_eq!(registry.remove(handle), Some(value));
+                    freed.push(handle);
+                }
+                _ => {}
+            }
+            // A freed handle never reaches the value now in its slot
+            if let Some(&stale) = freed.get(next(freed.len().max(1))) {
+                let error = registry.get(stale).unwrap_err();
+                assert_eq!(
+                    error,
+                    StaleHandle {
+                        handle: stale,
+                        freed: true
+                    }
+                );
+            }
+        }
+        assert_eq!(registry.len(), live.len());
+        for (handle, value) in &live {
+            assert_eq!(registry.get(*handle), Ok(value));
+        }
+        assert_eq!(registry.double_frees(), 0);
+        for handle in &freed[..100] {
+            assert_eq!(registry.remove(*handle), None);
+        }
+        assert_eq!(registry.double_frees(), 100);
+        let unknown = 1 << 40 | 5_000_000;
+        assert!(!registry.get(unknown).unwrap_err().freed);
+
+        // A slot freed as often as its generation can count is retired, so
+        // handles stay exact in a JS number and are never reused
+        let mut churn: HandleRegistry<()> = HandleRegistry::new();
+        let mut last = 0;
+        for _ in 0..1 << 21 {
+            last = churn.insert(());
+            churn.remove(last);
+        }
+        assert!(last < 1 << 53);
+        assert_eq!(churn.insert(()), 1);
+    }
+
+    #[test]
+    fn test_clear_makes_every_handle_stale() {
+        let mut registry: HandleRegistry<u32> = HandleRegistry::new();
+        let handles: Vec<u64> = (0..100).map(|value| registry.insert(value)).collect();
+        registry.remove(handles[7]);
+        registry.clear();
+        assert!(registry.is_empty());
+        for handle in &handles {
+            assert!(registry.get(*handle).unwrap_err().freed);
+        }
+        let fresh: Vec<u64> = (0..100).map(|value| registry.insert(value)).collect();
+        assert!(fresh.iter().all(|handle| !handles.contains(handle)));
+        assert_eq!(registry.double_frees(), 0);
+    }
+}
@@ -0,0 +1,258 @@
+//! Layout benchmarks against a 60fps frame budget.
+//!
+//! Run with `cargo bench -p layout`. Each iteration lays out a freshly built
+//! tree so taffy's layout cache cannot turn the measurement into a no-op;
+//! tree construction happens in the untimed setup. Allocation counts are
+//! written to `bench.baseline.json` at the workspace root.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use criterion::{criterion_group, BatchSize, BenchmarkId, Criterion};
+    use layout::{DisplayKw, FlexDirectionKw, FlexWrapKw, LayoutId, StyleInput, TaffyLayoutEngine};
+    use serde_json::{json, Value};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::fs;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts allocations so the arena's effect is visible next to timings.
+    struct CountingAlloc;
+
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    fn allocations_per_call(mut f: impl FnMut()) -> usize {
+        // Warm up so retained scratch capacity is not counted
+        f();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        f();
+        ALLOCATIONS.load(Ordering::Relaxed) - before
+    }
+
+    /// Writes an allocation count into the workspace's `bench.baseline.json`,
+    /// keeping the entries other benches recorded there.
+    fn record_allocations(id: &str, allocations: usize) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../bench.baseline.json");
+        let mut baseline: Value = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_else(|| json!({}));
+        if !baseline["allocations"].is_object() {
+            baseline["allocations"] = json!({});
+        }
+        baseline["allocations"][id] = json!(allocations);
+        let text = serde_json::to_string_pretty(&baseline).unwrap();
+        fs::write(&path, text + "\n").unwrap();
+    }
+
+    type TreeBuilder = fn(usize) -> (TaffyLayoutEngine, LayoutId);
+
+    const NODE_COUNTS: [usize; 2] = [1_000, 10_000];
+
+    /// Rows mounted per iteration of the mount benchmark.
+    const MOUNT_COUNT: usize = 3_000;
+
+    /// A list row's style as JS sends it.
+    const ROW_STYLE_JSON: &str = r#"{"display": "flex", "flexDirection": "row",
+        "alignItems": "center", "height": 32, "paddingLeft": 8, "paddingRight": 8,
+        "gap": 4, "flexShrink": 0}"#;
+
+    /// Depth of each chain in the deep tree. Taffy recurses per level, so a
+    /// single 10k-deep chain would measure stack overflow rather than layout.
+    const CHAIN_DEPTH: usize = 100;
+
+    /// Fan-out per level of the wide tree.
+    const BRANCHING: usize = 10;
+
+    fn leaf_style() -> StyleInput {
+        StyleInput {
+            width: Some(24.0),
+            height: Some(16.0),
+            margin_right: Some(2.0),
+            ..Default::default()
+        }
+    }
+
+    fn container_style(direction: FlexDirectionKw) -> StyleInput {
+        StyleInput {
+            display: Some(DisplayKw::Flex),
+            flex_direction: Some(direction),
+            flex_wrap: Some(FlexWrapKw::Wrap),
+            padding_top: Some(4.0),
+            padding_right: Some(4.0),
+            padding_bottom: Some(4.0),
+            padding_left: Some(4.0),
+            gap: Some(2.0),
+            ..Default::default()
+        }
+    }
+
+    fn leaves(engine: &mut TaffyLayoutEngine, count: usize) -> Vec<u64> {
+        let style = leaf_style();
+        (0..count)
+            .map(|_| engine.new_leaf_native(&style).unwrap().id())
+            .collect()
+    }
+
+    /// One root holding every node as a direct child.
+    fn flat_tree(count: usize) -> (TaffyLayoutEngine, LayoutId) {
+        let mut engine = TaffyLayoutEngine::new();
+        let children = leaves(&mut engine, count - 1);
+        let root = engine
+            .new_with_children_native(&container_style(FlexDirectionKw::Row), &children)
+            .unwrap();
+        (engine, root)
+    }
+
+    /// Parallel chains of nested columns, each `CHAIN_DEPTH` levels deep.
+    fn deep_tree(count: usize) -> (TaffyLayoutEngine, LayoutId) {
+        let mut engine = TaffyLayoutEngine::new();
+        let style = container_style(FlexDirectionKw::Column);
+        let mut chains = Vec::new();
+        let mut remaining = count - 1;
+        while remaining > 0 {
+            let depth = remaining.min(CHAIN_DEPTH);
+            let mut node = engine.new_leaf_native(&leaf_style()).unwrap();
+            for _ in 1..depth {
+                node = engine
+                    .new_with_children_native(&style, &[node.id()])
+                    .unwrap();
+            }
+            chains.push(node.id());
+            remaining -= depth;
+        }
+        let root = engine
+            .new_with_children_native(&container_style(FlexDirectionKw::Row), &chains)
+            .unwrap();
+        (engine, root)
+    }
+
+    /// A balanced tree with `BRANCHING` children per container.
+    fn wide_tree(count: usize) -> (TaffyLayoutEngine, LayoutId) {
+        let mut engine = TaffyLayoutEngine::new();
+        let mut level = leaves(&mut engine, count - 1);
+        let mut direction = FlexDirectionKw::Row;
+        while level.len() > 1 {
+            let style = container_style(direction);
+            level = level
+                .chunks(BRANCHING)
+                .map(|group| engine.new_with_children_native(&style, group).unwrap().id())
+                .collect();
+            direction = match direction {
+                FlexDirectionKw::Row => FlexDirectionKw::Column,
+                _ => FlexDirectionKw::Row,
+            };
+        }
+        let root = engine
+            .new_with_children_native(&container_style(FlexDirectionKw::Column), &level)
+            .unwrap();
+        (engine, root)
+    }
+
+    fn bench_compute_layout(c: &mut Criterion) {
+        let shapes: [(&str, TreeBuilder); 3] = [
+            ("flat", flat_tree),
+            ("deep", deep_tree),
+            ("wide", wide_tree),
+        ];
+
+        let mut group = c.benchmark_group("compute_layout");
+        for (name, build) in shapes {
+            for count in NODE_COUNTS {
+                group.bench_with_input(BenchmarkId::new(name, count), &count, |b, &count| {
+                    b.iter_batched_ref(
+                        || build(count),
+                        |(engine, root)| engine.compute_layout_native(root, 1280.0, 800.0).unwrap(),
+                        BatchSize::LargeInput,
+                    )
+                });
+            }
+        }
+        group.finish();
+    }
+
+    fn bench_absolute_layouts(c: &mut Criterion) {
+        let mut group = c.benchmark_group("absolute_layouts");
+        for count in NODE_COUNTS {
+            let (mut engine, root) = wide_tree(count);
+            engine.compute_layout_native(&root, 1280.0, 800.0).unwrap();
+            let allocations = allocations_per_call(|| {
+                engine.absolute_layouts(&root).unwrap();
+            });
+            record_allocations(&format!("absolute_layouts/wide/{count}"), allocations);
+            group.bench_with_input(BenchmarkId::new("wide", count), &count, |b, _| {
+                b.iter(|| engine.absolute_layouts(&root).unwrap())
+            });
+        }
+        group.finish();
+    }
+
+    fn parse_row_style() -> StyleInput {
+        serde_json::from_str(ROW_STYLE_JSON).unwrap()
+    }
+
+    /// Creating rows that share one style, parsing it per node as `new_leaf`
+    /// does or once through `register_style`. JSON parsing stands in for
+    /// reading the style object from JS.
+    fn bench_mount(c: &mut Criterion) {
+        let mut group = c.benchmark_group("mount");
+        group.bench_function(BenchmarkId::new("ad_hoc", MOUNT_COUNT), |b| {
+            b.iter_batched_ref(
+                TaffyLayoutEngine::new,
+                |engine| {
+                    (0..MOUNT_COUNT)
+                        .map(|_| engine.new_leaf_native(&parse_row_style()).unwrap())
+                        .collect::<Vec<_>>()
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("registered", MOUNT_COUNT), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut engine = TaffyLayoutEngine::new();
+                    let style_id = engine.register_style_native(&parse_row_style());
+                    (engine, style_id)
+                },
+                |(engine, style_id)| {
+                    (0..MOUNT_COUNT)
+                        .map(|_| engine.new_leaf_with_style_id_native(style_id).unwrap())
+                        .collect::<Vec<_>>()
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.finish();
+    }
+
+    criterion_group!(
+        benches,
+        bench_compute_layout,
+        bench_absolute_layouts,
+        bench_mount
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(native::benches);
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -3,14 +3,19 @@
 //! Provides a wrapper around Taffy's flexbox/grid layout engine,
 //! exposing it via wasm-bindgen for use in TypeScript.
 
+pub use handles::{HandleRegistry, StaleHandle};
 use js_sys::Function;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use taffy::prelude::*;
+use taffy::util::ResolveOrZero;
+#[cfg(feature = "grid")]
 use taffy::{
     GridAutoFlow, GridPlacement, GridTemplateComponent, MaxTrackSizingFunction,
-    MinTrackSizingFunction, Overflow, Point as TaffyPoint, TrackSizingFunction,
+    MinTrackSizingFunction, TrackSizingFunction,
 };
+use taffy::{Overflow, Point as TaffyPoint};
+use tolerance::Tolerance;
 use wasm_bindgen::prelude::*;
 
 /// Opaque layout node ID exposed to JS.
@@ -26,6 +31,20 @@ impl LayoutId {
     }
 }
 
+/// Opaque handle to a style registered with `register_style`. It goes stale
+/// when the style is unregistered, and is never reissued.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StyleId(u64);
+
+#[wasm_bindgen]
+impl StyleId {
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> u64 {
+        self.0
+    }
+}
+
 /// Available space for layout computation.
 #[wasm_bindgen]
 #[derive(Clone, Copy, Debug)]
@@ -76,6 +95,433 @@ impl LayoutBounds {
     }
 }
 
+/// A position in layout coordinates.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LayoutPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A direction for `find_in_direction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NavDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl NavDirection {
+    pub fn parse(name: &str) -> Result<Self, LayoutError> {
+        match name {
+            "up" => Ok(NavDirection::Up),
+            "down" => Ok(NavDirection::Down),
+            "left" => Ok(NavDirection::Left),
+            "right" => Ok(NavDirection::Right),
+            _ => Err(LayoutError::UnknownDirection(name.to_string())),
+        }
+    }
+
+    /// How much more than distance along the direction a candidate's
+    /// misalignment across it counts, as in CSS Spatial Navigation. Rows
+    /// are wide, so moving left or right penalizes leaving the row heavily;
+    /// moving up or down tolerates drifting sideways to the nearest column.
+    fn orthogonal_weight(self) -> f32 {
+        match self {
+            NavDirection::Left | NavDirection::Right => 30.0,
+            NavDirection::Up | NavDirection::Down => 2.0,
+        }
+    }
+
+    /// The gap from `from` to `to` along the direction, or None when `to`
+    /// does not lie wholly past `from`'s edge in that direction, and the gap
+    /// across it, zero when their projections overlap.
+    fn gaps(self, from: &LayoutBounds, to: &LayoutBounds) -> Option<(f32, f32)> {
+        let along = match self {
+            NavDirection::Up => from.y - (to.y + to.height),
+            NavDirection::Down => to.y - (from.y + from.height),
+            NavDirection::Left => from.x - (to.x + to.width),
+            NavDirection::Right => to.x - (from.x + from.width),
+        };
+        let across = match self {
+            NavDirection::Up | NavDirection::Down => {
+                (to.x - (from.x + from.width)).max(from.x - (to.x + to.width))
+            }
+            NavDirection::Left | NavDirection::Right => {
+                (to.y - (from.y + from.height)).max(from.y - (to.y + to.height))
+            }
+        };
+        (along >= 0.0).then_some((along, across.max(0.0)))
+    }
+
+    /// Where `bounds` starts across the direction: its top moving left or
+    /// right, its left moving up or down.
+    fn across_start(self, bounds: &LayoutBounds) -> f32 {
+        match self {
+            NavDirection::Up | NavDirection::Down => bounds.x,
+            NavDirection::Left | NavDirection::Right => bounds.y,
+        }
+    }
+}
+
+/// Errors produced by the layout engine.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutError {
+    InvalidLayoutId(u64),
+    InvalidStyleId(u64),
+    /// A style ID whose style was unregistered.
+    StaleStyleId(StaleHandle),
+    /// An ID issued before the engine was last cleared.
+    StaleLayoutId {
+        id: u64,
+        generation: usize,
+    },
+    UnknownDefaults(String),
+    UnknownDirection(String),
+    /// `descendant` is not in the subtree of `ancestor`.
+    NotADescendant {
+        ancestor: u64,
+        descendant: u64,
+    },
+    /// The node has no virtual children.
+    NotVirtual(u64),
+    /// A virtual item index at or past the container's item count.
+    VirtualItemOutOfRange {
+        index: usize,
+        count: usize,
+    },
+    /// A list of virtual item sizes whose length is not the item count.
+    VirtualSizeCount {
+        count: usize,
+        sizes: usize,
+    },
+    /// A readback of node `id` came out NaN or infinite.
+    NonFinite {
+        id: u64,
+        value: NonFiniteValue,
+    },
+    Taffy(String),
+}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::InvalidLayoutId(_) => write!(f, "Invalid layout ID"),
+            LayoutError::InvalidStyleId(id) => write!(f, "Invalid style ID {}", id),
+            LayoutError::StaleStyleId(stale) => write!(f, "Unregistered style: {}", stale),
+            LayoutError::StaleLayoutId { id, generation } => write!(
+                f,
+                "Stale layout ID {} (from generation {}, cleared since)",
+                id, generation
+            ),
+            LayoutError::UnknownDefaults(name) => write!(f, "Unknown defaults profile: {}", name),
+            LayoutError::UnknownDirection(name) => write!(f, "Unknown direction: {}", name),
+            LayoutError::NotADescendant {
+                ancestor,
+                descendant,
+            } => write!(
+                f,
+                "Layout ID {} is not a descendant of {}",
+                descendant, ancestor
+            ),
+            LayoutError::NotVirtual(id) => write!(f, "Layout ID {} has no virtual children", id),
+            LayoutError::VirtualItemOutOfRange { index, count } => write!(
+                f,
+                "Virtual item {} is out of range for {} items",
+                index, count
+            ),
+            LayoutError::VirtualSizeCount { count, sizes } => {
+                write!(f, "{} virtual item sizes given for {} items", sizes, count)
+            }
+            LayoutError::NonFinite { id, value } => write!(
+                f,
+                "Layout ID {} has non-finite {}: {}",
+                id, value.field, value.value
+            ),
+            LayoutError::Taffy(message) => write!(f, "Taffy error: {}", message),
+        }
+    }
+}
+
+impl From<taffy::TaffyError> for LayoutError {
+    fn from(e: taffy::TaffyError) -> Self {
+        LayoutError::Taffy(format!("{:?}", e))
+    }
+}
+
+impl From<LayoutError> for JsValue {
+    fn from(e: LayoutError) -> Self {
+        JsValue::from_str(&e.to_string())
+    }
+}
+
+/// Clip rectangle in absolute coordinates, stored as min/max edges.
+///
+/// Nodes without a clipping ancestor report `ClipRect::UNBOUNDED`, which uses
+/// the largest finite f32 values so it survives serialization to JS.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClipRect {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+/// Overlaps this thin, in pixels, are edges touching after float error in
+/// summed offsets and scrolls, not content showing through a clip.
+const EDGE_CONTACT: Tolerance = Tolerance::absolute(1e-3);
+
+impl ClipRect {
+    pub const UNBOUNDED: ClipRect = ClipRect {
+        min_x: f32::MIN,
+        min_y: f32::MIN,
+        max_x: f32::MAX,
+        max_y: f32::MAX,
+    };
+
+    fn intersect(&self, other: &ClipRect) -> ClipRect {
+        ClipRect {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        }
+    }
+
+    /// Whether a rect lies entirely outside this clip. Zero-sized rects are
+    /// only clipped when they fall strictly outside.
+    fn excludes(&self, x: f32, y: f32, width: f32, height: f32) -> bool {
+        let overlap_x = (x + width).min(self.max_x) - x.max(self.min_x);
+        let overlap_y = (y + height).min(self.max_y) - y.max(self.min_y);
+        overlap_x < 0.0
+            || overlap_y < 0.0
+            || (width > 0.0 && EDGE_CONTACT.is_zero(overlap_x))
+            || (height > 0.0 && EDGE_CONTACT.is_zero(overlap_y))
+    }
+}
+
+/// A readback field holding NaN or an infinity.
+///
+/// `serde_wasm_bindgen` passes non-finite floats through to JS, where they
+/// crash the renderer far from their cause, so every readback is checked as
+/// it is built. A non-finite value there is a bug, so debug builds assert;
+/// release builds fail the readback with `LayoutError::NonFinite`, or with
+/// the `sanitize-non-finite` feature replace the value with zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonFiniteValue {
+    /// The field, with an index for arrays, such as `matrix[4]`
+    pub field: String,
+    pub value: f32,
+}
+
+/// Readback results whose floats can be checked and repaired in place.
+trait FiniteFields {
+    /// Call `visit` with every float field, named, as a slice.
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(&'static str, &mut [f32]));
+
+    /// Apply the policy of `NonFiniteValue` to a freshly built readback of
+    /// node `id`.
+    fn enforce_finite(&mut self, id: u64) -> Result<(), LayoutError> {
+        let mut found = None;
+        self.visit_floats(&mut |name, values| {
+            let Some(index) = values.iter().position(|value| !value.is_finite()) else {
+                return;
+            };
+            let field = if values.len() == 1 {
+                name.to_string()
+            } else {
+                format!("{}[{}]", name, index)
+            };
+            found.get_or_insert(NonFiniteValue {
+                field,
+                value: values[index],
+            });
+        });
+        let Some(value) = found else {
+            return Ok(());
+        };
+        debug_assert!(false, "Layout ID {} has non-finite {}", id, value.field);
+        if cfg!(feature = "sanitize-non-finite") {
+            self.visit_floats(&mut |_, values| {
+                for value in values.iter_mut().filter(|value| !value.is_finite()) {
+                    *value = 0.0;
+                }
+            });
+            return Ok(());
+        }
+        Err(LayoutError::NonFinite { id, value })
+    }
+}
+
+impl FiniteFields for LayoutBounds {
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(&'static str, &mut [f32])) {
+        visit("x", std::slice::from_mut(&mut self.x));
+        visit("y", std::slice::from_mut(&mut self.y));
+        visit("width", std::slice::from_mut(&mut self.width));
+        visit("height", std::slice::from_mut(&mut self.height));
+    }
+}
+
+impl FiniteFields for LayoutPoint {
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(&'static str, &mut [f32])) {
+        visit("x", std::slice::from_mut(&mut self.x));
+        visit("y", std::slice::from_mut(&mut self.y));
+    }
+}
+
+impl FiniteFields for AbsoluteLayout {
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(&'static str, &mut [f32])) {
+        visit("x", std::slice::from_mut(&mut self.x));
+        visit("y", std::slice::from_mut(&mut self.y));
+        visit("width", std::slice::from_mut(&mut self.width));
+        visit("height", std::slice::from_mut(&mut self.height));
+        visit("clip.min_x", std::slice::from_mut(&mut self.clip.min_x));
+        visit("clip.min_y", std::slice::from_mut(&mut self.clip.min_y));
+        visit("clip.max_x", std::slice::from_mut(&mut self.clip.max_x));
+        visit("clip.max_y", std::slice::from_mut(&mut self.clip.max_y));
+        visit("matrix", &mut self.matrix);
+        visit("scroll_width", std::slice::from_mut(&mut self.scroll_width));
+        visit(
+            "scroll_height",
+            std::slice::from_mut(&mut self.scroll_height),
+        );
+    }
+}
+
+/// A node's layout in absolute coordinates, as returned by the batch readback.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AbsoluteLayout {
+    pub id: u64,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Intersection of the padding boxes of all overflow-clipping ancestors,
+    /// shifted by their scroll offsets.
+    pub clip: ClipRect,
+    /// True when no part of the node is inside `clip`.
+    pub fully_clipped: bool,
+    /// The node's transform composed with its ancestors', over absolute
+    /// coordinates. `x`, `y` and `clip` are not transformed by it.
+    pub matrix: Matrix2D,
+    /// Size of the area a scroll container scrolls over, as DOM
+    /// `scrollWidth` and `scrollHeight`: the padding box inside any
+    /// scrollbar, grown to the far edge of overflowing content. Equal to
+    /// that padding box when nothing overflows.
+    pub scroll_width: f32,
+    pub scroll_height: f32,
+}
+
+/// A 2D affine matrix `[a, b, c, d, e, f]` mapping (x, y) to
+/// (a x + c y + e, b x + d y + f), as in CSS and SVG `matrix()`.
+pub type Matrix2D = [f32; 6];
+
+pub const IDENTITY_MATRIX: Matrix2D = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// The matrix applying `inner` first, then `outer`.
+fn multiply(outer: &Matrix2D, inner: &Matrix2D) -> Matrix2D {
+    let [a, b, c, d, e, f] = *outer;
+    let [ia, ib, ic, id, ie, if_] = *inner;
+    [
+        a * ia + c * ib,
+        b * ia + d * ib,
+        a * ic + c * id,
+        b * ic + d * id,
+        a * ie + c * if_ + e,
+        b * ie + d * if_ + f,
+    ]
+}
+
+/// A rendering transform stored on a node, from `set_transform`. Applied in
+/// CSS order: translate, rotate, then scale, about the origin. As with
+/// StyleInput sizes, percentages go in the `_percent` fields and resolve
+/// against the node's own laid out size; a px value wins over a percentage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransformInput {
+    pub translate_x: Option<f32>,
+    pub translate_x_percent: Option<f32>,
+    pub translate_y: Option<f32>,
+    pub translate_y_percent: Option<f32>,
+    /// Defaults to 1
+    pub scale_x: Option<f32>,
+    /// Defaults to 1
+    pub scale_y: Option<f32>,
+    /// Clockwise on a y-down screen
+    pub rotate_deg: Option<f32>,
+    /// Defaults to 50%, the center, as in CSS
+    pub origin_x: Option<f32>,
+    pub origin_x_percent: Option<f32>,
+    pub origin_y: Option<f32>,
+    pub origin_y_percent: Option<f32>,
+}
+
+impl TransformInput {
+    /// The transform over absolute coordinates for a node laid out at
+    /// `x`, `y` with the given size.
+    fn resolve(&self, x: f32, y: f32, width: f32, height: f32) -> Matrix2D {
+        let resolve = |px: Option<f32>, percent: Option<f32>, size: f32, default: f32| {
+            px.unwrap_or_else(|| percent.unwrap_or(default) / 100.0 * size)
+        };
+        let translate_x = resolve(self.translate_x, self.translate_x_percent, width, 0.0);
+        let translate_y = resolve(self.translate_y, self.translate_y_percent, height, 0.0);
+        let origin_x = x + resolve(self.origin_x, self.origin_x_percent, width, 50.0);
+        let origin_y = y + resolve(self.origin_y, self.origin_y_percent, height, 50.0);
+        let (sin, cos) = self.rotate_deg.unwrap_or(0.0).to_radians().sin_cos();
+        let scale_x = self.scale_x.unwrap_or(1.0);
+        let scale_y = self.scale_y.unwrap_or(1.0);
+
+        let (a, b, c, d) = (cos * scale_x, sin * scale_x, -sin * scale_y, cos * scale_y);
+        [
+            a,
+            b,
+            c,
+            d,
+            origin_x + translate_x - (a * origin_x + c * origin_y),
+            origin_y + translate_y - (b * origin_x + d * origin_y),
+        ]
+    }
+}
+
+/// A rectangle that content of a container flows around, such as a floated
+/// image or pull-quote. Coordinates are relative to the container's border
+/// box, the same space as its children's layouts.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExclusionRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ExclusionRect {
+    /// Whether the rect covers any part of the band `top`..`bottom`.
+    fn overlaps_band(&self, top: f32, bottom: f32) -> bool {
+        self.width > 0.0 && self.y < bottom && self.y + self.height > top
+    }
+}
+
+/// The widest part of `left`..`right` not covered horizontally by any of
+/// `exclusions`, as `(start, end)`. The leftmost span wins a tie, and a fully
+/// covered range returns an empty span at `left`.
+fn widest_free_span(left: f32, right: f32, exclusions: &mut [(f32, f32)]) -> (f32, f32) {
+    exclusions.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut best = (left, left);
+    let mut cursor = left;
+    for &(start, end) in exclusions.iter() {
+        let start = start.min(right);
+        if start - cursor > best.1 - best.0 {
+            best = (cursor, start);
+        }
+        cursor = cursor.max(end);
+    }
+    if right - cursor > best.1 - best.0 {
+        best = (cursor, right);
+    }
+    best
+}
+
 // ============ CSS Grid Input Types ============
 
 /// Track size input from JavaScript.
@@ -120,22 +566,492 @@ pub enum GridPlacementInput {
     LineObj { line: i16 },
 }
 
+// ============ Style Keywords ============
+
+/// Deserialize an optional keyword field, naming the field in the error so a
+/// typo like `flexDirection: "colum"` is reported against the right property.
+fn deserialize_keyword<'de, D, T>(deserializer: D, field: &str) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    use serde::de::{Error, IntoDeserializer};
+
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let keyword_deserializer: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+        value.as_str().into_deserializer();
+    T::deserialize(keyword_deserializer)
+        .map(Some)
+        .map_err(|_| D::Error::custom(format!("invalid {} keyword \"{}\"", field, value)))
+}
+
+macro_rules! keyword_field {
+    ($fn_name:ident, $field:literal) => {
+        fn $fn_name<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+            T: serde::de::DeserializeOwned,
+        {
+            deserialize_keyword(deserializer, $field)
+        }
+    };
+}
+
+keyword_field!(de_display, "display");
+keyword_field!(de_flex_direction, "flex_direction");
+keyword_field!(de_flex_wrap, "flex_wrap");
+keyword_field!(de_align_items, "align_items");
+keyword_field!(de_justify_content, "justify_content");
+keyword_field!(de_align_self, "align_self");
+keyword_field!(de_grid_auto_flow, "grid_auto_flow");
+keyword_field!(de_position, "position");
+keyword_field!(de_overflow, "overflow");
+keyword_field!(de_overflow_x, "overflow_x");
+keyword_field!(de_overflow_y, "overflow_y");
+keyword_field!(de_width_keyword, "width");
+keyword_field!(de_height_keyword, "height");
+keyword_field!(de_min_width_keyword, "min_width");
+keyword_field!(de_max_width_keyword, "max_width");
+keyword_field!(de_min_height_keyword, "min_height");
+keyword_field!(de_max_height_keyword, "max_height");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayKw {
+    #[serde(alias = "Flex")]
+    Flex,
+    #[serde(alias = "Block")]
+    Block,
+    #[serde(alias = "Grid")]
+    Grid,
+    #[serde(alias = "None")]
+    None,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlexDirectionKw {
+    #[serde(alias = "Row")]
+    Row,
+    #[serde(alias = "Column")]
+    Column,
+    #[serde(alias = "RowReverse")]
+    RowReverse,
+    #[serde(alias = "ColumnReverse")]
+    ColumnReverse,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlexWrapKw {
+    #[serde(alias = "Wrap")]
+    Wrap,
+    #[serde(rename = "nowrap", alias = "no-wrap", alias = "NoWrap")]
+    NoWrap,
+    #[serde(alias = "WrapReverse")]
+    WrapReverse,
+}
+
+/// Keyword for `align_items` and `align_self`. `auto` leaves the property
+/// unset so the parent's `align_items` (or Taffy's default) applies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlignKw {
+    #[serde(alias = "Auto")]
+    Auto,
+    #[serde(alias = "Start")]
+    Start,
+    #[serde(alias = "End")]
+    End,
+    #[serde(alias = "FlexStart")]
+    FlexStart,
+    #[serde(alias = "FlexEnd")]
+    FlexEnd,
+    #[serde(alias = "Center")]
+    Center,
+    #[serde(alias = "Baseline")]
+    Baseline,
+    #[serde(alias = "Stretch")]
+    Stretch,
+}
+
+/// Keyword for `justify_content` (Taffy's `AlignContent`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JustifyKw {
+    #[serde(alias = "Start")]
+    Start,
+    #[serde(alias = "End")]
+    End,
+    #[serde(alias = "FlexStart")]
+    FlexStart,
+    #[serde(alias = "FlexEnd")]
+    FlexEnd,
+    #[serde(alias = "Center")]
+    Center,
+    #[serde(alias = "Stretch")]
+    Stretch,
+    #[serde(alias = "SpaceBetween")]
+    SpaceBetween,
+    #[serde(alias = "SpaceAround")]
+    SpaceAround,
+    #[serde(alias = "SpaceEvenly")]
+    SpaceEvenly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GridAutoFlowKw {
+    #[serde(alias = "Row")]
+    Row,
+    #[serde(alias = "Column")]
+    Column,
+    #[serde(alias = "RowDense")]
+    RowDense,
+    #[serde(alias = "ColumnDense")]
+    ColumnDense,
+}
+
+/// `static` is accepted as an alias of `relative`, which Taffy treats the same
+/// way when no insets are set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PositionKw {
+    #[serde(alias = "Relative", alias = "static")]
+    Relative,
+    #[serde(alias = "Absolute")]
+    Absolute,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowKw {
+    #[serde(alias = "Visible")]
+    Visible,
+    #[serde(alias = "Clip")]
+    Clip,
+    #[serde(alias = "Hidden")]
+    Hidden,
+    #[serde(alias = "Scroll")]
+    Scroll,
+}
+
+/// Intrinsic sizing keyword for the `_keyword` sizing fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SizeKw {
+    MinContent,
+    MaxContent,
+    FitContent,
+}
+
+impl From<DisplayKw> for Display {
+    fn from(kw: DisplayKw) -> Self {
+        match kw {
+            DisplayKw::Flex => Display::Flex,
+            DisplayKw::Block => Display::Block,
+            #[cfg(feature = "grid")]
+            DisplayKw::Grid => Display::Grid,
+            // Without grid support a grid container lays out as block
+            #[cfg(not(feature = "grid"))]
+            DisplayKw::Grid => Display::Block,
+            DisplayKw::None => Display::None,
+        }
+    }
+}
+
+impl From<Display> for DisplayKw {
+    fn from(display: Display) -> Self {
+        match display {
+            Display::Flex => DisplayKw::Flex,
+            Display::Block => DisplayKw::Block,
+            #[cfg(feature = "grid")]
+            Display::Grid => DisplayKw::Grid,
+            Display::None => DisplayKw::None,
+        }
+    }
+}
+
+impl From<FlexDirectionKw> for FlexDirection {
+    fn from(kw: FlexDirectionKw) -> Self {
+        match kw {
+            FlexDirectionKw::Row => FlexDirection::Row,
+            FlexDirectionKw::Column => FlexDirection::Column,
+            FlexDirectionKw::RowReverse => FlexDirection::RowReverse,
+            FlexDirectionKw::ColumnReverse => FlexDirection::ColumnReverse,
+        }
+    }
+}
+
+impl From<FlexDirection> for FlexDirectionKw {
+    fn from(direction: FlexDirection) -> Self {
+        match direction {
+            FlexDirection::Row => FlexDirectionKw::Row,
+            FlexDirection::Column => FlexDirectionKw::Column,
+            FlexDirection::RowReverse => FlexDirectionKw::RowReverse,
+            FlexDirection::ColumnReverse => FlexDirectionKw::ColumnReverse,
+        }
+    }
+}
+
+impl From<FlexWrapKw> for FlexWrap {
+    fn from(kw: FlexWrapKw) -> Self {
+        match kw {
+            FlexWrapKw::Wrap => FlexWrap::Wrap,
+            FlexWrapKw::NoWrap => FlexWrap::NoWrap,
+            FlexWrapKw::WrapReverse => FlexWrap::WrapReverse,
+        }
+    }
+}
+
+impl From<FlexWrap> for FlexWrapKw {
+    fn from(wrap: FlexWrap) -> Self {
+        match wrap {
+            FlexWrap::Wrap => FlexWrapKw::Wrap,
+            FlexWrap::NoWrap => FlexWrapKw::NoWrap,
+            FlexWrap::WrapReverse => FlexWrapKw::WrapReverse,
+        }
+    }
+}
+
+impl AlignKw {
+    fn to_taffy(self) -> Option<AlignItems> {
+        match self {
+            AlignKw::Auto => None,
+            AlignKw::Start => Some(AlignItems::Start),
+            AlignKw::End => Some(AlignItems::End),
+            AlignKw::FlexStart => Some(AlignItems::FlexStart),
+            AlignKw::FlexEnd => Some(AlignItems::FlexEnd),
+            AlignKw::Center => Some(AlignItems::Center),
+            AlignKw::Baseline => Some(AlignItems::Baseline),
+            AlignKw::Stretch => Some(AlignItems::Stretch),
+        }
+    }
+
+    fn from_taffy(align: Option<AlignItems>) -> Self {
+        match align {
+            None => AlignKw::Auto,
+            Some(AlignItems::Start) => AlignKw::Start,
+            Some(AlignItems::End) => AlignKw::End,
+            Some(AlignItems::FlexStart) => AlignKw::FlexStart,
+            Some(AlignItems::FlexEnd) => AlignKw::FlexEnd,
+            Some(AlignItems::Center) => AlignKw::Center,
+            Some(AlignItems::Baseline) => AlignKw::Baseline,
+            Some(AlignItems::Stretch) => AlignKw::Stretch,
+        }
+    }
+}
+
+impl From<JustifyKw> for JustifyContent {
+    fn from(kw: JustifyKw) -> Self {
+        match kw {
+            JustifyKw::Start => JustifyContent::Start,
+            JustifyKw::End => JustifyContent::End,
+            JustifyKw::FlexStart => JustifyContent::FlexStart,
+            JustifyKw::FlexEnd => JustifyContent::FlexEnd,
+            JustifyKw::Center => JustifyContent::Center,
+            JustifyKw::Stretch => JustifyContent::Stretch,
+            JustifyKw::SpaceBetween => JustifyContent::SpaceBetween,
+            JustifyKw::SpaceAround => JustifyContent::SpaceAround,
+            JustifyKw::SpaceEvenly => JustifyContent::SpaceEvenly,
+        }
+    }
+}
+
+impl From<JustifyContent> for JustifyKw {
+    fn from(justify: JustifyContent) -> Self {
+        match justify {
+            JustifyContent::Start => JustifyKw::Start,
+            JustifyContent::End => JustifyKw::End,
+            JustifyContent::FlexStart => JustifyKw::FlexStart,
+            JustifyContent::FlexEnd => JustifyKw::FlexEnd,
+            JustifyContent::Center => JustifyKw::Center,
+            JustifyContent::Stretch => JustifyKw::Stretch,
+            JustifyContent::SpaceBetween => JustifyKw::SpaceBetween,
+            JustifyContent::SpaceAround => JustifyKw::SpaceAround,
+            JustifyContent::SpaceEvenly => JustifyKw::SpaceEvenly,
+        }
+    }
+}
+
+#[cfg(feature = "grid")]
+impl From<GridAutoFlowKw> for GridAutoFlow {
+    fn from(kw: GridAutoFlowKw) -> Self {
+        match kw {
+            GridAutoFlowKw::Row => GridAutoFlow::Row,
+            GridAutoFlowKw::Column => GridAutoFlow::Column,
+            GridAutoFlowKw::RowDense => GridAutoFlow::RowDense,
+            GridAutoFlowKw::ColumnDense => GridAutoFlow::ColumnDense,
+        }
+    }
+}
+
+#[cfg(feature = "grid")]
+impl From<GridAutoFlow> for GridAutoFlowKw {
+    fn from(flow: GridAutoFlow) -> Self {
+        match flow {
+            GridAutoFlow::Row => GridAutoFlowKw::Row,
+            GridAutoFlow::Column => GridAutoFlowKw::Column,
+            GridAutoFlow::RowDense => GridAutoFlowKw::RowDense,
+            GridAutoFlow::ColumnDense => GridAutoFlowKw::ColumnDense,
+        }
+    }
+}
+
+impl From<PositionKw> for Position {
+    fn from(kw: PositionKw) -> Self {
+        match kw {
+            PositionKw::Relative => Position::Relative,
+            PositionKw::Absolute => Position::Absolute,
+        }
+    }
+}
+
+impl From<Position> for PositionKw {
+    fn from(position: Position) -> Self {
+        match position {
+            Position::Relative => PositionKw::Relative,
+            Position::Absolute => PositionKw::Absolute,
+        }
+    }
+}
+
+impl From<OverflowKw> for Overflow {
+    fn from(kw: OverflowKw) -> Self {
+        match kw {
+            OverflowKw::Visible => Overflow::Visible,
+            OverflowKw::Clip => Overflow::Clip,
+            OverflowKw::Hidden => Overflow::Hidden,
+            OverflowKw::Scroll => Overflow::Scroll,
+        }
+    }
+}
+
+impl From<Overflow> for OverflowKw {
+    fn from(overflow: Overflow) -> Self {
+        match overflow {
+            Overflow::Visible => OverflowKw::Visible,
+            Overflow::Clip => OverflowKw::Clip,
+            Overflow::Hidden => OverflowKw::Hidden,
+            Overflow::Scroll => OverflowKw::Scroll,
+        }
+    }
+}
+
+/// CSS's computed overflow: visible and clip become hidden beside an axis
+/// that is hidden or scrolls.
+fn computed_overflow(overflow: TaffyPoint<Overflow>) -> TaffyPoint<Overflow> {
+    let scrolls = |o: Overflow| matches!(o, Overflow::Hidden | Overflow::Scroll);
+    let compute = |own: Overflow, other: Overflow| {
+        if scrolls(other) && !scrolls(own) {
+            Overflow::Hidden
+        } else {
+            own
+        }
+    };
+    TaffyPoint {
+        x: compute(overflow.x, overflow.y),
+        y: compute(overflow.y, overflow.x),
+    }
+}
+
 // ============ Style Input ============
 
+/// Which values fill the style properties a StyleInput leaves unset.
+///
+/// | Property          | `taffy`                   | `css`        |
+/// |-------------------|---------------------------|--------------|
+/// | `display`         | flex                      | block        |
+/// | `box-sizing`      | border-box                | content-box  |
+/// | `flex-direction`  | row                       | row          |
+/// | `flex-wrap`       | nowrap                    | nowrap       |
+/// | `flex-grow`       | 0                         | 0            |
+/// | `flex-shrink`     | 1                         | 1            |
+/// | `flex-basis`      | auto                      | auto         |
+/// | `align-items`     | unset (stretch)           | stretch      |
+/// | `align-content`   | unset (stretch)           | stretch      |
+/// | `justify-content` | unset (flex-start)        | flex-start   |
+/// | `min-width/height`| auto                      | auto         |
+/// | `max-width/height`| auto (none)               | auto (none)  |
+/// | `position`        | relative                  | relative     |
+/// | `overflow`        | visible                   | visible      |
+///
+/// The css column is the CSS initial value, except `position`: Taffy has no
+/// `static`, and `relative` without insets lays out identically. Alignment is
+/// written out explicitly under css so a node's style reads back the same as
+/// the computed style a browser reports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DefaultsProfile {
+    #[default]
+    Taffy,
+    Css,
+}
+
+impl DefaultsProfile {
+    pub fn parse(name: &str) -> Result<Self, LayoutError> {
+        match name {
+            "taffy" => Ok(DefaultsProfile::Taffy),
+            "css" => Ok(DefaultsProfile::Css),
+            _ => Err(LayoutError::UnknownDefaults(name.to_string())),
+        }
+    }
+
+    /// The style a node starts from before its StyleInput is applied.
+    fn base_style(self) -> Style {
+        match self {
+            DefaultsProfile::Taffy => Style::default(),
+            DefaultsProfile::Css => Style {
+                display: Display::Block,
+                box_sizing: BoxSizing::ContentBox,
+                flex_shrink: 1.0,
+                flex_basis: Dimension::auto(),
+                align_items: Some(AlignItems::Stretch),
+                align_content: Some(AlignContent::Stretch),
+                justify_content: Some(JustifyContent::FlexStart),
+                min_size: Size::auto(),
+                ..Style::default()
+            },
+        }
+    }
+}
+
+/// A style value the engine could not use, from `take_style_warnings`. The
+/// rest of the style still applies.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StyleWarning {
+    /// The StyleInput field holding the value
+    pub property: String,
+    pub value: String,
+    pub message: String,
+}
+
 /// Style input from JavaScript.
 /// Maps to Glade's Styles interface.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct StyleInput {
     // Display & Flexbox
-    pub display: Option<String>,
-    pub flex_direction: Option<String>,
-    pub flex_wrap: Option<String>,
+    #[serde(default, deserialize_with = "de_display")]
+    pub display: Option<DisplayKw>,
+    #[serde(default, deserialize_with = "de_flex_direction")]
+    pub flex_direction: Option<FlexDirectionKw>,
+    #[serde(default, deserialize_with = "de_flex_wrap")]
+    pub flex_wrap: Option<FlexWrapKw>,
     pub flex_grow: Option<f32>,
     pub flex_shrink: Option<f32>,
     pub flex_basis: Option<f32>,
-    pub align_items: Option<String>,
-    pub justify_content: Option<String>,
-    pub align_self: Option<String>,
+    // The `flex` shorthand as CSS writes it, such as "1", "1 1 auto",
+    // "0 0 200px" or "none". When it parses it sets grow, shrink and basis,
+    // beating the three fields above; when it does not, they apply and the
+    // value is reported by `take_style_warnings`.
+    pub flex: Option<String>,
+    #[serde(default, deserialize_with = "de_align_items")]
+    pub align_items: Option<AlignKw>,
+    #[serde(default, deserialize_with = "de_justify_content")]
+    pub justify_content: Option<JustifyKw>,
+    #[serde(default, deserialize_with = "de_align_self")]
+    pub align_self: Option<AlignKw>,
     pub gap: Option<f32>,
     pub row_gap: Option<f32>,
     pub column_gap: Option<f32>,
@@ -145,7 +1061,8 @@ pub struct StyleInput {
     pub grid_template_rows: Option<GridTemplateInput>,
     pub grid_auto_columns: Option<TrackSizeInput>,
     pub grid_auto_rows: Option<TrackSizeInput>,
-    pub grid_auto_flow: Option<String>,
+    #[serde(default, deserialize_with = "de_grid_auto_flow")]
+    pub grid_auto_flow: Option<GridAutoFlowKw>,
 
     // CSS Grid Item
     pub grid_column_start: Option<GridPlacementInput>,
@@ -169,6 +1086,27 @@ pub struct StyleInput {
     pub min_height_percent: Option<f32>,
     pub max_height_percent: Option<f32>,
 
+    // Sizing keywords (separate fields, beating px and percentages). Taffy
+    // has no intrinsic sizes, so before each layout a node using them is
+    // measured on its own under min- and max-content space and given the
+    // lengths it measured. `fit-content` on width is an auto width clamped
+    // between the two, and to `max_width` when that is in px; on the min
+    // and max fields it is the min- and max-content width respectively.
+    // Heights size to the content at the resolved width, so the three
+    // keywords agree there.
+    #[serde(default, deserialize_with = "de_width_keyword")]
+    pub width_keyword: Option<SizeKw>,
+    #[serde(default, deserialize_with = "de_height_keyword")]
+    pub height_keyword: Option<SizeKw>,
+    #[serde(default, deserialize_with = "de_min_width_keyword")]
+    pub min_width_keyword: Option<SizeKw>,
+    #[serde(default, deserialize_with = "de_max_width_keyword")]
+    pub max_width_keyword: Option<SizeKw>,
+    #[serde(default, deserialize_with = "de_min_height_keyword")]
+    pub min_height_keyword: Option<SizeKw>,
+    #[serde(default, deserialize_with = "de_max_height_keyword")]
+    pub max_height_keyword: Option<SizeKw>,
+
     // Spacing
     pub padding_top: Option<f32>,
     pub padding_right: Option<f32>,
@@ -186,26 +1124,42 @@ pub struct StyleInput {
     pub margin_left_auto: Option<bool>,
 
     // Position
-    pub position: Option<String>,
+    #[serde(default, deserialize_with = "de_position")]
+    pub position: Option<PositionKw>,
     pub top: Option<f32>,
     pub right: Option<f32>,
     pub bottom: Option<f32>,
     pub left: Option<f32>,
 
-    // Overflow
-    pub overflow: Option<String>,
-    pub overflow_x: Option<String>,
-    pub overflow_y: Option<String>,
+    // Overflow. `overflow_x` and `overflow_y` beat `overflow` on their axis
+    // whichever was written last, and as in CSS an axis left visible or clip
+    // beside one that is hidden or scrolls computes to hidden, so a node is
+    // a scroll container on both axes or neither. A flex item's automatic
+    // minimum size is zero on each scroll container axis, which lets it
+    // shrink below its content and scroll instead.
+    #[serde(default, deserialize_with = "de_overflow")]
+    pub overflow: Option<OverflowKw>,
+    #[serde(default, deserialize_with = "de_overflow_x")]
+    pub overflow_x: Option<OverflowKw>,
+    #[serde(default, deserialize_with = "de_overflow_y")]
+    pub overflow_y: Option<OverflowKw>,
 
     // Border (for layout purposes - affects content box)
     pub border_width: Option<f32>,
 
     // Aspect ratio (width / height)
     pub aspect_ratio: Option<f32>,
+
+    // Readback only: under `compute_layout_with_insets`, grow into the
+    // insets on every edge lying on the inset box, as a full-bleed
+    // background does. Layout sizes the node as usual and its children stay
+    // where they were laid out.
+    pub ignore_safe_area: Option<bool>,
 }
 
 // ============ Grid Type Conversions ============
 
+#[cfg(feature = "grid")]
 impl TrackSizeInput {
     /// Parse a string keyword like "1fr", "auto", "min-content", "max-content"
     fn parse_keyword(s: &str) -> TrackSizingFunction {
@@ -285,6 +1239,7 @@ impl TrackSizeInput {
     }
 }
 
+#[cfg(feature = "grid")]
 impl GridTemplateInput {
     /// Convert to Vec<TrackSizingFunction> for Taffy
     fn to_taffy(&self) -> Vec<GridTemplateComponent<String>> {
@@ -308,6 +1263,7 @@ impl GridTemplateInput {
     }
 }
 
+#[cfg(feature = "grid")]
 impl GridPlacementInput {
     /// Convert to Taffy's GridPlacement
     fn to_taffy(&self) -> GridPlacement {
@@ -322,40 +1278,226 @@ impl GridPlacementInput {
 
 // ============ StyleInput Conversion ============
 
-impl StyleInput {
-    fn to_taffy(&self) -> Style {
-        let mut style = Style::default();
+/// Split a Taffy length into (px, percent) parts for StyleInput readback.
+/// Percentages are reported on the 0-100 scale StyleInput uses.
+fn length_parts(raw: taffy::style::CompactLength) -> (Option<f32>, Option<f32>) {
+    match raw.tag() {
+        taffy::style::CompactLength::LENGTH_TAG => (Some(raw.value()), None),
+        taffy::style::CompactLength::PERCENT_TAG => (None, Some(raw.value() * 100.0)),
+        _ => (None, None),
+    }
+}
 
-        // Display
-        if let Some(ref d) = self.display {
-            style.display = match d.as_str() {
-                "flex" => Display::Flex,
-                "block" => Display::Block,
-                "grid" => Display::Grid,
-                "none" => Display::None,
-                _ => Display::Flex,
-            };
+fn length_px(raw: taffy::style::CompactLength) -> Option<f32> {
+    length_parts(raw).0
+}
+
+/// Resolve a CSS `flex` shorthand to grow, shrink and basis. Besides the
+/// keywords `none`, `auto` and `initial` it takes one to three values: up to
+/// two factors, grow then shrink, with a basis before or after them. An
+/// omitted factor is 1, and an omitted basis is 0 when a factor is given, so
+/// `flex: 1` shares space by grow alone while `flex: auto` starts from the
+/// content. A unitless number is a factor unless it is the third value.
+fn parse_flex_shorthand(value: &str) -> Result<(f32, f32, Dimension), String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    match words.as_slice() {
+        [] => return Err("the value is empty".to_string()),
+        ["none"] => return Ok((0.0, 0.0, Dimension::auto())),
+        ["auto"] => return Ok((1.0, 1.0, Dimension::auto())),
+        ["initial"] => return Ok((0.0, 1.0, Dimension::auto())),
+        _ if words.len() > 3 => {
+            return Err(format!("expected at most 3 values, got {}", words.len()))
         }
+        _ => {}
+    }
+    let (factors, basis) = match words.as_slice() {
+        [basis, factors @ ..] if flex_factor(basis).is_none() => (factors, Some(*basis)),
+        [factors @ .., basis] if flex_factor(basis).is_none() || words.len() == 3 => {
+            (factors, Some(*basis))
+        }
+        factors => (factors, None),
+    };
+    let factor = |word: Option<&&str>| match word {
+        Some(word) => flex_factor(word).ok_or_else(|| format!("{:?} is not a flex factor", word)),
+        None => Ok(1.0),
+    };
+    let grow = factor(factors.first())?;
+    let shrink = factor(factors.get(1))?;
+    let basis = match basis {
+        Some(basis) => flex_basis(basis)?,
+        None => Dimension::length(0.0),
+    };
+    Ok((grow, shrink, basis))
+}
 
-        // Flex direction
-        if let Some(ref fd) = self.flex_direction {
-            style.flex_direction = match fd.as_str() {
-                "row" => FlexDirection::Row,
-                "column" => FlexDirection::Column,
-                "row-reverse" => FlexDirection::RowReverse,
-                "column-reverse" => FlexDirection::ColumnReverse,
-                _ => FlexDirection::Row,
-            };
+/// A flex grow or shrink factor: a finite, non-negative number.
+fn flex_factor(word: &str) -> Option<f32> {
+    word.parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+}
+
+/// A flex basis in px or percent, or `auto`. Taffy has no `content` basis,
+/// so it lays out as `auto`, which only differs when the item also sets its
+/// main size.
+fn flex_basis(word: &str) -> Result<Dimension, String> {
+    let number = |text: &str| {
+        text.parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite() && *value >= 0.0)
+    };
+    let basis = match word {
+        "auto" | "content" => Some(Dimension::auto()),
+        "0" => Some(Dimension::length(0.0)),
+        _ => match word.strip_suffix('%') {
+            Some(percent) => number(percent).map(|value| Dimension::percent(value / 100.0)),
+            None => word
+                .strip_suffix("px")
+                .and_then(number)
+                .map(Dimension::length),
+        },
+    };
+    basis.ok_or_else(|| format!("{:?} is not a flex basis", word))
+}
+
+impl StyleInput {
+    /// Reconstruct a StyleInput from a Taffy style, with canonical keywords.
+    ///
+    /// Lengths come back in the px fields and percentages in the `_percent`
+    /// fields. Grid templates, grid placement, and calc values are not read back.
+    fn from_taffy(style: &Style) -> Self {
+        let mut input = StyleInput {
+            display: Some(style.display.into()),
+            flex_direction: Some(style.flex_direction.into()),
+            flex_wrap: Some(style.flex_wrap.into()),
+            flex_grow: Some(style.flex_grow),
+            flex_shrink: Some(style.flex_shrink),
+            flex_basis: length_px(style.flex_basis.into_raw()),
+            align_items: Some(AlignKw::from_taffy(style.align_items)),
+            justify_content: style.justify_content.map(Into::into),
+            align_self: Some(AlignKw::from_taffy(style.align_self)),
+            row_gap: length_px(style.gap.height.into_raw()),
+            column_gap: length_px(style.gap.width.into_raw()),
+            #[cfg(feature = "grid")]
+            grid_auto_flow: Some(style.grid_auto_flow.into()),
+            padding_top: length_px(style.padding.top.into_raw()),
+            padding_right: length_px(style.padding.right.into_raw()),
+            padding_bottom: length_px(style.padding.bottom.into_raw()),
+            padding_left: length_px(style.padding.left.into_raw()),
+            margin_top: length_px(style.margin.top.into_raw()),
+            margin_right: length_px(style.margin.right.into_raw()),
+            margin_bottom: length_px(style.margin.bottom.into_raw()),
+            margin_left: length_px(style.margin.left.into_raw()),
+            margin_top_auto: style.margin.top.is_auto().then_some(true),
+            margin_right_auto: style.margin.right.is_auto().then_some(true),
+            margin_bottom_auto: style.margin.bottom.is_auto().then_some(true),
+            margin_left_auto: style.margin.left.is_auto().then_some(true),
+            position: Some(style.position.into()),
+            top: length_px(style.inset.top.into_raw()),
+            right: length_px(style.inset.right.into_raw()),
+            bottom: length_px(style.inset.bottom.into_raw()),
+            left: length_px(style.inset.left.into_raw()),
+            overflow_x: Some(style.overflow.x.into()),
+            overflow_y: Some(style.overflow.y.into()),
+            aspect_ratio: style.aspect_ratio,
+            ..Default::default()
+        };
+
+        (input.width, input.width_percent) = length_parts(style.size.width.into_raw());
+        (input.height, input.height_percent) = length_parts(style.size.height.into_raw());
+        (input.min_width, input.min_width_percent) = length_parts(style.min_size.width.into_raw());
+        (input.max_width, input.max_width_percent) = length_parts(style.max_size.width.into_raw());
+        (input.min_height, input.min_height_percent) =
+            length_parts(style.min_size.height.into_raw());
+        (input.max_height, input.max_height_percent) =
+            length_parts(style.max_size.height.into_raw());
+
+        let border = style.border;
+        if border.top == border.right && border.top == border.bottom && border.top == border.left {
+            input.border_width = length_px(border.top.into_raw());
         }
 
-        // Flex wrap
-        if let Some(ref fw) = self.flex_wrap {
-            style.flex_wrap = match fw.as_str() {
-                "wrap" => FlexWrap::Wrap,
-                "nowrap" => FlexWrap::NoWrap,
-                "wrap-reverse" => FlexWrap::WrapReverse,
-                _ => FlexWrap::NoWrap,
-            };
+        input
+    }
+
+    /// Values in this style that cannot be used.
+    fn warnings(&self) -> Vec<StyleWarning> {
+        let mut warnings = Vec::new();
+        if let Some(flex) = &self.flex {
+            if let Err(message) = parse_flex_shorthand(flex) {
+                warnings.push(StyleWarning {
+                    property: "flex".to_string(),
+                    value: flex.clone(),
+                    message,
+                });
+            }
+        }
+        #[cfg(not(feature = "grid"))]
+        self.grid_warnings(&mut warnings);
+        warnings
+    }
+
+    /// Grid values, which this build cannot lay out.
+    #[cfg(not(feature = "grid"))]
+    fn grid_warnings(&self, warnings: &mut Vec<StyleWarning>) {
+        let mut unsupported = |property: &str, value: String| {
+            warnings.push(StyleWarning {
+                property: property.to_string(),
+                value,
+                message: "built without the grid feature".to_string(),
+            });
+        };
+        if self.display == Some(DisplayKw::Grid) {
+            unsupported("display", "grid".to_string());
+        }
+        fn debug<T: std::fmt::Debug>(value: &Option<T>) -> Option<String> {
+            value.as_ref().map(|value| format!("{:?}", value))
+        }
+        let fields = [
+            ("grid_template_columns", debug(&self.grid_template_columns)),
+            ("grid_template_rows", debug(&self.grid_template_rows)),
+            ("grid_auto_columns", debug(&self.grid_auto_columns)),
+            ("grid_auto_rows", debug(&self.grid_auto_rows)),
+            ("grid_auto_flow", debug(&self.grid_auto_flow)),
+            ("grid_column_start", debug(&self.grid_column_start)),
+            ("grid_column_end", debug(&self.grid_column_end)),
+            ("grid_row_start", debug(&self.grid_row_start)),
+            ("grid_row_end", debug(&self.grid_row_end)),
+        ];
+        for (property, value) in fields {
+            if let Some(value) = value {
+                unsupported(property, value);
+            }
+        }
+    }
+
+    fn size_keywords(&self) -> SizeKeywords {
+        SizeKeywords {
+            width: self.width_keyword,
+            height: self.height_keyword,
+            min_width: self.min_width_keyword,
+            max_width: self.max_width_keyword,
+            min_height: self.min_height_keyword,
+            max_height: self.max_height_keyword,
+        }
+    }
+
+    fn to_taffy(&self, defaults: DefaultsProfile) -> Style {
+        let mut style = defaults.base_style();
+
+        // Display
+        if let Some(d) = self.display {
+            style.display = d.into();
+        }
+
+        // Flex direction
+        if let Some(fd) = self.flex_direction {
+            style.flex_direction = fd.into();
+        }
+
+        // Flex wrap
+        if let Some(fw) = self.flex_wrap {
+            style.flex_wrap = fw.into();
         }
 
         // Flex properties
@@ -368,42 +1510,25 @@ impl StyleInput {
         if let Some(fb) = self.flex_basis {
             style.flex_basis = Dimension::length(fb);
         }
+        if let Some(Ok((grow, shrink, basis))) = self.flex.as_deref().map(parse_flex_shorthand) {
+            style.flex_grow = grow;
+            style.flex_shrink = shrink;
+            style.flex_basis = basis;
+        }
 
         // Align items
-        if let Some(ref ai) = self.align_items {
-            style.align_items = Some(match ai.as_str() {
-                "flex-start" => AlignItems::FlexStart,
-                "flex-end" => AlignItems::FlexEnd,
-                "center" => AlignItems::Center,
-                "stretch" => AlignItems::Stretch,
-                "baseline" => AlignItems::Baseline,
-                _ => AlignItems::Stretch,
-            });
+        if let Some(ai) = self.align_items {
+            style.align_items = ai.to_taffy();
         }
 
         // Justify content
-        if let Some(ref jc) = self.justify_content {
-            style.justify_content = Some(match jc.as_str() {
-                "flex-start" => JustifyContent::FlexStart,
-                "flex-end" => JustifyContent::FlexEnd,
-                "center" => JustifyContent::Center,
-                "space-between" => JustifyContent::SpaceBetween,
-                "space-around" => JustifyContent::SpaceAround,
-                "space-evenly" => JustifyContent::SpaceEvenly,
-                _ => JustifyContent::FlexStart,
-            });
+        if let Some(jc) = self.justify_content {
+            style.justify_content = Some(jc.into());
         }
 
         // Align self
-        if let Some(ref als) = self.align_self {
-            style.align_self = Some(match als.as_str() {
-                "flex-start" => AlignSelf::FlexStart,
-                "flex-end" => AlignSelf::FlexEnd,
-                "center" => AlignSelf::Center,
-                "stretch" => AlignSelf::Stretch,
-                "baseline" => AlignSelf::Baseline,
-                _ => AlignSelf::Start,
-            });
+        if let Some(als) = self.align_self {
+            style.align_self = als.to_taffy();
         }
 
         // Gap
@@ -457,6 +1582,21 @@ impl StyleInput {
             style.max_size.height = Dimension::percent(mhp / 100.0);
         }
 
+        // Keyword sizes are left auto here and resolved before each layout
+        let keywords = self.size_keywords();
+        for (keyword, dimension) in [
+            (keywords.width, &mut style.size.width),
+            (keywords.height, &mut style.size.height),
+            (keywords.min_width, &mut style.min_size.width),
+            (keywords.max_width, &mut style.max_size.width),
+            (keywords.min_height, &mut style.min_size.height),
+            (keywords.max_height, &mut style.max_size.height),
+        ] {
+            if keyword.is_some() {
+                *dimension = Dimension::auto();
+            }
+        }
+
         // Padding
         if let Some(pt) = self.padding_top {
             style.padding.top = LengthPercentage::length(pt);
@@ -497,12 +1637,8 @@ impl StyleInput {
         }
 
         // Position
-        if let Some(ref p) = self.position {
-            style.position = match p.as_str() {
-                "relative" => Position::Relative,
-                "absolute" => Position::Absolute,
-                _ => Position::Relative,
-            };
+        if let Some(p) = self.position {
+            style.position = p.into();
         }
 
         if let Some(t) = self.top {
@@ -519,23 +1655,13 @@ impl StyleInput {
         }
 
         // Overflow
-        let parse_overflow = |s: &str| match s {
-            "visible" => Overflow::Visible,
-            "hidden" => Overflow::Hidden,
-            "scroll" => Overflow::Scroll,
-            _ => Overflow::Visible,
-        };
-
-        if let Some(ref o) = self.overflow {
-            let ov = parse_overflow(o);
-            style.overflow = TaffyPoint { x: ov, y: ov };
-        }
-        if let Some(ref ox) = self.overflow_x {
-            style.overflow.x = parse_overflow(ox);
+        if let Some(ox) = self.overflow_x.or(self.overflow) {
+            style.overflow.x = ox.into();
         }
-        if let Some(ref oy) = self.overflow_y {
-            style.overflow.y = parse_overflow(oy);
+        if let Some(oy) = self.overflow_y.or(self.overflow) {
+            style.overflow.y = oy.into();
         }
+        style.overflow = computed_overflow(style.overflow);
 
         // Border (affects layout)
         if let Some(bw) = self.border_width {
@@ -552,6 +1678,14 @@ impl StyleInput {
             style.aspect_ratio = Some(ar);
         }
 
+        #[cfg(feature = "grid")]
+        self.apply_grid(&mut style);
+
+        style
+    }
+
+    #[cfg(feature = "grid")]
+    fn apply_grid(&self, style: &mut Style) {
         // CSS Grid Container Properties
         if let Some(ref cols) = self.grid_template_columns {
             style.grid_template_columns = cols.to_taffy();
@@ -565,14 +1699,8 @@ impl StyleInput {
         if let Some(ref auto_rows) = self.grid_auto_rows {
             style.grid_auto_rows = vec![auto_rows.to_taffy()];
         }
-        if let Some(ref flow) = self.grid_auto_flow {
-            style.grid_auto_flow = match flow.as_str() {
-                "row" => GridAutoFlow::Row,
-                "column" => GridAutoFlow::Column,
-                "row-dense" => GridAutoFlow::RowDense,
-                "column-dense" => GridAutoFlow::ColumnDense,
-                _ => GridAutoFlow::Row,
-            };
+        if let Some(flow) = self.grid_auto_flow {
+            style.grid_auto_flow = flow.into();
         }
 
         // CSS Grid Item Properties
@@ -588,8 +1716,299 @@ impl StyleInput {
         if let Some(ref end) = self.grid_row_end {
             style.grid_row.end = end.to_taffy();
         }
+    }
+}
 
-        style
+/// The intrinsic sizing keywords of a StyleInput.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct SizeKeywords {
+    width: Option<SizeKw>,
+    height: Option<SizeKw>,
+    min_width: Option<SizeKw>,
+    max_width: Option<SizeKw>,
+    min_height: Option<SizeKw>,
+    max_height: Option<SizeKw>,
+}
+
+impl SizeKeywords {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn has_height(&self) -> bool {
+        self.height.is_some() || self.min_height.is_some() || self.max_height.is_some()
+    }
+
+    /// Report the keywords on a read-back StyleInput in place of lengths.
+    fn write_to(&self, input: &mut StyleInput) {
+        let fields = [
+            (self.width, &mut input.width_keyword, &mut input.width),
+            (self.height, &mut input.height_keyword, &mut input.height),
+            (
+                self.min_width,
+                &mut input.min_width_keyword,
+                &mut input.min_width,
+            ),
+            (
+                self.max_width,
+                &mut input.max_width_keyword,
+                &mut input.max_width,
+            ),
+            (
+                self.min_height,
+                &mut input.min_height_keyword,
+                &mut input.min_height,
+            ),
+            (
+                self.max_height,
+                &mut input.max_height_keyword,
+                &mut input.max_height,
+            ),
+        ];
+        for (keyword, field, length) in fields {
+            if keyword.is_some() {
+                *field = keyword;
+                *length = None;
+            }
+        }
+    }
+
+    /// Set the width fields of `style` from a node's border-box min- and
+    /// max-content widths.
+    fn resolve_widths(&self, style: &mut Style, min_content: f32, max_content: f32) {
+        let pick = |keyword: SizeKw, fit: f32| match keyword {
+            SizeKw::MinContent => min_content,
+            SizeKw::MaxContent => max_content,
+            SizeKw::FitContent => fit,
+        };
+        if let Some(keyword) = self.width {
+            if keyword == SizeKw::FitContent {
+                if style.min_size.width.is_auto() {
+                    style.min_size.width = Dimension::length(min_content);
+                }
+                let max = length_px(style.max_size.width.into_raw())
+                    .map_or(max_content, |max| max.min(max_content));
+                style.max_size.width = Dimension::length(max);
+            } else {
+                style.size.width = Dimension::length(pick(keyword, 0.0));
+            }
+        }
+        if let Some(keyword) = self.min_width {
+            style.min_size.width = Dimension::length(pick(keyword, min_content));
+        }
+        if let Some(keyword) = self.max_width {
+            style.max_size.width = Dimension::length(pick(keyword, max_content));
+        }
+    }
+
+    /// Set the height fields of `style` to its content height.
+    fn resolve_heights(&self, style: &mut Style, content: f32) {
+        let fields = [
+            (self.height, &mut style.size.height),
+            (self.min_height, &mut style.min_size.height),
+            (self.max_height, &mut style.max_size.height),
+        ];
+        for (keyword, dimension) in fields {
+            if keyword.is_some() {
+                *dimension = Dimension::length(content);
+            }
+        }
+    }
+}
+
+/// A node with intrinsic sizes: its keywords and the style they are resolved
+/// against, with those fields left auto.
+#[derive(Clone, Debug)]
+struct IntrinsicSizing {
+    keywords: SizeKeywords,
+    base: Style,
+}
+
+/// A style from `register_style`, converted once for every node using it.
+#[derive(Clone, Debug)]
+struct RegisteredStyle {
+    style: Style,
+    keywords: SizeKeywords,
+    ignore_safe_area: bool,
+}
+
+impl RegisteredStyle {
+    fn new(input: &StyleInput, defaults: DefaultsProfile) -> Self {
+        Self {
+            style: input.to_taffy(defaults),
+            keywords: input.size_keywords(),
+            ignore_safe_area: input.ignore_safe_area.unwrap_or(false),
+        }
+    }
+}
+
+/// Edges within half a pixel of the inset box count as on it, which absorbs
+/// Taffy's rounding.
+const SAFE_AREA_EDGE: Tolerance = Tolerance::absolute(0.5);
+
+/// The viewport a root was laid out in by `compute_layout_with_insets`.
+#[derive(Clone, Copy, Debug)]
+struct SafeArea {
+    width: f32,
+    height: f32,
+    insets: EdgeSizes,
+}
+
+impl SafeArea {
+    /// How far a flagged node's edges are grown, in absolute coordinates.
+    fn grow(&self, x: f32, y: f32, width: f32, height: f32) -> (f32, f32, f32, f32) {
+        let insets = &self.insets;
+        let on = |edge: f32, bound: f32| SAFE_AREA_EDGE.eq(edge, bound);
+        let left = if on(x, insets.left) { x } else { 0.0 };
+        let top = if on(y, insets.top) { y } else { 0.0 };
+        let right = if on(x + width, self.width - insets.right) {
+            self.width - (x + width)
+        } else {
+            0.0
+        };
+        let bottom = if on(y + height, self.height - insets.bottom) {
+            self.height - (y + height)
+        } else {
+            0.0
+        };
+        (left, top, right, bottom)
+    }
+}
+
+/// Main-axis sizes of a virtual container's logical children, from
+/// `set_virtual_children`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VirtualItemSizes {
+    /// Every item has this size
+    Uniform(f32),
+    /// One size per item
+    Each(Vec<f32>),
+}
+
+/// How a root fits one available width tried by `probe_layout_widths`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WidthProbe {
+    pub width: f32,
+    /// How far the root reaches past the available width, or its content
+    /// past the root's padding box, whichever is further. Zero when it fits.
+    pub overflow_x: f32,
+    /// The same vertically, against the available height when it is finite.
+    pub overflow_y: f32,
+    /// Main-axis space left unfilled by items, summed over every flex line
+    /// of every flex container in the tree. Free space that auto margins
+    /// take counts as filled; space `justify_content` spreads out does not.
+    pub slack: f32,
+}
+
+/// The logical children of a virtual container that overlap a viewport,
+/// from `get_virtual_window`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VirtualWindow {
+    pub first_index: usize,
+    /// Inclusive; equal to `first_index` when one item is visible
+    pub last_index: usize,
+    /// Main-axis offset of each item from `first_index` to `last_index`,
+    /// from the container's padding edge, for use as the `top` or `left`
+    /// inset of an absolutely positioned child. Empty when the container
+    /// has no items.
+    pub offsets: Vec<f32>,
+}
+
+/// Sizes of a virtual container's logical children in a Fenwick tree, so
+/// changing one size and finding offsets each cost a walk of about log2(n)
+/// entries. Sums are kept in f64: in f32, offsets tens of thousands of rows
+/// down would be off by whole fractions of a pixel.
+#[derive(Clone, Debug)]
+struct VirtualChildren {
+    sizes: Vec<f32>,
+    /// `tree[i]` sums the sizes of items `i - lowbit(i)..i`
+    tree: Vec<f64>,
+}
+
+impl VirtualChildren {
+    fn new(sizes: Vec<f32>) -> Self {
+        let sizes: Vec<f32> = sizes.into_iter().map(virtual_size).collect();
+        let mut tree = vec![0.0; sizes.len() + 1];
+        for (index, &size) in sizes.iter().enumerate() {
+            let i = index + 1;
+            tree[i] += f64::from(size);
+            let parent = i + (i & i.wrapping_neg());
+            if parent < tree.len() {
+                tree[parent] += tree[i];
+            }
+        }
+        Self { sizes, tree }
+    }
+
+    fn len(&self) -> usize {
+        self.sizes.len()
+    }
+
+    fn set(&mut self, index: usize, size: f32) {
+        let size = virtual_size(size);
+        let delta = f64::from(size) - f64::from(self.sizes[index]);
+        self.sizes[index] = size;
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] += delta;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Total size of the items before `index`.
+    fn offset(&self, index: usize) -> f64 {
+        let mut sum = 0.0;
+        let mut i = index.min(self.len());
+        while i > 0 {
+            sum += self.tree[i];
+            i &= i - 1;
+        }
+        sum
+    }
+
+    /// The item covering `position`, measured from the first item's start.
+    /// An item covers its start but not its end, and positions outside the
+    /// items clamp to the first or last one. Call with at least one item.
+    fn index_at(&self, position: f64) -> usize {
+        let mut index = 0;
+        let mut remaining = position;
+        let mut step = self.tree.len().next_power_of_two() / 2;
+        while step > 0 {
+            let next = index + step;
+            if next < self.tree.len() && self.tree[next] <= remaining {
+                index = next;
+                remaining -= self.tree[next];
+            }
+            step /= 2;
+        }
+        index.min(self.len() - 1)
+    }
+}
+
+/// Item sizes are lengths: negative and non-finite ones count as zero.
+fn virtual_size(size: f32) -> f32 {
+    if size.is_finite() {
+        size.max(0.0)
+    } else {
+        0.0
+    }
+}
+
+/// Whether a container lays its children out in a row, which makes its
+/// virtual children run horizontally. Everything else, including block
+/// and grid containers, runs them vertically.
+fn lays_out_in_row(style: &Style) -> bool {
+    style.display == Display::Flex
+        && matches!(
+            style.flex_direction,
+            FlexDirection::Row | FlexDirection::RowReverse
+        )
+}
+
+/// Give `to` a copy of the entry `from` has in a per-node table.
+fn copy_entry<T: Clone>(table: &mut HashMap<u64, T>, from: u64, to: u64) {
+    if let Some(value) = table.get(&from).cloned() {
+        table.insert(to, value);
     }
 }
 
@@ -602,46 +2021,152 @@ pub struct NodeContext {
     pub measure_id: Option<u64>,
 }
 
+/// Resolved widths of a node's four edges in pixels. Also the insets of
+/// `compute_layout_with_insets`, where missing edges are zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EdgeSizes {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl From<Rect<f32>> for EdgeSizes {
+    fn from(rect: Rect<f32>) -> Self {
+        Self {
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+            left: rect.left,
+        }
+    }
+}
+
+/// What a measure function is asked to size.
+///
+/// All sizes describe the node's content box: its padding, border and any
+/// scrollbar gutter are already subtracted from the known and available
+/// sizes, and the size the function returns is a content size that the
+/// engine grows by the same amounts. So a text leaf with 12px of horizontal
+/// padding stretched across a 200px column wraps at 176px and is laid out
+/// 200px wide. Unbounded available space (min- or max-content) is infinite.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeasureRequest {
+    pub measure_id: u64,
+    pub known_width: Option<f32>,
+    pub known_height: Option<f32>,
+    pub available_width: f32,
+    pub available_height: f32,
+    /// The padding removed from the sizes above, for hosts to check against
+    pub padding: EdgeSizes,
+    pub border: EdgeSizes,
+}
+
+/// Pending readback entry: node, parent content origin in absolute
+/// coordinates, inherited clip, and inherited transform.
+type ReadbackEntry = (NodeId, f32, f32, ClipRect, Matrix2D);
+
+/// Scratch state reused across absolute layout readbacks.
+struct ReadbackArena {
+    /// When false the stack is dropped after each readback. Controlled by the
+    /// `arena` feature so outputs can be compared with and without reuse.
+    retain: bool,
+    stack: Vec<ReadbackEntry>,
+    /// Result count of the previous readback, used to size the next one so a
+    /// steady-state frame allocates its output exactly once.
+    last_len: usize,
+    peak_bytes: usize,
+    resets: u64,
+}
+
+impl ReadbackArena {
+    fn new(retain: bool) -> Self {
+        Self {
+            retain,
+            stack: Vec::new(),
+            last_len: 0,
+            peak_bytes: 0,
+            resets: 0,
+        }
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.stack.capacity() * std::mem::size_of::<ReadbackEntry>()
+    }
+}
+
+/// Scratch memory held by the engine, from `memory_report`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MemoryReport {
+    /// Bytes currently reserved by the readback arena.
+    pub arena_capacity_bytes: usize,
+    /// Largest arena reservation at the end of any readback.
+    pub arena_peak_bytes: usize,
+    /// Number of readbacks that have reset the arena.
+    pub arena_resets: u64,
+}
+
 /// The main layout engine, wrapping Taffy.
 #[wasm_bindgen]
 pub struct TaffyLayoutEngine {
     tree: TaffyTree<NodeContext>,
     node_map: HashMap<u64, NodeId>,
     reverse_map: HashMap<NodeId, u64>,
+    /// Never reset, so IDs held across `clear` cannot alias new nodes.
     next_id: u64,
+    /// First ID issued in each generation; `clear` starts a new one.
+    generation_starts: Vec<u64>,
+    /// Scroll offsets of scroll containers, keyed by layout ID.
+    scroll_offsets: HashMap<u64, (f32, f32)>,
+    /// Exclusions set on containers, keyed by layout ID.
+    exclusions: HashMap<u64, Vec<ExclusionRect>>,
+    /// Rendering transforms, keyed by layout ID.
+    transforms: HashMap<u64, TransformInput>,
+    /// Nodes with intrinsic sizing keywords, keyed by layout ID.
+    intrinsic_sizes: HashMap<u64, IntrinsicSizing>,
+    /// Styles from `register_style`, keyed by style ID. They outlive `clear`.
+    styles: HandleRegistry<RegisteredStyle>,
+    /// Registered style each node uses, keyed by layout ID. Nodes with
+    /// ad-hoc styles have no entry.
+    node_styles: HashMap<u64, u64>,
+    /// Logical children of virtual containers, keyed by layout ID.
+    virtual_children: HashMap<u64, VirtualChildren>,
+    /// Viewports of roots laid out with insets, keyed by layout ID.
+    safe_areas: HashMap<u64, SafeArea>,
+    /// Available size each root was last laid out in, keyed by layout ID,
+    /// so probes can put the layout back.
+    root_spaces: HashMap<u64, (f32, f32)>,
+    /// Nodes whose style sets `ignore_safe_area`.
+    ignores_safe_area: HashSet<u64>,
+    readback: ReadbackArena,
+    defaults: DefaultsProfile,
+    /// Warnings from styles set since the last `take_style_warnings`.
+    style_warnings: Vec<StyleWarning>,
 }
 
 #[wasm_bindgen]
 impl TaffyLayoutEngine {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        Self {
-            tree: TaffyTree::new(),
-            node_map: HashMap::new(),
-            reverse_map: HashMap::new(),
-            next_id: 0,
-        }
+        Self::with_arena(cfg!(feature = "arena"))
+    }
+
+    /// Select the defaults for unset style properties: "taffy" (the default)
+    /// or "css". Applies to styles set after the call; see DefaultsProfile for
+    /// the table of values.
+    #[wasm_bindgen]
+    pub fn set_defaults(&mut self, profile: &str) -> Result<(), JsValue> {
+        self.defaults = DefaultsProfile::parse(profile)?;
+        Ok(())
     }
 
     /// Create a new layout node with the given style.
     /// Returns a LayoutId that can be used to reference this node.
     #[wasm_bindgen]
     pub fn new_leaf(&mut self, style_js: JsValue) -> Result<LayoutId, JsValue> {
-        let style_input: StyleInput = serde_wasm_bindgen::from_value(style_js)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse style: {}", e)))?;
-
-        let taffy_style = style_input.to_taffy();
-        let node_id = self
-            .tree
-            .new_leaf_with_context(taffy_style, NodeContext::default())
-            .map_err(|e| JsValue::from_str(&format!("Taffy error: {:?}", e)))?;
-
-        let id = self.next_id;
-        self.next_id += 1;
-        self.node_map.insert(id, node_id);
-        self.reverse_map.insert(node_id, id);
-
-        Ok(LayoutId(id))
+        let style_input = parse_style(style_js)?;
+        Ok(self.new_leaf_native(&style_input)?)
     }
 
     /// Create a new measurable leaf node (e.g., text).
@@ -652,25 +2177,8 @@ impl TaffyLayoutEngine {
         style_js: JsValue,
         measure_id: u64,
     ) -> Result<LayoutId, JsValue> {
-        let style_input: StyleInput = serde_wasm_bindgen::from_value(style_js)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse style: {}", e)))?;
-
-        let taffy_style = style_input.to_taffy();
-        let context = NodeContext {
-            measure_id: Some(measure_id),
-        };
-
-        let node_id = self
-            .tree
-            .new_leaf_with_context(taffy_style, context)
-            .map_err(|e| JsValue::from_str(&format!("Taffy error: {:?}", e)))?;
-
-        let id = self.next_id;
-        self.next_id += 1;
-        self.node_map.insert(id, node_id);
-        self.reverse_map.insert(node_id, id);
-
-        Ok(LayoutId(id))
+        let style_input = parse_style(style_js)?;
+        Ok(self.new_measurable_leaf_native(&style_input, measure_id)?)
     }
 
     /// Create a new layout node with children.
@@ -680,47 +2188,86 @@ impl TaffyLayoutEngine {
         style_js: JsValue,
         children_js: JsValue,
     ) -> Result<LayoutId, JsValue> {
-        let style_input: StyleInput = serde_wasm_bindgen::from_value(style_js)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse style: {}", e)))?;
+        let style_input = parse_style(style_js)?;
+        let child_ids = parse_children(children_js)?;
+        Ok(self.new_with_children_native(&style_input, &child_ids)?)
+    }
 
-        let child_ids: Vec<u64> = serde_wasm_bindgen::from_value(children_js)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse children: {}", e)))?;
+    /// Update the style of an existing node.
+    #[wasm_bindgen]
+    pub fn set_style(&mut self, layout_id: &LayoutId, style_js: JsValue) -> Result<(), JsValue> {
+        let style_input = parse_style(style_js)?;
+        Ok(self.set_style_native(layout_id, &style_input)?)
+    }
 
-        let child_nodes: Vec<NodeId> = child_ids
-            .iter()
-            .filter_map(|id| self.node_map.get(id).copied())
-            .collect();
+    /// Parse a style once for any number of nodes. Unset properties take the
+    /// defaults selected when the style is registered or updated.
+    #[wasm_bindgen]
+    pub fn register_style(&mut self, style_js: JsValue) -> Result<StyleId, JsValue> {
+        let style_input = parse_style(style_js)?;
+        Ok(self.register_style_native(&style_input))
+    }
 
-        let taffy_style = style_input.to_taffy();
-        let node_id = self
-            .tree
-            .new_with_children(taffy_style, &child_nodes)
-            .map_err(|e| JsValue::from_str(&format!("Taffy error: {:?}", e)))?;
+    /// A 64-bit hash of a style as 16 hex digits, for memoizing the layout
+    /// of static subtrees. It covers every field of the style as parsed and
+    /// the defaults profile unset fields take, so styles that lay out
+    /// differently hash differently. Key available space and measured
+    /// content alongside it.
+    #[wasm_bindgen]
+    pub fn hash_style(&self, style_js: JsValue) -> Result<String, JsValue> {
+        let style_input = parse_style(style_js)?;
+        Ok(format!("{:016x}", self.hash_style_native(&style_input)))
+    }
 
-        let id = self.next_id;
-        self.next_id += 1;
-        self.node_map.insert(id, node_id);
-        self.reverse_map.insert(node_id, id);
+    /// Create a new layout node using a registered style.
+    #[wasm_bindgen]
+    pub fn new_leaf_with_style_id(&mut self, style_id: &StyleId) -> Result<LayoutId, JsValue> {
+        Ok(self.new_leaf_with_style_id_native(style_id)?)
+    }
 
-        Ok(LayoutId(id))
+    /// Switch a node to a registered style. A later `set_style` switches it
+    /// back to an ad-hoc one.
+    #[wasm_bindgen]
+    pub fn set_style_id(
+        &mut self,
+        layout_id: &LayoutId,
+        style_id: &StyleId,
+    ) -> Result<(), JsValue> {
+        Ok(self.set_style_id_native(layout_id, style_id)?)
     }
 
-    /// Update the style of an existing node.
+    /// Replace a registered style, restyling every node that uses it.
     #[wasm_bindgen]
-    pub fn set_style(&mut self, layout_id: &LayoutId, style_js: JsValue) -> Result<(), JsValue> {
-        let style_input: StyleInput = serde_wasm_bindgen::from_value(style_js)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse style: {}", e)))?;
+    pub fn update_registered_style(
+        &mut self,
+        style_id: &StyleId,
+        style_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let style_input = parse_style(style_js)?;
+        Ok(self.update_registered_style_native(style_id, &style_input)?)
+    }
 
-        let node_id = self
-            .node_map
-            .get(&layout_id.0)
-            .ok_or_else(|| JsValue::from_str("Invalid layout ID"))?;
+    /// Free a registered style. Nodes using it keep its properties as an
+    /// ad-hoc style, and the ID goes stale. Unregistering a stale or
+    /// unknown ID does nothing but count toward `double_frees`.
+    #[wasm_bindgen]
+    pub fn unregister_style(&mut self, style_id: &StyleId) {
+        self.unregister_style_native(style_id);
+    }
 
-        self.tree
-            .set_style(*node_id, style_input.to_taffy())
-            .map_err(|e| JsValue::from_str(&format!("Taffy error: {:?}", e)))?;
+    /// Number of frees of IDs that were already freed or never issued,
+    /// which point at a host bug.
+    #[wasm_bindgen]
+    pub fn double_frees(&self) -> u64 {
+        self.styles.double_frees()
+    }
 
-        Ok(())
+    /// Values that could not be used in the styles set or registered since
+    /// the last call, as `StyleWarning[]`, oldest first. Calling clears them.
+    #[wasm_bindgen]
+    pub fn take_style_warnings(&mut self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.take_style_warnings_native())
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
     /// Set children of a node.
@@ -730,24 +2277,8 @@ impl TaffyLayoutEngine {
         layout_id: &LayoutId,
         children_js: JsValue,
     ) -> Result<(), JsValue> {
-        let child_ids: Vec<u64> = serde_wasm_bindgen::from_value(children_js)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse children: {}", e)))?;
-
-        let node_id = self
-            .node_map
-            .get(&layout_id.0)
-            .ok_or_else(|| JsValue::from_str("Invalid layout ID"))?;
-
-        let child_nodes: Vec<NodeId> = child_ids
-            .iter()
-            .filter_map(|id| self.node_map.get(id).copied())
-            .collect();
-
-        self.tree
-            .set_children(*node_id, &child_nodes)
-            .map_err(|e| JsValue::from_str(&format!("Taffy error: {:?}", e)))?;
-
-        Ok(())
+        let child_ids = parse_children(children_js)?;
+        Ok(self.set_children_native(layout_id, &child_ids)?)
     }
 
     /// Compute layout for the tree rooted at the given node.
@@ -758,28 +2289,16 @@ impl TaffyLayoutEngine {
         available_width: f32,
         available_height: f32,
     ) -> Result<(), JsValue> {
-        let node_id = self
-            .node_map
-            .get(&root_id.0)
-            .ok_or_else(|| JsValue::from_str("Invalid layout ID"))?;
-
-        self.tree
-            .compute_layout(
-                *node_id,
-                Size {
-                    width: AvailableSpace::Definite(available_width),
-                    height: AvailableSpace::Definite(available_height),
-                },
-            )
-            .map_err(|e| JsValue::from_str(&format!("Taffy error: {:?}", e)))?;
-
-        Ok(())
+        Ok(self.compute_layout_native(root_id, available_width, available_height)?)
     }
 
     /// Compute layout with a measure function callback for measurable nodes.
     ///
-    /// The callback receives: (measure_id, known_width, known_height, available_width, available_height)
-    /// And should return: { width: number, height: number }
+    /// The callback receives: (measure_id, known_width, known_height,
+    /// available_width, available_height, padding, border), sized to the
+    /// node's content box as described on MeasureRequest, with NaN for
+    /// unknown and Infinity for unbounded sizes.
+    /// And should return the content size: { width: number, height: number }
     #[wasm_bindgen]
     pub fn compute_layout_with_measure(
         &mut self,
@@ -788,111 +2307,95 @@ impl TaffyLayoutEngine {
         available_height: f32,
         measure_callback: &Function,
     ) -> Result<(), JsValue> {
-        let node_id = self
-            .node_map
-            .get(&root_id.0)
-            .ok_or_else(|| JsValue::from_str("Invalid layout ID"))?;
+        self.compute_layout_with_measure_native(
+            root_id,
+            available_width,
+            available_height,
+            js_measure(measure_callback),
+        )?;
 
-        let this = JsValue::null();
-
-        self.tree
-            .compute_layout_with_measure(
-                *node_id,
-                Size {
-                    width: AvailableSpace::Definite(available_width),
-                    height: AvailableSpace::Definite(available_height),
-                },
-                |known_dimensions, available_space, _node_id, node_context, _style| {
-                    // Only call JS for nodes with measure_id
-                    let Some(context) = node_context else {
-                        return Size::ZERO;
-                    };
-                    let Some(measure_id) = context.measure_id else {
-                        return Size::ZERO;
-                    };
-
-                    // Convert AvailableSpace to f64 for JS
-                    // For MinContent and MaxContent, we pass Infinity to signal "don't wrap"
-                    // The JS callback will interpret Infinity as no wrapping constraint
-                    let avail_width = match available_space.width {
-                        AvailableSpace::Definite(v) => v as f64,
-                        AvailableSpace::MinContent => f64::INFINITY,
-                        AvailableSpace::MaxContent => f64::INFINITY,
-                    };
-                    let avail_height = match available_space.height {
-                        AvailableSpace::Definite(v) => v as f64,
-                        AvailableSpace::MinContent => f64::INFINITY,
-                        AvailableSpace::MaxContent => f64::INFINITY,
-                    };
-
-                    // Convert known dimensions (None becomes NaN in JS)
-                    let known_w = known_dimensions.width.map(|v| v as f64).unwrap_or(f64::NAN);
-                    let known_h = known_dimensions
-                        .height
-                        .map(|v| v as f64)
-                        .unwrap_or(f64::NAN);
-
-                    // Call JS: measure_callback(measure_id, known_w, known_h, avail_w, avail_h)
-                    let args = js_sys::Array::new();
-                    args.push(&JsValue::from(measure_id as f64));
-                    args.push(&JsValue::from(known_w));
-                    args.push(&JsValue::from(known_h));
-                    args.push(&JsValue::from(avail_width));
-                    args.push(&JsValue::from(avail_height));
-
-                    let result = match measure_callback.apply(&this, &args) {
-                        Ok(r) => r,
-                        Err(_) => return Size::ZERO,
-                    };
-
-                    // Parse result: { width, height }
-                    let width = js_sys::Reflect::get(&result, &"width".into())
-                        .ok()
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0) as f32;
-                    let height = js_sys::Reflect::get(&result, &"height".into())
-                        .ok()
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0) as f32;
-
-                    Size { width, height }
-                },
-            )
-            .map_err(|e| JsValue::from_str(&format!("Taffy error: {:?}", e)))?;
+        Ok(())
+    }
 
+    /// Compute layout for a root filling a `available_width` by
+    /// `available_height` viewport, of which the `insets` ({ top, right,
+    /// bottom, left }, missing edges zero) are covered by system UI such as
+    /// a notch or home indicator. The root is laid out in the space inside
+    /// the insets and positioned at their top-left corner, so
+    /// `get_absolute_layouts` and `get_absolute_position` report viewport
+    /// coordinates. Nodes styled `ignore_safe_area` report their edges that
+    /// touch the inset box grown out to the viewport's. `measure_callback`
+    /// is as for `compute_layout_with_measure`, or undefined when nothing
+    /// is measurable.
+    #[wasm_bindgen]
+    pub fn compute_layout_with_insets(
+        &mut self,
+        root_id: &LayoutId,
+        available_width: f32,
+        available_height: f32,
+        insets_js: JsValue,
+        measure_callback: Option<Function>,
+    ) -> Result<(), JsValue> {
+        let insets: EdgeSizes = if insets_js.is_undefined() || insets_js.is_null() {
+            EdgeSizes::default()
+        } else {
+            serde_wasm_bindgen::from_value(insets_js)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse insets: {}", e)))?
+        };
+        match &measure_callback {
+            Some(callback) => self.compute_layout_with_insets_native(
+                root_id,
+                available_width,
+                available_height,
+                insets,
+                js_measure(callback),
+            )?,
+            None => self.compute_layout_with_insets_native(
+                root_id,
+                available_width,
+                available_height,
+                insets,
+                |_| Size::ZERO,
+            )?,
+        }
         Ok(())
     }
 
     /// Get the computed layout for a node.
     #[wasm_bindgen]
     pub fn get_layout(&self, layout_id: &LayoutId) -> Result<LayoutBounds, JsValue> {
-        let node_id = self
-            .node_map
-            .get(&layout_id.0)
-            .ok_or_else(|| JsValue::from_str("Invalid layout ID"))?;
+        let node_id = self.node(layout_id)?;
 
         let layout = self
             .tree
-            .layout(*node_id)
+            .layout(node_id)
             .map_err(|e| JsValue::from_str(&format!("Failed to get layout: {:?}", e)))?;
 
-        Ok(LayoutBounds {
+        let mut bounds = LayoutBounds {
             x: layout.location.x,
             y: layout.location.y,
             width: layout.size.width,
             height: layout.size.height,
-        })
+        };
+        bounds.enforce_finite(layout_id.0)?;
+        Ok(bounds)
     }
 
     /// Remove a node from the tree.
     #[wasm_bindgen]
     pub fn remove(&mut self, layout_id: &LayoutId) -> Result<(), JsValue> {
-        let node_id = self
-            .node_map
-            .remove(&layout_id.0)
-            .ok_or_else(|| JsValue::from_str("Invalid layout ID"))?;
-
+        let node_id = self.node(layout_id)?;
+        self.node_map.remove(&layout_id.0);
         self.reverse_map.remove(&node_id);
+        self.scroll_offsets.remove(&layout_id.0);
+        self.exclusions.remove(&layout_id.0);
+        self.transforms.remove(&layout_id.0);
+        self.intrinsic_sizes.remove(&layout_id.0);
+        self.node_styles.remove(&layout_id.0);
+        self.virtual_children.remove(&layout_id.0);
+        self.safe_areas.remove(&layout_id.0);
+        self.root_spaces.remove(&layout_id.0);
+        self.ignores_safe_area.remove(&layout_id.0);
         self.tree
             .remove(node_id)
             .map_err(|e| JsValue::from_str(&format!("Taffy error: {:?}", e)))?;
@@ -900,96 +2403,3215 @@ impl TaffyLayoutEngine {
         Ok(())
     }
 
-    /// Clear all nodes from the tree.
+    /// Copy a node and its descendants into new nodes under a detached root,
+    /// with the same styles, measure IDs, transforms, exclusions, scroll
+    /// offsets and virtual children. The copy starts dirty and shares nothing
+    /// with the original but registered styles: nodes using one in both
+    /// trees are restyled together by `update_registered_style`.
+    #[wasm_bindgen]
+    pub fn clone_subtree(&mut self, layout_id: &LayoutId) -> Result<LayoutId, JsValue> {
+        Ok(self.clone_subtree_native(layout_id)?)
+    }
+
+    /// Clear all nodes from the tree. IDs keep counting up rather than
+    /// restarting, and IDs issued before the clear report a stale ID error
+    /// naming their generation. Clearing an engine that has issued no IDs
+    /// since the last clear does nothing.
     #[wasm_bindgen]
     pub fn clear(&mut self) {
         self.tree.clear();
         self.node_map.clear();
         self.reverse_map.clear();
-        self.next_id = 0;
+        self.scroll_offsets.clear();
+        self.exclusions.clear();
+        self.transforms.clear();
+        self.intrinsic_sizes.clear();
+        self.node_styles.clear();
+        self.virtual_children.clear();
+        self.safe_areas.clear();
+        self.root_spaces.clear();
+        self.ignores_safe_area.clear();
+        if self.next_id > self.generation_start() {
+            self.generation_starts.push(self.next_id);
+        }
+    }
+
+    /// Number of clears that removed issued IDs, which is the generation of
+    /// IDs issued now.
+    #[wasm_bindgen]
+    pub fn generation(&self) -> usize {
+        self.generation_starts.len() - 1
+    }
+
+    /// Get the number of nodes in the tree.
+    #[wasm_bindgen]
+    pub fn node_count(&self) -> usize {
+        self.node_map.len()
+    }
+
+    /// Set the scroll offset of a scroll container. Descendants are shifted by
+    /// `-offset` in the absolute readback.
+    #[wasm_bindgen]
+    pub fn set_scroll_offset(
+        &mut self,
+        layout_id: &LayoutId,
+        scroll_x: f32,
+        scroll_y: f32,
+    ) -> Result<(), JsValue> {
+        self.node(layout_id)?;
+        self.scroll_offsets
+            .insert(layout_id.0, (scroll_x, scroll_y));
+        Ok(())
+    }
+
+    /// Set the rectangles that content of `container_id` flows around,
+    /// replacing any set before; an empty array removes them. `rects_js` is
+    /// an array of `{ x, y, width, height }` relative to the container's
+    /// border box. The engine does not move children out of the way: measure
+    /// functions query `available_rect` to wrap text around the rects, so
+    /// the container's children are re-measured on the next layout.
+    #[wasm_bindgen]
+    pub fn set_exclusions(
+        &mut self,
+        container_id: &LayoutId,
+        rects_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let rects: Vec<ExclusionRect> = serde_wasm_bindgen::from_value(rects_js)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse exclusions: {}", e)))?;
+        Ok(self.set_exclusions_native(container_id, rects)?)
+    }
+
+    /// Declare `count` logical children of `container_id` without creating
+    /// nodes for them, replacing any declared before. `sizes_js` is their
+    /// main-axis size: one number for all of them, or an array of `count`
+    /// numbers. Items are packed back to back along the container's main
+    /// axis, horizontal for flex rows and vertical otherwise; fold any gap
+    /// into the sizes. The container's `scroll_width` or `scroll_height`
+    /// covers every item, and the host creates nodes only for the items
+    /// `get_virtual_window` returns, positioned absolutely at their offsets.
+    #[wasm_bindgen]
+    pub fn set_virtual_children(
+        &mut self,
+        container_id: &LayoutId,
+        count: u32,
+        sizes_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let sizes =
+            match sizes_js.as_f64() {
+                Some(size) => VirtualItemSizes::Uniform(size as f32),
+                None => VirtualItemSizes::Each(serde_wasm_bindgen::from_value(sizes_js).map_err(
+                    |e| JsValue::from_str(&format!("Failed to parse item sizes: {}", e)),
+                )?),
+            };
+        Ok(self.set_virtual_children_native(container_id, count as usize, sizes)?)
+    }
+
+    /// Change the main-axis size of one virtual child, moving the offsets of
+    /// the items after it.
+    #[wasm_bindgen]
+    pub fn set_virtual_item_size(
+        &mut self,
+        container_id: &LayoutId,
+        index: u32,
+        size: f32,
+    ) -> Result<(), JsValue> {
+        Ok(self.set_virtual_item_size_native(container_id, index as usize, size)?)
+    }
+
+    /// The virtual children of `container_id` overlapping a viewport
+    /// `viewport_size` long that is scrolled `scroll_offset` along the main
+    /// axis, as a `VirtualWindow`. Offsets include the container's padding
+    /// from its most recent layout.
+    #[wasm_bindgen]
+    pub fn get_virtual_window(
+        &self,
+        container_id: &LayoutId,
+        scroll_offset: f32,
+        viewport_size: f32,
+    ) -> Result<JsValue, JsValue> {
+        let window = self.virtual_window_native(container_id, scroll_offset, viewport_size)?;
+        serde_wasm_bindgen::to_value(&window)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Lay out the tree rooted at `root_id` at each of `widths` and
+    /// `available_height`, returning a `WidthProbe` per width, for finding
+    /// responsive breakpoints in one call. Taffy's caches carry over between
+    /// probes. Afterwards the root is laid out again at the size of its last
+    /// `compute_layout`, so pass the same `measure_callback` as that call, or
+    /// undefined when nothing is measurable. A root never laid out keeps the
+    /// last probe's layout.
+    #[wasm_bindgen]
+    pub fn probe_layout_widths(
+        &mut self,
+        root_id: &LayoutId,
+        widths: &[f32],
+        available_height: f32,
+        measure_callback: Option<Function>,
+    ) -> Result<JsValue, JsValue> {
+        let probes = match &measure_callback {
+            Some(callback) => self.probe_layout_widths_native(
+                root_id,
+                widths,
+                available_height,
+                js_measure(callback),
+            )?,
+            None => {
+                self.probe_layout_widths_native(root_id, widths, available_height, |_| Size::ZERO)?
+            }
+        };
+        serde_wasm_bindgen::to_value(&probes)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Set a node's rendering transform, a `TransformInput`, or remove it
+    /// with null or undefined. Layout ignores transforms; they are resolved
+    /// against the node's size and composed with its ancestors' into the
+    /// `matrix` of `get_absolute_layouts`.
+    #[wasm_bindgen]
+    pub fn set_transform(
+        &mut self,
+        layout_id: &LayoutId,
+        transform_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let transform: Option<TransformInput> = if transform_js.is_undefined()
+            || transform_js.is_null()
+        {
+            None
+        } else {
+            Some(
+                serde_wasm_bindgen::from_value(transform_js)
+                    .map_err(|e| JsValue::from_str(&format!("Failed to parse transform: {}", e)))?,
+            )
+        };
+        Ok(self.set_transform_native(layout_id, transform)?)
+    }
+
+    /// The part of a child's width left free by its parent's exclusions over
+    /// the band `band_y`..`band_y + band_height`, measured from the child's
+    /// top. Uses the child's most recent layout. The result is relative to
+    /// the child: the widest free span, with zero width when the band is
+    /// fully covered, and the child's whole width without exclusions.
+    #[wasm_bindgen]
+    pub fn available_rect(
+        &self,
+        child_id: &LayoutId,
+        band_y: f32,
+        band_height: f32,
+    ) -> Result<LayoutBounds, JsValue> {
+        Ok(self.available_rect_native(child_id, band_y, band_height)?)
+    }
+
+    /// The position of a node's border box relative to the root of its tree,
+    /// from its most recent layout, with every ancestor's scroll offset
+    /// applied as in `get_absolute_layouts`. Walks up from the node, so it
+    /// costs its depth rather than a whole-tree readback. Transforms are not
+    /// applied.
+    #[wasm_bindgen]
+    pub fn get_absolute_position(&self, layout_id: &LayoutId) -> Result<LayoutPoint, JsValue> {
+        Ok(self.absolute_position_native(layout_id)?)
+    }
+
+    /// The position of `descendant_id`'s border box relative to
+    /// `ancestor_id`'s, with the scroll offsets of `ancestor_id` and every
+    /// node between them applied. Fails when `descendant_id` is not in the
+    /// subtree of `ancestor_id`; a node is zero from itself.
+    #[wasm_bindgen]
+    pub fn get_offset_between(
+        &self,
+        ancestor_id: &LayoutId,
+        descendant_id: &LayoutId,
+    ) -> Result<LayoutPoint, JsValue> {
+        Ok(self.offset_between_native(ancestor_id, descendant_id)?)
+    }
+
+    /// The node of `candidate_ids_js`, an array of layout IDs, that spatial
+    /// navigation moves to from `from_id` in `direction`: "up", "down",
+    /// "left" or "right". Undefined when no candidate lies that way. Uses
+    /// positions as `get_absolute_position` reports them; see
+    /// `find_in_direction_native` for how candidates are ranked.
+    #[wasm_bindgen]
+    pub fn find_in_direction(
+        &self,
+        from_id: &LayoutId,
+        direction: &str,
+        candidate_ids_js: JsValue,
+    ) -> Result<Option<LayoutId>, JsValue> {
+        let direction = NavDirection::parse(direction)?;
+        let candidate_ids: Vec<u64> = serde_wasm_bindgen::from_value(candidate_ids_js)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse candidates: {}", e)))?;
+        Ok(self.find_in_direction_native(from_id, direction, &candidate_ids)?)
+    }
+
+    /// Read back the style of a node as a StyleInput with canonical keywords.
+    #[cfg(feature = "debug-tools")]
+    #[wasm_bindgen]
+    pub fn get_style(&self, layout_id: &LayoutId) -> Result<JsValue, JsValue> {
+        let style = self.style_input(layout_id)?;
+        serde_wasm_bindgen::to_value(&style)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    #[cfg(not(feature = "debug-tools"))]
+    #[wasm_bindgen]
+    pub fn get_style(&self, _layout_id: &LayoutId) -> Result<JsValue, JsValue> {
+        Err(feature_disabled("debug-tools"))
+    }
+
+    /// Read back the absolute layout of every node in the subtree rooted at
+    /// `root_id`, in depth-first order, including per-node clip information.
+    #[wasm_bindgen]
+    pub fn get_absolute_layouts(&mut self, root_id: &LayoutId) -> Result<JsValue, JsValue> {
+        let layouts = self.absolute_layouts(root_id)?;
+        serde_wasm_bindgen::to_value(&layouts)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Report the scratch memory retained between readbacks.
+    #[cfg(feature = "debug-tools")]
+    #[wasm_bindgen]
+    pub fn memory_report(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.memory_report_native())
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    #[cfg(not(feature = "debug-tools"))]
+    #[wasm_bindgen]
+    pub fn memory_report(&self) -> Result<JsValue, JsValue> {
+        Err(feature_disabled("debug-tools"))
+    }
+}
+
+impl TaffyLayoutEngine {
+    fn with_arena(retain: bool) -> Self {
+        Self {
+            tree: TaffyTree::new(),
+            node_map: HashMap::new(),
+            reverse_map: HashMap::new(),
+            next_id: 0,
+            generation_starts: vec![0],
+            scroll_offsets: HashMap::new(),
+            exclusions: HashMap::new(),
+            transforms: HashMap::new(),
+            intrinsic_sizes: HashMap::new(),
+            styles: HandleRegistry::new(),
+            node_styles: HashMap::new(),
+            virtual_children: HashMap::new(),
+            safe_areas: HashMap::new(),
+            root_spaces: HashMap::new(),
+            ignores_safe_area: HashSet::new(),
+            readback: ReadbackArena::new(retain),
+            defaults: DefaultsProfile::default(),
+            style_warnings: Vec::new(),
+        }
+    }
+
+    /// Native counterpart of `memory_report`.
+    pub fn memory_report_native(&self) -> MemoryReport {
+        MemoryReport {
+            arena_capacity_bytes: self.readback.capacity_bytes(),
+            arena_peak_bytes: self.readback.peak_bytes,
+            arena_resets: self.readback.resets,
+        }
+    }
+
+    fn node(&self, layout_id: &LayoutId) -> Result<NodeId, LayoutError> {
+        self.node_map
+            .get(&layout_id.0)
+            .copied()
+            .ok_or_else(|| self.missing_id(layout_id.0))
+    }
+
+    fn generation_start(&self) -> u64 {
+        self.generation_starts.last().copied().unwrap_or(0)
+    }
+
+    /// The error for an ID with no node: stale if it predates the last clear.
+    fn missing_id(&self, id: u64) -> LayoutError {
+        if id < self.generation_start() {
+            LayoutError::StaleLayoutId {
+                id,
+                generation: self.generation_starts.partition_point(|start| *start <= id) - 1,
+            }
+        } else {
+            LayoutError::InvalidLayoutId(id)
+        }
+    }
+
+    /// Nodes for raw child IDs. Unknown IDs are skipped, but stale ones are
+    /// an error since they likely came from a detached component.
+    fn child_nodes(&self, child_ids: &[u64]) -> Result<Vec<NodeId>, LayoutError> {
+        let mut nodes = Vec::with_capacity(child_ids.len());
+        for &id in child_ids {
+            match self.node_map.get(&id) {
+                Some(&node_id) => nodes.push(node_id),
+                None if id < self.generation_start() => return Err(self.missing_id(id)),
+                None => {}
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Remember a node's intrinsic sizing keywords, or forget them when its
+    /// new style has none, along with its `ignore_safe_area` flag.
+    fn record_intrinsic_sizes(&mut self, layout_id: &LayoutId, style: &StyleInput) {
+        self.record_safe_area_flag(layout_id, style.ignore_safe_area.unwrap_or(false));
+        let keywords = style.size_keywords();
+        if keywords.is_empty() {
+            self.intrinsic_sizes.remove(&layout_id.0);
+            return;
+        }
+        let base = style.to_taffy(self.defaults);
+        self.intrinsic_sizes
+            .insert(layout_id.0, IntrinsicSizing { keywords, base });
+    }
+
+    fn record_safe_area_flag(&mut self, layout_id: &LayoutId, ignore_safe_area: bool) {
+        if ignore_safe_area {
+            self.ignores_safe_area.insert(layout_id.0);
+        } else {
+            self.ignores_safe_area.remove(&layout_id.0);
+        }
+    }
+
+    fn registered_style(&self, style_id: &StyleId) -> Result<&RegisteredStyle, LayoutError> {
+        self.styles.get(style_id.0).map_err(|stale| {
+            if stale.freed {
+                LayoutError::StaleStyleId(stale)
+            } else {
+                LayoutError::InvalidStyleId(style_id.0)
+            }
+        })
+    }
+
+    /// Record that a node uses a registered style, which the tree already
+    /// holds, along with the style's intrinsic sizing keywords.
+    fn record_style_id(&mut self, layout_id: &LayoutId, style_id: &StyleId) {
+        let Ok(registered) = self.styles.get(style_id.0) else {
+            return;
+        };
+        let ignore_safe_area = registered.ignore_safe_area;
+        let keywords = registered.keywords;
+        let base = (!keywords.is_empty()).then(|| registered.style.clone());
+        self.record_safe_area_flag(layout_id, ignore_safe_area);
+        match base {
+            Some(base) => {
+                let sizing = IntrinsicSizing { keywords, base };
+                self.intrinsic_sizes.insert(layout_id.0, sizing);
+            }
+            None => {
+                self.intrinsic_sizes.remove(&layout_id.0);
+            }
+        }
+        self.node_styles.insert(layout_id.0, style_id.0);
+    }
+
+    /// Resolve the intrinsic sizes under `root` into lengths, children
+    /// before parents so each parent measures its children's final sizes.
+    fn resolve_intrinsic_sizes(
+        &mut self,
+        root: NodeId,
+        measure: &mut impl FnMut(&MeasureRequest) -> Size<f32>,
+    ) -> Result<(), LayoutError> {
+        if self.intrinsic_sizes.is_empty() {
+            return Ok(());
+        }
+        let mut order = Vec::new();
+        let mut stack = vec![(root, false)];
+        while let Some((node, visited)) = stack.pop() {
+            if visited {
+                order.push(node);
+                continue;
+            }
+            stack.push((node, true));
+            stack.extend(
+                self.tree
+                    .children(node)?
+                    .into_iter()
+                    .map(|child| (child, false)),
+            );
+        }
+        for node in order {
+            let sizing = self
+                .reverse_map
+                .get(&node)
+                .and_then(|id| self.intrinsic_sizes.get(id))
+                .cloned();
+            if let Some(sizing) = sizing {
+                let style = self.intrinsic_style(node, &sizing, measure)?;
+                self.tree.set_style(node, style)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A node's style with its keywords replaced by measured lengths.
+    fn intrinsic_style(
+        &mut self,
+        node: NodeId,
+        sizing: &IntrinsicSizing,
+        measure: &mut impl FnMut(&MeasureRequest) -> Size<f32>,
+    ) -> Result<Style, LayoutError> {
+        let mut style = sizing.base.clone();
+        self.tree.set_style(node, style.clone())?;
+        let min_content = self.measure_subtree(node, AvailableSpace::MinContent, measure)?;
+        let max_content = self.measure_subtree(node, AvailableSpace::MaxContent, measure)?;
+        sizing
+            .keywords
+            .resolve_widths(&mut style, min_content.width, max_content.width);
+        if sizing.keywords.has_height() {
+            self.tree.set_style(node, style.clone())?;
+            let content = self.measure_subtree(node, AvailableSpace::MaxContent, measure)?;
+            sizing.keywords.resolve_heights(&mut style, content.height);
+        }
+        Ok(style)
+    }
+
+    /// Border-box size of `node` laid out on its own with `width` space.
+    fn measure_subtree(
+        &mut self,
+        node: NodeId,
+        width: AvailableSpace,
+        measure: &mut impl FnMut(&MeasureRequest) -> Size<f32>,
+    ) -> Result<Size<f32>, LayoutError> {
+        let space = Size {
+            width,
+            height: AvailableSpace::MaxContent,
+        };
+        self.tree.compute_layout_with_measure(
+            node,
+            space,
+            |known_dimensions, available_space, _node_id, node_context, style| {
+                measure_node(
+                    measure,
+                    known_dimensions,
+                    available_space,
+                    node_context,
+                    style,
+                )
+            },
+        )?;
+        Ok(self.tree.layout(node)?.size)
+    }
+
+    fn register_node(&mut self, node_id: NodeId) -> LayoutId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.node_map.insert(id, node_id);
+        self.reverse_map.insert(node_id, id);
+        LayoutId(id)
+    }
+
+    /// Native counterpart of `set_defaults`.
+    pub fn set_defaults_native(&mut self, profile: DefaultsProfile) {
+        self.defaults = profile;
+    }
+
+    /// Native counterpart of `hash_style`.
+    pub fn hash_style_native(&self, style: &StyleInput) -> u64 {
+        // Debug output names every field and prints floats exactly, so a
+        // field added to StyleInput is covered without listing it here
+        xxh64(format!("{:?} {:?}", self.defaults, style).as_bytes())
+    }
+
+    /// Native counterpart of `new_leaf`.
+    pub fn new_leaf_native(&mut self, style: &StyleInput) -> Result<LayoutId, LayoutError> {
+        let node_id = self
+            .tree
+            .new_leaf_with_context(style.to_taffy(self.defaults), NodeContext::default())?;
+        let layout_id = self.register_node(node_id);
+        self.record_intrinsic_sizes(&layout_id, style);
+        self.style_warnings.extend(style.warnings());
+        Ok(layout_id)
+    }
+
+    /// Native counterpart of `new_measurable_leaf`.
+    pub fn new_measurable_leaf_native(
+        &mut self,
+        style: &StyleInput,
+        measure_id: u64,
+    ) -> Result<LayoutId, LayoutError> {
+        let context = NodeContext {
+            measure_id: Some(measure_id),
+        };
+        let node_id = self
+            .tree
+            .new_leaf_with_context(style.to_taffy(self.defaults), context)?;
+        let layout_id = self.register_node(node_id);
+        self.record_intrinsic_sizes(&layout_id, style);
+        self.style_warnings.extend(style.warnings());
+        Ok(layout_id)
+    }
+
+    /// Native counterpart of `new_with_children`.
+    pub fn new_with_children_native(
+        &mut self,
+        style: &StyleInput,
+        child_ids: &[u64],
+    ) -> Result<LayoutId, LayoutError> {
+        let child_nodes = self.child_nodes(child_ids)?;
+        let node_id = self
+            .tree
+            .new_with_children(style.to_taffy(self.defaults), &child_nodes)?;
+        let layout_id = self.register_node(node_id);
+        self.record_intrinsic_sizes(&layout_id, style);
+        self.style_warnings.extend(style.warnings());
+        Ok(layout_id)
+    }
+
+    /// Native counterpart of `set_style`.
+    pub fn set_style_native(
+        &mut self,
+        layout_id: &LayoutId,
+        style: &StyleInput,
+    ) -> Result<(), LayoutError> {
+        let node_id = self.node(layout_id)?;
+        self.tree
+            .set_style(node_id, style.to_taffy(self.defaults))?;
+        self.record_intrinsic_sizes(layout_id, style);
+        self.style_warnings.extend(style.warnings());
+        self.node_styles.remove(&layout_id.0);
+        Ok(())
+    }
+
+    /// Native counterpart of `register_style`.
+    pub fn register_style_native(&mut self, style: &StyleInput) -> StyleId {
+        let id = self
+            .styles
+            .insert(RegisteredStyle::new(style, self.defaults));
+        self.style_warnings.extend(style.warnings());
+        StyleId(id)
+    }
+
+    /// Native counterpart of `unregister_style`. Returns whether the style
+    /// was registered.
+    pub fn unregister_style_native(&mut self, style_id: &StyleId) -> bool {
+        if self.styles.remove(style_id.0).is_none() {
+            return false;
+        }
+        self.node_styles.retain(|_, id| *id != style_id.0);
+        true
+    }
+
+    /// Native counterpart of `take_style_warnings`.
+    pub fn take_style_warnings_native(&mut self) -> Vec<StyleWarning> {
+        std::mem::take(&mut self.style_warnings)
+    }
+
+    /// Native counterpart of `new_leaf_with_style_id`.
+    pub fn new_leaf_with_style_id_native(
+        &mut self,
+        style_id: &StyleId,
+    ) -> Result<LayoutId, LayoutError> {
+        let style = self.registered_style(style_id)?.style.clone();
+        let node_id = self
+            .tree
+            .new_leaf_with_context(style, NodeContext::default())?;
+        let layout_id = self.register_node(node_id);
+        self.record_style_id(&layout_id, style_id);
+        Ok(layout_id)
+    }
+
+    /// Native counterpart of `set_style_id`.
+    pub fn set_style_id_native(
+        &mut self,
+        layout_id: &LayoutId,
+        style_id: &StyleId,
+    ) -> Result<(), LayoutError> {
+        let node_id = self.node(layout_id)?;
+        let style = self.registered_style(style_id)?.style.clone();
+        self.tree.set_style(node_id, style)?;
+        self.record_style_id(layout_id, style_id);
+        Ok(())
+    }
+
+    /// Native counterpart of `update_registered_style`.
+    pub fn update_registered_style_native(
+        &mut self,
+        style_id: &StyleId,
+        style: &StyleInput,
+    ) -> Result<(), LayoutError> {
+        self.registered_style(style_id)?;
+        if let Ok(registered) = self.styles.get_mut(style_id.0) {
+            *registered = RegisteredStyle::new(style, self.defaults);
+        }
+        self.style_warnings.extend(style.warnings());
+        let users: Vec<u64> = self
+            .node_styles
+            .iter()
+            .filter(|(_, id)| **id == style_id.0)
+            .map(|(layout_id, _)| *layout_id)
+            .collect();
+        // Setting a style marks the node and its ancestors dirty, leaving the
+        // rest of the tree's cached layout alone
+        for layout_id in users {
+            self.set_style_id_native(&LayoutId(layout_id), style_id)?;
+        }
+        Ok(())
+    }
+
+    /// Native counterpart of `set_children`.
+    pub fn set_children_native(
+        &mut self,
+        layout_id: &LayoutId,
+        child_ids: &[u64],
+    ) -> Result<(), LayoutError> {
+        let node_id = self.node(layout_id)?;
+        let child_nodes = self.child_nodes(child_ids)?;
+        self.tree.set_children(node_id, &child_nodes)?;
+        Ok(())
+    }
+
+    /// Native counterpart of `clone_subtree`.
+    pub fn clone_subtree_native(&mut self, layout_id: &LayoutId) -> Result<LayoutId, LayoutError> {
+        let node_id = self.node(layout_id)?;
+        self.clone_node(layout_id.0, node_id)
+    }
+
+    /// Copy a node and, children first, its subtree.
+    fn clone_node(&mut self, id: u64, node_id: NodeId) -> Result<LayoutId, LayoutError> {
+        let mut child_nodes = Vec::new();
+        for child in self.tree.children(node_id)? {
+            let copy = self.clone_node(self.reverse_map[&child], child)?;
+            child_nodes.push(self.node_map[&copy.0]);
+        }
+        let style = self.tree.style(node_id)?.clone();
+        let context = self.tree.get_node_context(node_id).cloned();
+        let copy_node = self.tree.new_with_children(style, &child_nodes)?;
+        self.tree.set_node_context(copy_node, context)?;
+        let copy = self.register_node(copy_node);
+        copy_entry(&mut self.scroll_offsets, id, copy.0);
+        copy_entry(&mut self.exclusions, id, copy.0);
+        copy_entry(&mut self.transforms, id, copy.0);
+        copy_entry(&mut self.intrinsic_sizes, id, copy.0);
+        copy_entry(&mut self.node_styles, id, copy.0);
+        copy_entry(&mut self.virtual_children, id, copy.0);
+        if self.ignores_safe_area.contains(&id) {
+            self.ignores_safe_area.insert(copy.0);
+        }
+        Ok(copy)
+    }
+
+    /// Native counterpart of `set_exclusions`.
+    pub fn set_exclusions_native(
+        &mut self,
+        container_id: &LayoutId,
+        rects: Vec<ExclusionRect>,
+    ) -> Result<(), LayoutError> {
+        let node_id = self.node(container_id)?;
+        // Dirtying a node only clears the caches of it and its ancestors, so
+        // children would otherwise keep sizes measured around the old rects
+        for index in 0..self.tree.child_count(node_id) {
+            let child = self.tree.child_at_index(node_id, index)?;
+            self.tree.mark_dirty(child)?;
+        }
+        self.tree.mark_dirty(node_id)?;
+        if rects.is_empty() {
+            self.exclusions.remove(&container_id.0);
+        } else {
+            self.exclusions.insert(container_id.0, rects);
+        }
+        Ok(())
+    }
+
+    /// Native counterpart of `set_transform`.
+    pub fn set_transform_native(
+        &mut self,
+        layout_id: &LayoutId,
+        transform: Option<TransformInput>,
+    ) -> Result<(), LayoutError> {
+        self.node(layout_id)?;
+        match transform {
+            Some(transform) => self.transforms.insert(layout_id.0, transform),
+            None => self.transforms.remove(&layout_id.0),
+        };
+        Ok(())
+    }
+
+    /// Native counterpart of `set_virtual_children`.
+    pub fn set_virtual_children_native(
+        &mut self,
+        container_id: &LayoutId,
+        count: usize,
+        sizes: VirtualItemSizes,
+    ) -> Result<(), LayoutError> {
+        self.node(container_id)?;
+        let sizes = match sizes {
+            VirtualItemSizes::Uniform(size) => vec![size; count],
+            VirtualItemSizes::Each(sizes) if sizes.len() == count => sizes,
+            VirtualItemSizes::Each(sizes) => {
+                return Err(LayoutError::VirtualSizeCount {
+                    count,
+                    sizes: sizes.len(),
+                })
+            }
+        };
+        self.virtual_children
+            .insert(container_id.0, VirtualChildren::new(sizes));
+        Ok(())
+    }
+
+    /// Native counterpart of `set_virtual_item_size`.
+    pub fn set_virtual_item_size_native(
+        &mut self,
+        container_id: &LayoutId,
+        index: usize,
+        size: f32,
+    ) -> Result<(), LayoutError> {
+        self.node(container_id)?;
+        let items = self
+            .virtual_children
+            .get_mut(&container_id.0)
+            .ok_or(LayoutError::NotVirtual(container_id.0))?;
+        if index >= items.len() {
+            return Err(LayoutError::VirtualItemOutOfRange {
+                index,
+                count: items.len(),
+            });
+        }
+        items.set(index, size);
+        Ok(())
+    }
+
+    /// Native counterpart of `get_virtual_window`.
+    pub fn virtual_window_native(
+        &self,
+        container_id: &LayoutId,
+        scroll_offset: f32,
+        viewport_size: f32,
+    ) -> Result<VirtualWindow, LayoutError> {
+        let node_id = self.node(container_id)?;
+        let items = self
+            .virtual_children
+            .get(&container_id.0)
+            .ok_or(LayoutError::NotVirtual(container_id.0))?;
+        let padding = self.tree.layout(node_id)?.padding;
+        let padding_start = if lays_out_in_row(self.tree.style(node_id)?) {
+            padding.left
+        } else {
+            padding.top
+        };
+        if items.len() == 0 {
+            return Ok(VirtualWindow {
+                first_index: 0,
+                last_index: 0,
+                offsets: Vec::new(),
+            });
+        }
+
+        let start = f64::from(scroll_offset - padding_start);
+        let end = start + f64::from(viewport_size.max(0.0));
+        let first_index = items.index_at(start);
+        let mut last_index = items.index_at(end).max(first_index);
+        // An item starting right at the viewport's end is not in view
+        if last_index > first_index && items.offset(last_index) >= end {
+            last_index -= 1;
+        }
+        let mut offset = items.offset(first_index);
+        let offsets = items.sizes[first_index..=last_index]
+            .iter()
+            .map(|&size| {
+                let item_offset = f64::from(padding_start) + offset;
+                offset += f64::from(size);
+                item_offset as f32
+            })
+            .collect();
+        Ok(VirtualWindow {
+            first_index,
+            last_index,
+            offsets,
+        })
+    }
+
+    /// The far edges, from the border box origin, that a virtual
+    /// container's logical children reach: past the last item and the end
+    /// padding on its main axis, and zero across it or without virtual
+    /// children.
+    fn virtual_extent(
+        &self,
+        id: u64,
+        node_id: NodeId,
+        layout: &taffy::Layout,
+    ) -> Result<(f32, f32), LayoutError> {
+        let Some(items) = self.virtual_children.get(&id) else {
+            return Ok((0.0, 0.0));
+        };
+        let total = items.offset(items.len()) as f32;
+        if lays_out_in_row(self.tree.style(node_id)?) {
+            let right = layout.border.left + layout.padding.left + total + layout.padding.right;
+            Ok((right, 0.0))
+        } else {
+            let bottom = layout.border.top + layout.padding.top + total + layout.padding.bottom;
+            Ok((0.0, bottom))
+        }
+    }
+
+    /// Native counterpart of `available_rect`.
+    pub fn available_rect_native(
+        &self,
+        child_id: &LayoutId,
+        band_y: f32,
+        band_height: f32,
+    ) -> Result<LayoutBounds, LayoutError> {
+        let node_id = self.node(child_id)?;
+        let layout = self.tree.layout(node_id)?;
+        let exclusions = self
+            .tree
+            .parent(node_id)
+            .and_then(|parent| self.reverse_map.get(&parent))
+            .and_then(|id| self.exclusions.get(id))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let left = layout.location.x;
+        let right = left + layout.size.width;
+        let top = layout.location.y + band_y;
+        let mut covered: Vec<(f32, f32)> = exclusions
+            .iter()
+            .filter(|rect| rect.overlaps_band(top, top + band_height))
+            .filter(|rect| rect.x < right && rect.x + rect.width > left)
+            .map(|rect| (rect.x, rect.x + rect.width))
+            .collect();
+        let (start, end) = widest_free_span(left, right, &mut covered);
+
+        let mut bounds = LayoutBounds {
+            x: start - left,
+            y: band_y,
+            width: end - start,
+            height: band_height,
+        };
+        bounds.enforce_finite(child_id.0)?;
+        Ok(bounds)
+    }
+
+    /// Native counterpart of `get_absolute_position`.
+    pub fn absolute_position_native(
+        &self,
+        layout_id: &LayoutId,
+    ) -> Result<LayoutPoint, LayoutError> {
+        let node_id = self.node(layout_id)?;
+        let mut point = self.offset_from(node_id, None)?.unwrap_or_default();
+        if self.ignores_safe_area.contains(&layout_id.0) {
+            let mut root = node_id;
+            while let Some(parent) = self.tree.parent(root) {
+                root = parent;
+            }
+            if let Some(safe_area) = self.root_safe_area(root) {
+                let size = self.tree.layout(node_id)?.size;
+                let (left, top, _, _) = safe_area.grow(point.x, point.y, size.width, size.height);
+                point.x -= left;
+                point.y -= top;
+            }
+        }
+        point.enforce_finite(layout_id.0)?;
+        Ok(point)
+    }
+
+    /// Native counterpart of `get_offset_between`.
+    pub fn offset_between_native(
+        &self,
+        ancestor_id: &LayoutId,
+        descendant_id: &LayoutId,
+    ) -> Result<LayoutPoint, LayoutError> {
+        let ancestor = self.node(ancestor_id)?;
+        let descendant = self.node(descendant_id)?;
+        let mut point =
+            self.offset_from(descendant, Some(ancestor))?
+                .ok_or(LayoutError::NotADescendant {
+                    ancestor: ancestor_id.0,
+                    descendant: descendant_id.0,
+                })?;
+        point.enforce_finite(descendant_id.0)?;
+        Ok(point)
+    }
+
+    /// Native counterpart of `find_in_direction`.
+    ///
+    /// A candidate qualifies when its border box lies wholly past `from_id`'s
+    /// edge in `direction`; touching counts. Qualifying candidates are
+    /// ranked by the CSS Spatial Navigation distance between the facing
+    /// edges: the Euclidean distance between their nearest points, plus the
+    /// gap along the direction, plus the gap across it times 30 moving left
+    /// or right and 2 moving up or down. The cross gap is zero while the
+    /// boxes' projections overlap, so aligned candidates are preferred, but
+    /// a near candidate slightly out of line beats a far aligned one when
+    /// its weighted distance is smaller. Equal distances go to the candidate
+    /// that starts first across the direction, the topmost moving left or
+    /// right and the leftmost moving up or down, then to the earliest in
+    /// `candidate_ids`. `from_id` itself is never a candidate.
+    pub fn find_in_direction_native(
+        &self,
+        from_id: &LayoutId,
+        direction: NavDirection,
+        candidate_ids: &[u64],
+    ) -> Result<Option<LayoutId>, LayoutError> {
+        let from = self.absolute_bounds(from_id)?;
+        let mut best: Option<(f32, f32, LayoutId)> = None;
+        for &id in candidate_ids {
+            let candidate_id = LayoutId(id);
+            if candidate_id == *from_id {
+                continue;
+            }
+            let candidate = self.absolute_bounds(&candidate_id)?;
+            let Some((along, across)) = direction.gaps(&from, &candidate) else {
+                continue;
+            };
+            let distance = along.hypot(across) + along + across * direction.orthogonal_weight();
+            let start = direction.across_start(&candidate);
+            let closer = !best.is_some_and(|(best_distance, best_start, _)| {
+                (best_distance, best_start) <= (distance, start)
+            });
+            if closer {
+                best = Some((distance, start, candidate_id));
+            }
+        }
+        Ok(best.map(|(_, _, id)| id))
+    }
+
+    /// A node's border box at its `absolute_position_native`.
+    fn absolute_bounds(&self, layout_id: &LayoutId) -> Result<LayoutBounds, LayoutError> {
+        let position = self.absolute_position_native(layout_id)?;
+        let size = self.tree.layout(self.node(layout_id)?)?.size;
+        Ok(LayoutBounds::new(
+            position.x,
+            position.y,
+            size.width,
+            size.height,
+        ))
+    }
+
+    /// The viewport `node_id` was last laid out in as a root with insets.
+    fn root_safe_area(&self, node_id: NodeId) -> Option<&SafeArea> {
+        self.reverse_map
+            .get(&node_id)
+            .and_then(|id| self.safe_areas.get(id))
+    }
+
+    /// Sum the layout offsets and parent scroll offsets from `node_id` up to
+    /// `ancestor`, or to the root of the tree when None, which starts at the
+    /// root's insets. None when the root is reached without passing
+    /// `ancestor`.
+    fn offset_from(
+        &self,
+        node_id: NodeId,
+        ancestor: Option<NodeId>,
+    ) -> Result<Option<LayoutPoint>, LayoutError> {
+        let mut point = LayoutPoint::default();
+        let mut current = node_id;
+        while Some(current) != ancestor {
+            let location = self.tree.layout(current)?.location;
+            point.x += location.x;
+            point.y += location.y;
+            let Some(parent) = self.tree.parent(current) else {
+                if let Some(safe_area) = self.root_safe_area(current) {
+                    point.x += safe_area.insets.left;
+                    point.y += safe_area.insets.top;
+                }
+                return Ok(ancestor.is_none().then_some(point));
+            };
+            let (scroll_x, scroll_y) = self
+                .reverse_map
+                .get(&parent)
+                .and_then(|id| self.scroll_offsets.get(id))
+                .copied()
+                .unwrap_or((0.0, 0.0));
+            point.x -= scroll_x;
+            point.y -= scroll_y;
+            current = parent;
+        }
+        Ok(Some(point))
+    }
+
+    /// Native counterpart of `compute_layout_with_measure`. `measure` is
+    /// called for measurable nodes and returns their content size.
+    pub fn compute_layout_with_measure_native(
+        &mut self,
+        root_id: &LayoutId,
+        available_width: f32,
+        available_height: f32,
+        mut measure: impl FnMut(&MeasureRequest) -> Size<f32>,
+    ) -> Result<(), LayoutError> {
+        let node_id = self.node(root_id)?;
+        self.safe_areas.remove(&root_id.0);
+        self.resolve_intrinsic_sizes(node_id, &mut measure)?;
+        self.layout_root(node_id, available_width, available_height, &mut measure)?;
+        self.root_spaces
+            .insert(root_id.0, (available_width, available_height));
+        Ok(())
+    }
+
+    /// Run Taffy on a root whose intrinsic sizes are resolved.
+    fn layout_root(
+        &mut self,
+        node_id: NodeId,
+        available_width: f32,
+        available_height: f32,
+        measure: &mut impl FnMut(&MeasureRequest) -> Size<f32>,
+    ) -> Result<(), LayoutError> {
+        self.tree.compute_layout_with_measure(
+            node_id,
+            Size {
+                width: root_available_space(available_width),
+                height: root_available_space(available_height),
+            },
+            |known_dimensions, available_space, _node_id, node_context, style| {
+                measure_node(
+                    measure,
+                    known_dimensions,
+                    available_space,
+                    node_context,
+                    style,
+                )
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Native counterpart of `probe_layout_widths`.
+    pub fn probe_layout_widths_native(
+        &mut self,
+        root_id: &LayoutId,
+        widths: &[f32],
+        available_height: f32,
+        mut measure: impl FnMut(&MeasureRequest) -> Size<f32>,
+    ) -> Result<Vec<WidthProbe>, LayoutError> {
+        let node_id = self.node(root_id)?;
+        // Keywords size under min- and max-content space, whatever the width
+        self.resolve_intrinsic_sizes(node_id, &mut measure)?;
+        let mut probes = Vec::with_capacity(widths.len());
+        for &width in widths {
+            self.layout_root(node_id, width, available_height, &mut measure)?;
+            let layout = self.tree.layout(node_id)?;
+            probes.push(WidthProbe {
+                width,
+                overflow_x: overflow_past(
+                    layout.location.x + layout.size.width,
+                    width,
+                    layout.content_size.width,
+                    layout.size.width - layout.border.right - layout.scrollbar_size.width,
+                ),
+                overflow_y: overflow_past(
+                    layout.location.y + layout.size.height,
+                    available_height,
+                    layout.content_size.height,
+                    layout.size.height - layout.border.bottom - layout.scrollbar_size.height,
+                ),
+                slack: self.flex_slack(node_id)?,
+            });
+        }
+        if let Some(&(width, height)) = self.root_spaces.get(&root_id.0) {
+            self.layout_root(node_id, width, height, &mut measure)?;
+        }
+        Ok(probes)
+    }
+
+    /// Unfilled main-axis space in the flex lines of `root` and its
+    /// descendants, from their current layout. Taffy does not report lines,
+    /// so they are found where the item positions turn back along the main
+    /// axis.
+    fn flex_slack(&self, root: NodeId) -> Result<f32, LayoutError> {
+        let mut slack = 0.0;
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            stack.extend(self.tree.child_ids(node));
+            let style = self.tree.style(node)?;
+            if style.display != Display::Flex {
+                continue;
+            }
+            let layout = self.tree.layout(node)?;
+            let row = lays_out_in_row(style);
+            let reverse = matches!(
+                style.flex_direction,
+                FlexDirection::RowReverse | FlexDirection::ColumnReverse
+            );
+            let main = |size: Size<f32>| if row { size.width } else { size.height };
+            let start_end = |rect: Rect<f32>| {
+                if row {
+                    (rect.left, rect.right)
+                } else {
+                    (rect.top, rect.bottom)
+                }
+            };
+            let (padding_start, padding_end) = start_end(layout.padding);
+            let (border_start, border_end) = start_end(layout.border);
+            let inner = main(layout.size)
+                - padding_start
+                - padding_end
+                - border_start
+                - border_end
+                - main(layout.scrollbar_size);
+            let (gap_px, gap_percent) = length_parts(
+                if row {
+                    style.gap.width
+                } else {
+                    style.gap.height
+                }
+                .into_raw(),
+            );
+            let gap =
+                gap_px.unwrap_or(0.0) + gap_percent.map_or(0.0, |percent| percent / 100.0 * inner);
+
+            // (items, their outer main size, last main position)
+            let mut line: Option<(usize, f32, f32)> = None;
+            for child in self.tree.child_ids(node) {
+                let child_style = self.tree.style(child)?;
+                if child_style.display == Display::None
+                    || child_style.position == Position::Absolute
+                {
+                    continue;
+                }
+                let child_layout = self.tree.layout(child)?;
+                let (margin_start, margin_end) = start_end(child_layout.margin);
+                let outer = main(child_layout.size) + margin_start + margin_end;
+                let position = if row {
+                    child_layout.location.x
+                } else {
+                    child_layout.location.y
+                };
+                line = match line {
+                    Some((count, filled, last))
+                        if (position >= last) != reverse || position == last =>
+                    {
+                        Some((count + 1, filled + outer, position))
+                    }
+                    Some((count, filled, _)) => {
+                        slack += line_slack(inner, count, filled, gap);
+                        Some((1, outer, position))
+                    }
+                    None => Some((1, outer, position)),
+                };
+            }
+            if let Some((count, filled, _)) = line {
+                slack += line_slack(inner, count, filled, gap);
+            }
+        }
+        Ok(slack)
+    }
+
+    /// Native counterpart of `compute_layout_with_insets`. Negative and
+    /// non-finite insets count as zero.
+    pub fn compute_layout_with_insets_native(
+        &mut self,
+        root_id: &LayoutId,
+        available_width: f32,
+        available_height: f32,
+        insets: EdgeSizes,
+        measure: impl FnMut(&MeasureRequest) -> Size<f32>,
+    ) -> Result<(), LayoutError> {
+        let inset = |edge: f32| if edge.is_finite() { edge.max(0.0) } else { 0.0 };
+        let insets = EdgeSizes {
+            top: inset(insets.top),
+            right: inset(insets.right),
+            bottom: inset(insets.bottom),
+            left: inset(insets.left),
+        };
+        self.compute_layout_with_measure_native(
+            root_id,
+            (available_width - insets.left - insets.right).max(0.0),
+            (available_height - insets.top - insets.bottom).max(0.0),
+            measure,
+        )?;
+        self.safe_areas.insert(
+            root_id.0,
+            SafeArea {
+                width: available_width,
+                height: available_height,
+                insets,
+            },
+        );
+        Ok(())
+    }
+
+    /// Native counterpart of `compute_layout`. Measurable nodes measure as
+    /// empty.
+    pub fn compute_layout_native(
+        &mut self,
+        root_id: &LayoutId,
+        available_width: f32,
+        available_height: f32,
+    ) -> Result<(), LayoutError> {
+        self.compute_layout_with_measure_native(root_id, available_width, available_height, |_| {
+            Size::ZERO
+        })
+    }
+
+    /// Native counterpart of `get_style`.
+    pub fn style_input(&self, layout_id: &LayoutId) -> Result<StyleInput, LayoutError> {
+        let node_id = self.node(layout_id)?;
+        let mut input = match self.intrinsic_sizes.get(&layout_id.0) {
+            Some(sizing) => {
+                // The tree holds the lengths last resolved from the keywords
+                let mut input = StyleInput::from_taffy(&sizing.base);
+                sizing.keywords.write_to(&mut input);
+                input
+            }
+            None => StyleInput::from_taffy(self.tree.style(node_id)?),
+        };
+        if self.ignores_safe_area.contains(&layout_id.0) {
+            input.ignore_safe_area = Some(true);
+        }
+        Ok(input)
+    }
+
+    /// Padding plus border on each side of a node after its last layout,
+    /// which is where its content box sits within its bounds.
+    pub fn content_insets_native(&self, layout_id: &LayoutId) -> Result<EdgeSizes, LayoutError> {
+        let layout = self.tree.layout(self.node(layout_id)?)?;
+        let (padding, border) = (layout.padding, layout.border);
+        Ok(EdgeSizes {
+            top: padding.top + border.top,
+            right: padding.right + border.right,
+            bottom: padding.bottom + border.bottom,
+            left: padding.left + border.left,
+        })
+    }
+
+    /// Native counterpart of `get_absolute_layouts`.
+    pub fn absolute_layouts(
+        &mut self,
+        root_id: &LayoutId,
+    ) -> Result<Vec<AbsoluteLayout>, LayoutError> {
+        let mut stack = std::mem::take(&mut self.readback.stack);
+        let mut results = Vec::with_capacity(self.readback.last_len);
+        let collected = self.collect_absolute_layouts(root_id, &mut stack, &mut results);
+
+        let arena = &mut self.readback;
+        arena.last_len = results.len();
+        arena.resets += 1;
+        if arena.retain {
+            stack.clear();
+            arena.stack = stack;
+        }
+        arena.peak_bytes = arena.peak_bytes.max(arena.capacity_bytes());
+
+        collected.map(|()| results)
+    }
+
+    fn collect_absolute_layouts(
+        &self,
+        root_id: &LayoutId,
+        stack: &mut Vec<ReadbackEntry>,
+        results: &mut Vec<AbsoluteLayout>,
+    ) -> Result<(), LayoutError> {
+        let safe_area = self.safe_areas.get(&root_id.0).copied();
+        let (root_x, root_y) = safe_area.map_or((0.0, 0.0), |safe_area| {
+            (safe_area.insets.left, safe_area.insets.top)
+        });
+        stack.push((
+            self.node(root_id)?,
+            root_x,
+            root_y,
+            ClipRect::UNBOUNDED,
+            IDENTITY_MATRIX,
+        ));
+
+        while let Some((node_id, origin_x, origin_y, clip, parent_matrix)) = stack.pop() {
+            let layout = self.tree.layout(node_id)?;
+            let x = origin_x + layout.location.x;
+            let y = origin_y + layout.location.y;
+            let width = layout.size.width;
+            let height = layout.size.height;
+
+            let id = self.reverse_map.get(&node_id).copied();
+            // Children stay where they were laid out, inside the insets
+            let (x, y, width, height, child_x, child_y) = match safe_area
+                .filter(|_| id.is_some_and(|id| self.ignores_safe_area.contains(&id)))
+            {
+                Some(safe_area) => {
+                    let (left, top, right, bottom) = safe_area.grow(x, y, width, height);
+                    let grown_width = width + left + right;
+                    let grown_height = height + top + bottom;
+                    (x - left, y - top, grown_width, grown_height, x, y)
+                }
+                None => (x, y, width, height, x, y),
+            };
+            let matrix = match id.and_then(|id| self.transforms.get(&id)) {
+                Some(transform) => {
+                    multiply(&parent_matrix, &transform.resolve(x, y, width, height))
+                }
+                None => parent_matrix,
+            };
+            if let Some(id) = id {
+                // Content size runs from the border box's origin
+                let (virtual_right, virtual_bottom) = self.virtual_extent(id, node_id, layout)?;
+                let scroll_right = layout
+                    .content_size
+                    .width
+                    .max(layout.size.width - layout.border.right - layout.scrollbar_size.width)
+                    .max(virtual_right);
+                let scroll_bottom = layout
+                    .content_size
+                    .height
+                    .max(layout.size.height - layout.border.bottom - layout.scrollbar_size.height)
+                    .max(virtual_bottom);
+                let mut absolute = AbsoluteLayout {
+                    id,
+                    x,
+                    y,
+                    width,
+                    height,
+                    clip,
+                    fully_clipped: clip.excludes(x, y, width, height),
+                    matrix,
+                    scroll_width: scroll_right - layout.border.left,
+                    scroll_height: scroll_bottom - layout.border.top,
+                };
+                absolute.enforce_finite(id)?;
+                results.push(absolute);
+            }
+
+            let child_clip = self.child_clip(node_id, layout, child_x, child_y, &clip)?;
+            let (scroll_x, scroll_y) = id
+                .and_then(|id| self.scroll_offsets.get(&id))
+                .copied()
+                .unwrap_or((0.0, 0.0));
+
+            // Reverse the pushed children so they pop in document order.
+            // child_ids borrows the tree instead of cloning a Vec per node.
+            let first_child = stack.len();
+            stack.extend(self.tree.child_ids(node_id).map(|child| {
+                (
+                    child,
+                    child_x - scroll_x,
+                    child_y - scroll_y,
+                    child_clip,
+                    matrix,
+                )
+            }));
+            stack[first_child..].reverse();
+        }
+
+        Ok(())
+    }
+
+    /// The clip a node imposes on its children: its padding box on every axis
+    /// whose overflow is not visible, intersected with the inherited clip.
+    fn child_clip(
+        &self,
+        node_id: NodeId,
+        layout: &taffy::Layout,
+        x: f32,
+        y: f32,
+        inherited: &ClipRect,
+    ) -> Result<ClipRect, LayoutError> {
+        let overflow = self.tree.style(node_id)?.overflow;
+        let mut clip = ClipRect::UNBOUNDED;
+
+        if overflow.x != Overflow::Visible {
+            clip.min_x = x + layout.border.left;
+            clip.max_x = x + layout.size.width - layout.border.right;
+        }
+        if overflow.y != Overflow::Visible {
+            clip.min_y = y + layout.border.top;
+            clip.max_y = y + layout.size.height - layout.border.bottom;
+        }
+
+        Ok(inherited.intersect(&clip))
+    }
+}
+
+/// Available space for a root from the size the host passed: a size that is
+/// not finite, such as Infinity for unbounded, lays out at max-content
+/// instead of poisoning every position with it.
+/// How far a root ending at `end` reaches past `available`, or content
+/// `content` long past the `client` length it can show, whichever is more.
+fn overflow_past(end: f32, available: f32, content: f32, client: f32) -> f32 {
+    let past_available = if available.is_finite() {
+        end - available
+    } else {
+        0.0
+    };
+    past_available.max(content - client).max(0.0)
+}
+
+/// Free main-axis space in a flex line of `count` items with outer sizes
+/// adding up to `filled`.
+fn line_slack(inner: f32, count: usize, filled: f32, gap: f32) -> f32 {
+    (inner - filled - gap * count.saturating_sub(1) as f32).max(0.0)
+}
+
+fn root_available_space(size: f32) -> AvailableSpace {
+    if size.is_finite() {
+        AvailableSpace::Definite(size)
+    } else {
+        AvailableSpace::MaxContent
+    }
+}
+
+/// Ask `measure` for the content size of a measurable node. Other nodes
+/// measure as empty, as do nodes measured NaN or infinite.
+fn measure_node(
+    measure: &mut impl FnMut(&MeasureRequest) -> Size<f32>,
+    known_dimensions: Size<Option<f32>>,
+    available_space: Size<AvailableSpace>,
+    node_context: Option<&mut NodeContext>,
+    style: &Style,
+) -> Size<f32> {
+    // Only measure nodes with a measure_id
+    let Some(measure_id) = node_context.and_then(|context| context.measure_id) else {
+        return Size::ZERO;
+    };
+
+    // Taffy already passes content-box available space and grows
+    // the result, but known dimensions arrive as the border box
+    let padding = style.padding.resolve_or_zero(None, |_, _| 0.0);
+    let border = style.border.resolve_or_zero(None, |_, _| 0.0);
+    let mut inset = padding + border;
+    if style.overflow.y == Overflow::Scroll {
+        inset.right += style.scrollbar_width;
+    }
+    if style.overflow.x == Overflow::Scroll {
+        inset.bottom += style.scrollbar_width;
+    }
+
+    // For MinContent and MaxContent, we pass Infinity to signal "don't wrap"
+    let available = |space: AvailableSpace| match space {
+        AvailableSpace::Definite(v) => v,
+        AvailableSpace::MinContent | AvailableSpace::MaxContent => f32::INFINITY,
+    };
+
+    let size = measure(&MeasureRequest {
+        measure_id,
+        known_width: known_dimensions
+            .width
+            .map(|w| (w - inset.left - inset.right).max(0.0)),
+        known_height: known_dimensions
+            .height
+            .map(|h| (h - inset.top - inset.bottom).max(0.0)),
+        available_width: available(available_space.width),
+        available_height: available(available_space.height),
+        padding: padding.into(),
+        border: border.into(),
+    });
+    let finite_or_zero = |value: f32| if value.is_finite() { value } else { 0.0 };
+    Size {
+        width: finite_or_zero(size.width),
+        height: finite_or_zero(size.height),
+    }
+}
+
+/// The error a wasm export gives in a build without the cargo feature that
+/// implements it.
+#[cfg(not(feature = "debug-tools"))]
+fn feature_disabled(feature: &str) -> JsValue {
+    JsValue::from_str(&format!("built without the {} feature", feature))
+}
+
+/// A measure function calling a JS callback as `compute_layout_with_measure`
+/// describes.
+fn js_measure(measure_callback: &Function) -> impl FnMut(&MeasureRequest) -> Size<f32> + '_ {
+    let this = JsValue::null();
+    move |request| {
+        // Convert known dimensions (None becomes NaN in JS)
+        let known_w = request.known_width.map(|v| v as f64).unwrap_or(f64::NAN);
+        let known_h = request.known_height.map(|v| v as f64).unwrap_or(f64::NAN);
+
+        let args = js_sys::Array::new();
+        args.push(&JsValue::from(request.measure_id as f64));
+        args.push(&JsValue::from(known_w));
+        args.push(&JsValue::from(known_h));
+        args.push(&JsValue::from(request.available_width as f64));
+        args.push(&JsValue::from(request.available_height as f64));
+        args.push(&serde_wasm_bindgen::to_value(&request.padding).unwrap_or_default());
+        args.push(&serde_wasm_bindgen::to_value(&request.border).unwrap_or_default());
+
+        let result = match measure_callback.apply(&this, &args) {
+            Ok(r) => r,
+            Err(_) => return Size::ZERO,
+        };
+
+        // Parse result: { width, height }
+        let width = js_sys::Reflect::get(&result, &"width".into())
+            .ok()
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0) as f32;
+        let height = js_sys::Reflect::get(&result, &"height".into())
+            .ok()
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0) as f32;
+
+        Size { width, height }
+    }
+}
+
+const XXH_PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const XXH_PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const XXH_PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const XXH_PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const XXH_PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(XXH_PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(XXH_PRIME_1)
+}
+
+fn read_le_u64(bytes: &[u8]) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(word)
+}
+
+/// XXH64 of `data` with a zero seed: a fast non-cryptographic hash for
+/// cache keys.
+fn xxh64(data: &[u8]) -> u64 {
+    let mut stripes = data.chunks_exact(32);
+    let mut hash = if data.len() >= 32 {
+        let mut lanes = [
+            XXH_PRIME_1.wrapping_add(XXH_PRIME_2),
+            XXH_PRIME_2,
+            0,
+            0u64.wrapping_sub(XXH_PRIME_1),
+        ];
+        for stripe in &mut stripes {
+            for (lane, word) in lanes.iter_mut().zip(stripe.chunks_exact(8)) {
+                *lane = xxh64_round(*lane, read_le_u64(word));
+            }
+        }
+        let [a, b, c, d] = lanes;
+        let hash = a
+            .rotate_left(1)
+            .wrapping_add(b.rotate_left(7))
+            .wrapping_add(c.rotate_left(12))
+            .wrapping_add(d.rotate_left(18));
+        lanes.iter().fold(hash, |hash, &lane| {
+            (hash ^ xxh64_round(0, lane))
+                .wrapping_mul(XXH_PRIME_1)
+                .wrapping_add(XXH_PRIME_4)
+        })
+    } else {
+        XXH_PRIME_5
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+
+    let mut rest = stripes.remainder();
+    while rest.len() >= 8 {
+        hash ^= xxh64_round(0, read_le_u64(rest));
+        hash = hash
+            .rotate_left(27)
+            .wrapping_mul(XXH_PRIME_1)
+            .wrapping_add(XXH_PRIME_4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        let mut word = [0; 4];
+        word.copy_from_slice(&rest[..4]);
+        hash ^= u64::from(u32::from_le_bytes(word)).wrapping_mul(XXH_PRIME_1);
+        hash = hash
+            .rotate_left(23)
+            .wrapping_mul(XXH_PRIME_2)
+            .wrapping_add(XXH_PRIME_3);
+        rest = &rest[4..];
+    }
+    for &byte in rest {
+        hash ^= u64::from(byte).wrapping_mul(XXH_PRIME_5);
+        hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(XXH_PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(XXH_PRIME_3);
+    hash ^ (hash >> 32)
+}
+
+fn parse_style(style_js: JsValue) -> Result<StyleInput, JsValue> {
+    serde_wasm_bindgen::from_value(style_js)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse style: {}", e)))
+}
+
+fn parse_children(children_js: JsValue) -> Result<Vec<u64>, JsValue> {
+    serde_wasm_bindgen::from_value(children_js)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse children: {}", e)))
+}
+
+impl Default for TaffyLayoutEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_layout() {
+        let mut engine = TaffyLayoutEngine::new();
+
+        // Create a simple column layout
+        let child1 = engine
+            .tree
+            .new_leaf(Style {
+                size: Size {
+                    width: Dimension::length(100.0),
+                    height: Dimension::length(50.0),
+                },
+                ..Default::default()
+            })
+            .unwrap();
+
+        let child2 = engine
+            .tree
+            .new_leaf(Style {
+                size: Size {
+                    width: Dimension::length(100.0),
+                    height: Dimension::length(50.0),
+                },
+                ..Default::default()
+            })
+            .unwrap();
+
+        let root = engine
+            .tree
+            .new_with_children(
+                Style {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    size: Size {
+                        width: Dimension::length(200.0),
+                        height: Dimension::length(200.0),
+                    },
+                    ..Default::default()
+                },
+                &[child1, child2],
+            )
+            .unwrap();
+
+        engine
+            .tree
+            .compute_layout(
+                root,
+                Size {
+                    width: AvailableSpace::Definite(200.0),
+                    height: AvailableSpace::Definite(200.0),
+                },
+            )
+            .unwrap();
+
+        let named = [("root", root), ("child1", child1), ("child2", child2)];
+        let values: Vec<(String, f32)> = named
+            .iter()
+            .flat_map(|&(name, node)| {
+                let layout = engine.tree.layout(node).unwrap();
+                let (x, y) = (layout.location.x, layout.location.y);
+                box_values(&[(name, (x, y, layout.size.width, layout.size.height))])
+            })
+            .collect();
+        tolerance::assert_matches_goldens!("basic_layout", &values);
+    }
+
+    fn absolute_box(left: f32, top: f32, width: f32, height: f32) -> StyleInput {
+        StyleInput {
+            position: Some(PositionKw::Absolute),
+            left: Some(left),
+            top: Some(top),
+            width: Some(width),
+            height: Some(height),
+            ..Default::default()
+        }
+    }
+
+    fn hidden_box(left: f32, top: f32, width: f32, height: f32) -> StyleInput {
+        StyleInput {
+            overflow: Some(OverflowKw::Hidden),
+            ..absolute_box(left, top, width, height)
+        }
+    }
+
+    fn find(layouts: &[AbsoluteLayout], id: &LayoutId) -> AbsoluteLayout {
+        layouts.iter().find(|l| l.id == id.id()).unwrap().clone()
+    }
+
+    /// A node's `(x, y, width, height)`.
+    type NodeBox = (f32, f32, f32, f32);
+
+    /// Named boxes as golden values.
+    fn box_values(named: &[(&str, NodeBox)]) -> Vec<(String, f32)> {
+        named
+            .iter()
+            .flat_map(|&(name, (x, y, width, height))| {
+                [("x", x), ("y", y), ("width", width), ("height", height)]
+                    .map(|(field, value)| (format!("{}.{}", name, field), value))
+            })
+            .collect()
+    }
+
+    fn clip_values(name: &str, clip: &ClipRect) -> Vec<(String, f32)> {
+        [
+            ("min_x", clip.min_x),
+            ("min_y", clip.min_y),
+            ("max_x", clip.max_x),
+            ("max_y", clip.max_y),
+        ]
+        .map(|(field, value)| (format!("{}.clip.{}", name, field), value))
+        .to_vec()
+    }
+
+    #[test]
+    fn test_clip_outside_hidden_parent() {
+        let mut engine = TaffyLayoutEngine::new();
+        let outside = engine
+            .new_leaf_native(&absolute_box(150.0, 10.0, 20.0, 20.0))
+            .unwrap();
+        let partial = engine
+            .new_leaf_native(&absolute_box(80.0, 10.0, 40.0, 20.0))
+            .unwrap();
+        let parent = engine
+            .new_with_children_native(
+                &hidden_box(10.0, 10.0, 100.0, 100.0),
+                &[outside.id(), partial.id()],
+            )
+            .unwrap();
+        let root = engine
+            .new_with_children_native(
+                &StyleInput {
+                    width: Some(500.0),
+                    height: Some(500.0),
+                    ..Default::default()
+                },
+                &[parent.id()],
+            )
+            .unwrap();
+        engine.compute_layout(&root, 500.0, 500.0).unwrap();
+
+        let layouts = engine.absolute_layouts(&root).unwrap();
+        assert_eq!(layouts.len(), 4);
+
+        let root_layout = find(&layouts, &root);
+        assert_eq!(root_layout.clip, ClipRect::UNBOUNDED);
+        assert!(!root_layout.fully_clipped);
+
+        let outside_layout = find(&layouts, &outside);
+        assert!(outside_layout.fully_clipped);
+
+        let partial_layout = find(&layouts, &partial);
+        assert!(!partial_layout.fully_clipped);
+        let mut values = vec![("outside.x".to_string(), outside_layout.x)];
+        values.extend(clip_values("partial", &partial_layout.clip));
+        tolerance::assert_matches_goldens!("clip_outside_hidden_parent", &values);
+    }
+
+    #[test]
+    fn test_nested_clips_intersect_and_scroll() {
+        let mut engine = TaffyLayoutEngine::new();
+        let child = engine
+            .new_leaf_native(&absolute_box(0.0, 0.0, 200.0, 20.0))
+            .unwrap();
+        let inner = engine
+            .new_with_children_native(&hidden_box(50.0, 20.0, 100.0, 50.0), &[child.id()])
+            .unwrap();
+        let outer = engine
+            .new_with_children_native(&hidden_box(0.0, 0.0, 100.0, 100.0), &[inner.id()])
+            .unwrap();
+        engine.set_scroll_offset(&inner, 30.0, 0.0).unwrap();
+        engine.compute_layout(&outer, 100.0, 100.0).unwrap();
+
+        let layouts = engine.absolute_layouts(&outer).unwrap();
+        let child_layout = find(&layouts, &child);
+        let mut values = vec![
+            ("child.x".to_string(), child_layout.x),
+            ("child.y".to_string(), child_layout.y),
+        ];
+        values.extend(clip_values("child", &child_layout.clip));
+        tolerance::assert_matches_goldens!("nested_clips_intersect_and_scroll", &values);
+        assert!(!child_layout.fully_clipped);
+    }
+
+    /// A scrolled, clipped tree whose readback exercises every arena path.
+    fn clipped_tree(engine: &mut TaffyLayoutEngine) -> (LayoutId, LayoutId) {
+        let leaves: Vec<u64> = (0..50)
+            .map(|i| {
+                let style = absolute_box(i as f32 * 7.0, i as f32 * 3.0, 20.0, 20.0);
+                engine.new_leaf_native(&style).unwrap().id()
+            })
+            .collect();
+        let inner = engine
+            .new_with_children_native(&hidden_box(10.0, 10.0, 120.0, 80.0), &leaves)
+            .unwrap();
+        let outer = engine
+            .new_with_children_native(&hidden_box(0.0, 0.0, 200.0, 200.0), &[inner.id()])
+            .unwrap();
+        engine.set_scroll_offset(&inner, 15.0, 5.0).unwrap();
+        engine.compute_layout(&outer, 200.0, 200.0).unwrap();
+        (outer, inner)
+    }
+
+    #[test]
+    fn test_arena_readback_matches_fresh_allocation() {
+        let mut reused = TaffyLayoutEngine::with_arena(true);
+        let mut fresh = TaffyLayoutEngine::with_arena(false);
+        let (reused_root, reused_inner) = clipped_tree(&mut reused);
+        let (fresh_root, fresh_inner) = clipped_tree(&mut fresh);
+
+        let expected = fresh.absolute_layouts(&fresh_root).unwrap();
+        assert_eq!(expected.len(), 52);
+        for _ in 0..3 {
+            assert_eq!(reused.absolute_layouts(&reused_root).unwrap(), expected);
+        }
+
+        // A smaller readback after a larger one must not see stale entries
+        assert_eq!(
+            reused.absolute_layouts(&reused_inner).unwrap(),
+            fresh.absolute_layouts(&fresh_inner).unwrap()
+        );
+
+        let report = reused.memory_report_native();
+        assert_eq!(report.arena_resets, 4);
+        assert!(report.arena_capacity_bytes > 0);
+        assert_eq!(fresh.memory_report_native().arena_capacity_bytes, 0);
+    }
+
+    fn style_from_json(json: &str) -> Result<StyleInput, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    fn round_trip<T>(values: &[T], field: &str)
+    where
+        T: Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        for value in values {
+            let keyword = serde_json::to_value(value).unwrap();
+            let json = serde_json::json!({ field: keyword });
+            let parsed: StyleInput = serde_json::from_value(json).unwrap();
+            let reserialized = serde_json::to_value(&parsed).unwrap();
+            assert_eq!(reserialized[field], keyword, "{} did not round-trip", field);
+        }
+    }
+
+    #[test]
+    fn test_keywords_round_trip() {
+        use AlignKw as A;
+        use JustifyKw as J;
+
+        round_trip(
+            &[
+                DisplayKw::Flex,
+                DisplayKw::Block,
+                DisplayKw::Grid,
+                DisplayKw::None,
+            ],
+            "display",
+        );
+        round_trip(
+            &[
+                FlexDirectionKw::Row,
+                FlexDirectionKw::Column,
+                FlexDirectionKw::RowReverse,
+                FlexDirectionKw::ColumnReverse,
+            ],
+            "flex_direction",
+        );
+        round_trip(
+            &[
+                FlexWrapKw::Wrap,
+                FlexWrapKw::NoWrap,
+                FlexWrapKw::WrapReverse,
+            ],
+            "flex_wrap",
+        );
+        let aligns = [
+            A::Auto,
+            A::Start,
+            A::End,
+            A::FlexStart,
+            A::FlexEnd,
+            A::Center,
+            A::Baseline,
+            A::Stretch,
+        ];
+        round_trip(&aligns, "align_items");
+        round_trip(&aligns, "align_self");
+        round_trip(
+            &[
+                J::Start,
+                J::End,
+                J::FlexStart,
+                J::FlexEnd,
+                J::Center,
+                J::Stretch,
+                J::SpaceBetween,
+                J::SpaceAround,
+                J::SpaceEvenly,
+            ],
+            "justify_content",
+        );
+        round_trip(
+            &[
+                GridAutoFlowKw::Row,
+                GridAutoFlowKw::Column,
+                GridAutoFlowKw::RowDense,
+                GridAutoFlowKw::ColumnDense,
+            ],
+            "grid_auto_flow",
+        );
+        round_trip(&[PositionKw::Relative, PositionKw::Absolute], "position");
+        let overflows = [
+            OverflowKw::Visible,
+            OverflowKw::Clip,
+            OverflowKw::Hidden,
+            OverflowKw::Scroll,
+        ];
+        round_trip(&overflows, "overflow");
+        round_trip(&overflows, "overflow_x");
+        round_trip(&overflows, "overflow_y");
+
+        let canonical = serde_json::to_value(FlexWrapKw::NoWrap).unwrap();
+        assert_eq!(canonical, "nowrap");
+        let canonical = serde_json::to_value(JustifyKw::SpaceBetween).unwrap();
+        assert_eq!(canonical, "space-between");
+    }
+
+    #[test]
+    fn test_keyword_aliases() {
+        let style = style_from_json(
+            r#"{"flex_wrap": "no-wrap", "align_items": "FlexStart", "position": "static",
+                "justify_content": "SpaceEvenly", "display": "Grid"}"#,
+        )
+        .unwrap();
+        assert_eq!(style.flex_wrap, Some(FlexWrapKw::NoWrap));
+        assert_eq!(style.align_items, Some(AlignKw::FlexStart));
+        assert_eq!(style.position, Some(PositionKw::Relative));
+        assert_eq!(style.justify_content, Some(JustifyKw::SpaceEvenly));
+        assert_eq!(style.display, Some(DisplayKw::Grid));
+
+        let style = style_from_json(r#"{"flex_wrap": "NoWrap", "align_items": null}"#).unwrap();
+        assert_eq!(style.flex_wrap, Some(FlexWrapKw::NoWrap));
+        assert_eq!(style.align_items, None);
+    }
+
+    #[test]
+    fn test_unknown_keyword_names_field_and_value() {
+        let err = style_from_json(r#"{"flex_direction": "colum"}"#).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("flex_direction"), "{}", message);
+        assert!(message.contains("colum"), "{}", message);
+    }
+
+    #[test]
+    fn test_get_style_reads_back_canonical_keywords() {
+        let mut engine = TaffyLayoutEngine::new();
+        let style = style_from_json(
+            r#"{"display": "flex", "flex_direction": "ColumnReverse", "align_self": "auto",
+                "align_items": "start", "width": 120, "height_percent": 50}"#,
+        )
+        .unwrap();
+        let node = engine.new_leaf_native(&style).unwrap();
+
+        let readback = engine.style_input(&node).unwrap();
+        assert_eq!(
+            readback.flex_direction,
+            Some(FlexDirectionKw::ColumnReverse)
+        );
+        assert_eq!(readback.align_items, Some(AlignKw::Start));
+        assert_eq!(readback.align_self, Some(AlignKw::Auto));
+        assert_eq!(readback.width, Some(120.0));
+        assert_eq!(readback.height_percent, Some(50.0));
+
+        let json = serde_json::to_value(&readback).unwrap();
+        assert_eq!(json["flex_direction"], "column-reverse");
+        assert_eq!(json["align_items"], "start");
+    }
+
+    fn styled_node(engine: &mut TaffyLayoutEngine, json: &str, children: &[LayoutId]) -> LayoutId {
+        let style = style_from_json(json).unwrap();
+        let child_ids: Vec<u64> = children.iter().map(|child| child.0).collect();
+        engine.new_with_children_native(&style, &child_ids).unwrap()
+    }
+
+    fn boxes(engine: &mut TaffyLayoutEngine, root: &LayoutId) -> Vec<NodeBox> {
+        engine
+            .absolute_layouts(root)
+            .unwrap()
+            .iter()
+            .map(|layout| (layout.x, layout.y, layout.width, layout.height))
+            .collect()
+    }
+
+    #[test]
+    fn test_css_defaults_holy_grail() {
+        let mut engine = TaffyLayoutEngine::new();
+        engine.set_defaults_native(DefaultsProfile::Css);
+
+        let header = styled_node(&mut engine, r#"{"height": 60}"#, &[]);
+        let nav = styled_node(
+            &mut engine,
+            r#"{"width": 150, "padding_top": 10, "padding_right": 10,
+                "padding_bottom": 10, "padding_left": 10}"#,
+            &[],
+        );
+        let main = styled_node(&mut engine, r#"{"flex_grow": 1}"#, &[]);
+        let aside = styled_node(&mut engine, r#"{"width": 150}"#, &[]);
+        let body = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "flex_grow": 1}"#,
+            &[nav, main, aside],
+        );
+        let footer = styled_node(&mut engine, r#"{"height": 40}"#, &[]);
+        let root = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "flex_direction": "column", "width": 800, "height": 600}"#,
+            &[header, body, footer],
+        );
+        engine.compute_layout_native(&root, 1000.0, 1000.0).unwrap();
+
+        let names = ["root", "header", "body", "nav", "main", "aside", "footer"];
+        let named: Vec<_> = names.into_iter().zip(boxes(&mut engine, &root)).collect();
+        tolerance::assert_matches_goldens!("css_defaults_holy_grail", &box_values(&named));
+    }
+
+    #[test]
+    fn test_css_defaults_media_object() {
+        let mut engine = TaffyLayoutEngine::new();
+        engine.set_defaults_native(DefaultsProfile::Css);
+
+        let image = styled_node(
+            &mut engine,
+            r#"{"width": 64, "height": 64, "margin_right": 16, "flex_shrink": 0}"#,
+            &[],
+        );
+        let title = styled_node(&mut engine, r#"{"height": 20}"#, &[]);
+        let text = styled_node(&mut engine, r#"{"height": 40}"#, &[]);
+        let content = styled_node(&mut engine, r#"{"flex_grow": 1}"#, &[title, text]);
+        let media = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "align_items": "flex-start", "padding_top": 8,
+                "padding_right": 8, "padding_bottom": 8, "padding_left": 8}"#,
+            &[image, content],
+        );
+        let after = styled_node(&mut engine, r#"{"height": 10}"#, &[]);
+        let root = styled_node(&mut engine, r#"{"width": 400}"#, &[media, after]);
+        engine.compute_layout_native(&root, 1000.0, 1000.0).unwrap();
+
+        let names = [
+            "root", "media", "image", "content", "title", "text", "after",
+        ];
+        let named: Vec<_> = names.into_iter().zip(boxes(&mut engine, &root)).collect();
+        tolerance::assert_matches_goldens!("css_defaults_media_object", &box_values(&named));
+    }
+
+    #[test]
+    fn test_measure_sees_content_box_of_padded_text() {
+        let mut engine = TaffyLayoutEngine::new();
+        let style =
+            style_from_json(r#"{"padding_left": 12, "padding_right": 12, "padding_top": 4}"#)
+                .unwrap();
+        let text = engine.new_measurable_leaf_native(&style, 7).unwrap();
+        let column = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "flex_direction": "column", "width": 200}"#,
+            &[text],
+        );
+
+        // 300px of text in 20px lines, wrapped at the width it is given
+        let mut wrap_widths = Vec::new();
+        engine
+            .compute_layout_with_measure_native(&column, 1000.0, 1000.0, |request| {
+                assert_eq!(request.measure_id, 7);
+                assert_eq!(request.padding.left, 12.0);
+                assert_eq!(request.padding.right, 12.0);
+                assert_eq!(request.padding.top, 4.0);
+                let width = request.known_width.unwrap_or(request.available_width);
+                wrap_widths.push(width);
+                let lines = (300.0 / width).ceil();
+                Size {
+                    width: width.min(300.0),
+                    height: lines * 20.0,
+                }
+            })
+            .unwrap();
+
+        assert!(!wrap_widths.is_empty());
+        assert!(
+            wrap_widths.iter().all(|&width| width == wrap_widths[0]),
+            "{:?}",
+            wrap_widths
+        );
+        let mut values = box_values(&[("text", boxes(&mut engine, &column)[1])]);
+        values.push(("text.wrap_width".to_string(), wrap_widths[0]));
+        tolerance::assert_matches_goldens!("measure_sees_content_box_of_padded_text", &values);
+    }
+
+    #[test]
+    fn test_available_rect_flows_around_corner_exclusion() {
+        let mut engine = TaffyLayoutEngine::new();
+        let text_style = style_from_json(r#"{"height": 200}"#).unwrap();
+        let text = engine.new_measurable_leaf_native(&text_style, 1).unwrap();
+        let container = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "flex_direction": "column", "width": 300, "padding_top": 10}"#,
+            &[text],
+        );
+        let measured = std::cell::Cell::new(0);
+        let measure = |request: &MeasureRequest| {
+            measured.set(measured.get() + 1);
+            Size {
+                width: request.known_width.unwrap_or(300.0),
+                height: 200.0,
+            }
+        };
+        engine
+            .compute_layout_with_measure_native(&container, 1000.0, 1000.0, measure)
+            .unwrap();
+
+        // A 100px square floated into the top right corner of the container
+        let corner = ExclusionRect {
+            x: 200.0,
+            y: 0.0,
+            width: 100.0,
+            height: 100.0,
+        };
+        engine
+            .set_exclusions_native(&container, vec![corner])
+            .unwrap();
+
+        let mut values = Vec::new();
+        let mut band = |engine: &TaffyLayoutEngine, name: &str, y: f32| {
+            let rect = engine.available_rect_native(&text, y, 20.0).unwrap();
+            values.push((format!("{}.y{}.x", name, y), rect.x));
+            values.push((format!("{}.y{}.width", name, y), rect.width));
+        };
+        for y in [0.0, 89.0, 90.0, 150.0] {
+            band(&engine, "corner", y);
+        }
+
+        // With a second float on the left, the band is what lies between
+        let left = ExclusionRect {
+            x: 0.0,
+            y: 0.0,
+            width: 80.0,
+            height: 40.0,
+        };
+        engine
+            .set_exclusions_native(&container, vec![corner, left])
+            .unwrap();
+        band(&engine, "both", 0.0);
+        band(&engine, "both", 40.0);
+
+        // Changing exclusions drops cached measurements of the children
+        engine
+            .compute_layout_with_measure_native(&container, 1000.0, 1000.0, measure)
+            .unwrap();
+        let before = measured.get();
+        engine
+            .compute_layout_with_measure_native(&container, 1000.0, 1000.0, measure)
+            .unwrap();
+        assert_eq!(measured.get(), before);
+        engine
+            .set_exclusions_native(&container, Vec::new())
+            .unwrap();
+        engine
+            .compute_layout_with_measure_native(&container, 1000.0, 1000.0, measure)
+            .unwrap();
+        assert!(measured.get() > before);
+        band(&engine, "cleared", 0.0);
+        tolerance::assert_matches_goldens!("available_rect_flows_around_corner_exclusion", &values);
     }
 
-    /// Get the number of nodes in the tree.
-    #[wasm_bindgen]
-    pub fn node_count(&self) -> usize {
-        self.node_map.len()
+    #[test]
+    fn test_defaults_profile_applies_to_later_styles() {
+        let mut engine = TaffyLayoutEngine::new();
+        let style = style_from_json(r#"{"width": 100, "padding_left": 10}"#).unwrap();
+        let taffy_node = engine.new_leaf_native(&style).unwrap();
+        engine.set_defaults_native(DefaultsProfile::Css);
+        let css_node = engine.new_leaf_native(&style).unwrap();
+
+        let taffy_style = engine
+            .tree
+            .style(engine.node(&taffy_node).unwrap())
+            .unwrap();
+        assert_eq!(taffy_style.display, Display::Flex);
+        assert_eq!(taffy_style.box_sizing, BoxSizing::BorderBox);
+        assert_eq!(taffy_style.align_content, None);
+
+        let css_style = engine.tree.style(engine.node(&css_node).unwrap()).unwrap();
+        assert_eq!(css_style.display, Display::Block);
+        assert_eq!(css_style.box_sizing, BoxSizing::ContentBox);
+        assert_eq!(css_style.align_content, Some(AlignContent::Stretch));
+
+        assert_eq!(
+            DefaultsProfile::parse("web").unwrap_err().to_string(),
+            "Unknown defaults profile: web"
+        );
     }
-}
 
-impl Default for TaffyLayoutEngine {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_clear_never_reuses_layout_ids() {
+        let mut engine = TaffyLayoutEngine::new();
+        let style = StyleInput::default();
+        let old = engine.new_leaf_native(&style).unwrap();
+        engine.clear();
+        engine.clear();
+        assert_eq!(engine.generation(), 1);
+
+        let new = engine.new_leaf_native(&style).unwrap();
+        assert_ne!(old, new);
+        assert!(engine.set_style_native(&new, &style).is_ok());
+        let stale = LayoutError::StaleLayoutId {
+            id: old.id(),
+            generation: 0,
+        };
+        assert_eq!(engine.set_style_native(&old, &style), Err(stale.clone()));
+        assert_eq!(
+            engine.set_children_native(&new, &[old.id()]),
+            Err(stale.clone())
+        );
+        assert_eq!(
+            stale.to_string(),
+            "Stale layout ID 0 (from generation 0, cleared since)"
+        );
+
+        // Removed and never-issued IDs in the current generation are not stale
+        engine.remove(&new).unwrap();
+        assert_eq!(
+            engine.style_input(&new).unwrap_err(),
+            LayoutError::InvalidLayoutId(new.id())
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_percent_transform_composes_with_parent() {
+        let mut engine = TaffyLayoutEngine::new();
+        let child = styled_node(&mut engine, r#"{"width": 200, "height": 50}"#, &[]);
+        let root = styled_node(&mut engine, r#"{"width": 400, "height": 100}"#, &[child]);
+        engine.compute_layout_native(&root, 400.0, 100.0).unwrap();
+
+        let parent = TransformInput {
+            scale_x: Some(2.0),
+            scale_y: Some(2.0),
+            origin_x: Some(0.0),
+            origin_y: Some(0.0),
+            ..Default::default()
+        };
+        engine.set_transform_native(&root, Some(parent)).unwrap();
+        let slide = TransformInput {
+            translate_x_percent: Some(-50.0),
+            ..Default::default()
+        };
+        engine.set_transform_native(&child, Some(slide)).unwrap();
+
+        let layouts = engine.absolute_layouts(&root).unwrap();
+        let mut values = Vec::new();
+        for (name, layout) in ["root", "child"].into_iter().zip(&layouts) {
+            for (index, value) in layout.matrix.iter().enumerate() {
+                values.push((format!("{}.matrix[{}]", name, index), *value));
+            }
+        }
+        values.push(("child.x".to_string(), layouts[1].x));
+        values.push(("child.width".to_string(), layouts[1].width));
+        tolerance::assert_matches_goldens!("percent_transform_composes_with_parent", &values);
+
+        // A quarter turn about the center keeps the center in place
+        let turn = TransformInput {
+            rotate_deg: Some(90.0),
+            ..Default::default()
+        };
+        engine.set_transform_native(&root, None).unwrap();
+        engine.set_transform_native(&child, Some(turn)).unwrap();
+        let [a, b, c, d, e, f] = engine.absolute_layouts(&root).unwrap()[1].matrix;
+        let (cx, cy) = (100.0, 25.0);
+        let pixel = Tolerance::absolute(1e-3);
+        assert!(pixel.eq(a * cx + c * cy + e, cx));
+        assert!(pixel.eq(b * cx + d * cy + f, cy));
+        assert!(Tolerance::absolute(1e-6).eq(b, 1.0));
+    }
 
     #[test]
-    fn test_basic_layout() {
+    fn test_scroll_axis_lets_flex_row_shrink_and_scroll() {
+        let layout_row = |engine: &mut TaffyLayoutEngine, row_json: &str| {
+            let cards: Vec<LayoutId> = (0..3)
+                .map(|_| {
+                    styled_node(
+                        engine,
+                        r#"{"width": 150, "height": 40, "flex_shrink": 0}"#,
+                        &[],
+                    )
+                })
+                .collect();
+            let row = styled_node(engine, row_json, &cards);
+            let root = styled_node(engine, r#"{"width": 200, "height": 100}"#, &[row]);
+            engine.compute_layout_native(&root, 200.0, 100.0).unwrap();
+            engine.absolute_layouts(&root).unwrap()[1].clone()
+        };
         let mut engine = TaffyLayoutEngine::new();
 
-        // Create a simple column layout
-        let child1 = engine
-            .tree
-            .new_leaf(Style {
-                size: Size {
-                    width: Dimension::length(100.0),
-                    height: Dimension::length(50.0),
-                },
-                ..Default::default()
+        let scroller = layout_row(
+            &mut engine,
+            r#"{"overflow": "visible", "overflow_x": "scroll"}"#,
+        );
+        assert_eq!(scroller.scroll_height, scroller.height);
+        let style = engine.style_input(&LayoutId(scroller.id)).unwrap();
+        assert_eq!(style.overflow_x, Some(OverflowKw::Scroll));
+        assert_eq!(style.overflow_y, Some(OverflowKw::Hidden));
+
+        let visible = layout_row(&mut engine, r#"{"overflow_x": "visible"}"#);
+        let values = [
+            ("scroller.width", scroller.width),
+            ("scroller.scroll_width", scroller.scroll_width),
+            ("visible.width", visible.width),
+            ("visible.scroll_width", visible.scroll_width),
+        ]
+        .map(|(key, value)| (key.to_string(), value));
+        tolerance::assert_matches_goldens!("scroll_axis_lets_flex_row_shrink_and_scroll", &values);
+    }
+
+    #[test]
+    fn test_max_content_chips_size_to_their_text() {
+        let mut engine = TaffyLayoutEngine::new();
+        // Text widths by measure ID, one 20px line each
+        let text_widths: [f32; 6] = [0.0, 40.0, 72.0, 25.0, 100.0, 30.0];
+        let measure = |request: &MeasureRequest| Size {
+            width: text_widths[request.measure_id as usize].min(request.available_width),
+            height: 20.0,
+        };
+        let chip_style =
+            r#"{"width_keyword": "max-content", "padding_left": 8, "padding_right": 8}"#;
+        let chips: Vec<LayoutId> = (1..=3)
+            .map(|measure_id| {
+                let text = engine
+                    .new_measurable_leaf_native(&StyleInput::default(), measure_id)
+                    .unwrap();
+                styled_node(&mut engine, chip_style, &[text])
             })
+            .collect();
+        // A column stretches auto widths, so only the keyword keeps chips
+        // to their text
+        let list = styled_node(
+            &mut engine,
+            r#"{"flex_direction": "column", "width": 500, "gap": 4}"#,
+            &chips,
+        );
+        engine
+            .compute_layout_with_measure_native(&list, 1000.0, 1000.0, measure)
             .unwrap();
+        let layouts = boxes(&mut engine, &list);
+        let mut values = Vec::new();
+        for (chip, index) in [1, 3, 5].into_iter().enumerate() {
+            values.push((format!("chip{}.y", chip), layouts[index].1));
+            values.push((format!("chip{}.width", chip), layouts[index].2));
+        }
+        let read_back = engine.style_input(&chips[0]).unwrap();
+        assert_eq!(read_back.width_keyword, Some(SizeKw::MaxContent));
+        assert_eq!(read_back.width, None);
 
-        let child2 = engine
-            .tree
-            .new_leaf(Style {
-                size: Size {
-                    width: Dimension::length(100.0),
-                    height: Dimension::length(50.0),
+        // fit-content fills the column up to the text, then stops at max_width
+        let sidebars: Vec<LayoutId> = [4, 5]
+            .into_iter()
+            .map(|measure_id| {
+                let text = engine
+                    .new_measurable_leaf_native(&StyleInput::default(), measure_id)
+                    .unwrap();
+                styled_node(
+                    &mut engine,
+                    r#"{"width_keyword": "fit-content", "max_width": 60}"#,
+                    &[text],
+                )
+            })
+            .collect();
+        let column = styled_node(
+            &mut engine,
+            r#"{"flex_direction": "column", "width": 300}"#,
+            &sidebars,
+        );
+        engine
+            .compute_layout_with_measure_native(&column, 1000.0, 1000.0, measure)
+            .unwrap();
+        let layouts = boxes(&mut engine, &column);
+        values.push(("long_sidebar.width".to_string(), layouts[1].2));
+        values.push(("short_sidebar.width".to_string(), layouts[3].2));
+        tolerance::assert_matches_goldens!("max_content_chips_size_to_their_text", &values);
+        assert!(matches!(
+            style_from_json(r#"{"width_keyword": "max-contnet"}"#),
+            Err(error) if error.to_string().contains("invalid width keyword")
+        ));
+    }
+
+    #[test]
+    fn test_positions_accumulate_offsets_and_scrolls() {
+        let mut engine = TaffyLayoutEngine::new();
+        // (left, top, scroll_x, scroll_y) from the root down. Insets do not
+        // move the root.
+        let levels = [
+            (0.0, 0.0, 2.0, 5.0),
+            (10.0, 20.0, 3.0, 40.0),
+            (15.0, 5.0, 0.0, 12.0),
+            (8.0, 30.0, 25.0, 0.0),
+            (4.0, 9.0, 6.0, 2.0),
+        ];
+        let mut ids: Vec<LayoutId> = Vec::new();
+        for &(left, top, _, _) in levels.iter().rev() {
+            let children: Vec<u64> = ids.last().map(|child| child.id()).into_iter().collect();
+            let node = engine
+                .new_with_children_native(&hidden_box(left, top, 100.0, 100.0), &children)
+                .unwrap();
+            ids.push(node);
+        }
+        ids.reverse();
+        for (id, &(_, _, scroll_x, scroll_y)) in ids.iter().zip(&levels) {
+            engine.set_scroll_offset(id, scroll_x, scroll_y).unwrap();
+        }
+        let sibling = engine
+            .new_leaf_native(&absolute_box(0.0, 0.0, 10.0, 10.0))
+            .unwrap();
+        engine.compute_layout(&ids[0], 500.0, 500.0).unwrap();
+
+        // Each level adds its offset and its parent's scroll, never its own
+        let (mut x, mut y) = (0.0, 0.0);
+        let layouts = engine.absolute_layouts(&ids[0]).unwrap();
+        for (depth, id) in ids.iter().enumerate() {
+            let (left, top, _, _) = levels[depth];
+            let (scroll_x, scroll_y) = depth
+                .checked_sub(1)
+                .map(|parent| (levels[parent].2, levels[parent].3))
+                .unwrap_or((0.0, 0.0));
+            x += left - scroll_x;
+            y += top - scroll_y;
+            let position = engine.absolute_position_native(id).unwrap();
+            assert_eq!(position, LayoutPoint { x, y });
+            let readback = find(&layouts, id);
+            assert_eq!((readback.x, readback.y), (x, y));
+        }
+
+        let between = engine.offset_between_native(&ids[1], &ids[4]).unwrap();
+        let (top, bottom) = (
+            engine.absolute_position_native(&ids[1]).unwrap(),
+            engine.absolute_position_native(&ids[4]).unwrap(),
+        );
+        assert_eq!(
+            between,
+            LayoutPoint {
+                x: bottom.x - top.x,
+                y: bottom.y - top.y,
+            }
+        );
+        assert_eq!(
+            engine.offset_between_native(&ids[2], &ids[2]).unwrap(),
+            LayoutPoint::default()
+        );
+
+        for (ancestor, descendant) in [(&ids[4], &ids[1]), (&ids[0], &sibling)] {
+            let error = engine
+                .offset_between_native(ancestor, descendant)
+                .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "Layout ID {} is not a descendant of {}",
+                    descendant.id(),
+                    ancestor.id()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_degenerate_space_and_measures_read_back_finite() {
+        let mut engine = TaffyLayoutEngine::new();
+        let text = engine
+            .new_measurable_leaf_native(
+                &StyleInput {
+                    flex_grow: Some(1.0),
+                    ..Default::default()
                 },
+                1,
+            )
+            .unwrap();
+        let square = engine
+            .new_leaf_native(&StyleInput {
+                width_percent: Some(0.5),
+                aspect_ratio: Some(1.0),
                 ..Default::default()
             })
             .unwrap();
-
         let root = engine
-            .tree
-            .new_with_children(
-                Style {
-                    display: Display::Flex,
-                    flex_direction: FlexDirection::Column,
-                    size: Size {
-                        width: Dimension::length(200.0),
-                        height: Dimension::length(200.0),
-                    },
+            .new_with_children_native(
+                &StyleInput {
+                    width_percent: Some(1.0),
+                    height_percent: Some(1.0),
+                    padding_top: Some(4.0),
                     ..Default::default()
                 },
-                &[child1, child2],
+                &[text.id(), square.id()],
             )
             .unwrap();
 
+        let spaces = [
+            (0.0, 0.0),
+            (0.0, 100.0),
+            (f32::INFINITY, 0.0),
+            (f32::NAN, f32::NEG_INFINITY),
+        ];
+        for (width, height) in spaces {
+            // A host measure that divides by a zero width
+            engine
+                .compute_layout_with_measure_native(&root, width, height, |_| Size {
+                    width: f32::NAN,
+                    height: f32::INFINITY,
+                })
+                .unwrap();
+            for layout in engine.absolute_layouts(&root).unwrap() {
+                let floats = [layout.x, layout.y, layout.width, layout.height];
+                assert!(
+                    floats.iter().chain(&layout.matrix).all(|v| v.is_finite()),
+                    "{}x{}: {:?}",
+                    width,
+                    height,
+                    layout
+                );
+            }
+            let position = engine.absolute_position_native(&square).unwrap();
+            assert!(position.x.is_finite() && position.y.is_finite());
+        }
+    }
+
+    #[test]
+    fn test_updating_registered_style_restyles_only_its_nodes() {
+        let mut engine = TaffyLayoutEngine::new();
+        let row = engine.register_style_native(&style_from_json(r#"{"height": 20}"#).unwrap());
+        let first = engine.new_leaf_with_style_id_native(&row).unwrap();
+        let second = engine.new_leaf_with_style_id_native(&row).unwrap();
+        let switched = styled_node(&mut engine, r#"{"height": 5}"#, &[]);
+        engine.set_style_id_native(&switched, &row).unwrap();
+        let detached = engine.new_leaf_with_style_id_native(&row).unwrap();
+        engine
+            .set_style_native(&detached, &style_from_json(r#"{"height": 20}"#).unwrap())
+            .unwrap();
+        let other = styled_node(&mut engine, r#"{"height": 20}"#, &[]);
+        let list = styled_node(
+            &mut engine,
+            r#"{"flexDirection": "column"}"#,
+            &[first, second, switched],
+        );
+        let root = styled_node(
+            &mut engine,
+            r#"{"flexDirection": "column", "width": 100}"#,
+            &[list, detached, other],
+        );
+        engine.compute_layout_native(&root, 100.0, 500.0).unwrap();
+        let heights = |engine: &TaffyLayoutEngine| {
+            [first, second, switched, detached, other]
+                .map(|id| engine.get_layout(&id).unwrap().height)
+        };
+        assert_eq!(heights(&engine), [20.0; 5]);
+
         engine
-            .tree
-            .compute_layout(
-                root,
-                Size {
-                    width: AvailableSpace::Definite(200.0),
-                    height: AvailableSpace::Definite(200.0),
-                },
+            .update_registered_style_native(&row, &style_from_json(r#"{"height": 32}"#).unwrap())
+            .unwrap();
+        let dirty = |id: &LayoutId| engine.tree.dirty(engine.node(id).unwrap()).unwrap();
+        assert!(dirty(&first) && dirty(&second) && dirty(&switched) && dirty(&list));
+        assert!(!dirty(&detached) && !dirty(&other));
+
+        engine.compute_layout_native(&root, 100.0, 500.0).unwrap();
+        assert_eq!(heights(&engine), [32.0, 32.0, 32.0, 20.0, 20.0]);
+        assert_eq!(engine.style_input(&first).unwrap().height, Some(32.0));
+
+        // Registered styles outlive clear, unknown ones are an error
+        engine.clear();
+        assert!(engine.new_leaf_with_style_id_native(&row).is_ok());
+        assert_eq!(
+            engine.new_leaf_with_style_id_native(&StyleId(7)),
+            Err(LayoutError::InvalidStyleId(7))
+        );
+    }
+
+    #[test]
+    fn test_virtual_window_tracks_variable_item_sizes() {
+        // Heights from 10 to 89 from a fixed linear congruential sequence
+        let mut seed: u32 = 12345;
+        let mut sizes: Vec<f32> = (0..50_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                10.0 + ((seed >> 16) % 80) as f32
+            })
+            .collect();
+        let mut engine = TaffyLayoutEngine::new();
+        let list = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "flex_direction": "column", "height": 600,
+                "padding_top": 8, "padding_bottom": 8, "overflow_y": "scroll"}"#,
+            &[],
+        );
+        engine
+            .set_virtual_children_native(&list, sizes.len(), VirtualItemSizes::Each(sizes.clone()))
+            .unwrap();
+        engine.compute_layout_native(&list, 400.0, 600.0).unwrap();
+
+        // Every item overlapping the viewport, found by walking the sizes
+        let expected = |sizes: &[f32], scroll: f32, viewport: f32| {
+            let mut offset = 8.0f64;
+            let mut window = Vec::new();
+            for (index, &size) in sizes.iter().enumerate() {
+                let end = offset + f64::from(size);
+                if end > f64::from(scroll) && offset < f64::from(scroll + viewport) {
+                    window.push((index, offset as f32));
+                }
+                offset = end;
+            }
+            window
+        };
+        let pixel = Tolerance::absolute(1e-3);
+        let check = |engine: &TaffyLayoutEngine, sizes: &[f32], scroll: f32| {
+            let window = engine.virtual_window_native(&list, scroll, 600.0).unwrap();
+            let expected = expected(sizes, scroll, 600.0);
+            assert_eq!(window.first_index, expected[0].0);
+            assert_eq!(window.last_index, expected[expected.len() - 1].0);
+            for (offset, (_, expected)) in window.offsets.iter().zip(&expected) {
+                assert!(pixel.eq(*offset, *expected), "{} vs {}", offset, expected);
+            }
+        };
+        for scroll in [0.0, 333.3, 25_000.0, 1_000_000.0, 2_000_000.0] {
+            check(&engine, &sizes, scroll);
+        }
+
+        // The scroll extent covers every item and both paddings
+        let total: f64 = sizes.iter().map(|&size| f64::from(size)).sum();
+        let scroll_height = engine.absolute_layouts(&list).unwrap()[0].scroll_height;
+        assert!(Tolerance::absolute(0.5).eq(scroll_height, (total + 16.0) as f32));
+        let last = engine
+            .virtual_window_native(&list, scroll_height - 600.0, 600.0)
+            .unwrap();
+        assert_eq!(last.last_index, sizes.len() - 1);
+
+        // Growing one item moves only the items after it
+        let before = engine
+            .virtual_window_native(&list, 25_000.0, 600.0)
+            .unwrap();
+        sizes[100] += 50.0;
+        engine
+            .set_virtual_item_size_native(&list, 100, sizes[100])
+            .unwrap();
+        let after = engine
+            .virtual_window_native(&list, 25_050.0, 600.0)
+            .unwrap();
+        assert_eq!(after.first_index, before.first_index);
+        for (after, before) in after.offsets.iter().zip(&before.offsets) {
+            assert!(pixel.eq(after - before, 50.0));
+        }
+        check(&engine, &sizes, 25_000.0);
+        let head = engine.virtual_window_native(&list, 0.0, 600.0).unwrap();
+        assert_eq!(head.offsets[0], 8.0);
+
+        assert_eq!(
+            engine.set_virtual_item_size_native(&list, sizes.len(), 10.0),
+            Err(LayoutError::VirtualItemOutOfRange {
+                index: sizes.len(),
+                count: sizes.len(),
+            })
+        );
+        assert_eq!(
+            engine.set_virtual_children_native(&list, 3, VirtualItemSizes::Each(vec![1.0])),
+            Err(LayoutError::VirtualSizeCount { count: 3, sizes: 1 })
+        );
+    }
+
+    #[test]
+    fn test_safe_area_insets_and_full_bleed_background() {
+        let mut engine = TaffyLayoutEngine::new();
+        let background = styled_node(
+            &mut engine,
+            r#"{"position": "absolute", "top": 0, "left": 0,
+                "width_percent": 100, "height_percent": 100, "ignore_safe_area": true}"#,
+            &[],
+        );
+        let header = styled_node(&mut engine, r#"{"height": 50}"#, &[]);
+        let root = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "flex_direction": "column",
+                "width_percent": 100, "height_percent": 100}"#,
+            &[background, header],
+        );
+        let insets = EdgeSizes {
+            top: 40.0,
+            right: 0.0,
+            bottom: 20.0,
+            left: 0.0,
+        };
+        engine
+            .compute_layout_with_insets_native(&root, 300.0, 600.0, insets, |_| Size::ZERO)
+            .unwrap();
+
+        let inset = boxes(&mut engine, &root);
+        let mut values = box_values(&[
+            ("root", inset[0]),
+            ("background", inset[1]),
+            ("header", inset[2]),
+        ]);
+        for (name, id) in [("header", &header), ("background", &background)] {
+            let position = engine.absolute_position_native(id).unwrap();
+            values.push((format!("{}.position.x", name), position.x));
+            values.push((format!("{}.position.y", name), position.y));
+        }
+        // Layout itself is unchanged, and the flag reads back
+        let node = engine.node(&background).unwrap();
+        let height = engine.tree.layout(node).unwrap().size.height;
+        values.push(("background.layout_height".to_string(), height));
+        assert_eq!(
+            engine.style_input(&background).unwrap().ignore_safe_area,
+            Some(true)
+        );
+
+        // A plain layout forgets the insets
+        engine.compute_layout_native(&root, 300.0, 600.0).unwrap();
+        let plain = boxes(&mut engine, &root);
+        values.extend(box_values(&[
+            ("plain.background", plain[1]),
+            ("plain.header", plain[2]),
+        ]));
+        tolerance::assert_matches_goldens!("safe_area_insets_and_full_bleed_background", &values);
+    }
+
+    #[test]
+    fn test_hash_style_covers_every_field() {
+        assert_eq!(xxh64(b""), 0xef46_db37_51d8_e999);
+        assert_eq!(
+            xxh64(b"Nobody inspects the spammish repetition"),
+            0xfbce_a83c_8a37_8bf1
+        );
+
+        // Sets every field, to values other than those a missing field
+        // defaults to
+        let full: serde_json::Value = serde_json::from_str(
+            r#"{
+                "display": "grid", "flex_direction": "column", "flex_wrap": "wrap",
+                "flex_grow": 1, "flex_shrink": 0, "flex_basis": 10, "flex": "1 1 auto",
+                "align_items": "center", "justify_content": "center",
+                "align_self": "center", "gap": 1, "row_gap": 2, "column_gap": 3,
+                "grid_template_columns": ["1fr", 20], "grid_template_rows": [10],
+                "grid_auto_columns": 30, "grid_auto_rows": 40, "grid_auto_flow": "column",
+                "grid_column_start": 1, "grid_column_end": 2,
+                "grid_row_start": 1, "grid_row_end": 2,
+                "width": 100, "height": 50, "min_width": 10, "max_width": 200,
+                "min_height": 5, "max_height": 100,
+                "width_percent": 50, "height_percent": 50, "min_width_percent": 10,
+                "max_width_percent": 90, "min_height_percent": 10, "max_height_percent": 90,
+                "width_keyword": "min-content", "height_keyword": "min-content",
+                "min_width_keyword": "min-content", "max_width_keyword": "max-content",
+                "min_height_keyword": "min-content", "max_height_keyword": "max-content",
+                "padding_top": 1, "padding_right": 2, "padding_bottom": 3, "padding_left": 4,
+                "margin_top": 1, "margin_right": 2, "margin_bottom": 3, "margin_left": 4,
+                "margin_top_auto": true, "margin_right_auto": true,
+                "margin_bottom_auto": true, "margin_left_auto": true,
+                "position": "absolute", "top": 1, "right": 2, "bottom": 3, "left": 4,
+                "overflow": "hidden", "overflow_x": "scroll", "overflow_y": "scroll",
+                "border_width": 1, "aspect_ratio": 2, "ignore_safe_area": true
+            }"#,
+        )
+        .unwrap();
+        let parse = |json: &serde_json::Value| -> StyleInput {
+            serde_json::from_value(json.clone()).unwrap()
+        };
+        // A field added to StyleInput reads back null until it is set above
+        let round_trip = serde_json::to_value(parse(&full)).unwrap();
+        for (field, value) in round_trip.as_object().unwrap() {
+            assert!(!value.is_null(), "{} is not set", field);
+        }
+
+        let mut engine = TaffyLayoutEngine::new();
+        let hash = engine.hash_style_native(&parse(&full));
+        assert_eq!(engine.hash_style_native(&parse(&full)), hash);
+        for field in round_trip.as_object().unwrap().keys() {
+            let mut without = full.clone();
+            without.as_object_mut().unwrap().remove(field);
+            assert_ne!(
+                engine.hash_style_native(&parse(&without)),
+                hash,
+                "{}",
+                field
+            );
+        }
+
+        // Unset fields lay out differently under another profile
+        engine.set_defaults_native(DefaultsProfile::Css);
+        assert_ne!(engine.hash_style_native(&parse(&full)), hash);
+    }
+
+    #[test]
+    fn test_probe_layout_widths_finds_toolbar_breakpoint() {
+        let mut engine = TaffyLayoutEngine::new();
+        let items: Vec<LayoutId> = [60, 80, 40, 100, 70]
+            .iter()
+            .map(|width| {
+                let style = format!(r#"{{"width": {}, "height": 40, "flex_shrink": 0}}"#, width);
+                styled_node(&mut engine, &style, &[])
+            })
+            .collect();
+        let toolbar = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "flex_wrap": "wrap", "column_gap": 8,
+                "width_percent": 100, "height": 40}"#,
+            &items,
+        );
+        engine
+            .compute_layout_native(&toolbar, 500.0, 600.0)
+            .unwrap();
+        let before = boxes(&mut engine, &toolbar);
+
+        let widths: Vec<f32> = (150..=210).map(|step| step as f32 * 2.0).collect();
+        let probes = engine
+            .probe_layout_widths_native(&toolbar, &widths, 600.0, |_| Size::ZERO)
+            .unwrap();
+        assert_eq!(boxes(&mut engine, &toolbar), before);
+
+        // The first width at which nothing wraps below the toolbar, found
+        // one layout at a time
+        let manual = widths
+            .iter()
+            .copied()
+            .find(|&width| {
+                engine
+                    .compute_layout_native(&toolbar, width, 600.0)
+                    .unwrap();
+                let layout = engine.tree.layout(engine.node(&toolbar).unwrap()).unwrap();
+                layout.content_size.height <= 40.0
+            })
+            .unwrap();
+        let probed = probes
+            .iter()
+            .find(|probe| probe.overflow_x == 0.0 && probe.overflow_y == 0.0)
+            .unwrap();
+        assert_eq!(probed.width, manual);
+
+        let at_300 = probes.iter().find(|probe| probe.width == 300.0).unwrap();
+        let at_400 = probes.iter().find(|probe| probe.width == 400.0).unwrap();
+        let values = [
+            ("breakpoint", manual),
+            ("at_300.overflow_y", at_300.overflow_y),
+            ("at_300.slack", at_300.slack),
+            ("at_400.overflow_y", at_400.overflow_y),
+            ("at_400.slack", at_400.slack),
+        ]
+        .map(|(key, value)| (key.to_string(), value));
+        tolerance::assert_matches_goldens!("probe_layout_widths_finds_toolbar_breakpoint", &values);
+    }
+
+    #[test]
+    fn test_find_in_direction_across_a_grid_of_cards() {
+        let mut engine = TaffyLayoutEngine::new();
+        let cards: Vec<LayoutId> = (0..9)
+            .map(|_| styled_node(&mut engine, r#"{"width": 100, "height": 60}"#, &[]))
+            .collect();
+        let grid = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "flex_wrap": "wrap", "gap": 10, "width": 330}"#,
+            &cards,
+        );
+        engine.compute_layout_native(&grid, 800.0, 600.0).unwrap();
+        let ids: Vec<u64> = cards.iter().map(|card| card.0).collect();
+        let find = |engine: &TaffyLayoutEngine, from: &LayoutId, direction, candidates: &[u64]| {
+            engine
+                .find_in_direction_native(from, direction, candidates)
+                .unwrap()
+        };
+
+        // From the center card, each neighbor sharing a row or column
+        for (direction, expected) in [
+            (NavDirection::Up, 1),
+            (NavDirection::Down, 7),
+            (NavDirection::Left, 3),
+            (NavDirection::Right, 5),
+        ] {
+            let found = find(&engine, &cards[4], direction, &ids);
+            assert_eq!(found, Some(cards[expected]), "{:?}", direction);
+        }
+        // Nothing lies past the edge, and a node is never its own target
+        assert_eq!(find(&engine, &cards[0], NavDirection::Up, &ids), None);
+        assert_eq!(find(&engine, &cards[2], NavDirection::Right, &ids), None);
+        assert_eq!(
+            find(&engine, &cards[8], NavDirection::Left, &[cards[8].0]),
+            None
+        );
+        assert_eq!(
+            NavDirection::parse("forward"),
+            Err(LayoutError::UnknownDirection("forward".to_string()))
+        );
+
+        // Off-axis candidates against an aligned one three card widths on
+        let mut engine = TaffyLayoutEngine::new();
+        let card = |engine: &mut TaffyLayoutEngine, left: f32, top: f32| {
+            let style = format!(
+                r#"{{"position": "absolute", "left": {}, "top": {}, "width": 100, "height": 60}}"#,
+                left, top
+            );
+            styled_node(engine, &style, &[])
+        };
+        let from = card(&mut engine, 0.0, 0.0);
+        let aligned = card(&mut engine, 400.0, 0.0);
+        let near = card(&mut engine, 150.0, 65.0);
+        let below = card(&mut engine, 0.0, 200.0);
+        let drifted = card(&mut engine, 130.0, 100.0);
+        let upper = card(&mut engine, 300.0, 20.0);
+        let lower = card(&mut engine, 300.0, 140.0);
+        let nodes = [from, aligned, near, below, drifted, upper, lower];
+        let root = styled_node(&mut engine, r#"{"width": 800, "height": 600}"#, &nodes);
+        engine.compute_layout_native(&root, 800.0, 600.0).unwrap();
+
+        // Moving right, 5px out of line weighs 150: 50.2 + 50 + 150 beats
+        // 300 + 300 for the aligned card
+        let right = [aligned.0, near.0];
+        assert_eq!(
+            find(&engine, &from, NavDirection::Right, &right),
+            Some(near)
+        );
+        // 20px out of line weighs 600 and loses
+        engine
+            .set_style_native(
+                &near,
+                &style_from_json(
+                    r#"{"position": "absolute", "left": 150, "top": 80,
+                        "width": 100, "height": 60}"#,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        engine.compute_layout_native(&root, 800.0, 600.0).unwrap();
+        assert_eq!(
+            find(&engine, &from, NavDirection::Right, &right),
+            Some(aligned)
+        );
+        // Moving down, drifting 30px sideways only weighs 60: 50 + 40 + 60
+        // beats 140 + 140
+        assert_eq!(
+            find(&engine, &from, NavDirection::Down, &[below.0, drifted.0]),
+            Some(drifted)
+        );
+
+        // Equal distances go to the topmost, whatever the candidate order:
+        // the cards 50px right of the one at (150, 80) both touch its row,
+        // one ending at its top and one starting at its bottom
+        assert_eq!(
+            find(&engine, &near, NavDirection::Right, &[lower.0, upper.0]),
+            Some(upper)
+        );
+    }
+
+    #[test]
+    fn flex_shorthand_resolves_each_form_and_warns_on_bad_values() {
+        let auto = Dimension::auto();
+        let px = Dimension::length;
+        for (value, expected) in [
+            ("none", (0.0, 0.0, auto)),
+            ("auto", (1.0, 1.0, auto)),
+            ("initial", (0.0, 1.0, auto)),
+            ("1", (1.0, 1.0, px(0.0))),
+            ("0", (0.0, 1.0, px(0.0))),
+            ("200px", (1.0, 1.0, px(200.0))),
+            ("content", (1.0, 1.0, auto)),
+            ("2 3", (2.0, 3.0, px(0.0))),
+            ("2 30%", (2.0, 1.0, Dimension::percent(0.3))),
+            ("1 1 auto", (1.0, 1.0, auto)),
+            ("0 0 200px", (0.0, 0.0, px(200.0))),
+            ("1 0 0", (1.0, 0.0, px(0.0))),
+            ("auto 2 0", (2.0, 0.0, auto)),
+        ] {
+            assert_eq!(parse_flex_shorthand(value), Ok(expected), "flex: {}", value);
+        }
+        for value in ["", "1 2 3 4", "-1", "1 1 1", "1 auto auto", "fill"] {
+            assert!(parse_flex_shorthand(value).is_err(), "flex: {}", value);
+        }
+
+        // The shorthand beats the individual fields, in a row 300px wide
+        let mut engine = TaffyLayoutEngine::new();
+        let fixed = styled_node(
+            &mut engine,
+            r#"{"flex": "none", "flex_grow": 1, "width": 100, "height": 10}"#,
+            &[],
+        );
+        let fill = styled_node(&mut engine, r#"{"flex": "1", "height": 10}"#, &[]);
+        let root = styled_node(
+            &mut engine,
+            r#"{"display": "flex", "width": 300, "height": 10}"#,
+            &[fixed, fill],
+        );
+        assert!(engine.take_style_warnings_native().is_empty());
+        engine.compute_layout_native(&root, 300.0, 10.0).unwrap();
+        let widths: Vec<f32> = boxes(&mut engine, &root).iter().map(|b| b.2).collect();
+        assert_eq!(widths, [300.0, 100.0, 200.0]);
+
+        // A value that does not parse leaves the fields in charge and is
+        // reported once
+        let style = style_from_json(r#"{"flex": "1 1 1", "flex_grow": 2}"#).unwrap();
+        engine.set_style_native(&fixed, &style).unwrap();
+        assert_eq!(engine.style_input(&fixed).unwrap().flex_grow, Some(2.0));
+        assert_eq!(
+            engine.take_style_warnings_native(),
+            vec![StyleWarning {
+                property: "flex".to_string(),
+                value: "1 1 1".to_string(),
+                message: "\"1\" is not a flex basis".to_string(),
+            }]
+        );
+        assert!(engine.take_style_warnings_native().is_empty());
+    }
+
+    #[test]
+    fn test_unregistered_style_ids_go_stale() {
+        let mut engine = TaffyLayoutEngine::new();
+        let row = engine.register_style_native(&style_from_json(r#"{"height": 20}"#).unwrap());
+        let node = engine.new_leaf_with_style_id_native(&row).unwrap();
+        assert!(engine.unregister_style_native(&row));
+        let error = engine.new_leaf_with_style_id_native(&row).unwrap_err();
+        assert_eq!(
+            error,
+            LayoutError::StaleStyleId(StaleHandle {
+                handle: row.0,
+                freed: true
+            })
+        );
+        assert!(error
+            .to_string()
+            .starts_with("Unregistered style: Stale handle"));
+        assert!(!engine.unregister_style_native(&row));
+        assert_eq!(engine.double_frees(), 1);
+
+        // The slot is reused for the next style, which the old ID cannot reach
+        let column = engine.register_style_native(&style_from_json(r#"{"height": 8}"#).unwrap());
+        assert_eq!(column.0 as u32, row.0 as u32);
+        assert!(engine.set_style_id_native(&node, &row).is_err());
+        engine.compute_layout_native(&node, 100.0, 100.0).unwrap();
+        assert_eq!(engine.get_layout(&node).unwrap().height, 20.0);
+    }
+
+    #[test]
+    fn test_cloned_subtree_lays_out_on_its_own() {
+        let mut engine = TaffyLayoutEngine::new();
+        let measure = |request: &MeasureRequest| Size {
+            width: if request.measure_id == 7 { 50.0 } else { 0.0 },
+            height: 12.0,
+        };
+        let chip = engine.register_style_native(&style_from_json(r#"{"height": 10}"#).unwrap());
+        let mut rows = Vec::new();
+        for _ in 0..3 {
+            let mut cells: Vec<LayoutId> = (0..4)
+                .map(|_| styled_node(&mut engine, r#"{"width": 20, "height": 10}"#, &[]))
+                .collect();
+            cells.push(engine.new_leaf_with_style_id_native(&chip).unwrap());
+            rows.push(styled_node(
+                &mut engine,
+                r#"{"flex_direction": "row", "gap": 4}"#,
+                &cells,
+            ));
+        }
+        rows.push(
+            engine
+                .new_measurable_leaf_native(&StyleInput::default(), 7)
+                .unwrap(),
+        );
+        let root = styled_node(
+            &mut engine,
+            r#"{"flex_direction": "column", "width": 300, "align_items": "flex-start"}"#,
+            &rows,
+        );
+        let shifted = TransformInput {
+            translate_x: Some(5.0),
+            ..Default::default()
+        };
+        engine
+            .set_transform_native(&rows[1], Some(shifted))
+            .unwrap();
+        engine
+            .compute_layout_with_measure_native(&root, 1000.0, 1000.0, measure)
+            .unwrap();
+        let original = engine.absolute_layouts(&root).unwrap();
+        assert_eq!(original.len(), 20);
+
+        // The copy starts dirty, even though the original is laid out
+        let nodes = engine.node_count();
+        let copy = engine.clone_subtree_native(&root).unwrap();
+        assert_eq!(engine.node_count(), nodes + 20);
+        engine
+            .compute_layout_with_measure_native(&copy, 1000.0, 1000.0, measure)
+            .unwrap();
+        let copied = engine.absolute_layouts(&copy).unwrap();
+        let ids: Vec<u64> = copied.iter().map(|layout| layout.id).collect();
+        assert!(ids
+            .iter()
+            .all(|id| original.iter().all(|layout| layout.id != *id)));
+        let geometry = |layouts: &[AbsoluteLayout]| -> Vec<(f32, f32, f32, f32, Matrix2D)> {
+            layouts
+                .iter()
+                .map(|layout| {
+                    (
+                        layout.x,
+                        layout.y,
+                        layout.width,
+                        layout.height,
+                        layout.matrix,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(geometry(&copied), geometry(&original));
+        assert_eq!(copied[19].width, 50.0);
+
+        // Restyling the copy leaves the original's styles and layout alone
+        let wide = style_from_json(r#"{"width": 40, "height": 30}"#).unwrap();
+        for &id in &ids[2..6] {
+            engine.set_style_native(&LayoutId(id), &wide).unwrap();
+        }
+        engine
+            .set_style_native(
+                &LayoutId(ids[0]),
+                &style_from_json(r#"{"flex_direction": "column", "width": 500}"#).unwrap(),
             )
             .unwrap();
+        engine
+            .compute_layout_with_measure_native(&copy, 1000.0, 1000.0, measure)
+            .unwrap();
+        engine
+            .compute_layout_with_measure_native(&root, 1000.0, 1000.0, measure)
+            .unwrap();
+        let restyled = engine.absolute_layouts(&copy).unwrap();
+        assert_eq!(restyled[0].width, 500.0);
+        assert_eq!((restyled[2].width, restyled[2].height), (40.0, 30.0));
+        assert_eq!(restyled[19].width, 500.0);
+        let after = engine.absolute_layouts(&root).unwrap();
+        assert_eq!(geometry(&after), geometry(&original));
+        assert_eq!(engine.style_input(&root).unwrap().width, Some(300.0));
+
+        // Removing the copy keeps the original too
+        for id in ids.iter().rev() {
+            engine.remove(&LayoutId(*id)).unwrap();
+        }
+        assert_eq!(engine.node_count(), nodes);
+        assert_eq!(
+            geometry(&engine.absolute_layouts(&root).unwrap()),
+            geometry(&original)
+        );
 
-        let root_layout = engine.tree.layout(root).unwrap();
-        assert_eq!(root_layout.size.width, 200.0);
-        assert_eq!(root_layout.size.height, 200.0);
+        // Registered styles are the one thing the copies share
+        let copy = engine.clone_subtree_native(&root).unwrap();
+        engine
+            .update_registered_style_native(&chip, &style_from_json(r#"{"height": 16}"#).unwrap())
+            .unwrap();
+        for tree in [&root, &copy] {
+            engine
+                .compute_layout_with_measure_native(tree, 1000.0, 1000.0, measure)
+                .unwrap();
+            assert_eq!(engine.absolute_layouts(tree).unwrap()[6].height, 16.0);
+        }
+    }
 
-        let child1_layout = engine.tree.layout(child1).unwrap();
-        assert_eq!(child1_layout.location.x, 0.0);
-        assert_eq!(child1_layout.location.y, 0.0);
+    #[test]
+    fn test_grid_styles_without_the_grid_feature_lay_out_as_block() {
+        let mut engine = TaffyLayoutEngine::new();
+        let cells: Vec<LayoutId> = (0..2)
+            .map(|_| styled_node(&mut engine, r#"{"height": 10}"#, &[]))
+            .collect();
+        let root = styled_node(
+            &mut engine,
+            r#"{"display": "grid", "width": 200, "grid_template_columns": 2}"#,
+            &cells,
+        );
+        engine.compute_layout_native(&root, 1000.0, 1000.0).unwrap();
+        let warnings = engine.take_style_warnings_native();
 
-        let child2_layout = engine.tree.layout(child2).unwrap();
-        assert_eq!(child2_layout.location.x, 0.0);
-        assert_eq!(child2_layout.location.y, 50.0);
+        if cfg!(feature = "grid") {
+            assert!(warnings.is_empty(), "{:?}", warnings);
+            assert_eq!(boxes(&mut engine, &root)[2], (100.0, 0.0, 100.0, 10.0));
+        } else {
+            let properties: Vec<&str> = warnings.iter().map(|w| w.property.as_str()).collect();
+            assert_eq!(properties, ["display", "grid_template_columns"]);
+            assert_eq!(boxes(&mut engine, &root)[2], (0.0, 10.0, 200.0, 10.0));
+        }
     }
 }
@@ -9,7 +9,7 @@
 import { log } from "@glade/logging";
 import { base64ToBytes, formatBytes } from "@glade/utils";
 
-import type { LayoutBounds, LayoutId } from "../pkg/layout";
+import type { LayoutBounds, LayoutId, LayoutPoint, StyleId } from "../pkg/layout";
 import {
   type InitOutput,
   initSync,
@@ -37,20 +37,160 @@ export function createLayoutEngine(): TaffyLayoutEngine {
 }
 
 // Re-export types
-export type { InitOutput, LayoutBounds, LayoutId };
+export type { InitOutput, LayoutBounds, LayoutId, LayoutPoint, StyleId };
+
+/**
+ * Resolved widths of a node's four edges. Also the insets passed to
+ * compute_layout_with_insets, such as a notch or home indicator.
+ */
+export type EdgeSizes = {
+  top: number;
+  right: number;
+  bottom: number;
+  left: number;
+};
 
 /**
  * Callback type for measuring nodes during layout computation. Called by
  * Taffy when it needs the intrinsic size of a measurable node.
+ *
+ * Sizes describe the node's content box: padding and border are already
+ * subtracted, and are passed along for reference. Return the content size;
+ * the engine adds the padding and border back.
  */
 export type MeasureCallback = (
   measureId: number,
   knownWidth: number,
   knownHeight: number,
   availableWidth: number,
-  availableHeight: number
+  availableHeight: number,
+  padding: EdgeSizes,
+  border: EdgeSizes
 ) => { width: number; height: number };
 
+/**
+ * Clip rectangle in absolute coordinates. Nodes without a clipping ancestor
+ * report the largest finite f32 extents.
+ */
+export type ClipRect = {
+  min_x: number;
+  min_y: number;
+  max_x: number;
+  max_y: number;
+};
+
+/**
+ * A rectangle that a container's content flows around, relative to the
+ * container's border box, as passed to set_exclusions.
+ */
+export type ExclusionRect = {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+};
+
+/**
+ * A node's layout in absolute coordinates, as returned by get_absolute_layouts.
+ */
+export type AbsoluteLayout = {
+  id: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  clip: ClipRect;
+  fully_clipped: boolean;
+  /**
+   * The node's transform composed with its ancestors', as [a, b, c, d, e, f]
+   * over absolute coordinates. The position and clip are untransformed.
+   */
+  matrix: [number, number, number, number, number, number];
+  /**
+   * Size of the area the node scrolls over, as DOM scrollWidth and
+   * scrollHeight: its padding box, grown to reach any overflowing content.
+   */
+  scroll_width: number;
+  scroll_height: number;
+};
+
+/**
+ * The virtual children of a container that overlap a viewport, as returned
+ * by get_virtual_window. `last_index` is inclusive, and `offsets` holds the
+ * main-axis offset of each item in the window from the container's padding
+ * edge, ready to use as the `top` or `left` inset of an absolutely
+ * positioned node. Containers with no items return empty offsets.
+ */
+export type VirtualWindow = {
+  first_index: number;
+  last_index: number;
+  offsets: number[];
+};
+
+/**
+ * How a root fits one width tried by probe_layout_widths. The overflows are
+ * how far the root or its content reaches past the available space, zero
+ * when it fits; `slack` is the main-axis space flex items leave unfilled,
+ * summed over every flex line in the tree.
+ */
+export type WidthProbe = {
+  width: number;
+  overflow_x: number;
+  overflow_y: number;
+  slack: number;
+};
+
+/**
+ * A rendering transform passed to set_transform, applied as translate,
+ * rotate, then scale about the origin. Percentages resolve against the
+ * node's own size; the origin defaults to the center.
+ */
+export type LayoutTransform = {
+  translate_x?: number;
+  translate_x_percent?: number;
+  translate_y?: number;
+  translate_y_percent?: number;
+  scale_x?: number;
+  scale_y?: number;
+  rotate_deg?: number;
+  origin_x?: number;
+  origin_x_percent?: number;
+  origin_y?: number;
+  origin_y_percent?: number;
+};
+
+/**
+ * Scratch memory retained between readbacks, as returned by memory_report.
+ */
+export type MemoryReport = {
+  arena_capacity_bytes: number;
+  arena_peak_bytes: number;
+  arena_resets: number;
+};
+
+/**
+ * A style value the engine could not use, as returned by
+ * take_style_warnings. The rest of the style still applies.
+ */
+export type StyleWarning = {
+  property: string;
+  value: string;
+  message: string;
+};
+
+/**
+ * Defaults for style properties left unset, passed to set_defaults. "css"
+ * uses CSS initial values (block display, content-box sizing) for parity
+ * with the web build; "taffy" keeps Taffy's own defaults.
+ */
+export type DefaultsProfile = "css" | "taffy";
+
+/**
+ * Direction for find_in_direction, which picks the candidate spatial
+ * navigation moves to, preferring ones aligned with the focused node.
+ */
+export type NavDirection = "up" | "down" | "left" | "right";
+
 /**
  * Grid auto-flow direction for CSS Grid.
  */
@@ -82,6 +222,29 @@ export type TrackSize =
  */
 export type GridTemplate = number | TrackSize[];
 
+/**
+ * Alignment keyword for alignItems and alignSelf. "auto" leaves the property
+ * unset so the parent's alignItems applies.
+ */
+export type AlignKeyword =
+  | "auto"
+  | "start"
+  | "end"
+  | "flex-start"
+  | "flex-end"
+  | "center"
+  | "stretch"
+  | "baseline";
+
+export type OverflowKeyword = "visible" | "clip" | "hidden" | "scroll";
+
+/**
+ * Intrinsic size keyword. fit-content on width fills the available space
+ * between the min- and max-content widths, and stops at a px maxWidth.
+ * Heights size to their content, so the keywords agree there.
+ */
+export type IntrinsicSize = "min-content" | "max-content" | "fit-content";
+
 /**
  * Style input for layout computation. Maps to Glade's Styles interface.
  */
@@ -93,15 +256,22 @@ export interface StyleInput {
   flexGrow?: number;
   flexShrink?: number;
   flexBasis?: number;
-  alignItems?: "flex-start" | "flex-end" | "center" | "stretch" | "baseline";
+  // The flex shorthand as CSS writes it, such as "1", "1 1 auto", "0 0 200px"
+  // or "none". It beats flexGrow, flexShrink and flexBasis when it parses;
+  // otherwise they apply and take_style_warnings reports it.
+  flex?: string;
+  alignItems?: AlignKeyword;
   justifyContent?:
+    | "start"
+    | "end"
     | "flex-start"
     | "flex-end"
     | "center"
+    | "stretch"
     | "space-between"
     | "space-around"
     | "space-evenly";
-  alignSelf?: "auto" | "flex-start" | "flex-end" | "center" | "stretch" | "baseline";
+  alignSelf?: AlignKeyword;
   gap?: number;
   rowGap?: number;
   columnGap?: number;
@@ -119,13 +289,13 @@ export interface StyleInput {
   gridRowStart?: GridPlacement;
   gridRowEnd?: GridPlacement;
 
-  // Sizing
-  width?: number;
-  height?: number;
-  minWidth?: number;
-  maxWidth?: number;
-  minHeight?: number;
-  maxHeight?: number;
+  // Sizing, in px or an intrinsic keyword measured before each layout
+  width?: number | IntrinsicSize;
+  height?: number | IntrinsicSize;
+  minWidth?: number | IntrinsicSize;
+  maxWidth?: number | IntrinsicSize;
+  minHeight?: number | IntrinsicSize;
+  maxHeight?: number | IntrinsicSize;
 
   // Sizing percentages
   widthPercent?: number;
@@ -158,16 +328,32 @@ export interface StyleInput {
   bottom?: number;
   left?: number;
 
-  // Overflow
-  overflow?: "visible" | "hidden" | "scroll";
-  overflowX?: "visible" | "hidden" | "scroll";
-  overflowY?: "visible" | "hidden" | "scroll";
+  // Overflow. overflowX and overflowY beat overflow on their axis, and an
+  // axis left visible or clip beside a hidden or scrolling one computes to
+  // hidden, as in CSS. A scrolling axis drops a flex item's automatic
+  // minimum size to zero so it can shrink and scroll.
+  overflow?: OverflowKeyword;
+  overflowX?: OverflowKeyword;
+  overflowY?: OverflowKeyword;
 
   // Border (for layout)
   borderWidth?: number;
 
   // Aspect ratio (width / height)
   aspectRatio?: number;
+
+  // Readback only: under compute_layout_with_insets, edges lying on the
+  // inset box are reported grown out to the viewport, as for a full-bleed
+  // background. Layout and children are unaffected.
+  ignoreSafeArea?: boolean;
+}
+
+/**
+ * Split a size field into its px and keyword parts for WASM, which keeps
+ * keywords in separate fields.
+ */
+function sizeToWasm(value: number | IntrinsicSize | undefined): [number?, IntrinsicSize?] {
+  return typeof value === "string" ? [undefined, value] : [value, undefined];
 }
 
 /**
@@ -176,6 +362,13 @@ export interface StyleInput {
  * Grid types are passed directly - Rust uses untagged serde to deserialize them.
  */
 export function styleToWasm(style: StyleInput): Record<string, unknown> {
+  const [width, widthKeyword] = sizeToWasm(style.width);
+  const [height, heightKeyword] = sizeToWasm(style.height);
+  const [minWidth, minWidthKeyword] = sizeToWasm(style.minWidth);
+  const [maxWidth, maxWidthKeyword] = sizeToWasm(style.maxWidth);
+  const [minHeight, minHeightKeyword] = sizeToWasm(style.minHeight);
+  const [maxHeight, maxHeightKeyword] = sizeToWasm(style.maxHeight);
+
   return {
     display: style.display,
     flex_direction: style.flexDirection,
@@ -183,6 +376,7 @@ export function styleToWasm(style: StyleInput): Record<string, unknown> {
     flex_grow: style.flexGrow,
     flex_shrink: style.flexShrink,
     flex_basis: style.flexBasis,
+    flex: style.flex,
     align_items: style.alignItems,
     justify_content: style.justifyContent,
     align_self: style.alignSelf,
@@ -203,12 +397,18 @@ export function styleToWasm(style: StyleInput): Record<string, unknown> {
     grid_row_start: style.gridRowStart,
     grid_row_end: style.gridRowEnd,
 
-    width: style.width,
-    height: style.height,
-    min_width: style.minWidth,
-    max_width: style.maxWidth,
-    min_height: style.minHeight,
-    max_height: style.maxHeight,
+    width,
+    height,
+    min_width: minWidth,
+    max_width: maxWidth,
+    min_height: minHeight,
+    max_height: maxHeight,
+    width_keyword: widthKeyword,
+    height_keyword: heightKeyword,
+    min_width_keyword: minWidthKeyword,
+    max_width_keyword: maxWidthKeyword,
+    min_height_keyword: minHeightKeyword,
+    max_height_keyword: maxHeightKeyword,
     width_percent: style.widthPercent,
     height_percent: style.heightPercent,
     min_width_percent: style.minWidthPercent,
@@ -237,5 +437,6 @@ export function styleToWasm(style: StyleInput): Record<string, unknown> {
     overflow_y: style.overflowY,
     border_width: style.borderWidth,
     aspect_ratio: style.aspectRatio,
+    ignore_safe_area: style.ignoreSafeArea,
   };
 }
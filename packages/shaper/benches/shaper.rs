@@ -0,0 +1,171 @@
+//! Text shaping benchmarks against a 60fps frame budget.
+//!
+//! Run with `cargo bench -p shaper`. Uses the Inter font from the repo's
+//! `assets` directory so results do not depend on system fonts.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    #[cfg(feature = "raster")]
+    use cosmic_text::fontdb::{Database, Source};
+    #[cfg(feature = "raster")]
+    use cosmic_text::{CacheKey, CacheKeyFlags, FontSystem, SwashCache, Weight};
+    use criterion::{criterion_group, BenchmarkId, Criterion, Throughput};
+    use shaper::{FontStyleInput, LayoutOptions, TextShaper};
+    #[cfg(feature = "raster")]
+    use std::sync::Arc;
+
+    const INTER: &[u8] = include_bytes!("../../../assets/InterVariable.ttf");
+
+    const LABELS: [&str; 6] = [
+        "OK",
+        "Cancel",
+        "Save changes",
+        "File",
+        "Settings",
+        "Q3 revenue",
+    ];
+
+    const PARAGRAPH: &str = "The layout engine measures every text node before it \
+        places a single box, so shaping sits directly on the critical path of \
+        each frame. Long documents wrap across dozens of lines, mixing short \
+        words with long identifiers like get_absolute_layouts and numbers such \
+        as 3.14159 or 1,024. ";
+
+    #[cfg(feature = "raster")]
+    const ALPHABET: &str = "The quick brown fox jumps over the lazy dog? 0123456789 \
+        ABCDEFGHIJKLMNOPQRSTUVWXYZ abcdefghijklmnopq";
+
+    /// Sizes a glyph is rasterized at in the rasterize benchmark.
+    #[cfg(feature = "raster")]
+    const RASTER_SIZES: [f32; 5] = [12.0, 14.0, 16.0, 20.0, 24.0];
+
+    fn shaper() -> (TextShaper, FontStyleInput) {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        let style = FontStyleInput {
+            family: Some("Inter".to_string()),
+            weight: Some(400),
+            ..Default::default()
+        };
+        (shaper, style)
+    }
+
+    fn bench_short_labels(c: &mut Criterion) {
+        let (mut shaper, style) = shaper();
+        let mut group = c.benchmark_group("shape_line");
+        group.throughput(Throughput::Elements(LABELS.len() as u64));
+        group.bench_function("labels", |b| {
+            b.iter(|| {
+                for label in LABELS {
+                    shaper.shape_line_native(label, 14.0, 20.0, &style).unwrap();
+                }
+            })
+        });
+        group.finish();
+    }
+
+    fn bench_long_article(c: &mut Criterion) {
+        let (mut shaper, style) = shaper();
+        let mut group = c.benchmark_group("layout_text");
+        for paragraphs in [10usize, 50] {
+            let article = PARAGRAPH.repeat(paragraphs);
+            group.throughput(Throughput::Bytes(article.len() as u64));
+            group.bench_with_input(
+                BenchmarkId::new("article", paragraphs),
+                &article,
+                |b, article| {
+                    b.iter(|| {
+                        shaper.layout_text_native(
+                            article,
+                            16.0,
+                            24.0,
+                            640.0,
+                            &style,
+                            &LayoutOptions::default(),
+                        )
+                    })
+                },
+            );
+        }
+        group.finish();
+    }
+
+    fn bench_measure(c: &mut Criterion) {
+        let (mut shaper, style) = shaper();
+        let article = PARAGRAPH.repeat(10);
+        let mut group = c.benchmark_group("measure_text");
+        group.bench_function("article", |b| {
+            b.iter(|| shaper.measure_text_native(&article, 16.0, 24.0, Some(640.0), &style))
+        });
+        group.finish();
+    }
+
+    /// 100 glyphs at 5 sizes with empty caches, against rasterizing each
+    /// through cosmic-text's `SwashCache` as the shaper used to.
+    #[cfg(feature = "raster")]
+    fn bench_rasterize(c: &mut Criterion) {
+        let (mut shaper, style) = shaper();
+        let line = shaper
+            .shape_line_native(ALPHABET, 16.0, 20.0, &style)
+            .unwrap();
+        // Every size of a glyph, then the next glyph
+        let requests: Vec<(u64, u32, f32)> = line.glyphs[..100]
+            .iter()
+            .flat_map(|glyph| RASTER_SIZES.map(|size| (glyph.cosmic_font_id, glyph.glyph_id, size)))
+            .collect();
+        let mut group = c.benchmark_group("rasterize");
+        group.throughput(Throughput::Elements(requests.len() as u64));
+        group.bench_function("shaper", |b| {
+            b.iter(|| {
+                shaper.clear_cache();
+                for &(cosmic_font_id, glyph_id, size) in &requests {
+                    shaper.rasterize_glyph_native(cosmic_font_id, glyph_id, size);
+                }
+            })
+        });
+
+        let mut font_system = FontSystem::new_with_locale_and_db("en-US".into(), Database::new());
+        let face = font_system
+            .db_mut()
+            .load_font_source(Source::Binary(Arc::new(INTER.to_vec())))[0];
+        let mut swash_cache = SwashCache::new();
+        group.bench_function("swash_cache", |b| {
+            b.iter(|| {
+                for &(_, glyph_id, size) in &requests {
+                    let (cache_key, _, _) = CacheKey::new(
+                        face,
+                        glyph_id as u16,
+                        size,
+                        (0.0, 0.0),
+                        Weight::NORMAL,
+                        CacheKeyFlags::empty(),
+                    );
+                    swash_cache.get_image_uncached(&mut font_system, cache_key);
+                }
+            })
+        });
+        group.finish();
+    }
+
+    #[cfg(feature = "raster")]
+    criterion_group!(
+        benches,
+        bench_short_labels,
+        bench_long_article,
+        bench_measure,
+        bench_rasterize
+    );
+    #[cfg(not(feature = "raster"))]
+    criterion_group!(
+        benches,
+        bench_short_labels,
+        bench_long_article,
+        bench_measure
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(native::benches);
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,91 @@
+//! Raised and lowered spans in rich text.
+//!
+//! A span's baseline shift moves its glyphs up or down after line breaking,
+//! so it never changes where lines wrap. The `super` and `sub` presets come
+//! from the font's OS/2 superscript and subscript offsets, in the span's
+//! unscaled size as in CSS. Lines grow to fit shifted glyphs, and every
+//! shift is reported per glyph for hosts placing selection boxes.
+
+use cosmic_text::fontdb::ID;
+use cosmic_text::{FontSystem, Weight};
+use serde::{Deserialize, Serialize};
+use skrifa::instance::{LocationRef, Size};
+use skrifa::raw::TableProvider;
+use skrifa::{FontRef, MetadataProvider};
+
+/// Superscript offset for fonts without an OS/2 table, in ems.
+const FALLBACK_SUPERSCRIPT: f32 = 0.34;
+/// Subscript offset for fonts without an OS/2 table, in ems, downward.
+const FALLBACK_SUBSCRIPT: f32 = 0.2;
+
+/// How far `RichTextSpan::baseline_shift` moves a span: the font's
+/// superscript or subscript position, or pixels with positive values
+/// raising the text.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BaselineShift {
+    Super,
+    Sub,
+    #[serde(untagged)]
+    Px(f32),
+}
+
+/// A face's vertical metrics, in ems. Descent is positive below the
+/// baseline, and the offsets positive in the direction they move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct FaceExtents {
+    pub(crate) ascent: f32,
+    pub(crate) descent: f32,
+    superscript: f32,
+    subscript: f32,
+}
+
+impl FaceExtents {
+    pub(crate) fn read(font_system: &mut FontSystem, id: ID, weight: Weight) -> Self {
+        let index = font_system.db().face(id).map_or(0, |face| face.index);
+        let font = font_system.get_font(id, weight);
+        let face = font
+            .as_ref()
+            .and_then(|font| FontRef::from_index(font.data(), index).ok());
+        let Some(face) = face else {
+            return Self {
+                ascent: 0.0,
+                descent: 0.0,
+                superscript: FALLBACK_SUPERSCRIPT,
+                subscript: FALLBACK_SUBSCRIPT,
+            };
+        };
+        let metrics = face.metrics(Size::unscaled(), LocationRef::default());
+        let units = f32::from(metrics.units_per_em.max(1));
+        let offsets = face.os2().ok().map(|os2| {
+            (
+                f32::from(os2.y_superscript_y_offset()) / units,
+                f32::from(os2.y_subscript_y_offset()) / units,
+            )
+        });
+        // Zero offsets are unset in practice, not a request for no shift
+        let (superscript, subscript) = match offsets {
+            Some((superscript, subscript)) if superscript > 0.0 && subscript > 0.0 => {
+                (superscript, subscript)
+            }
+            _ => (FALLBACK_SUPERSCRIPT, FALLBACK_SUBSCRIPT),
+        };
+        Self {
+            ascent: metrics.ascent / units,
+            descent: -metrics.descent / units,
+            superscript,
+            subscript,
+        }
+    }
+
+    /// The shift in pixels, positive upward, for text whose unscaled size
+    /// is `font_size`. Pixel shifts that are not finite are dropped.
+    pub(crate) fn resolve(&self, shift: BaselineShift, font_size: f32) -> f32 {
+        match shift {
+            BaselineShift::Super => self.superscript * font_size,
+            BaselineShift::Sub => -self.subscript * font_size,
+            BaselineShift::Px(px) if px.is_finite() => px,
+            BaselineShift::Px(_) => 0.0,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Why laid out lines end where they do.
+//!
+//! cosmic-text does not record why it broke a line, so the reason is read
+//! back from the lines: a line followed by another paragraph ended at a
+//! newline, and one followed by more of its paragraph broke either at a
+//! Unicode line break opportunity, because the text was too wide, or inside
+//! a word that did not fit on a line of its own.
+
+use cosmic_text::{LayoutRun, Wrap};
+use serde::{Deserialize, Serialize};
+use unicode_linebreak::linebreaks;
+
+/// Where lines may break when text is too wide, for `LayoutOptions::wrap`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WrapMode {
+    /// Only at line break opportunities, so an overlong word overflows
+    #[default]
+    Word,
+    /// At line break opportunities, and between graphemes of a word too
+    /// long for a line of its own
+    WordOrGlyph,
+    /// Between any graphemes
+    Glyph,
+}
+
+impl WrapMode {
+    pub(crate) fn to_cosmic(self) -> Wrap {
+        match self {
+            WrapMode::Word => Wrap::Word,
+            WrapMode::WordOrGlyph => Wrap::WordOrGlyph,
+            WrapMode::Glyph => Wrap::Glyph,
+        }
+    }
+}
+
+/// Why a line ends, for `LayoutLine::break_reason`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BreakReason {
+    /// The next word did not fit, so the line broke at an opportunity
+    Width,
+    /// The paragraph ended
+    Newline,
+    /// A word did not fit on a line of its own and was broken inside
+    GraphemeForced,
+    /// Lines past `LayoutOptions::max_lines` were dropped
+    MaxLines,
+}
+
+/// Where a paragraph may break: offsets after which a line can end, short
+/// of the paragraph's end.
+pub(crate) fn opportunities(text: &str) -> Vec<usize> {
+    linebreaks(text)
+        .map(|(offset, _)| offset)
+        .filter(|offset| *offset < text.len())
+        .collect()
+}
+
+/// The offset in its paragraph where the text of a line starts.
+pub(crate) fn line_start(run: &LayoutRun) -> Option<usize> {
+    run.glyphs.iter().map(|glyph| glyph.start).min()
+}
+
+/// Why `run` ends and where, as an offset in its paragraph's shaped text,
+/// given the line after it and its paragraph's `opportunities`. None for
+/// the last line.
+pub(crate) fn line_break(
+    run: &LayoutRun,
+    next: Option<&LayoutRun>,
+    opportunities: &[usize],
+) -> Option<(BreakReason, usize)> {
+    let next = next?;
+    if next.line_i != run.line_i {
+        return Some((BreakReason::Newline, run.text.len()));
+    }
+    let offset = line_start(next)?;
+    let reason = match opportunities.binary_search(&offset) {
+        Ok(_) => BreakReason::Width,
+        Err(_) => BreakReason::GraphemeForced,
+    };
+    Some((reason, offset))
+}
+
+/// The opportunities inside `run`, which ends at `end`, that layout passed
+/// over.
+pub(crate) fn passed_over<'a>(
+    run: &LayoutRun,
+    end: usize,
+    opportunities: &'a [usize],
+) -> impl Iterator<Item = usize> + 'a {
+    let start = line_start(run).unwrap_or(0);
+    opportunities
+        .iter()
+        .copied()
+        .filter(move |offset| *offset > start && *offset < end)
+}
@@ -0,0 +1,1207 @@
+//! A Brotli decoder (RFC 7932), for the compressed tables of WOFF2 fonts.
+//!
+//! WOFF2 decompresses a font in one piece, so the whole stream is decoded
+//! into memory: there is no streaming state, and backward references read
+//! the output directly instead of a ring buffer. Prefix codes are decoded
+//! a bit at a time, which is slower than table lookup but keeps the decoder
+//! small; a font is decoded once, when it is registered.
+//!
+//! The static dictionary is RFC 7932's, 122,784 bytes, and accounts for
+//! most of what the decoder adds to a build.
+
+use std::fmt;
+
+/// RFC 7932 Appendix A
+static DICTIONARY: &[u8; 122_784] = include_bytes!("brotli_dictionary.bin");
+
+/// Base 2 logarithm of the number of dictionary words of each length
+const DICTIONARY_SIZE_BITS: [u8; 25] = [
+    0, 0, 0, 0, 10, 10, 11, 11, 10, 10, 10, 10, 10, 9, 9, 8, 7, 7, 8, 7, 7, 6, 6, 5, 5,
+];
+
+/// Where the dictionary words of each length start
+const DICTIONARY_OFFSETS: [usize; 25] = {
+    let mut offsets = [0; 25];
+    let mut length = 1;
+    while length < 25 {
+        let bits = DICTIONARY_SIZE_BITS[length - 1];
+        let words = if bits == 0 { 0 } else { 1 << bits };
+        offsets[length] = offsets[length - 1] + (length - 1) * words;
+        length += 1;
+    }
+    offsets
+};
+
+/// Why a stream did not decode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct BrotliError(&'static str);
+
+impl fmt::Display for BrotliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+type Result<T> = std::result::Result<T, BrotliError>;
+
+/// Decode a whole Brotli stream, failing if it would produce more than
+/// `max_len` bytes.
+pub(crate) fn decompress(input: &[u8], max_len: usize) -> Result<Vec<u8>> {
+    let mut bits = BitReader {
+        data: input,
+        bit: 0,
+    };
+    let window = (1usize << window_bits(&mut bits)?) - 16;
+    let mut out = Vec::with_capacity(max_len);
+    let mut distances = [16, 15, 11, 4];
+    loop {
+        let last = bits.read(1)? == 1;
+        if last && bits.read(1)? == 1 {
+            return Ok(out);
+        }
+        let nibbles = match bits.read(2)? {
+            3 => 0,
+            n => n + 4,
+        };
+        if nibbles == 0 {
+            if last {
+                return Err(BrotliError("metadata in the last meta-block"));
+            }
+            skip_metadata(&mut bits)?;
+            continue;
+        }
+        let mut len = 0usize;
+        for i in 0..nibbles {
+            let nibble = bits.read(4)? as usize;
+            if i + 1 == nibbles && nibbles > 4 && nibble == 0 {
+                return Err(BrotliError("meta-block length has a leading zero nibble"));
+            }
+            len |= nibble << (4 * i);
+        }
+        let len = len + 1;
+        if out.len() + len > max_len {
+            return Err(BrotliError("output is longer than expected"));
+        }
+        if !last && bits.read(1)? == 1 {
+            bits.align()?;
+            out.extend_from_slice(bits.take_bytes(len)?);
+        } else {
+            MetaBlock::read(&mut bits)?.decode(&mut bits, &mut out, len, window, &mut distances)?;
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// The sliding window size, WBITS, from the stream header.
+fn window_bits(bits: &mut BitReader) -> Result<u32> {
+    if bits.read(1)? == 0 {
+        return Ok(16);
+    }
+    match bits.read(3)? {
+        0 => match bits.read(3)? {
+            0 => Ok(17),
+            1 => Err(BrotliError("large window streams are not supported")),
+            n => Ok(8 + n),
+        },
+        n => Ok(17 + n),
+    }
+}
+
+fn skip_metadata(bits: &mut BitReader) -> Result<()> {
+    if bits.read(1)? != 0 {
+        return Err(BrotliError("reserved bit set"));
+    }
+    let skip_bytes = bits.read(2)?;
+    let mut skip = 0usize;
+    for i in 0..skip_bytes {
+        let byte = bits.read(8)? as usize;
+        if i + 1 == skip_bytes && skip_bytes > 1 && byte == 0 {
+            return Err(BrotliError("metadata length has a leading zero byte"));
+        }
+        skip |= byte << (8 * i);
+    }
+    bits.align()?;
+    if skip_bytes > 0 {
+        bits.take_bytes(skip + 1)?;
+    }
+    Ok(())
+}
+
+/// Reads bits least significant first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl BitReader<'_> {
+    /// The next `n` bits, at most 24, zero past the end.
+    fn peek(&self, n: u32) -> u32 {
+        let start = self.bit >> 3;
+        let mut word = 0u64;
+        for (i, &byte) in self.data.iter().skip(start).take(4).enumerate() {
+            word |= u64::from(byte) << (8 * i);
+        }
+        ((word >> (self.bit & 7)) & ((1u64 << n) - 1)) as u32
+    }
+
+    fn skip(&mut self, n: u32) -> Result<()> {
+        if self.bit + n as usize > self.data.len() * 8 {
+            return Err(BrotliError("stream is truncated"));
+        }
+        self.bit += n as usize;
+        Ok(())
+    }
+
+    fn read(&mut self, n: u32) -> Result<u32> {
+        let value = self.peek(n);
+        self.skip(n)?;
+        Ok(value)
+    }
+
+    /// Skip to the next byte boundary, over padding that must be zero.
+    fn align(&mut self) -> Result<()> {
+        let padding = (8 - (self.bit & 7) as u32) & 7;
+        if self.read(padding)? != 0 {
+            return Err(BrotliError("padding bits are not zero"));
+        }
+        Ok(())
+    }
+
+    /// `len` whole bytes, after `align`.
+    fn take_bytes(&mut self, len: usize) -> Result<&[u8]> {
+        let start = self.bit >> 3;
+        let bytes = self
+            .data
+            .get(start..start + len)
+            .ok_or(BrotliError("stream is truncated"))?;
+        self.bit += len * 8;
+        Ok(bytes)
+    }
+}
+
+/// A canonical prefix code, decoded one bit at a time.
+struct PrefixCode {
+    /// Number of codes of each length
+    counts: [u16; 16],
+    /// Symbols ordered by code length, then value
+    symbols: Vec<u16>,
+}
+
+impl PrefixCode {
+    fn from_lengths(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols = Vec::with_capacity(lengths.len());
+        for length in 1..16 {
+            symbols.extend(
+                (0..lengths.len())
+                    .filter(|&symbol| lengths[symbol] == length)
+                    .map(|symbol| symbol as u16),
+            );
+        }
+        Self { counts, symbols }
+    }
+
+    /// A code with one symbol, which takes no bits.
+    fn single(symbol: u16) -> Self {
+        Self {
+            counts: [0; 16],
+            symbols: vec![symbol],
+        }
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Result<u16> {
+        if self.symbols.len() == 1 {
+            return Ok(self.symbols[0]);
+        }
+        // As in zlib's puff: codes of each length follow those of the
+        // length before, so a code is found by counting
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= bits.read(1)? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(BrotliError("invalid prefix code"))
+    }
+
+    /// Read a prefix code over `alphabet_size` symbols.
+    fn read(bits: &mut BitReader, alphabet_size: usize) -> Result<Self> {
+        let skip = bits.read(2)?;
+        if skip == 1 {
+            return Self::read_simple(bits, alphabet_size);
+        }
+
+        // Code lengths of the code length alphabet, in this order, each with
+        // a fixed variable length code
+        const ORDER: [usize; 18] = [1, 2, 3, 4, 0, 5, 17, 6, 16, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+        const FIXED_LENGTH: [u8; 16] = [2, 2, 2, 3, 2, 2, 2, 4, 2, 2, 2, 3, 2, 2, 2, 4];
+        const FIXED_VALUE: [u8; 16] = [0, 4, 3, 2, 0, 4, 3, 1, 0, 4, 3, 2, 0, 4, 3, 5];
+        let mut length_lengths = [0u8; 18];
+        let mut space = 32i32;
+        let mut codes = 0;
+        for &symbol in &ORDER[skip as usize..] {
+            let peeked = bits.peek(4) as usize;
+            bits.skip(u32::from(FIXED_LENGTH[peeked]))?;
+            let length = FIXED_VALUE[peeked];
+            length_lengths[symbol] = length;
+            if length != 0 {
+                space -= 32 >> length;
+                codes += 1;
+                if space <= 0 {
+                    break;
+                }
+            }
+        }
+        if codes != 1 && space != 0 {
+            return Err(BrotliError("incomplete code length code"));
+        }
+        let length_code = if codes == 1 {
+            let symbol = length_lengths.iter().position(|&length| length != 0);
+            Self::single(symbol.unwrap_or_default() as u16)
+        } else {
+            Self::from_lengths(&length_lengths)
+        };
+
+        let mut lengths = vec![0u8; alphabet_size];
+        let mut symbol = 0;
+        let mut previous = 8u8;
+        let (mut repeat, mut repeat_length) = (0usize, 0u8);
+        let mut space = 32768i32;
+        while symbol < alphabet_size && space > 0 {
+            let code = length_code.decode(bits)?;
+            if code < 16 {
+                repeat = 0;
+                lengths[symbol] = code as u8;
+                symbol += 1;
+                if code != 0 {
+                    previous = code as u8;
+                    space -= 32768 >> code;
+                }
+                continue;
+            }
+            let (extra, length) = if code == 16 { (2, previous) } else { (3, 0) };
+            if repeat_length != length {
+                repeat = 0;
+                repeat_length = length;
+            }
+            let old_repeat = repeat;
+            if repeat > 0 {
+                repeat = (repeat - 2) << extra;
+            }
+            repeat += bits.read(extra)? as usize + 3;
+            let added = repeat - old_repeat;
+            if symbol + added > alphabet_size {
+                return Err(BrotliError("code lengths overrun the alphabet"));
+            }
+            lengths[symbol..symbol + added].fill(length);
+            symbol += added;
+            if length != 0 {
+                space -= (added as i32) * (32768 >> length);
+            }
+        }
+        if space != 0 {
+            return Err(BrotliError("incomplete prefix code"));
+        }
+        Ok(Self::from_lengths(&lengths))
+    }
+
+    fn read_simple(bits: &mut BitReader, alphabet_size: usize) -> Result<Self> {
+        let alphabet_bits = usize::BITS - (alphabet_size - 1).leading_zeros();
+        let count = bits.read(2)? as usize + 1;
+        let mut symbols = [0u16; 4];
+        for i in 0..count {
+            let symbol = bits.read(alphabet_bits)? as u16;
+            if usize::from(symbol) >= alphabet_size || symbols[..i].contains(&symbol) {
+                return Err(BrotliError("invalid simple prefix code"));
+            }
+            symbols[i] = symbol;
+        }
+        let code_lengths: &[u8] = match count {
+            1 => return Ok(Self::single(symbols[0])),
+            2 => &[1, 1],
+            3 => &[1, 2, 2],
+            _ if bits.read(1)? == 1 => &[1, 2, 3, 3],
+            _ => &[2, 2, 2, 2],
+        };
+        let mut lengths = vec![0u8; alphabet_size];
+        for (&symbol, &length) in symbols.iter().zip(code_lengths) {
+            lengths[usize::from(symbol)] = length;
+        }
+        Ok(Self::from_lengths(&lengths))
+    }
+}
+
+/// Base and extra bits of the block count codes
+const BLOCK_COUNTS: [(u32, u32); 26] = [
+    (1, 2),
+    (5, 2),
+    (9, 2),
+    (13, 2),
+    (17, 3),
+    (25, 3),
+    (33, 3),
+    (41, 3),
+    (49, 4),
+    (65, 4),
+    (81, 4),
+    (97, 4),
+    (113, 5),
+    (145, 5),
+    (177, 5),
+    (209, 5),
+    (241, 6),
+    (305, 6),
+    (369, 7),
+    (497, 8),
+    (753, 9),
+    (1265, 10),
+    (2289, 11),
+    (4337, 12),
+    (8433, 13),
+    (16625, 24),
+];
+
+/// Base and extra bits of the insert length codes
+const INSERT_LENGTHS: [(u32, u32); 24] = [
+    (0, 0),
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 1),
+    (8, 1),
+    (10, 2),
+    (14, 2),
+    (18, 3),
+    (26, 3),
+    (34, 4),
+    (50, 4),
+    (66, 5),
+    (98, 5),
+    (130, 6),
+    (194, 7),
+    (322, 8),
+    (578, 9),
+    (1090, 10),
+    (2114, 12),
+    (6210, 14),
+    (22594, 24),
+];
+
+/// Base and extra bits of the copy length codes
+const COPY_LENGTHS: [(u32, u32); 24] = [
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 1),
+    (12, 1),
+    (14, 2),
+    (18, 2),
+    (22, 3),
+    (30, 3),
+    (38, 4),
+    (54, 4),
+    (70, 5),
+    (102, 5),
+    (134, 6),
+    (198, 7),
+    (326, 8),
+    (582, 9),
+    (1094, 10),
+    (2118, 24),
+];
+
+/// The block types of one category, literals, commands or distances, and
+/// the block being decoded.
+struct BlockTypes {
+    count: u32,
+    codes: Option<(PrefixCode, PrefixCode)>,
+    /// The last and second to last block types
+    current: u32,
+    previous: u32,
+    remaining: u32,
+}
+
+impl BlockTypes {
+    fn read(bits: &mut BitReader) -> Result<Self> {
+        let count = read_var_u8(bits)? + 1;
+        if count < 2 {
+            return Ok(Self {
+                count,
+                codes: None,
+                current: 0,
+                previous: 1,
+                remaining: u32::MAX,
+            });
+        }
+        let type_code = PrefixCode::read(bits, count as usize + 2)?;
+        let count_code = PrefixCode::read(bits, 26)?;
+        let remaining = read_block_count(bits, &count_code)?;
+        Ok(Self {
+            count,
+            codes: Some((type_code, count_code)),
+            current: 0,
+            previous: 1,
+            remaining,
+        })
+    }
+
+    /// Start the next block when this one is used up, and count one symbol
+    /// against it.
+    fn advance(&mut self, bits: &mut BitReader) -> Result<()> {
+        if self.remaining == 0 {
+            let Some((type_code, count_code)) = &self.codes else {
+                return Err(BrotliError("block count overrun"));
+            };
+            let next = match type_code.decode(bits)? {
+                0 => self.previous,
+                1 => self.current + 1,
+                code => u32::from(code) - 2,
+            } % self.count;
+            self.previous = self.current;
+            self.current = next;
+            self.remaining = read_block_count(bits, count_code)?;
+        }
+        self.remaining -= 1;
+        Ok(())
+    }
+}
+
+fn read_block_count(bits: &mut BitReader, code: &PrefixCode) -> Result<u32> {
+    let (base, extra) = BLOCK_COUNTS[usize::from(code.decode(bits)?)];
+    Ok(base + bits.read(extra)?)
+}
+
+/// RFC 7932's VarLenUint8: 0 to 255.
+fn read_var_u8(bits: &mut BitReader) -> Result<u32> {
+    if bits.read(1)? == 0 {
+        return Ok(0);
+    }
+    match bits.read(3)? {
+        0 => Ok(1),
+        n => Ok((1 << n) + bits.read(n)?),
+    }
+}
+
+/// Read a context map of `size` entries over `trees` prefix codes.
+fn read_context_map(bits: &mut BitReader, size: usize, trees: u32) -> Result<Vec<u8>> {
+    if trees < 2 {
+        return Ok(vec![0; size]);
+    }
+    let max_run = if bits.read(1)? == 1 {
+        bits.read(4)? + 1
+    } else {
+        0
+    };
+    let code = PrefixCode::read(bits, (trees + max_run) as usize)?;
+    let mut map = Vec::with_capacity(size);
+    while map.len() < size {
+        let symbol = u32::from(code.decode(bits)?);
+        if symbol == 0 {
+            map.push(0);
+        } else if symbol <= max_run {
+            let run = (1usize << symbol) + bits.read(symbol)? as usize;
+            if map.len() + run > size {
+                return Err(BrotliError("context map run overruns the map"));
+            }
+            map.resize(map.len() + run, 0);
+        } else {
+            map.push((symbol - max_run) as u8);
+        }
+    }
+    if bits.read(1)? == 1 {
+        inverse_move_to_front(&mut map);
+    }
+    Ok(map)
+}
+
+fn inverse_move_to_front(values: &mut [u8]) {
+    let mut order: [u8; 256] = std::array::from_fn(|i| i as u8);
+    for value in values {
+        let index = usize::from(*value);
+        let moved = order[index];
+        *value = moved;
+        order.copy_within(..index, 1);
+        order[0] = moved;
+    }
+}
+
+/// The codes and context maps of one compressed meta-block.
+struct MetaBlock {
+    literal_types: BlockTypes,
+    command_types: BlockTypes,
+    distance_types: BlockTypes,
+    postfix_bits: u32,
+    direct_codes: u32,
+    /// Context mode of each literal block type
+    context_modes: Vec<u8>,
+    literal_map: Vec<u8>,
+    distance_map: Vec<u8>,
+    literal_codes: Vec<PrefixCode>,
+    command_codes: Vec<PrefixCode>,
+    distance_codes: Vec<PrefixCode>,
+}
+
+impl MetaBlock {
+    fn read(bits: &mut BitReader) -> Result<Self> {
+        let literal_types = BlockTypes::read(bits)?;
+        let command_types = BlockTypes::read(bits)?;
+        let distance_types = BlockTypes::read(bits)?;
+        let postfix_bits = bits.read(2)?;
+        let direct_codes = bits.read(4)? << postfix_bits;
+        let context_modes = (0..literal_types.count)
+            .map(|_| bits.read(2).map(|mode| mode as u8))
+            .collect::<Result<Vec<_>>>()?;
+        let literal_trees = read_var_u8(bits)? + 1;
+        let literal_map = read_context_map(bits, 64 * literal_types.count as usize, literal_trees)?;
+        let distance_trees = read_var_u8(bits)? + 1;
+        let distance_map =
+            read_context_map(bits, 4 * distance_types.count as usize, distance_trees)?;
+        let codes = |bits: &mut BitReader, count: u32, alphabet_size: usize| {
+            (0..count)
+                .map(|_| PrefixCode::read(bits, alphabet_size))
+                .collect::<Result<Vec<_>>>()
+        };
+        let literal_codes = codes(bits, literal_trees, 256)?;
+        let command_codes = codes(bits, command_types.count, 704)?;
+        let distance_alphabet = 16 + direct_codes as usize + (48 << postfix_bits);
+        let distance_codes = codes(bits, distance_trees, distance_alphabet)?;
+        Ok(Self {
+            literal_types,
+            command_types,
+            distance_types,
+            postfix_bits,
+            direct_codes,
+            context_modes,
+            literal_map,
+            distance_map,
+            literal_codes,
+            command_codes,
+            distance_codes,
+        })
+    }
+
+    /// Decode `len` bytes onto `out`.
+    fn decode(
+        mut self,
+        bits: &mut BitReader,
+        out: &mut Vec<u8>,
+        len: usize,
+        window: usize,
+        distances: &mut [u32; 4],
+    ) -> Result<()> {
+        let end = out.len() + len;
+        while out.len() < end {
+            self.command_types.advance(bits)?;
+            let command = self.command_codes[self.command_types.current as usize].decode(bits)?;
+            let (insert, copy, implicit_distance) = read_command(bits, command)?;
+            if out.len() + insert > end {
+                return Err(BrotliError("literals overrun the meta-block"));
+            }
+            for _ in 0..insert {
+                self.literal_types.advance(bits)?;
+                let block_type = self.literal_types.current as usize;
+                let previous = out.last().copied().unwrap_or(0);
+                let before = out.len().checked_sub(2).map_or(0, |at| out[at]);
+                let context = literal_context(self.context_modes[block_type], previous, before);
+                let tree = self.literal_map[64 * block_type + usize::from(context)];
+                out.push(self.literal_codes[usize::from(tree)].decode(bits)? as u8);
+            }
+            if out.len() == end {
+                break;
+            }
+
+            let (distance, remember) = if implicit_distance {
+                (distances[3], false)
+            } else {
+                self.distance_types.advance(bits)?;
+                let context = copy.clamp(2, 5) - 2;
+                let tree = self.distance_map[4 * self.distance_types.current as usize + context];
+                let code = self.distance_codes[usize::from(tree)].decode(bits)?;
+                (self.distance(bits, u32::from(code), distances)?, code != 0)
+            };
+            let max_distance = out.len().min(window);
+            if distance as usize > max_distance {
+                let word_id = distance as usize - max_distance - 1;
+                dictionary_word(out, copy, word_id)?;
+                if out.len() > end {
+                    return Err(BrotliError("dictionary word overruns the meta-block"));
+                }
+                continue;
+            }
+            if out.len() + copy > end {
+                return Err(BrotliError("copy overruns the meta-block"));
+            }
+            let start = out.len() - distance as usize;
+            for i in 0..copy {
+                out.push(out[start + i]);
+            }
+            if remember {
+                distances.copy_within(1.., 0);
+                distances[3] = distance;
+            }
+        }
+        Ok(())
+    }
+
+    /// The distance a distance code stands for, relative to the recent
+    /// distances, last at the end.
+    fn distance(&self, bits: &mut BitReader, code: u32, recent: &[u32; 4]) -> Result<u32> {
+        let [fourth, third, second, last] = *recent;
+        let distance = match code {
+            0 => i64::from(last),
+            1 => i64::from(second),
+            2 => i64::from(third),
+            3 => i64::from(fourth),
+            4..=9 => {
+                let offset = i64::from((code - 4) / 2 + 1);
+                i64::from(last) + if code % 2 == 0 { -offset } else { offset }
+            }
+            10..=15 => {
+                let offset = i64::from((code - 10) / 2 + 1);
+                i64::from(second) + if code % 2 == 0 { -offset } else { offset }
+            }
+            _ if code < 16 + self.direct_codes => i64::from(code - 15),
+            _ => {
+                let code = code - self.direct_codes - 16;
+                let extra_bits = 1 + (code >> (self.postfix_bits + 1));
+                let extra = i64::from(bits.read(extra_bits)?);
+                let high = i64::from(code >> self.postfix_bits);
+                let low = i64::from(code & ((1 << self.postfix_bits) - 1));
+                let offset = ((2 + (high & 1)) << extra_bits) - 4;
+                ((offset + extra) << self.postfix_bits) + low + i64::from(self.direct_codes) + 1
+            }
+        };
+        if distance <= 0 || distance > i64::from(u32::MAX) {
+            return Err(BrotliError("invalid distance"));
+        }
+        Ok(distance as u32)
+    }
+}
+
+/// The insert length, copy length and whether the distance is implicitly
+/// the last one, from an insert-and-copy command.
+fn read_command(bits: &mut BitReader, command: u16) -> Result<(usize, usize, bool)> {
+    const INSERT_BASE: [u16; 9] = [0, 0, 8, 8, 0, 16, 8, 16, 16];
+    const COPY_BASE: [u16; 9] = [0, 8, 0, 8, 16, 0, 16, 8, 16];
+    let (insert_base, copy_base, implicit) = match command {
+        0..=63 => (0, 0, true),
+        64..=127 => (0, 8, true),
+        _ => {
+            let cell = usize::from((command - 128) >> 6);
+            (INSERT_BASE[cell], COPY_BASE[cell], false)
+        }
+    };
+    let insert_code = usize::from(insert_base + ((command >> 3) & 7));
+    let copy_code = usize::from(copy_base + (command & 7));
+    let (insert, insert_extra) = INSERT_LENGTHS[insert_code];
+    let insert = insert + bits.read(insert_extra)?;
+    let (copy, copy_extra) = COPY_LENGTHS[copy_code];
+    let copy = copy + bits.read(copy_extra)?;
+    Ok((insert as usize, copy as usize, implicit))
+}
+
+/// The context of the next literal from the two before it, in one of the
+/// four context modes.
+fn literal_context(mode: u8, previous: u8, before: u8) -> u8 {
+    match mode {
+        0 => previous & 0x3f,
+        1 => previous >> 2,
+        2 => utf8_context(previous) | utf8_context_before(before),
+        _ => (signed_context(previous) << 3) | signed_context(before),
+    }
+}
+
+/// RFC 7932 Lut0 for ASCII; bytes above it alternate by parity between
+/// continuation and lead bytes.
+fn utf8_context(byte: u8) -> u8 {
+    const ASCII: [u8; 128] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 4, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 8, 12, 16, 12, 12, 20, 12, 16, 24, 28, 12, 12, 32, 12, 36, 12, 44, 44, 44, 44, 44,
+        44, 44, 44, 44, 44, 32, 32, 24, 40, 28, 12, 12, 48, 52, 52, 52, 48, 52, 52, 52, 48, 52, 52,
+        52, 52, 52, 48, 52, 52, 52, 52, 52, 48, 52, 52, 52, 52, 52, 24, 12, 28, 12, 12, 12, 56, 60,
+        60, 60, 56, 60, 60, 60, 56, 60, 60, 60, 60, 60, 56, 60, 60, 60, 60, 60, 56, 60, 60, 60, 60,
+        60, 24, 12, 28, 12, 0,
+    ];
+    match byte {
+        0..=127 => ASCII[usize::from(byte)],
+        128..=191 => byte & 1,
+        _ => 2 + (byte & 1),
+    }
+}
+
+/// RFC 7932 Lut1: the class of the byte two back.
+fn utf8_context_before(byte: u8) -> u8 {
+    match byte {
+        b'0'..=b'9' | b'A'..=b'Z' => 2,
+        b'a'..=b'z' => 3,
+        33..=126 => 1,
+        224..=255 => 2,
+        _ => 0,
+    }
+}
+
+/// RFC 7932 Lut2
+fn signed_context(byte: u8) -> u8 {
+    match byte {
+        0 => 0,
+        1..=15 => 1,
+        16..=63 => 2,
+        64..=127 => 3,
+        128..=191 => 4,
+        192..=239 => 5,
+        240..=254 => 6,
+        255 => 7,
+    }
+}
+
+/// How a dictionary word is changed before it is output.
+#[derive(Clone, Copy)]
+enum WordTransform {
+    Identity,
+    OmitLast(usize),
+    OmitFirst(usize),
+    UppercaseFirst,
+    UppercaseAll,
+}
+
+/// Append a dictionary word of `len` bytes, transformed as `word_id` says.
+fn dictionary_word(out: &mut Vec<u8>, len: usize, word_id: usize) -> Result<()> {
+    let bits = DICTIONARY_SIZE_BITS
+        .get(len)
+        .copied()
+        .filter(|&bits| bits > 0)
+        .ok_or(BrotliError("invalid dictionary word length"))?;
+    let index = word_id & ((1 << bits) - 1);
+    let (prefix, transform, suffix) = *TRANSFORMS
+        .get(word_id >> bits)
+        .ok_or(BrotliError("invalid dictionary transform"))?;
+    let start = DICTIONARY_OFFSETS[len] + index * len;
+    let mut word = &DICTIONARY[start..start + len];
+
+    out.extend_from_slice(prefix);
+    match transform {
+        WordTransform::OmitLast(n) => word = &word[..len.saturating_sub(n)],
+        WordTransform::OmitFirst(n) => word = &word[n.min(len)..],
+        _ => {}
+    }
+    let word_start = out.len();
+    out.extend_from_slice(word);
+    match transform {
+        WordTransform::UppercaseFirst => {
+            uppercase(&mut out[word_start..]);
+        }
+        WordTransform::UppercaseAll => {
+            let mut at = word_start;
+            while at < out.len() {
+                at += uppercase(&mut out[at..]);
+            }
+        }
+        _ => {}
+    }
+    out.extend_from_slice(suffix);
+    Ok(())
+}
+
+/// Uppercase the character at the start of `word` the way RFC 7932 does,
+/// by flipping a bit rather than by Unicode, returning its length.
+fn uppercase(word: &mut [u8]) -> usize {
+    match word[0] {
+        b'a'..=b'z' => {
+            word[0] ^= 32;
+            1
+        }
+        0..=0xbf => 1,
+        0xc0..=0xdf => {
+            if let Some(byte) = word.get_mut(1) {
+                *byte ^= 32;
+            }
+            2
+        }
+        _ => {
+            if let Some(byte) = word.get_mut(2) {
+                *byte ^= 5;
+            }
+            3
+        }
+    }
+}
+
+/// RFC 7932 Appendix B: prefix, transform and suffix of each word transform
+#[rustfmt::skip]
+static TRANSFORMS: [(&[u8], WordTransform, &[u8]); 121] = {
+    use WordTransform::*;
+    [
+        (b"", Identity, b""),
+        (b"", Identity, b" "),
+        (b" ", Identity, b" "),
+        (b"", OmitFirst(1), b""),
+        (b"", UppercaseFirst, b" "),
+        (b"", Identity, b" the "),
+        (b" ", Identity, b""),
+        (b"s ", Identity, b" "),
+        (b"", Identity, b" of "),
+        (b"", UppercaseFirst, b""),
+        (b"", Identity, b" and "),
+        (b"", OmitFirst(2), b""),
+        (b"", OmitLast(1), b""),
+        (b", ", Identity, b" "),
+        (b"", Identity, b", "),
+        (b" ", UppercaseFirst, b" "),
+        (b"", Identity, b" in "),
+        (b"", Identity, b" to "),
+        (b"e ", Identity, b" "),
+        (b"", Identity, b"\""),
+        (b"", Identity, b"."),
+        (b"", Identity, b"\">"),
+        (b"", Identity, b"\n"),
+        (b"", OmitLast(3), b""),
+        (b"", Identity, b"]"),
+        (b"", Identity, b" for "),
+        (b"", OmitFirst(3), b""),
+        (b"", OmitLast(2), b""),
+        (b"", Identity, b" a "),
+        (b"", Identity, b" that "),
+        (b" ", UppercaseFirst, b""),
+        (b"", Identity, b". "),
+        (b".", Identity, b""),
+        (b" ", Identity, b", "),
+        (b"", OmitFirst(4), b""),
+        (b"", Identity, b" with "),
+        (b"", Identity, b"'"),
+        (b"", Identity, b" from "),
+        (b"", Identity, b" by "),
+        (b"", OmitFirst(5), b""),
+        (b"", OmitFirst(6), b""),
+        (b" the ", Identity, b""),
+        (b"", OmitLast(4), b""),
+        (b"", Identity, b". The "),
+        (b"", UppercaseAll, b""),
+        (b"", Identity, b" on "),
+        (b"", Identity, b" as "),
+        (b"", Identity, b" is "),
+        (b"", OmitLast(7), b""),
+        (b"", OmitLast(1), b"ing "),
+        (b"", Identity, b"\n\t"),
+        (b"", Identity, b":"),
+        (b" ", Identity, b". "),
+        (b"", Identity, b"ed "),
+        (b"", OmitFirst(9), b""),
+        (b"", OmitFirst(7), b""),
+        (b"", OmitLast(6), b""),
+        (b"", Identity, b"("),
+        (b"", UppercaseFirst, b", "),
+        (b"", OmitLast(8), b""),
+        (b"", Identity, b" at "),
+        (b"", Identity, b"ly "),
+        (b" the ", Identity, b" of "),
+        (b"", OmitLast(5), b""),
+        (b"", OmitLast(9), b""),
+        (b" ", UppercaseFirst, b", "),
+        (b"", UppercaseFirst, b"\""),
+        (b".", Identity, b"("),
+        (b"", UppercaseAll, b" "),
+        (b"", UppercaseFirst, b"\">"),
+        (b"", Identity, b"=\""),
+        (b" ", Identity, b"."),
+        (b".com/", Identity, b""),
+        (b" the ", Identity, b" of the "),
+        (b"", UppercaseFirst, b"'"),
+        (b"", Identity, b". This "),
+        (b"", Identity, b","),
+        (b".", Identity, b" "),
+        (b"", UppercaseFirst, b"("),
+        (b"", UppercaseFirst, b"."),
+        (b"", Identity, b" not "),
+        (b" ", Identity, b"=\""),
+        (b"", Identity, b"er "),
+        (b" ", UppercaseAll, b" "),
+        (b"", Identity, b"al "),
+        (b" ", UppercaseAll, b""),
+        (b"", Identity, b"='"),
+        (b"", UppercaseAll, b"\""),
+        (b"", UppercaseFirst, b". "),
+        (b" ", Identity, b"("),
+        (b"", Identity, b"ful "),
+        (b" ", UppercaseFirst, b". "),
+        (b"", Identity, b"ive "),
+        (b"", Identity, b"less "),
+        (b"", UppercaseAll, b"'"),
+        (b"", Identity, b"est "),
+        (b" ", UppercaseFirst, b"."),
+        (b"", UppercaseAll, b"\">"),
+        (b" ", Identity, b"='"),
+        (b"", UppercaseFirst, b","),
+        (b"", Identity, b"ize "),
+        (b"", UppercaseAll, b"."),
+        (b"\xc2\xa0", Identity, b""),
+        (b" ", Identity, b","),
+        (b"", UppercaseFirst, b"=\""),
+        (b"", UppercaseAll, b"=\""),
+        (b"", Identity, b"ous "),
+        (b"", UppercaseAll, b", "),
+        (b"", UppercaseFirst, b"='"),
+        (b" ", UppercaseFirst, b","),
+        (b" ", UppercaseAll, b"=\""),
+        (b" ", UppercaseAll, b", "),
+        (b"", UppercaseAll, b","),
+        (b"", UppercaseAll, b"("),
+        (b"", UppercaseAll, b". "),
+        (b" ", UppercaseAll, b"."),
+        (b"", UppercaseAll, b"='"),
+        (b" ", UppercaseAll, b". "),
+        (b" ", UppercaseFirst, b"=\""),
+        (b" ", UppercaseAll, b"='"),
+        (b" ", UppercaseFirst, b"='"),
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes bits least significant first, to build streams by hand.
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bit: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, n: u32, value: u32) {
+            for i in 0..n {
+                if self.bit % 8 == 0 {
+                    self.bytes.push(0);
+                }
+                if (value >> i) & 1 == 1 {
+                    *self.bytes.last_mut().unwrap() |= 1 << (self.bit % 8);
+                }
+                self.bit += 1;
+            }
+        }
+
+        fn align(&mut self) {
+            self.bit = self.bit.next_multiple_of(8);
+        }
+
+        fn bytes(&mut self, bytes: &[u8]) {
+            self.align();
+            self.bytes.extend_from_slice(bytes);
+            self.bit += 8 * bytes.len();
+        }
+
+        /// A meta-block header of `len` bytes, with 4 nibbles of length.
+        fn header(&mut self, len: usize, last: bool, uncompressed: bool) {
+            self.write(1, u32::from(last));
+            if last {
+                self.write(1, 0);
+            }
+            self.write(2, 0);
+            self.write(16, len as u32 - 1);
+            if !last {
+                self.write(1, u32::from(uncompressed));
+            }
+        }
+
+        /// A simple prefix code over `symbols`, in an alphabet of
+        /// `alphabet_bits` bit symbols.
+        fn simple_code(&mut self, alphabet_bits: u32, symbols: &[u16]) {
+            self.write(2, 1);
+            self.write(2, symbols.len() as u32 - 1);
+            for &symbol in symbols {
+                self.write(alphabet_bits, u32::from(symbol));
+            }
+        }
+
+        /// The header of a compressed meta-block with one block type of
+        /// each kind, no context modeling and one code each for literals,
+        /// commands and distances. `literals` holds at most two symbols.
+        fn compressed_block(&mut self, literals: &[u16], command: u16, distance_code: u16) {
+            // One block type each, NPOSTFIX, NDIRECT, the literal context
+            // mode, then one literal and one distance tree
+            for bits in [1, 1, 1, 2, 4, 2, 1, 1] {
+                self.write(bits, 0);
+            }
+            self.simple_code(8, literals);
+            self.simple_code(10, &[command]);
+            self.simple_code(6, &[distance_code]);
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            self.write(1, 1);
+            self.write(1, 1);
+            self.bytes
+        }
+    }
+
+    /// The distance code and extra bits of `distance` with no postfix or
+    /// direct codes.
+    fn distance_code(distance: u32) -> (u16, u32, u32) {
+        // Codes from 16 on stand for `((2 + high) << extra_bits) - 3 + extra`
+        let value = distance + 3;
+        let extra_bits = 30 - value.leading_zeros();
+        let high = (value >> extra_bits) & 1;
+        let extra = value & ((1 << extra_bits) - 1);
+        (16 + (2 * (extra_bits - 1) + high) as u16, extra_bits, extra)
+    }
+
+    /// A stream of one dictionary reference to a `len` byte word, after
+    /// the literal `before`, which decodes to `expected`.
+    fn dictionary_stream(before: &[u8], len: usize, word_id: usize, expected: &[u8]) -> Vec<u8> {
+        // Insert code `before.len()` and copy code `len - 2` need no extra bits
+        let command = 128 + ((before.len() as u16) << 3) + (len as u16 - 2);
+        let (code, extra_bits, extra) = distance_code((before.len() + word_id + 1) as u32);
+        let mut stream = BitWriter::default();
+        stream.write(1, 0);
+        stream.header(expected.len(), false, false);
+        // Distinct literals, each coded by whether it is the larger
+        let mut literals: Vec<u16> = before.iter().map(|&byte| u16::from(byte)).collect();
+        literals.sort_unstable();
+        literals.dedup();
+        if literals.is_empty() {
+            literals.push(0);
+        }
+        stream.compressed_block(&literals, command, code);
+        if literals.len() > 1 {
+            for &byte in before {
+                stream.write(1, u32::from(u16::from(byte) == literals[1]));
+            }
+        }
+        stream.write(extra_bits, extra);
+        stream.finish()
+    }
+
+    #[test]
+    fn test_empty_stream() {
+        assert_eq!(decompress(&[0x06], 0), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_uncompressed_meta_block() {
+        // x.compressed from the reference decoder's test data
+        assert_eq!(
+            decompress(&[0x0b, 0x00, 0x80, 0x58, 0x03], 1),
+            Ok(b"X".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_uncompressed_meta_blocks_around_metadata() {
+        let mut stream = BitWriter::default();
+        stream.write(1, 0);
+        stream.header(5, false, true);
+        stream.bytes(b"hello");
+        // Metadata: MNIBBLES of 0, the reserved bit, one length byte
+        stream.write(1, 0);
+        stream.write(2, 3);
+        stream.write(1, 0);
+        stream.write(2, 1);
+        stream.write(8, 2);
+        stream.bytes(b"xyz");
+        stream.header(6, false, true);
+        stream.bytes(b" world");
+        let stream = stream.finish();
+        assert_eq!(decompress(&stream, 11), Ok(b"hello world".to_vec()));
+        assert_eq!(
+            decompress(&stream, 10),
+            Err(BrotliError("output is longer than expected"))
+        );
+        assert_eq!(
+            decompress(&stream[..stream.len() - 1], 11),
+            Err(BrotliError("stream is truncated"))
+        );
+    }
+
+    #[test]
+    fn test_backward_reference_overlaps_its_own_output() {
+        // Insert "ab", then copy 6 bytes from 2 back
+        let (code, extra_bits, extra) = distance_code(2);
+        let mut stream = BitWriter::default();
+        stream.write(1, 0);
+        stream.header(8, false, false);
+        stream.compressed_block(
+            &[u16::from(b'a'), u16::from(b'b')],
+            128 + (2 << 3) + 4,
+            code,
+        );
+        stream.write(1, 0);
+        stream.write(1, 1);
+        stream.write(extra_bits, extra);
+        assert_eq!(decompress(&stream.finish(), 8), Ok(b"abababab".to_vec()));
+    }
+
+    #[test]
+    fn test_dictionary_reference() {
+        assert_eq!(
+            decompress(&dictionary_stream(b"", 4, 0, b"time"), 4),
+            Ok(b"time".to_vec())
+        );
+        assert_eq!(
+            decompress(&dictionary_stream(b"", 4, 1, b"down"), 4),
+            Ok(b"down".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_dictionary_reference_counts_past_the_output() {
+        let stream = dictionary_stream(b"ab", 4, 1, b"abdown");
+        assert_eq!(decompress(&stream, 6), Ok(b"abdown".to_vec()));
+    }
+
+    #[test]
+    fn test_dictionary_transform_uppercase_first() {
+        let stream = dictionary_stream(b"", 4, 9 << 10, b"Time");
+        assert_eq!(decompress(&stream, 4), Ok(b"Time".to_vec()));
+    }
+
+    #[test]
+    fn test_dictionary_transform_uppercase_all() {
+        let stream = dictionary_stream(b"", 4, 44 << 10, b"TIME");
+        assert_eq!(decompress(&stream, 4), Ok(b"TIME".to_vec()));
+    }
+
+    #[test]
+    fn test_dictionary_transform_omit_first() {
+        let stream = dictionary_stream(b"", 4, 3 << 10, b"ime");
+        assert_eq!(decompress(&stream, 3), Ok(b"ime".to_vec()));
+    }
+
+    #[test]
+    fn test_dictionary_transform_omit_last_with_suffix() {
+        let stream = dictionary_stream(b"", 4, 49 << 10, b"timing ");
+        assert_eq!(decompress(&stream, 7), Ok(b"timing ".to_vec()));
+    }
+
+    #[test]
+    fn test_dictionary_transform_prefix_and_suffix() {
+        let expected = b" the time of the ";
+        let stream = dictionary_stream(b"", 4, 73 << 10, expected);
+        assert_eq!(decompress(&stream, expected.len()), Ok(expected.to_vec()));
+    }
+
+    #[test]
+    fn test_dictionary_transform_out_of_range() {
+        let stream = dictionary_stream(b"", 4, 121 << 10, b"time");
+        assert_eq!(
+            decompress(&stream, 4),
+            Err(BrotliError("invalid dictionary transform"))
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Byte-accounted LRU cache shared by the shaped-line and glyph caches.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+struct Entry<V> {
+    value: V,
+    bytes: usize,
+    last_used: u64,
+}
+
+/// A map that tracks the approximate heap size of its values and when each
+/// was last read, so callers can trim it to a byte target in LRU order.
+pub(crate) struct LruCache<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    bytes: usize,
+    tick: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            bytes: 0,
+            tick: 0,
+        }
+    }
+
+    pub(crate) fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.tick;
+        Some(&entry.value)
+    }
+
+    pub(crate) fn insert(&mut self, key: K, value: V, bytes: usize) {
+        self.tick += 1;
+        let entry = Entry {
+            value,
+            bytes,
+            last_used: self.tick,
+        };
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.bytes -= old.bytes;
+        }
+        self.bytes += bytes;
+    }
+
+    /// Evict least-recently-used entries until at most `target` bytes remain.
+    /// Returns the evicted keys, oldest first.
+    pub(crate) fn trim_to(&mut self, target: usize) -> Vec<K> {
+        if self.bytes <= target {
+            return Vec::new();
+        }
+        let mut by_age: Vec<(u64, K)> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.last_used, key.clone()))
+            .collect();
+        by_age.sort_unstable_by_key(|(last_used, _)| *last_used);
+
+        let mut evicted = Vec::new();
+        for (_, key) in by_age {
+            if self.bytes <= target {
+                break;
+            }
+            if let Some(entry) = self.entries.remove(&key) {
+                self.bytes -= entry.bytes;
+                evicted.push(key);
+            }
+        }
+        evicted
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
@@ -0,0 +1,209 @@
+//! Locale-sensitive case transforms, as `PreparedText` edits.
+//!
+//! Changing case can change a text's length: `ß` uppercases to `SS`, and
+//! `İ` lowercases to `i` followed by a combining dot, so offsets into the
+//! transformed text do not index the original. Each character whose case
+//! changes becomes one edit, and offsets map back as they do for collapsed
+//! whitespace.
+//!
+//! Mappings are Rust's full Unicode case mappings, plus the rules that
+//! depend on language: Turkish and Azerbaijani dotted and dotless i, Dutch
+//! `ij` titled as a pair, and Greek final sigma, which applies in every
+//! language.
+
+use crate::prepared::Edit;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A case transform, after CSS `text-transform`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextCase {
+    Upper,
+    Lower,
+    /// The first letter of each word in title case, the rest in lowercase
+    Title,
+}
+
+/// A stretch of the original text and the transformed text it became.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaseMapping {
+    pub original_start: usize,
+    pub original_end: usize,
+    pub transformed_start: usize,
+    pub transformed_end: usize,
+    /// Whether every offset in the stretch maps to the same distance into
+    /// the other text. Otherwise the stretch is one character whose mapping
+    /// changed its length or character count, such as `ß` to `SS`, and only
+    /// its ends correspond.
+    pub one_to_one: bool,
+}
+
+/// Text with a case transform applied, from `transform_text_case`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaseTransform {
+    pub text: String,
+    /// Stretches covering both texts in order, merged where they map one
+    /// to one.
+    pub mappings: Vec<CaseMapping>,
+}
+
+impl CaseTransform {
+    fn push(&mut self, original_start: usize, original: &str, transformed: &str, one_to_one: bool) {
+        if original.is_empty() && transformed.is_empty() {
+            return;
+        }
+        let transformed_start = self.text.len();
+        self.text.push_str(transformed);
+        let mapping = CaseMapping {
+            original_start,
+            original_end: original_start + original.len(),
+            transformed_start,
+            transformed_end: self.text.len(),
+            one_to_one,
+        };
+        match self.mappings.last_mut() {
+            Some(last) if one_to_one && last.one_to_one => {
+                last.original_end = mapping.original_end;
+                last.transformed_end = mapping.transformed_end;
+            }
+            _ => self.mappings.push(mapping),
+        }
+    }
+}
+
+/// Language-specific rules, from the primary subtag of a BCP 47 tag.
+#[derive(Clone, Copy, Default)]
+struct Rules {
+    /// Turkish and Azerbaijani: `i` pairs with `İ` and `ı` with `I`
+    turkic: bool,
+    /// Dutch: a word starting with `ij` titles both letters
+    dutch: bool,
+}
+
+impl Rules {
+    fn new(language: Option<&str>) -> Self {
+        let primary = language
+            .and_then(|tag| tag.split(['-', '_']).next())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Self {
+            turkic: matches!(primary.as_str(), "tr" | "az"),
+            dutch: primary == "nl",
+        }
+    }
+
+    fn upper(self, c: char, out: &mut String) {
+        match c {
+            'i' if self.turkic => out.push('\u{130}'),
+            _ => out.extend(c.to_uppercase()),
+        }
+    }
+
+    fn lower(self, c: char, prev: Option<char>, next: Option<char>, out: &mut String) {
+        match c {
+            'I' if self.turkic => out.push('\u{131}'),
+            '\u{130}' if self.turkic => out.push('i'),
+            // The dot of a decomposed dotted capital I is part of the i
+            '\u{307}' if self.turkic && prev == Some('I') => {}
+            'Σ' if prev.is_some_and(char::is_alphabetic)
+                && !next.is_some_and(char::is_alphabetic) =>
+            {
+                out.push('ς')
+            }
+            _ => out.extend(c.to_lowercase()),
+        }
+    }
+
+    /// Title case: the first character of the uppercase mapping and the
+    /// rest lowercased, so `ß` becomes `Ss` and `ﬁ` becomes `Fi`, except for
+    /// the Latin digraphs, which have title case forms of their own.
+    fn title(self, c: char, out: &mut String) {
+        let digraph = match c {
+            '\u{1C4}'..='\u{1C6}' => Some('\u{1C5}'),
+            '\u{1C7}'..='\u{1C9}' => Some('\u{1C8}'),
+            '\u{1CA}'..='\u{1CC}' => Some('\u{1CB}'),
+            '\u{1F1}'..='\u{1F3}' => Some('\u{1F2}'),
+            _ => None,
+        };
+        if let Some(digraph) = digraph {
+            out.push(digraph);
+            return;
+        }
+        let start = out.len();
+        self.upper(c, out);
+        let mut rest = out[start..].chars();
+        if let Some(first) = rest.next() {
+            let lowered: String = rest.flat_map(char::to_lowercase).collect();
+            out.truncate(start + first.len_utf8());
+            out.push_str(&lowered);
+        }
+    }
+}
+
+impl TextCase {
+    /// Edits that transform `text`, one per character that changes.
+    pub(crate) fn edits(self, text: &str, language: Option<&str>) -> Vec<Edit> {
+        let rules = Rules::new(language);
+        let titled = match self {
+            TextCase::Title => title_offsets(text, rules),
+            _ => Vec::new(),
+        };
+        let mut edits = Vec::new();
+        let mut mapped = String::new();
+        let mut prev = None;
+        let mut chars = text.char_indices().peekable();
+        while let Some((at, c)) = chars.next() {
+            let next = chars.peek().map(|&(_, next)| next);
+            mapped.clear();
+            match self {
+                TextCase::Upper => rules.upper(c, &mut mapped),
+                TextCase::Title if titled.binary_search(&at).is_ok() => rules.title(c, &mut mapped),
+                TextCase::Lower | TextCase::Title => rules.lower(c, prev, next, &mut mapped),
+            }
+            if mapped.chars().ne([c]) {
+                edits.push(Edit {
+                    at,
+                    removed: c.len_utf8(),
+                    inserted: mapped.clone(),
+                });
+            }
+            prev = Some(c);
+        }
+        edits
+    }
+}
+
+/// Offsets of the letters title case applies to: the first character of
+/// each word when it is a letter, and the `j` of a Dutch `ij`.
+fn title_offsets(text: &str, rules: Rules) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    for (start, word) in text.split_word_bound_indices() {
+        let mut chars = word.chars();
+        let Some(first) = chars.next().filter(|c| c.is_alphabetic()) else {
+            continue;
+        };
+        offsets.push(start);
+        if rules.dutch && matches!(first, 'i' | 'I') && matches!(chars.next(), Some('j' | 'J')) {
+            offsets.push(start + 1);
+        }
+    }
+    offsets
+}
+
+/// Apply `case` to `text`, mapping each stretch of the result back.
+pub(crate) fn transform(text: &str, case: TextCase, language: Option<&str>) -> CaseTransform {
+    let mut result = CaseTransform::default();
+    let mut copied = 0;
+    for edit in case.edits(text, language) {
+        result.push(copied, &text[copied..edit.at], &text[copied..edit.at], true);
+        let end = edit.at + edit.removed;
+        let original = &text[edit.at..end];
+        let one_to_one =
+            original.len() == edit.inserted.len() && edit.inserted.chars().count() == 1;
+        result.push(edit.at, original, &edit.inserted, one_to_one);
+        copied = end;
+    }
+    result.push(copied, &text[copied..], &text[copied..], true);
+    result
+}
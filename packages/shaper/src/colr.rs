@@ -0,0 +1,753 @@
+//! COLR v1 color glyphs, which swash does not draw.
+//!
+//! swash draws COLR v0 layers, bitmap strikes and outlines. A glyph with a
+//! COLR v1 paint graph is walked with skrifa and composited here on the CPU:
+//! solid fills, linear, radial and sweep gradients, transforms, glyph and box
+//! clips, and layers merged with the Porter-Duff modes. The blend modes, such
+//! as multiply or screen, composite as source-over.
+//!
+//! skrifa rejects cyclic graphs and stops at 64 levels of nesting. Layers,
+//! clips and paint operations are limited here too, as is the bitmap size,
+//! so a malformed font costs bounded time and memory. A glyph over any limit
+//! is left to swash, which draws its plain outline.
+
+use cosmic_text::SwashImage;
+use skrifa::color::{Brush, ColorGlyphFormat, ColorPainter, ColorStop, CompositeMode, Extend};
+use skrifa::instance::{LocationRef, NormalizedCoord, Size};
+use skrifa::outline::{DrawSettings, OutlinePen};
+use skrifa::raw::types::BoundingBox;
+use skrifa::raw::TableProvider;
+use skrifa::{FontRef, GlyphId, MetadataProvider};
+use swash::scale::image::Content;
+use swash::scale::Source;
+use swash::zeno::{Command, Mask, Placement, Point};
+use tolerance::Tolerance;
+
+type Transform = skrifa::color::Transform;
+
+/// Largest bitmap drawn, in pixels. Each open layer holds four floats per
+/// pixel.
+const MAX_PIXELS: usize = 512 * 512;
+/// Layers open at once.
+const MAX_LAYERS: usize = 8;
+/// Clips open at once.
+const MAX_CLIPS: usize = 32;
+/// Fills and clips in one glyph.
+const MAX_OPERATIONS: usize = 1024;
+
+/// Determinants, squared lengths, sweeps and quadratic coefficients this
+/// close to zero make a transform or gradient degenerate.
+const DEGENERATE: Tolerance = Tolerance::absolute(f32::EPSILON);
+
+/// Palette index CPAL reserves for the text color, which an image cannot
+/// know. Drawn as opaque black.
+const FOREGROUND: u16 = 0xFFFF;
+
+/// Whether a face has a COLR table of version 1 or later.
+pub(crate) fn has_colr_v1(data: &[u8], index: u32) -> bool {
+    FontRef::from_index(data, index)
+        .ok()
+        .and_then(|font| font.colr().ok())
+        .is_some_and(|colr| colr.version() >= 1)
+}
+
+/// Draw a glyph's COLR v1 paint graph at `font_size` as straight RGBA, or
+/// None when it has none or the graph is over a limit. `coords` are the
+/// face's normalized variation coordinates.
+pub(crate) fn render(
+    data: &[u8],
+    index: u32,
+    coords: &[i16],
+    glyph_id: u16,
+    font_size: f32,
+) -> Option<SwashImage> {
+    let font = FontRef::from_index(data, index).ok()?;
+    let glyph = font
+        .color_glyphs()
+        .get_with_format(GlyphId::new(glyph_id.into()), ColorGlyphFormat::ColrV1)?;
+    let coords: Vec<NormalizedCoord> = coords
+        .iter()
+        .map(|&coord| NormalizedCoord::from_bits(coord))
+        .collect();
+    let location = LocationRef::new(&coords);
+    let units_per_em = font.head().ok()?.units_per_em();
+    let scale = font_size / f32::from(units_per_em);
+    if !scale.is_finite() || scale <= 0.0 {
+        return None;
+    }
+    // Font units, y up, to pixels, y down
+    let to_pixels = Transform {
+        xx: scale,
+        yy: -scale,
+        ..Transform::default()
+    };
+
+    let mut bounds = BoundsPainter {
+        geometry: Geometry::new(&font, location, to_pixels),
+        clips: Vec::new(),
+        ink: None,
+    };
+    glyph.paint(location, &mut bounds).ok()?;
+    if bounds.geometry.failed {
+        return None;
+    }
+    let [x_min, y_min, x_max, y_max] = bounds.ink?;
+    let (left, top) = (x_min.floor(), y_min.floor());
+    let width = (x_max.ceil() - left) as usize;
+    let height = (y_max.ceil() - top) as usize;
+    if width == 0 || height == 0 || width.saturating_mul(height) > MAX_PIXELS {
+        return None;
+    }
+
+    let to_bitmap = Transform {
+        dx: -left,
+        dy: -top,
+        ..to_pixels
+    };
+    let palette = font
+        .color_palettes()
+        .get(0)
+        .map(|palette| {
+            palette
+                .colors()
+                .iter()
+                .map(|color| {
+                    [color.red, color.green, color.blue, color.alpha].map(|c| f32::from(c) / 255.0)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut canvas = Canvas {
+        geometry: Geometry::new(&font, location, to_bitmap),
+        width,
+        height,
+        palette,
+        layers: vec![(vec![[0.0; 4]; width * height], CompositeMode::SrcOver)],
+        clips: Vec::new(),
+    };
+    glyph.paint(location, &mut canvas).ok()?;
+    if canvas.geometry.failed || canvas.layers.len() != 1 {
+        return None;
+    }
+
+    let mut image = SwashImage::new();
+    image.source = Source::ColorOutline(0);
+    image.content = Content::Color;
+    image.placement = Placement {
+        left: left as i32,
+        top: -top as i32,
+        width: width as u32,
+        height: height as u32,
+    };
+    image.data = canvas.layers[0]
+        .0
+        .iter()
+        .flat_map(|&pixel| to_straight(pixel))
+        .collect();
+    Some(image)
+}
+
+/// Premultiplied color to straight sRGB bytes.
+fn to_straight([r, g, b, a]: [f32; 4]) -> [u8; 4] {
+    if a <= 0.0 {
+        return [0; 4];
+    }
+    let byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [byte(r / a), byte(g / a), byte(b / a), byte(a)]
+}
+
+fn apply(transform: &Transform, x: f32, y: f32) -> (f32, f32) {
+    (
+        transform.xx * x + transform.xy * y + transform.dx,
+        transform.yx * x + transform.yy * y + transform.dy,
+    )
+}
+
+fn invert(transform: &Transform) -> Option<Transform> {
+    let det = transform.xx * transform.yy - transform.xy * transform.yx;
+    if DEGENERATE.is_zero(det) || !det.is_finite() {
+        return None;
+    }
+    let (xx, xy) = (transform.yy / det, -transform.xy / det);
+    let (yx, yy) = (-transform.yx / det, transform.xx / det);
+    Some(Transform {
+        xx,
+        yx,
+        xy,
+        yy,
+        dx: -(xx * transform.dx + xy * transform.dy),
+        dy: -(yx * transform.dx + yy * transform.dy),
+    })
+}
+
+/// Transforms and clip shapes, shared by both passes over the graph.
+struct Geometry<'a> {
+    font: &'a FontRef<'a>,
+    location: LocationRef<'a>,
+    /// Paint space to output space, innermost last
+    transforms: Vec<Transform>,
+    operations: usize,
+    /// Set once the graph is over a limit; later calls do nothing
+    failed: bool,
+}
+
+impl<'a> Geometry<'a> {
+    fn new(font: &'a FontRef<'a>, location: LocationRef<'a>, base: Transform) -> Self {
+        Self {
+            font,
+            location,
+            transforms: vec![base],
+            operations: 0,
+            failed: false,
+        }
+    }
+
+    fn current(&self) -> Transform {
+        self.transforms.last().copied().unwrap_or_default()
+    }
+
+    /// Count an operation, failing past the limit. Whether to go on.
+    fn operation(&mut self) -> bool {
+        self.operations += 1;
+        if self.operations > MAX_OPERATIONS {
+            self.failed = true;
+        }
+        !self.failed
+    }
+
+    fn push_transform(&mut self, transform: Transform) {
+        let current = self.current();
+        self.transforms.push(current * transform);
+    }
+
+    fn pop_transform(&mut self) {
+        if self.transforms.len() > 1 {
+            self.transforms.pop();
+        }
+    }
+
+    /// A glyph's outline in output space.
+    fn glyph_path(&self, glyph_id: GlyphId) -> Vec<Command> {
+        let mut pen = PathPen {
+            transform: self.current(),
+            commands: Vec::new(),
+        };
+        let settings = DrawSettings::unhinted(Size::unscaled(), self.location);
+        if let Some(outline) = self.font.outline_glyphs().get(glyph_id) {
+            // A glyph that fails to draw clips everything away
+            if outline.draw(settings, &mut pen).is_err() {
+                pen.commands.clear();
+            }
+        }
+        pen.commands
+    }
+
+    /// A clip box in output space.
+    fn box_path(&self, clip_box: &BoundingBox<f32>) -> Vec<Command> {
+        let mut pen = PathPen {
+            transform: self.current(),
+            commands: Vec::new(),
+        };
+        pen.move_to(clip_box.x_min, clip_box.y_min);
+        pen.line_to(clip_box.x_max, clip_box.y_min);
+        pen.line_to(clip_box.x_max, clip_box.y_max);
+        pen.line_to(clip_box.x_min, clip_box.y_max);
+        pen.close();
+        pen.commands
+    }
+}
+
+/// Collects an outline as zeno commands, transformed.
+struct PathPen {
+    transform: Transform,
+    commands: Vec<Command>,
+}
+
+impl PathPen {
+    fn point(&self, x: f32, y: f32) -> Point {
+        let (x, y) = apply(&self.transform, x, y);
+        Point::new(x, y)
+    }
+}
+
+impl OutlinePen for PathPen {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.commands.push(Command::MoveTo(self.point(x, y)));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.commands.push(Command::LineTo(self.point(x, y)));
+    }
+
+    fn quad_to(&mut self, cx0: f32, cy0: f32, x: f32, y: f32) {
+        let command = Command::QuadTo(self.point(cx0, cy0), self.point(x, y));
+        self.commands.push(command);
+    }
+
+    fn curve_to(&mut self, cx0: f32, cy0: f32, cx1: f32, cy1: f32, x: f32, y: f32) {
+        let command =
+            Command::CurveTo(self.point(cx0, cy0), self.point(cx1, cy1), self.point(x, y));
+        self.commands.push(command);
+    }
+
+    fn close(&mut self) {
+        self.commands.push(Command::Close);
+    }
+}
+
+/// `[x_min, y_min, x_max, y_max]` of a path's points, or None when empty.
+fn path_bounds(commands: &[Command]) -> Option<[f32; 4]> {
+    let mut points = commands.iter().flat_map(|command| match *command {
+        Command::MoveTo(p) | Command::LineTo(p) => vec![p],
+        Command::QuadTo(c, p) => vec![c, p],
+        Command::CurveTo(c0, c1, p) => vec![c0, c1, p],
+        Command::Close => Vec::new(),
+    });
+    let first = points.next()?;
+    let bounds = points.fold(
+        [first.x, first.y, first.x, first.y],
+        |[x0, y0, x1, y1], p| [x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y)],
+    );
+    bounds.iter().all(|v| v.is_finite()).then_some(bounds)
+}
+
+fn intersect(a: Option<[f32; 4]>, b: Option<[f32; 4]>) -> Option<[f32; 4]> {
+    let ([ax0, ay0, ax1, ay1], [bx0, by0, bx1, by1]) = (a?, b?);
+    let bounds = [ax0.max(bx0), ay0.max(by0), ax1.min(bx1), ay1.min(by1)];
+    (bounds[0] < bounds[2] && bounds[1] < bounds[3]).then_some(bounds)
+}
+
+/// First pass: the pixel bounds of everything filled, each fill bounded by
+/// the clips it is drawn in. A fill outside any clip has no bounds and is
+/// ignored.
+struct BoundsPainter<'a> {
+    geometry: Geometry<'a>,
+    /// Clipped area so far, None once it is empty
+    clips: Vec<Option<[f32; 4]>>,
+    ink: Option<[f32; 4]>,
+}
+
+impl BoundsPainter<'_> {
+    fn push_clip(&mut self, path: &[Command]) {
+        let bounds = path_bounds(path);
+        let clipped = match self.clips.last() {
+            Some(&outer) => intersect(outer, bounds),
+            None => bounds,
+        };
+        self.clips.push(clipped);
+        if self.clips.len() > MAX_CLIPS {
+            self.geometry.failed = true;
+        }
+    }
+}
+
+impl ColorPainter for BoundsPainter<'_> {
+    fn push_transform(&mut self, transform: Transform) {
+        self.geometry.push_transform(transform);
+    }
+
+    fn pop_transform(&mut self) {
+        self.geometry.pop_transform();
+    }
+
+    fn push_clip_glyph(&mut self, glyph_id: GlyphId) {
+        if self.geometry.operation() {
+            let path = self.geometry.glyph_path(glyph_id);
+            self.push_clip(&path);
+        }
+    }
+
+    fn push_clip_box(&mut self, clip_box: BoundingBox<f32>) {
+        if self.geometry.operation() {
+            let path = self.geometry.box_path(&clip_box);
+            self.push_clip(&path);
+        }
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    fn fill(&mut self, _brush: Brush<'_>) {
+        if !self.geometry.operation() {
+            return;
+        }
+        let Some(&Some([x0, y0, x1, y1])) = self.clips.last() else {
+            return;
+        };
+        self.ink = Some(match self.ink {
+            Some([ix0, iy0, ix1, iy1]) => [ix0.min(x0), iy0.min(y0), ix1.max(x1), iy1.max(y1)],
+            None => [x0, y0, x1, y1],
+        });
+    }
+
+    fn push_layer(&mut self, _composite_mode: CompositeMode) {}
+}
+
+/// Second pass: composite the graph into premultiplied RGBA.
+struct Canvas<'a> {
+    geometry: Geometry<'a>,
+    width: usize,
+    height: usize,
+    /// Straight RGBA of the first palette
+    palette: Vec<[f32; 4]>,
+    /// Open layers and the modes they merge down with; the first is the
+    /// output
+    layers: Vec<(Vec<[f32; 4]>, CompositeMode)>,
+    /// Coverage of each pixel by the clips so far
+    clips: Vec<Vec<f32>>,
+}
+
+impl Canvas<'_> {
+    fn push_clip(&mut self, path: &[Command]) {
+        if self.clips.len() >= MAX_CLIPS {
+            self.geometry.failed = true;
+            return;
+        }
+        let (mask, _) = Mask::new(path)
+            .size(self.width as u32, self.height as u32)
+            .render();
+        let mut coverage: Vec<f32> = mask.iter().map(|&c| f32::from(c) / 255.0).collect();
+        if let Some(outer) = self.clips.last() {
+            coverage.iter_mut().zip(outer).for_each(|(c, o)| *c *= o);
+        }
+        self.clips.push(coverage);
+    }
+
+    /// Straight RGBA of a palette entry, with `alpha` applied.
+    fn color(&self, palette_index: u16, alpha: f32) -> [f32; 4] {
+        let [r, g, b, a] = match palette_index {
+            FOREGROUND => [0.0, 0.0, 0.0, 1.0],
+            index => self
+                .palette
+                .get(usize::from(index))
+                .copied()
+                .unwrap_or_default(),
+        };
+        [r, g, b, a * alpha.clamp(0.0, 1.0)]
+    }
+}
+
+impl ColorPainter for Canvas<'_> {
+    fn push_transform(&mut self, transform: Transform) {
+        self.geometry.push_transform(transform);
+    }
+
+    fn pop_transform(&mut self) {
+        self.geometry.pop_transform();
+    }
+
+    fn push_clip_glyph(&mut self, glyph_id: GlyphId) {
+        if self.geometry.operation() {
+            let path = self.geometry.glyph_path(glyph_id);
+            self.push_clip(&path);
+        }
+    }
+
+    fn push_clip_box(&mut self, clip_box: BoundingBox<f32>) {
+        if self.geometry.operation() {
+            let path = self.geometry.box_path(&clip_box);
+            self.push_clip(&path);
+        }
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    fn fill(&mut self, brush: Brush<'_>) {
+        if !self.geometry.operation() {
+            return;
+        }
+        // Pixel centers are mapped back to paint space to sample the brush
+        let Some(to_paint) = invert(&self.geometry.current()) else {
+            return;
+        };
+        let Some(shader) = Shader::new(&brush, |index, alpha| self.color(index, alpha)) else {
+            return;
+        };
+        let Some((layer, _)) = self.layers.last_mut() else {
+            return;
+        };
+        let clip = self.clips.last();
+        for (index, pixel) in layer.iter_mut().enumerate() {
+            let coverage = clip.map_or(1.0, |clip| clip[index]);
+            if coverage <= 0.0 {
+                continue;
+            }
+            let (x, y) = (index % self.width, index / self.width);
+            let (x, y) = apply(&to_paint, x as f32 + 0.5, y as f32 + 0.5);
+            let source = shader.sample(x, y).map(|channel| channel * coverage);
+            *pixel = composite(CompositeMode::SrcOver, source, *pixel);
+        }
+    }
+
+    fn push_layer(&mut self, composite_mode: CompositeMode) {
+        if self.layers.len() > MAX_LAYERS {
+            self.geometry.failed = true;
+        }
+        if self.geometry.failed {
+            return;
+        }
+        let pixels = vec![[0.0; 4]; self.width * self.height];
+        self.layers.push((pixels, composite_mode));
+    }
+
+    fn pop_layer(&mut self) {
+        if self.geometry.failed || self.layers.len() < 2 {
+            return;
+        }
+        let Some((source, mode)) = self.layers.pop() else {
+            return;
+        };
+        if let Some((backdrop, _)) = self.layers.last_mut() {
+            for (pixel, source) in backdrop.iter_mut().zip(source) {
+                *pixel = composite(mode, source, *pixel);
+            }
+        }
+    }
+}
+
+/// Porter-Duff composite of premultiplied colors. Blend modes draw as
+/// source-over.
+fn composite(mode: CompositeMode, source: [f32; 4], backdrop: [f32; 4]) -> [f32; 4] {
+    let (sa, da) = (source[3], backdrop[3]);
+    let (source_factor, backdrop_factor) = match mode {
+        CompositeMode::Clear => (0.0, 0.0),
+        CompositeMode::Src => (1.0, 0.0),
+        CompositeMode::Dest => (0.0, 1.0),
+        CompositeMode::DestOver => (1.0 - da, 1.0),
+        CompositeMode::SrcIn => (da, 0.0),
+        CompositeMode::DestIn => (0.0, sa),
+        CompositeMode::SrcOut => (1.0 - da, 0.0),
+        CompositeMode::DestOut => (0.0, 1.0 - sa),
+        CompositeMode::SrcAtop => (da, 1.0 - sa),
+        CompositeMode::DestAtop => (1.0 - da, sa),
+        CompositeMode::Xor => (1.0 - da, 1.0 - sa),
+        CompositeMode::Plus => (1.0, 1.0),
+        _ => (1.0, 1.0 - sa),
+    };
+    let mut result = [0.0; 4];
+    for (channel, out) in result.iter_mut().enumerate() {
+        *out = (source[channel] * source_factor + backdrop[channel] * backdrop_factor).min(1.0);
+    }
+    result
+}
+
+/// A brush resolved against the palette, sampled in paint space.
+enum Shader {
+    Solid([f32; 4]),
+    Linear {
+        start: (f32, f32),
+        /// `p1 - p0` over its squared length, so the dot product with a
+        /// point's offset from `start` is its position on the color line
+        direction: (f32, f32),
+        stops: ColorLine,
+    },
+    Radial {
+        c0: (f32, f32),
+        r0: f32,
+        c1: (f32, f32),
+        r1: f32,
+        stops: ColorLine,
+    },
+    Sweep {
+        center: (f32, f32),
+        start_angle: f32,
+        end_angle: f32,
+        stops: ColorLine,
+    },
+}
+
+impl Shader {
+    /// None when the brush paints nothing, such as a gradient with no stops
+    /// or between coincident points.
+    fn new(brush: &Brush<'_>, color: impl Fn(u16, f32) -> [f32; 4]) -> Option<Self> {
+        let shader = match *brush {
+            Brush::Solid {
+                palette_index,
+                alpha,
+            } => Shader::Solid(premultiply(color(palette_index, alpha))),
+            Brush::LinearGradient {
+                p0,
+                p1,
+                color_stops,
+                extend,
+            } => {
+                let (dx, dy) = (p1.x - p0.x, p1.y - p0.y);
+                let length_squared = dx * dx + dy * dy;
+                let stops = ColorLine::new(color_stops, extend, color)?;
+                if DEGENERATE.is_zero(length_squared) {
+                    return None;
+                }
+                Shader::Linear {
+                    start: (p0.x, p0.y),
+                    direction: (dx / length_squared, dy / length_squared),
+                    stops,
+                }
+            }
+            Brush::RadialGradient {
+                c0,
+                r0,
+                c1,
+                r1,
+                color_stops,
+                extend,
+            } => Shader::Radial {
+                c0: (c0.x, c0.y),
+                r0,
+                c1: (c1.x, c1.y),
+                r1,
+                stops: ColorLine::new(color_stops, extend, color)?,
+            },
+            Brush::SweepGradient {
+                c0,
+                start_angle,
+                end_angle,
+                color_stops,
+                extend,
+            } => {
+                if DEGENERATE.eq(start_angle, end_angle) {
+                    return None;
+                }
+                Shader::Sweep {
+                    center: (c0.x, c0.y),
+                    start_angle,
+                    end_angle,
+                    stops: ColorLine::new(color_stops, extend, color)?,
+                }
+            }
+        };
+        Some(shader)
+    }
+
+    /// Premultiplied color at a point.
+    fn sample(&self, x: f32, y: f32) -> [f32; 4] {
+        match self {
+            Shader::Solid(color) => *color,
+            Shader::Linear {
+                start,
+                direction,
+                stops,
+            } => stops.at((x - start.0) * direction.0 + (y - start.1) * direction.1),
+            Shader::Radial {
+                c0,
+                r0,
+                c1,
+                r1,
+                stops,
+            } => radial_position((x, y), *c0, *r0, *c1, *r1)
+                .map_or([0.0; 4], |position| stops.at(position)),
+            Shader::Sweep {
+                center,
+                start_angle,
+                end_angle,
+                stops,
+            } => {
+                // Clockwise in paint space, as skrifa reports the angles
+                let angle = (center.1 - y).atan2(x - center.0).to_degrees();
+                let angle = angle.rem_euclid(360.0);
+                stops.at((angle - start_angle) / (end_angle - start_angle))
+            }
+        }
+    }
+}
+
+/// Position on the color line of a two-point conical gradient at `point`:
+/// the largest `t` whose circle, centered between `c0` and `c1` with a
+/// radius between `r0` and `r1`, passes through the point with a radius of
+/// at least zero. None outside every such circle.
+fn radial_position(
+    point: (f32, f32),
+    c0: (f32, f32),
+    r0: f32,
+    c1: (f32, f32),
+    r1: f32,
+) -> Option<f32> {
+    let (cdx, cdy) = (c1.0 - c0.0, c1.1 - c0.1);
+    let (pdx, pdy) = (point.0 - c0.0, point.1 - c0.1);
+    let dr = r1 - r0;
+    let a = cdx * cdx + cdy * cdy - dr * dr;
+    let b = pdx * cdx + pdy * cdy + r0 * dr;
+    let c = pdx * pdx + pdy * pdy - r0 * r0;
+    let radius_ok = |t: f32| r0 + t * dr >= 0.0;
+    if DEGENERATE.is_zero(a) {
+        let t = c / (2.0 * b);
+        return (t.is_finite() && radius_ok(t)).then_some(t);
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let (t1, t2) = ((b + root) / a, (b - root) / a);
+    let (larger, smaller) = (t1.max(t2), t1.min(t2));
+    [larger, smaller].into_iter().find(|&t| radius_ok(t))
+}
+
+/// Premultiplied gradient stops, sorted, with how the line extends past
+/// them.
+struct ColorLine {
+    stops: Vec<(f32, [f32; 4])>,
+    extend: Extend,
+}
+
+impl ColorLine {
+    fn new(
+        color_stops: &[ColorStop],
+        extend: Extend,
+        color: impl Fn(u16, f32) -> [f32; 4],
+    ) -> Option<Self> {
+        let mut stops: Vec<(f32, [f32; 4])> = color_stops
+            .iter()
+            .filter(|stop| stop.offset.is_finite())
+            .map(|stop| {
+                let rgba = premultiply(color(stop.palette_index, stop.alpha));
+                (stop.offset, rgba)
+            })
+            .collect();
+        if stops.is_empty() {
+            return None;
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Some(Self { stops, extend })
+    }
+
+    fn at(&self, position: f32) -> [f32; 4] {
+        if !position.is_finite() {
+            return [0.0; 4];
+        }
+        let position = match self.extend {
+            Extend::Repeat => position - position.floor(),
+            Extend::Reflect => {
+                let cycle = position.rem_euclid(2.0);
+                if cycle > 1.0 {
+                    2.0 - cycle
+                } else {
+                    cycle
+                }
+            }
+            _ => position.clamp(0.0, 1.0),
+        };
+        let after = self
+            .stops
+            .partition_point(|&(offset, _)| offset <= position);
+        match (after.checked_sub(1), self.stops.get(after)) {
+            (Some(before), Some(&(end, end_color))) => {
+                let (start, start_color) = self.stops[before];
+                let t = (position - start) / (end - start);
+                let mut color = [0.0; 4];
+                for (channel, out) in color.iter_mut().enumerate() {
+                    *out = start_color[channel] + (end_color[channel] - start_color[channel]) * t;
+                }
+                color
+            }
+            (Some(before), None) => self.stops[before].1,
+            (None, _) => self.stops[0].1,
+        }
+    }
+}
+
+fn premultiply([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    [r * a, g * a, b * a, a]
+}
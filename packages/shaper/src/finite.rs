@@ -0,0 +1,163 @@
+//! Shaping results never hold NaN or an infinity.
+//!
+//! `serde_wasm_bindgen` passes non-finite floats through to JS, where they
+//! crash the renderer far from their cause. Results are checked as they
+//! leave for JS, since the native API hands them out directly. A
+//! non-finite value there is a bug, so debug builds assert; release builds
+//! reject the result with a `NonFiniteValue` naming the field, or with the
+//! `sanitize-non-finite` feature replace the value with zero.
+
+use crate::{InkBounds, LayoutResult, MeasureResult, ShapedGlyph, ShapedLineResult};
+
+/// A result field holding NaN or an infinity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonFiniteValue {
+    /// The field, with indices into arrays, such as `lines[2].glyphs[5].x`
+    pub field: String,
+    pub value: f32,
+}
+
+impl std::fmt::Display for NonFiniteValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Non-finite {}: {}", self.field, self.value)
+    }
+}
+
+/// Calls made by `FiniteFields::visit_floats`: a field's name, built only
+/// when it is reported, and the field.
+type Visit<'a> = dyn FnMut(&dyn Fn() -> String, &mut f32) + 'a;
+
+/// Results whose floats can be checked and repaired in place.
+pub(crate) trait FiniteFields {
+    /// Call `visit` with every float field, prefixing names with `prefix`.
+    fn visit_floats(&mut self, prefix: &dyn Fn() -> String, visit: &mut Visit);
+}
+
+impl FiniteFields for ShapedGlyph {
+    fn visit_floats(&mut self, prefix: &dyn Fn() -> String, visit: &mut Visit) {
+        visit(&|| format!("{}.x", prefix()), &mut self.x);
+        visit(&|| format!("{}.y", prefix()), &mut self.y);
+        visit(&|| format!("{}.x_advance", prefix()), &mut self.x_advance);
+        visit(&|| format!("{}.y_advance", prefix()), &mut self.y_advance);
+        visit(&|| format!("{}.x_offset", prefix()), &mut self.x_offset);
+        visit(&|| format!("{}.y_offset", prefix()), &mut self.y_offset);
+        visit(
+            &|| format!("{}.baseline_shift", prefix()),
+            &mut self.baseline_shift,
+        );
+    }
+}
+
+impl FiniteFields for InkBounds {
+    fn visit_floats(&mut self, prefix: &dyn Fn() -> String, visit: &mut Visit) {
+        visit(&|| format!("{}.x_min", prefix()), &mut self.x_min);
+        visit(&|| format!("{}.y_min", prefix()), &mut self.y_min);
+        visit(&|| format!("{}.x_max", prefix()), &mut self.x_max);
+        visit(&|| format!("{}.y_max", prefix()), &mut self.y_max);
+    }
+}
+
+fn visit_glyphs(glyphs: &mut [ShapedGlyph], prefix: &dyn Fn() -> String, visit: &mut Visit) {
+    for (index, glyph) in glyphs.iter_mut().enumerate() {
+        glyph.visit_floats(&|| format!("{}glyphs[{}]", prefix(), index), visit);
+    }
+}
+
+impl FiniteFields for ShapedLineResult {
+    fn visit_floats(&mut self, prefix: &dyn Fn() -> String, visit: &mut Visit) {
+        visit_glyphs(&mut self.glyphs, prefix, visit);
+        visit(&|| format!("{}width", prefix()), &mut self.width);
+        visit(
+            &|| format!("{}width_with_trailing_whitespace", prefix()),
+            &mut self.width_with_trailing_whitespace,
+        );
+        visit(&|| format!("{}height", prefix()), &mut self.height);
+        visit(&|| format!("{}ascent", prefix()), &mut self.ascent);
+        visit(&|| format!("{}descent", prefix()), &mut self.descent);
+    }
+}
+
+impl FiniteFields for LayoutResult {
+    fn visit_floats(&mut self, prefix: &dyn Fn() -> String, visit: &mut Visit) {
+        for (index, line) in self.lines.iter_mut().enumerate() {
+            let line_prefix = || format!("{}lines[{}].", prefix(), index);
+            visit_glyphs(&mut line.glyphs, &line_prefix, visit);
+            visit(&|| format!("{}width", line_prefix()), &mut line.width);
+            visit(
+                &|| format!("{}width_with_trailing_whitespace", line_prefix()),
+                &mut line.width_with_trailing_whitespace,
+            );
+            visit(&|| format!("{}y", line_prefix()), &mut line.y);
+            visit(
+                &|| format!("{}line_height", line_prefix()),
+                &mut line.line_height,
+            );
+            visit(&|| format!("{}ascent", line_prefix()), &mut line.ascent);
+            visit(&|| format!("{}descent", line_prefix()), &mut line.descent);
+            visit(
+                &|| format!("{}baseline_shift", line_prefix()),
+                &mut line.baseline_shift,
+            );
+            for (index, bounds) in line.glyph_ink_bounds.iter_mut().enumerate() {
+                if let Some(bounds) = bounds {
+                    let name = || format!("{}glyph_ink_bounds[{}]", line_prefix(), index);
+                    bounds.visit_floats(&name, visit);
+                }
+            }
+            if let Some(bounds) = &mut line.ink_bounds {
+                bounds.visit_floats(&|| format!("{}ink_bounds", line_prefix()), visit);
+            }
+        }
+        visit(
+            &|| format!("{}total_width", prefix()),
+            &mut self.total_width,
+        );
+        visit(
+            &|| format!("{}total_height", prefix()),
+            &mut self.total_height,
+        );
+        for (index, run) in self.shadow_runs.iter_mut().enumerate() {
+            let run_prefix = || format!("{}shadow_runs[{}].", prefix(), index);
+            visit(&|| format!("{}x", run_prefix()), &mut run.x);
+            visit(&|| format!("{}y", run_prefix()), &mut run.y);
+        }
+    }
+}
+
+impl FiniteFields for MeasureResult {
+    fn visit_floats(&mut self, prefix: &dyn Fn() -> String, visit: &mut Visit) {
+        visit(&|| format!("{}width", prefix()), &mut self.width);
+        visit(&|| format!("{}height", prefix()), &mut self.height);
+    }
+}
+
+/// The first non-finite float of `value`, in field order.
+pub(crate) fn first_non_finite(value: &mut impl FiniteFields) -> Option<NonFiniteValue> {
+    let mut found = None;
+    value.visit_floats(&String::new, &mut |name, value| {
+        if found.is_none() && !value.is_finite() {
+            found = Some(NonFiniteValue {
+                field: name(),
+                value: *value,
+            });
+        }
+    });
+    found
+}
+
+/// Apply the policy to a result on its way to JS.
+pub(crate) fn enforce_finite(value: &mut impl FiniteFields) -> Result<(), NonFiniteValue> {
+    let Some(non_finite) = first_non_finite(value) else {
+        return Ok(());
+    };
+    debug_assert!(false, "{}", non_finite);
+    if cfg!(feature = "sanitize-non-finite") {
+        value.visit_floats(&String::new, &mut |_, value| {
+            if !value.is_finite() {
+                *value = 0.0;
+            }
+        });
+        return Ok(());
+    }
+    Err(non_finite)
+}
@@ -0,0 +1,178 @@
+//! Hyphenation with soft hyphens and Knuth–Liang patterns.
+//!
+//! cosmic-text already breaks lines after a soft hyphen (U+00AD) and shapes
+//! it invisibly; layout adds the visible hyphen to lines broken there.
+//! Automatic hyphenation inserts soft hyphens where a language's patterns
+//! allow a break, as `PreparedText` edits.
+
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub(crate) const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Fewest letters kept on each side of an automatic break.
+const MIN_FRAGMENT: usize = 2;
+
+/// One language's hyphenation patterns and exception words.
+pub(crate) struct HyphenationPatterns {
+    /// Pattern letters, with `.` for a word edge, to the values between and
+    /// around them
+    patterns: HashMap<String, Vec<u8>>,
+    /// Longest pattern in chars
+    max_len: usize,
+    /// Lowercase words to the char indices they break before
+    exceptions: HashMap<String, Vec<usize>>,
+}
+
+impl HyphenationPatterns {
+    /// Parse a pattern file: whitespace-separated patterns such as `.ab1c`,
+    /// with `%` comments. TeX `\patterns{...}` and `\hyphenation{...}`
+    /// blocks are accepted, the latter holding exception words like
+    /// `ta-ble`.
+    pub(crate) fn parse(source: &str) -> Result<Self, String> {
+        let mut parsed = Self {
+            patterns: HashMap::new(),
+            max_len: 0,
+            exceptions: HashMap::new(),
+        };
+        let mut exceptions = false;
+        let tokens = source.lines().flat_map(|line| {
+            line.split('%')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+        });
+        for token in tokens {
+            match token {
+                "\\patterns{" => exceptions = false,
+                "\\hyphenation{" => exceptions = true,
+                _ if token.starts_with('\\') => {
+                    return Err(format!("Unsupported pattern command: {}", token));
+                }
+                _ => {
+                    let token = token.trim_matches(|c| c == '{' || c == '}');
+                    if token.is_empty() {
+                        continue;
+                    }
+                    if exceptions {
+                        parsed.add_exception(token);
+                    } else {
+                        parsed.add_pattern(token)?;
+                    }
+                }
+            }
+        }
+        if parsed.patterns.is_empty() && parsed.exceptions.is_empty() {
+            return Err("No hyphenation patterns found".to_string());
+        }
+        Ok(parsed)
+    }
+
+    fn add_pattern(&mut self, token: &str) -> Result<(), String> {
+        let mut letters = String::new();
+        let mut values = vec![0u8];
+        for c in token.chars() {
+            match c.to_digit(10) {
+                Some(value) => {
+                    if let Some(last) = values.last_mut() {
+                        *last = value as u8;
+                    }
+                }
+                None if c.is_alphabetic() || c == '.' || c == '\'' => {
+                    letters.push(c);
+                    values.push(0);
+                }
+                None => return Err(format!("Invalid hyphenation pattern: {}", token)),
+            }
+        }
+        if letters.is_empty() {
+            return Err(format!("Invalid hyphenation pattern: {}", token));
+        }
+        self.max_len = self.max_len.max(values.len() - 1);
+        self.patterns.insert(letters, values);
+        Ok(())
+    }
+
+    fn add_exception(&mut self, token: &str) {
+        let mut word = String::new();
+        let mut breaks = Vec::new();
+        for c in token.chars() {
+            if c == '-' {
+                breaks.push(word.chars().count());
+            } else {
+                word.extend(c.to_lowercase());
+            }
+        }
+        self.exceptions.insert(word, breaks);
+    }
+
+    /// Byte offsets within `word` where it may be hyphenated, in order.
+    pub(crate) fn break_offsets(&self, word: &str) -> Vec<usize> {
+        let mut lower = Vec::with_capacity(word.len());
+        for c in word.chars() {
+            let mut mapped = c.to_lowercase();
+            // Skip words whose case mapping changes their length
+            match (mapped.next(), mapped.next()) {
+                (Some(single), None) => lower.push(single),
+                _ => return Vec::new(),
+            }
+        }
+        let len = lower.len();
+        if len < MIN_FRAGMENT * 2 {
+            return Vec::new();
+        }
+
+        let breaks = match self.exceptions.get(&lower.iter().collect::<String>()) {
+            Some(breaks) => breaks.clone(),
+            None => self.pattern_breaks(&lower),
+        };
+        let offsets: Vec<usize> = word.char_indices().map(|(offset, _)| offset).collect();
+        breaks
+            .into_iter()
+            .filter(|&index| index >= MIN_FRAGMENT && len - index >= MIN_FRAGMENT)
+            .map(|index| offsets[index])
+            .collect()
+    }
+
+    /// Char indices a lowercase word may break before, by Liang's method:
+    /// every pattern found in the word votes on the gaps it spans, the
+    /// highest value wins, and odd values allow a break.
+    fn pattern_breaks(&self, lower: &[char]) -> Vec<usize> {
+        let dotted: Vec<char> = std::iter::once('.')
+            .chain(lower.iter().copied())
+            .chain(std::iter::once('.'))
+            .collect();
+        let mut values = vec![0u8; dotted.len() + 1];
+        let mut key = String::new();
+        for start in 0..dotted.len() {
+            key.clear();
+            for &c in dotted.iter().skip(start).take(self.max_len) {
+                key.push(c);
+                let Some(pattern) = self.patterns.get(&key) else {
+                    continue;
+                };
+                for (offset, &value) in pattern.iter().enumerate() {
+                    let slot = &mut values[start + offset];
+                    *slot = (*slot).max(value);
+                }
+            }
+        }
+        // The gap before word char `index` follows dotted char `index`
+        (1..lower.len())
+            .filter(|&index| values[index + 1] % 2 == 1)
+            .collect()
+    }
+
+    /// Offsets in `text` to insert soft hyphens at. Words that already
+    /// contain a soft hyphen are left as the author hyphenated them.
+    pub(crate) fn text_breaks(&self, text: &str) -> Vec<usize> {
+        text.split_word_bound_indices()
+            .filter(|(_, word)| word.chars().all(char::is_alphabetic))
+            .flat_map(|(start, word)| {
+                self.break_offsets(word)
+                    .into_iter()
+                    .map(move |offset| start + offset)
+            })
+            .collect()
+    }
+}
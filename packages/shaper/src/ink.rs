@@ -0,0 +1,153 @@
+//! Ink extents of glyph outlines.
+//!
+//! Outline points include off-curve control points, which can lie well
+//! outside the painted shape, so curves are bounded by their extrema instead.
+
+use serde::{Deserialize, Serialize};
+use swash::zeno::{Command, Vector};
+use tolerance::Tolerance;
+
+/// A derivative coefficient this small leaves the derivative linear.
+const LINEAR_DERIVATIVE: Tolerance = Tolerance::absolute(f32::EPSILON);
+
+/// The box around the painted part of a glyph or string, with y growing
+/// downward from the baseline, so ink above the baseline has negative y.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InkBounds {
+    pub x_min: f32,
+    pub y_min: f32,
+    pub x_max: f32,
+    pub y_max: f32,
+}
+
+impl InkBounds {
+    /// A box containing nothing, which any included point replaces.
+    pub(crate) const EMPTY: InkBounds = InkBounds {
+        x_min: f32::INFINITY,
+        y_min: f32::INFINITY,
+        x_max: f32::NEG_INFINITY,
+        y_max: f32::NEG_INFINITY,
+    };
+
+    pub fn width(&self) -> f32 {
+        self.x_max - self.x_min
+    }
+
+    pub fn height(&self) -> f32 {
+        self.y_max - self.y_min
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.x_min > self.x_max || self.y_min > self.y_max
+    }
+
+    pub(crate) fn union(&self, other: &InkBounds) -> InkBounds {
+        InkBounds {
+            x_min: self.x_min.min(other.x_min),
+            y_min: self.y_min.min(other.y_min),
+            x_max: self.x_max.max(other.x_max),
+            y_max: self.y_max.max(other.y_max),
+        }
+    }
+
+    pub(crate) fn translate(&self, x: f32, y: f32) -> InkBounds {
+        InkBounds {
+            x_min: self.x_min + x,
+            y_min: self.y_min + y,
+            x_max: self.x_max + x,
+            y_max: self.y_max + y,
+        }
+    }
+
+    fn include(&mut self, point: Vector) {
+        self.x_min = self.x_min.min(point.x);
+        self.y_min = self.y_min.min(point.y);
+        self.x_max = self.x_max.max(point.x);
+        self.y_max = self.y_max.max(point.y);
+    }
+}
+
+/// Bounds of outline commands in swash's y-up space, flipped to y-down.
+/// None for an outline with no points, such as a space.
+pub(crate) fn outline_bounds(commands: &[Command]) -> Option<InkBounds> {
+    let mut bounds = InkBounds::EMPTY;
+    let mut current = Vector::ZERO;
+    for command in commands {
+        match *command {
+            Command::MoveTo(to) | Command::LineTo(to) => {
+                bounds.include(to);
+                current = to;
+            }
+            Command::QuadTo(control, to) => {
+                bounds.include(to);
+                for t in quad_extrema(current, control, to) {
+                    bounds.include(quad_point(current, control, to, t));
+                }
+                current = to;
+            }
+            Command::CurveTo(control1, control2, to) => {
+                bounds.include(to);
+                for t in cubic_extrema(current, control1, control2, to) {
+                    bounds.include(cubic_point(current, control1, control2, to, t));
+                }
+                current = to;
+            }
+            Command::Close => {}
+        }
+    }
+    (!bounds.is_empty()).then(|| InkBounds {
+        x_min: bounds.x_min,
+        y_min: -bounds.y_max,
+        x_max: bounds.x_max,
+        y_max: -bounds.y_min,
+    })
+}
+
+/// Parameters strictly inside (0, 1) where a quadratic turns on either axis.
+fn quad_extrema(p0: Vector, p1: Vector, p2: Vector) -> impl Iterator<Item = f32> {
+    let axis = |a: f32, b: f32, c: f32| {
+        let denominator = a - 2.0 * b + c;
+        (denominator != 0.0).then(|| (a - b) / denominator)
+    };
+    [axis(p0.x, p1.x, p2.x), axis(p0.y, p1.y, p2.y)]
+        .into_iter()
+        .flatten()
+        .filter(|t| *t > 0.0 && *t < 1.0)
+}
+
+/// Parameters strictly inside (0, 1) where a cubic turns on either axis.
+fn cubic_extrema(p0: Vector, p1: Vector, p2: Vector, p3: Vector) -> impl Iterator<Item = f32> {
+    let axis = |a: f32, b: f32, c: f32, d: f32| {
+        // Roots of the derivative, a quadratic in t
+        let qa = -a + 3.0 * b - 3.0 * c + d;
+        let qb = 2.0 * (a - 2.0 * b + c);
+        let qc = b - a;
+        if LINEAR_DERIVATIVE.is_zero(qa) {
+            return [(qb != 0.0).then(|| -qc / qb), None];
+        }
+        let discriminant = qb * qb - 4.0 * qa * qc;
+        if discriminant < 0.0 {
+            return [None, None];
+        }
+        let root = discriminant.sqrt();
+        [
+            Some((-qb + root) / (2.0 * qa)),
+            Some((-qb - root) / (2.0 * qa)),
+        ]
+    };
+    axis(p0.x, p1.x, p2.x, p3.x)
+        .into_iter()
+        .chain(axis(p0.y, p1.y, p2.y, p3.y))
+        .flatten()
+        .filter(|t| *t > 0.0 && *t < 1.0)
+}
+
+fn quad_point(p0: Vector, p1: Vector, p2: Vector, t: f32) -> Vector {
+    let mt = 1.0 - t;
+    p0 * (mt * mt) + p1 * (2.0 * mt * t) + p2 * (t * t)
+}
+
+fn cubic_point(p0: Vector, p1: Vector, p2: Vector, p3: Vector, t: f32) -> Vector {
+    let mt = 1.0 - t;
+    p0 * (mt * mt * mt) + p1 * (3.0 * mt * mt * t) + p2 * (3.0 * mt * t * t) + p3 * (t * t * t)
+}
@@ -0,0 +1,129 @@
+//! Justified lines for `LayoutOptions::justify`.
+//!
+//! A line is stretched to the layout width by widening gaps after glyphs:
+//! the advance of the glyph before each gap grows and everything after it
+//! moves right, so glyph boxes still tile the line and carets placed from
+//! `x` and `x_advance` land where the glyphs are drawn. Word gaps are word
+//! separator spaces; character gaps fall between clusters. Hanging
+//! trailing whitespace is never widened.
+
+use crate::{InkBounds, ShapedGlyph};
+use cosmic_text::LayoutRun;
+use serde::{Deserialize, Serialize};
+use unicode_script::{Script, UnicodeScript};
+
+/// Where `LayoutOptions::justify` puts a line's extra space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JustifyDistribution {
+    /// Between words. Lines without a word gap fall back to
+    /// `InterCharacter`.
+    #[default]
+    InterWord,
+    /// Between all clusters, as CJK text is justified
+    InterCharacter,
+    /// `InterCharacter` for lines mostly in Han, kana or Bopomofo, and
+    /// `InterWord` for the rest
+    Auto,
+}
+
+/// Word separators that take extra space, as in CSS `text-justify`.
+fn is_word_separator(cluster: &str) -> bool {
+    cluster
+        .chars()
+        .all(|c| matches!(c, ' ' | '\u{00A0}' | '\u{1361}' | '\u{10100}' | '\u{10101}'))
+}
+
+/// Whitespace that hangs at the end of a line, as in `line_widths`.
+fn is_space(cluster: &str) -> bool {
+    cluster
+        .chars()
+        .all(|c| c.is_whitespace() && !matches!(c, '\u{00A0}' | '\u{2007}' | '\u{202F}'))
+}
+
+/// Whether most of a line's non-space clusters are in scripts written
+/// without spaces between words.
+fn is_mostly_cjk(run: &LayoutRun) -> bool {
+    let (cjk, total) = run
+        .glyphs
+        .iter()
+        .filter_map(|glyph| run.text[glyph.start..glyph.end].chars().next())
+        .filter(|c| !c.is_whitespace())
+        .fold((0usize, 0usize), |(cjk, total), c| {
+            let is_cjk = matches!(
+                c.script(),
+                Script::Han | Script::Hiragana | Script::Katakana | Script::Bopomofo
+            );
+            (cjk + usize::from(is_cjk), total + 1)
+        });
+    cjk * 2 > total
+}
+
+/// The advance each of the first `run.glyphs.len()` glyphs of a line gains
+/// so the line grows by `extra`. Word gaps take at most `max_word_stretch`
+/// each, and whatever they cannot take is spread between clusters.
+pub(crate) fn added_advances(
+    run: &LayoutRun,
+    extra: f32,
+    distribution: JustifyDistribution,
+    max_word_stretch: Option<f32>,
+) -> Vec<f32> {
+    let glyphs = run.glyphs;
+    let cluster = |index: usize| &run.text[glyphs[index].start..glyphs[index].end];
+    let content_end = (0..glyphs.len())
+        .filter(|index| !is_space(cluster(*index)))
+        .map(|index| glyphs[index].end)
+        .max()
+        .unwrap_or(0);
+    let hangs = |index: usize| glyphs[index].start >= content_end;
+    // A gap follows the last glyph of each cluster, in visual order, short
+    // of the last one on the line
+    let last_content = (0..glyphs.len()).rev().find(|index| !hangs(*index));
+    let character_gaps: Vec<usize> = (0..glyphs.len())
+        .filter(|index| Some(*index) < last_content && !hangs(*index))
+        .filter(|index| glyphs[index + 1].start != glyphs[*index].start)
+        .collect();
+    let word_gaps: Vec<usize> = (0..glyphs.len())
+        .filter(|index| !hangs(*index) && is_word_separator(cluster(*index)))
+        .collect();
+
+    let inter_word = match distribution {
+        JustifyDistribution::InterWord => true,
+        JustifyDistribution::InterCharacter => false,
+        JustifyDistribution::Auto => !is_mostly_cjk(run),
+    };
+    let mut added = vec![0.0; glyphs.len()];
+    let mut remaining = extra;
+    if inter_word && !word_gaps.is_empty() {
+        let mut per_gap = extra / word_gaps.len() as f32;
+        if let Some(max) = max_word_stretch.filter(|max| *max >= 0.0 && max.is_finite()) {
+            per_gap = per_gap.min(max);
+        }
+        for &index in &word_gaps {
+            added[index] += per_gap;
+        }
+        remaining -= per_gap * word_gaps.len() as f32;
+    }
+    if remaining > 0.0 && !character_gaps.is_empty() {
+        let per_gap = remaining / character_gaps.len() as f32;
+        for &index in &character_gaps {
+            added[index] += per_gap;
+        }
+    }
+    added
+}
+
+/// Widen glyphs by `added`, moving each later glyph and its ink box right.
+/// Glyphs past the end of `added`, such as a visible hyphen, only move.
+pub(crate) fn stretch(glyphs: &mut [ShapedGlyph], ink: &mut [Option<InkBounds>], added: &[f32]) {
+    let mut shift = 0.0;
+    for (index, glyph) in glyphs.iter_mut().enumerate() {
+        glyph.x += shift;
+        if let Some(Some(bounds)) = ink.get_mut(index) {
+            *bounds = bounds.translate(shift, 0.0);
+        }
+        let grow = added.get(index).copied().unwrap_or(0.0);
+        glyph.x_advance += grow;
+        shift += grow;
+    }
+}
@@ -3,13 +3,67 @@
 //! Provides text shaping, font management, and glyph positioning
 //! via wasm-bindgen for use in Glade.
 
+mod baseline;
+mod breaks;
+#[cfg(feature = "woff2")]
+mod brotli;
+mod cache;
+mod case;
+#[cfg(feature = "color-emoji")]
+mod colr;
+mod content_hash;
+mod finite;
+mod hyphenate;
+mod ink;
+mod justify;
+mod metadata;
+mod metrics;
+mod path_walk;
+mod prepared;
+#[cfg(feature = "raster")]
+mod raster;
+#[cfg(feature = "raster")]
+mod scaler;
+mod visible;
+mod whitespace;
+mod woff;
+mod words;
+
+pub use baseline::BaselineShift;
+use baseline::FaceExtents;
+pub use breaks::{BreakReason, WrapMode};
+use cache::LruCache;
+pub use case::{CaseMapping, CaseTransform, TextCase};
+use content_hash::ContentHasher;
+use cosmic_text::fontdb::{Database, Query, Source};
 use cosmic_text::{
-    Attrs, Buffer, CacheKey, CacheKeyFlags, Family, FeatureTag, FontFeatures, FontSystem, Metrics,
-    ShapeBuffer, Shaping, Stretch, Style, SwashCache, Weight, Wrap,
+    Attrs, Buffer, CacheKey, CacheKeyFlags, Fallback, Family, FeatureTag, FontFeatures, FontSystem,
+    LayoutGlyph, LayoutRun, Metrics, PlatformFallback, ShapeBuffer, Shaping, Stretch, Style,
+    SwashCache, Weight, Wrap,
 };
+pub use finite::NonFiniteValue;
+use hyphenate::{HyphenationPatterns, SOFT_HYPHEN};
+pub use ink::InkBounds;
+pub use justify::JustifyDistribution;
+pub use metadata::{FontAxis, RegisteredFont};
+pub use metrics::{MetricsError, MetricsField, MAX_FONT_SIZE};
+use path_walk::PathWalker;
+use prepared::{Edit, PreparedText};
+#[cfg(feature = "raster")]
+pub use raster::RasterOptions;
+#[cfg(feature = "raster")]
+use scaler::GlyphScaler;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use unicode_script::Script;
+pub use visible::{ClippedGlyph, VisibleGlyphRange};
 use wasm_bindgen::prelude::*;
+pub use whitespace::WhiteSpace;
+pub use woff::{FontContainer, FontDataError};
+pub use words::WordBox;
 
 /// Opaque font ID exposed to JS.
 #[wasm_bindgen]
@@ -38,33 +92,221 @@ pub struct ShapedGlyph {
     pub y_offset: f32,
     pub start: usize,
     pub end: usize,
+    /// How far the glyph was raised by its span's
+    /// `RichTextSpan::baseline_shift`, negative when lowered. Already in
+    /// `y`; a selection box around the glyph moves up by the same amount.
+    #[serde(default)]
+    pub baseline_shift: f32,
 }
 
 /// A shaped line of text with metrics.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ShapedLineResult {
     pub glyphs: Vec<ShapedGlyph>,
+    /// Advance width without trailing whitespace, which hangs past the end
+    /// of the line as in browsers
     pub width: f32,
+    /// Advance width including trailing whitespace, where a caret after it
+    /// goes
+    pub width_with_trailing_whitespace: f32,
     pub height: f32,
     pub ascent: f32,
     pub descent: f32,
+    /// The line height is below the font size, so lines stacked at it would
+    /// overlap
+    pub clipped_lines: bool,
 }
 
 /// A laid out line from multi-line text.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LayoutLine {
     pub glyphs: Vec<ShapedGlyph>,
+    /// Advance width without trailing whitespace, for alignment and fitting.
+    /// The whitespace glyphs are still in `glyphs`.
     pub width: f32,
+    /// Advance width including trailing whitespace
+    pub width_with_trailing_whitespace: f32,
     pub y: f32,
     pub line_height: f32,
+    /// How far the line's glyphs reach above `y`, with shifted glyphs at
+    /// their shifted positions. Raised glyphs reaching past the text make
+    /// room by moving the line down.
+    pub ascent: f32,
+    /// How far the line's glyphs reach below `y`. Lowered glyphs reaching
+    /// past the text make `line_height` taller.
+    pub descent: f32,
+    /// The line's glyphs grouped for drawing, when requested with
+    /// `LayoutOptions::draw_runs`; empty otherwise.
+    pub draw_runs: Vec<DrawRun>,
+    /// How far `y` was moved down to land on `LayoutOptions::baseline_grid`;
+    /// zero without a grid.
+    pub baseline_shift: f32,
+    /// Whether the line breaks at a soft hyphen, in which case its last
+    /// glyph is a visible hyphen counted in `width`.
+    pub hyphenated: bool,
+    /// Why the line ends, or None for the last line of the text.
+    pub break_reason: Option<BreakReason>,
+    /// Where the line breaks, as a glyph offset: the start of the next
+    /// line's text, or the end of the paragraph for a newline. None with
+    /// `break_reason`.
+    pub break_offset: Option<usize>,
+    /// Ink box of each glyph in `glyphs`, in layout space with y down, or
+    /// None for a glyph that paints nothing, when requested with
+    /// `LayoutOptions::ink_bounds`; empty otherwise.
+    pub glyph_ink_bounds: Vec<Option<InkBounds>>,
+    /// Union of `glyph_ink_bounds`, which can reach past `width` for
+    /// overhanging italics and marks. None when not requested or when the
+    /// line paints nothing.
+    pub ink_bounds: Option<InkBounds>,
+}
+
+/// Consecutive glyphs of a line that share a font and color, so a renderer
+/// can draw them with one texture bind. Runs cover the line's glyphs in
+/// order, which is visual order, left to right.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DrawRun {
+    pub cosmic_font_id: u64,
+    /// Packed 0xRRGGBBAA, or None for the host's default text color
+    pub color: Option<u32>,
+    /// Index of the run's first glyph in `LayoutLine::glyphs`
+    pub glyph_start: usize,
+    /// Index one past the run's last glyph
+    pub glyph_end: usize,
+}
+
+/// A drop shadow for `LayoutOptions::shadows`. Blurring is left to the
+/// renderer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextShadow {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// Packed 0xRRGGBBAA
+    pub color: u32,
+}
+
+/// Glyphs of a line drawn again for a shadow, offset and in the shadow's
+/// color. They are the glyphs of the main text, not copies, so selection
+/// and caret positions never see them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShadowRun {
+    /// Index of the line in `LayoutResult::lines`
+    pub line: usize,
+    pub cosmic_font_id: u64,
+    /// Packed 0xRRGGBBAA
+    pub color: u32,
+    /// Index of the run's first glyph in `LayoutLine::glyphs`
+    pub glyph_start: usize,
+    /// Index one past the run's last glyph
+    pub glyph_end: usize,
+    /// Origin the glyphs' own positions are added to, in place of the
+    /// line's `(0, y)`: the shadow's offset is already applied
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Options for `layout_text` and `layout_rich_text`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutOptions {
+    /// Fill each line's `draw_runs`
+    pub draw_runs: bool,
+    /// Fill each line's `glyph_ink_bounds` and `ink_bounds`, for damage
+    /// rectangles. Off by default, since it roughly doubles the result.
+    pub ink_bounds: bool,
+    /// Move each line down so its baseline falls on a multiple of this
+    /// spacing. Lines keep at least their natural distance apart, so a
+    /// shift carries over to the lines below. Ignored unless positive.
+    pub baseline_grid: Option<f32>,
+    /// Break long words where the patterns registered for `language` allow,
+    /// as if the text had soft hyphens there. `layout_text` only.
+    pub hyphenate: bool,
+    /// Language of the text, as a BCP 47 tag: picks the patterns used by
+    /// `hyphenate`, as registered with `register_hyphenation_patterns`, and
+    /// the casing rules of `text_case`.
+    pub language: Option<String>,
+    /// How spaces, tabs and newlines are laid out. Glyph offsets still index
+    /// the text as passed in; with newlines collapsed, they count from the
+    /// start of the paragraph a line's text began in. `layout_text` only.
+    pub white_space: WhiteSpace,
+    /// Lay out the text in this case. Glyph offsets still index the text as
+    /// passed in; the glyphs of a character whose case mapping is longer,
+    /// such as `ß` uppercased to `SS`, all fall within its range.
+    /// `layout_text` only.
+    pub text_case: Option<TextCase>,
+    /// Fill `LayoutResult::shadow_runs` with these shadows, the first on
+    /// top as in CSS `text-shadow`. Shadows with a non-finite offset are
+    /// skipped.
+    pub shadows: Vec<TextShadow>,
+    /// Where lines may break when the text is too wide. Lines do not wrap
+    /// at all with `white_space` set to `pre`.
+    pub wrap: WrapMode,
+    /// Drop the lines after this many, ending the last one kept with
+    /// `BreakReason::MaxLines`. Ignored unless positive.
+    pub max_lines: Option<usize>,
+    /// Fill `LayoutResult::rejected_breaks`, for hosts polishing where the
+    /// last line starts.
+    pub break_candidates: bool,
+    /// Stretch lines that wrapped to the layout width, leaving the last
+    /// line of each paragraph as it is. Glyph advances grow to cover the
+    /// space added after them.
+    pub justify: bool,
+    /// Where `justify` adds space.
+    pub justify_distribution: JustifyDistribution,
+    /// Most space `justify` adds to one word gap, in pixels; the rest is
+    /// spread between characters. Ignored unless finite and not negative.
+    pub max_inter_word_stretch: Option<f32>,
+}
+
+/// A run of text with its own style and color, for `layout_rich_text`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RichTextSpan {
+    pub text: String,
+    pub style: FontStyleInput,
+    /// Packed 0xRRGGBBAA, or None for the host's default text color
+    pub color: Option<u32>,
+    /// Raise or lower the span, such as to `super` for an exponent. Lines
+    /// grow to fit the shifted glyphs.
+    pub baseline_shift: Option<BaselineShift>,
+    /// Multiple of the layout's font size the span is drawn at. Ignored
+    /// unless positive and finite.
+    pub font_scale: Option<f32>,
+}
+
+#[cfg(feature = "rich-text")]
+impl RichTextSpan {
+    fn font_size(&self, font_size: f32) -> f32 {
+        match self.font_scale {
+            Some(scale) if scale > 0.0 && scale.is_finite() => font_size * scale,
+            _ => font_size,
+        }
+    }
 }
 
 /// Multi-line layout result.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct LayoutResult {
     pub lines: Vec<LayoutLine>,
     pub total_width: f32,
     pub total_height: f32,
+    /// The line height is below the font size, so lines overlap
+    pub clipped_lines: bool,
+    /// Runs for `LayoutOptions::shadows`, in drawing order: the last shadow
+    /// first, each over every line, all before any line's own glyphs.
+    pub shadow_runs: Vec<ShadowRun>,
+    /// Line break opportunities within the last line, which layout passed
+    /// over because the text after them fit, as glyph offsets. Only filled
+    /// when requested with `LayoutOptions::break_candidates`.
+    pub rejected_breaks: Vec<usize>,
+}
+
+/// Text dimensions from `measure_text`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MeasureResult {
+    pub width: f32,
+    pub height: f32,
+    /// The line height is below the font size, so lines overlap
+    pub clipped_lines: bool,
 }
 
 /// Font metrics for a loaded font.
@@ -89,7 +331,7 @@ pub struct RasterizedGlyph {
 }
 
 /// Font style input from JavaScript.
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FontStyleInput {
     pub family: Option<String>,
     pub weight: Option<u16>,
@@ -97,6 +339,102 @@ pub struct FontStyleInput {
     pub stretch: Option<String>,
 }
 
+/// Cache occupancy and counters from `cache_stats`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub shaped_lines: usize,
+    pub shaped_line_bytes: usize,
+    pub glyphs: usize,
+    pub glyph_bytes: usize,
+    pub budget_bytes: Option<usize>,
+    pub shape_hits: u64,
+    pub shape_misses: u64,
+    pub glyph_hits: u64,
+    pub rasterizations: u64,
+    pub evictions: u64,
+    /// Faces with rasterization state kept, at most `MAX_RASTER_FACES`
+    pub raster_faces: usize,
+}
+
+/// A rasterized glyph dropped from the glyph cache. Hosts remove the matching
+/// atlas entry so the space can be repacked.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EvictedGlyph {
+    pub cosmic_font_id: u64,
+    pub glyph_id: u32,
+    /// The bucketed size the glyph was rasterized at
+    pub font_size: f32,
+}
+
+/// Default font size quantization step in pixels. Sizes that round to the
+/// same step share shaped lines and rasterized glyphs, so a font-size
+/// animation fills the caches once per step rather than once per frame.
+pub const DEFAULT_SIZE_BUCKET: f32 = 0.5;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ShapedLineKey {
+    text: String,
+    font_size_bits: u32,
+    line_height_bits: u32,
+    style: FontStyleInput,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    cosmic_font_id: u64,
+    glyph_id: u32,
+    font_size_bits: u32,
+}
+
+/// How cluster advances map to distance along the path in
+/// `layout_text_on_path`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SpacingMode {
+    /// Clusters keep their shaped advances
+    #[default]
+    Exact,
+    /// Spacing stretches or shrinks so the text ends at the end of the path
+    Fit,
+}
+
+/// Options for `layout_text_on_path`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextPathOptions {
+    /// Distance along the path where the text starts
+    pub start_offset: f32,
+    pub spacing_mode: SpacingMode,
+    /// Drop clusters that fall outside the path instead of placing them on
+    /// the extended end tangent
+    pub clip_overflow: bool,
+}
+
+/// A glyph placed along a path. `x`/`y` is the glyph origin on the baseline,
+/// including its shaped x offset, and the glyph is drawn rotated by
+/// `rotation_rad` around that point.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PathGlyph {
+    pub glyph_id: u32,
+    pub cosmic_font_id: u64,
+    pub x: f32,
+    pub y: f32,
+    pub rotation_rad: f32,
+    pub start: usize,
+    pub end: usize,
+    /// Whether the glyph's cluster falls outside the path
+    pub overflow: bool,
+}
+
+/// Text laid out along a path.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TextOnPathResult {
+    pub glyphs: Vec<PathGlyph>,
+    pub path_length: f32,
+    /// Clusters that fell outside the path, whether clipped or placed
+    pub overflow_clusters: usize,
+}
+
 /// Internal font properties extracted from font file.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct FontInfo {
@@ -106,11 +444,44 @@ struct FontInfo {
     weight: u16,
 }
 
+/// Font fallback that tries the host's fallback chain before any other font.
+/// An empty chain defers to the platform lists cosmic-text uses by default.
+#[derive(Default)]
+struct ChainFallback {
+    families: Vec<&'static str>,
+}
+
+impl Fallback for ChainFallback {
+    fn common_fallback(&self) -> &[&'static str] {
+        if self.families.is_empty() {
+            return PlatformFallback.common_fallback();
+        }
+        &self.families
+    }
+
+    fn forbidden_fallback(&self) -> &[&'static str] {
+        PlatformFallback.forbidden_fallback()
+    }
+
+    // Script lists are consulted before the common list, so the chain has to
+    // stand in for them to take priority
+    fn script_fallback(&self, script: Script, locale: &str) -> &[&'static str] {
+        if self.families.is_empty() {
+            return PlatformFallback.script_fallback(script, locale);
+        }
+        &self.families
+    }
+}
+
 /// Text shaping engine using cosmic-text.
 #[wasm_bindgen]
 pub struct TextShaper {
     font_system: FontSystem,
+    /// Outline extraction for ink bounds
     swash_cache: SwashCache,
+    /// Glyph rasterization
+    #[cfg(feature = "raster")]
+    glyph_scaler: GlyphScaler,
     #[allow(dead_code)]
     shape_buffer: ShapeBuffer,
     font_data: HashMap<u32, Vec<u8>>,
@@ -119,37 +490,86 @@ pub struct TextShaper {
     font_name_to_info: HashMap<String, FontInfo>,
     /// Maps serialized cosmic font ID (u64) back to fontdb::ID for rasterization
     cosmic_id_to_fontdb: HashMap<u64, cosmic_text::fontdb::ID>,
+    /// Memoized fontdb::ID to serialized u64 conversions
+    serialized_font_ids: HashMap<cosmic_text::fontdb::ID, u64>,
+    /// Faces loaded by each registration, in file order
+    font_faces: HashMap<u32, Vec<cosmic_text::fontdb::ID>>,
+    /// Maps each loaded face back to the registration that loaded it
+    face_registrations: HashMap<cosmic_text::fontdb::ID, u32>,
+    /// Family names of the fallback chain, highest priority first
+    fallback_families: Vec<&'static str>,
+    /// Family names leaked to 'static for `Attrs` and `Fallback`, leaked once per distinct name
+    static_family_names: HashMap<String, &'static str>,
+    /// Font size quantization step in pixels, 0 to disable
+    size_bucket: f32,
+    /// Combined byte budget for the shaped-line and glyph caches
+    cache_budget: Option<usize>,
+    shaped_lines: LruCache<ShapedLineKey, ShapedLineResult>,
+    glyphs: LruCache<GlyphKey, RasterizedGlyph>,
+    /// Glyphs evicted since the host last collected them
+    evicted_glyphs: Vec<EvictedGlyph>,
+    /// Hit, miss and eviction counters; occupancy is filled in on read
+    cache_counters: CacheStats,
+    /// Hyphenation patterns by language tag
+    hyphenation: HashMap<String, HyphenationPatterns>,
+    /// Glyph ink boxes by outline cache key, None for glyphs without ink
+    ink_extents: HashMap<CacheKey, Option<InkBounds>>,
+    /// Vertical metrics of each face glyphs were laid out in
+    face_extents: HashMap<cosmic_text::fontdb::ID, FaceExtents>,
 }
 
 #[wasm_bindgen]
 impl TextShaper {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        let font_system = FontSystem::new_with_locale_and_db(
+        let font_system = FontSystem::new_with_locale_and_db_and_fallback(
             "en-US".to_string(),
-            cosmic_text::fontdb::Database::new(),
+            Database::new(),
+            ChainFallback::default(),
         );
         Self {
             font_system,
             swash_cache: SwashCache::new(),
+            #[cfg(feature = "raster")]
+            glyph_scaler: GlyphScaler::new(),
             shape_buffer: ShapeBuffer::default(),
             font_data: HashMap::new(),
             cosmic_id_to_fontdb: HashMap::new(),
             next_font_id: 0,
             font_name_to_info: HashMap::new(),
+            serialized_font_ids: HashMap::new(),
+            font_faces: HashMap::new(),
+            face_registrations: HashMap::new(),
+            fallback_families: Vec::new(),
+            static_family_names: HashMap::new(),
+            size_bucket: DEFAULT_SIZE_BUCKET,
+            cache_budget: None,
+            shaped_lines: LruCache::new(),
+            glyphs: LruCache::new(),
+            evicted_glyphs: Vec::new(),
+            cache_counters: CacheStats::default(),
+            hyphenation: HashMap::new(),
+            ink_extents: HashMap::new(),
+            face_extents: HashMap::new(),
         }
     }
 
-    /// Register a font from raw font data (TTF/OTF bytes).
+    /// Register a font from raw font data: TTF/OTF bytes, or a WOFF or WOFF2
+    /// file, which is unpacked first.
     /// Returns a FontId that can be used to reference this font.
     #[wasm_bindgen]
     pub fn register_font(&mut self, font_data: &[u8]) -> Result<FontId, JsValue> {
+        Ok(self.register_font_native(font_data)?)
+    }
+
+    /// Native counterpart of `register_font`.
+    pub fn register_font_native(&mut self, font_data: &[u8]) -> Result<FontId, FontDataError> {
+        let font_data = woff::decode(font_data)?;
         let id = self.next_font_id;
         self.next_font_id += 1;
 
-        self.font_data.insert(id, font_data.to_vec());
-
-        self.font_system.db_mut().load_font_data(font_data.to_vec());
+        self.load_faces(id, &font_data);
+        self.font_data.insert(id, font_data.into_owned());
 
         Ok(FontId(id))
     }
@@ -164,29 +584,18 @@ impl TextShaper {
         name: &str,
         font_data: &[u8],
     ) -> Result<FontId, JsValue> {
-        let count_before = self.font_system.db().len();
-
+        let font_data = woff::decode(font_data)?;
         let id = self.next_font_id;
         self.next_font_id += 1;
 
-        self.font_data.insert(id, font_data.to_vec());
-
-        self.font_system.db_mut().load_font_data(font_data.to_vec());
-
-        // Find the newly added font(s) and extract their properties
-        let db = self.font_system.db();
-        let faces: Vec<_> = db.faces().collect();
-
-        if faces.len() > count_before {
-            // Register all newly added faces in the cosmic_id_to_fontdb mapping
-            for face in faces.iter().skip(count_before) {
-                let cosmic_id: u64 = format!("{}", face.id).parse().unwrap_or(0);
-                self.cosmic_id_to_fontdb.insert(cosmic_id, face.id);
-            }
-
-            // Use the first newly added face for font info (primary variant)
-            let face = &faces[count_before];
+        let faces = self.load_faces(id, &font_data);
+        self.font_data.insert(id, font_data.into_owned());
 
+        // Use the first face for font info (primary variant)
+        if let Some(face) = faces
+            .first()
+            .and_then(|&face| self.font_system.db().face(face))
+        {
             // Get the English family name (first in the list)
             let family = face
                 .families
@@ -203,6 +612,70 @@ impl TextShaper {
         Ok(FontId(id))
     }
 
+    /// Register several font files at once, given as an array of
+    /// Uint8Arrays. Returns a `RegisteredFont` for each file, in order, with
+    /// its id and metadata. A file that fails to load gets an entry with
+    /// only `error` set, and the rest of the batch still registers.
+    #[wasm_bindgen]
+    pub fn register_fonts_batch(&mut self, fonts_js: js_sys::Array) -> Result<JsValue, JsValue> {
+        let fonts: Vec<Vec<u8>> = fonts_js
+            .iter()
+            .map(|font| js_sys::Uint8Array::new(&font).to_vec())
+            .collect();
+        let registered = self.register_fonts_batch_native(fonts);
+        serde_wasm_bindgen::to_value(&registered)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// The registered font shaping would pick first for `family` at a
+    /// weight and style (`normal`, `italic` or `oblique`), or `undefined`
+    /// when no registered face belongs to the family. Names from
+    /// `register_font_with_name` resolve as they do in shaping.
+    #[wasm_bindgen]
+    pub fn find_font(&mut self, family: &str, weight: u16, style: Option<String>) -> Option<u32> {
+        self.find_font_native(&FontStyleInput {
+            family: Some(family.to_string()),
+            weight: Some(weight),
+            style,
+            stretch: None,
+        })
+    }
+
+    /// Set the fonts to try, in order, for characters the requested family
+    /// does not cover. Styles without a family use the first font in the
+    /// chain. Fonts are matched by family name, so faces of one family move
+    /// together. An empty chain restores the platform fallback lists.
+    #[wasm_bindgen]
+    pub fn set_fallback_chain(&mut self, font_ids_js: Vec<u32>) -> Result<(), JsValue> {
+        self.set_fallback_chain_native(&font_ids_js)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Load Knuth–Liang hyphenation patterns for a language, replacing any
+    /// registered for it before, so `layout_text` can hyphenate with them.
+    /// `patterns_bytes` is a UTF-8 pattern file, either whitespace-separated
+    /// patterns or TeX `\patterns{}` and `\hyphenation{}` blocks.
+    #[wasm_bindgen]
+    pub fn register_hyphenation_patterns(
+        &mut self,
+        lang: &str,
+        patterns_bytes: &[u8],
+    ) -> Result<(), JsValue> {
+        let source = std::str::from_utf8(patterns_bytes).map_err(|e| {
+            JsValue::from_str(&format!("Hyphenation patterns are not UTF-8: {}", e))
+        })?;
+        self.register_hyphenation_patterns_native(lang, source)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// The registered font that shaping would use for `ch` under `style_js`,
+    /// or `undefined` if no registered font covers it.
+    #[wasm_bindgen]
+    pub fn effective_font_for(&mut self, ch: char, style_js: JsValue) -> Option<u32> {
+        let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
+        self.effective_font_for_native(ch, &style)
+    }
+
     /// Get the internal font info for a registered name.
     /// Returns the internal family name and weight if found.
     #[wasm_bindgen]
@@ -232,60 +705,14 @@ impl TextShaper {
         style_js: JsValue,
     ) -> Result<JsValue, JsValue> {
         let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
-
-        let attrs = self.build_attrs(&style);
-        let metrics = Metrics::new(font_size, line_height);
-
-        // Use Buffer for shaping - set a large width to allow cosmic-text to
-        // process the text without word wrapping
-        let mut buffer = Buffer::new(&mut self.font_system, metrics);
-        buffer.set_size(&mut self.font_system, Some(f32::MAX), None);
-        buffer.set_wrap(&mut self.font_system, Wrap::None);
-        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
-        buffer.shape_until_scroll(&mut self.font_system, false);
-
-        let mut glyphs = Vec::new();
-        let mut total_width = 0.0f32;
-        let mut max_ascent = 0.0f32;
-        let mut max_descent = 0.0f32;
-
-        for run in buffer.layout_runs() {
-            for glyph in run.glyphs.iter() {
-                let cosmic_font_id: u64 = format!("{}", glyph.font_id).parse().unwrap_or(0);
-                glyphs.push(ShapedGlyph {
-                    glyph_id: glyph.glyph_id as u32,
-                    cosmic_font_id,
-                    x: glyph.x,
-                    y: glyph.y,
-                    x_advance: glyph.w,
-                    y_advance: 0.0,
-                    x_offset: glyph.x_offset,
-                    y_offset: glyph.y_offset,
-                    start: glyph.start,
-                    end: glyph.end,
-                });
-
-                total_width = total_width.max(glyph.x + glyph.w);
-            }
-
-            // Get metrics from run
-            max_ascent = max_ascent.max(run.line_top);
-            max_descent = max_descent.max(run.line_height - run.line_top);
-        }
-
-        let result = ShapedLineResult {
-            glyphs,
-            width: total_width,
-            height: line_height,
-            ascent: max_ascent,
-            descent: max_descent,
-        };
-
+        let mut result = self.shape_line_native(text, font_size, line_height, &style)?;
+        finite::enforce_finite(&mut result).map_err(|e| JsValue::from_str(&e.to_string()))?;
         serde_wasm_bindgen::to_value(&result)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    /// Layout multi-line text with word wrapping.
+    /// Layout multi-line text with word wrapping. `options_js` is an
+    /// optional `LayoutOptions`.
     #[wasm_bindgen]
     pub fn layout_text(
         &mut self,
@@ -294,64 +721,53 @@ impl TextShaper {
         line_height: f32,
         max_width: f32,
         style_js: JsValue,
+        options_js: JsValue,
     ) -> Result<JsValue, JsValue> {
         let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
-        let attrs = self.build_attrs(&style);
-        let metrics = Metrics::new(font_size, line_height);
-
-        let mut buffer = Buffer::new(&mut self.font_system, metrics);
-        buffer.set_size(&mut self.font_system, Some(max_width), None);
-        buffer.set_wrap(&mut self.font_system, Wrap::Word);
-        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
-        buffer.shape_until_scroll(&mut self.font_system, false);
-
-        let mut lines = Vec::new();
-        let mut total_height = 0.0f32;
-        let mut max_width_seen = 0.0f32;
-
-        for run in buffer.layout_runs() {
-            let mut line_glyphs = Vec::new();
-            let mut line_width = 0.0f32;
-
-            for glyph in run.glyphs.iter() {
-                let cosmic_font_id: u64 = format!("{}", glyph.font_id).parse().unwrap_or(0);
-                line_glyphs.push(ShapedGlyph {
-                    glyph_id: glyph.glyph_id as u32,
-                    cosmic_font_id,
-                    x: glyph.x,
-                    y: glyph.y,
-                    x_advance: glyph.w,
-                    y_advance: 0.0,
-                    x_offset: glyph.x_offset,
-                    y_offset: glyph.y_offset,
-                    start: glyph.start,
-                    end: glyph.end,
-                });
-
-                line_width = line_width.max(glyph.x + glyph.w);
-            }
-
-            lines.push(LayoutLine {
-                glyphs: line_glyphs,
-                width: line_width,
-                y: run.line_y,
-                line_height: run.line_height,
-            });
-
-            max_width_seen = max_width_seen.max(line_width);
-            total_height = total_height.max(run.line_y + run.line_height);
-        }
-
-        let result = LayoutResult {
-            lines,
-            total_width: max_width_seen,
-            total_height,
-        };
+        let options = parse_options(options_js)?;
+        let mut result =
+            self.layout_text_native(text, font_size, line_height, max_width, &style, &options)?;
+        finite::enforce_finite(&mut result).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
+    /// Layout multi-line text made of spans with their own styles and colors,
+    /// wrapping across span boundaries. `spans_js` is an array of
+    /// `{ text, style, color }` and `options_js` an optional `LayoutOptions`.
+    #[cfg(feature = "rich-text")]
+    #[wasm_bindgen]
+    pub fn layout_rich_text(
+        &mut self,
+        spans_js: JsValue,
+        font_size: f32,
+        line_height: f32,
+        max_width: f32,
+        options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let spans: Vec<RichTextSpan> = serde_wasm_bindgen::from_value(spans_js)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse spans: {}", e)))?;
+        let options = parse_options(options_js)?;
+        let mut result =
+            self.layout_rich_text_native(&spans, font_size, line_height, max_width, &options)?;
+        finite::enforce_finite(&mut result).map_err(|e| JsValue::from_str(&e.to_string()))?;
         serde_wasm_bindgen::to_value(&result)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
+    #[cfg(not(feature = "rich-text"))]
+    #[wasm_bindgen]
+    pub fn layout_rich_text(
+        &mut self,
+        _spans_js: JsValue,
+        _font_size: f32,
+        _line_height: f32,
+        _max_width: f32,
+        _options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        Err(feature_disabled("rich-text"))
+    }
+
     /// Measure text dimensions without full layout.
     #[wasm_bindgen]
     pub fn measure_text(
@@ -363,95 +779,42 @@ impl TextShaper {
         style_js: JsValue,
     ) -> Result<JsValue, JsValue> {
         let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
-        let attrs = self.build_attrs(&style);
-        let metrics = Metrics::new(font_size, line_height);
-
-        // Handle explicit newlines when wrapping is disabled. cosmic-text with
-        // Wrap::None does not allocate multiple layout runs for '\n', so we
-        // split and measure each line ourselves.
-        if max_width.is_none() && text.contains('\n') {
-            let ascent_offset = font_size * 0.8;
-            let mut max_width_seen = 0.0f32;
-            let mut line_count = 0usize;
-
-            for line in text.split('\n') {
-                let mut buffer = Buffer::new(&mut self.font_system, metrics);
-                buffer.set_size(&mut self.font_system, Some(f32::MAX), None);
-                buffer.set_wrap(&mut self.font_system, Wrap::None);
-                buffer.set_text(&mut self.font_system, line, &attrs, Shaping::Advanced, None);
-                buffer.shape_until_scroll(&mut self.font_system, false);
-
-                let mut line_width = 0.0f32;
-                for run in buffer.layout_runs() {
-                    for glyph in run.glyphs.iter() {
-                        line_width = line_width.max(glyph.x + glyph.w);
-                    }
-                }
-
-                max_width_seen = max_width_seen.max(line_width);
-                line_count += 1;
-            }
-
-            #[derive(Serialize)]
-            struct MeasureResult {
-                width: f32,
-                height: f32,
-            }
-
-            let result = MeasureResult {
-                width: max_width_seen,
-                height: line_height * line_count as f32 + ascent_offset,
-            };
-
-            return serde_wasm_bindgen::to_value(&result)
-                .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)));
-        }
-
-        let mut buffer = Buffer::new(&mut self.font_system, metrics);
-        // When no max_width is provided, use a very large value to allow cosmic-text
-        // to process newlines while not wrapping. This enables whitespace: pre behavior.
-        let effective_width = max_width.unwrap_or(f32::MAX);
-        buffer.set_size(&mut self.font_system, Some(effective_width), None);
-        buffer.set_wrap(
-            &mut self.font_system,
-            if max_width.is_some() {
-                Wrap::Word
-            } else {
-                Wrap::None
-            },
-        );
-        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
-        buffer.shape_until_scroll(&mut self.font_system, false);
-
-        let mut total_width = 0.0f32;
-        let mut total_height = 0.0f32;
-
-        for run in buffer.layout_runs() {
-            let mut line_width = 0.0f32;
-            for glyph in run.glyphs.iter() {
-                line_width = line_width.max(glyph.x + glyph.w);
-            }
-            total_width = total_width.max(line_width);
-            total_height = total_height.max(run.line_y + run.line_height);
-        }
-
-        #[derive(Serialize)]
-        struct MeasureResult {
-            width: f32,
-            height: f32,
-        }
+        let mut result =
+            self.measure_text_native(text, font_size, line_height, max_width, &style)?;
+        finite::enforce_finite(&mut result).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-        let result = MeasureResult {
-            width: total_width,
-            height: total_height,
+    /// Lay out a single line of text along an SVG path. Each cluster is
+    /// centered at its arc-length position and rotated to follow the path.
+    /// `options_js` is an optional `{ start_offset, spacing_mode, clip_overflow }`.
+    #[wasm_bindgen]
+    pub fn layout_text_on_path(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        style_js: JsValue,
+        path_d: &str,
+        options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
+        let options: TextPathOptions = if options_js.is_undefined() || options_js.is_null() {
+            TextPathOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?
         };
-
+        let result = self.layout_text_on_path_native(text, font_size, &style, path_d, &options)?;
         serde_wasm_bindgen::to_value(&result)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
     /// Rasterize a glyph using cosmic-text's internal font ID and glyph ID.
-    /// This uses SwashCache which properly handles the cosmic-text internal glyph IDs.
+    /// This draws with swash, which takes the cosmic-text internal glyph IDs.
+    /// The glyph is rasterized at the bucketed font size and cached.
+    /// `options_js` is an optional `RasterOptions` choosing the pixel encoding.
+    #[cfg(feature = "raster")]
     #[wasm_bindgen]
     pub fn rasterize_glyph_by_cosmic_id(
         &mut self,
@@ -459,89 +822,26 @@ impl TextShaper {
         glyph_id: u32,
         font_size: f32,
         _weight: Option<u16>,
+        options_js: JsValue,
     ) -> Result<JsValue, JsValue> {
-        // Look up the fontdb::ID from our mapping
-        let font_id = match self.cosmic_id_to_fontdb.get(&cosmic_font_id) {
-            Some(&id) => id,
-            None => {
-                // Font ID not found - return empty glyph
-                let result = RasterizedGlyph {
-                    width: 0,
-                    height: 0,
-                    bearing_x: 0,
-                    bearing_y: 0,
-                    advance: 0.0,
-                    pixels: Vec::new(),
-                    is_color: false,
-                };
-                return serde_wasm_bindgen::to_value(&result)
-                    .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)));
-            }
-        };
-
-        // Create the proper CacheKey with the correct font_id
-        let (cache_key, _x_int, _y_int) = CacheKey::new(
-            font_id,
-            glyph_id as u16,
-            font_size,
-            (0.0, 0.0),
-            Weight::NORMAL,
-            CacheKeyFlags::empty(),
-        );
-
-        // Use SwashCache to get the glyph image
-        let image = self
-            .swash_cache
-            .get_image_uncached(&mut self.font_system, cache_key);
-
-        match image {
-            Some(img) => {
-                use cosmic_text::SwashContent;
-                let (pixels, is_color) = match img.content {
-                    SwashContent::Mask => (img.data.clone(), false),
-                    SwashContent::SubpixelMask => {
-                        // Extract alpha channel from subpixel glyphs
-                        let mut alpha = Vec::with_capacity(
-                            (img.placement.width as usize)
-                                .saturating_mul(img.placement.height as usize),
-                        );
-                        for chunk in img.data.chunks_exact(4) {
-                            alpha.push(chunk[3]);
-                        }
-                        (alpha, false)
-                    }
-                    SwashContent::Color => (img.data.clone(), true),
-                };
-
-                let result = RasterizedGlyph {
-                    width: img.placement.width,
-                    height: img.placement.height,
-                    bearing_x: img.placement.left,
-                    bearing_y: img.placement.top,
-                    advance: 0.0,
-                    pixels,
-                    is_color,
-                };
-
-                serde_wasm_bindgen::to_value(&result)
-                    .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
-            }
-            None => {
-                // Return empty glyph (e.g., for space character)
-                let result = RasterizedGlyph {
-                    width: 0,
-                    height: 0,
-                    bearing_x: 0,
-                    bearing_y: 0,
-                    advance: 0.0,
-                    pixels: Vec::new(),
-                    is_color: false,
-                };
+        let options: RasterOptions = parse_options(options_js)?;
+        let result =
+            self.rasterize_glyph_with_options_native(cosmic_font_id, glyph_id, font_size, &options);
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-                serde_wasm_bindgen::to_value(&result)
-                    .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
-            }
-        }
+    #[cfg(not(feature = "raster"))]
+    #[wasm_bindgen]
+    pub fn rasterize_glyph_by_cosmic_id(
+        &mut self,
+        _cosmic_font_id: u64,
+        _glyph_id: u32,
+        _font_size: f32,
+        _weight: Option<u16>,
+        _options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        Err(feature_disabled("raster"))
     }
 
     /// Rasterize a glyph at the given font size (legacy API, prefer rasterize_glyph_by_cosmic_id).
@@ -552,6 +852,7 @@ impl TextShaper {
         glyph_id: u32,
         font_size: f32,
         weight: Option<u16>,
+        options_js: JsValue,
     ) -> Result<JsValue, JsValue> {
         // For the legacy API, try to use the first registered font
         let db = self.font_system.db();
@@ -560,19 +861,176 @@ impl TextShaper {
         match first_face {
             Some(face) => {
                 let cosmic_font_id: u64 = format!("{}", face.id).parse().unwrap_or(0);
-                self.rasterize_glyph_by_cosmic_id(cosmic_font_id, glyph_id, font_size, weight)
+                self.rasterize_glyph_by_cosmic_id(
+                    cosmic_font_id,
+                    glyph_id,
+                    font_size,
+                    weight,
+                    options_js,
+                )
             }
             None => Err(JsValue::from_str("No fonts registered")),
         }
     }
 
-    /// Clear cached data to free memory.
+    /// The ink extents of one glyph at a font size, relative to its origin on
+    /// the baseline with y down, or null for a glyph that paints nothing.
+    /// Unlike advance metrics, these cover only the painted outline, for
+    /// centering icons and initials visually.
+    #[wasm_bindgen]
+    pub fn glyph_ink_bounds(
+        &mut self,
+        cosmic_font_id: u64,
+        glyph_id: u32,
+        font_size: f32,
+    ) -> Result<JsValue, JsValue> {
+        let bounds = self.glyph_ink_bounds_native(cosmic_font_id, glyph_id, font_size);
+        serde_wasm_bindgen::to_value(&bounds)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// The ink extents of a shaped string: the union of its glyphs' ink
+    /// boxes where shaping places them, relative to the first baseline with
+    /// y down, or null when nothing is painted. Lines are separated by the
+    /// font size. Overhanging glyphs such as "f" and "j" can reach past the
+    /// box `measure_text` reports.
+    #[wasm_bindgen]
+    pub fn ink_bounds(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        style_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
+        let bounds = self.ink_bounds_native(text, font_size, &style);
+        serde_wasm_bindgen::to_value(&bounds)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Lay out text like `layout_text` and return a box for each word, for
+    /// drawing focus and spoken-word highlights. Boxes span the word's glyphs
+    /// and the full height of its line; a word wrapped across lines gets one
+    /// box per line. Whitespace gets no box.
+    #[wasm_bindgen]
+    pub fn word_boxes(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        line_height: f32,
+        max_width: f32,
+        style_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
+        let boxes = self.word_boxes_native(text, font_size, line_height, max_width, &style);
+        serde_wasm_bindgen::to_value(&boxes)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Shape a single line like `shape_line` and find the glyphs that show
+    /// when it is scrolled left by `scroll_x` in a viewport `viewport_width`
+    /// wide, for single-line text fields. Indices are into the shaped
+    /// glyphs, which are in visual order.
+    #[wasm_bindgen]
+    pub fn visible_glyph_range(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        line_height: f32,
+        style_js: JsValue,
+        scroll_x: f32,
+        viewport_width: f32,
+    ) -> Result<JsValue, JsValue> {
+        let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
+        let range = self.visible_glyph_range_native(
+            text,
+            font_size,
+            line_height,
+            &style,
+            scroll_x,
+            viewport_width,
+        )?;
+        serde_wasm_bindgen::to_value(&range)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Clear cached data to free memory. Hosts should drop their glyph atlas
+    /// as well, since no evictions are reported for a full clear.
     #[wasm_bindgen]
     pub fn clear_cache(&mut self) {
         self.shape_buffer = ShapeBuffer::default();
+        self.shaped_lines.clear();
+        self.glyphs.clear();
+        self.evicted_glyphs.clear();
+        #[cfg(feature = "raster")]
+        self.glyph_scaler.clear();
+        self.ink_extents.clear();
+    }
+
+    /// Set the font size quantization step in pixels used for shaping and
+    /// rasterization cache keys. 0 disables bucketing. Clears the caches,
+    /// since existing entries were keyed with the old step.
+    #[wasm_bindgen]
+    pub fn set_size_bucket(&mut self, step: f32) {
+        self.size_bucket = step.max(0.0);
+        self.clear_cache();
+    }
+
+    /// Limit the combined size of the shaped-line and glyph caches, or remove
+    /// the limit with `undefined`. Returns the glyphs evicted to fit, least
+    /// recently used first, so the host can repack its atlas.
+    #[wasm_bindgen]
+    pub fn set_cache_budget(&mut self, bytes: Option<usize>) -> Result<JsValue, JsValue> {
+        let evicted = self.set_cache_budget_native(bytes);
+        serde_wasm_bindgen::to_value(&evicted)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Collect glyphs evicted by the cache budget since the last call.
+    #[wasm_bindgen]
+    pub fn take_evicted_glyphs(&mut self) -> Result<JsValue, JsValue> {
+        let evicted = self.take_evicted_glyphs_native();
+        serde_wasm_bindgen::to_value(&evicted)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// A 64-bit hash of a text input as 16 hex digits, for memoizing shaping
+    /// and layout. It covers `text`, `font_size` and every field of the
+    /// style, parsed as shaping parses it. Other arguments, such as line
+    /// height and options, and the engine's fonts are not covered: key them
+    /// alongside it.
+    #[wasm_bindgen]
+    pub fn hash_text_input(&self, text: &str, font_size: f32, style_js: JsValue) -> String {
+        let style: FontStyleInput = serde_wasm_bindgen::from_value(style_js).unwrap_or_default();
+        content_hash::to_hex(self.hash_text_input_native(text, font_size, &style))
+    }
+
+    /// Transform text to `"upper"`, `"lower"` or `"title"` case by the rules
+    /// of `lang`, a BCP 47 tag. Returns `{ text, mappings }`, where each
+    /// mapping pairs a byte range of the original text with the range of
+    /// the result it became, so selections made in either text can be moved
+    /// to the other.
+    #[wasm_bindgen]
+    pub fn transform_text_case(
+        &self,
+        text: &str,
+        mode: JsValue,
+        lang: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let case: TextCase = serde_wasm_bindgen::from_value(mode)
+            .map_err(|e| JsValue::from_str(&format!("Invalid case mode: {}", e)))?;
+        let result = self.transform_text_case_native(text, case, lang.as_deref());
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Report cache occupancy, hit rates and evictions.
+    #[wasm_bindgen]
+    pub fn cache_stats(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.cache_stats_native())
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    fn build_attrs(&self, style: &FontStyleInput) -> Attrs<'static> {
+    fn build_attrs(&mut self, style: &FontStyleInput) -> Attrs<'static> {
         let mut attrs = Attrs::new();
 
         // Check if the family name is a registered name with internal font info
@@ -585,14 +1043,14 @@ impl TextShaper {
                 (Some(family.clone()), None)
             }
         } else {
-            (None, None)
+            // The chain head stands in for the default family, which would
+            // otherwise resolve to whichever font claims "sans-serif"
+            let head = self.fallback_families.first();
+            (head.map(|family| family.to_string()), None)
         };
 
-        if let Some(ref family) = actual_family {
-            // We need to leak the string to get a 'static lifetime
-            // This is acceptable for font family names as they are typically long-lived
-            let family_static: &'static str = Box::leak(family.clone().into_boxed_str());
-            attrs = attrs.family(Family::Name(family_static));
+        if let Some(family) = actual_family {
+            attrs = attrs.family(Family::Name(self.static_family(family)));
         }
 
         // Use explicit weight from style, or fall back to internal weight from font file
@@ -634,19 +1092,2961 @@ impl TextShaper {
     }
 }
 
-impl Default for TextShaper {
-    fn default() -> Self {
-        Self::new()
+impl TextShaper {
+    /// Native counterpart of `shape_line`. Lines are shaped at the bucketed
+    /// font size, cached, and scaled back to the requested size.
+    pub fn shape_line_native(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        line_height: f32,
+        style: &FontStyleInput,
+    ) -> Result<ShapedLineResult, MetricsError> {
+        let metrics = metrics::checked_metrics(font_size, line_height)?;
+        let bucket_size = self.bucket_size(font_size);
+        let scale = font_size / bucket_size;
+        // Line height scales with the font, so an animation that keeps their
+        // ratio still lands on one key per bucket. Rounding the ratio to a
+        // 1/1024 grid absorbs float noise; common ratios like 1.25 sit on the
+        // grid rather than between two points.
+        let bucket_line_height = if bucket_size == font_size {
+            line_height
+        } else {
+            (line_height / font_size * 1024.0).round() / 1024.0 * bucket_size
+        };
+        let key = ShapedLineKey {
+            text: text.to_string(),
+            font_size_bits: bucket_size.to_bits(),
+            line_height_bits: bucket_line_height.to_bits(),
+            style: style.clone(),
+        };
+
+        let shaped = match self.shaped_lines.get(&key).cloned() {
+            Some(line) => {
+                self.cache_counters.shape_hits += 1;
+                line
+            }
+            None => {
+                self.cache_counters.shape_misses += 1;
+                let line = self.shape_line_uncached(text, bucket_size, bucket_line_height, style);
+                let bytes = std::mem::size_of::<ShapedLineKey>()
+                    + std::mem::size_of::<ShapedLineResult>()
+                    + text.len()
+                    + line.glyphs.len() * std::mem::size_of::<ShapedGlyph>();
+                self.shaped_lines.insert(key, line.clone(), bytes);
+                self.enforce_cache_budget();
+                line
+            }
+        };
+
+        let mut line = if bucket_size == font_size {
+            shaped
+        } else {
+            scale_shaped_line(shaped, scale, line_height)
+        };
+        // The bucket's rounded line height can land on either side of its
+        // font size, so the flag follows the requested sizes
+        line.clipped_lines = metrics::clips_lines(metrics);
+        Ok(line)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn shape_line_uncached(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        line_height: f32,
+        style: &FontStyleInput,
+    ) -> ShapedLineResult {
+        let attrs = self.build_attrs(style);
+        let metrics = Metrics::new(font_size, line_height);
 
-    #[test]
-    fn test_create_shaper() {
-        let shaper = TextShaper::new();
-        assert_eq!(shaper.font_count(), 0);
+        // Use Buffer for shaping without a width, so nothing wraps. A width
+        // would be where right-to-left lines start, and at a large one their
+        // glyph positions would round away.
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+        buffer.set_size(&mut self.font_system, None, None);
+        buffer.set_wrap(&mut self.font_system, Wrap::None);
+        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        let mut glyphs = Vec::with_capacity(text.len());
+        let mut total_width = 0.0f32;
+        let mut total_width_with_trailing = 0.0f32;
+        let mut max_ascent = 0.0f32;
+        let mut max_descent = 0.0f32;
+
+        for run in buffer.layout_runs() {
+            for glyph in run.glyphs.iter() {
+                glyphs.push(self.shaped_glyph(glyph));
+            }
+            let (width, width_with_trailing) = line_widths(&run);
+            total_width = total_width.max(width);
+            total_width_with_trailing = total_width_with_trailing.max(width_with_trailing);
+
+            // Get metrics from run
+            max_ascent = max_ascent.max(run.line_top);
+            max_descent = max_descent.max(run.line_height - run.line_top);
+        }
+
+        ShapedLineResult {
+            glyphs,
+            width: total_width,
+            width_with_trailing_whitespace: total_width_with_trailing,
+            height: line_height,
+            ascent: max_ascent,
+            descent: max_descent,
+            clipped_lines: metrics::clips_lines(metrics),
+        }
+    }
+
+    /// Native counterpart of `layout_text`.
+    pub fn layout_text_native(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        line_height: f32,
+        max_width: f32,
+        style: &FontStyleInput,
+        options: &LayoutOptions,
+    ) -> Result<LayoutResult, MetricsError> {
+        let metrics = metrics::checked_metrics(font_size, line_height)?;
+        let attrs = self.build_attrs(style);
+        let hyphen = self.hyphen_glyph(font_size, line_height, style);
+        let hyphen_width = hyphen.as_ref().map_or(0.0, |glyph| glyph.x_advance);
+        let breaks = match (&options.language, options.hyphenate) {
+            (Some(language), true) => self
+                .hyphenation
+                .get(language)
+                .map(|patterns| patterns.text_breaks(text))
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        // A hyphen that would push its line past max_width gives up its
+        // break, and the text is laid out again without it
+        let mut dropped: Vec<usize> = Vec::new();
+        loop {
+            let mut edits = options.white_space.edits(text);
+            if let Some(case) = options.text_case {
+                edits.extend(case.edits(text, options.language.as_deref()));
+            }
+            let inserted = breaks
+                .iter()
+                .copied()
+                .filter(|offset| dropped.binary_search(offset).is_err())
+                .map(|offset| Edit::insert(offset, SOFT_HYPHEN));
+            let removed = dropped
+                .iter()
+                .copied()
+                .filter(|offset| breaks.binary_search(offset).is_err())
+                .map(|offset| Edit::remove(offset, SOFT_HYPHEN.len_utf8()));
+            edits.extend(inserted.chain(removed));
+            let prepared = (!edits.is_empty() || text.contains(SOFT_HYPHEN))
+                .then(|| PreparedText::new(text, edits));
+            let shaped_text = prepared.as_ref().map_or(text, |prepared| &prepared.text);
+
+            let mut buffer = Buffer::new(&mut self.font_system, metrics);
+            buffer.set_size(&mut self.font_system, Some(max_width), None);
+            let wrap = if options.white_space.wraps() {
+                options.wrap.to_cosmic()
+            } else {
+                Wrap::None
+            };
+            buffer.set_wrap(&mut self.font_system, wrap);
+            buffer.set_text(
+                &mut self.font_system,
+                shaped_text,
+                &attrs,
+                Shaping::Advanced,
+                None,
+            );
+            buffer.shape_until_scroll(&mut self.font_system, false);
+
+            let overflowing = prepared.as_ref().map_or_else(Vec::new, |prepared| {
+                overflowing_hyphens(&buffer, prepared, hyphen_width, max_width)
+            });
+            if overflowing.is_empty() {
+                return Ok(self.collect_layout(
+                    &buffer,
+                    &[None],
+                    &[hyphen],
+                    &[None],
+                    prepared.as_ref(),
+                    options,
+                ));
+            }
+            dropped.extend(overflowing);
+            dropped.sort_unstable();
+        }
+    }
+
+    /// Native counterpart of `layout_rich_text`.
+    #[cfg(feature = "rich-text")]
+    pub fn layout_rich_text_native(
+        &mut self,
+        spans: &[RichTextSpan],
+        font_size: f32,
+        line_height: f32,
+        max_width: f32,
+        options: &LayoutOptions,
+    ) -> Result<LayoutResult, MetricsError> {
+        let metrics = metrics::checked_metrics(font_size, line_height)?;
+        // Glyphs carry their span's index as metadata, to look up its color
+        let span_attrs: Vec<Attrs<'static>> = spans
+            .iter()
+            .enumerate()
+            .map(|(index, span)| {
+                let attrs = self.build_attrs(&span.style).metadata(index);
+                match span.font_size(font_size) {
+                    size if size == font_size => attrs,
+                    size => attrs.metrics(Metrics::new(size, line_height)),
+                }
+            })
+            .collect();
+        let default_attrs = self.build_attrs(&FontStyleInput::default());
+
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+        buffer.set_size(&mut self.font_system, Some(max_width), None);
+        buffer.set_wrap(&mut self.font_system, options.wrap.to_cosmic());
+        buffer.set_rich_text(
+            &mut self.font_system,
+            spans
+                .iter()
+                .zip(span_attrs)
+                .map(|(span, attrs)| (span.text.as_str(), attrs)),
+            &default_attrs,
+            Shaping::Advanced,
+            None,
+        );
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        let colors: Vec<Option<u32>> = spans.iter().map(|span| span.color).collect();
+        let hyphens: Vec<Option<ShapedGlyph>> = spans
+            .iter()
+            .map(|span| self.hyphen_glyph(span.font_size(font_size), line_height, &span.style))
+            .collect();
+        let shifts: Vec<Option<BaselineShift>> =
+            spans.iter().map(|span| span.baseline_shift).collect();
+        Ok(self.collect_layout(&buffer, &colors, &hyphens, &shifts, None, options))
+    }
+
+    /// Read the laid out lines of a shaped buffer. `colors`, `hyphens`
+    /// and `shifts` are indexed by glyph metadata, which is the span index
+    /// for rich text. Offsets are mapped back through `prepared` when the
+    /// shaped text was edited from the caller's.
+    fn collect_layout(
+        &mut self,
+        buffer: &Buffer,
+        colors: &[Option<u32>],
+        hyphens: &[Option<ShapedGlyph>],
+        shifts: &[Option<BaselineShift>],
+        prepared: Option<&PreparedText>,
+        options: &LayoutOptions,
+    ) -> LayoutResult {
+        let mut lines = Vec::new();
+        let mut total_height = 0.0f32;
+        let mut max_width_seen = 0.0f32;
+        let grid = options
+            .baseline_grid
+            .filter(|grid| *grid > 0.0 && grid.is_finite());
+        let mut baseline_shift = 0.0f32;
+        // Room made so far for shifted glyphs, which moves every later line
+        let mut growth = 0.0f32;
+        let runs: Vec<LayoutRun> = buffer.layout_runs().collect();
+        let kept = match options.max_lines {
+            Some(max_lines) if max_lines > 0 => runs.len().min(max_lines),
+            _ => runs.len(),
+        };
+        let max_width = buffer.size().0.filter(|width| width.is_finite());
+        // Break opportunities of the paragraph the current line is in
+        let mut paragraph: Option<(usize, Vec<usize>)> = None;
+        let mut rejected_breaks = Vec::new();
+
+        for (index, run) in runs[..kept].iter().enumerate() {
+            if paragraph.as_ref().map(|(line_i, _)| *line_i) != Some(run.line_i) {
+                paragraph = Some((run.line_i, breaks::opportunities(run.text)));
+            }
+            let opportunities = paragraph.as_ref().map_or(&[][..], |(_, found)| found);
+            let last = index + 1 == kept;
+            let mut line_break = breaks::line_break(run, runs.get(index + 1), opportunities);
+            let wrapped = matches!(
+                line_break,
+                Some((BreakReason::Width | BreakReason::GraphemeForced, _))
+            );
+            if last {
+                line_break = line_break.map(|(_, offset)| (BreakReason::MaxLines, offset));
+            }
+            let break_offset = line_break.map(|(reason, offset)| {
+                let ends_paragraph = reason == BreakReason::Newline;
+                original_offset(prepared, run.line_i, offset, ends_paragraph)
+            });
+            if options.break_candidates && last {
+                let end = line_break.map_or(run.text.len(), |(_, offset)| offset);
+                rejected_breaks = breaks::passed_over(run, end, opportunities)
+                    .map(|offset| original_offset(prepared, run.line_i, offset, false))
+                    .collect();
+            }
+
+            let mut line_glyphs = Vec::with_capacity(run.glyphs.len());
+            let mut draw_runs: Vec<DrawRun> = Vec::new();
+            let mut glyph_ink_bounds = Vec::new();
+            let (mut line_width, mut width_with_trailing) = line_widths(run);
+            let mut extents = LineExtents::default();
+
+            for glyph in run.glyphs.iter() {
+                let mut shaped = self.shaped_glyph(glyph);
+                remap_glyph(&mut shaped, prepared, run.line_i);
+                let face = self.face_extents(glyph.font_id, glyph.font_weight);
+                if let Some(shift) = shifts.get(glyph.metadata).copied().flatten() {
+                    let shift = face.resolve(shift, buffer.metrics().font_size);
+                    shaped.y -= shift;
+                    shaped.baseline_shift = shift;
+                }
+                extents.include(&face, glyph.font_size, shaped.baseline_shift);
+
+                if options.draw_runs {
+                    let color = colors.get(glyph.metadata).copied().flatten();
+                    push_draw_glyph(
+                        &mut draw_runs,
+                        line_glyphs.len(),
+                        shaped.cosmic_font_id,
+                        color,
+                    );
+                }
+                if options.ink_bounds {
+                    let bounds = self.layout_glyph_ink_bounds(glyph);
+                    glyph_ink_bounds
+                        .push(bounds.map(|bounds| bounds.translate(0.0, -shaped.baseline_shift)));
+                }
+                line_glyphs.push(shaped);
+            }
+
+            let hyphen = line_hyphen(run, hyphens, line_width);
+            let hyphenated = hyphen.is_some();
+            if let Some((mut hyphen, metadata)) = hyphen {
+                remap_glyph(&mut hyphen, prepared, run.line_i);
+                // The soft hyphen it replaces was shifted with its span
+                if let Some(soft) = line_glyphs.iter().find(|glyph| glyph.end == hyphen.end) {
+                    hyphen.y = soft.y;
+                    hyphen.baseline_shift = soft.baseline_shift;
+                }
+                line_width += hyphen.x_advance;
+                width_with_trailing += hyphen.x_advance;
+                if options.draw_runs {
+                    let color = colors.get(metadata).copied().flatten();
+                    push_draw_glyph(
+                        &mut draw_runs,
+                        line_glyphs.len(),
+                        hyphen.cosmic_font_id,
+                        color,
+                    );
+                }
+                if options.ink_bounds {
+                    let font_size = buffer.metrics().font_size;
+                    let bounds = self
+                        .glyph_ink_bounds_native(hyphen.cosmic_font_id, hyphen.glyph_id, font_size)
+                        .map(|bounds| bounds.translate(hyphen.x, hyphen.y));
+                    glyph_ink_bounds.push(bounds);
+                }
+                line_glyphs.push(hyphen);
+            }
+
+            let extra = max_width.map_or(0.0, |max_width| max_width - line_width);
+            if options.justify && wrapped && extra > 0.0 {
+                let added = justify::added_advances(
+                    run,
+                    extra,
+                    options.justify_distribution,
+                    options.max_inter_word_stretch,
+                );
+                justify::stretch(&mut line_glyphs, &mut glyph_ink_bounds, &added);
+                let grown: f32 = added.iter().sum();
+                line_width += grown;
+                width_with_trailing += grown;
+            }
+
+            growth += extents.raised();
+            if let Some(grid) = grid {
+                baseline_shift =
+                    snap_to_grid(run.line_y + growth + baseline_shift, grid) - run.line_y - growth;
+            }
+            // Glyph boxes were placed relative to the baseline
+            let line_y = run.line_y + growth + baseline_shift;
+            let line_height = run.line_height + extents.raised() + extents.lowered();
+            for bounds in glyph_ink_bounds.iter_mut().flatten() {
+                *bounds = bounds.translate(0.0, line_y);
+            }
+            let ink_bounds = glyph_ink_bounds
+                .iter()
+                .flatten()
+                .fold(InkBounds::EMPTY, |union, bounds| union.union(bounds));
+            lines.push(LayoutLine {
+                glyphs: line_glyphs,
+                width: line_width,
+                width_with_trailing_whitespace: width_with_trailing,
+                y: line_y,
+                line_height,
+                ascent: extents.shifted_ascent,
+                descent: extents.shifted_descent,
+                draw_runs,
+                baseline_shift,
+                hyphenated,
+                break_reason: line_break.map(|(reason, _)| reason),
+                break_offset,
+                glyph_ink_bounds,
+                ink_bounds: (!ink_bounds.is_empty()).then_some(ink_bounds),
+            });
+
+            max_width_seen = max_width_seen.max(line_width);
+            growth += extents.lowered();
+            total_height = total_height.max(line_y + run.line_height + extents.lowered());
+        }
+
+        LayoutResult {
+            shadow_runs: shadow_runs(&lines, &options.shadows),
+            lines,
+            total_width: max_width_seen,
+            total_height,
+            clipped_lines: metrics::clips_lines(buffer.metrics()),
+            rejected_breaks,
+        }
+    }
+
+    /// Native counterpart of `measure_text`.
+    pub fn measure_text_native(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        line_height: f32,
+        max_width: Option<f32>,
+        style: &FontStyleInput,
+    ) -> Result<MeasureResult, MetricsError> {
+        let metrics = metrics::checked_metrics(font_size, line_height)?;
+        let attrs = self.build_attrs(style);
+
+        // Handle explicit newlines when wrapping is disabled. cosmic-text with
+        // Wrap::None does not allocate multiple layout runs for '\n', so we
+        // split and measure each line ourselves.
+        if max_width.is_none() && text.contains('\n') {
+            let ascent_offset = font_size * 0.8;
+            let mut max_width_seen = 0.0f32;
+            let mut line_count = 0usize;
+
+            for line in text.split('\n') {
+                let mut buffer = Buffer::new(&mut self.font_system, metrics);
+                buffer.set_size(&mut self.font_system, Some(f32::MAX), None);
+                buffer.set_wrap(&mut self.font_system, Wrap::None);
+                buffer.set_text(&mut self.font_system, line, &attrs, Shaping::Advanced, None);
+                buffer.shape_until_scroll(&mut self.font_system, false);
+
+                let mut line_width = 0.0f32;
+                for run in buffer.layout_runs() {
+                    line_width = line_width.max(line_widths(&run).0);
+                }
+
+                max_width_seen = max_width_seen.max(line_width);
+                line_count += 1;
+            }
+
+            return Ok(MeasureResult {
+                width: max_width_seen,
+                height: line_height * line_count as f32 + ascent_offset,
+                clipped_lines: metrics::clips_lines(metrics),
+            });
+        }
+
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+        // When no max_width is provided, use a very large value to allow cosmic-text
+        // to process newlines while not wrapping. This enables whitespace: pre behavior.
+        let effective_width = max_width.unwrap_or(f32::MAX);
+        buffer.set_size(&mut self.font_system, Some(effective_width), None);
+        buffer.set_wrap(
+            &mut self.font_system,
+            if max_width.is_some() {
+                Wrap::Word
+            } else {
+                Wrap::None
+            },
+        );
+        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        let mut total_width = 0.0f32;
+        let mut total_height = 0.0f32;
+
+        for run in buffer.layout_runs() {
+            total_width = total_width.max(line_widths(&run).0);
+            total_height = total_height.max(run.line_y + run.line_height);
+        }
+
+        Ok(MeasureResult {
+            width: total_width,
+            height: total_height,
+            clipped_lines: metrics::clips_lines(metrics),
+        })
+    }
+
+    /// Native counterpart of `layout_text_on_path`.
+    pub fn layout_text_on_path_native(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        style: &FontStyleInput,
+        path_d: &str,
+        options: &TextPathOptions,
+    ) -> Result<TextOnPathResult, MetricsError> {
+        let line = self.shape_line_native(text, font_size, font_size, style)?;
+        let walker = PathWalker::parse(path_d);
+        let path_length = walker.length();
+
+        let spacing = match options.spacing_mode {
+            SpacingMode::Fit if line.width > 0.0 => {
+                ((path_length - options.start_offset) / line.width).max(0.0)
+            }
+            _ => 1.0,
+        };
+
+        let mut glyphs = Vec::with_capacity(line.glyphs.len());
+        let mut overflow_clusters = 0;
+
+        // Ligatures and combining marks share a cluster, and must stay
+        // together rather than each following the curve on its own
+        for cluster in line.glyphs.chunk_by(|a, b| a.start == b.start) {
+            let left = cluster.iter().fold(f32::MAX, |left, g| left.min(g.x));
+            let right = cluster
+                .iter()
+                .fold(f32::MIN, |right, g| right.max(g.x + g.x_advance));
+            let middle = (left + right) / 2.0;
+            let distance = options.start_offset + middle * spacing;
+
+            let overflow = distance < 0.0 || distance > path_length;
+            if overflow {
+                overflow_clusters += 1;
+                if options.clip_overflow {
+                    continue;
+                }
+            }
+            let Some(sample) = walker.sample(distance) else {
+                continue;
+            };
+
+            let (sin, cos) = sample.angle.sin_cos();
+            for glyph in cluster {
+                let along = glyph.x + glyph.x_offset - middle;
+                glyphs.push(PathGlyph {
+                    glyph_id: glyph.glyph_id,
+                    cosmic_font_id: glyph.cosmic_font_id,
+                    x: sample.x + cos * along,
+                    y: sample.y + sin * along,
+                    rotation_rad: sample.angle,
+                    start: glyph.start,
+                    end: glyph.end,
+                    overflow,
+                });
+            }
+        }
+
+        Ok(TextOnPathResult {
+            glyphs,
+            path_length,
+            overflow_clusters,
+        })
+    }
+
+    /// Native counterpart of `register_hyphenation_patterns`.
+    pub fn register_hyphenation_patterns_native(
+        &mut self,
+        lang: &str,
+        source: &str,
+    ) -> Result<(), String> {
+        let patterns = HyphenationPatterns::parse(source)?;
+        self.hyphenation.insert(lang.to_string(), patterns);
+        Ok(())
+    }
+
+    /// The glyph drawn at a hyphenated line break, in the style's font.
+    fn hyphen_glyph(
+        &mut self,
+        font_size: f32,
+        line_height: f32,
+        style: &FontStyleInput,
+    ) -> Option<ShapedGlyph> {
+        self.shape_line_native("-", font_size, line_height, style)
+            .ok()?
+            .glyphs
+            .into_iter()
+            .next()
+    }
+
+    /// Native counterpart of `set_fallback_chain`.
+    pub fn set_fallback_chain_native(&mut self, font_ids: &[u32]) -> Result<(), String> {
+        let mut families = Vec::with_capacity(font_ids.len());
+        for &font_id in font_ids {
+            let family = self
+                .font_faces
+                .get(&font_id)
+                .and_then(|faces| faces.first())
+                .and_then(|&face| self.font_system.db().face(face))
+                .and_then(|face| face.families.first())
+                .map(|(name, _)| name.clone())
+                .ok_or_else(|| format!("Unknown font ID: {}", font_id))?;
+            let family = self.static_family(family);
+            if !families.contains(&family) {
+                families.push(family);
+            }
+        }
+
+        // cosmic-text copies the fallback lists when the font system is
+        // created, so the system is rebuilt around the same database. Face
+        // IDs survive, so cached glyphs stay valid; shaped lines do not.
+        let placeholder = FontSystem::new_with_locale_and_db_and_fallback(
+            String::new(),
+            Database::new(),
+            ChainFallback::default(),
+        );
+        let (locale, db) =
+            std::mem::replace(&mut self.font_system, placeholder).into_locale_and_db();
+        self.font_system = FontSystem::new_with_locale_and_db_and_fallback(
+            locale,
+            db,
+            ChainFallback {
+                families: families.clone(),
+            },
+        );
+        self.fallback_families = families;
+        self.shaped_lines.clear();
+        Ok(())
+    }
+
+    /// Native counterpart of `register_fonts_batch`.
+    pub fn register_fonts_batch_native(&mut self, fonts: Vec<Vec<u8>>) -> Vec<RegisteredFont> {
+        fonts
+            .into_iter()
+            .enumerate()
+            .map(|(index, font_data)| {
+                let font_data = match woff::decode(&font_data) {
+                    Ok(Cow::Owned(decoded)) => decoded,
+                    Ok(Cow::Borrowed(_)) => font_data,
+                    Err(error) => return RegisteredFont::failed(error.to_string()),
+                };
+                let id = self.next_font_id;
+                let faces = self.load_faces(id, &font_data);
+                let Some(face) = faces
+                    .first()
+                    .and_then(|&face| self.font_system.db().face(face))
+                else {
+                    self.font_faces.remove(&id);
+                    return RegisteredFont::failed(format!(
+                        "Font {} has no faces that could be parsed",
+                        index
+                    ));
+                };
+                let registered = RegisteredFont::from_face(id, face, &font_data);
+                self.next_font_id += 1;
+                self.font_data.insert(id, font_data);
+                registered
+            })
+            .collect()
+    }
+
+    /// Native counterpart of `find_font`, for the family, weight and style
+    /// of `style`.
+    pub fn find_font_native(&mut self, style: &FontStyleInput) -> Option<u32> {
+        let attrs = self.build_attrs(style);
+        let Family::Name(family) = attrs.family else {
+            return None;
+        };
+        // The CSS font matching shaping uses for the requested family itself
+        let face = self.font_system.db().query(&Query {
+            families: &[Family::Name(family)],
+            weight: attrs.weight,
+            stretch: attrs.stretch,
+            style: attrs.style,
+        })?;
+        self.face_registrations.get(&face).copied()
+    }
+
+    /// Native counterpart of `transform_text_case`.
+    pub fn transform_text_case_native(
+        &self,
+        text: &str,
+        case: TextCase,
+        language: Option<&str>,
+    ) -> CaseTransform {
+        case::transform(text, case, language)
+    }
+
+    /// Native counterpart of `hash_text_input`.
+    pub fn hash_text_input_native(
+        &self,
+        text: &str,
+        font_size: f32,
+        style: &FontStyleInput,
+    ) -> u64 {
+        let mut hasher = ContentHasher::default();
+        text.hash(&mut hasher);
+        font_size.to_bits().hash(&mut hasher);
+        style.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Native counterpart of `effective_font_for`.
+    pub fn effective_font_for_native(&mut self, ch: char, style: &FontStyleInput) -> Option<u32> {
+        let mut utf8 = [0; 4];
+        // Font selection does not depend on size, so any size will do
+        let line = self.shape_line_uncached(ch.encode_utf8(&mut utf8), 16.0, 16.0, style);
+        // Glyph 0 is .notdef, drawn when no font covers the character
+        let glyph = line.glyphs.first().filter(|glyph| glyph.glyph_id != 0)?;
+        let face = self.cosmic_id_to_fontdb.get(&glyph.cosmic_font_id)?;
+        self.face_registrations.get(face).copied()
+    }
+
+    /// Native counterpart of `rasterize_glyph_by_cosmic_id`.
+    #[cfg(feature = "raster")]
+    pub fn rasterize_glyph_with_options_native(
+        &mut self,
+        cosmic_font_id: u64,
+        glyph_id: u32,
+        font_size: f32,
+        options: &RasterOptions,
+    ) -> RasterizedGlyph {
+        let mut glyph = self.rasterize_glyph_native(cosmic_font_id, glyph_id, font_size);
+        options.apply(&mut glyph);
+        glyph
+    }
+
+    /// `rasterize_glyph_with_options_native` with the default encoding.
+    #[cfg(feature = "raster")]
+    pub fn rasterize_glyph_native(
+        &mut self,
+        cosmic_font_id: u64,
+        glyph_id: u32,
+        font_size: f32,
+    ) -> RasterizedGlyph {
+        let Some(&font_id) = self.cosmic_id_to_fontdb.get(&cosmic_font_id) else {
+            // Font ID not found - return empty glyph
+            return empty_glyph();
+        };
+
+        let font_size = self.bucket_size(font_size);
+        let key = GlyphKey {
+            cosmic_font_id,
+            glyph_id,
+            font_size_bits: font_size.to_bits(),
+        };
+        if let Some(glyph) = self.glyphs.get(&key).cloned() {
+            self.cache_counters.glyph_hits += 1;
+            return glyph;
+        }
+
+        let glyph = self.rasterize_uncached(font_id, glyph_id, font_size);
+        self.cache_counters.rasterizations += 1;
+        let bytes = std::mem::size_of::<GlyphKey>()
+            + std::mem::size_of::<RasterizedGlyph>()
+            + glyph.pixels.len();
+        self.glyphs.insert(key, glyph.clone(), bytes);
+        self.enforce_cache_budget();
+        glyph
+    }
+
+    #[cfg(feature = "raster")]
+    fn rasterize_uncached(
+        &mut self,
+        font_id: cosmic_text::fontdb::ID,
+        glyph_id: u32,
+        font_size: f32,
+    ) -> RasterizedGlyph {
+        let image =
+            self.glyph_scaler
+                .render(&mut self.font_system, font_id, glyph_id as u16, font_size);
+
+        let Some(img) = image else {
+            // Return empty glyph (e.g., for space character)
+            return empty_glyph();
+        };
+
+        use cosmic_text::SwashContent;
+        let (pixels, is_color) = match img.content {
+            SwashContent::Mask => (img.data, false),
+            SwashContent::SubpixelMask => {
+                // Extract alpha channel from subpixel glyphs
+                let mut alpha = Vec::with_capacity(
+                    (img.placement.width as usize).saturating_mul(img.placement.height as usize),
+                );
+                for chunk in img.data.chunks_exact(4) {
+                    alpha.push(chunk[3]);
+                }
+                (alpha, false)
+            }
+            SwashContent::Color => (img.data, true),
+        };
+
+        RasterizedGlyph {
+            width: img.placement.width,
+            height: img.placement.height,
+            bearing_x: img.placement.left,
+            bearing_y: img.placement.top,
+            advance: 0.0,
+            pixels,
+            is_color,
+        }
+    }
+
+    /// Native counterpart of `glyph_ink_bounds`.
+    pub fn glyph_ink_bounds_native(
+        &mut self,
+        cosmic_font_id: u64,
+        glyph_id: u32,
+        font_size: f32,
+    ) -> Option<InkBounds> {
+        let &font_id = self.cosmic_id_to_fontdb.get(&cosmic_font_id)?;
+        // The same key the rasterizer uses, so hinting matches the pixels
+        let (cache_key, _, _) = CacheKey::new(
+            font_id,
+            glyph_id as u16,
+            font_size,
+            (0.0, 0.0),
+            Weight::NORMAL,
+            CacheKeyFlags::empty(),
+        );
+        self.cache_key_ink_bounds(cache_key)
+    }
+
+    /// Native counterpart of `ink_bounds`.
+    pub fn ink_bounds_native(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        style: &FontStyleInput,
+    ) -> Option<InkBounds> {
+        let metrics = metrics::checked_metrics(font_size, font_size).ok()?;
+        let attrs = self.build_attrs(style);
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+        buffer.set_size(&mut self.font_system, None, None);
+        buffer.set_wrap(&mut self.font_system, Wrap::None);
+        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        let mut bounds = InkBounds::EMPTY;
+        let mut first_baseline = None;
+        for run in buffer.layout_runs() {
+            let baseline = *first_baseline.get_or_insert(run.line_y);
+            for glyph in run.glyphs {
+                // Place glyphs as the renderer does, with the baseline offset
+                // applied before y is snapped to whole pixels
+                let physical = glyph.physical((0.0, run.line_y - baseline), 1.0);
+                let Some(glyph_bounds) = self.cache_key_ink_bounds(physical.cache_key) else {
+                    continue;
+                };
+                let x = physical.x as f32 + physical.cache_key.x_bin.as_float();
+                bounds = bounds.union(&glyph_bounds.translate(x, physical.y as f32));
+            }
+        }
+        (!bounds.is_empty()).then_some(bounds)
+    }
+
+    /// Native counterpart of `word_boxes`.
+    pub fn word_boxes_native(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        line_height: f32,
+        max_width: f32,
+        style: &FontStyleInput,
+    ) -> Vec<WordBox> {
+        let Ok(metrics) = metrics::checked_metrics(font_size, line_height) else {
+            return Vec::new();
+        };
+        let attrs = self.build_attrs(style);
+
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+        buffer.set_size(&mut self.font_system, Some(max_width), None);
+        buffer.set_wrap(&mut self.font_system, Wrap::Word);
+        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        let paragraphs = words::paragraph_words(text);
+        let mut boxes = Vec::new();
+        for (line_index, run) in buffer.layout_runs().enumerate() {
+            if let Some(paragraph) = paragraphs.get(run.line_i) {
+                boxes.extend(words::line_word_boxes(&run, paragraph, line_index));
+            }
+        }
+        boxes
+    }
+
+    /// Native counterpart of `visible_glyph_range`.
+    pub fn visible_glyph_range_native(
+        &mut self,
+        text: &str,
+        font_size: f32,
+        line_height: f32,
+        style: &FontStyleInput,
+        scroll_x: f32,
+        viewport_width: f32,
+    ) -> Result<VisibleGlyphRange, MetricsError> {
+        let line = self.shape_line_native(text, font_size, line_height, style)?;
+        Ok(visible::visible_glyph_range(
+            &line.glyphs,
+            scroll_x,
+            viewport_width,
+        ))
+    }
+
+    fn cache_key_ink_bounds(&mut self, cache_key: CacheKey) -> Option<InkBounds> {
+        if let Some(&bounds) = self.ink_extents.get(&cache_key) {
+            return bounds;
+        }
+        let bounds = self
+            .swash_cache
+            .get_outline_commands_uncached(&mut self.font_system, cache_key)
+            .and_then(|commands| ink::outline_bounds(&commands));
+        self.ink_extents.insert(cache_key, bounds);
+        bounds
+    }
+
+    /// Ink box of a laid out glyph relative to its line's baseline, at the
+    /// glyph's unrounded position. Extents are looked up without a subpixel
+    /// offset, so each glyph is outlined once per size.
+    fn layout_glyph_ink_bounds(&mut self, glyph: &LayoutGlyph) -> Option<InkBounds> {
+        let (cache_key, _, _) = CacheKey::new(
+            glyph.font_id,
+            glyph.glyph_id,
+            glyph.font_size,
+            (0.0, 0.0),
+            glyph.font_weight,
+            glyph.cache_key_flags,
+        );
+        let x = glyph.x + glyph.font_size * glyph.x_offset;
+        let y = glyph.y - glyph.font_size * glyph.y_offset;
+        Some(self.cache_key_ink_bounds(cache_key)?.translate(x, y))
+    }
+
+    fn face_extents(&mut self, id: cosmic_text::fontdb::ID, weight: Weight) -> FaceExtents {
+        if let Some(&extents) = self.face_extents.get(&id) {
+            return extents;
+        }
+        let extents = FaceExtents::read(&mut self.font_system, id, weight);
+        self.face_extents.insert(id, extents);
+        extents
+    }
+
+    /// Native counterpart of `set_cache_budget`.
+    pub fn set_cache_budget_native(&mut self, bytes: Option<usize>) -> Vec<EvictedGlyph> {
+        self.cache_budget = bytes;
+        self.enforce_cache_budget();
+        self.take_evicted_glyphs_native()
+    }
+
+    /// Native counterpart of `take_evicted_glyphs`.
+    pub fn take_evicted_glyphs_native(&mut self) -> Vec<EvictedGlyph> {
+        std::mem::take(&mut self.evicted_glyphs)
+    }
+
+    /// Native counterpart of `cache_stats`.
+    pub fn cache_stats_native(&self) -> CacheStats {
+        CacheStats {
+            shaped_lines: self.shaped_lines.len(),
+            shaped_line_bytes: self.shaped_lines.bytes(),
+            glyphs: self.glyphs.len(),
+            glyph_bytes: self.glyphs.bytes(),
+            budget_bytes: self.cache_budget,
+            #[cfg(feature = "raster")]
+            raster_faces: self.glyph_scaler.face_count(),
+            ..self.cache_counters
+        }
+    }
+
+    /// The font size used for cache keys and for the actual shaping or
+    /// rasterization work.
+    fn bucket_size(&self, font_size: f32) -> f32 {
+        if self.size_bucket <= 0.0 {
+            return font_size;
+        }
+        let step = self.size_bucket;
+        ((font_size / step).round() * step).max(step)
+    }
+
+    /// Trim both caches once they exceed the budget, each keeping its current
+    /// share of the space.
+    fn enforce_cache_budget(&mut self) {
+        let Some(budget) = self.cache_budget else {
+            return;
+        };
+        let shaped_bytes = self.shaped_lines.bytes();
+        let total = shaped_bytes + self.glyphs.bytes();
+        if total <= budget {
+            return;
+        }
+
+        // Trim below the budget so steady inserts at the limit do not sort
+        // the caches on every call
+        let target = budget - budget / 8;
+        let shaped_target = (shaped_bytes as u128 * target as u128 / total as u128) as usize;
+        let evicted_lines = self.shaped_lines.trim_to(shaped_target).len();
+        let evicted_glyphs = self.glyphs.trim_to(target - shaped_target);
+
+        self.cache_counters.evictions += (evicted_lines + evicted_glyphs.len()) as u64;
+        self.evicted_glyphs
+            .extend(evicted_glyphs.into_iter().map(|key| EvictedGlyph {
+                cosmic_font_id: key.cosmic_font_id,
+                glyph_id: key.glyph_id,
+                font_size: f32::from_bits(key.font_size_bits),
+            }));
+    }
+
+    /// We need to leak family names to get the 'static lifetime `Attrs` and
+    /// `Fallback` require. Each distinct name is leaked once, and family names
+    /// are few and long-lived.
+    fn static_family(&mut self, family: String) -> &'static str {
+        self.static_family_names
+            .entry(family)
+            .or_insert_with_key(|name| Box::leak(name.clone().into_boxed_str()))
+    }
+
+    /// Load a font file's faces into the database and record which
+    /// registration they belong to.
+    fn load_faces(&mut self, font_id: u32, font_data: &[u8]) -> Vec<cosmic_text::fontdb::ID> {
+        let faces = self
+            .font_system
+            .db_mut()
+            .load_font_source(Source::Binary(Arc::new(font_data.to_vec())))
+            .to_vec();
+        for &face in &faces {
+            let cosmic_id = self.cosmic_font_id(face);
+            self.cosmic_id_to_fontdb.insert(cosmic_id, face);
+            self.face_registrations.insert(face, font_id);
+        }
+        self.font_faces.insert(font_id, faces.clone());
+        faces
+    }
+
+    #[inline]
+    fn shaped_glyph(&mut self, glyph: &LayoutGlyph) -> ShapedGlyph {
+        ShapedGlyph {
+            glyph_id: glyph.glyph_id as u32,
+            cosmic_font_id: self.cosmic_font_id(glyph.font_id),
+            x: glyph.x,
+            y: glyph.y,
+            x_advance: glyph.w,
+            y_advance: 0.0,
+            x_offset: glyph.x_offset,
+            y_offset: glyph.y_offset,
+            start: glyph.start,
+            end: glyph.end,
+            baseline_shift: 0.0,
+        }
+    }
+
+    /// Serialized form of a fontdb ID. fontdb only exposes the raw key through
+    /// Display, so the formatted value is memoized instead of re-formatting
+    /// for every glyph.
+    #[inline]
+    fn cosmic_font_id(&mut self, id: cosmic_text::fontdb::ID) -> u64 {
+        *self
+            .serialized_font_ids
+            .entry(id)
+            .or_insert_with(|| format!("{}", id).parse().unwrap_or(0))
+    }
+}
+
+/// How far a line's glyphs reach above and below its baseline, unshifted
+/// as cosmic-text spaced the line, and at their shifted positions.
+#[derive(Default)]
+struct LineExtents {
+    ascent: f32,
+    descent: f32,
+    shifted_ascent: f32,
+    shifted_descent: f32,
+}
+
+impl LineExtents {
+    fn include(&mut self, face: &FaceExtents, font_size: f32, shift: f32) {
+        let ascent = face.ascent * font_size;
+        let descent = face.descent * font_size;
+        self.ascent = self.ascent.max(ascent);
+        self.descent = self.descent.max(descent);
+        self.shifted_ascent = self.shifted_ascent.max(ascent + shift);
+        self.shifted_descent = self.shifted_descent.max(descent - shift);
+    }
+
+    /// How far raised glyphs reach past the unshifted text.
+    fn raised(&self) -> f32 {
+        (self.shifted_ascent - self.ascent).max(0.0)
+    }
+
+    /// How far lowered glyphs reach past the unshifted text.
+    fn lowered(&self) -> f32 {
+        (self.shifted_descent - self.descent).max(0.0)
+    }
+}
+
+/// Where a line ends, within its paragraph, when it breaks right after a
+/// soft hyphen. Lines ending their paragraph are not broken there.
+fn soft_hyphen_break(run: &LayoutRun) -> Option<usize> {
+    let end = run.glyphs.iter().map(|glyph| glyph.end).max()?;
+    (end < run.text.len() && run.text[..end].ends_with(SOFT_HYPHEN)).then_some(end)
+}
+
+/// The visible hyphen for a line broken at a soft hyphen, placed at `x`
+/// with the soft hyphen's offsets, and the metadata of the glyph before it.
+fn line_hyphen(
+    run: &LayoutRun,
+    hyphens: &[Option<ShapedGlyph>],
+    x: f32,
+) -> Option<(ShapedGlyph, usize)> {
+    let end = soft_hyphen_break(run)?;
+    let last = run.glyphs.iter().find(|glyph| glyph.end == end)?;
+    let mut hyphen = hyphens.get(last.metadata)?.clone()?;
+    hyphen.x = x;
+    hyphen.y = last.y;
+    hyphen.start = end - SOFT_HYPHEN.len_utf8();
+    hyphen.end = end;
+    Some((hyphen, last.metadata))
+}
+
+/// A line's width without and with its trailing whitespace. Whitespace
+/// after the last other glyph in text order hangs, as in browsers; no-break
+/// spaces do not.
+fn line_widths(run: &LayoutRun) -> (f32, f32) {
+    let is_space = |glyph: &LayoutGlyph| {
+        run.text[glyph.start..glyph.end]
+            .chars()
+            .all(|c| c.is_whitespace() && !matches!(c, '\u{00A0}' | '\u{2007}' | '\u{202F}'))
+    };
+    let content_end = run
+        .glyphs
+        .iter()
+        .filter(|glyph| !is_space(glyph))
+        .map(|glyph| glyph.end)
+        .max()
+        .unwrap_or(0);
+    run.glyphs
+        .iter()
+        .fold((0.0f32, 0.0f32), |(trimmed, full), glyph| {
+            let right = glyph.x + glyph.w;
+            let hangs = glyph.start >= content_end && is_space(glyph);
+            (
+                if hangs { trimmed } else { trimmed.max(right) },
+                full.max(right),
+            )
+        })
+}
+
+/// Original text offsets of the soft hyphens whose visible hyphen would not
+/// fit on its line.
+fn overflowing_hyphens(
+    buffer: &Buffer,
+    prepared: &PreparedText,
+    hyphen_width: f32,
+    max_width: f32,
+) -> Vec<usize> {
+    buffer
+        .layout_runs()
+        .filter_map(|run| {
+            let end = soft_hyphen_break(&run)?;
+            let width = run
+                .glyphs
+                .iter()
+                .fold(0.0f32, |width, glyph| width.max(glyph.x + glyph.w));
+            (width + hyphen_width > max_width)
+                .then(|| prepared.text_offset(run.line_i, end - SOFT_HYPHEN.len_utf8()))
+        })
+        .collect()
+}
+
+/// Map a glyph's offsets from prepared text back to the caller's.
+fn remap_glyph(glyph: &mut ShapedGlyph, prepared: Option<&PreparedText>, paragraph: usize) {
+    if let Some(prepared) = prepared {
+        glyph.start = prepared.paragraph_offset(paragraph, glyph.start);
+        glyph.end = prepared.paragraph_end(paragraph, glyph.end);
+    }
+}
+
+/// Map an offset in shaped paragraph `paragraph` back to the caller's
+/// paragraph, as `remap_glyph` does, treating it as the end of something
+/// when `end` is set.
+fn original_offset(
+    prepared: Option<&PreparedText>,
+    paragraph: usize,
+    offset: usize,
+    end: bool,
+) -> usize {
+    match prepared {
+        Some(prepared) if end => prepared.paragraph_end(paragraph, offset),
+        Some(prepared) => prepared.paragraph_offset(paragraph, offset),
+        None => offset,
+    }
+}
+
+/// Round `y` up to the next multiple of `grid`. Values within float error of
+/// a multiple stay on it.
+fn snap_to_grid(y: f32, grid: f32) -> f32 {
+    ((y / grid) - 1e-4).ceil() * grid
+}
+
+/// Add glyph `index` to the last draw run if it shares its font and color,
+/// or start a new run.
+fn push_draw_glyph(runs: &mut Vec<DrawRun>, index: usize, cosmic_font_id: u64, color: Option<u32>) {
+    if let Some(last) = runs.last_mut() {
+        if last.cosmic_font_id == cosmic_font_id && last.color == color {
+            last.glyph_end = index + 1;
+            return;
+        }
+    }
+    runs.push(DrawRun {
+        cosmic_font_id,
+        color,
+        glyph_start: index,
+        glyph_end: index + 1,
+    });
+}
+
+/// Runs drawing `lines` for each of `shadows`, the last shadow first.
+/// Glyphs are grouped by font alone, since a shadow has one color.
+fn shadow_runs(lines: &[LayoutLine], shadows: &[TextShadow]) -> Vec<ShadowRun> {
+    let mut runs: Vec<ShadowRun> = Vec::new();
+    let shadows = shadows
+        .iter()
+        .rev()
+        .filter(|shadow| shadow.offset_x.is_finite() && shadow.offset_y.is_finite());
+    for shadow in shadows {
+        for (line_index, line) in lines.iter().enumerate() {
+            let first_run = runs.len();
+            for (index, glyph) in line.glyphs.iter().enumerate() {
+                match runs[first_run..].last_mut() {
+                    Some(run) if run.cosmic_font_id == glyph.cosmic_font_id => {
+                        run.glyph_end = index + 1;
+                    }
+                    _ => runs.push(ShadowRun {
+                        line: line_index,
+                        cosmic_font_id: glyph.cosmic_font_id,
+                        color: shadow.color,
+                        glyph_start: index,
+                        glyph_end: index + 1,
+                        x: shadow.offset_x,
+                        y: line.y + shadow.offset_y,
+                    }),
+                }
+            }
+        }
+    }
+    runs
+}
+
+fn parse_options<T: serde::de::DeserializeOwned + Default>(
+    options_js: JsValue,
+) -> Result<T, JsValue> {
+    if options_js.is_undefined() || options_js.is_null() {
+        return Ok(T::default());
+    }
+    serde_wasm_bindgen::from_value(options_js)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))
+}
+
+/// The error a wasm export gives in a build without the cargo feature that
+/// implements it.
+#[cfg(not(all(feature = "raster", feature = "rich-text")))]
+fn feature_disabled(feature: &str) -> JsValue {
+    JsValue::from_str(&format!("built without the {} feature", feature))
+}
+
+#[cfg(feature = "raster")]
+fn empty_glyph() -> RasterizedGlyph {
+    RasterizedGlyph {
+        width: 0,
+        height: 0,
+        bearing_x: 0,
+        bearing_y: 0,
+        advance: 0.0,
+        pixels: Vec::new(),
+        is_color: false,
+    }
+}
+
+/// Scale a line shaped at a bucketed size to the requested size.
+fn scale_shaped_line(mut line: ShapedLineResult, scale: f32, line_height: f32) -> ShapedLineResult {
+    for glyph in &mut line.glyphs {
+        glyph.x *= scale;
+        glyph.y *= scale;
+        glyph.x_advance *= scale;
+        glyph.y_advance *= scale;
+        glyph.x_offset *= scale;
+        glyph.y_offset *= scale;
+    }
+    line.width *= scale;
+    line.width_with_trailing_whitespace *= scale;
+    line.height = line_height;
+    line.ascent *= scale;
+    line.descent *= scale;
+    line
+}
+
+impl Default for TextShaper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_shaper() {
+        let shaper = TextShaper::new();
+        assert_eq!(shaper.font_count(), 0);
+    }
+
+    const INTER: &[u8] = include_bytes!("../../../assets/InterVariable.ttf");
+
+    fn inter_shaper() -> (TextShaper, FontStyleInput) {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        let style = FontStyleInput {
+            family: Some("Inter".to_string()),
+            ..Default::default()
+        };
+        (shaper, style)
+    }
+
+    /// 100 sizes from 12.00 to 15.96px in 0.04px steps.
+    #[cfg(feature = "raster")]
+    fn animated_sizes() -> impl Iterator<Item = f32> {
+        (0..100).map(|step| 12.0 + step as f32 * 0.04)
+    }
+
+    #[test]
+    #[cfg(feature = "raster")]
+    fn test_font_size_animation_reuses_buckets() {
+        let (mut shaper, style) = inter_shaper();
+        let glyph = shaper
+            .shape_line_native("A", 12.0, 16.0, &style)
+            .unwrap()
+            .glyphs[0]
+            .clone();
+        let before = shaper.cache_stats_native();
+
+        let buckets: std::collections::HashSet<u32> = animated_sizes()
+            .map(|size| shaper.bucket_size(size).to_bits())
+            .collect();
+        assert_eq!(buckets.len(), 9);
+
+        for size in animated_sizes() {
+            shaper.rasterize_glyph_native(glyph.cosmic_font_id, glyph.glyph_id, size);
+            shaper
+                .shape_line_native("Animated", size, size * 1.25, &style)
+                .unwrap();
+        }
+
+        let stats = shaper.cache_stats_native();
+        assert_eq!(stats.rasterizations, buckets.len() as u64);
+        assert_eq!(stats.glyph_hits, 100 - buckets.len() as u64);
+        assert_eq!(
+            stats.shape_misses - before.shape_misses,
+            buckets.len() as u64
+        );
+        assert_eq!(stats.shaped_lines - before.shaped_lines, buckets.len());
+    }
+
+    #[test]
+    fn test_bucketed_shaping_scales_to_requested_size() {
+        let (mut shaper, style) = inter_shaper();
+        let bucketed = shaper
+            .shape_line_native("Scale me", 13.2, 20.0, &style)
+            .unwrap();
+        shaper.set_size_bucket(0.0);
+        let exact = shaper
+            .shape_line_native("Scale me", 13.2, 20.0, &style)
+            .unwrap();
+
+        assert_eq!(bucketed.height, 20.0);
+        assert_eq!(bucketed.glyphs.len(), exact.glyphs.len());
+        // Advances are hinting-free, so they scale linearly with size
+        assert!((bucketed.width - exact.width).abs() < 0.05);
+    }
+
+    /// Upper half of a radius 100 circle centered on (100, 100), drawn left
+    /// to right over the top.
+    const SEMICIRCLE: &str = "M0 100 A100 100 0 0 1 200 100";
+
+    #[test]
+    fn test_text_on_semicircle_follows_tangent() {
+        let (mut shaper, style) = inter_shaper();
+        let options = TextPathOptions {
+            spacing_mode: SpacingMode::Fit,
+            ..Default::default()
+        };
+        let result = shaper
+            .layout_text_on_path_native("HELLO", 24.0, &style, SEMICIRCLE, &options)
+            .unwrap();
+
+        assert!((result.path_length - std::f32::consts::PI * 100.0).abs() < 0.5);
+        assert_eq!(result.glyphs.len(), 5);
+        assert_eq!(result.overflow_clusters, 0);
+
+        for glyph in &result.glyphs {
+            // Single-glyph clusters are offset from the curve by half their
+            // advance along the tangent, so they sit just outside the circle
+            let radius = (glyph.x - 100.0).hypot(glyph.y - 100.0);
+            assert!((radius - 100.0).abs() < 2.0, "radius {}", radius);
+        }
+
+        let middle = &result.glyphs[2];
+        assert!(middle.rotation_rad.abs() < 0.15, "{}", middle.rotation_rad);
+        assert!(middle.y < 5.0);
+
+        let first = &result.glyphs[0];
+        let last = &result.glyphs[4];
+        let quarter_turn = std::f32::consts::FRAC_PI_2;
+        assert!((first.rotation_rad + quarter_turn).abs() < 0.5);
+        assert!((last.rotation_rad - quarter_turn).abs() < 0.5);
+        assert!(result
+            .glyphs
+            .windows(2)
+            .all(|pair| pair[0].rotation_rad < pair[1].rotation_rad));
+    }
+
+    #[test]
+    fn test_text_on_path_overflow_is_clipped_or_reported() {
+        let (mut shaper, style) = inter_shaper();
+        let short_line = "M0 0 L40 0";
+
+        let reported = shaper
+            .layout_text_on_path_native(
+                "Overflowing",
+                16.0,
+                &style,
+                short_line,
+                &TextPathOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(reported.glyphs.len(), 11);
+        assert!(reported.overflow_clusters > 0);
+        let overflowed = reported.glyphs.iter().filter(|g| g.overflow).count();
+        assert_eq!(overflowed, reported.overflow_clusters);
+        // Overflowing glyphs continue along the final tangent
+        assert!(reported.glyphs.iter().all(|g| g.y.abs() < 1e-4));
+        assert!(reported.glyphs.last().unwrap().x > 40.0);
+
+        let clipped = shaper
+            .layout_text_on_path_native(
+                "Overflowing",
+                16.0,
+                &style,
+                short_line,
+                &TextPathOptions {
+                    clip_overflow: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(clipped.overflow_clusters, reported.overflow_clusters);
+        assert_eq!(clipped.glyphs.len(), 11 - reported.overflow_clusters);
+        assert!(clipped.glyphs.iter().all(|g| !g.overflow && g.x <= 40.0));
+    }
+
+    #[test]
+    #[cfg(feature = "raster")]
+    fn test_cache_budget_evicts_least_recently_used() {
+        let (mut shaper, style) = inter_shaper();
+        let glyphs = shaper
+            .shape_line_native("abcdef", 32.0, 40.0, &style)
+            .unwrap()
+            .glyphs;
+        for glyph in &glyphs {
+            shaper.rasterize_glyph_native(glyph.cosmic_font_id, glyph.glyph_id, 32.0);
+        }
+        // Touch the first glyph so it becomes the most recently used
+        shaper.rasterize_glyph_native(glyphs[0].cosmic_font_id, glyphs[0].glyph_id, 32.0);
+
+        let before = shaper.cache_stats_native();
+        let budget = (before.shaped_line_bytes + before.glyph_bytes) / 2;
+        let evicted = shaper.set_cache_budget_native(Some(budget));
+
+        let after = shaper.cache_stats_native();
+        assert!(after.shaped_line_bytes + after.glyph_bytes <= budget);
+        assert!(!evicted.is_empty());
+        assert_eq!(
+            after.evictions as usize,
+            evicted.len() + (1 - after.shaped_lines)
+        );
+        assert_eq!(evicted[0].glyph_id, glyphs[1].glyph_id);
+        assert!(evicted.iter().all(|e| e.glyph_id != glyphs[0].glyph_id));
+        assert!(shaper.take_evicted_glyphs_native().is_empty());
+    }
+
+    const JETBRAINS_MONO: &[u8] = include_bytes!("../../../assets/JetBrainsMono-Regular.ttf");
+
+    #[test]
+    #[cfg(feature = "rich-text")]
+    fn test_draw_runs_merge_font_and_color() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        shaper
+            .register_font_with_name("Mono", JETBRAINS_MONO)
+            .unwrap();
+        let span = |text: &str, family: &str, color: u32| RichTextSpan {
+            text: text.to_string(),
+            style: FontStyleInput {
+                family: Some(family.to_string()),
+                ..Default::default()
+            },
+            color: Some(color),
+            ..Default::default()
+        };
+        let (red, blue) = (0xff0000ff, 0x0000ffff);
+        let spans = [
+            span("Hello ", "Inter", red),
+            span("world ", "Inter", blue),
+            span("let", "Mono", blue),
+            span(" x", "Mono", blue),
+        ];
+        let options = LayoutOptions {
+            draw_runs: true,
+            ..Default::default()
+        };
+
+        let layout = shaper
+            .layout_rich_text_native(&spans, 16.0, 20.0, 1000.0, &options)
+            .unwrap();
+        assert_eq!(layout.lines.len(), 1);
+        let line = &layout.lines[0];
+        let runs: Vec<(Option<u32>, usize, usize)> = line
+            .draw_runs
+            .iter()
+            .map(|run| (run.color, run.glyph_start, run.glyph_end))
+            .collect();
+        assert_eq!(
+            runs,
+            [(Some(red), 0, 6), (Some(blue), 6, 12), (Some(blue), 12, 17)]
+        );
+        assert_eq!(
+            line.draw_runs[0].cosmic_font_id,
+            line.draw_runs[1].cosmic_font_id
+        );
+        assert_ne!(
+            line.draw_runs[1].cosmic_font_id,
+            line.draw_runs[2].cosmic_font_id
+        );
+        for run in &line.draw_runs {
+            assert!(line.glyphs[run.glyph_start..run.glyph_end]
+                .iter()
+                .all(|glyph| glyph.cosmic_font_id == run.cosmic_font_id));
+        }
+
+        // Plain text is one run without a color, and runs are opt-in
+        let style = FontStyleInput {
+            family: Some("Inter".to_string()),
+            ..Default::default()
+        };
+        let plain = shaper
+            .layout_text_native("Hello", 16.0, 20.0, 1000.0, &style, &options)
+            .unwrap();
+        assert_eq!(plain.lines[0].draw_runs.len(), 1);
+        assert_eq!(plain.lines[0].draw_runs[0].color, None);
+        assert_eq!(plain.lines[0].draw_runs[0].glyph_end, 5);
+        let without = shaper
+            .layout_rich_text_native(&spans, 16.0, 20.0, 1000.0, &LayoutOptions::default())
+            .unwrap();
+        assert!(without.lines[0].draw_runs.is_empty());
+    }
+
+    #[test]
+    fn test_baseline_grid_snaps_every_line() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(6);
+        let style = FontStyleInput::default();
+        let plain = shaper
+            .layout_text_native(&text, 15.0, 22.0, 160.0, &style, &LayoutOptions::default())
+            .unwrap();
+        let options = LayoutOptions {
+            baseline_grid: Some(4.0),
+            ..Default::default()
+        };
+        let snapped = shaper
+            .layout_text_native(&text, 15.0, 22.0, 160.0, &style, &options)
+            .unwrap();
+
+        assert!(snapped.lines.len() > 3);
+        assert_eq!(snapped.lines.len(), plain.lines.len());
+        for (line, natural) in snapped.lines.iter().zip(&plain.lines) {
+            assert_eq!(line.y % 4.0, 0.0, "baseline {} is off the grid", line.y);
+            assert!(line.baseline_shift >= 0.0);
+            assert_eq!(line.y, natural.y + line.baseline_shift);
+        }
+        for pair in snapped.lines.windows(2) {
+            assert!(pair[1].y - pair[0].y >= 22.0);
+        }
+        let last = snapped.lines.last().unwrap();
+        assert_eq!(
+            snapped.total_height,
+            plain.total_height + last.baseline_shift
+        );
+    }
+
+    #[test]
+    fn test_ink_bounds_follow_painted_outline() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        let style = FontStyleInput::default();
+        let size = 32.0;
+
+        let period = shaper.shape_line_native(".", size, size, &style).unwrap();
+        let glyph = &period.glyphs[0];
+        let ink = shaper
+            .glyph_ink_bounds_native(glyph.cosmic_font_id, glyph.glyph_id, size)
+            .unwrap();
+        // A small dot sitting on the baseline, inside a full-height advance box
+        let advance_area = glyph.x_advance * (period.ascent + period.descent);
+        assert!(ink.width() * ink.height() < advance_area / 10.0);
+        assert!(ink.y_max > -1.0 && ink.y_max < 1.0, "{:?}", ink);
+        assert!(ink.x_min > 0.0 && ink.x_max < glyph.x_advance);
+        let string_ink = shaper.ink_bounds_native(".", size, &style).unwrap();
+        assert!((string_ink.width() - ink.width()).abs() < 1.0);
+
+        // The ring of an A with ring above reaches past the font's ascent
+        let ring = shaper.ink_bounds_native("\u{c5}", size, &style).unwrap();
+        let font_id = shaper.cosmic_id_to_fontdb[&glyph.cosmic_font_id];
+        let font = shaper
+            .font_system
+            .get_font(font_id, Weight::NORMAL)
+            .unwrap();
+        let metrics = font.as_swash().metrics(&[]).scale(size);
+        assert!(
+            -ring.y_min > metrics.ascent,
+            "{:?} vs {}",
+            ring,
+            metrics.ascent
+        );
+
+        // Glyphs that paint nothing have no ink
+        assert_eq!(shaper.ink_bounds_native(" ", size, &style), None);
+    }
+
+    #[test]
+    fn test_fallback_chain_orders_overlapping_fonts() {
+        let mut shaper = TextShaper::new();
+        let inter = shaper.register_font(INTER).unwrap().id();
+        let mono = shaper.register_font(JETBRAINS_MONO).unwrap().id();
+        let style = FontStyleInput::default();
+        let fonts_used = |shaper: &mut TextShaper| {
+            let line = shaper
+                .shape_line_native("Hello", 16.0, 20.0, &style)
+                .unwrap();
+            let layout = shaper
+                .layout_text_native(
+                    "Hello world",
+                    16.0,
+                    20.0,
+                    1000.0,
+                    &style,
+                    &LayoutOptions::default(),
+                )
+                .unwrap();
+            line.glyphs
+                .iter()
+                .chain(layout.lines.iter().flat_map(|line| line.glyphs.iter()))
+                .map(|glyph| {
+                    shaper.face_registrations[&shaper.cosmic_id_to_fontdb[&glyph.cosmic_font_id]]
+                })
+                .collect::<std::collections::HashSet<u32>>()
+        };
+
+        shaper.set_fallback_chain_native(&[inter, mono]).unwrap();
+        assert_eq!(fonts_used(&mut shaper), [inter].into());
+        assert_eq!(shaper.effective_font_for_native('A', &style), Some(inter));
+
+        shaper.set_fallback_chain_native(&[mono, inter]).unwrap();
+        assert_eq!(fonts_used(&mut shaper), [mono].into());
+        assert_eq!(shaper.effective_font_for_native('A', &style), Some(mono));
+
+        // A family that matches nothing falls through to the chain as well
+        let missing = FontStyleInput {
+            family: Some("Missing".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(shaper.effective_font_for_native('A', &missing), Some(mono));
+        shaper.set_fallback_chain_native(&[inter, mono]).unwrap();
+        assert_eq!(shaper.effective_font_for_native('A', &missing), Some(inter));
+
+        // Neither font covers CJK, so there is nothing to pre-warm
+        assert_eq!(shaper.effective_font_for_native('\u{4E00}', &style), None);
+        assert_eq!(
+            shaper.set_fallback_chain_native(&[99]).unwrap_err(),
+            "Unknown font ID: 99"
+        );
+    }
+
+    #[test]
+    fn test_word_boxes_cover_each_word_once_per_line() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        let text = "The quick brown fox jumps over the lazy dog";
+        let boxes = shaper.word_boxes_native(text, 16.0, 20.0, 200.0, &FontStyleInput::default());
+
+        let words: Vec<&str> = boxes
+            .iter()
+            .map(|b| &text[b.byte_start..b.byte_end])
+            .collect();
+        assert_eq!(words, text.split(' ').collect::<Vec<_>>());
+        assert_eq!(boxes.last().unwrap().line_index, 1);
+        for pair in boxes.windows(2) {
+            if pair[0].line_index == pair[1].line_index {
+                assert!(pair[0].x + pair[0].width <= pair[1].x, "{:?}", pair);
+            } else {
+                assert!(pair[1].y >= pair[0].y + pair[0].height);
+            }
+        }
+        assert!(boxes.iter().all(|b| b.width > 0.0 && b.height == 20.0));
+    }
+
+    #[test]
+    fn test_hyphenated_lines_end_with_visible_hyphen() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        shaper
+            .register_hyphenation_patterns_native("de", "\\patterns{ u1d f1s }")
+            .unwrap();
+        let style = FontStyleInput::default();
+        let plain = LayoutOptions::default();
+        let hyphenate = LayoutOptions {
+            hyphenate: true,
+            language: Some("de".to_string()),
+            ..Default::default()
+        };
+
+        let soft = "Donau\u{AD}dampf\u{AD}schiff";
+        let auto = "Donaudampfschiff";
+        for (text, options) in [(soft, &plain), (auto, &hyphenate)] {
+            let layout = shaper
+                .layout_text_native(text, 16.0, 20.0, 70.0, &style, options)
+                .unwrap();
+            // Each hyphen ends where the text after the break begins
+            let rest: Vec<&str> = layout.lines[..layout.lines.len() - 1]
+                .iter()
+                .map(|line| {
+                    assert!(line.hyphenated && line.width <= 70.0, "{:?}", line);
+                    &text[line.glyphs.last().unwrap().end..]
+                })
+                .collect();
+            assert_eq!(rest.last().copied(), Some("schiff"));
+            assert!(!layout.lines.last().unwrap().hyphenated);
+            let end = layout.lines.last().unwrap().glyphs.last().unwrap().end;
+            assert_eq!(end, text.len());
+        }
+
+        let wide = shaper
+            .layout_text_native(soft, 16.0, 20.0, 400.0, &style, &plain)
+            .unwrap();
+        assert_eq!(wide.lines.len(), 1);
+        assert!(!wide.lines[0].hyphenated);
+        let unhyphenated = shaper
+            .layout_text_native(auto, 16.0, 20.0, 70.0, &style, &plain)
+            .unwrap();
+        assert!(unhyphenated.lines.iter().all(|line| !line.hyphenated));
+    }
+
+    #[test]
+    fn test_batch_registration_reports_metadata_and_finds_members() {
+        let mut shaper = TextShaper::new();
+        let fonts = vec![
+            INTER.to_vec(),
+            include_bytes!("../../../assets/InterVariable-Italic.ttf").to_vec(),
+            JETBRAINS_MONO.to_vec(),
+            include_bytes!("../../../assets/JetBrainsMono-SemiBold.ttf").to_vec(),
+            b"not a font".to_vec(),
+        ];
+        let registered = shaper.register_fonts_batch_native(fonts);
+        assert_eq!(registered.len(), 5);
+        let [inter, inter_italic, mono, mono_semibold, broken] = &registered[..] else {
+            unreachable!();
+        };
+
+        assert!(inter.families.contains(&"Inter Variable".to_string()));
+        assert_eq!(inter.style, "normal");
+        assert_eq!(inter_italic.style, "italic");
+        assert!(inter.is_variable);
+        let wght = inter.axes.iter().find(|axis| axis.tag == "wght").unwrap();
+        assert!(wght.min <= 100.0 && wght.max >= 900.0);
+        assert!(!mono.is_variable && mono.axes.is_empty());
+        assert_eq!((mono.weight, mono_semibold.weight), (400, 600));
+        assert_eq!(mono.stretch, "normal");
+
+        // A bad file gets an error entry without costing the batch an id
+        assert!(broken.id.is_none() && broken.error.is_some());
+        let ids: Vec<u32> = registered[..4].iter().filter_map(|font| font.id).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+
+        let find = |shaper: &mut TextShaper, family: &str, weight, style: &str| {
+            shaper.find_font(family, weight, Some(style.to_string()))
+        };
+        assert_eq!(find(&mut shaper, "Inter Variable", 400, "normal"), inter.id);
+        assert_eq!(
+            find(&mut shaper, "Inter Variable", 400, "italic"),
+            inter_italic.id
+        );
+        assert_eq!(find(&mut shaper, "JetBrains Mono", 400, "normal"), mono.id);
+        assert_eq!(
+            find(&mut shaper, "JetBrains Mono", 600, "normal"),
+            mono_semibold.id
+        );
+        assert_eq!(find(&mut shaper, "Missing Family", 400, "normal"), None);
+    }
+
+    #[test]
+    fn test_collapsed_whitespace_maps_offsets_to_original_text() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        let style = FontStyleInput::default();
+        let layout = |shaper: &mut TextShaper, text: &str, white_space| {
+            let options = LayoutOptions {
+                white_space,
+                ..Default::default()
+            };
+            shaper
+                .layout_text_native(text, 16.0, 20.0, 1000.0, &style, &options)
+                .unwrap()
+        };
+        // The original byte range covered by a line's glyphs
+        let range = |line: &LayoutLine| {
+            let start = line.glyphs.iter().map(|glyph| glyph.start).min().unwrap();
+            let end = line.glyphs.iter().map(|glyph| glyph.end).max().unwrap();
+            start..end
+        };
+
+        let text = "  Hello \t\n  wide   world \n";
+        let normal = layout(&mut shaper, text, WhiteSpace::Normal);
+        assert_eq!(normal.lines.len(), 1);
+        let glyphs = &normal.lines[0].glyphs;
+        // "Hello wide world": the word after each collapsed run still
+        // selects its own bytes, and each collapsed space its whole run
+        assert_eq!(glyphs.len(), 16);
+        let word = glyphs[11].start..glyphs[15].end;
+        assert_eq!(&text[word], "world");
+        assert_eq!(&text[glyphs[5].start..glyphs[5].end], " \t\n  ");
+        assert_eq!(&text[range(&normal.lines[0])], "Hello \t\n  wide   world");
+
+        let pre_line = layout(&mut shaper, text, WhiteSpace::PreLine);
+        // The kept trailing newline ends an empty last line, as in pre-wrap
+        assert_eq!(pre_line.lines.len(), 3);
+        // The second line counts from the original paragraph it starts in
+        let second = text.split('\n').nth(1).unwrap();
+        assert_eq!(&second[range(&pre_line.lines[1])], "wide   world");
+        assert_eq!(pre_line.lines[1].glyphs.len(), "wide world".len());
+
+        let pre = layout(&mut shaper, text, WhiteSpace::Pre);
+        let pre_wrap = layout(&mut shaper, text, WhiteSpace::PreWrap);
+        assert_eq!(pre.lines.len(), pre_wrap.lines.len());
+        assert_eq!(&text[range(&pre.lines[0])], "  Hello \t");
+
+        // Only pre keeps a long line from wrapping
+        let long = "one two three four five six";
+        let narrow = |shaper: &mut TextShaper, white_space| {
+            let options = LayoutOptions {
+                white_space,
+                ..Default::default()
+            };
+            let layout = shaper
+                .layout_text_native(long, 16.0, 20.0, 60.0, &style, &options)
+                .unwrap();
+            layout.lines.len()
+        };
+        assert_eq!(narrow(&mut shaper, WhiteSpace::Pre), 1);
+        assert!(narrow(&mut shaper, WhiteSpace::PreWrap) > 1);
+    }
+
+    #[test]
+    #[cfg(feature = "color-emoji")]
+    fn test_raster_options_premultiply_and_gamma_correct() {
+        let (mut shaper, style) = inter_shaper();
+        shaper
+            .register_font_with_name(
+                "Noto Color Emoji",
+                include_bytes!("../../../assets/NotoColorEmoji-Regular.ttf"),
+            )
+            .unwrap();
+        let emoji_style = FontStyleInput {
+            family: Some("Noto Color Emoji".to_string()),
+            ..Default::default()
+        };
+        let premultiplied = RasterOptions {
+            premultiplied: true,
+            ..Default::default()
+        };
+
+        let emoji = &shaper
+            .shape_line_native("\u{1F600}", 64.0, 80.0, &emoji_style)
+            .unwrap()
+            .glyphs[0];
+        let (font, glyph) = (emoji.cosmic_font_id, emoji.glyph_id);
+        let straight = shaper.rasterize_glyph_native(font, glyph, 64.0);
+        let multiplied =
+            shaper.rasterize_glyph_with_options_native(font, glyph, 64.0, &premultiplied);
+        assert!(straight.is_color && multiplied.is_color);
+        // Only the edges are translucent, so those pixels must change
+        assert_ne!(straight.pixels, multiplied.pixels);
+        for pixel in multiplied.pixels.chunks_exact(4) {
+            assert!(pixel[..3].iter().all(|&channel| channel <= pixel[3]));
+        }
+
+        let letter = &shaper
+            .shape_line_native("O", 24.0, 30.0, &style)
+            .unwrap()
+            .glyphs[0];
+        let (font, glyph) = (letter.cosmic_font_id, letter.glyph_id);
+        let raw = shaper.rasterize_glyph_native(font, glyph, 24.0);
+        let corrected = shaper.rasterize_glyph_with_options_native(
+            font,
+            glyph,
+            24.0,
+            &RasterOptions {
+                gamma_correct: true,
+                ..Default::default()
+            },
+        );
+        // Masks have no color to premultiply
+        let mask_premultiplied =
+            shaper.rasterize_glyph_with_options_native(font, glyph, 24.0, &premultiplied);
+        assert_eq!(mask_premultiplied.pixels, raw.pixels);
+
+        let mut midtones = 0;
+        for (&before, &after) in raw.pixels.iter().zip(&corrected.pixels) {
+            match before {
+                0 | 255 => assert_eq!(after, before),
+                _ => {
+                    assert!(after >= before);
+                    if (64..192).contains(&before) {
+                        assert!(after > before);
+                        midtones += 1;
+                    }
+                }
+            }
+        }
+        assert!(midtones > 0);
+    }
+
+    #[test]
+    fn test_trailing_whitespace_hangs_outside_line_width() {
+        let (mut shaper, style) = inter_shaper();
+        let options = LayoutOptions::default();
+        let plain = shaper
+            .layout_text_native("abc", 16.0, 20.0, 400.0, &style, &options)
+            .unwrap();
+        let spaced = shaper
+            .layout_text_native("abc ", 16.0, 20.0, 400.0, &style, &options)
+            .unwrap();
+        let (plain, spaced) = (&plain.lines[0], &spaced.lines[0]);
+        assert_eq!(spaced.width, plain.width);
+        assert_eq!(plain.width_with_trailing_whitespace, plain.width);
+
+        // The space keeps its glyph, so a caret after it sits past the width
+        let space = spaced.glyphs.last().unwrap();
+        assert_eq!((space.start, space.end), (3, 4));
+        let caret = space.x + space.x_advance;
+        assert!(caret > spaced.width);
+        assert_eq!(spaced.width_with_trailing_whitespace, caret);
+
+        let measured = |shaper: &mut TextShaper, text| {
+            shaper
+                .measure_text_native(text, 16.0, 20.0, None, &style)
+                .unwrap()
+                .width
+        };
+        assert_eq!(measured(&mut shaper, "abc  "), measured(&mut shaper, "abc"));
+        let shaped = shaper
+            .shape_line_native("abc ", 16.0, 20.0, &style)
+            .unwrap();
+        assert_eq!(shaped.width, plain.width);
+        assert!(shaped.width_with_trailing_whitespace > shaped.width);
+
+        // Wrapped lines hang the space they break at; inner spaces count
+        let wrapped = shaper
+            .layout_text_native("abc abc abc", 16.0, 20.0, 40.0, &style, &options)
+            .unwrap();
+        assert!(wrapped.lines.len() > 1);
+        assert_eq!(wrapped.lines[0].width, plain.width);
+        let inner = shaper
+            .layout_text_native("a b", 16.0, 20.0, 400.0, &style, &options)
+            .unwrap();
+        assert_eq!(
+            inner.lines[0].width,
+            inner.lines[0].width_with_trailing_whitespace
+        );
+    }
+
+    #[test]
+    fn test_non_finite_fields_are_named() {
+        let (mut shaper, style) = inter_shaper();
+        let mut layout = shaper
+            .layout_text_native("a b", 12.0, 16.0, 100.0, &style, &LayoutOptions::default())
+            .unwrap();
+        layout.lines[0].glyphs[1].x_advance = f32::NAN;
+        let non_finite = finite::first_non_finite(&mut layout).unwrap();
+        assert_eq!(non_finite.field, "lines[0].glyphs[1].x_advance");
+        assert_eq!(
+            non_finite.to_string(),
+            "Non-finite lines[0].glyphs[1].x_advance: NaN"
+        );
+    }
+
+    #[test]
+    fn test_invalid_metrics_are_rejected_with_codes() {
+        let (mut shaper, style) = inter_shaper();
+        let cases = [
+            (0.0, 16.0, "font_size_not_positive"),
+            (-4.0, 16.0, "font_size_not_positive"),
+            (f32::NAN, 16.0, "font_size_not_a_number"),
+            (f32::INFINITY, 16.0, "font_size_too_large"),
+            (MAX_FONT_SIZE + 1.0, 16.0, "font_size_too_large"),
+            (12.0, 0.0, "line_height_not_positive"),
+            (12.0, -1.0, "line_height_not_positive"),
+            (12.0, f32::NAN, "line_height_not_a_number"),
+            (12.0, f32::INFINITY, "line_height_too_large"),
+            (0.0, f32::NAN, "font_size_not_positive"),
+        ];
+        for (font_size, line_height, code) in cases {
+            let shaped = shaper.shape_line_native("Hi", font_size, line_height, &style);
+            assert_eq!(shaped.unwrap_err().code(), code);
+            let layout = shaper.layout_text_native(
+                "Hi there",
+                font_size,
+                line_height,
+                100.0,
+                &style,
+                &LayoutOptions::default(),
+            );
+            assert_eq!(layout.unwrap_err().code(), code);
+            let measured = shaper.measure_text_native("Hi", font_size, line_height, None, &style);
+            assert_eq!(measured.unwrap_err().code(), code);
+        }
+        assert_eq!(
+            shaper
+                .shape_line_native("Hi", MAX_FONT_SIZE + 1.0, 16.0, &style)
+                .unwrap_err()
+                .to_string(),
+            "font_size 10001 is above the maximum of 10000"
+        );
+
+        // The largest font size still shapes
+        let line = shaper
+            .shape_line_native("Hi", MAX_FONT_SIZE, MAX_FONT_SIZE, &style)
+            .unwrap();
+        assert!(!line.glyphs.is_empty() && !line.clipped_lines);
+
+        // A line height below the font size shapes, flagged as clipping
+        for (line_height, clipped) in [(11.0, true), (12.0, false), (16.0, false)] {
+            let line = shaper.shape_line_native("Hi", 12.0, line_height, &style);
+            assert_eq!(line.unwrap().clipped_lines, clipped);
+            let layout = shaper
+                .layout_text_native(
+                    "Hi there you",
+                    12.0,
+                    line_height,
+                    40.0,
+                    &style,
+                    &LayoutOptions::default(),
+                )
+                .unwrap();
+            assert!(layout.lines.len() > 1);
+            assert_eq!(layout.clipped_lines, clipped);
+            let measured = shaper
+                .measure_text_native("Hi\nthere", 12.0, line_height, None, &style)
+                .unwrap();
+            assert_eq!(measured.clipped_lines, clipped);
+        }
+        // Bucketed sizes flag against the requested sizes
+        let line = shaper.shape_line_native("Hi", 13.2, 13.1, &style).unwrap();
+        assert!(line.clipped_lines);
+    }
+
+    #[test]
+    #[cfg(feature = "raster")]
+    fn test_glyph_scaler_matches_swash_cache() {
+        let (mut shaper, style) = inter_shaper();
+        let text = "The quick brown fox jumps over the lazy dog? 0123456789 \
+            ABCDEFGHIJKLMNOPQRSTUVWXYZ abcdefghijklmnopq";
+        let line = shaper.shape_line_native(text, 16.0, 20.0, &style).unwrap();
+        let cosmic_font_id = line.glyphs[0].cosmic_font_id;
+        let font_id = shaper.cosmic_id_to_fontdb[&cosmic_font_id];
+        let glyph_ids: Vec<u16> = line.glyphs.iter().map(|g| g.glyph_id as u16).collect();
+        assert!(glyph_ids.len() >= 100);
+
+        let image = |image: Option<cosmic_text::SwashImage>| {
+            image.map(|image| {
+                let placement = image.placement;
+                let bounds = (
+                    placement.left,
+                    placement.top,
+                    placement.width,
+                    placement.height,
+                );
+                (bounds, image.content, image.data)
+            })
+        };
+        let mut swash_cache = SwashCache::new();
+        let sizes = [12.0, 14.0, 16.5, 20.0, 24.0];
+        let requests = sizes.iter().flat_map(|&size| {
+            glyph_ids[..100]
+                .iter()
+                .map(move |&glyph_id| (size, glyph_id))
+        });
+        for (size, glyph_id) in requests {
+            let (cache_key, _, _) = CacheKey::new(
+                font_id,
+                glyph_id,
+                size,
+                (0.0, 0.0),
+                Weight::NORMAL,
+                CacheKeyFlags::empty(),
+            );
+            let expected = swash_cache.get_image_uncached(&mut shaper.font_system, cache_key);
+            let actual =
+                shaper
+                    .glyph_scaler
+                    .render(&mut shaper.font_system, font_id, glyph_id, size);
+            assert_eq!(
+                image(actual),
+                image(expected),
+                "glyph {glyph_id} at {size}px"
+            );
+        }
+        assert_eq!(shaper.cache_stats_native().raster_faces, 1);
+        shaper.clear_cache();
+        assert_eq!(shaper.cache_stats_native().raster_faces, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "rich-text")]
+    fn test_shadow_runs_offset_main_glyphs_in_drawing_order() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        shaper
+            .register_font_with_name("Mono", JETBRAINS_MONO)
+            .unwrap();
+        let span = |text: &str, family: &str| RichTextSpan {
+            text: text.to_string(),
+            style: FontStyleInput {
+                family: Some(family.to_string()),
+                ..Default::default()
+            },
+            color: None,
+            ..Default::default()
+        };
+        let spans = [span("Shadowed text ", "Inter"), span("wraps", "Mono")];
+        let (near, far) = (0x00000080, 0xff000040);
+        let options = LayoutOptions {
+            shadows: vec![
+                TextShadow {
+                    offset_x: 1.0,
+                    offset_y: 1.5,
+                    color: near,
+                },
+                TextShadow {
+                    offset_x: -3.0,
+                    offset_y: 4.0,
+                    color: far,
+                },
+            ],
+            ..Default::default()
+        };
+        let layout = shaper
+            .layout_rich_text_native(&spans, 16.0, 20.0, 90.0, &options)
+            .unwrap();
+        assert!(layout.lines.len() > 1);
+
+        // The last shadow is drawn first, and each covers every glyph of
+        // every line once, grouped by font
+        let split = layout
+            .shadow_runs
+            .iter()
+            .position(|run| run.color == near)
+            .unwrap();
+        let (first, second) = layout.shadow_runs.split_at(split);
+        for (runs, shadow) in [(first, &options.shadows[1]), (second, &options.shadows[0])] {
+            let mut covered: Vec<(usize, usize)> = Vec::new();
+            for run in runs {
+                let line = &layout.lines[run.line];
+                assert_eq!(run.color, shadow.color);
+                assert_eq!(run.x, shadow.offset_x);
+                assert_eq!(run.y, line.y + shadow.offset_y);
+                assert!(line.glyphs[run.glyph_start..run.glyph_end]
+                    .iter()
+                    .all(|glyph| glyph.cosmic_font_id == run.cosmic_font_id));
+                covered.extend((run.glyph_start..run.glyph_end).map(|index| (run.line, index)));
+            }
+            let all: Vec<(usize, usize)> = layout
+                .lines
+                .iter()
+                .enumerate()
+                .flat_map(|(line, l)| (0..l.glyphs.len()).map(move |index| (line, index)))
+                .collect();
+            assert_eq!(covered, all);
+        }
+        assert!(first.iter().all(|run| run.color == far));
+
+        // Shadows add no glyphs, and none are emitted unless asked for
+        let plain = shaper
+            .layout_rich_text_native(&spans, 16.0, 20.0, 90.0, &LayoutOptions::default())
+            .unwrap();
+        assert!(plain.shadow_runs.is_empty());
+        let glyph_counts = |layout: &LayoutResult| -> Vec<usize> {
+            layout.lines.iter().map(|line| line.glyphs.len()).collect()
+        };
+        assert_eq!(glyph_counts(&plain), glyph_counts(&layout));
+    }
+
+    #[test]
+    fn test_hash_text_input_covers_every_input() {
+        let shaper = TextShaper::new();
+        let style = FontStyleInput {
+            family: Some("Inter".to_string()),
+            weight: Some(600),
+            style: Some("italic".to_string()),
+            stretch: Some("condensed".to_string()),
+        };
+        let hash = shaper.hash_text_input_native("Hello", 14.0, &style);
+        assert_eq!(shaper.hash_text_input_native("Hello", 14.0, &style), hash);
+
+        // A new style field fails to compile here until it is changed below
+        let FontStyleInput {
+            family: _,
+            weight: _,
+            style: _,
+            stretch: _,
+        } = &style;
+        let changed = [
+            FontStyleInput {
+                family: Some("Inter Display".to_string()),
+                ..style.clone()
+            },
+            FontStyleInput {
+                weight: Some(700),
+                ..style.clone()
+            },
+            FontStyleInput {
+                style: None,
+                ..style.clone()
+            },
+            FontStyleInput {
+                stretch: Some("expanded".to_string()),
+                ..style.clone()
+            },
+        ];
+        for changed in &changed {
+            assert_ne!(
+                shaper.hash_text_input_native("Hello", 14.0, changed),
+                hash,
+                "{:?}",
+                changed
+            );
+        }
+        assert_ne!(shaper.hash_text_input_native("Hello!", 14.0, &style), hash);
+        assert_ne!(shaper.hash_text_input_native("Hello", 14.5, &style), hash);
+    }
+
+    /// A font whose glyph 1 is a square filled by a COLR v1 linear gradient
+    /// from red to blue, and whose glyph 0 paints itself, a cycle.
+    #[cfg(feature = "color-emoji")]
+    fn colr_v1_font() -> Vec<u8> {
+        fn be(values: &[i32], widths: &[usize]) -> Vec<u8> {
+            values
+                .iter()
+                .zip(widths)
+                .flat_map(|(&value, &width)| value.to_be_bytes()[4 - width..].to_vec())
+                .collect()
+        }
+        let utf16 =
+            |text: &str| -> Vec<u8> { text.encode_utf16().flat_map(u16::to_be_bytes).collect() };
+        let (family, postscript) = (utf16("Colr Test"), utf16("ColrTest"));
+        let name = [
+            be(&[0, 2, 30], &[2, 2, 2]),
+            be(&[3, 1, 0x409, 1, family.len() as i32, 0], &[2; 6]),
+            be(
+                &[3, 1, 0x409, 6, postscript.len() as i32, family.len() as i32],
+                &[2; 6],
+            ),
+            family,
+            postscript,
+        ]
+        .concat();
+        let tables: [(&[u8; 4], Vec<u8>); 9] = [
+            (
+                b"COLR",
+                [
+                    // Version 1 with only a base glyph list, at 34
+                    be(
+                        &[1, 0, 0, 0, 0, 34, 0, 0, 0, 0],
+                        &[2, 2, 4, 4, 2, 4, 4, 4, 4, 4],
+                    ),
+                    be(&[2, 0, 16, 1, 19], &[4, 2, 4, 2, 4]),
+                    // PaintColrGlyph of glyph 0
+                    be(&[11, 0], &[1, 2]),
+                    // PaintGlyph of glyph 1, then a PaintLinearGradient
+                    be(&[10, 6, 1], &[1, 3, 2]),
+                    be(
+                        &[4, 16, 100, 500, 900, 500, 100, 900],
+                        &[1, 3, 2, 2, 2, 2, 2, 2],
+                    ),
+                    // Padded color line, red to blue
+                    be(
+                        &[0, 2, 0, 0, 0x4000, 0x4000, 1, 0x4000],
+                        &[1, 2, 2, 2, 2, 2, 2, 2],
+                    ),
+                ]
+                .concat(),
+            ),
+            (
+                b"CPAL",
+                [
+                    be(&[0, 2, 1, 2, 14, 0], &[2, 2, 2, 2, 4, 2]),
+                    vec![0, 0, 255, 255, 255, 0, 0, 255],
+                ]
+                .concat(),
+            ),
+            (
+                b"glyf",
+                be(
+                    &[
+                        1, 100, 100, 900, 900, 3, 0, 0x0101, 0x0101, 100, 0, 800, 0, 100, 800, 0,
+                        -800, 0,
+                    ],
+                    &[2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+                ),
+            ),
+            (
+                b"head",
+                be(
+                    &[
+                        0x10000,
+                        0x10000,
+                        0,
+                        0x5F0F_3CF5,
+                        3,
+                        1000,
+                        0,
+                        0,
+                        0,
+                        0,
+                        0,
+                        0,
+                        1000,
+                        1000,
+                        0,
+                        8,
+                        2,
+                        0,
+                        0,
+                    ],
+                    &[4, 4, 4, 4, 2, 2, 4, 4, 4, 4, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+                ),
+            ),
+            (
+                b"hhea",
+                be(
+                    &[
+                        0x10000, 1000, -200, 0, 1000, 0, 0, 1000, 1, 0, 0, 0, 0, 0, 0, 0, 2,
+                    ],
+                    &[4, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+                ),
+            ),
+            (b"hmtx", be(&[1000, 0, 1000, 100], &[2; 4])),
+            (b"loca", be(&[0, 0, 18], &[2; 3])),
+            (
+                b"maxp",
+                be(&[0x10000, 2, 4, 1, 0, 0, 2], &[4, 2, 2, 2, 2, 2, 2])
+                    .into_iter()
+                    .chain([0; 16])
+                    .collect(),
+            ),
+            (b"name", name),
+        ];
+
+        let mut font = be(&[0x10000, 9, 128, 3, 16], &[4, 2, 2, 2, 2]);
+        let mut offset = 12 + 16 * tables.len();
+        let mut data = Vec::new();
+        for (tag, table) in &tables {
+            font.extend_from_slice(*tag);
+            font.extend(be(&[0, offset as i32, table.len() as i32], &[4; 3]));
+            data.extend_from_slice(table);
+            data.resize(data.len().next_multiple_of(4), 0);
+            offset = 12 + 16 * tables.len() + data.len();
+        }
+        font.extend(data);
+        font
+    }
+
+    #[test]
+    #[cfg(feature = "color-emoji")]
+    fn test_colr_v1_gradient_glyph_rasterizes_to_rgba() {
+        let mut shaper = TextShaper::new();
+        let registered = shaper.register_fonts_batch_native(vec![colr_v1_font()]);
+        let id = registered[0].id.expect("test font loads");
+        let face = shaper.font_faces[&id][0];
+        let cosmic_font_id = shaper.cosmic_font_id(face);
+
+        let glyph = shaper.rasterize_glyph_native(cosmic_font_id, 1, 64.0);
+        assert!(glyph.is_color);
+        // The square spans 0.1 to 0.9 em
+        assert_eq!((glyph.width, glyph.height), (52, 52));
+        assert_eq!((glyph.bearing_x, glyph.bearing_y), (6, 58));
+        assert_eq!(glyph.pixels.len(), 52 * 52 * 4);
+        let colors: std::collections::HashSet<&[u8]> = glyph.pixels.chunks_exact(4).collect();
+        assert!(colors.len() > 1);
+        // Red at the left, blue at the right, inside the antialiased edges
+        let row = &glyph.pixels[26 * 52 * 4..27 * 52 * 4];
+        let (left, right) = (&row[4..8], &row[row.len() - 8..row.len() - 4]);
+        assert!(
+            left[0] > 200 && left[2] < 50 && left[3] == 255,
+            "{:?}",
+            left
+        );
+        assert!(
+            right[2] > 200 && right[0] < 50 && right[3] == 255,
+            "{:?}",
+            right
+        );
+
+        // The cycle is rejected rather than followed, leaving glyph 0 to
+        // its empty outline
+        let cycle = shaper.rasterize_glyph_native(cosmic_font_id, 0, 64.0);
+        assert!(!cycle.is_color && cycle.pixels.is_empty());
+    }
+
+    #[test]
+    fn test_text_case_maps_offsets_to_original_text() {
+        let (mut shaper, style) = inter_shaper();
+        let cased = |shaper: &TextShaper, text: &str, case, lang| {
+            shaper.transform_text_case_native(text, case, Some(lang))
+        };
+        assert_eq!(cased(&shaper, "i", TextCase::Upper, "tr").text, "\u{130}");
+        assert_eq!(cased(&shaper, "i", TextCase::Upper, "en").text, "I");
+        assert_eq!(
+            cased(&shaper, "I", TextCase::Lower, "tr-TR").text,
+            "\u{131}"
+        );
+        assert_eq!(cased(&shaper, "I", TextCase::Lower, "en").text, "i");
+        assert_eq!(
+            cased(&shaper, "straße", TextCase::Title, "de").text,
+            "Straße"
+        );
+
+        let mapping =
+            |original: std::ops::Range<usize>, transformed: std::ops::Range<usize>| CaseMapping {
+                original_start: original.start,
+                original_end: original.end,
+                transformed_start: transformed.start,
+                transformed_end: transformed.end,
+                one_to_one: original.len() == transformed.len(),
+            };
+        let upper = cased(&shaper, "straße", TextCase::Upper, "de");
+        assert_eq!(upper.text, "STRASSE");
+        // ß and SS are both two bytes, but S does not map into the middle of ß
+        let sharp_s = CaseMapping {
+            one_to_one: false,
+            ..mapping(4..6, 4..6)
+        };
+        assert_eq!(
+            upper.mappings,
+            vec![mapping(0..4, 0..4), sharp_s, mapping(6..7, 6..7)]
+        );
+        let turkish = cased(&shaper, "istanbul", TextCase::Upper, "tr");
+        assert_eq!(turkish.text, "\u{130}STANBUL");
+        assert_eq!(
+            turkish.mappings,
+            vec![mapping(0..1, 0..2), mapping(1..8, 2..9)]
+        );
+
+        // Laid out cased, glyphs still select whole characters of the text
+        // as passed in, and together cover all of it
+        let cases = [
+            ("straße", TextCase::Upper, "de", 7),
+            ("straße", TextCase::Title, "de", 6),
+            ("istanbul", TextCase::Upper, "tr", 8),
+        ];
+        for (text, text_case, language, glyph_count) in cases {
+            let options = LayoutOptions {
+                text_case: Some(text_case),
+                language: Some(language.to_string()),
+                ..Default::default()
+            };
+            let layout = shaper
+                .layout_text_native(text, 16.0, 20.0, 1000.0, &style, &options)
+                .unwrap();
+            let glyphs = &layout.lines[0].glyphs;
+            assert_eq!(glyphs.len(), glyph_count, "{text} {text_case:?}");
+            let mut covered = 0;
+            for glyph in glyphs {
+                assert!(text.is_char_boundary(glyph.start) && text.is_char_boundary(glyph.end));
+                assert!(
+                    glyph.start <= covered && glyph.start <= glyph.end,
+                    "{:?}",
+                    glyph
+                );
+                covered = covered.max(glyph.end);
+            }
+            assert_eq!(covered, text.len());
+        }
+        let options = LayoutOptions {
+            text_case: Some(TextCase::Upper),
+            ..Default::default()
+        };
+        let layout = shaper
+            .layout_text_native("straße", 16.0, 20.0, 1000.0, &style, &options)
+            .unwrap();
+        // Both glyphs of SS fall within ß
+        for glyph in &layout.lines[0].glyphs[4..6] {
+            assert!(glyph.start >= 4 && glyph.end <= 6, "{:?}", glyph);
+        }
+        assert_eq!(
+            layout.lines[0].glyphs[4].start..layout.lines[0].glyphs[4].end,
+            4..6
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "woff2")]
+    fn test_woff2_and_woff_fonts_shape_like_the_ttf() {
+        let woff2 = include_bytes!("../../../assets/JetBrainsMono-Regular.woff2");
+        let woff = include_bytes!("../../../assets/JetBrainsMono-Regular.woff");
+        let style = FontStyleInput {
+            family: Some("Mono".to_string()),
+            ..Default::default()
+        };
+        let shape = |font_data: &[u8]| {
+            let mut shaper = TextShaper::new();
+            shaper.register_font_with_name("Mono", font_data).unwrap();
+            let line = shaper
+                .shape_line_native("fn main() { x != 42; } // ->", 16.0, 20.0, &style)
+                .unwrap();
+            line.glyphs
+                .iter()
+                .map(|glyph| (glyph.glyph_id, glyph.x_advance))
+                .collect::<Vec<_>>()
+        };
+        let expected = shape(JETBRAINS_MONO);
+        assert_eq!(shape(woff2), expected);
+        assert_eq!(shape(woff), expected);
+
+        let mut shaper = TextShaper::new();
+        assert!(shaper.register_font_native(woff2).is_ok());
+        let registered = shaper.register_fonts_batch_native(vec![woff2.to_vec()]);
+        assert_eq!(registered[0].families, ["JetBrains Mono"]);
+
+        let truncated = &woff2[..woff2.len() / 2];
+        let error = shaper.register_font_native(truncated).unwrap_err();
+        assert_eq!(error.code(), "woff2_malformed");
+        let mut corrupt = woff2.to_vec();
+        let compressed_start = corrupt.len() - 1000;
+        corrupt[compressed_start..].fill(0xff);
+        let error = shaper.register_font_native(&corrupt).unwrap_err();
+        assert_eq!(error.code(), "woff2_decompress_failed");
+        let mut corrupt = woff.to_vec();
+        corrupt.truncate(100);
+        corrupt[8..12].copy_from_slice(&100u32.to_be_bytes());
+        let error = shaper.register_font_native(&corrupt).unwrap_err();
+        assert_eq!(error, FontDataError::Truncated(FontContainer::Woff));
+
+        let failed = shaper.register_fonts_batch_native(vec![truncated.to_vec()]);
+        assert_eq!(failed[0].id, None);
+        assert!(failed[0].error.as_deref().unwrap().starts_with("WOFF2"));
+    }
+
+    #[test]
+    fn test_layout_ink_bounds_cover_overhanging_italics() {
+        let mut shaper = TextShaper::new();
+        let italic = include_bytes!("../../../assets/InterVariable-Italic.ttf");
+        shaper
+            .register_font_with_name("Inter Italic", italic)
+            .unwrap();
+        let style = FontStyleInput {
+            family: Some("Inter Italic".to_string()),
+            style: Some("italic".to_string()),
+            ..FontStyleInput::default()
+        };
+        let options = LayoutOptions {
+            ink_bounds: true,
+            ..LayoutOptions::default()
+        };
+
+        let result = shaper
+            .layout_text_native("stuff\nof", 32.0, 40.0, 500.0, &style, &options)
+            .unwrap();
+        assert_eq!(result.lines.len(), 2);
+        for line in &result.lines {
+            assert_eq!(line.glyph_ink_bounds.len(), line.glyphs.len());
+            let ink = line.ink_bounds.unwrap();
+            // The hook of the final italic f hangs past its advance
+            assert!(ink.x_max > line.width, "{:?} vs {}", ink, line.width);
+            // Boxes are in layout space, around the line's baseline
+            assert!(ink.y_min < line.y && ink.y_max > line.y - 1.0, "{:?}", ink);
+
+            let union = line
+                .glyph_ink_bounds
+                .iter()
+                .flatten()
+                .fold(InkBounds::EMPTY, |union, bounds| union.union(bounds));
+            assert_eq!(union, ink);
+        }
+        assert!(result.lines[1].ink_bounds.unwrap().y_min > result.lines[0].y);
+
+        // Off by default, with nothing extra in the result
+        let plain = shaper
+            .layout_text_native(
+                "stuff",
+                32.0,
+                40.0,
+                500.0,
+                &style,
+                &LayoutOptions::default(),
+            )
+            .unwrap();
+        assert!(plain.lines[0].glyph_ink_bounds.is_empty());
+        assert_eq!(plain.lines[0].ink_bounds, None);
+    }
+
+    #[test]
+    fn test_wrapped_paragraph_matches_golden_numbers() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        let style = FontStyleInput::default();
+        let text = "The quick brown fox jumps over the lazy dog";
+        let result = shaper
+            .layout_text_native(text, 16.0, 20.0, 150.0, &style, &LayoutOptions::default())
+            .unwrap();
+
+        let mut values = vec![
+            ("lines".to_string(), result.lines.len() as f32),
+            ("total_width".to_string(), result.total_width),
+            ("total_height".to_string(), result.total_height),
+        ];
+        for (index, line) in result.lines.iter().enumerate() {
+            values.push((format!("line{}.width", index), line.width));
+            values.push((format!("line{}.y", index), line.y));
+        }
+        let ink = shaper.ink_bounds_native(text, 16.0, &style).unwrap();
+        values.extend([
+            ("ink.x_min".to_string(), ink.x_min),
+            ("ink.y_min".to_string(), ink.y_min),
+            ("ink.x_max".to_string(), ink.x_max),
+            ("ink.y_max".to_string(), ink.y_max),
+        ]);
+        tolerance::assert_matches_goldens!("wrapped_paragraph", &values);
+    }
+
+    #[test]
+    #[cfg(feature = "rich-text")]
+    fn test_superscript_span_is_raised_smaller_and_grows_the_line() {
+        let mut shaper = TextShaper::new();
+        shaper.register_font_with_name("Inter", INTER).unwrap();
+        let style = FontStyleInput {
+            family: Some("Inter".to_string()),
+            ..Default::default()
+        };
+        let span = |text: &str, baseline_shift, font_scale| RichTextSpan {
+            text: text.to_string(),
+            style: style.clone(),
+            baseline_shift,
+            font_scale,
+            ..Default::default()
+        };
+        let options = LayoutOptions {
+            ink_bounds: true,
+            ..Default::default()
+        };
+        let layout = |shaper: &mut TextShaper, spans: &[RichTextSpan]| {
+            shaper
+                .layout_rich_text_native(spans, 32.0, 40.0, 1000.0, &options)
+                .unwrap()
+        };
+
+        let plain = layout(&mut shaper, &[span("x2\nx", None, None)]);
+        let squared = layout(
+            &mut shaper,
+            &[
+                span("x", None, None),
+                span("2", Some(BaselineShift::Super), Some(0.75)),
+                span("\nx", None, None),
+            ],
+        );
+        let (plain_line, line) = (&plain.lines[0], &squared.lines[0]);
+        let (x, two) = (&line.glyphs[0], &line.glyphs[1]);
+        assert_eq!(x.baseline_shift, 0.0);
+        assert!(two.baseline_shift > 5.0, "{:?}", two);
+        assert_eq!(two.y, x.y - two.baseline_shift);
+        assert!(two.x_advance < plain_line.glyphs[1].x_advance * 0.8);
+
+        // The raised 2 reaches past the text's ascent, so the line makes room
+        assert!(line.ascent > plain_line.ascent + 1.0);
+        assert_eq!(plain_line.descent, line.descent);
+        assert!(line.y > plain_line.y);
+        let next = &squared.lines[1];
+        assert!(next.y - next.ascent >= line.y + line.descent);
+
+        // A selection box is the unshifted text box moved up by the shift,
+        // and covers the 2's ink
+        let text_box = |line: &LayoutLine, glyph: &ShapedGlyph| {
+            let baseline = line.y - glyph.baseline_shift;
+            (baseline - plain_line.ascent, baseline + plain_line.descent)
+        };
+        let (x_top, x_bottom) = text_box(line, x);
+        let (two_top, two_bottom) = text_box(line, two);
+        assert_eq!(x_top - two_top, two.baseline_shift);
+        assert_eq!(x_bottom - two_bottom, two.baseline_shift);
+        let ink = line.glyph_ink_bounds[1].unwrap();
+        assert!(ink.y_min >= two_top && ink.y_max <= two_bottom, "{:?}", ink);
+        assert!(ink.y_max < line.y - two.baseline_shift / 2.0, "{:?}", ink);
+
+        let lowered = layout(
+            &mut shaper,
+            &[
+                span("x", None, None),
+                span("2", Some(BaselineShift::Sub), None),
+            ],
+        );
+        assert!(lowered.lines[0].glyphs[1].baseline_shift < 0.0);
+        assert!(lowered.lines[0].descent > plain_line.descent);
+        assert!(lowered.lines[0].line_height > plain_line.line_height);
+    }
+
+    #[test]
+    fn test_lines_report_why_and_where_they_break() {
+        let (mut shaper, style) = inter_shaper();
+        let mut layout = |text: &str, max_width: f32, options: &LayoutOptions| {
+            shaper
+                .layout_text_native(text, 16.0, 20.0, max_width, &style, options)
+                .unwrap()
+        };
+        let reasons = |result: &LayoutResult| {
+            result
+                .lines
+                .iter()
+                .map(|line| (line.break_reason, line.break_offset))
+                .collect::<Vec<_>>()
+        };
+
+        let newline = layout("Hello\nworld", 1000.0, &LayoutOptions::default());
+        assert_eq!(
+            reasons(&newline),
+            [(Some(BreakReason::Newline), Some(5)), (None, None)]
+        );
+
+        // Each line starts where the previous one broke
+        let text = "the quick brown fox jumps over the lazy dog";
+        let wrapped = layout(text, 90.0, &LayoutOptions::default());
+        assert!(wrapped.lines.len() > 2);
+        for pair in wrapped.lines.windows(2) {
+            let start = pair[1].glyphs.iter().map(|glyph| glyph.start).min();
+            assert_eq!(pair[0].break_reason, Some(BreakReason::Width));
+            assert_eq!(pair[0].break_offset, start);
+            assert_eq!(&text[start.unwrap() - 1..start.unwrap()], " ");
+        }
+        assert_eq!(wrapped.lines.last().unwrap().break_reason, None);
+
+        let forced_options = LayoutOptions {
+            wrap: WrapMode::WordOrGlyph,
+            ..Default::default()
+        };
+        let forced = layout("a Pneumonoultramicroscopic", 60.0, &forced_options);
+        let forced_reasons = reasons(&forced);
+        assert_eq!(forced_reasons[0], (Some(BreakReason::Width), Some(2)));
+        assert_eq!(forced_reasons[1].0, Some(BreakReason::GraphemeForced));
+        assert!(forced_reasons[1].1.unwrap() > 3);
+        assert_eq!(forced_reasons.last().unwrap().0, None);
+        let overflowing = layout(
+            "a Pneumonoultramicroscopic",
+            60.0,
+            &LayoutOptions::default(),
+        );
+        assert_eq!(overflowing.lines.len(), 2);
+
+        let limited = layout(
+            text,
+            90.0,
+            &LayoutOptions {
+                max_lines: Some(2),
+                break_candidates: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(limited.lines.len(), 2);
+        assert_eq!(limited.lines[0].break_offset, wrapped.lines[0].break_offset);
+        assert_eq!(limited.lines[1].break_reason, Some(BreakReason::MaxLines));
+        assert_eq!(limited.lines[1].break_offset, wrapped.lines[1].break_offset);
+        assert!(limited.total_height < wrapped.total_height);
+        // The second line could also have ended after each of its inner spaces
+        let second =
+            &text[wrapped.lines[0].break_offset.unwrap()..limited.lines[1].break_offset.unwrap()];
+        let spaces = second.trim_end().matches(' ').count();
+        assert!(spaces > 0);
+        assert_eq!(limited.rejected_breaks.len(), spaces);
+        assert!(wrapped.rejected_breaks.is_empty());
+    }
+
+    #[test]
+    fn test_justified_lines_fill_the_width() {
+        let (mut shaper, style) = inter_shaper();
+        let width = 200.0;
+        let mut layout = |text: &str, options: &LayoutOptions| {
+            shaper
+                .layout_text_native(text, 16.0, 20.0, width, &style, options)
+                .unwrap()
+        };
+        let justify = |distribution| LayoutOptions {
+            justify: true,
+            justify_distribution: distribution,
+            ..Default::default()
+        };
+        // Carets after each glyph land where the next one starts
+        let assert_tiled = |line: &LayoutLine| {
+            for pair in line.glyphs.windows(2) {
+                assert!((pair[0].x + pair[0].x_advance - pair[1].x).abs() < 1e-3);
+            }
+        };
+
+        let english = "The quick brown fox jumps over the lazy dog and keeps on running";
+        let plain = layout(english, &LayoutOptions::default());
+        let justified = layout(english, &justify(JustifyDistribution::Auto));
+        assert!(justified.lines.len() > 2);
+        let (last, wrapped) = justified.lines.split_last().unwrap();
+        for (line, plain) in wrapped.iter().zip(&plain.lines) {
+            assert!((line.width - width).abs() < 1e-3, "{}", line.width);
+            assert_eq!(line.glyphs.len(), plain.glyphs.len());
+            assert_tiled(line);
+            // Only spaces grew
+            for (glyph, plain) in line.glyphs.iter().zip(&plain.glyphs) {
+                let grew = glyph.x_advance - plain.x_advance > 1e-3;
+                assert_eq!(grew, &english[glyph.start..glyph.end] == " ");
+            }
+        }
+        assert_eq!(last.width, plain.lines.last().unwrap().width);
+
+        let chinese = "我们在这里测试中文段落的两端对齐效果，看看每一行是否都均匀分布字符间距，\
+                       并且最后一行保持原样不被拉伸。";
+        let justified = layout(chinese, &justify(JustifyDistribution::Auto));
+        assert!(justified.lines.len() > 2);
+        for line in &justified.lines[..justified.lines.len() - 1] {
+            assert!((line.width - width).abs() < 1e-3, "{}", line.width);
+            assert_tiled(line);
+            let steps: Vec<f32> = line.glyphs.windows(2).map(|p| p[1].x - p[0].x).collect();
+            assert!(steps.iter().all(|step| (step - steps[0]).abs() < 1e-3));
+        }
+        let forced = layout(chinese, &justify(JustifyDistribution::InterCharacter));
+        let advances = |result: &LayoutResult| -> Vec<f32> {
+            result.lines[0]
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.x_advance)
+                .collect()
+        };
+        assert_eq!(advances(&forced), advances(&justified));
+
+        // Capped word gaps hand the rest to letter spacing
+        let capped = layout(
+            english,
+            &LayoutOptions {
+                max_inter_word_stretch: Some(0.0),
+                ..justify(JustifyDistribution::InterWord)
+            },
+        );
+        let line = &capped.lines[0];
+        assert!((line.width - width).abs() < 1e-3);
+        for (glyph, plain) in line.glyphs.iter().zip(&plain.lines[0].glyphs) {
+            if glyph.end < line.glyphs.last().unwrap().start {
+                assert!(glyph.x_advance > plain.x_advance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_visible_glyph_range_in_scrolled_fields() {
+        let (mut shaper, style) = inter_shaper();
+        // Whatever the line, the range lists the glyphs wholly in view left
+        // to right, and the clipped ones cross the edge they are reported at
+        let mut check = |text: &str, scroll_x: f32, width: f32| {
+            let line = shaper.shape_line_native(text, 16.0, 20.0, &style).unwrap();
+            let range = shaper
+                .visible_glyph_range_native(text, 16.0, 20.0, &style, scroll_x, width)
+                .unwrap();
+            let (left, right) = (scroll_x, scroll_x + width);
+            assert_eq!(range.glyphs.len(), range.end - range.start);
+            assert!(range
+                .glyphs
+                .windows(2)
+                .all(|pair| line.glyphs[pair[0]].x <= line.glyphs[pair[1]].x));
+            for (index, glyph) in line.glyphs.iter().enumerate() {
+                let whole = glyph.x >= left && glyph.x + glyph.x_advance <= right;
+                assert_eq!(whole, range.glyphs.contains(&index), "{}", text);
+            }
+            if let Some(leading) = range.leading {
+                let glyph = &line.glyphs[leading.index];
+                assert!(glyph.x < left && glyph.x + glyph.x_advance > left);
+                let cut = glyph.x + leading.visible_start * glyph.x_advance;
+                assert!((cut - left).abs() < 1e-3);
+                assert!(leading.visible_end <= 1.0);
+            }
+            if let Some(trailing) = range.trailing {
+                let glyph = &line.glyphs[trailing.index];
+                assert!(glyph.x + glyph.x_advance > right && glyph.x < right);
+                assert_eq!(trailing.visible_start, 0.0);
+                let cut = glyph.x + trailing.visible_end * glyph.x_advance;
+                assert!((cut - right).abs() < 1e-3);
+            }
+            (line, range)
+        };
+
+        // LTR: the whole line, then a window cutting into two glyphs
+        let (line, range) = check("Scrolling field", 0.0, 500.0);
+        assert_eq!((range.start, range.end), (0, line.glyphs.len()));
+        assert_eq!((range.leading, range.trailing), (None, None));
+        let cut = line.glyphs[2].x + line.glyphs[2].x_advance * 0.25;
+        let (_, range) = check("Scrolling field", cut, line.glyphs[6].x + 1.0 - cut);
+        let leading = range.leading.unwrap();
+        assert_eq!(leading.index, 2);
+        assert!((leading.visible_start - 0.25).abs() < 1e-3);
+        assert_eq!((range.start, range.end), (3, 6));
+        assert_eq!(range.glyphs, vec![3, 4, 5]);
+        assert_eq!(range.trailing.unwrap().index, 6);
+        for scroll_x in [-20.0, 13.7, 41.0, 88.5, 300.0] {
+            check("Scrolling field", scroll_x, 37.5);
+        }
+
+        // RTL: the text starts on the right, so the left of the field shows
+        // its second word, and the glyph cut at the right edge comes earlier
+        // in the text
+        let hebrew = "\u{05E9}\u{05DC}\u{05D5}\u{05DD} \u{05E2}\u{05D5}\u{05DC}\u{05DD}";
+        let (line, range) = check(hebrew, 0.0, 25.0);
+        assert!(line.glyphs[0].x > line.glyphs.last().unwrap().x);
+        assert_eq!(range.start, 0);
+        assert!(!range.glyphs.is_empty());
+        let space = hebrew.find(' ').unwrap();
+        assert!(range
+            .glyphs
+            .iter()
+            .all(|index| line.glyphs[*index].start > space));
+        let cut = range.trailing.unwrap();
+        assert!(line.glyphs[cut.index].start < line.glyphs[range.glyphs[0]].start);
+        for scroll_x in [-5.0, 10.25, 31.0, 60.0] {
+            check(hebrew, scroll_x, 22.0);
+        }
+
+        // Mixed: a window over the Hebrew word, reversed between the Latin
+        // ones, shows just its letters, its logical end leftmost
+        let mixed = "abc \u{05E9}\u{05DC}\u{05D5}\u{05DD} def";
+        let (line, _) = check(mixed, 0.0, 0.0);
+        let mut hebrew_glyphs: Vec<usize> = (0..line.glyphs.len())
+            .filter(|index| (4..12).contains(&line.glyphs[*index].start))
+            .collect();
+        hebrew_glyphs.sort_by(|a, b| line.glyphs[*a].x.total_cmp(&line.glyphs[*b].x));
+        let first = &line.glyphs[hebrew_glyphs[0]];
+        let last = &line.glyphs[*hebrew_glyphs.last().unwrap()];
+        assert!(first.start > last.start);
+        let (_, range) = check(mixed, first.x, last.x + last.x_advance - first.x);
+        assert_eq!(range.glyphs, hebrew_glyphs);
+        assert_eq!((range.leading, range.trailing), (None, None));
+        for scroll_x in [0.0, 12.5, 29.0, 47.75, 70.0] {
+            check(mixed, scroll_x, 30.0);
+        }
     }
 }
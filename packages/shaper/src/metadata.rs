@@ -0,0 +1,88 @@
+//! Font metadata reported by `register_fonts_batch`.
+
+use cosmic_text::fontdb::{FaceInfo, Stretch, Style};
+use serde::{Deserialize, Serialize};
+
+/// A variation axis of a variable font.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FontAxis {
+    /// Four-letter axis tag, such as `wght`
+    pub tag: String,
+    pub min: f32,
+    pub default: f32,
+    pub max: f32,
+}
+
+/// One font of a `register_fonts_batch` call, described by its first face.
+/// A font that failed to load has only `error` set.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RegisteredFont {
+    pub id: Option<u32>,
+    pub error: Option<String>,
+    /// Family names from the font's name table, English first
+    pub families: Vec<String>,
+    pub weight: u16,
+    /// `normal`, `italic` or `oblique`, as `FontStyleInput::style` takes
+    pub style: String,
+    /// A CSS `font-stretch` keyword, as `FontStyleInput::stretch` takes
+    pub stretch: String,
+    pub is_variable: bool,
+    pub axes: Vec<FontAxis>,
+}
+
+impl RegisteredFont {
+    pub(crate) fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+
+    /// Metadata of `face`, read from fontdb and, for axes, from `data`.
+    pub(crate) fn from_face(id: u32, face: &FaceInfo, data: &[u8]) -> Self {
+        let axes: Vec<FontAxis> = swash::FontRef::from_index(data, face.index as usize)
+            .map(|font| {
+                font.variations()
+                    .map(|axis| FontAxis {
+                        tag: String::from_utf8_lossy(&axis.tag().to_be_bytes()).into_owned(),
+                        min: axis.min_value(),
+                        default: axis.default_value(),
+                        max: axis.max_value(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            id: Some(id),
+            error: None,
+            families: face.families.iter().map(|(name, _)| name.clone()).collect(),
+            weight: face.weight.0,
+            style: style_name(face.style).to_string(),
+            stretch: stretch_name(face.stretch).to_string(),
+            is_variable: !axes.is_empty(),
+            axes,
+        }
+    }
+}
+
+fn style_name(style: Style) -> &'static str {
+    match style {
+        Style::Normal => "normal",
+        Style::Italic => "italic",
+        Style::Oblique => "oblique",
+    }
+}
+
+fn stretch_name(stretch: Stretch) -> &'static str {
+    match stretch {
+        Stretch::UltraCondensed => "ultra-condensed",
+        Stretch::ExtraCondensed => "extra-condensed",
+        Stretch::Condensed => "condensed",
+        Stretch::SemiCondensed => "semi-condensed",
+        Stretch::Normal => "normal",
+        Stretch::SemiExpanded => "semi-expanded",
+        Stretch::Expanded => "expanded",
+        Stretch::ExtraExpanded => "extra-expanded",
+        Stretch::UltraExpanded => "ultra-expanded",
+    }
+}
@@ -0,0 +1,127 @@
+//! Validation of the font size and line height passed to shaping calls.
+//!
+//! cosmic-text panics on a zero size and shapes negative or non-finite ones
+//! into NaN, so sizes are checked before a `Metrics` is built. A line height
+//! below the font size is valid, but lines then overlap their neighbors'
+//! ascenders and descenders, which results report as `clipped_lines`.
+
+use cosmic_text::Metrics;
+use wasm_bindgen::JsValue;
+
+/// Largest font size that is shaped. Anything above is a unit mixup rather
+/// than text anyone can read, and would allocate glyphs to match.
+pub const MAX_FONT_SIZE: f32 = 10_000.0;
+
+/// The size argument a `MetricsError` is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricsField {
+    FontSize,
+    LineHeight,
+}
+
+impl MetricsField {
+    fn name(self) -> &'static str {
+        match self {
+            MetricsField::FontSize => "font_size",
+            MetricsField::LineHeight => "line_height",
+        }
+    }
+}
+
+/// Why text could not be shaped at the requested sizes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricsError {
+    NotANumber(MetricsField),
+    /// Zero or negative
+    NotPositive {
+        field: MetricsField,
+        value: f32,
+    },
+    /// Infinite, or a font size above `MAX_FONT_SIZE`
+    TooLarge {
+        field: MetricsField,
+        value: f32,
+    },
+}
+
+impl MetricsError {
+    /// Stable identifier for hosts to match on, such as
+    /// `font_size_not_positive`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            MetricsError::NotANumber(MetricsField::FontSize) => "font_size_not_a_number",
+            MetricsError::NotANumber(MetricsField::LineHeight) => "line_height_not_a_number",
+            MetricsError::NotPositive {
+                field: MetricsField::FontSize,
+                ..
+            } => "font_size_not_positive",
+            MetricsError::NotPositive {
+                field: MetricsField::LineHeight,
+                ..
+            } => "line_height_not_positive",
+            MetricsError::TooLarge {
+                field: MetricsField::FontSize,
+                ..
+            } => "font_size_too_large",
+            MetricsError::TooLarge {
+                field: MetricsField::LineHeight,
+                ..
+            } => "line_height_too_large",
+        }
+    }
+}
+
+impl std::fmt::Display for MetricsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetricsError::NotANumber(field) => write!(f, "{} is NaN", field.name()),
+            MetricsError::NotPositive { field, value } => {
+                write!(f, "{} must be positive, got {}", field.name(), value)
+            }
+            MetricsError::TooLarge { field, value } if value.is_finite() => write!(
+                f,
+                "{} {} is above the maximum of {}",
+                field.name(),
+                value,
+                MAX_FONT_SIZE
+            ),
+            MetricsError::TooLarge { field, value } => {
+                write!(f, "{} must be finite, got {}", field.name(), value)
+            }
+        }
+    }
+}
+
+/// An `Error` whose `code` property holds `MetricsError::code`.
+impl From<MetricsError> for JsValue {
+    fn from(error: MetricsError) -> Self {
+        let js_error = js_sys::Error::new(&error.to_string());
+        let _ = js_sys::Reflect::set(&js_error, &"code".into(), &error.code().into());
+        js_error.into()
+    }
+}
+
+fn check(field: MetricsField, value: f32, max: f32) -> Result<(), MetricsError> {
+    if value.is_nan() {
+        Err(MetricsError::NotANumber(field))
+    } else if value <= 0.0 {
+        Err(MetricsError::NotPositive { field, value })
+    } else if value > max {
+        Err(MetricsError::TooLarge { field, value })
+    } else {
+        Ok(())
+    }
+}
+
+/// The metrics to shape with, if both sizes are usable.
+pub(crate) fn checked_metrics(font_size: f32, line_height: f32) -> Result<Metrics, MetricsError> {
+    check(MetricsField::FontSize, font_size, MAX_FONT_SIZE)?;
+    check(MetricsField::LineHeight, line_height, f32::MAX)?;
+    Ok(Metrics::new(font_size, line_height))
+}
+
+/// Whether lines at these metrics are closer together than the font is
+/// tall.
+pub(crate) fn clips_lines(metrics: Metrics) -> bool {
+    metrics.line_height < metrics.font_size
+}
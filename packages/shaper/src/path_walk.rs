@@ -0,0 +1,377 @@
+//! Arc-length walker over SVG path data, used to lay text along a path.
+//!
+//! This is a deliberately small duplicate of the svg package's path parsing:
+//! the shaper only needs positions and tangents, and linking the tessellator
+//! into the shaper's wasm module would cost far more than flattening here.
+
+use std::f32::consts::PI;
+use tolerance::Tolerance;
+
+/// Line segments per Bezier curve when flattening.
+const CURVE_SEGMENTS: usize = 16;
+
+/// Largest angle one flattened arc segment may sweep.
+const ARC_SEGMENT_ANGLE: f32 = PI / 32.0;
+
+/// Lengths and radii this small count as zero.
+const DEGENERATE_LENGTH: Tolerance = Tolerance::absolute(f32::EPSILON);
+
+struct Segment {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    /// Distance along the path where this segment starts
+    start: f32,
+    length: f32,
+}
+
+/// A point on the path with the direction of travel in radians.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct PathSample {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) angle: f32,
+}
+
+/// A path flattened into line segments with cumulative lengths. Moves between
+/// subpaths add no length, so distances skip the gap.
+pub(crate) struct PathWalker {
+    segments: Vec<Segment>,
+    length: f32,
+}
+
+impl PathWalker {
+    pub(crate) fn parse(d: &str) -> Self {
+        let mut walker = Self {
+            segments: Vec::new(),
+            length: 0.0,
+        };
+        PathParser::new(d, &mut walker).run();
+        walker
+    }
+
+    pub(crate) fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// The point `distance` along the path. Distances outside the path are
+    /// extrapolated along the first or last segment, so callers can place
+    /// overflowing glyphs where they would have gone.
+    pub(crate) fn sample(&self, distance: f32) -> Option<PathSample> {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.start + segment.length < distance)
+            .min(self.segments.len().checked_sub(1)?);
+        let segment = &self.segments[index];
+
+        let t = (distance - segment.start) / segment.length;
+        Some(PathSample {
+            x: segment.x0 + (segment.x1 - segment.x0) * t,
+            y: segment.y0 + (segment.y1 - segment.y0) * t,
+            angle: (segment.y1 - segment.y0).atan2(segment.x1 - segment.x0),
+        })
+    }
+
+    fn line_to(&mut self, (x0, y0): (f32, f32), (x1, y1): (f32, f32)) {
+        let length = (x1 - x0).hypot(y1 - y0);
+        // Zero-length segments have no direction to sample
+        if DEGENERATE_LENGTH.is_zero(length) || !length.is_finite() {
+            return;
+        }
+        self.segments.push(Segment {
+            x0,
+            y0,
+            x1,
+            y1,
+            start: self.length,
+            length,
+        });
+        self.length += length;
+    }
+}
+
+struct PathParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    walker: &'a mut PathWalker,
+    current: (f32, f32),
+    subpath_start: (f32, f32),
+    /// Second control point of the previous command, if it was a cubic,
+    /// for reflecting into S
+    cubic_control: Option<(f32, f32)>,
+    /// Control point of the previous command, if it was a quadratic, for
+    /// reflecting into T
+    quadratic_control: Option<(f32, f32)>,
+}
+
+impl<'a> PathParser<'a> {
+    fn new(d: &'a str, walker: &'a mut PathWalker) -> Self {
+        Self {
+            bytes: d.as_bytes(),
+            pos: 0,
+            walker,
+            current: (0.0, 0.0),
+            subpath_start: (0.0, 0.0),
+            cubic_control: None,
+            quadratic_control: None,
+        }
+    }
+
+    fn run(&mut self) {
+        let mut command = b'M';
+        loop {
+            self.skip_separators();
+            let Some(&next) = self.bytes.get(self.pos) else {
+                return;
+            };
+            if next.is_ascii_alphabetic() {
+                command = next;
+                self.pos += 1;
+            } else if command.eq_ignore_ascii_case(&b'Z') {
+                // Closepath takes no arguments, so a number here is malformed
+                return;
+            }
+            if !self.command(command) {
+                return;
+            }
+            // Coordinates after a moveto are implicit linetos
+            command = match command {
+                b'M' => b'L',
+                b'm' => b'l',
+                other => other,
+            };
+        }
+    }
+
+    /// Apply one command's worth of arguments. Returns false on malformed
+    /// input so parsing stops rather than looping.
+    fn command(&mut self, command: u8) -> bool {
+        let origin = if command.is_ascii_lowercase() {
+            self.current
+        } else {
+            (0.0, 0.0)
+        };
+        let point = |(x, y): (f32, f32)| (origin.0 + x, origin.1 + y);
+        let reflect = |control: Option<(f32, f32)>, (x, y): (f32, f32)| {
+            control.map_or((x, y), |(cx, cy)| (2.0 * x - cx, 2.0 * y - cy))
+        };
+        let cubic_control = self.cubic_control.take();
+        let quadratic_control = self.quadratic_control.take();
+
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                let Some(to) = self.pair() else { return false };
+                self.current = point(to);
+                self.subpath_start = self.current;
+            }
+            b'L' => {
+                let Some(to) = self.pair() else { return false };
+                self.line(point(to));
+            }
+            b'H' => {
+                let Some(x) = self.number() else { return false };
+                self.line((origin.0 + x, self.current.1));
+            }
+            b'V' => {
+                let Some(y) = self.number() else { return false };
+                self.line((self.current.0, origin.1 + y));
+            }
+            b'C' => {
+                let (Some(c1), Some(c2), Some(to)) = (self.pair(), self.pair(), self.pair()) else {
+                    return false;
+                };
+                self.cubic(point(c1), point(c2), point(to));
+            }
+            b'S' => {
+                let (Some(c2), Some(to)) = (self.pair(), self.pair()) else {
+                    return false;
+                };
+                let c1 = reflect(cubic_control, self.current);
+                self.cubic(c1, point(c2), point(to));
+            }
+            b'Q' => {
+                let (Some(c), Some(to)) = (self.pair(), self.pair()) else {
+                    return false;
+                };
+                self.quadratic(point(c), point(to));
+            }
+            b'T' => {
+                let Some(to) = self.pair() else { return false };
+                let c = reflect(quadratic_control, self.current);
+                self.quadratic(c, point(to));
+            }
+            b'A' => {
+                let (Some(rx), Some(ry), Some(rotation)) =
+                    (self.number(), self.number(), self.number())
+                else {
+                    return false;
+                };
+                let (Some(large_arc), Some(sweep), Some(to)) =
+                    (self.flag(), self.flag(), self.pair())
+                else {
+                    return false;
+                };
+                self.arc((rx, ry), rotation, large_arc, sweep, point(to));
+            }
+            b'Z' => {
+                self.line(self.subpath_start);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn line(&mut self, to: (f32, f32)) {
+        self.walker.line_to(self.current, to);
+        self.current = to;
+    }
+
+    fn cubic(&mut self, c1: (f32, f32), c2: (f32, f32), to: (f32, f32)) {
+        let from = self.current;
+        for step in 1..=CURVE_SEGMENTS {
+            let t = step as f32 / CURVE_SEGMENTS as f32;
+            let u = 1.0 - t;
+            let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+            self.line((
+                a * from.0 + b * c1.0 + c * c2.0 + d * to.0,
+                a * from.1 + b * c1.1 + c * c2.1 + d * to.1,
+            ));
+        }
+        self.current = to;
+        self.cubic_control = Some(c2);
+    }
+
+    fn quadratic(&mut self, control: (f32, f32), to: (f32, f32)) {
+        let from = self.current;
+        for step in 1..=CURVE_SEGMENTS {
+            let t = step as f32 / CURVE_SEGMENTS as f32;
+            let u = 1.0 - t;
+            let (a, b, c) = (u * u, 2.0 * u * t, t * t);
+            self.line((
+                a * from.0 + b * control.0 + c * to.0,
+                a * from.1 + b * control.1 + c * to.1,
+            ));
+        }
+        self.current = to;
+        self.quadratic_control = Some(control);
+    }
+
+    /// Flatten an elliptical arc using the endpoint-to-center conversion from
+    /// the SVG implementation notes (F.6.5).
+    fn arc(
+        &mut self,
+        (rx, ry): (f32, f32),
+        rotation_deg: f32,
+        large_arc: bool,
+        sweep: bool,
+        to: (f32, f32),
+    ) {
+        let from = self.current;
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if DEGENERATE_LENGTH.is_zero(rx) || DEGENERATE_LENGTH.is_zero(ry) {
+            self.line(to);
+            return;
+        }
+
+        let (sin_phi, cos_phi) = rotation_deg.to_radians().sin_cos();
+        let dx = (from.0 - to.0) / 2.0;
+        let dy = (from.1 - to.1) / 2.0;
+        let x1 = cos_phi * dx + sin_phi * dy;
+        let y1 = -sin_phi * dx + cos_phi * dy;
+
+        // Scale up radii that cannot span the endpoints
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let cx1 = coefficient * rx * y1 / ry;
+        let cy1 = -coefficient * ry * x1 / rx;
+        let cx = cos_phi * cx1 - sin_phi * cy1 + (from.0 + to.0) / 2.0;
+        let cy = sin_phi * cx1 + cos_phi * cy1 + (from.1 + to.1) / 2.0;
+
+        let start_angle = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+        let end_angle = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx);
+        let mut sweep_angle = end_angle - start_angle;
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        }
+
+        let steps = ((sweep_angle.abs() / ARC_SEGMENT_ANGLE).ceil() as usize).max(1);
+        for step in 1..steps {
+            let angle = start_angle + sweep_angle * step as f32 / steps as f32;
+            let (sin, cos) = angle.sin_cos();
+            self.line((
+                cx + rx * cos * cos_phi - ry * sin * sin_phi,
+                cy + rx * cos * sin_phi + ry * sin * cos_phi,
+            ));
+        }
+        // Land exactly on the endpoint rather than accumulating float error
+        self.line(to);
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            if !(byte.is_ascii_whitespace() || byte == b',') {
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn pair(&mut self) -> Option<(f32, f32)> {
+        Some((self.number()?, self.number()?))
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.pos;
+        let mut seen_dot = false;
+        let mut seen_exponent = false;
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            let accept = match byte {
+                b'0'..=b'9' => true,
+                b'+' | b'-' => self.pos == start || matches!(self.bytes[self.pos - 1], b'e' | b'E'),
+                b'.' if !seen_dot && !seen_exponent => {
+                    seen_dot = true;
+                    true
+                }
+                b'e' | b'E' if !seen_exponent && self.pos > start => {
+                    seen_exponent = true;
+                    true
+                }
+                _ => false,
+            };
+            if !accept {
+                break;
+            }
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// Arc flags may be written without separators, as in `a1 1 0 011 1`.
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.bytes.get(self.pos)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(flag)
+    }
+}
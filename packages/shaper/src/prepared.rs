@@ -0,0 +1,148 @@
+//! Text edited for shaping, with offsets mapped back to the caller's text.
+//!
+//! Hyphenation inserts and removes soft hyphens and whitespace handling
+//! collapses runs of whitespace, but glyph offsets must still index the
+//! text the caller passed in, for selection and caret placement.
+
+use cosmic_text::LineIter;
+
+/// Replace `removed` bytes of the original text at `at` with `inserted`.
+pub(crate) struct Edit {
+    pub(crate) at: usize,
+    pub(crate) removed: usize,
+    pub(crate) inserted: String,
+}
+
+impl Edit {
+    pub(crate) fn insert(at: usize, inserted: char) -> Self {
+        Self {
+            at,
+            removed: 0,
+            inserted: inserted.to_string(),
+        }
+    }
+
+    pub(crate) fn remove(at: usize, removed: usize) -> Self {
+        Self {
+            at,
+            removed,
+            inserted: String::new(),
+        }
+    }
+}
+
+/// A stretch of prepared text starting at `prepared`. Copied text starts at
+/// `original`; inserted text replaced `original..replaced_end`.
+struct Segment {
+    prepared: usize,
+    original: usize,
+    replaced_end: Option<usize>,
+}
+
+/// Text with edits applied for shaping, and the map from its offsets back
+/// to the original text's.
+pub(crate) struct PreparedText {
+    pub(crate) text: String,
+    /// Stretches of the prepared text in order, each copied or inserted
+    segments: Vec<Segment>,
+    /// Prepared paragraph starts, with the start of the original paragraph
+    /// each begins in
+    paragraphs: Vec<(usize, usize)>,
+}
+
+impl PreparedText {
+    /// Apply `edits`, which must not overlap. Inserted text maps to the whole
+    /// range it replaced, so a space collapsed from a run of whitespace
+    /// covers the run, and removed text belongs to no glyph.
+    pub(crate) fn new(original: &str, mut edits: Vec<Edit>) -> Self {
+        edits.sort_unstable_by_key(|edit| (edit.at, edit.removed));
+
+        let mut text = String::with_capacity(original.len());
+        let mut segments = vec![Segment {
+            prepared: 0,
+            original: 0,
+            replaced_end: None,
+        }];
+        let mut copied = 0;
+        for edit in edits {
+            text.push_str(&original[copied..edit.at]);
+            copied = edit.at + edit.removed;
+            segments.push(Segment {
+                prepared: text.len(),
+                original: edit.at,
+                replaced_end: Some(copied),
+            });
+            text.push_str(&edit.inserted);
+            segments.push(Segment {
+                prepared: text.len(),
+                original: copied,
+                replaced_end: None,
+            });
+        }
+        text.push_str(&original[copied..]);
+
+        let mut prepared = Self {
+            text,
+            segments,
+            paragraphs: Vec::new(),
+        };
+        // Collapsed newlines join original paragraphs into one, so each
+        // prepared paragraph counts from the original one it starts in
+        let original_starts: Vec<usize> = LineIter::new(original)
+            .map(|(range, _)| range.start)
+            .collect();
+        prepared.paragraphs = LineIter::new(&prepared.text)
+            .map(|(range, _)| {
+                let start = prepared.original_offset(range.start);
+                let index = original_starts.partition_point(|&offset| offset <= start);
+                (range.start, original_starts[index.saturating_sub(1)])
+            })
+            .collect();
+        prepared
+    }
+
+    /// The original whole-text offset of a prepared whole-text offset that
+    /// starts something, which skips any text removed there.
+    fn original_offset(&self, prepared: usize) -> usize {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.prepared <= prepared);
+        self.segment_offset(&self.segments[index - 1], prepared)
+    }
+
+    /// Like `original_offset` for an offset that ends something, which
+    /// stops before any text removed there.
+    fn original_end(&self, prepared: usize) -> usize {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.prepared < prepared);
+        self.segment_offset(&self.segments[index.saturating_sub(1)], prepared)
+    }
+
+    fn segment_offset(&self, segment: &Segment, prepared: usize) -> usize {
+        match segment.replaced_end {
+            Some(end) if prepared > segment.prepared => end,
+            Some(_) => segment.original,
+            None => segment.original + (prepared - segment.prepared),
+        }
+    }
+
+    /// Map an offset within prepared paragraph `paragraph` to an offset
+    /// from the start of the original paragraph it began in.
+    pub(crate) fn paragraph_offset(&self, paragraph: usize, offset: usize) -> usize {
+        let (prepared_start, original_start) = self.paragraphs[paragraph];
+        self.original_offset(prepared_start + offset) - original_start
+    }
+
+    /// `paragraph_offset` for an offset that ends something.
+    pub(crate) fn paragraph_end(&self, paragraph: usize, offset: usize) -> usize {
+        let (prepared_start, original_start) = self.paragraphs[paragraph];
+        self.original_end(prepared_start + offset) - original_start
+    }
+
+    /// Map an offset within prepared paragraph `paragraph` to the original
+    /// whole text.
+    pub(crate) fn text_offset(&self, paragraph: usize, offset: usize) -> usize {
+        self.original_offset(self.paragraphs[paragraph].0 + offset)
+    }
+}
@@ -0,0 +1,73 @@
+//! Output encodings for rasterized glyphs.
+//!
+//! By default a glyph's pixels are what swash produces: for outline glyphs
+//! one byte per pixel of geometric coverage, the fraction of the pixel the
+//! outline covers scaled to 0-255; for color glyphs straight (not
+//! premultiplied) RGBA with sRGB-encoded color channels.
+
+use crate::RasterizedGlyph;
+use serde::{Deserialize, Serialize};
+
+/// How `rasterize_glyph` encodes pixels. Applied after the glyph cache, so
+/// glyphs rasterized with different options share one cache entry.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RasterOptions {
+    /// Multiply a color glyph's RGB channels by its alpha, rounding to the
+    /// nearest value, so no channel exceeds alpha. Coverage masks are alpha
+    /// only and already usable as premultiplied alpha; the compositor
+    /// multiplies its text color by them.
+    pub premultiplied: bool,
+    /// Adjust coverage masks for compositing in linear space. Raw coverage
+    /// blended in linear space paints dark text on light backgrounds thinner
+    /// than the same coverage blended in sRGB, so each value becomes
+    /// `1 - (1 - coverage)^gamma`, the linear-space alpha giving that sRGB
+    /// result. Partial coverage grows while 0 and 255 stay put. Color
+    /// glyphs are images, not coverage, and are left alone.
+    pub gamma_correct: bool,
+    /// Exponent for `gamma_correct`. 1 leaves coverage unchanged.
+    pub gamma: f32,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            premultiplied: false,
+            gamma_correct: false,
+            gamma: 2.2,
+        }
+    }
+}
+
+impl RasterOptions {
+    /// Re-encode `glyph`'s pixels from the default encoding.
+    pub(crate) fn apply(&self, glyph: &mut RasterizedGlyph) {
+        if glyph.is_color {
+            if self.premultiplied {
+                glyph.pixels.chunks_exact_mut(4).for_each(premultiply);
+            }
+        } else if self.gamma_correct && self.gamma > 0.0 {
+            let table = coverage_table(self.gamma);
+            for coverage in &mut glyph.pixels {
+                *coverage = table[*coverage as usize];
+            }
+        }
+    }
+}
+
+fn premultiply(pixel: &mut [u8]) {
+    let alpha = pixel[3] as u32;
+    for channel in &mut pixel[..3] {
+        *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+    }
+}
+
+/// The adjusted value of every coverage byte for `gamma`.
+fn coverage_table(gamma: f32) -> [u8; 256] {
+    let mut table = [0; 256];
+    for (coverage, adjusted) in table.iter_mut().enumerate() {
+        let uncovered = 1.0 - coverage as f32 / 255.0;
+        *adjusted = ((1.0 - uncovered.powf(gamma)) * 255.0).round() as u8;
+    }
+    table
+}
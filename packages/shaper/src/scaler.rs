@@ -0,0 +1,151 @@
+//! Glyph rasterization with swash, drawing what cosmic-text's `SwashCache`
+//! draws for an unpositioned glyph at normal weight.
+//!
+//! `SwashCache` looks up the font and sets its `wght` axis on every call.
+//! swash keeps hinting state for its last few sizes, keyed by the font's
+//! variation coordinates, and an axis set to its default value does not
+//! match the state built for that default, so each glyph rebuilt the hinting
+//! state from the font's programs. Faces are resolved here once, with
+//! default coordinates left empty, and the hinting state for each size is
+//! built once and reused by every glyph drawn at that size.
+//!
+//! Outlines are not shared between sizes. Hinting moves points to the pixel
+//! grid of each size, so a hinted outline scaled to another size would not
+//! produce the same pixels as drawing it at that size.
+//!
+//! swash has no COLR v1 support, so glyphs of faces with a COLR v1 table
+//! are tried with `colr` first. Without the `color-emoji` feature, glyphs
+//! are drawn from their outlines alone.
+
+#[cfg(feature = "color-emoji")]
+use crate::colr;
+use cosmic_text::fontdb::ID;
+use cosmic_text::{Font, FontSystem, SwashImage, Weight};
+use std::collections::HashMap;
+use std::sync::Arc;
+#[cfg(feature = "color-emoji")]
+use swash::scale::StrikeWith;
+use swash::scale::{Render, ScaleContext, Source};
+use swash::zeno::{Format, Vector};
+use swash::{Setting, Tag};
+
+/// Where glyph images come from, in order of preference.
+#[cfg(feature = "color-emoji")]
+const SOURCES: &[Source] = &[
+    Source::ColorOutline(0),
+    Source::ColorBitmap(StrikeWith::BestFit),
+    Source::Outline,
+];
+#[cfg(not(feature = "color-emoji"))]
+const SOURCES: &[Source] = &[Source::Outline];
+
+/// Faces kept resolved at once. Fonts are few, so past this the cache
+/// starts over rather than tracking recency.
+pub(crate) const MAX_RASTER_FACES: usize = 64;
+
+/// What drawing a face needs, resolved once.
+struct RasterFace {
+    font: Arc<Font>,
+    /// Index of the face in its font file, which only COLR v1 drawing reads
+    #[cfg_attr(not(feature = "color-emoji"), allow(dead_code))]
+    index: u32,
+    /// Whether the face has COLR v1 paint graphs
+    #[cfg(feature = "color-emoji")]
+    colr_v1: bool,
+    /// Normalized variation coordinates for normal weight, or empty when
+    /// every axis is at its default
+    coords: Vec<i16>,
+}
+
+impl RasterFace {
+    fn new(font: Arc<Font>, index: u32) -> Self {
+        let wght = Tag::from_be_bytes(*b"wght");
+        let swash_font = font.as_swash();
+        let variations = swash_font.variations();
+        // As `SwashCache` does, clamped to the axis range
+        let coords: Vec<i16> = match variations.find_by_tag(wght) {
+            Some(axis) => {
+                let setting = Setting {
+                    tag: wght,
+                    value: f32::from(Weight::NORMAL.0).clamp(axis.min_value(), axis.max_value()),
+                };
+                variations.normalized_coords([setting]).collect()
+            }
+            None => Vec::new(),
+        };
+        let coords = if coords.iter().all(|&coord| coord == 0) {
+            Vec::new()
+        } else {
+            coords
+        };
+        Self {
+            #[cfg(feature = "color-emoji")]
+            colr_v1: colr::has_colr_v1(font.data(), index),
+            font,
+            index,
+            coords,
+        }
+    }
+}
+
+/// A swash context with the faces it has drawn.
+pub(crate) struct GlyphScaler {
+    context: ScaleContext,
+    faces: HashMap<ID, RasterFace>,
+}
+
+impl GlyphScaler {
+    pub(crate) fn new() -> Self {
+        Self {
+            context: ScaleContext::new(),
+            faces: HashMap::new(),
+        }
+    }
+
+    /// Draw a glyph at `font_size`, or None when the face or glyph has
+    /// nothing to draw.
+    pub(crate) fn render(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_id: ID,
+        glyph_id: u16,
+        font_size: f32,
+    ) -> Option<SwashImage> {
+        if !self.faces.contains_key(&font_id) {
+            let font = font_system.get_font(font_id, Weight::NORMAL)?;
+            let index = font_system.db().face(font_id).map_or(0, |face| face.index);
+            if self.faces.len() >= MAX_RASTER_FACES {
+                self.faces.clear();
+            }
+            self.faces.insert(font_id, RasterFace::new(font, index));
+        }
+        let face = &self.faces[&font_id];
+        #[cfg(feature = "color-emoji")]
+        if face.colr_v1 {
+            let data = face.font.data();
+            let image = colr::render(data, face.index, &face.coords, glyph_id, font_size);
+            if image.is_some() {
+                return image;
+            }
+        }
+        let mut scaler = self
+            .context
+            .builder(face.font.as_swash())
+            .size(font_size)
+            .hint(true)
+            .normalized_coords(&face.coords)
+            .build();
+        Render::new(SOURCES)
+            .format(Format::Alpha)
+            .offset(Vector::new(0.0, 0.0))
+            .render(&mut scaler, glyph_id)
+    }
+
+    pub(crate) fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.faces.clear();
+    }
+}
@@ -0,0 +1,84 @@
+//! The glyphs of a horizontally scrolled line that show in its viewport.
+//!
+//! Right-to-left runs keep their glyphs in logical order, so a shaped line's
+//! glyphs are put in visual order, left to right, by position first. In that
+//! order the glyphs wholly in view are one range even in mixed-direction
+//! text. Only the glyphs cut by the viewport's edges need a clip, given as
+//! fractions of their advance from their left edge, which holds for
+//! right-to-left glyphs too.
+
+use crate::ShapedGlyph;
+use serde::{Deserialize, Serialize};
+
+/// A glyph cut by an edge of the viewport. The visible part of its advance
+/// runs from `visible_start` to `visible_end`, as fractions from its left
+/// edge.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClippedGlyph {
+    /// Index in the line's glyphs
+    pub index: usize,
+    pub visible_start: f32,
+    pub visible_end: f32,
+}
+
+/// What `visible_glyph_range` found in view.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct VisibleGlyphRange {
+    /// Glyphs from `start` up to `end` in visual order lie wholly in the
+    /// viewport. The range is empty, just right of `leading`, when none
+    /// does.
+    pub start: usize,
+    pub end: usize,
+    /// Indices in the line's glyphs of the glyphs wholly in view, left to
+    /// right
+    pub glyphs: Vec<usize>,
+    /// The glyph cut by the viewport's left edge, or by both edges
+    pub leading: Option<ClippedGlyph>,
+    /// The glyph cut by the viewport's right edge
+    pub trailing: Option<ClippedGlyph>,
+}
+
+/// Find the glyphs of a shaped line that show between `scroll_x` and
+/// `scroll_x + viewport_width`. Zero-advance glyphs, such as marks, count as
+/// wholly in view when their origin is.
+pub(crate) fn visible_glyph_range(
+    glyphs: &[ShapedGlyph],
+    scroll_x: f32,
+    viewport_width: f32,
+) -> VisibleGlyphRange {
+    let left = scroll_x;
+    let right = scroll_x + viewport_width.max(0.0);
+    // Stable, so marks sharing their base's position keep following it
+    let mut visual: Vec<usize> = (0..glyphs.len()).collect();
+    visual.sort_by(|a, b| glyphs[*a].x.total_cmp(&glyphs[*b].x));
+
+    let mut whole: Option<(usize, usize)> = None;
+    let mut range = VisibleGlyphRange::default();
+    let mut after_leading = 0;
+    for (position, &index) in visual.iter().enumerate() {
+        let glyph = &glyphs[index];
+        let (start, end) = (glyph.x, glyph.x + glyph.x_advance);
+        if start >= left && end <= right {
+            whole = Some((whole.map_or(position, |(first, _)| first), position + 1));
+            continue;
+        }
+        // Outside the viewport, or cut by an edge, which takes some advance
+        if end <= left || start >= right {
+            continue;
+        }
+        let clipped = ClippedGlyph {
+            index,
+            visible_start: ((left - start) / glyph.x_advance).max(0.0),
+            visible_end: ((right - start) / glyph.x_advance).min(1.0),
+        };
+        if start >= left {
+            range.trailing = range.trailing.or(Some(clipped));
+        } else if range.leading.is_none() {
+            range.leading = Some(clipped);
+            after_leading = position + 1;
+        }
+    }
+    (range.start, range.end) = whole.unwrap_or((after_leading, after_leading));
+    range.glyphs = visual[range.start..range.end].to_vec();
+    range
+}
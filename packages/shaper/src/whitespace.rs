@@ -0,0 +1,77 @@
+//! CSS `white-space` handling, as `PreparedText` edits.
+
+use crate::prepared::Edit;
+use serde::{Deserialize, Serialize};
+
+/// How `layout_text` treats spaces, tabs and newlines, after CSS
+/// `white-space`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WhiteSpace {
+    /// Runs of whitespace, newlines included, collapse to one space, and
+    /// whitespace at the start and end of the text is dropped
+    Normal,
+    /// Whitespace is kept as written and lines only break at newlines
+    Pre,
+    /// Whitespace is kept as written and long lines wrap
+    #[default]
+    PreWrap,
+    /// Runs of spaces and tabs collapse to one space, newlines are kept,
+    /// and spaces around newlines and at either end are dropped
+    PreLine,
+}
+
+impl WhiteSpace {
+    /// Whether lines wrap at the layout width.
+    pub(crate) fn wraps(self) -> bool {
+        self != WhiteSpace::Pre
+    }
+
+    /// The edits that collapse `text`'s whitespace for this mode.
+    pub(crate) fn edits(self, text: &str) -> Vec<Edit> {
+        if matches!(self, WhiteSpace::Pre | WhiteSpace::PreWrap) {
+            return Vec::new();
+        }
+        let mut edits = Vec::new();
+        let mut chars = text.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if !is_collapsible(c) {
+                continue;
+            }
+            let mut end = start + c.len_utf8();
+            while let Some(&(offset, c)) = chars.peek().filter(|(_, c)| is_collapsible(*c)) {
+                end = offset + c.len_utf8();
+                chars.next();
+            }
+            let at_edge = start == 0 || end == text.len();
+            let replacement = self.collapse(&text[start..end], at_edge);
+            if replacement != text[start..end] {
+                edits.push(Edit {
+                    at: start,
+                    removed: end - start,
+                    inserted: replacement,
+                });
+            }
+        }
+        edits
+    }
+
+    /// What a whole run of whitespace collapses to.
+    fn collapse(self, run: &str, at_edge: bool) -> String {
+        // A CR ending a line is part of its CRLF, so count LFs and lone CRs
+        let newlines = run
+            .char_indices()
+            .filter(|&(offset, c)| c == '\n' || (c == '\r' && !run[offset + 1..].starts_with('\n')))
+            .count();
+        match self {
+            WhiteSpace::PreLine if newlines > 0 => "\n".repeat(newlines),
+            _ if at_edge => String::new(),
+            _ => " ".to_string(),
+        }
+    }
+}
+
+/// CSS document white space: spaces, tabs and segment breaks.
+fn is_collapsible(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
@@ -0,0 +1,1059 @@
+//! WOFF and WOFF2 containers, unpacked to the SFNT fonts they hold.
+//!
+//! fontdb reads only TrueType and OpenType files, so a web font is decoded
+//! before it is registered and the engine keeps the SFNT, never the
+//! container. WOFF compresses each table with zlib. WOFF2 compresses all
+//! tables as one Brotli stream and stores `glyf`, `loca` and usually `hmtx`
+//! in transformed forms, which are rebuilt here into standard tables.
+//!
+//! WOFF2 needs the Brotli decoder and its static dictionary, behind the
+//! `woff2` feature. Neither rebuilt font is byte for byte the original: table
+//! checksums are recomputed and glyph outlines re-encoded, but every table's
+//! contents are equivalent.
+
+use std::borrow::Cow;
+use wasm_bindgen::JsValue;
+
+/// The kind of container a `FontDataError` is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FontContainer {
+    Woff,
+    Woff2,
+}
+
+impl FontContainer {
+    fn name(self) -> &'static str {
+        match self {
+            FontContainer::Woff => "WOFF",
+            FontContainer::Woff2 => "WOFF2",
+        }
+    }
+}
+
+/// Why a WOFF or WOFF2 font could not be unpacked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FontDataError {
+    /// The file ends before a header, directory or table it declares
+    Truncated(FontContainer),
+    /// A header or table is inconsistent with the rest of the file
+    Malformed {
+        container: FontContainer,
+        reason: &'static str,
+    },
+    /// Compressed table data did not decompress to its declared size
+    Decompress {
+        container: FontContainer,
+        reason: String,
+    },
+    /// A WOFF2 font collection, which fontdb could load as TrueType but is
+    /// not unpacked
+    UnsupportedCollection,
+    /// A WOFF2 font in a build without the `woff2` feature
+    Woff2Disabled,
+}
+
+impl FontDataError {
+    /// Stable identifier for hosts to match on, such as `woff2_truncated`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FontDataError::Truncated(FontContainer::Woff) => "woff_truncated",
+            FontDataError::Truncated(FontContainer::Woff2) => "woff2_truncated",
+            FontDataError::Malformed {
+                container: FontContainer::Woff,
+                ..
+            } => "woff_malformed",
+            FontDataError::Malformed {
+                container: FontContainer::Woff2,
+                ..
+            } => "woff2_malformed",
+            FontDataError::Decompress {
+                container: FontContainer::Woff,
+                ..
+            } => "woff_decompress_failed",
+            FontDataError::Decompress {
+                container: FontContainer::Woff2,
+                ..
+            } => "woff2_decompress_failed",
+            FontDataError::UnsupportedCollection => "woff2_collection_unsupported",
+            FontDataError::Woff2Disabled => "woff2_disabled",
+        }
+    }
+}
+
+impl std::fmt::Display for FontDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontDataError::Truncated(container) => {
+                write!(f, "{} font data is truncated", container.name())
+            }
+            FontDataError::Malformed { container, reason } => {
+                write!(f, "{} font is malformed: {}", container.name(), reason)
+            }
+            FontDataError::Decompress { container, reason } => {
+                write!(
+                    f,
+                    "{} font did not decompress: {}",
+                    container.name(),
+                    reason
+                )
+            }
+            FontDataError::UnsupportedCollection => {
+                f.write_str("WOFF2 font collections are not supported")
+            }
+            FontDataError::Woff2Disabled => {
+                f.write_str("WOFF2 fonts need the shaper's woff2 feature")
+            }
+        }
+    }
+}
+
+/// An `Error` whose `code` property holds `FontDataError::code`.
+impl From<FontDataError> for JsValue {
+    fn from(error: FontDataError) -> Self {
+        let js_error = js_sys::Error::new(&error.to_string());
+        let _ = js_sys::Reflect::set(&js_error, &"code".into(), &error.code().into());
+        js_error.into()
+    }
+}
+
+/// The SFNT font in `data`: unpacked if it is WOFF or WOFF2, and as given
+/// otherwise, for fontdb to accept or reject.
+pub(crate) fn decode(data: &[u8]) -> Result<Cow<'_, [u8]>, FontDataError> {
+    match data.get(..4) {
+        Some(b"wOFF") => decode_woff(data).map(Cow::Owned),
+        #[cfg(feature = "woff2")]
+        Some(b"wOF2") => decode_woff2(data).map(Cow::Owned),
+        #[cfg(not(feature = "woff2"))]
+        Some(b"wOF2") => Err(FontDataError::Woff2Disabled),
+        _ => Ok(Cow::Borrowed(data)),
+    }
+}
+
+/// Largest SFNT either container may unpack to. A font's declared size is
+/// otherwise trusted for allocation, and a corrupt one could ask for
+/// gigabytes.
+const MAX_SFNT_SIZE: usize = 256 << 20;
+
+/// Reads big-endian values, reporting a short read as truncation of
+/// `container`.
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+    container: FontContainer,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], container: FontContainer) -> Self {
+        Self {
+            data,
+            at: 0,
+            container,
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], FontDataError> {
+        let bytes = self
+            .at
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.at..end))
+            .ok_or(FontDataError::Truncated(self.container))?;
+        self.at += len;
+        Ok(bytes)
+    }
+
+    #[cfg(feature = "woff2")]
+    fn u8(&mut self) -> Result<u8, FontDataError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, FontDataError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    #[cfg(feature = "woff2")]
+    fn i16(&mut self) -> Result<i16, FontDataError> {
+        self.u16().map(|value| value as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, FontDataError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn tag(&mut self) -> Result<[u8; 4], FontDataError> {
+        let bytes = self.bytes(4)?;
+        Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn malformed(&self, reason: &'static str) -> FontDataError {
+        FontDataError::Malformed {
+            container: self.container,
+            reason,
+        }
+    }
+}
+
+fn decode_woff(data: &[u8]) -> Result<Vec<u8>, FontDataError> {
+    let mut header = Reader::new(data, FontContainer::Woff);
+    header.bytes(4)?;
+    let flavor = header.u32()?;
+    let length = header.u32()?;
+    let table_count = header.u16()?;
+    header.u16()?;
+    let sfnt_size = header.u32()?;
+    if length as usize != data.len() {
+        return Err(header.malformed("declared length does not match the file"));
+    }
+    if sfnt_size as usize > MAX_SFNT_SIZE {
+        return Err(header.malformed("declared font size is too large"));
+    }
+    header.bytes(24)?;
+
+    let mut tables = Vec::with_capacity(usize::from(table_count));
+    for _ in 0..table_count {
+        let tag = header.tag()?;
+        let offset = header.u32()? as usize;
+        let compressed_length = header.u32()? as usize;
+        let length = header.u32()? as usize;
+        header.u32()?;
+        if compressed_length > length || length > MAX_SFNT_SIZE {
+            return Err(header.malformed("table is longer compressed than uncompressed"));
+        }
+        let mut table = Reader::new(data, FontContainer::Woff);
+        table.at = offset;
+        let stored = table.bytes(compressed_length)?;
+        let contents = if compressed_length == length {
+            stored.to_vec()
+        } else {
+            miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(stored, length)
+                .ok()
+                .filter(|contents| contents.len() == length)
+                .ok_or_else(|| FontDataError::Decompress {
+                    container: FontContainer::Woff,
+                    reason: format!(
+                        "table {} is not zlib data of its declared length",
+                        String::from_utf8_lossy(&tag)
+                    ),
+                })?
+        };
+        tables.push((tag, contents));
+    }
+    Ok(write_sfnt(flavor, tables))
+}
+
+/// Lay `tables` out as an SFNT font, with checksums and the table
+/// directory's search fields filled in.
+fn write_sfnt(flavor: u32, mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|&(tag, _)| tag);
+    let count = tables.len() as u16;
+    let power: u16 = if count == 0 {
+        0
+    } else {
+        1 << (15 - count.leading_zeros())
+    };
+    // Both overflow past 4095 tables, which no font has
+    let search_range = power.wrapping_mul(16);
+    let mut font = Vec::new();
+    font.extend_from_slice(&flavor.to_be_bytes());
+    font.extend_from_slice(&count.to_be_bytes());
+    font.extend_from_slice(&search_range.to_be_bytes());
+    font.extend_from_slice(&(power.max(1).trailing_zeros() as u16).to_be_bytes());
+    font.extend_from_slice(
+        &count
+            .wrapping_mul(16)
+            .wrapping_sub(search_range)
+            .to_be_bytes(),
+    );
+
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for (tag, contents) in &mut tables {
+        if tag == b"head" && contents.len() >= 12 {
+            contents[8..12].fill(0);
+            head_offset = Some(offset);
+        }
+        font.extend_from_slice(tag.as_slice());
+        font.extend_from_slice(&checksum(contents).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(contents.len() as u32).to_be_bytes());
+        offset += contents.len().next_multiple_of(4);
+    }
+    for (_, contents) in &tables {
+        font.extend_from_slice(contents);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+    if let Some(head) = head_offset {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+        font[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    font
+}
+
+/// The OpenType checksum: the sum of big-endian u32s, zero padded.
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+#[cfg(feature = "woff2")]
+use woff2::decode_woff2;
+
+#[cfg(feature = "woff2")]
+mod woff2 {
+    use super::{write_sfnt, FontContainer, FontDataError, Reader, MAX_SFNT_SIZE};
+
+    /// Tags of the WOFF2 known table indices
+    const KNOWN_TAGS: [&[u8; 4]; 63] = [
+        b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+        b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+        b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+        b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+        b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+        b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+        b"Gloc", b"Feat", b"Sill",
+    ];
+
+    struct TableEntry {
+        tag: [u8; 4],
+        length: usize,
+        /// Length in the decompressed stream, when the table is transformed
+        transform_length: Option<usize>,
+    }
+
+    impl TableEntry {
+        fn stored_length(&self) -> usize {
+            self.transform_length.unwrap_or(self.length)
+        }
+    }
+
+    pub(super) fn decode_woff2(data: &[u8]) -> Result<Vec<u8>, FontDataError> {
+        let mut header = Reader::new(data, FontContainer::Woff2);
+        header.bytes(4)?;
+        let flavor = header.u32()?;
+        if &flavor.to_be_bytes() == b"ttcf" {
+            return Err(FontDataError::UnsupportedCollection);
+        }
+        let length = header.u32()?;
+        let table_count = header.u16()?;
+        header.u16()?;
+        header.u32()?;
+        let compressed_length = header.u32()? as usize;
+        if length as usize != data.len() {
+            return Err(header.malformed("declared length does not match the file"));
+        }
+        header.bytes(24)?;
+
+        let entries = (0..table_count)
+            .map(|_| read_table_entry(&mut header))
+            .collect::<Result<Vec<_>, _>>()?;
+        let stream_length = entries
+            .iter()
+            .try_fold(0usize, |sum, entry| sum.checked_add(entry.stored_length()))
+            .filter(|&sum| sum <= MAX_SFNT_SIZE)
+            .ok_or(header.malformed("declared font size is too large"))?;
+        let compressed = header.bytes(compressed_length)?;
+        let stream = crate::brotli::decompress(compressed, stream_length)
+            .map_err(|error| error.to_string())
+            .and_then(|stream| {
+                if stream.len() == stream_length {
+                    Ok(stream)
+                } else {
+                    Err("tables are shorter than declared".to_string())
+                }
+            })
+            .map_err(|reason| FontDataError::Decompress {
+                container: FontContainer::Woff2,
+                reason,
+            })?;
+
+        let mut tables = Vec::with_capacity(entries.len());
+        let mut glyf = None;
+        let mut hmtx = None;
+        let mut at = 0;
+        for entry in &entries {
+            let contents = &stream[at..at + entry.stored_length()];
+            at += entry.stored_length();
+            match (&entry.tag, entry.transform_length) {
+                (b"glyf", Some(_)) => glyf = Some(contents),
+                (b"loca", Some(_)) => {}
+                (b"hmtx", Some(_)) => hmtx = Some(contents),
+                _ => tables.push((entry.tag, contents.to_vec())),
+            }
+        }
+
+        let x_mins = match glyf {
+            Some(glyf) => {
+                let glyphs = Glyphs::read(glyf)?;
+                let (glyf, loca) = glyphs.write();
+                tables.push((*b"glyf", glyf));
+                tables.push((*b"loca", loca));
+                Some(glyphs.x_mins)
+            }
+            None => None,
+        };
+        if let Some(hmtx) = hmtx {
+            let x_mins = x_mins.ok_or(malformed("hmtx is transformed but glyf is not"))?;
+            let hhea = tables
+                .iter()
+                .find(|(tag, _)| tag == b"hhea")
+                .ok_or(malformed("hmtx is transformed without an hhea table"))?;
+            let mut hhea = Reader::new(&hhea.1, FontContainer::Woff2);
+            hhea.bytes(34)?;
+            let metric_count = hhea.u16()?;
+            tables.push((*b"hmtx", rebuild_hmtx(hmtx, metric_count, &x_mins)?));
+        }
+        Ok(write_sfnt(flavor, tables))
+    }
+
+    fn malformed(reason: &'static str) -> FontDataError {
+        FontDataError::Malformed {
+            container: FontContainer::Woff2,
+            reason,
+        }
+    }
+
+    fn read_table_entry(header: &mut Reader) -> Result<TableEntry, FontDataError> {
+        let flags = header.u8()?;
+        let tag = match flags & 0x3f {
+            63 => header.tag()?,
+            index => *KNOWN_TAGS[usize::from(index)],
+        };
+        let version = flags >> 6;
+        let length = read_base128(header)? as usize;
+        // glyf and loca are transformed by default, other tables only when
+        // asked
+        let transformed = match &tag {
+            b"glyf" | b"loca" => version == 0,
+            _ => version != 0,
+        };
+        let transform_length = if transformed {
+            Some(read_base128(header)? as usize)
+        } else {
+            None
+        };
+        match (&tag, version, transform_length) {
+            (b"loca", _, Some(length)) if length != 0 => {
+                Err(malformed("transformed loca is not empty"))
+            }
+            (b"glyf" | b"loca", 0 | 3, _) | (b"hmtx", 0 | 1, _) | (_, 0, _) => Ok(TableEntry {
+                tag,
+                length,
+                transform_length,
+            }),
+            _ => Err(malformed("unknown table transform")),
+        }
+    }
+
+    /// WOFF2's UIntBase128: seven bits a byte, high bits first.
+    fn read_base128(reader: &mut Reader) -> Result<u32, FontDataError> {
+        let mut value = 0u32;
+        for i in 0..5 {
+            let byte = reader.u8()?;
+            if i == 0 && byte == 0x80 {
+                return Err(malformed("UIntBase128 has a leading zero"));
+            }
+            if value & 0xfe00_0000 != 0 {
+                return Err(malformed("UIntBase128 overflows"));
+            }
+            value = (value << 7) | u32::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(malformed("UIntBase128 is longer than five bytes"))
+    }
+
+    /// WOFF2's 255UInt16.
+    fn read_255_u16(reader: &mut Reader) -> Result<u16, FontDataError> {
+        Ok(match reader.u8()? {
+            253 => reader.u16()?,
+            254 => u16::from(reader.u8()?) + 506,
+            255 => u16::from(reader.u8()?) + 253,
+            code => u16::from(code),
+        })
+    }
+
+    /// The streams of a transformed `glyf` table.
+    struct GlyfStreams<'a> {
+        contour_counts: Reader<'a>,
+        point_counts: Reader<'a>,
+        flags: Reader<'a>,
+        glyphs: Reader<'a>,
+        composites: Reader<'a>,
+        bbox_bitmap: &'a [u8],
+        bboxes: Reader<'a>,
+        instructions: Reader<'a>,
+        overlap_bitmap: Option<&'a [u8]>,
+    }
+
+    /// Glyphs rebuilt from a transformed `glyf` table.
+    struct Glyphs {
+        /// Each glyph as a standard `glyf` entry, empty for no outline
+        glyphs: Vec<Vec<u8>>,
+        /// Each glyph's left edge, which a transformed `hmtx` omits as its
+        /// left side bearing
+        x_mins: Vec<i16>,
+        long_offsets: bool,
+    }
+
+    fn bit(bitmap: &[u8], index: usize) -> bool {
+        bitmap[index >> 3] & (0x80 >> (index & 7)) != 0
+    }
+
+    impl Glyphs {
+        fn read(table: &[u8]) -> Result<Self, FontDataError> {
+            let mut header = Reader::new(table, FontContainer::Woff2);
+            header.u16()?;
+            let options = header.u16()?;
+            let glyph_count = usize::from(header.u16()?);
+            let long_offsets = header.u16()? != 0;
+            let mut sizes = [0usize; 7];
+            for size in &mut sizes {
+                *size = header.u32()? as usize;
+            }
+            let mut stream = |len| {
+                header
+                    .bytes(len)
+                    .map(|bytes| Reader::new(bytes, FontContainer::Woff2))
+            };
+            let contour_counts = stream(sizes[0])?;
+            let point_counts = stream(sizes[1])?;
+            let flags = stream(sizes[2])?;
+            let glyphs = stream(sizes[3])?;
+            let composites = stream(sizes[4])?;
+            let mut bboxes = stream(sizes[5])?;
+            let instructions = stream(sizes[6])?;
+            let bbox_bitmap = bboxes.bytes(4 * glyph_count.div_ceil(32))?;
+            let overlap_bitmap = if options & 1 != 0 {
+                Some(header.bytes(glyph_count.div_ceil(8))?)
+            } else {
+                None
+            };
+            let mut streams = GlyfStreams {
+                contour_counts,
+                point_counts,
+                flags,
+                glyphs,
+                composites,
+                bbox_bitmap,
+                bboxes,
+                instructions,
+                overlap_bitmap,
+            };
+
+            let mut rebuilt = Self {
+                glyphs: Vec::with_capacity(glyph_count),
+                x_mins: Vec::with_capacity(glyph_count),
+                long_offsets,
+            };
+            for index in 0..glyph_count {
+                let (glyph, x_min) = streams.glyph(index)?;
+                rebuilt.glyphs.push(glyph);
+                rebuilt.x_mins.push(x_min);
+            }
+            Ok(rebuilt)
+        }
+
+        /// The `glyf` and `loca` tables.
+        fn write(&self) -> (Vec<u8>, Vec<u8>) {
+            let mut glyf = Vec::new();
+            let mut loca = Vec::new();
+            let offset = |loca: &mut Vec<u8>, at: usize| {
+                if self.long_offsets {
+                    loca.extend_from_slice(&(at as u32).to_be_bytes());
+                } else {
+                    loca.extend_from_slice(&((at / 2) as u16).to_be_bytes());
+                }
+            };
+            for glyph in &self.glyphs {
+                offset(&mut loca, glyf.len());
+                glyf.extend_from_slice(glyph);
+                glyf.resize(glyf.len().next_multiple_of(4), 0);
+            }
+            offset(&mut loca, glyf.len());
+            (glyf, loca)
+        }
+    }
+
+    impl GlyfStreams<'_> {
+        /// Glyph `index` as a `glyf` entry, and its left edge.
+        fn glyph(&mut self, index: usize) -> Result<(Vec<u8>, i16), FontDataError> {
+            let contour_count = self.contour_counts.i16()?;
+            let has_bbox = bit(self.bbox_bitmap, index);
+            let mut glyph = Vec::new();
+            glyph.extend_from_slice(&contour_count.to_be_bytes());
+            match contour_count {
+                0 if has_bbox => Err(malformed("empty glyph has a bounding box")),
+                0 => Ok((Vec::new(), 0)),
+                -1 if !has_bbox => Err(malformed("composite glyph has no bounding box")),
+                -1 => {
+                    let bbox = self.bboxes.bytes(8)?;
+                    glyph.extend_from_slice(bbox);
+                    let has_instructions = self.composite(&mut glyph)?;
+                    if has_instructions {
+                        self.instructions(&mut glyph)?;
+                    }
+                    Ok((glyph, i16::from_be_bytes([bbox[0], bbox[1]])))
+                }
+                count if count < 0 => Err(malformed("glyph has a negative contour count")),
+                count => {
+                    let x_min = self.simple(&mut glyph, count as usize, index, has_bbox)?;
+                    Ok((glyph, x_min))
+                }
+            }
+        }
+
+        /// Copy a composite glyph's components onto `glyph`, returning
+        /// whether instructions follow them.
+        fn composite(&mut self, glyph: &mut Vec<u8>) -> Result<bool, FontDataError> {
+            let mut has_instructions = false;
+            loop {
+                let flags = self.composites.u16()?;
+                let mut size = 2 + if flags & 0x0001 != 0 { 4 } else { 2 };
+                if flags & 0x0008 != 0 {
+                    size += 2;
+                } else if flags & 0x0040 != 0 {
+                    size += 4;
+                } else if flags & 0x0080 != 0 {
+                    size += 8;
+                }
+                glyph.extend_from_slice(&flags.to_be_bytes());
+                glyph.extend_from_slice(self.composites.bytes(size)?);
+                has_instructions |= flags & 0x0100 != 0;
+                if flags & 0x0020 == 0 {
+                    return Ok(has_instructions);
+                }
+            }
+        }
+
+        fn instructions(&mut self, glyph: &mut Vec<u8>) -> Result<(), FontDataError> {
+            let length = read_255_u16(&mut self.glyphs)?;
+            glyph.extend_from_slice(&length.to_be_bytes());
+            glyph.extend_from_slice(self.instructions.bytes(usize::from(length))?);
+            Ok(())
+        }
+
+        /// Write a simple glyph of `contour_count` contours after its
+        /// contour count, returning its left edge.
+        fn simple(
+            &mut self,
+            glyph: &mut Vec<u8>,
+            contour_count: usize,
+            index: usize,
+            has_bbox: bool,
+        ) -> Result<i16, FontDataError> {
+            let mut end_points = Vec::with_capacity(contour_count);
+            let mut point_count = 0usize;
+            for _ in 0..contour_count {
+                point_count += usize::from(read_255_u16(&mut self.point_counts)?);
+                if point_count == 0 || point_count > 0x10000 {
+                    return Err(malformed("glyph contour has no points"));
+                }
+                end_points.push((point_count - 1) as u16);
+            }
+            let points = self.points(point_count)?;
+
+            let bbox = if has_bbox {
+                let mut bbox = [0i16; 4];
+                for value in &mut bbox {
+                    *value = self.bboxes.i16()?;
+                }
+                bbox
+            } else {
+                points.iter().fold(
+                    [i16::MAX, i16::MAX, i16::MIN, i16::MIN],
+                    |[x_min, y_min, x_max, y_max], &(x, y, _)| {
+                        [x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y)]
+                    },
+                )
+            };
+            for value in bbox {
+                glyph.extend_from_slice(&value.to_be_bytes());
+            }
+            for end in end_points {
+                glyph.extend_from_slice(&end.to_be_bytes());
+            }
+            self.instructions(glyph)?;
+
+            let overlaps = self.overlap_bitmap.is_some_and(|bitmap| bit(bitmap, index));
+            write_points(glyph, &points, overlaps);
+            Ok(bbox[0])
+        }
+
+        /// Decode `count` points from the flag and glyph streams, as
+        /// absolute coordinates and whether each is on the curve.
+        fn points(&mut self, count: usize) -> Result<Vec<(i16, i16, bool)>, FontDataError> {
+            let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+            let mut points = Vec::with_capacity(count);
+            let (mut x, mut y) = (0i32, 0i32);
+            for _ in 0..count {
+                let flag = self.flags.u8()?;
+                let on_curve = flag & 0x80 == 0;
+                let flag = flag & 0x7f;
+                let data_length = match flag {
+                    0..=83 => 1,
+                    84..=119 => 2,
+                    120..=123 => 3,
+                    _ => 4,
+                };
+                let data: Vec<i32> = self
+                    .glyphs
+                    .bytes(data_length)?
+                    .iter()
+                    .map(|&byte| i32::from(byte))
+                    .collect();
+                let (dx, dy) = match flag {
+                    0..=9 => (0, with_sign(flag, (i32::from(flag & 14) << 7) + data[0])),
+                    10..=19 => (
+                        with_sign(flag, (i32::from((flag - 10) & 14) << 7) + data[0]),
+                        0,
+                    ),
+                    20..=83 => {
+                        let high = i32::from(flag - 20);
+                        (
+                            with_sign(flag, 1 + (high & 0x30) + (data[0] >> 4)),
+                            with_sign(flag >> 1, 1 + ((high & 0x0c) << 2) + (data[0] & 0x0f)),
+                        )
+                    }
+                    84..=119 => {
+                        let high = i32::from(flag - 84);
+                        (
+                            with_sign(flag, 1 + ((high / 12) << 8) + data[0]),
+                            with_sign(flag >> 1, 1 + (((high % 12) >> 2) << 8) + data[1]),
+                        )
+                    }
+                    120..=123 => (
+                        with_sign(flag, (data[0] << 4) + (data[1] >> 4)),
+                        with_sign(flag >> 1, ((data[1] & 0x0f) << 8) + data[2]),
+                    ),
+                    _ => (
+                        with_sign(flag, (data[0] << 8) + data[1]),
+                        with_sign(flag >> 1, (data[2] << 8) + data[3]),
+                    ),
+                };
+                x += dx;
+                y += dy;
+                let (Ok(point_x), Ok(point_y)) = (i16::try_from(x), i16::try_from(y)) else {
+                    return Err(malformed("glyph point is out of range"));
+                };
+                points.push((point_x, point_y, on_curve));
+            }
+            Ok(points)
+        }
+    }
+
+    /// Write the flags and coordinates of a simple glyph, with each delta in
+    /// its shortest form.
+    fn write_points(glyph: &mut Vec<u8>, points: &[(i16, i16, bool)], overlaps: bool) {
+        let mut flags = Vec::with_capacity(points.len());
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        let (mut x, mut y) = (0i16, 0i16);
+        for (index, &(point_x, point_y, on_curve)) in points.iter().enumerate() {
+            let mut flag = u8::from(on_curve);
+            if overlaps && index == 0 {
+                flag |= 0x40;
+            }
+            flag |= write_delta(point_x.wrapping_sub(x), 0x02, 0x10, &mut xs);
+            flag |= write_delta(point_y.wrapping_sub(y), 0x04, 0x20, &mut ys);
+            flags.push(flag);
+            (x, y) = (point_x, point_y);
+        }
+        glyph.extend_from_slice(&flags);
+        glyph.extend_from_slice(&xs);
+        glyph.extend_from_slice(&ys);
+    }
+
+    /// Write one coordinate delta in its shortest form, returning its flags:
+    /// `short` for one byte, `same` for a zero delta or a positive short one.
+    fn write_delta(delta: i16, short: u8, same: u8, out: &mut Vec<u8>) -> u8 {
+        if delta == 0 {
+            same
+        } else if (-255..=255).contains(&delta) {
+            out.push(delta.unsigned_abs() as u8);
+            if delta > 0 {
+                short | same
+            } else {
+                short
+            }
+        } else {
+            out.extend_from_slice(&delta.to_be_bytes());
+            0
+        }
+    }
+
+    /// A standard `hmtx` table from its transformed form.
+    fn rebuild_hmtx(
+        table: &[u8],
+        metric_count: u16,
+        x_mins: &[i16],
+    ) -> Result<Vec<u8>, FontDataError> {
+        let metric_count = usize::from(metric_count);
+        if metric_count == 0 || metric_count > x_mins.len() {
+            return Err(malformed("hhea has more metrics than there are glyphs"));
+        }
+        let mut reader = Reader::new(table, FontContainer::Woff2);
+        let flags = reader.u8()?;
+        let advances = (0..metric_count)
+            .map(|_| reader.u16())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut side_bearings = Vec::with_capacity(x_mins.len());
+        for (index, &x_min) in x_mins.iter().enumerate() {
+            let omitted = if index < metric_count {
+                flags & 1 != 0
+            } else {
+                flags & 2 != 0
+            };
+            side_bearings.push(if omitted { x_min } else { reader.i16()? });
+        }
+        let mut hmtx = Vec::with_capacity(2 * (metric_count + x_mins.len()));
+        for (index, side_bearing) in side_bearings.iter().enumerate() {
+            if let Some(advance) = advances.get(index) {
+                hmtx.extend_from_slice(&advance.to_be_bytes());
+            }
+            hmtx.extend_from_slice(&side_bearing.to_be_bytes());
+        }
+        Ok(hmtx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_woff_rejects_a_font_larger_than_the_limit() {
+        let mut woff = Vec::new();
+        woff.extend_from_slice(b"wOFF");
+        woff.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        woff.extend_from_slice(&44u32.to_be_bytes());
+        woff.extend_from_slice(&u16s(&[0, 0]));
+        woff.extend_from_slice(&(MAX_SFNT_SIZE as u32 + 1).to_be_bytes());
+        woff.resize(44, 0);
+        assert_eq!(
+            decode(&woff),
+            Err(FontDataError::Malformed {
+                container: FontContainer::Woff,
+                reason: "declared font size is too large",
+            })
+        );
+    }
+
+    #[cfg(feature = "woff2")]
+    mod woff2 {
+        use super::*;
+
+        /// The tables of an SFNT font by tag.
+        fn sfnt_tables(font: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+            let count = usize::from(u16::from_be_bytes([font[4], font[5]]));
+            (0..count)
+                .map(|index| {
+                    let record = &font[12 + 16 * index..28 + 16 * index];
+                    let field = |at: usize| {
+                        u32::from_be_bytes([
+                            record[at],
+                            record[at + 1],
+                            record[at + 2],
+                            record[at + 3],
+                        ]) as usize
+                    };
+                    let (offset, length) = (field(8), field(12));
+                    let tag = [record[0], record[1], record[2], record[3]];
+                    (tag, font[offset..offset + length].to_vec())
+                })
+                .collect()
+        }
+
+        /// `data` as a Brotli stream of one uncompressed meta-block.
+        fn stored_brotli(data: &[u8]) -> Vec<u8> {
+            // WBITS 16, not last, four length nibbles, uncompressed
+            let header = ((data.len() as u32 - 1) << 4) | 1 << 20;
+            let mut stream = header.to_le_bytes()[..3].to_vec();
+            stream.extend_from_slice(data);
+            // A last, empty meta-block
+            stream.push(0x03);
+            stream
+        }
+
+        fn base128(value: u32) -> Vec<u8> {
+            let mut bytes = vec![(value & 0x7f) as u8];
+            let mut rest = value >> 7;
+            while rest > 0 {
+                bytes.insert(0, (rest & 0x7f) as u8 | 0x80);
+                rest >>= 7;
+            }
+            bytes
+        }
+
+        /// A WOFF2 file of the given directory entries and table stream.
+        fn woff2(directory: &[u8], table_count: u16, tables: &[u8]) -> Vec<u8> {
+            let compressed = stored_brotli(tables);
+            let length = 48 + directory.len() + compressed.len();
+            let mut file = Vec::new();
+            file.extend_from_slice(b"wOF2");
+            file.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+            file.extend_from_slice(&(length as u32).to_be_bytes());
+            file.extend_from_slice(&u16s(&[table_count, 0]));
+            file.extend_from_slice(&0u32.to_be_bytes());
+            file.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+            file.resize(48, 0);
+            file.extend_from_slice(directory);
+            file.extend_from_slice(&compressed);
+            file
+        }
+
+        /// A transformed `glyf` table of an empty glyph, a triangle with no
+        /// explicit bounding box, and a composite of two triangles, the
+        /// second scaled and with instructions.
+        fn transformed_glyf() -> Vec<u8> {
+            let contour_counts = u16s(&[0, 1, 0xffff]);
+            let point_counts = vec![3];
+            // On-curve points with 16-bit deltas: +x +y, +x +y, -x +y
+            let flags = vec![127, 127, 126];
+            let mut glyphs = u16s(&[10, 0, 50, 0, 25, 50]);
+            // The triangle's instruction length, then the composite's
+            glyphs.extend_from_slice(&[0, 2]);
+            let composites = [
+                // MORE_COMPONENTS, ARGS_ARE_XY_VALUES, ARG_1_AND_2_ARE_WORDS
+                u16s(&[0x0023, 1, 0, 0]),
+                // WE_HAVE_INSTRUCTIONS and WE_HAVE_A_SCALE of 1.0
+                u16s(&[0x010b, 1, 100, 0, 0x4000]),
+            ]
+            .concat();
+            // Only the composite has a bounding box
+            let mut bboxes = vec![0x20, 0, 0, 0];
+            bboxes.extend_from_slice(&u16s(&[0, 0, 160, 50]));
+            let instructions = vec![0xb0, 0x01];
+
+            let streams = [
+                contour_counts,
+                point_counts,
+                flags,
+                glyphs,
+                composites,
+                bboxes,
+                instructions,
+            ];
+            let mut table = u16s(&[0, 0, 3, 0]);
+            for stream in &streams {
+                table.extend_from_slice(&(stream.len() as u32).to_be_bytes());
+            }
+            for stream in &streams {
+                table.extend_from_slice(stream);
+            }
+            table
+        }
+
+        fn hhea(metric_count: u16) -> Vec<u8> {
+            let mut hhea = vec![0; 34];
+            hhea.extend_from_slice(&metric_count.to_be_bytes());
+            hhea
+        }
+
+        /// A font with transformed `glyf`, `loca` and `hmtx` tables.
+        fn transformed_font() -> Vec<u8> {
+            let glyf = transformed_glyf();
+            // Left side bearings of the proportional glyphs are omitted
+            let hmtx = [vec![0x01], u16s(&[500, 600, 0xfffb])].concat();
+            let mut directory = Vec::new();
+            directory.push(2);
+            directory.extend(base128(36));
+            directory.push(10);
+            directory.extend(base128(56));
+            directory.extend(base128(glyf.len() as u32));
+            directory.push(11);
+            directory.extend(base128(8));
+            directory.extend(base128(0));
+            directory.push(3 | 1 << 6);
+            directory.extend(base128(10));
+            directory.extend(base128(hmtx.len() as u32));
+            let tables = [hhea(2), glyf, hmtx].concat();
+            woff2(&directory, 4, &tables)
+        }
+
+        #[test]
+        fn test_transformed_glyf_rebuilds_simple_and_composite_glyphs() {
+            let tables = sfnt_tables(&decode(&transformed_font()).unwrap());
+            let tags: Vec<&[u8; 4]> = tables.iter().map(|(tag, _)| tag).collect();
+            assert_eq!(tags, [b"glyf", b"hhea", b"hmtx", b"loca"]);
+
+            let triangle = [
+                u16s(&[1, 10, 0, 60, 50, 2, 0]),
+                vec![0x33, 0x33, 0x27, 10, 50, 25, 50, 0, 0, 0],
+            ]
+            .concat();
+            let composite = u16s(&[
+                0xffff, 0, 0, 160, 50, 0x0023, 1, 0, 0, 0x010b, 1, 100, 0, 0x4000, 2, 0xb001,
+            ]);
+            assert_eq!(tables[0].1, [triangle, composite].concat());
+        }
+
+        #[test]
+        fn test_transformed_loca_has_short_offsets_to_each_glyph() {
+            let tables = sfnt_tables(&decode(&transformed_font()).unwrap());
+            assert_eq!(tables[3].1, u16s(&[0, 0, 12, 28]));
+        }
+
+        #[test]
+        fn test_transformed_hmtx_takes_omitted_bearings_from_glyph_bounds() {
+            let tables = sfnt_tables(&decode(&transformed_font()).unwrap());
+            // The empty glyph's left edge is 0 and the triangle's 10; the
+            // composite's bearing is stored
+            assert_eq!(tables[2].1, u16s(&[500, 0, 600, 10, 0xfffb]));
+        }
+
+        #[test]
+        fn test_composite_glyph_needs_a_bounding_box() {
+            let mut glyf = transformed_glyf();
+            // Clear the composite's bit in the bounding box bitmap
+            let bitmap = glyf.len() - 2 - 8 - 4;
+            glyf[bitmap] = 0;
+            let mut directory = vec![10];
+            directory.extend(base128(56));
+            directory.extend(base128(glyf.len() as u32));
+            directory.extend([11, 8, 0]);
+            assert_eq!(
+                decode(&woff2(&directory, 2, &glyf)),
+                Err(FontDataError::Malformed {
+                    container: FontContainer::Woff2,
+                    reason: "composite glyph has no bounding box",
+                })
+            );
+        }
+
+        #[test]
+        fn test_rejects_tables_larger_than_the_limit() {
+            let mut directory = vec![2];
+            directory.extend(base128(MAX_SFNT_SIZE as u32 + 1));
+            assert_eq!(
+                decode(&woff2(&directory, 1, &hhea(1))),
+                Err(FontDataError::Malformed {
+                    container: FontContainer::Woff2,
+                    reason: "declared font size is too large",
+                })
+            );
+        }
+
+        #[test]
+        fn test_accepts_tables_up_to_the_limit() {
+            // At the limit, only the stream's real length stops decoding
+            let mut directory = vec![2];
+            directory.extend(base128(MAX_SFNT_SIZE as u32));
+            assert!(matches!(
+                decode(&woff2(&directory, 1, &hhea(1))),
+                Err(FontDataError::Decompress { .. })
+            ));
+        }
+    }
+}
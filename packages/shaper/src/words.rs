@@ -0,0 +1,81 @@
+//! Word geometry for accessibility overlays.
+//!
+//! Words come from Unicode word boundaries within each paragraph, and each
+//! box covers the glyphs whose clusters start inside the word, so a word
+//! wrapped across lines yields one box per line.
+
+use cosmic_text::{LayoutRun, LineIter};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The part of one word laid out on one line. Byte offsets index the whole
+/// text and cover only the part of the word on this line.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WordBox {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub line_index: usize,
+}
+
+/// The words of one paragraph, split as cosmic-text splits paragraphs.
+pub(crate) struct ParagraphWords {
+    /// Byte offset of the paragraph in the whole text
+    start: usize,
+    /// Word ranges in the whole text, without whitespace-only segments
+    words: Vec<Range<usize>>,
+}
+
+pub(crate) fn paragraph_words(text: &str) -> Vec<ParagraphWords> {
+    LineIter::new(text)
+        .map(|(range, _)| {
+            let start = range.start;
+            let words = text[range]
+                .split_word_bound_indices()
+                .filter(|(_, segment)| !segment.chars().all(char::is_whitespace))
+                .map(|(offset, segment)| start + offset..start + offset + segment.len())
+                .collect();
+            ParagraphWords { start, words }
+        })
+        .collect()
+}
+
+/// Boxes for the words on one laid out line of `paragraph`, in text order.
+pub(crate) fn line_word_boxes(
+    run: &LayoutRun,
+    paragraph: &ParagraphWords,
+    line_index: usize,
+) -> Vec<WordBox> {
+    let words = &paragraph.words;
+    let paragraph_start = paragraph.start;
+    let mut boxes: BTreeMap<usize, WordBox> = BTreeMap::new();
+    for glyph in run.glyphs {
+        let start = paragraph_start + glyph.start;
+        let end = paragraph_start + glyph.end;
+        let index = words.partition_point(|word| word.end <= start);
+        let Some(word) = words.get(index).filter(|word| word.start <= start) else {
+            continue;
+        };
+        let word_box = boxes.entry(index).or_insert(WordBox {
+            byte_start: start,
+            byte_end: end.min(word.end),
+            x: glyph.x,
+            y: run.line_top,
+            width: 0.0,
+            height: run.line_height,
+            line_index,
+        });
+        // Glyphs arrive in visual order, so grow both edges
+        let right = (word_box.x + word_box.width).max(glyph.x + glyph.w);
+        word_box.x = word_box.x.min(glyph.x);
+        word_box.width = right - word_box.x;
+        word_box.byte_start = word_box.byte_start.min(start);
+        word_box.byte_end = word_box.byte_end.max(end.min(word.end));
+    }
+    boxes.into_values().collect()
+}
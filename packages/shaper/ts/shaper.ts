@@ -42,6 +42,11 @@ export interface ShapedGlyph {
   yOffset: number;
   start: number;
   end: number;
+  /**
+   * How far the glyph was raised by its span's baselineShift, negative when
+   * lowered. Already in y; a selection box around it moves up as far.
+   */
+  baselineShift: number;
 }
 
 /**
@@ -49,10 +54,28 @@ export interface ShapedGlyph {
  */
 export interface ShapedLineResult {
   glyphs: ShapedGlyph[];
+  /** Advance width without trailing whitespace, which hangs as in browsers */
   width: number;
+  /** Advance width including trailing whitespace, where a caret after it goes */
+  widthWithTrailingWhitespace: number;
   height: number;
   ascent: number;
   descent: number;
+  /** The line height is below the font size, so stacked lines would overlap */
+  clippedLines: boolean;
+}
+
+/**
+ * Consecutive glyphs of a line sharing a font and color, drawable with one
+ * texture bind. Runs cover the line's glyphs left to right.
+ */
+export interface DrawRun {
+  cosmicFontId: number;
+  /** Packed 0xRRGGBBAA, or null for the default text color */
+  color: number | null;
+  glyphStart: number;
+  /** One past the run's last glyph */
+  glyphEnd: number;
 }
 
 /**
@@ -60,11 +83,182 @@ export interface ShapedLineResult {
  */
 export interface LayoutLine {
   glyphs: ShapedGlyph[];
+  /** Advance width without trailing whitespace, for alignment and fitting */
   width: number;
+  /** Advance width including trailing whitespace, whose glyphs stay in glyphs */
+  widthWithTrailingWhitespace: number;
   y: number;
   lineHeight: number;
+  /** How far the glyphs reach above y, shifted glyphs included */
+  ascent: number;
+  /** How far the glyphs reach below y, shifted glyphs included */
+  descent: number;
+  /** Empty unless requested with LayoutOptions.drawRuns */
+  drawRuns: DrawRun[];
+  /** How far y was moved down to land on LayoutOptions.baselineGrid */
+  baselineShift: number;
+  /** The line breaks at a soft hyphen and its last glyph is a visible hyphen */
+  hyphenated: boolean;
+  /** Why the line ends, null for the last line */
+  breakReason: BreakReason | null;
+  /**
+   * Glyph offset of the break: where the next line's text starts, or the end
+   * of the paragraph for a newline. Null for the last line.
+   */
+  breakOffset: number | null;
+  /**
+   * Ink box of each glyph in layout space, null for glyphs that paint nothing.
+   * Empty unless requested with LayoutOptions.inkBounds.
+   */
+  glyphInkBounds: Array<InkBounds | null>;
+  /** Union of glyphInkBounds, which can reach past width for overhanging glyphs */
+  inkBounds: InkBounds | null;
+}
+
+/**
+ * A drop shadow for LayoutOptions.shadows. Blurring is left to the renderer.
+ */
+export interface TextShadow {
+  offsetX: number;
+  offsetY: number;
+  /** Packed 0xRRGGBBAA */
+  color: number;
+}
+
+/**
+ * Glyphs of a line drawn again for a shadow, offset and in its color. The
+ * glyphs are the main text's, so selection and carets never see shadows.
+ */
+export interface ShadowRun {
+  /** Index into LayoutResult.lines */
+  line: number;
+  cosmicFontId: number;
+  /** Packed 0xRRGGBBAA */
+  color: number;
+  glyphStart: number;
+  /** One past the run's last glyph */
+  glyphEnd: number;
+  /** Origin the glyph positions are added to, in place of (0, line.y) */
+  x: number;
+  y: number;
+}
+
+export interface LayoutOptions {
+  /** Group each line's glyphs into drawRuns */
+  drawRuns?: boolean;
+  /** Fill each line's glyphInkBounds and inkBounds, roughly doubling the result */
+  inkBounds?: boolean;
+  /**
+   * Move each line down so its baseline falls on a multiple of this spacing,
+   * keeping lines at least their natural distance apart.
+   */
+  baselineGrid?: number;
+  /**
+   * Break long words where the patterns registered for language allow.
+   * layoutText only; soft hyphens in the text are honored regardless.
+   */
+  hyphenate?: boolean;
+  /**
+   * Language of the text as a BCP 47 tag, picking the patterns used by
+   * hyphenate and the casing rules of textCase
+   */
+  language?: string;
+  /**
+   * How spaces, tabs and newlines are laid out, as CSS white-space; defaults
+   * to "pre-wrap". Glyph offsets still index the text as passed in. layoutText
+   * only.
+   */
+  whiteSpace?: WhiteSpace;
+  /**
+   * Lay out the text in this case. Glyph offsets still index the text as
+   * passed in; the glyphs of a character whose case mapping is longer, such
+   * as "ß" uppercased to "SS", all fall within its range. layoutText only.
+   */
+  textCase?: TextCase;
+  /**
+   * Fill LayoutResult.shadowRuns with these shadows, the first on top as in
+   * CSS text-shadow. Shadows with a non-finite offset are skipped.
+   */
+  shadows?: TextShadow[];
+  /** Where lines may break when the text is too wide; defaults to "word" */
+  wrap?: WrapMode;
+  /** Drop the lines after this many, ending the last with "max-lines" */
+  maxLines?: number;
+  /** Fill LayoutResult.rejectedBreaks, for polishing where the last line starts */
+  breakCandidates?: boolean;
+  /**
+   * Stretch lines that wrapped to maxWidth, leaving each paragraph's last
+   * line as it is. Glyph advances grow to cover the space added after them.
+   */
+  justify?: boolean;
+  /** Where justify adds space; defaults to "inter-word" */
+  justifyDistribution?: JustifyDistribution;
+  /** Most pixels justify adds to one word gap; the rest goes between characters */
+  maxInterWordStretch?: number;
+}
+
+/**
+ * "inter-word" falls back to "inter-character" on lines without a word gap,
+ * and "auto" picks "inter-character" for lines mostly in CJK scripts.
+ */
+export type JustifyDistribution = "inter-word" | "inter-character" | "auto";
+
+/**
+ * "word" breaks only at line break opportunities, "word-or-glyph" also
+ * inside a word too long for a line of its own, "glyph" anywhere.
+ */
+export type WrapMode = "word" | "word-or-glyph" | "glyph";
+
+/** Why a line ends */
+export type BreakReason = "width" | "newline" | "grapheme-forced" | "max-lines";
+
+export type WhiteSpace = "normal" | "pre" | "pre-wrap" | "pre-line";
+
+/** A case transform, after CSS text-transform; title cases each word's first letter */
+export type TextCase = "upper" | "lower" | "title";
+
+/**
+ * A stretch of the original text and the transformed text it became, as UTF-8
+ * byte ranges. Offsets inside a oneToOne stretch map to the same distance into
+ * the other text; any other stretch is one character whose mapping changed
+ * length, such as "ß" to "SS", and only its ends correspond.
+ */
+export interface CaseMapping {
+  originalStart: number;
+  originalEnd: number;
+  transformedStart: number;
+  transformedEnd: number;
+  oneToOne: boolean;
+}
+
+/**
+ * Text with a case transform applied, and mappings covering both texts in
+ * order.
+ */
+export interface CaseTransform {
+  text: string;
+  mappings: CaseMapping[];
 }
 
+/**
+ * A run of text with its own style and color for layoutRichText.
+ */
+export interface RichTextSpan {
+  text: string;
+  style?: FontStyleOptions;
+  /** Packed 0xRRGGBBAA */
+  color?: number;
+  /**
+   * Raise or lower the span: "super" and "sub" use the font's positions,
+   * and a number is pixels, positive upward. Lines grow to fit.
+   */
+  baselineShift?: BaselineShift;
+  /** Multiple of the layout's font size to draw the span at */
+  fontScale?: number;
+}
+
+export type BaselineShift = "super" | "sub" | number;
+
 /**
  * Result of laying out multi-line text.
  */
@@ -72,6 +266,18 @@ export interface LayoutResult {
   lines: LayoutLine[];
   totalWidth: number;
   totalHeight: number;
+  /** The line height is below the font size, so lines overlap */
+  clippedLines: boolean;
+  /**
+   * Runs for LayoutOptions.shadows in drawing order: the last shadow first,
+   * all before any line's own glyphs
+   */
+  shadowRuns: ShadowRun[];
+  /**
+   * Break opportunities inside the last line that layout passed over, as
+   * glyph offsets. Empty unless requested with LayoutOptions.breakCandidates.
+   */
+  rejectedBreaks: number[];
 }
 
 /**
@@ -80,6 +286,180 @@ export interface LayoutResult {
 export interface MeasureResult {
   width: number;
   height: number;
+  /** The line height is below the font size, so lines overlap */
+  clippedLines: boolean;
+}
+
+/**
+ * The code property of the Error thrown when a font size or line height is
+ * NaN, not positive, infinite, or a font size is above 10,000.
+ */
+export type MetricsErrorCode =
+  | "font_size_not_a_number"
+  | "font_size_not_positive"
+  | "font_size_too_large"
+  | "line_height_not_a_number"
+  | "line_height_not_positive"
+  | "line_height_too_large";
+
+/**
+ * The code property of the Error thrown when registering a WOFF or WOFF2 file
+ * that is truncated, malformed, or fails to decompress.
+ */
+export type FontDataErrorCode =
+  | "woff_truncated"
+  | "woff2_truncated"
+  | "woff_malformed"
+  | "woff2_malformed"
+  | "woff_decompress_failed"
+  | "woff2_decompress_failed"
+  | "woff2_collection_unsupported"
+  | "woff2_disabled";
+
+/**
+ * Box around the painted part of a glyph or string, with y growing downward
+ * from the baseline.
+ */
+export interface InkBounds {
+  xMin: number;
+  yMin: number;
+  xMax: number;
+  yMax: number;
+}
+
+type RawInkBounds = { x_min: number; y_min: number; x_max: number; y_max: number } | null;
+
+function convertInkBounds(raw: RawInkBounds): InkBounds | null {
+  if (!raw) {
+    return null;
+  }
+  return { xMin: raw.x_min, yMin: raw.y_min, xMax: raw.x_max, yMax: raw.y_max };
+}
+
+/**
+ * The part of one word laid out on one line, from wordBoxes. Byte offsets
+ * index the UTF-8 text; the box spans the word's glyphs and its line's height.
+ */
+export interface WordBox {
+  byteStart: number;
+  byteEnd: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  lineIndex: number;
+}
+
+/**
+ * A glyph cut by an edge of the viewport in visibleGlyphRange. Its visible
+ * part runs from visibleStart to visibleEnd, as fractions of its advance
+ * from its left edge.
+ */
+export interface ClippedGlyph {
+  /** Index in the shaped line's glyphs */
+  index: number;
+  visibleStart: number;
+  visibleEnd: number;
+}
+
+/**
+ * The glyphs of a scrolled single line that show in its viewport. Shaped
+ * glyphs of right-to-left runs are in logical order, so positions start..end
+ * count glyphs in visual order, left to right.
+ */
+export interface VisibleGlyphRange {
+  start: number;
+  end: number;
+  /** Indices in the shaped line's glyphs of the glyphs wholly in view, left to right */
+  glyphs: number[];
+  /** The glyph cut by the viewport's left edge, or by both edges */
+  leading: ClippedGlyph | null;
+  /** The glyph cut by the viewport's right edge */
+  trailing: ClippedGlyph | null;
+}
+
+/**
+ * A variation axis of a variable font, such as `wght`.
+ */
+export interface FontAxis {
+  tag: string;
+  min: number;
+  default: number;
+  max: number;
+}
+
+/**
+ * One font of a registerFontsBatch call, described by its first face. A font
+ * that failed to load has a null id, an error and empty metadata.
+ */
+export interface RegisteredFont {
+  id: number | null;
+  error: string | null;
+  families: string[];
+  weight: number;
+  style: FontStyle;
+  stretch: FontStretch;
+  isVariable: boolean;
+  axes: FontAxis[];
+}
+
+type RawRegisteredFont = {
+  id: number | null;
+  error: string | null;
+  families: string[];
+  weight: number;
+  style: string;
+  stretch: string;
+  is_variable: boolean;
+  axes: FontAxis[];
+};
+
+function convertRegisteredFont(raw: RawRegisteredFont): RegisteredFont {
+  return {
+    id: raw.id ?? null,
+    error: raw.error ?? null,
+    families: raw.families,
+    weight: raw.weight,
+    style: raw.style as FontStyle,
+    stretch: raw.stretch as FontStretch,
+    isVariable: raw.is_variable,
+    axes: raw.axes,
+  };
+}
+
+/**
+ * Pixel encoding for rasterized glyphs. By default outline glyphs are one
+ * byte of geometric coverage per pixel, and color glyphs are straight RGBA
+ * with sRGB-encoded channels.
+ */
+export interface RasterOptions {
+  /**
+   * Multiply color glyphs' RGB by their alpha, so no channel exceeds alpha.
+   * Coverage masks are alpha only and unchanged.
+   */
+  premultiplied?: boolean;
+  /**
+   * Replace mask coverage with `1 - (1 - coverage)^gamma`, the alpha that
+   * makes dark-on-light text blended in linear space match sRGB blending.
+   * Color glyphs are unchanged.
+   */
+  gammaCorrect?: boolean;
+  /** Exponent for gammaCorrect, default 2.2 */
+  gamma?: number;
+}
+
+function rasterOptionsToWasm(options: RasterOptions): Record<string, unknown> {
+  const wasmOptions: Record<string, unknown> = {};
+  if (options.premultiplied !== undefined) {
+    wasmOptions.premultiplied = options.premultiplied;
+  }
+  if (options.gammaCorrect !== undefined) {
+    wasmOptions.gamma_correct = options.gammaCorrect;
+  }
+  if (options.gamma !== undefined) {
+    wasmOptions.gamma = options.gamma;
+  }
+  return wasmOptions;
 }
 
 /**
@@ -95,6 +475,84 @@ export interface RasterizedGlyph {
   isColor: boolean;
 }
 
+/**
+ * Cache occupancy and counters from `cacheStats`.
+ */
+export interface CacheStats {
+  shapedLines: number;
+  shapedLineBytes: number;
+  glyphs: number;
+  glyphBytes: number;
+  budgetBytes: number | null;
+  shapeHits: number;
+  shapeMisses: number;
+  glyphHits: number;
+  rasterizations: number;
+  evictions: number;
+  /** Font faces with rasterization state kept, at most 64 */
+  rasterFaces: number;
+}
+
+/**
+ * A rasterized glyph dropped from the glyph cache. `fontSize` is the bucketed
+ * size it was rasterized at.
+ */
+export interface EvictedGlyph {
+  cosmicFontId: number;
+  glyphId: number;
+  fontSize: number;
+}
+
+function convertEvictedGlyphs(
+  raw: Array<{ cosmic_font_id: number; glyph_id: number; font_size: number }>
+): EvictedGlyph[] {
+  return raw.map((glyph) => ({
+    cosmicFontId: glyph.cosmic_font_id,
+    glyphId: glyph.glyph_id,
+    fontSize: glyph.font_size,
+  }));
+}
+
+/**
+ * How cluster advances map to distance along the path.
+ */
+export type SpacingMode = "exact" | "fit";
+
+/**
+ * Options for laying text along a path.
+ */
+export interface TextPathOptions {
+  /** Distance along the path where the text starts */
+  startOffset?: number;
+  spacingMode?: SpacingMode;
+  /** Drop clusters outside the path instead of extending its end tangent */
+  clipOverflow?: boolean;
+}
+
+/**
+ * A glyph placed along a path, drawn rotated by `rotationRad` around its
+ * baseline origin at (`x`, `y`).
+ */
+export interface PathGlyph {
+  glyphId: number;
+  cosmicFontId: number;
+  x: number;
+  y: number;
+  rotationRad: number;
+  start: number;
+  end: number;
+  overflow: boolean;
+}
+
+/**
+ * Result of laying text along a path.
+ */
+export interface TextOnPathResult {
+  glyphs: PathGlyph[];
+  pathLength: number;
+  overflowClusters: number;
+}
+
 export type FontStretch =
   | "ultra-condensed"
   | "extra-condensed"
@@ -119,7 +577,7 @@ export interface FontStyleOptions {
 /**
  * Convert FontStyleOptions to the format expected by WASM.
  */
-function styleToWasm(style: FontStyleOptions): Record<string, unknown> {
+export function styleToWasm(style: FontStyleOptions): Record<string, unknown> {
   return {
     family: style.family,
     weight: style.weight,
@@ -142,6 +600,7 @@ function convertShapedGlyph(glyph: {
   y_offset: number;
   start: number;
   end: number;
+  baseline_shift?: number;
 }): ShapedGlyph {
   return {
     glyphId: glyph.glyph_id,
@@ -154,9 +613,141 @@ function convertShapedGlyph(glyph: {
     yOffset: glyph.y_offset,
     start: glyph.start,
     end: glyph.end,
+    baselineShift: glyph.baseline_shift ?? 0,
   };
 }
 
+type RawShapedGlyph = Parameters<typeof convertShapedGlyph>[0];
+
+interface RawLayoutResult {
+  lines: Array<{
+    glyphs: RawShapedGlyph[];
+    width: number;
+    width_with_trailing_whitespace: number;
+    y: number;
+    line_height: number;
+    ascent: number;
+    descent: number;
+    draw_runs: Array<{
+      cosmic_font_id: number;
+      color?: number | null;
+      glyph_start: number;
+      glyph_end: number;
+    }>;
+    baseline_shift: number;
+    hyphenated: boolean;
+    break_reason?: BreakReason | null;
+    break_offset?: number | null;
+    glyph_ink_bounds: RawInkBounds[];
+    ink_bounds: RawInkBounds;
+  }>;
+  total_width: number;
+  total_height: number;
+  clipped_lines: boolean;
+  shadow_runs: Array<{
+    line: number;
+    cosmic_font_id: number;
+    color: number;
+    glyph_start: number;
+    glyph_end: number;
+    x: number;
+    y: number;
+  }>;
+  rejected_breaks?: number[];
+}
+
+function convertLayoutResult(result: RawLayoutResult): LayoutResult {
+  return {
+    lines: result.lines.map((line) => ({
+      glyphs: line.glyphs.map(convertShapedGlyph),
+      width: line.width,
+      widthWithTrailingWhitespace: line.width_with_trailing_whitespace,
+      y: line.y,
+      lineHeight: line.line_height,
+      ascent: line.ascent,
+      descent: line.descent,
+      drawRuns: line.draw_runs.map((run) => ({
+        cosmicFontId: run.cosmic_font_id,
+        color: run.color ?? null,
+        glyphStart: run.glyph_start,
+        glyphEnd: run.glyph_end,
+      })),
+      baselineShift: line.baseline_shift,
+      hyphenated: line.hyphenated,
+      breakReason: line.break_reason ?? null,
+      breakOffset: line.break_offset ?? null,
+      glyphInkBounds: line.glyph_ink_bounds.map(convertInkBounds),
+      inkBounds: convertInkBounds(line.ink_bounds),
+    })),
+    totalWidth: result.total_width,
+    totalHeight: result.total_height,
+    clippedLines: result.clipped_lines,
+    shadowRuns: result.shadow_runs.map((run) => ({
+      line: run.line,
+      cosmicFontId: run.cosmic_font_id,
+      color: run.color,
+      glyphStart: run.glyph_start,
+      glyphEnd: run.glyph_end,
+      x: run.x,
+      y: run.y,
+    })),
+    rejectedBreaks: result.rejected_breaks ?? [],
+  };
+}
+
+export function layoutOptionsToWasm(options: LayoutOptions): Record<string, unknown> {
+  // Only send set fields; serde rejects an explicit undefined for a
+  // non-optional field even when the struct has defaults
+  const wasmOptions: Record<string, unknown> = {};
+  if (options.drawRuns !== undefined) {
+    wasmOptions.draw_runs = options.drawRuns;
+  }
+  if (options.inkBounds !== undefined) {
+    wasmOptions.ink_bounds = options.inkBounds;
+  }
+  if (options.baselineGrid !== undefined) {
+    wasmOptions.baseline_grid = options.baselineGrid;
+  }
+  if (options.hyphenate !== undefined) {
+    wasmOptions.hyphenate = options.hyphenate;
+  }
+  if (options.language !== undefined) {
+    wasmOptions.language = options.language;
+  }
+  if (options.whiteSpace !== undefined) {
+    wasmOptions.white_space = options.whiteSpace;
+  }
+  if (options.textCase !== undefined) {
+    wasmOptions.text_case = options.textCase;
+  }
+  if (options.shadows !== undefined) {
+    wasmOptions.shadows = options.shadows.map((shadow) => ({
+      offset_x: shadow.offsetX,
+      offset_y: shadow.offsetY,
+      color: shadow.color,
+    }));
+  }
+  if (options.wrap !== undefined) {
+    wasmOptions.wrap = options.wrap;
+  }
+  if (options.maxLines !== undefined) {
+    wasmOptions.max_lines = options.maxLines;
+  }
+  if (options.breakCandidates !== undefined) {
+    wasmOptions.break_candidates = options.breakCandidates;
+  }
+  if (options.justify !== undefined) {
+    wasmOptions.justify = options.justify;
+  }
+  if (options.justifyDistribution !== undefined) {
+    wasmOptions.justify_distribution = options.justifyDistribution;
+  }
+  if (options.maxInterWordStretch !== undefined) {
+    wasmOptions.max_inter_word_stretch = options.maxInterWordStretch;
+  }
+  return wasmOptions;
+}
+
 /**
  * High-level text shaper wrapping the WASM implementation.
  */
@@ -170,8 +761,10 @@ export class TextShaper {
   }
 
   /**
-   * Register a font from raw font data (TTF/OTF bytes). Returns a FontId
-   * that can be used to reference this font.
+   * Register a font from raw font data: TTF/OTF bytes, or a WOFF or WOFF2
+   * file, which is unpacked first. Returns a FontId that can be used to
+   * reference this font. Throws an Error with a FontDataErrorCode code for a
+   * WOFF or WOFF2 file that cannot be unpacked.
    */
   registerFont(fontData: Uint8Array): FontId {
     return this.inner.register_font(fontData);
@@ -187,6 +780,24 @@ export class TextShaper {
     return this.inner.register_font_with_name(name, fontData);
   }
 
+  /**
+   * Register several fonts at once. Returns an entry per font, in order, with
+   * its id and metadata; a font that fails to load gets an entry with only an
+   * error, and the rest of the batch still registers.
+   */
+  registerFontsBatch(fonts: Uint8Array[]): RegisteredFont[] {
+    const result = this.inner.register_fonts_batch(fonts) as RawRegisteredFont[];
+    return result.map(convertRegisteredFont);
+  }
+
+  /**
+   * The registered font id that shaping would pick for a family at a weight
+   * and style, or null if no registered font belongs to the family.
+   */
+  findFont(family: string, weight = 400, style: FontStyle = "normal"): number | null {
+    return this.inner.find_font(family, weight, style) ?? null;
+  }
+
   /**
    * Get the internal font info for a registered name. Returns the internal
    * family name and weight if found, or null if not found.
@@ -202,9 +813,37 @@ export class TextShaper {
     return this.inner.font_count();
   }
 
+  /**
+   * Set the fonts to try, in order, for characters the requested family does
+   * not cover. Styles without a family use the first font in the chain. An
+   * empty chain restores the platform fallback lists.
+   */
+  setFallbackChain(fontIds: FontId[]): void {
+    this.inner.set_fallback_chain(Uint32Array.from(fontIds, (fontId) => fontId.id));
+  }
+
+  /**
+   * Load Knuth-Liang hyphenation patterns for a language, replacing any
+   * registered for it before. The data is a UTF-8 pattern file, either
+   * whitespace-separated patterns or TeX \patterns{} and \hyphenation{}
+   * blocks.
+   */
+  registerHyphenationPatterns(lang: string, patternsData: Uint8Array): void {
+    this.inner.register_hyphenation_patterns(lang, patternsData);
+  }
+
+  /**
+   * The registered font id that shaping would use for a character, or null
+   * if no registered font covers it. Useful for pre-warming glyph atlases.
+   */
+  effectiveFontFor(char: string, style: FontStyleOptions = {}): number | null {
+    return this.inner.effective_font_for(char, styleToWasm(style)) ?? null;
+  }
+
   /**
    * Shape a single line of text. Returns shaped glyphs with positioning
-   * information.
+   * information. Throws an Error with a MetricsErrorCode code for unusable
+   * sizes, as do the other layout and measure calls.
    */
   shapeLine(
     text: string,
@@ -226,17 +865,21 @@ export class TextShaper {
         end: number;
       }>;
       width: number;
+      width_with_trailing_whitespace: number;
       height: number;
       ascent: number;
       descent: number;
+      clipped_lines: boolean;
     };
 
     return {
       glyphs: result.glyphs.map(convertShapedGlyph),
       width: result.width,
+      widthWithTrailingWhitespace: result.width_with_trailing_whitespace,
       height: result.height,
       ascent: result.ascent,
       descent: result.descent,
+      clippedLines: result.clipped_lines,
     };
   }
 
@@ -248,46 +891,56 @@ export class TextShaper {
     fontSize: number,
     lineHeight: number,
     maxWidth: number,
-    style: FontStyleOptions = {}
+    style: FontStyleOptions = {},
+    options: LayoutOptions = {}
   ): LayoutResult {
     const result = this.inner.layout_text(
       text,
       fontSize,
       lineHeight,
       maxWidth,
-      styleToWasm(style)
-    ) as {
-      lines: Array<{
-        glyphs: Array<{
-          glyph_id: number;
-          cosmic_font_id: number;
-          x: number;
-          y: number;
-          x_advance: number;
-          y_advance: number;
-          x_offset: number;
-          y_offset: number;
-          start: number;
-          end: number;
-        }>;
-        width: number;
-        y: number;
-        line_height: number;
-      }>;
-      total_width: number;
-      total_height: number;
-    };
+      styleToWasm(style),
+      layoutOptionsToWasm(options)
+    ) as RawLayoutResult;
+    return convertLayoutResult(result);
+  }
 
-    return {
-      lines: result.lines.map((line) => ({
-        glyphs: line.glyphs.map(convertShapedGlyph),
-        width: line.width,
-        y: line.y,
-        lineHeight: line.line_height,
-      })),
-      totalWidth: result.total_width,
-      totalHeight: result.total_height,
-    };
+  /**
+   * Layout multi-line text made of spans with their own styles and colors,
+   * wrapping across span boundaries. Throws in builds without the rich-text
+   * feature.
+   */
+  layoutRichText(
+    spans: RichTextSpan[],
+    fontSize: number,
+    lineHeight: number,
+    maxWidth: number,
+    options: LayoutOptions = {}
+  ): LayoutResult {
+    const wasmSpans = spans.map((span) => {
+      const wasmSpan: Record<string, unknown> = {
+        text: span.text,
+        style: styleToWasm(span.style ?? {}),
+      };
+      if (span.color !== undefined) {
+        wasmSpan.color = span.color;
+      }
+      if (span.baselineShift !== undefined) {
+        wasmSpan.baseline_shift = span.baselineShift;
+      }
+      if (span.fontScale !== undefined) {
+        wasmSpan.font_scale = span.fontScale;
+      }
+      return wasmSpan;
+    });
+    const result = this.inner.layout_rich_text(
+      wasmSpans,
+      fontSize,
+      lineHeight,
+      maxWidth,
+      layoutOptionsToWasm(options)
+    ) as RawLayoutResult;
+    return convertLayoutResult(result);
   }
 
   /**
@@ -306,27 +959,97 @@ export class TextShaper {
       lineHeight,
       maxWidth ?? null,
       styleToWasm(style)
-    ) as { width: number; height: number };
+    ) as { width: number; height: number; clipped_lines: boolean };
 
     return {
       width: result.width,
       height: result.height,
+      clippedLines: result.clipped_lines,
+    };
+  }
+
+  /**
+   * Lay out a single line of text along an SVG path. Each cluster is
+   * centered at its arc-length position and rotated to follow the path.
+   */
+  layoutTextOnPath(
+    text: string,
+    fontSize: number,
+    pathD: string,
+    options: TextPathOptions = {},
+    style: FontStyleOptions = {}
+  ): TextOnPathResult {
+    // Only send set fields; serde rejects an explicit undefined for a
+    // non-optional field even when the struct has defaults
+    const wasmOptions: Record<string, unknown> = {};
+    if (options.startOffset !== undefined) {
+      wasmOptions.start_offset = options.startOffset;
+    }
+    if (options.spacingMode !== undefined) {
+      wasmOptions.spacing_mode = options.spacingMode;
+    }
+    if (options.clipOverflow !== undefined) {
+      wasmOptions.clip_overflow = options.clipOverflow;
+    }
+
+    const result = this.inner.layout_text_on_path(
+      text,
+      fontSize,
+      styleToWasm(style),
+      pathD,
+      wasmOptions
+    ) as {
+      glyphs: Array<{
+        glyph_id: number;
+        cosmic_font_id: number;
+        x: number;
+        y: number;
+        rotation_rad: number;
+        start: number;
+        end: number;
+        overflow: boolean;
+      }>;
+      path_length: number;
+      overflow_clusters: number;
+    };
+
+    return {
+      glyphs: result.glyphs.map((glyph) => ({
+        glyphId: glyph.glyph_id,
+        cosmicFontId: glyph.cosmic_font_id,
+        x: glyph.x,
+        y: glyph.y,
+        rotationRad: glyph.rotation_rad,
+        start: glyph.start,
+        end: glyph.end,
+        overflow: glyph.overflow,
+      })),
+      pathLength: result.path_length,
+      overflowClusters: result.overflow_clusters,
     };
   }
 
   /**
    * Rasterize a glyph at the given font size and weight. Weight parameter is
    * used for variable fonts (e.g., 400 for regular, 700 for bold). Returns
-   * the rasterized glyph with alpha coverage values.
+   * the rasterized glyph encoded as `options` chooses, or null in builds
+   * without the raster feature.
    */
   rasterizeGlyph(
     fontId: FontId,
     glyphId: number,
     fontSize: number,
-    weight?: number
+    weight?: number,
+    options: RasterOptions = {}
   ): RasterizedGlyph | null {
     try {
-      const result = this.inner.rasterize_glyph(fontId.id, glyphId, fontSize, weight) as {
+      const result = this.inner.rasterize_glyph(
+        fontId.id,
+        glyphId,
+        fontSize,
+        weight,
+        rasterOptionsToWasm(options)
+      ) as {
         width: number;
         height: number;
         bearing_x: number;
@@ -352,13 +1075,15 @@ export class TextShaper {
   /**
    * Rasterize a glyph using cosmic-text's internal font ID. Used when shaping
    * falls back to a different font than requested. Weight parameter is used
-   * for variable fonts (e.g., 400 for regular, 700 for bold).
+   * for variable fonts (e.g., 400 for regular, 700 for bold). Returns null in
+   * builds without the raster feature.
    */
   rasterizeGlyphByCosmicId(
     cosmicFontId: number,
     glyphId: number,
     fontSize: number,
-    weight?: number
+    weight?: number,
+    options: RasterOptions = {}
   ): RasterizedGlyph | null {
     try {
       // WASM expects BigInt for u64 parameters
@@ -366,7 +1091,8 @@ export class TextShaper {
         BigInt(cosmicFontId),
         glyphId,
         fontSize,
-        weight
+        weight,
+        rasterOptionsToWasm(options)
       ) as {
         width: number;
         height: number;
@@ -392,9 +1118,205 @@ export class TextShaper {
   }
 
   /**
-   * Clear cached data to free memory.
+   * Ink extents of one glyph relative to its origin on the baseline, or null
+   * for a glyph that paints nothing. Use for visual centering, where advance
+   * metrics include side bearings and line spacing.
+   */
+  glyphInkBounds(cosmicFontId: number, glyphId: number, fontSize: number): InkBounds | null {
+    return convertInkBounds(
+      this.inner.glyph_ink_bounds(BigInt(cosmicFontId), glyphId, fontSize) as RawInkBounds
+    );
+  }
+
+  /**
+   * Ink extents of a shaped string relative to its first baseline, or null
+   * when nothing is painted. Overhanging glyphs can reach past measureText.
+   */
+  inkBounds(text: string, fontSize: number, style: FontStyleOptions = {}): InkBounds | null {
+    return convertInkBounds(
+      this.inner.ink_bounds(text, fontSize, styleToWasm(style)) as RawInkBounds
+    );
+  }
+
+  /**
+   * Shape a line like shapeLine and find the glyphs that show when it is
+   * scrolled left by scrollX in a viewport viewportWidth wide, for
+   * single-line text fields.
+   */
+  visibleGlyphRange(
+    text: string,
+    fontSize: number,
+    lineHeight: number,
+    scrollX: number,
+    viewportWidth: number,
+    style: FontStyleOptions = {}
+  ): VisibleGlyphRange {
+    type RawClippedGlyph = { index: number; visible_start: number; visible_end: number };
+    const range = this.inner.visible_glyph_range(
+      text,
+      fontSize,
+      lineHeight,
+      styleToWasm(style),
+      scrollX,
+      viewportWidth
+    ) as {
+      start: number;
+      end: number;
+      glyphs: number[];
+      leading?: RawClippedGlyph;
+      trailing?: RawClippedGlyph;
+    };
+    const convert = (glyph?: RawClippedGlyph): ClippedGlyph | null =>
+      glyph
+        ? { index: glyph.index, visibleStart: glyph.visible_start, visibleEnd: glyph.visible_end }
+        : null;
+    return {
+      start: range.start,
+      end: range.end,
+      glyphs: range.glyphs,
+      leading: convert(range.leading),
+      trailing: convert(range.trailing),
+    };
+  }
+
+  /**
+   * Lay out text like layoutText and return a box per word for focus and
+   * spoken-word highlights. A word wrapped across lines gets one box per
+   * line; whitespace gets none.
+   */
+  wordBoxes(
+    text: string,
+    fontSize: number,
+    lineHeight: number,
+    maxWidth: number,
+    style: FontStyleOptions = {}
+  ): WordBox[] {
+    const boxes = this.inner.word_boxes(
+      text,
+      fontSize,
+      lineHeight,
+      maxWidth,
+      styleToWasm(style)
+    ) as Array<{
+      byte_start: number;
+      byte_end: number;
+      x: number;
+      y: number;
+      width: number;
+      height: number;
+      line_index: number;
+    }>;
+    return boxes.map((box) => ({
+      byteStart: box.byte_start,
+      byteEnd: box.byte_end,
+      x: box.x,
+      y: box.y,
+      width: box.width,
+      height: box.height,
+      lineIndex: box.line_index,
+    }));
+  }
+
+  /**
+   * Clear cached data to free memory. Drop the glyph atlas as well, since no
+   * evictions are reported for a full clear.
    */
   clearCache(): void {
     this.inner.clear_cache();
   }
+
+  /**
+   * Set the font size quantization step in pixels. Sizes that round to the
+   * same step share shaped lines and rasterized glyphs; 0 disables
+   * bucketing. Clears the caches.
+   */
+  setSizeBucket(step: number): void {
+    this.inner.set_size_bucket(step);
+  }
+
+  /**
+   * Limit the combined size of the shaped-line and glyph caches, or pass
+   * null to remove the limit. Returns the glyphs evicted to fit, least
+   * recently used first, so their atlas slots can be repacked.
+   */
+  setCacheBudget(bytes: number | null): EvictedGlyph[] {
+    return convertEvictedGlyphs(this.inner.set_cache_budget(bytes ?? undefined));
+  }
+
+  /**
+   * Collect glyphs evicted by the cache budget since the last call.
+   */
+  takeEvictedGlyphs(): EvictedGlyph[] {
+    return convertEvictedGlyphs(this.inner.take_evicted_glyphs());
+  }
+
+  /**
+   * A 64-bit hash of a text input as 16 hex digits, for memoizing shaping
+   * and layout results. Covers the text, font size and style; key line
+   * height, width and options alongside it, and drop memoized results when
+   * fonts are registered.
+   */
+  hashTextInput(text: string, fontSize: number, style: FontStyleOptions = {}): string {
+    return this.inner.hash_text_input(text, fontSize, styleToWasm(style));
+  }
+
+  /**
+   * Transform text to upper, lower or title case by the rules of lang, a BCP
+   * 47 tag, mapping each byte range of the result back to the original so
+   * selections can move between them.
+   */
+  transformTextCase(text: string, mode: TextCase, lang?: string): CaseTransform {
+    const raw = this.inner.transform_text_case(text, mode, lang) as {
+      text: string;
+      mappings: Array<{
+        original_start: number;
+        original_end: number;
+        transformed_start: number;
+        transformed_end: number;
+        one_to_one: boolean;
+      }>;
+    };
+    return {
+      text: raw.text,
+      mappings: raw.mappings.map((mapping) => ({
+        originalStart: mapping.original_start,
+        originalEnd: mapping.original_end,
+        transformedStart: mapping.transformed_start,
+        transformedEnd: mapping.transformed_end,
+        oneToOne: mapping.one_to_one,
+      })),
+    };
+  }
+
+  /**
+   * Report cache occupancy, hit rates and evictions.
+   */
+  cacheStats(): CacheStats {
+    const raw = this.inner.cache_stats() as {
+      shaped_lines: number;
+      shaped_line_bytes: number;
+      glyphs: number;
+      glyph_bytes: number;
+      budget_bytes?: number | null;
+      shape_hits: number;
+      shape_misses: number;
+      glyph_hits: number;
+      rasterizations: number;
+      evictions: number;
+      raster_faces: number;
+    };
+    return {
+      shapedLines: raw.shaped_lines,
+      shapedLineBytes: raw.shaped_line_bytes,
+      glyphs: raw.glyphs,
+      glyphBytes: raw.glyph_bytes,
+      budgetBytes: raw.budget_bytes ?? null,
+      shapeHits: raw.shape_hits,
+      shapeMisses: raw.shape_misses,
+      glyphHits: raw.glyph_hits,
+      rasterizations: raw.rasterizations,
+      evictions: raw.evictions,
+      rasterFaces: raw.raster_faces,
+    };
+  }
 }
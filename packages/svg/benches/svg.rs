@@ -0,0 +1,265 @@
+//! SVG tessellation benchmarks against a 60fps frame budget.
+//!
+//! Run with `cargo bench -p svg`. The icon is the repo's gear asset; the
+//! illustration and map tile are generated so their complexity is explicit.
+//! Allocation counts are written to `bench.baseline.json` at the workspace
+//! root.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use criterion::{criterion_group, BenchmarkId, Criterion};
+    use serde_json::{json, Value};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::fmt::Write;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use svg::{DisplaySize, SvgTessellator, TessellateOptions};
+
+    /// Counts allocations so the arena's effect is visible next to timings.
+    struct CountingAlloc;
+
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    fn allocations_per_call(mut f: impl FnMut()) -> usize {
+        // Warm up so retained scratch capacity is not counted
+        f();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        f();
+        ALLOCATIONS.load(Ordering::Relaxed) - before
+    }
+
+    /// Writes an allocation count into the workspace's `bench.baseline.json`,
+    /// keeping the entries other benches recorded there.
+    fn record_allocations(id: &str, allocations: usize) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../bench.baseline.json");
+        let mut baseline: Value = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_else(|| json!({}));
+        if !baseline["allocations"].is_object() {
+            baseline["allocations"] = json!({});
+        }
+        baseline["allocations"][id] = json!(allocations);
+        let text = serde_json::to_string_pretty(&baseline).unwrap();
+        fs::write(&path, text + "\n").unwrap();
+    }
+
+    const ICON: &str = include_str!("../../../assets/gear.svg");
+
+    /// A grid of `count` filled and stroked curved paths.
+    fn many_paths(count: usize) -> String {
+        let mut svg = String::from(r#"<svg width="100" height="100" viewBox="0 0 100 100">"#);
+        for i in 0..count {
+            let x = (i % 10) as f32 * 10.0;
+            let y = (i / 10) as f32 * 5.0;
+            let _ = write!(
+                svg,
+                r##"<path d="M{x},{y} c4,-2 8,2 8,4 s-4,4 -8,4 l-1,-4 z" fill="#000" stroke="#f00" stroke-width="0.5"/>"##,
+            );
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Overlapping filled shapes with curves and outlines, like a spot
+    /// illustration.
+    fn illustration() -> String {
+        let mut svg = String::from(r#"<svg width="400" height="300" viewBox="0 0 400 300">"#);
+        for i in 0..120 {
+            let x = (i * 37 % 360) as f32 + 20.0;
+            let y = (i * 53 % 260) as f32 + 20.0;
+            let r = 6.0 + (i % 7) as f32 * 3.0;
+            let _ = write!(
+                svg,
+                r##"<path d="M{x} {y} C{} {} {} {} {} {} Q{} {} {x} {y} Z" fill="#3a6" stroke="#123" stroke-width="1.5"/>"##,
+                x + r * 2.0,
+                y - r,
+                x + r * 3.0,
+                y + r,
+                x + r,
+                y + r * 2.0,
+                x - r,
+                y + r,
+            );
+            let _ = write!(svg, r##"<circle cx="{x}" cy="{y}" r="{r}" fill="#fc3"/>"##);
+            let _ = write!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{r}" height="{r}" rx="2" fill="#e55"/>"##,
+                x - r,
+                y - r,
+            );
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Stroked road polylines over filled blocks, like a vector map tile.
+    fn map_tile() -> String {
+        let mut svg = String::from(r#"<svg width="256" height="256" viewBox="0 0 256 256">"#);
+        for block in 0..64 {
+            let x = (block % 8) as f32 * 32.0 + 2.0;
+            let y = (block / 8) as f32 * 32.0 + 2.0;
+            let _ = write!(
+                svg,
+                r##"<polygon points="{x},{y} {},{y} {},{} {x},{}" fill="#ddd"/>"##,
+                x + 26.0,
+                x + 28.0,
+                y + 27.0,
+                y + 26.0,
+            );
+        }
+        for road in 0..200 {
+            let mut d = format!("M{} 0", road as f32 * 1.28);
+            for step in 1..=16 {
+                let x = road as f32 * 1.28 + ((road + step) % 5) as f32 * 2.0;
+                let _ = write!(d, " L{x} {}", step as f32 * 16.0);
+            }
+            let _ = write!(
+                svg,
+                r##"<path d="{d}" fill="none" stroke="#fff" stroke-width="{}"/>"##,
+                1.0 + (road % 3) as f32,
+            );
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    fn bench_tessellate(c: &mut Criterion) {
+        let documents = [
+            ("icon", ICON.to_string(), 24.0),
+            ("illustration", illustration(), 400.0),
+            ("map_tile", map_tile(), 256.0),
+        ];
+
+        let mut tessellator = SvgTessellator::new();
+        let mut group = c.benchmark_group("tessellate_svg");
+        for (name, svg, size) in &documents {
+            group.bench_with_input(BenchmarkId::from_parameter(name), svg, |b, svg| {
+                b.iter(|| tessellator.tessellate_svg_meshes(svg, *size, *size))
+            });
+        }
+        group.finish();
+    }
+
+    /// Re-tessellating one document at a changing size, as during a zoom.
+    fn bench_retessellate(c: &mut Criterion) {
+        let svg = many_paths(200);
+        let mut tessellator = SvgTessellator::new();
+        let mut size = 16.0;
+        let mut next_size = move || {
+            size = if size >= 256.0 { 16.0 } else { size + 8.0 };
+            size
+        };
+
+        let allocations = allocations_per_call(|| {
+            let size = next_size();
+            tessellator.tessellate_svg_meshes(&svg, size, size);
+        });
+        record_allocations("retessellate/200_paths", allocations);
+
+        let mut group = c.benchmark_group("retessellate");
+        group.bench_function("200_paths", |b| {
+            b.iter(|| {
+                let size = next_size();
+                tessellator.tessellate_svg_meshes(&svg, size, size)
+            })
+        });
+        group.finish();
+    }
+
+    /// Six icon sizes tessellated with `tessellate_svg_lod_meshes`, which
+    /// parses once, versus one `tessellate_svg_document` call per size.
+    fn bench_lods(c: &mut Criterion) {
+        let svg = many_paths(100);
+        let sizes: Vec<DisplaySize> = [16.0, 24.0, 32.0, 48.0, 64.0, 128.0]
+            .into_iter()
+            .map(|size| DisplaySize {
+                width: size,
+                height: size,
+            })
+            .collect();
+
+        let options = TessellateOptions::default();
+
+        let mut tessellator = SvgTessellator::new();
+        let mut group = c.benchmark_group("lods_6_sizes");
+        group.bench_function("individual", |b| {
+            b.iter(|| {
+                for size in &sizes {
+                    tessellator
+                        .tessellate_svg_document(&svg, size.width, size.height, &options)
+                        .unwrap();
+                }
+            })
+        });
+        group.bench_function("combined", |b| {
+            b.iter(|| {
+                tessellator
+                    .tessellate_svg_lod_meshes(&svg, &sizes, &options)
+                    .unwrap()
+            })
+        });
+        group.finish();
+    }
+
+    /// A 50k-point chart line stroked from a flat point array versus from
+    /// the equivalent `d` string. The string is built outside the timed loop,
+    /// so only parsing is charged to it, not formatting.
+    fn bench_polyline(c: &mut Criterion) {
+        let points: Vec<f32> = (0..50_000)
+            .flat_map(|i| {
+                let x = i as f32 * 0.02;
+                [x, 100.0 + (x * 3.0).sin() * 80.0]
+            })
+            .collect();
+        let mut d = String::with_capacity(points.len() * 8);
+        for (i, pair) in points.chunks_exact(2).enumerate() {
+            let command = if i == 0 { 'M' } else { 'L' };
+            let _ = write!(d, "{command}{} {} ", pair[0], pair[1]);
+        }
+
+        let mut tessellator = SvgTessellator::new();
+        let mut group = c.benchmark_group("polyline_50k");
+        group.bench_function("points", |b| {
+            b.iter(|| tessellator.tessellate_points_native(&points, false, Some(1.5)))
+        });
+        group.bench_function("path_d", |b| {
+            b.iter(|| tessellator.tessellate_stroke_native(&d, 1.5, 0.0, 0.0, 1.0, 1.0))
+        });
+        group.finish();
+    }
+
+    criterion_group!(
+        benches,
+        bench_tessellate,
+        bench_retessellate,
+        bench_lods,
+        bench_polyline
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(native::benches);
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
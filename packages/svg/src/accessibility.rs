@@ -0,0 +1,185 @@
+//! What screen readers need to label a rendered SVG: the document's `title`
+//! and `desc`, the root element's ARIA attributes, and per-shape labels.
+//!
+//! Text is read by following the document's tags as they are tokenized
+//! rather than matching one element, so a `title` inside a shape or group,
+//! or the `dc:title` in Inkscape's metadata, is not taken for the
+//! document's. Character
+//! references such as `&amp;` and `&#233;` are decoded, and runs of
+//! whitespace collapse to single spaces as they do in an accessible name.
+
+use crate::{extract_attr, ElementTags};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Accessibility metadata of a document. Fields the document does not set,
+/// or sets to only whitespace, are None.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SvgAccessibility {
+    /// Text of the root element's first `title` child
+    pub title: Option<String>,
+    /// Text of the root element's first `desc` child
+    pub desc: Option<String>,
+    /// The root element's `role`, such as `img`
+    pub role: Option<String>,
+    /// The root element's `aria-label`
+    pub aria_label: Option<String>,
+    /// Whether the root element has `aria-hidden="true"`, marking the image
+    /// as decorative
+    pub aria_hidden: bool,
+    /// Shapes with an `id` or `aria-label`, in path order
+    pub paths: Vec<PathAccessibility>,
+}
+
+/// Labels of one shape.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PathAccessibility {
+    /// Index of the shape in `ParsedSvg::paths`
+    pub path: usize,
+    pub id: Option<String>,
+    pub aria_label: Option<String>,
+}
+
+impl PathAccessibility {
+    /// Labels of the shape with start tag `tag`, if it has any.
+    pub(crate) fn read(tag: &str, path: usize) -> Option<Self> {
+        let id = attribute(tag, "id");
+        let aria_label = attribute(tag, "aria-label");
+        if id.is_none() && aria_label.is_none() {
+            return None;
+        }
+        Some(Self {
+            path,
+            id,
+            aria_label,
+        })
+    }
+}
+
+/// Reads document-level metadata from the document's tokens in order,
+/// leaving `paths` empty.
+#[derive(Default)]
+pub(crate) struct DocumentLabels {
+    result: SvgAccessibility,
+    depth: usize,
+    seen_root: bool,
+    /// The field being read and its text so far
+    capture: Option<(&'static str, String)>,
+}
+
+impl DocumentLabels {
+    /// Text between tags, with its character references still written out.
+    pub(crate) fn text(&mut self, text: &str) {
+        if let Some((_, captured)) = self.capture.as_mut() {
+            captured.push_str(&decode_entities(text));
+        }
+    }
+
+    /// The contents of a CDATA section, which are taken as written.
+    pub(crate) fn cdata(&mut self, text: &str) {
+        if let Some((_, captured)) = self.capture.as_mut() {
+            captured.push_str(text);
+        }
+    }
+
+    /// A start tag, with the element name as written.
+    pub(crate) fn start_tag(&mut self, name: &str, tag: &str, tags: &ElementTags) {
+        let self_closing = tag.ends_with("/>");
+        if !self.seen_root {
+            self.seen_root = true;
+            self.result.role = attribute(tag, "role");
+            self.result.aria_label = attribute(tag, "aria-label");
+            self.result.aria_hidden = attribute(tag, "aria-hidden")
+                .is_some_and(|value| value.eq_ignore_ascii_case("true"));
+        } else if self.depth == 1 && self.capture.is_none() && !self_closing {
+            self.capture = ["title", "desc"]
+                .into_iter()
+                .find(|field| tags.is_svg_element(name, field))
+                .map(|field| (field, String::new()));
+        }
+        if !self_closing {
+            self.depth += 1;
+        }
+    }
+
+    pub(crate) fn end_tag(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        let finished = if self.depth == 1 {
+            self.capture.take()
+        } else {
+            None
+        };
+        if let Some((field, text)) = finished {
+            let slot = if field == "title" {
+                &mut self.result.title
+            } else {
+                &mut self.result.desc
+            };
+            *slot = slot.take().or_else(|| normalized(&text));
+        }
+    }
+
+    pub(crate) fn finish(self) -> SvgAccessibility {
+        self.result
+    }
+}
+
+/// An attribute of a start tag, decoded and normalized.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    normalized(&extract_attr(tag, name)?)
+}
+
+/// `text` with whitespace runs collapsed and trimmed, or None when empty.
+fn normalized(text: &str) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Replace XML's predefined entities and numeric character references.
+/// Anything else, such as entities a DTD would declare, is left as written.
+pub(crate) fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest[1..]
+            .find(';')
+            .and_then(|end| Some((end + 2, entity_char(&rest[1..end + 1])?)));
+        match reference {
+            Some((len, c)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// The character an entity name, without `&` and `;`, stands for.
+fn entity_char(name: &str) -> Option<char> {
+    let code = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        u32::from_str_radix(hex, 16).ok()?
+    } else if let Some(decimal) = name.strip_prefix('#') {
+        decimal.parse().ok()?
+    } else {
+        return match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => None,
+        };
+    };
+    char::from_u32(code)
+}
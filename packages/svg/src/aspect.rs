@@ -0,0 +1,115 @@
+//! `preserveAspectRatio`: how a document whose native size has a different
+//! aspect ratio than the display is fit into it.
+//!
+//! With an alignment, the document is scaled by the same factor on both
+//! axes, the smaller of the two so it fits (`meet`) or the larger so it
+//! covers the display (`slice`), then aligned to the display's start,
+//! middle or end on each axis. `none` stretches each axis on its own.
+
+use serde::{Deserialize, Serialize};
+
+/// Where a uniformly scaled document sits along one axis of the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AspectAlign {
+    Min,
+    Mid,
+    Max,
+}
+
+impl AspectAlign {
+    /// How much of the room left over along the axis goes before the
+    /// document.
+    fn share(self) -> f32 {
+        match self {
+            AspectAlign::Min => 0.0,
+            AspectAlign::Mid => 0.5,
+            AspectAlign::Max => 1.0,
+        }
+    }
+}
+
+/// A document's `preserveAspectRatio`. The default is `xMidYMid meet`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PreserveAspectRatio {
+    /// The x and y alignment, or None for `none`, which stretches
+    pub align: Option<[AspectAlign; 2]>,
+    /// Whether the document covers the display rather than fitting in it
+    pub slice: bool,
+}
+
+impl Default for PreserveAspectRatio {
+    fn default() -> Self {
+        Self {
+            align: Some([AspectAlign::Mid, AspectAlign::Mid]),
+            slice: false,
+        }
+    }
+}
+
+impl PreserveAspectRatio {
+    /// Stretch each axis on its own, as `none` does.
+    #[cfg(feature = "patterns")]
+    pub(crate) const STRETCH: Self = Self {
+        align: None,
+        slice: false,
+    };
+
+    /// Parse an attribute value such as `xMinYMax slice`. Anything else,
+    /// including a leading `defer`, which only applies to images, reads as
+    /// the default, as SVG treats an invalid value.
+    pub(crate) fn parse(value: &str) -> Self {
+        let mut words = value.split_ascii_whitespace();
+        let align = match words.next() {
+            Some("none") => None,
+            Some(word) => match parse_align(word) {
+                Some(align) => Some(align),
+                None => return Self::default(),
+            },
+            None => return Self::default(),
+        };
+        let slice = match words.next() {
+            None | Some("meet") => false,
+            Some("slice") => true,
+            Some(_) => return Self::default(),
+        };
+        if words.next().is_some() {
+            return Self::default();
+        }
+        Self { align, slice }
+    }
+
+    /// The scale on each axis and the offset of the document's origin, in
+    /// display units, that fit a document `native` units in size into
+    /// `display`.
+    pub(crate) fn place(&self, native: (f32, f32), display: (f32, f32)) -> [f32; 4] {
+        let scale_x = display.0 / native.0;
+        let scale_y = display.1 / native.1;
+        let Some([align_x, align_y]) = self.align else {
+            return [scale_x, scale_y, 0.0, 0.0];
+        };
+        let scale = if self.slice {
+            scale_x.max(scale_y)
+        } else {
+            scale_x.min(scale_y)
+        };
+        [
+            scale,
+            scale,
+            (display.0 - native.0 * scale) * align_x.share(),
+            (display.1 - native.1 * scale) * align_y.share(),
+        ]
+    }
+}
+
+/// The x and y alignment of a value such as `xMidYMax`.
+fn parse_align(word: &str) -> Option<[AspectAlign; 2]> {
+    let axis = |value: &str| match value {
+        "Min" => Some(AspectAlign::Min),
+        "Mid" => Some(AspectAlign::Mid),
+        "Max" => Some(AspectAlign::Max),
+        _ => None,
+    };
+    let (x, y) = word.strip_prefix('x')?.split_once('Y')?;
+    Some([axis(x)?, axis(y)?])
+}
@@ -0,0 +1,77 @@
+//! Boundary edges of triangle meshes, for outline and glow post-effects.
+//!
+//! An edge used by exactly one triangle lies on the mesh's outline. Edges
+//! keep the direction their triangle walks them, so with consistent winding
+//! every outline is a directed cycle and chains without reversing anything.
+
+use std::collections::HashMap;
+
+/// Boundary edges of an indexed triangle list as flat `[from, to, ...]`
+/// vertex index pairs. Edges are chained so each starts where the previous
+/// one ended, and a chain ends when it cannot continue, normally by closing
+/// a loop; a pair whose `from` differs from the previous `to` starts the
+/// next chain. Output order depends only on the indices.
+pub fn boundary_edges(indices: &[u32]) -> Vec<u32> {
+    // Keyed by the sorted vertex pair; the value is a directed edge and how
+    // many triangles share it
+    let mut counts: HashMap<(u32, u32), ((u32, u32), u32)> = HashMap::new();
+    for triangle in indices.chunks_exact(3) {
+        for (from, to) in [
+            (triangle[0], triangle[1]),
+            (triangle[1], triangle[2]),
+            (triangle[2], triangle[0]),
+        ] {
+            let key = (from.min(to), from.max(to));
+            counts.entry(key).or_insert(((from, to), 0)).1 += 1;
+        }
+    }
+    let mut edges: Vec<(u32, u32)> = counts
+        .into_values()
+        .filter(|&(_, count)| count == 1)
+        .map(|(edge, _)| edge)
+        .collect();
+    edges.sort_unstable();
+
+    let mut by_vertex: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (index, &(from, to)) in edges.iter().enumerate() {
+        by_vertex.entry(from).or_default().push(index);
+        by_vertex.entry(to).or_default().push(index);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut chained = Vec::with_capacity(edges.len() * 2);
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let (start, mut end) = edges[first];
+        chained.extend([start, end]);
+        while end != start {
+            let Some(next) = next_edge(&edges, &by_vertex, &used, end) else {
+                break;
+            };
+            used[next] = true;
+            // Walk an edge backwards when mixed winding left it reversed
+            let (from, to) = edges[next];
+            let (from, to) = if from == end { (from, to) } else { (to, from) };
+            chained.extend([from, to]);
+            end = to;
+        }
+    }
+    chained
+}
+
+/// An unused edge touching `vertex`, preferring one that leaves it.
+fn next_edge(
+    edges: &[(u32, u32)],
+    by_vertex: &HashMap<u32, Vec<usize>>,
+    used: &[bool],
+    vertex: u32,
+) -> Option<usize> {
+    let candidates = by_vertex.get(&vertex)?;
+    let unused = || candidates.iter().copied().filter(|&index| !used[index]);
+    unused()
+        .find(|&index| edges[index].0 == vertex)
+        .or_else(|| unused().next())
+}
@@ -0,0 +1,143 @@
+//! Per-call scratch storage for the tessellation pipeline.
+//!
+//! Stable Rust cannot route `Vec` allocations through a custom allocator, so
+//! instead of bumping raw pointers the arena lends out buffers that keep their
+//! capacity between calls. Public entry points reset the arena before they
+//! return; outputs handed back to callers are always fresh allocations.
+
+use std::mem;
+
+/// Buffers above this size are candidates for shrinking.
+const MIN_RETAINED_BYTES: usize = 16 * 1024;
+
+/// A reusable buffer lent out with `take` and handed back with `restore`.
+pub(crate) struct ScratchVec<T> {
+    buf: Vec<T>,
+    /// Largest length seen since the last reset.
+    call_peak: usize,
+    /// Decaying maximum of `call_peak` across resets. Capacity follows this
+    /// rather than the last call so alternating large and small documents do
+    /// not reallocate every frame.
+    watermark: usize,
+}
+
+impl<T> ScratchVec<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            call_peak: 0,
+            watermark: 0,
+        }
+    }
+
+    /// Lend out the buffer. It is always empty but may have capacity.
+    pub(crate) fn take(&mut self) -> Vec<T> {
+        mem::take(&mut self.buf)
+    }
+
+    /// Hand a buffer back. With `retain` unset the buffer is dropped, which
+    /// makes every call allocate as if the arena did not exist.
+    pub(crate) fn restore(&mut self, mut buf: Vec<T>, retain: bool) {
+        self.call_peak = self.call_peak.max(buf.len());
+        buf.clear();
+        if retain && buf.capacity() >= self.buf.capacity() {
+            self.buf = buf;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.watermark = self.call_peak.max(self.watermark - self.watermark / 8);
+        self.call_peak = 0;
+
+        let target = self.watermark * 2;
+        if self.capacity_bytes() > MIN_RETAINED_BYTES && self.buf.capacity() > target * 2 {
+            self.buf.shrink_to(target);
+        }
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.buf.capacity() * mem::size_of::<T>()
+    }
+
+    /// Release the buffer's capacity, keeping the size history.
+    fn shrink_to_fit(&mut self) {
+        self.buf = Vec::new();
+    }
+}
+
+/// Scratch buffers for one `SvgTessellator`.
+pub(crate) struct Arena<C, V> {
+    /// When false every buffer is dropped on `restore`. Controlled by the
+    /// `arena` feature so outputs can be compared with and without reuse.
+    pub(crate) retain: bool,
+    /// Token bytes of the path being parsed. Tokens are ASCII.
+    pub(crate) token_text: ScratchVec<u8>,
+    /// Byte ranges of each token in `token_text`.
+    pub(crate) token_spans: ScratchVec<(usize, usize)>,
+    /// Parsed commands of every path in the document, back to back.
+    pub(crate) commands: ScratchVec<C>,
+    /// Range of each path's commands in `commands`.
+    pub(crate) path_ranges: ScratchVec<(usize, usize)>,
+    /// Tessellator output before it is copied into a mesh.
+    pub(crate) vertices: ScratchVec<V>,
+    pub(crate) indices: ScratchVec<u32>,
+    peak_bytes: usize,
+    resets: u64,
+}
+
+impl<C, V> Arena<C, V> {
+    pub(crate) const fn new(retain: bool) -> Self {
+        Self {
+            retain,
+            token_text: ScratchVec::new(),
+            token_spans: ScratchVec::new(),
+            commands: ScratchVec::new(),
+            path_ranges: ScratchVec::new(),
+            vertices: ScratchVec::new(),
+            indices: ScratchVec::new(),
+            peak_bytes: 0,
+            resets: 0,
+        }
+    }
+
+    /// Mark the end of a public call.
+    pub(crate) fn reset(&mut self) {
+        self.peak_bytes = self.peak_bytes.max(self.capacity_bytes());
+        self.token_text.reset();
+        self.token_spans.reset();
+        self.commands.reset();
+        self.path_ranges.reset();
+        self.vertices.reset();
+        self.indices.reset();
+        self.resets += 1;
+    }
+
+    /// Release every buffer's capacity. Buffers regrow on the next call.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.token_text.shrink_to_fit();
+        self.token_spans.shrink_to_fit();
+        self.commands.shrink_to_fit();
+        self.path_ranges.shrink_to_fit();
+        self.vertices.shrink_to_fit();
+        self.indices.shrink_to_fit();
+    }
+
+    /// Bytes currently reserved across all buffers.
+    pub(crate) fn capacity_bytes(&self) -> usize {
+        self.token_text.capacity_bytes()
+            + self.token_spans.capacity_bytes()
+            + self.commands.capacity_bytes()
+            + self.path_ranges.capacity_bytes()
+            + self.vertices.capacity_bytes()
+            + self.indices.capacity_bytes()
+    }
+
+    /// Largest `capacity_bytes` observed at the end of any call.
+    pub(crate) fn peak_bytes(&self) -> usize {
+        self.peak_bytes
+    }
+
+    pub(crate) fn resets(&self) -> u64 {
+        self.resets
+    }
+}
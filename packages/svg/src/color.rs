@@ -0,0 +1,319 @@
+//! CSS color values, as fills, strokes and stop colors are written.
+//!
+//! `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa` hex, `rgb()` and `rgba()` with
+//! number or percentage channels, `hsl()` and `hsla()`, the CSS named
+//! colors and `transparent` are read. Function arguments may be separated
+//! by commas or by spaces with the alpha after a `/`, and the alpha may be
+//! a number or a percentage. Names and function names ignore case.
+//!
+//! Colors are straight RGBA, not premultiplied: each channel in [0, 1],
+//! with the alpha kept apart from the color channels.
+
+/// A color as straight RGBA, each channel in [0, 1].
+pub type Rgba = [f32; 4];
+
+/// The color `value` names, or None when it is not a CSS color. Paints
+/// such as `none`, `currentColor` and `url(#id)` are not colors.
+pub fn parse_color(value: &str) -> Option<Rgba> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+    if let Some((name, args)) = value.split_once('(') {
+        let args = args.strip_suffix(')')?;
+        return match name.trim().to_ascii_lowercase().as_str() {
+            "rgb" | "rgba" => parse_rgb(args),
+            "hsl" | "hsla" => parse_hsl(args),
+            _ => None,
+        };
+    }
+    let name = value.to_ascii_lowercase();
+    if name == "transparent" {
+        return Some([0.0; 4]);
+    }
+    let index = NAMED_COLORS
+        .binary_search_by(|(named, _)| named.cmp(&name.as_str()))
+        .ok()?;
+    let [r, g, b] = NAMED_COLORS[index].1;
+    Some([r, g, b, 255].map(|channel| f32::from(channel) / 255.0))
+}
+
+/// A color as RGBA8, rounding each channel.
+pub(crate) fn to_rgba8(color: Rgba) -> [u8; 4] {
+    color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !hex.is_ascii() {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+    let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    let rgba = match hex.len() {
+        3 => [digit(0)? * 17, digit(1)? * 17, digit(2)? * 17, 255],
+        4 => [
+            digit(0)? * 17,
+            digit(1)? * 17,
+            digit(2)? * 17,
+            digit(3)? * 17,
+        ],
+        6 => [byte(0)?, byte(2)?, byte(4)?, 255],
+        8 => [byte(0)?, byte(2)?, byte(4)?, byte(6)?],
+        _ => return None,
+    };
+    Some(rgba.map(|channel| f32::from(channel) / 255.0))
+}
+
+/// The arguments of a color function: three channels and an optional
+/// alpha, written `a, b, c[, alpha]` or `a b c[ / alpha]`.
+fn arguments(args: &str) -> Option<([&str; 3], Option<&str>)> {
+    let (channels, alpha) = if args.contains(',') {
+        let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+        match parts[..] {
+            [a, b, c] => ([a, b, c], None),
+            [a, b, c, alpha] => ([a, b, c], Some(alpha)),
+            _ => return None,
+        }
+    } else {
+        let (channels, alpha) = match args.split_once('/') {
+            Some((channels, alpha)) => (channels, Some(alpha.trim())),
+            None => (args, None),
+        };
+        let parts: Vec<&str> = channels.split_whitespace().collect();
+        match parts[..] {
+            [a, b, c] => ([a, b, c], alpha),
+            _ => return None,
+        }
+    };
+    Some((channels, alpha))
+}
+
+/// A number, or a percentage as a fraction of `full`.
+fn number_or_percentage(value: &str, full: f32) -> Option<f32> {
+    let number = match value.strip_suffix('%') {
+        Some(percentage) => percentage.trim().parse::<f32>().ok()? / 100.0 * full,
+        None => value.parse::<f32>().ok()?,
+    };
+    number.is_finite().then_some(number)
+}
+
+fn alpha(value: Option<&str>) -> Option<f32> {
+    value.map_or(Some(1.0), |alpha| {
+        number_or_percentage(alpha, 1.0).map(|alpha| alpha.clamp(0.0, 1.0))
+    })
+}
+
+fn parse_rgb(args: &str) -> Option<Rgba> {
+    let (channels, alpha_value) = arguments(args)?;
+    let mut rgba = [0.0; 4];
+    for (out, channel) in rgba.iter_mut().zip(channels) {
+        *out = (number_or_percentage(channel, 255.0)? / 255.0).clamp(0.0, 1.0);
+    }
+    rgba[3] = alpha(alpha_value)?;
+    Some(rgba)
+}
+
+fn parse_hsl(args: &str) -> Option<Rgba> {
+    let ([hue, saturation, lightness], alpha_value) = arguments(args)?;
+    // A hue turns around the color wheel, so -120 and 600 are 240
+    let hue = angle_degrees(hue)?.rem_euclid(360.0) / 360.0;
+    // SVG 1.1 documents write both with `%`; bare numbers are percentages
+    let fraction = |value: &str| {
+        let value = value.strip_suffix('%').unwrap_or(value);
+        let number = value.trim().parse::<f32>().ok()?;
+        number.is_finite().then(|| (number / 100.0).clamp(0.0, 1.0))
+    };
+    let (saturation, lightness) = (fraction(saturation)?, fraction(lightness)?);
+    let high = if lightness <= 0.5 {
+        lightness * (1.0 + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let low = 2.0 * lightness - high;
+    let channel = |offset: f32| {
+        let h = (hue + offset).rem_euclid(1.0);
+        if h < 1.0 / 6.0 {
+            low + (high - low) * 6.0 * h
+        } else if h < 0.5 {
+            high
+        } else if h < 2.0 / 3.0 {
+            low + (high - low) * (2.0 / 3.0 - h) * 6.0
+        } else {
+            low
+        }
+    };
+    Some([
+        channel(1.0 / 3.0),
+        channel(0.0),
+        channel(-1.0 / 3.0),
+        alpha(alpha_value)?,
+    ])
+}
+
+/// A hue in degrees, written bare or with a CSS angle unit.
+fn angle_degrees(value: &str) -> Option<f32> {
+    let value = value.to_ascii_lowercase();
+    let (number, per_degree) = [
+        ("deg", 1.0),
+        ("grad", 0.9),
+        ("rad", 180.0 / std::f32::consts::PI),
+        ("turn", 360.0),
+    ]
+    .into_iter()
+    .find_map(|(unit, scale)| Some((value.strip_suffix(unit)?.to_string(), scale)))
+    .unwrap_or((value, 1.0));
+    let degrees = number.trim().parse::<f32>().ok()? * per_degree;
+    degrees.is_finite().then_some(degrees)
+}
+
+/// The CSS named colors, sorted by name for binary search.
+const NAMED_COLORS: [(&str, [u8; 3]); 148] = [
+    ("aliceblue", [240, 248, 255]),
+    ("antiquewhite", [250, 235, 215]),
+    ("aqua", [0, 255, 255]),
+    ("aquamarine", [127, 255, 212]),
+    ("azure", [240, 255, 255]),
+    ("beige", [245, 245, 220]),
+    ("bisque", [255, 228, 196]),
+    ("black", [0, 0, 0]),
+    ("blanchedalmond", [255, 235, 205]),
+    ("blue", [0, 0, 255]),
+    ("blueviolet", [138, 43, 226]),
+    ("brown", [165, 42, 42]),
+    ("burlywood", [222, 184, 135]),
+    ("cadetblue", [95, 158, 160]),
+    ("chartreuse", [127, 255, 0]),
+    ("chocolate", [210, 105, 30]),
+    ("coral", [255, 127, 80]),
+    ("cornflowerblue", [100, 149, 237]),
+    ("cornsilk", [255, 248, 220]),
+    ("crimson", [220, 20, 60]),
+    ("cyan", [0, 255, 255]),
+    ("darkblue", [0, 0, 139]),
+    ("darkcyan", [0, 139, 139]),
+    ("darkgoldenrod", [184, 134, 11]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgreen", [0, 100, 0]),
+    ("darkgrey", [169, 169, 169]),
+    ("darkkhaki", [189, 183, 107]),
+    ("darkmagenta", [139, 0, 139]),
+    ("darkolivegreen", [85, 107, 47]),
+    ("darkorange", [255, 140, 0]),
+    ("darkorchid", [153, 50, 204]),
+    ("darkred", [139, 0, 0]),
+    ("darksalmon", [233, 150, 122]),
+    ("darkseagreen", [143, 188, 143]),
+    ("darkslateblue", [72, 61, 139]),
+    ("darkslategray", [47, 79, 79]),
+    ("darkslategrey", [47, 79, 79]),
+    ("darkturquoise", [0, 206, 209]),
+    ("darkviolet", [148, 0, 211]),
+    ("deeppink", [255, 20, 147]),
+    ("deepskyblue", [0, 191, 255]),
+    ("dimgray", [105, 105, 105]),
+    ("dimgrey", [105, 105, 105]),
+    ("dodgerblue", [30, 144, 255]),
+    ("firebrick", [178, 34, 34]),
+    ("floralwhite", [255, 250, 240]),
+    ("forestgreen", [34, 139, 34]),
+    ("fuchsia", [255, 0, 255]),
+    ("gainsboro", [220, 220, 220]),
+    ("ghostwhite", [248, 248, 255]),
+    ("gold", [255, 215, 0]),
+    ("goldenrod", [218, 165, 32]),
+    ("gray", [128, 128, 128]),
+    ("green", [0, 128, 0]),
+    ("greenyellow", [173, 255, 47]),
+    ("grey", [128, 128, 128]),
+    ("honeydew", [240, 255, 240]),
+    ("hotpink", [255, 105, 180]),
+    ("indianred", [205, 92, 92]),
+    ("indigo", [75, 0, 130]),
+    ("ivory", [255, 255, 240]),
+    ("khaki", [240, 230, 140]),
+    ("lavender", [230, 230, 250]),
+    ("lavenderblush", [255, 240, 245]),
+    ("lawngreen", [124, 252, 0]),
+    ("lemonchiffon", [255, 250, 205]),
+    ("lightblue", [173, 216, 230]),
+    ("lightcoral", [240, 128, 128]),
+    ("lightcyan", [224, 255, 255]),
+    ("lightgoldenrodyellow", [250, 250, 210]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgreen", [144, 238, 144]),
+    ("lightgrey", [211, 211, 211]),
+    ("lightpink", [255, 182, 193]),
+    ("lightsalmon", [255, 160, 122]),
+    ("lightseagreen", [32, 178, 170]),
+    ("lightskyblue", [135, 206, 250]),
+    ("lightslategray", [119, 136, 153]),
+    ("lightslategrey", [119, 136, 153]),
+    ("lightsteelblue", [176, 196, 222]),
+    ("lightyellow", [255, 255, 224]),
+    ("lime", [0, 255, 0]),
+    ("limegreen", [50, 205, 50]),
+    ("linen", [250, 240, 230]),
+    ("magenta", [255, 0, 255]),
+    ("maroon", [128, 0, 0]),
+    ("mediumaquamarine", [102, 205, 170]),
+    ("mediumblue", [0, 0, 205]),
+    ("mediumorchid", [186, 85, 211]),
+    ("mediumpurple", [147, 112, 219]),
+    ("mediumseagreen", [60, 179, 113]),
+    ("mediumslateblue", [123, 104, 238]),
+    ("mediumspringgreen", [0, 250, 154]),
+    ("mediumturquoise", [72, 209, 204]),
+    ("mediumvioletred", [199, 21, 133]),
+    ("midnightblue", [25, 25, 112]),
+    ("mintcream", [245, 255, 250]),
+    ("mistyrose", [255, 228, 225]),
+    ("moccasin", [255, 228, 181]),
+    ("navajowhite", [255, 222, 173]),
+    ("navy", [0, 0, 128]),
+    ("oldlace", [253, 245, 230]),
+    ("olive", [128, 128, 0]),
+    ("olivedrab", [107, 142, 35]),
+    ("orange", [255, 165, 0]),
+    ("orangered", [255, 69, 0]),
+    ("orchid", [218, 112, 214]),
+    ("palegoldenrod", [238, 232, 170]),
+    ("palegreen", [152, 251, 152]),
+    ("paleturquoise", [175, 238, 238]),
+    ("palevioletred", [219, 112, 147]),
+    ("papayawhip", [255, 239, 213]),
+    ("peachpuff", [255, 218, 185]),
+    ("peru", [205, 133, 63]),
+    ("pink", [255, 192, 203]),
+    ("plum", [221, 160, 221]),
+    ("powderblue", [176, 224, 230]),
+    ("purple", [128, 0, 128]),
+    ("rebeccapurple", [102, 51, 153]),
+    ("red", [255, 0, 0]),
+    ("rosybrown", [188, 143, 143]),
+    ("royalblue", [65, 105, 225]),
+    ("saddlebrown", [139, 69, 19]),
+    ("salmon", [250, 128, 114]),
+    ("sandybrown", [244, 164, 96]),
+    ("seagreen", [46, 139, 87]),
+    ("seashell", [255, 245, 238]),
+    ("sienna", [160, 82, 45]),
+    ("silver", [192, 192, 192]),
+    ("skyblue", [135, 206, 235]),
+    ("slateblue", [106, 90, 205]),
+    ("slategray", [112, 128, 144]),
+    ("slategrey", [112, 128, 144]),
+    ("snow", [255, 250, 250]),
+    ("springgreen", [0, 255, 127]),
+    ("steelblue", [70, 130, 180]),
+    ("tan", [210, 180, 140]),
+    ("teal", [0, 128, 128]),
+    ("thistle", [216, 191, 216]),
+    ("tomato", [255, 99, 71]),
+    ("turquoise", [64, 224, 208]),
+    ("violet", [238, 130, 238]),
+    ("wheat", [245, 222, 179]),
+    ("white", [255, 255, 255]),
+    ("whitesmoke", [245, 245, 245]),
+    ("yellow", [255, 255, 0]),
+    ("yellowgreen", [154, 205, 50]),
+];
@@ -0,0 +1,103 @@
+//! Which elements render: `display`, `visibility`, `<switch>` and `<defs>`.
+//!
+//! Both properties are read from attributes and from `style`; see `style`. `display="none"` removes an element and everything in
+//! it. `visibility` is inherited, so a hidden group hides its shapes unless
+//! one sets `visibility="visible"` again; hidden shapes are still parsed,
+//! flagged, so a host can show them. A `<switch>` renders only its first
+//! direct child whose conditional processing attributes all pass. Nothing
+//! inside a `<defs>` renders where it is written.
+
+use crate::extract_attr;
+use crate::style::property;
+
+/// Whether the conditional processing attributes of a start tag all pass.
+/// Feature strings are taken as supported, as browsers have done since
+/// SVG 2 dropped `requiredFeatures`. No extensions are supported.
+fn conditions_pass(tag: &str, language: Option<&str>) -> bool {
+    // An empty list fails each of the three attributes
+    let listed = |attr| extract_attr(tag, attr).map(|value| value.trim().to_string());
+    if listed("requiredFeatures").is_some_and(|features| features.is_empty()) {
+        return false;
+    }
+    if listed("requiredExtensions").is_some() {
+        return false;
+    }
+    match listed("systemLanguage") {
+        None => true,
+        Some(languages) => languages
+            .split(',')
+            .any(|entry| language.is_some_and(|language| language_matches(language, entry.trim()))),
+    }
+}
+
+/// Whether the user's `language` selects the `systemLanguage` entry `tag`:
+/// they are equal, or `language` is a prefix of `tag` ending at a `-`, so
+/// `de` selects `de-CH`. Case is ignored.
+fn language_matches(language: &str, tag: &str) -> bool {
+    let (language, tag) = (language.to_ascii_lowercase(), tag.to_ascii_lowercase());
+    !language.is_empty()
+        && tag
+            .strip_prefix(language.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+}
+
+/// An open element.
+struct Scope {
+    /// The element is not rendered, and neither is anything in it
+    skipped: bool,
+    /// The `visibility` its children inherit is hidden
+    hidden: bool,
+    /// Whether the element is a `<switch>` that has picked its child
+    switch_chosen: Option<bool>,
+}
+
+/// Follows the open elements of a document to tell which ones render.
+#[derive(Default)]
+pub(crate) struct RenderScopes {
+    scopes: Vec<Scope>,
+    /// The user's language, for `systemLanguage`
+    language: Option<String>,
+}
+
+impl RenderScopes {
+    pub(crate) fn set_language(&mut self, language: Option<String>) {
+        self.language = language;
+    }
+
+    /// Enter the element with start tag `tag`, returning None when it does
+    /// not render and otherwise whether its `visibility` is hidden. A `defs`
+    /// does not render.
+    pub(crate) fn start_tag(&mut self, tag: &str, is_switch: bool, is_defs: bool) -> Option<bool> {
+        let parent = self.scopes.last_mut();
+        let inherited_hidden = parent.as_ref().is_some_and(|parent| parent.hidden);
+        let mut skipped = parent.as_ref().is_some_and(|parent| parent.skipped);
+        if let Some(Scope {
+            switch_chosen: Some(chosen),
+            skipped: false,
+            ..
+        }) = parent
+        {
+            skipped = *chosen || !conditions_pass(tag, self.language.as_deref());
+            *chosen |= !skipped;
+        }
+        skipped |= is_defs;
+        skipped |= property(tag, "display").is_some_and(|display| display == "none");
+        let hidden = match property(tag, "visibility").as_deref() {
+            Some("hidden" | "collapse") => true,
+            Some("visible") => false,
+            _ => inherited_hidden,
+        };
+        if !tag.ends_with("/>") {
+            self.scopes.push(Scope {
+                skipped,
+                hidden,
+                switch_chosen: is_switch.then_some(false),
+            });
+        }
+        (!skipped).then_some(hidden)
+    }
+
+    pub(crate) fn end_tag(&mut self) {
+        self.scopes.pop();
+    }
+}
@@ -0,0 +1,172 @@
+//! `<defs>` and `<use>`: shapes drawn again where a `<use>` links to them.
+//!
+//! Every shape with an id is kept as written, before it inherits from the
+//! groups it is in, and so is every `<use>` with an id. Each rendered
+//! `<use>` draws a copy of the shape it links to, in its place, as if the
+//! shape were inside a group with the `<use>`'s presentation attributes and
+//! transform, translated by its `x` and `y`. A `<use>` may link to another
+//! `<use>`, and to shapes later in the document, so copies are made when
+//! the document is finished. Shapes inside `<defs>` draw only through a
+//! `<use>`.
+//!
+//! Links to groups and `<symbol>`s are not followed, and `width` and
+//! `height` are not read.
+
+use crate::inherit::Inherited;
+use crate::length::parse_length;
+use crate::style::StyleSheet;
+use crate::{extract_attr, href_id, ParsedPath, ParsedSvg};
+use std::collections::HashMap;
+
+/// Most links followed from one `<use>` through others, so cycles end.
+const MAX_USE_DEPTH: usize = 16;
+
+/// An element a `<use>` can link to.
+enum Definition {
+    /// A shape as its own start tag reads
+    Shape(Box<ParsedPath>),
+    /// The start tag of a `<use>`
+    Use(String),
+}
+
+/// A rendered `<use>`, copied in when the document is finished.
+struct UseSite {
+    tag: String,
+    /// What the elements around the `<use>` pass down
+    parent: Inherited,
+    /// Index of the path the copy goes before
+    index: usize,
+    hidden: bool,
+}
+
+/// Links a `<use>` led to that could not be followed.
+#[derive(Default)]
+struct Unfollowed {
+    missing: Vec<String>,
+    too_deep: Vec<String>,
+}
+
+/// The elements with an id that a `<use>` can link to, and the rendered
+/// `<use>` elements of a document.
+#[derive(Default)]
+pub(crate) struct Definitions {
+    elements: HashMap<String, Definition>,
+    uses: Vec<UseSite>,
+}
+
+impl Definitions {
+    /// Keep `shape`, before it inherits anything, if it has an id. The
+    /// first element with an id is the one linked to.
+    pub(crate) fn shape(&mut self, shape: &ParsedPath) {
+        if let Some(id) = &shape.id {
+            self.elements
+                .entry(id.clone())
+                .or_insert_with(|| Definition::Shape(Box::new(shape.clone())));
+        }
+    }
+
+    /// Keep the `<use>` with start tag `tag` if it has an id.
+    pub(crate) fn link(&mut self, tag: &str) {
+        if let Some(id) = extract_attr(tag, "id") {
+            self.elements
+                .entry(id)
+                .or_insert_with(|| Definition::Use(tag.to_string()));
+        }
+    }
+
+    /// Draw the `<use>` with start tag `tag`, inside elements that pass
+    /// down `parent`, before the path at `index`.
+    pub(crate) fn draw(&mut self, tag: &str, parent: Inherited, index: usize, hidden: bool) {
+        self.uses.push(UseSite {
+            tag: tag.to_string(),
+            parent,
+            index,
+            hidden,
+        });
+    }
+
+    /// Put the copies each rendered `<use>` draws into `parsed`'s paths,
+    /// with classes looked up in `sheet`, and warn about links that could
+    /// not be followed. Accessibility entries keep pointing at their paths.
+    pub(crate) fn expand(self, parsed: &mut ParsedSvg, sheet: &StyleSheet) {
+        if self.uses.is_empty() {
+            return;
+        }
+        let count = parsed.paths.len();
+        let mut written = std::mem::take(&mut parsed.paths).into_iter();
+        let mut moved_to = Vec::with_capacity(count);
+        let mut unfollowed = Unfollowed::default();
+        let mut sites = self.uses.iter().peekable();
+        for index in 0..=count {
+            while let Some(site) = sites.next_if(|site| site.index == index) {
+                let start = parsed.paths.len();
+                self.copy(&site.tag, &site.parent, sheet, 0, parsed, &mut unfollowed);
+                for copy in &mut parsed.paths[start..] {
+                    copy.id = extract_attr(&site.tag, "id");
+                    copy.hidden = site.hidden;
+                }
+            }
+            if let Some(path) = written.next() {
+                moved_to.push(parsed.paths.len());
+                parsed.paths.push(path);
+            }
+        }
+        for entry in &mut parsed.accessibility.paths {
+            entry.path = moved_to[entry.path];
+        }
+        if !unfollowed.missing.is_empty() {
+            parsed.document_warnings.push(format!(
+                "skipped use elements linking to undefined shapes: #{}",
+                unfollowed.missing.join(", #")
+            ));
+        }
+        if !unfollowed.too_deep.is_empty() {
+            parsed.document_warnings.push(format!(
+                "skipped use elements nested more than {} deep, as in a cycle: #{}",
+                MAX_USE_DEPTH,
+                unfollowed.too_deep.join(", #")
+            ));
+        }
+    }
+
+    /// Push the copy the `<use>` with start tag `tag` draws inside `parent`
+    /// onto `parsed`'s paths, following `depth` links so far.
+    fn copy(
+        &self,
+        tag: &str,
+        parent: &Inherited,
+        sheet: &StyleSheet,
+        depth: usize,
+        parsed: &mut ParsedSvg,
+        unfollowed: &mut Unfollowed,
+    ) {
+        // A `<use>` without a link draws nothing, as in browsers
+        let Some(id) = href_id(tag) else {
+            return;
+        };
+        let offset = |name| {
+            extract_attr(tag, name)
+                .and_then(|value| parse_length(&value, sheet.font_size())?.units())
+                .filter(|offset| offset.is_finite())
+                .unwrap_or(0.0)
+        };
+        let scope = Inherited::enter(tag, sheet, parent).translated(offset("x"), offset("y"));
+        let skipped = match self.elements.get(&id) {
+            Some(Definition::Shape(shape)) => {
+                let mut copy = ParsedPath::clone(shape);
+                scope.apply(&mut copy);
+                parsed.paths.push(copy);
+                return;
+            }
+            Some(Definition::Use(linked)) if depth < MAX_USE_DEPTH => {
+                self.copy(linked, &scope, sheet, depth + 1, parsed, unfollowed);
+                return;
+            }
+            Some(Definition::Use(_)) => &mut unfollowed.too_deep,
+            None => &mut unfollowed.missing,
+        };
+        if !skipped.contains(&id) {
+            skipped.push(id);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Meshes never hold NaN or an infinity.
+//!
+//! `serde_wasm_bindgen` passes non-finite floats through to JS, where they
+//! crash the renderer far from their cause. Every mesh is checked as it is
+//! built. A non-finite value there is a bug, so debug builds assert; release
+//! builds reject the mesh with a `NonFiniteValue` naming the field, or with
+//! the `sanitize-non-finite` feature replace the value with zero.
+
+use crate::TessellatedMesh;
+
+/// A result field holding NaN or an infinity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonFiniteValue {
+    /// The field, with an index for arrays, such as `vertices[7]`
+    pub field: String,
+    pub value: f32,
+}
+
+impl std::fmt::Display for NonFiniteValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Non-finite {}: {}", self.field, self.value)
+    }
+}
+
+/// Results whose floats can be checked and repaired in place.
+pub(crate) trait FiniteFields {
+    /// Call `visit` with every float field, named, as a slice.
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(&'static str, &mut [f32]));
+}
+
+impl FiniteFields for TessellatedMesh {
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(&'static str, &mut [f32])) {
+        visit("vertices", &mut self.vertices);
+        visit("bounds.min_x", std::slice::from_mut(&mut self.bounds.min_x));
+        visit("bounds.min_y", std::slice::from_mut(&mut self.bounds.min_y));
+        visit("bounds.max_x", std::slice::from_mut(&mut self.bounds.max_x));
+        visit("bounds.max_y", std::slice::from_mut(&mut self.bounds.max_y));
+        if let Some(transform) = self.transform.as_mut() {
+            visit("transform", transform);
+        }
+        if let Some(alpha) = self.alpha.as_mut() {
+            visit("alpha", std::slice::from_mut(alpha));
+        }
+    }
+}
+
+/// The first non-finite float of `value`, in field order.
+pub(crate) fn first_non_finite(value: &mut impl FiniteFields) -> Option<NonFiniteValue> {
+    let mut found = None;
+    value.visit_floats(&mut |name, values| {
+        if found.is_some() {
+            return;
+        }
+        if let Some(index) = values.iter().position(|value| !value.is_finite()) {
+            let field = if values.len() == 1 {
+                name.to_string()
+            } else {
+                format!("{}[{}]", name, index)
+            };
+            found = Some(NonFiniteValue {
+                field,
+                value: values[index],
+            });
+        }
+    });
+    found
+}
+
+/// Apply the policy to a freshly built result.
+pub(crate) fn enforce_finite(value: &mut impl FiniteFields) -> Result<(), NonFiniteValue> {
+    let Some(non_finite) = first_non_finite(value) else {
+        return Ok(());
+    };
+    debug_assert!(false, "{}", non_finite);
+    if cfg!(feature = "sanitize-non-finite") {
+        value.visit_floats(&mut |_, values| {
+            for value in values.iter_mut().filter(|value| !value.is_finite()) {
+                *value = 0.0;
+            }
+        });
+        return Ok(());
+    }
+    Err(non_finite)
+}
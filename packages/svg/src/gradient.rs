@@ -0,0 +1,360 @@
+//! Gradient paints, and what each mesh of a document is painted with.
+//!
+//! Gradients are read with the document and placed on each mesh that uses
+//! one, in mesh coordinates, with their stops.
+//!
+//! A linear gradient is placed as the two ends of its axis. A renderer
+//! shades a point by projecting it onto the axis, offset 0 at the start and
+//! 1 at the end, and padding with the end stops past them. The ends are
+//! chosen so the projection gives SVG's offset exactly: under a transform
+//! that skews or stretches the gradient, the lines of equal offset are no
+//! longer perpendicular to the axis from `x1`, `y1` to `x2`, `y2`, so the
+//! ends are not those points.
+//!
+//! A radial gradient is placed as its center, focal point and radius, the
+//! radius as two vectors since a transform can stretch the circle into an
+//! ellipse.
+//!
+//! The geometry attributes, `gradientUnits`, `gradientTransform`, and
+//! stops with `offset`, `stop-color` and `stop-opacity` are read. A
+//! gradient takes what it leaves unset from the gradient its `href` names.
+//! The focal radius `fr` and `spreadMethod` are not read.
+//! Percentages are read as fractions, which is
+//! exact for `objectBoundingBox`.
+
+use crate::inherit::clamp_opacity;
+#[cfg(feature = "gradients")]
+use crate::path_transform::IDENTITY;
+use crate::path_transform::{parse_transform, to_lyon};
+use crate::style::StyleSheet;
+use crate::{extract_attr, href_id, referenced_id, resolve_color};
+use lyon::math::{point, vector, Box2D, Transform};
+use serde::{Deserialize, Serialize};
+
+/// The geometry attributes of each kind of gradient, in `GradientSource`
+/// order.
+const LINEAR_ATTRIBUTES: [&str; 4] = ["x1", "y1", "x2", "y2"];
+const RADIAL_ATTRIBUTES: [&str; 5] = ["cx", "cy", "r", "fx", "fy"];
+
+/// Most `href` links followed from one gradient, so cycles end.
+#[cfg(feature = "gradients")]
+const MAX_HREF_DEPTH: usize = 16;
+
+/// Coordinate system of a gradient's geometry, as SVG `gradientUnits`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GradientUnits {
+    /// Fractions of the filled path's bounding box
+    #[default]
+    ObjectBoundingBox,
+    /// The path's own coordinates
+    UserSpaceOnUse,
+}
+
+impl GradientUnits {
+    fn from_attr(value: Option<&str>) -> Option<Self> {
+        match value.map(str::trim) {
+            Some("userSpaceOnUse") => Some(Self::UserSpaceOnUse),
+            Some("objectBoundingBox") => Some(Self::ObjectBoundingBox),
+            _ => None,
+        }
+    }
+}
+
+/// A gradient's color at one offset along it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    pub offset: f32,
+    /// RGBA8, with `stop-opacity` applied
+    pub color: [u8; 4],
+}
+
+/// A `<linearGradient>` or `<radialGradient>` with an id, with what its
+/// `href` supplies filled in.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SvgGradient {
+    pub id: String,
+    pub shape: GradientShape,
+    pub units: GradientUnits,
+    /// `gradientTransform` as `[a, b, c, d, e, f]`
+    pub transform: [f32; 6],
+    /// Offsets are clamped to [0, 1] and never decrease
+    pub stops: Vec<GradientStop>,
+}
+
+/// A gradient's geometry, in its `units` before its `transform`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GradientShape {
+    /// Offset 0 at `x1`, `y1` to 1 at `x2`, `y2`
+    Linear { x1: f32, y1: f32, x2: f32, y2: f32 },
+    /// Offset 0 at the focal point `fx`, `fy` to 1 on the circle around
+    /// `cx`, `cy` of radius `r`
+    Radial {
+        cx: f32,
+        cy: f32,
+        r: f32,
+        fx: f32,
+        fy: f32,
+    },
+}
+
+/// What a mesh of a document is painted with, before its `alpha`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MeshPaint {
+    /// One RGBA8 color
+    Solid { color: [u8; 4] },
+    /// A linear gradient from `start` to `end`, in mesh coordinates
+    LinearGradient {
+        start: [f32; 2],
+        end: [f32; 2],
+        stops: Vec<GradientStop>,
+    },
+    /// A radial gradient from `focus` to its end circle, in mesh
+    /// coordinates. A transform or `objectBoundingBox` units on a box that
+    /// is not square make the circle an ellipse, so its radius is two
+    /// vectors: the circle is the points `center + cos(a) * radius_x +
+    /// sin(a) * radius_y`. A round gradient has perpendicular radii of one
+    /// length.
+    RadialGradient {
+        center: [f32; 2],
+        focus: [f32; 2],
+        radius_x: [f32; 2],
+        radius_y: [f32; 2],
+        stops: Vec<GradientStop>,
+    },
+}
+
+impl SvgGradient {
+    /// The gradient on a path with bounding box `bounds` in user units,
+    /// which `user_to_mesh` takes to mesh coordinates. None when it paints
+    /// nothing: it has no stops, or its units collapse the path's box.
+    fn place(&self, bounds: &Box2D, user_to_mesh: &Transform) -> Option<MeshPaint> {
+        let last = self.stops.last()?;
+        // SVG paints a gradient without length or radius in its last stop
+        let solid = Some(MeshPaint::Solid { color: last.color });
+        if self.stops.len() == 1 {
+            return solid;
+        }
+        let units = match self.units {
+            GradientUnits::ObjectBoundingBox => Transform::new(
+                bounds.width(),
+                0.0,
+                0.0,
+                bounds.height(),
+                bounds.min.x,
+                bounds.min.y,
+            ),
+            GradientUnits::UserSpaceOnUse => Transform::identity(),
+        };
+        let to_mesh = to_lyon(self.transform).then(&units).then(user_to_mesh);
+        let from_mesh = to_mesh.inverse()?;
+        let paint = match self.shape {
+            GradientShape::Linear { x1, y1, x2, y2 } => {
+                let delta = vector(x2 - x1, y2 - y1);
+                let length_squared = delta.square_length();
+                if !length_squared.is_normal() {
+                    return solid;
+                }
+                // The offset is affine in mesh coordinates; the end lies
+                // along its gradient, where it reaches 1
+                let rate = vector(
+                    from_mesh.transform_vector(vector(1.0, 0.0)).dot(delta),
+                    from_mesh.transform_vector(vector(0.0, 1.0)).dot(delta),
+                ) / length_squared;
+                let start = to_mesh.transform_point(point(x1, y1));
+                MeshPaint::LinearGradient {
+                    start: start.to_array(),
+                    end: (start + rate / rate.square_length()).to_array(),
+                    stops: self.stops.clone(),
+                }
+            }
+            GradientShape::Radial { cx, cy, r, fx, fy } => {
+                if !r.is_normal() || r < 0.0 {
+                    return solid;
+                }
+                MeshPaint::RadialGradient {
+                    center: to_mesh.transform_point(point(cx, cy)).to_array(),
+                    focus: to_mesh.transform_point(point(fx, fy)).to_array(),
+                    radius_x: to_mesh.transform_vector(vector(r, 0.0)).to_array(),
+                    radius_y: to_mesh.transform_vector(vector(0.0, r)).to_array(),
+                    stops: self.stops.clone(),
+                }
+            }
+        };
+        paint.is_finite().then_some(paint)
+    }
+}
+
+impl MeshPaint {
+    fn is_finite(&self) -> bool {
+        let points: &[[f32; 2]] = match self {
+            MeshPaint::Solid { .. } => &[],
+            MeshPaint::LinearGradient { start, end, .. } => &[*start, *end],
+            MeshPaint::RadialGradient {
+                center,
+                focus,
+                radius_x,
+                radius_y,
+                ..
+            } => &[*center, *focus, *radius_x, *radius_y],
+        };
+        points.iter().flatten().all(|value| value.is_finite())
+    }
+}
+
+/// What the fill or stroke `value` paints a mesh with, or None when it
+/// paints nothing. A reference to a gradient places it on `bounds`, the
+/// path's bounding box in user units, which `user_to_mesh` takes to mesh
+/// coordinates; any other value is a solid color, with references that
+/// resolve to nothing in `gradients` taking their fallback or black.
+pub(crate) fn mesh_paint(
+    value: Option<&str>,
+    gradients: &[SvgGradient],
+    bounds: &Box2D,
+    user_to_mesh: &Transform,
+) -> Option<MeshPaint> {
+    let gradient = value
+        .and_then(referenced_id)
+        .and_then(|id| gradients.iter().find(|gradient| gradient.id == id));
+    match gradient {
+        Some(gradient) => gradient.place(bounds, user_to_mesh),
+        None => resolve_color(value).map(|color| MeshPaint::Solid { color }),
+    }
+}
+
+/// A gradient as written, before its `href` is followed.
+/// Without the `gradients` feature it is read and dropped.
+#[cfg_attr(not(feature = "gradients"), allow(dead_code))]
+pub(crate) struct GradientSource {
+    id: Option<String>,
+    href: Option<String>,
+    radial: bool,
+    /// The attributes of `LINEAR_ATTRIBUTES` or `RADIAL_ATTRIBUTES`
+    geometry: Vec<Option<f32>>,
+    units: Option<GradientUnits>,
+    transform: Option<[f32; 6]>,
+    stops: Vec<GradientStop>,
+}
+
+impl GradientSource {
+    /// The gradient with start tag `open`, before its stops, which is a
+    /// `radialGradient` with `radial` and else a `linearGradient`.
+    pub(crate) fn new(open: &str, radial: bool) -> Self {
+        let coordinate = |name: &&str| extract_attr(open, name).and_then(|value| fraction(&value));
+        let attributes: &[&str] = if radial {
+            &RADIAL_ATTRIBUTES
+        } else {
+            &LINEAR_ATTRIBUTES
+        };
+        Self {
+            id: extract_attr(open, "id"),
+            href: href_id(open),
+            radial,
+            geometry: attributes.iter().map(coordinate).collect(),
+            units: GradientUnits::from_attr(extract_attr(open, "gradientUnits").as_deref()),
+            transform: extract_attr(open, "gradientTransform")
+                .as_deref()
+                .and_then(parse_transform),
+            stops: Vec::new(),
+        }
+    }
+
+    /// Add the stop with start tag `tag`, whose classes are looked up in
+    /// `sheet`.
+    pub(crate) fn stop(&mut self, tag: &str, sheet: &StyleSheet) {
+        let previous = self.stops.last().map_or(0.0, |stop| stop.offset);
+        let offset = extract_attr(tag, "offset")
+            .and_then(|value| fraction(&value))
+            .unwrap_or(0.0)
+            .clamp(previous, 1.0);
+        let [r, g, b, a] =
+            resolve_color(sheet.property(tag, "stop-color").as_deref()).unwrap_or([0, 0, 0, 0]);
+        let opacity = sheet
+            .number_property(tag, "stop-opacity")
+            .and_then(clamp_opacity)
+            .unwrap_or(1.0);
+        self.stops.push(GradientStop {
+            offset,
+            color: [r, g, b, (f32::from(a) * opacity).round() as u8],
+        });
+    }
+}
+
+/// The gradients of `sources` that have an id, each completed from the
+/// gradients its `href` leads to. Geometry comes only from gradients of the
+/// same kind.
+#[cfg(feature = "gradients")]
+pub(crate) fn resolve_gradients(sources: &[GradientSource]) -> Vec<SvgGradient> {
+    let find = |id: &str| {
+        sources
+            .iter()
+            .find(|source| source.id.as_deref() == Some(id))
+    };
+    sources
+        .iter()
+        .filter_map(|source| {
+            let id = source.id.clone()?;
+            let mut chain = vec![source];
+            while let Some(linked) = chain
+                .last()
+                .and_then(|source| source.href.as_deref())
+                .and_then(find)
+                .filter(|_| chain.len() < MAX_HREF_DEPTH)
+            {
+                chain.push(linked);
+            }
+            let geometry = |index: usize| {
+                chain
+                    .iter()
+                    .filter(|linked| linked.radial == source.radial)
+                    .find_map(|linked| linked.geometry[index])
+            };
+            let shape = if source.radial {
+                let (cx, cy) = (geometry(0).unwrap_or(0.5), geometry(1).unwrap_or(0.5));
+                GradientShape::Radial {
+                    cx,
+                    cy,
+                    r: geometry(2).unwrap_or(0.5),
+                    fx: geometry(3).unwrap_or(cx),
+                    fy: geometry(4).unwrap_or(cy),
+                }
+            } else {
+                GradientShape::Linear {
+                    x1: geometry(0).unwrap_or(0.0),
+                    y1: geometry(1).unwrap_or(0.0),
+                    x2: geometry(2).unwrap_or(1.0),
+                    y2: geometry(3).unwrap_or(0.0),
+                }
+            };
+            Some(SvgGradient {
+                id,
+                shape,
+                units: chain
+                    .iter()
+                    .find_map(|source| source.units)
+                    .unwrap_or_default(),
+                transform: chain
+                    .iter()
+                    .find_map(|source| source.transform)
+                    .unwrap_or(IDENTITY),
+                stops: chain
+                    .iter()
+                    .find(|source| !source.stops.is_empty())
+                    .map(|source| source.stops.clone())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// A number, or a percentage as a fraction.
+fn fraction(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let number = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? / 100.0,
+        None => value.parse().ok()?,
+    };
+    number.is_finite().then_some(number)
+}
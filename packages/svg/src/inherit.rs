@@ -0,0 +1,175 @@
+//! Presentation attributes that groups pass down to their shapes.
+//!
+//! `fill`, `stroke`, `stroke-width`, `fill-opacity`, `stroke-opacity`,
+//! `fill-rule`, `stroke-linecap`, `stroke-linejoin`, `stroke-miterlimit`,
+//! `stroke-dasharray`, `stroke-dashoffset` and `color` are inherited: a
+//! shape without its own takes the nearest enclosing element's. `opacity` and `transform` are not, but a group's apply to
+//! everything in it, so they compose instead: opacities multiply, and a
+//! shape's transform is applied before its groups', innermost first.
+
+use crate::path_transform::{parse_transform, to_lyon};
+use crate::style::StyleSheet;
+use crate::{extract_attr, ParsedPath};
+
+/// What an open element passes to the elements inside it.
+#[derive(Clone, Default)]
+pub(crate) struct Inherited {
+    fill: Option<String>,
+    stroke: Option<String>,
+    stroke_width: Option<f32>,
+    fill_opacity: Option<f32>,
+    stroke_opacity: Option<f32>,
+    fill_rule: Option<String>,
+    stroke_linecap: Option<String>,
+    stroke_linejoin: Option<String>,
+    stroke_miterlimit: Option<f32>,
+    stroke_dasharray: Option<String>,
+    stroke_dashoffset: Option<f32>,
+    color: Option<String>,
+    opacity: Option<f32>,
+    transform: Option<[f32; 6]>,
+}
+
+/// Follows the open elements of a document to resolve the presentation
+/// attributes of each shape.
+#[derive(Default)]
+pub(crate) struct PaintScopes {
+    scopes: Vec<Inherited>,
+}
+
+impl PaintScopes {
+    /// Resolve `path` against the elements it is in. Call before entering
+    /// the shape's own start tag.
+    pub(crate) fn inherit(&self, path: &mut ParsedPath) {
+        if let Some(parent) = self.scopes.last() {
+            parent.apply(path);
+        }
+    }
+
+    /// What the innermost open element passes down.
+    pub(crate) fn current(&self) -> Inherited {
+        self.scopes.last().cloned().unwrap_or_default()
+    }
+
+    /// Enter the element with start tag `tag`, whose classes are looked up
+    /// in `sheet`.
+    pub(crate) fn start_tag(&mut self, tag: &str, sheet: &StyleSheet) {
+        if tag.ends_with("/>") {
+            return;
+        }
+        let scope = Inherited::enter(tag, sheet, &self.current());
+        self.scopes.push(scope);
+    }
+
+    pub(crate) fn end_tag(&mut self) {
+        self.scopes.pop();
+    }
+}
+
+impl Inherited {
+    /// Resolve `path` as a shape inside the element this came from.
+    pub(crate) fn apply(&self, path: &mut ParsedPath) {
+        path.fill = path.fill.take().or_else(|| self.fill.clone());
+        path.stroke = path.stroke.take().or_else(|| self.stroke.clone());
+        path.stroke_width = path.stroke_width.or(self.stroke_width);
+        path.fill_opacity = path.fill_opacity.or(self.fill_opacity);
+        path.stroke_opacity = path.stroke_opacity.or(self.stroke_opacity);
+        path.fill_rule = path.fill_rule.take().or_else(|| self.fill_rule.clone());
+        path.stroke_linecap = path
+            .stroke_linecap
+            .take()
+            .or_else(|| self.stroke_linecap.clone());
+        path.stroke_linejoin = path
+            .stroke_linejoin
+            .take()
+            .or_else(|| self.stroke_linejoin.clone());
+        path.stroke_miterlimit = path.stroke_miterlimit.or(self.stroke_miterlimit);
+        path.stroke_dasharray = path
+            .stroke_dasharray
+            .take()
+            .or_else(|| self.stroke_dasharray.clone());
+        path.stroke_dashoffset = path.stroke_dashoffset.or(self.stroke_dashoffset);
+        path.color = path.color.take().or_else(|| self.color.clone());
+        path.opacity = multiply(path.opacity, self.opacity);
+        path.transform = compose(path.transform, self.transform);
+    }
+
+    /// What the element with start tag `tag` passes down from inside
+    /// `parent`.
+    pub(crate) fn enter(tag: &str, sheet: &StyleSheet, parent: &Inherited) -> Inherited {
+        let parent = parent.clone();
+        Inherited {
+            fill: sheet.property(tag, "fill").or(parent.fill),
+            stroke: sheet.property(tag, "stroke").or(parent.stroke),
+            stroke_width: sheet
+                .length_property(tag, "stroke-width")
+                .or(parent.stroke_width),
+            fill_opacity: sheet
+                .number_property(tag, "fill-opacity")
+                .and_then(clamp_opacity)
+                .or(parent.fill_opacity),
+            stroke_opacity: sheet
+                .number_property(tag, "stroke-opacity")
+                .and_then(clamp_opacity)
+                .or(parent.stroke_opacity),
+            fill_rule: sheet.property(tag, "fill-rule").or(parent.fill_rule),
+            stroke_linecap: sheet
+                .property(tag, "stroke-linecap")
+                .or(parent.stroke_linecap),
+            stroke_linejoin: sheet
+                .property(tag, "stroke-linejoin")
+                .or(parent.stroke_linejoin),
+            stroke_miterlimit: sheet
+                .number_property(tag, "stroke-miterlimit")
+                .or(parent.stroke_miterlimit),
+            stroke_dasharray: sheet
+                .property(tag, "stroke-dasharray")
+                .or(parent.stroke_dasharray),
+            stroke_dashoffset: sheet
+                .number_property(tag, "stroke-dashoffset")
+                .or(parent.stroke_dashoffset),
+            color: sheet.property(tag, "color").or(parent.color),
+            opacity: multiply(
+                sheet
+                    .number_property(tag, "opacity")
+                    .and_then(clamp_opacity),
+                parent.opacity,
+            ),
+            transform: compose(
+                extract_attr(tag, "transform")
+                    .as_deref()
+                    .and_then(parse_transform),
+                parent.transform,
+            ),
+        }
+    }
+
+    /// This with its contents moved by `x`, `y` before its transform.
+    pub(crate) fn translated(mut self, x: f32, y: f32) -> Inherited {
+        if x != 0.0 || y != 0.0 {
+            let translate = Some([1.0, 0.0, 0.0, 1.0, x, y]);
+            self.transform = compose(translate, self.transform);
+        }
+        self
+    }
+}
+
+/// An opacity clamped to [0, 1], or None when it is not finite.
+pub(crate) fn clamp_opacity(opacity: f32) -> Option<f32> {
+    opacity.is_finite().then(|| opacity.clamp(0.0, 1.0))
+}
+
+fn multiply(own: Option<f32>, parent: Option<f32>) -> Option<f32> {
+    match (own, parent) {
+        (Some(own), Some(parent)) => Some(own * parent),
+        (own, parent) => own.or(parent),
+    }
+}
+
+/// `inner` applied first, then `outer`.
+fn compose(inner: Option<[f32; 6]>, outer: Option<[f32; 6]>) -> Option<[f32; 6]> {
+    match (inner, outer) {
+        (Some(inner), Some(outer)) => Some(to_lyon(inner).then(&to_lyon(outer)).to_array()),
+        (inner, outer) => inner.or(outer),
+    }
+}
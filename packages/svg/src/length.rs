@@ -0,0 +1,62 @@
+//! Lengths written with a unit, as in `width="10mm"` or
+//! `stroke-width="0.5em"`, in user units.
+//!
+//! Absolute units convert at 96 per inch, as CSS does. `em` is the font
+//! size the document is read with and `ex` half of it, since documents are
+//! read without fonts. A percentage is returned as written, for the caller
+//! to resolve against what it is a percentage of.
+
+/// The font size `em` lengths are relative to without one from the caller,
+/// the default of browsers.
+pub(crate) const DEFAULT_FONT_SIZE: f32 = 16.0;
+
+/// A length as written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Length {
+    /// User units
+    Units(f32),
+    /// Percent of a reference length
+    Percent(f32),
+}
+
+impl Length {
+    /// User units, or None for a percentage.
+    pub(crate) fn units(self) -> Option<f32> {
+        match self {
+            Length::Units(units) => Some(units),
+            Length::Percent(_) => None,
+        }
+    }
+}
+
+/// Read `value` as a number with an optional unit, with `em` being
+/// `font_size` user units. None for an unknown unit or a number that does
+/// not parse.
+pub(crate) fn parse_length(value: &str, font_size: f32) -> Option<Length> {
+    let value = value.trim();
+    if let Some(percent) = value.strip_suffix('%') {
+        return percent.parse().ok().map(Length::Percent);
+    }
+    let per_unit = |unit: &str| match unit {
+        "px" => Some(1.0),
+        "pt" => Some(96.0 / 72.0),
+        "pc" => Some(16.0),
+        "mm" => Some(96.0 / 25.4),
+        "cm" => Some(96.0 / 2.54),
+        "in" => Some(96.0),
+        "em" => Some(font_size),
+        "ex" => Some(font_size / 2.0),
+        _ => None,
+    };
+    // Units are letters, and a number only ends in a digit or `.`
+    let number_end = value
+        .rfind(|c: char| c.is_ascii_digit() || c == '.')
+        .map_or(0, |at| at + 1);
+    let (number, unit) = value.split_at(number_end);
+    let scale = match unit {
+        "" => 1.0,
+        unit => per_unit(&unit.to_ascii_lowercase())?,
+    };
+    let number: f32 = number.parse().ok()?;
+    Some(Length::Units(number * scale))
+}
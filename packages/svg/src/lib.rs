@@ -3,14 +3,63 @@
 //! Parses SVG content and tessellates paths into triangle meshes
 //! for GPU rendering in Glade.
 
-use lyon::math::Point;
+mod accessibility;
+mod aspect;
+mod boundary;
+mod bump_alloc;
+mod color;
+mod conditional;
+mod content_hash;
+mod definitions;
+mod finite;
+mod gradient;
+mod inherit;
+mod length;
+mod mesh_format;
+mod path_measure;
+mod path_transform;
+mod pattern;
+mod provenance;
+#[cfg(feature = "gradients")]
+mod radial;
+mod scene;
+mod stream;
+mod stroke_align;
+mod stroker;
+mod style;
+
+use bump_alloc::Arena;
+use handles::{HandleRegistry, StaleHandle};
+use lyon::math::{point, Box2D, Point, Transform};
+use lyon::path::builder::{NoAttributes, PathBuilder};
 use lyon::tessellation::{
     BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, StrokeOptions,
-    StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
+    StrokeVertex, StrokeVertexConstructor, TessellationError, UnsupportedParamater, VertexBuffers,
 };
+use provenance::{CommandTracker, SourcedBuilder, SourcedVertex};
 use serde::{Deserialize, Serialize};
+use stroker::Stroker;
+use tolerance::Tolerance;
 use wasm_bindgen::prelude::*;
 
+pub use accessibility::{PathAccessibility, SvgAccessibility};
+pub use aspect::{AspectAlign, PreserveAspectRatio};
+pub use boundary::boundary_edges;
+pub use color::{parse_color, Rgba};
+pub use finite::NonFiniteValue;
+pub use gradient::{GradientShape, GradientStop, GradientUnits, MeshPaint, SvgGradient};
+pub use mesh_format::{
+    decode_meshes, encode_meshes, MeshAsset, MeshFormatError, MESH_FORMAT_VERSION, MESH_MAGIC,
+};
+pub use path_measure::{PathMeasure, PathSample};
+pub use path_transform::{DynamicPath, PathRef};
+pub use pattern::{PatternFill, PatternUnits, SvgPattern, TileGrid};
+#[cfg(feature = "gradients")]
+pub use radial::{RadialGradientInput, RadialGradientMesh, ResolvedRadialGradient};
+pub use scene::{ColorMap, Scene, SceneEntry, SceneOptions, SCENE_FORMAT_VERSION, SCENE_MAGIC};
+pub use stream::SvgStreamParser;
+pub use stroke_align::StrokeAlign;
+
 /// A vertex with position and edge distance for antialiasing.
 #[derive(Clone, Debug)]
 pub struct TessVertex {
@@ -20,15 +69,124 @@ pub struct TessVertex {
     pub edge_dist: f32,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// A triangle mesh ready for upload.
+///
+/// Unless consistent winding is disabled on the tessellator, every triangle
+/// has positive signed area `(b - a) x (c - a)`: counter-clockwise when the
+/// mesh plane is read y-up, which appears clockwise on a y-down screen.
+/// Zero-area triangles are dropped, so back-face culling never discards
+/// visible geometry.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TessellatedMesh {
     /// Flat array of vertex data: [x, y, edge_dist, x, y, edge_dist, ...]
     pub vertices: Vec<f32>,
     pub indices: Vec<u32>,
     pub bounds: MeshBounds,
+    /// Position in the document's paint sequence: `2 * path + 0` for what
+    /// the path paints first and `+ 1` for what it paints second, following
+    /// `paint-order`. Meshes from one document are returned in this order,
+    /// so a renderer may batch them freely but must keep the relative order
+    /// of overlapping ones. Zero for meshes tessellated on their own.
+    #[serde(default)]
+    pub paint_order: u32,
+    /// Edges used by only one triangle, as flat vertex index pairs chained
+    /// into loops; see `boundary_edges`. Empty unless requested with
+    /// `TessellateOptions::boundary_edges`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub boundary_edges: Vec<u32>,
+    /// Index of the parsed path command behind each vertex, parallel to the
+    /// vertices. Empty unless requested with
+    /// `PathPaintOptions::source_commands`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_commands: Vec<u32>,
+    /// UTF-8 byte range in the path's `d` of each command that
+    /// `source_commands` indexes, as flat `[start, end, ...]` pairs. A range
+    /// covers the command letter, if written, and the command's arguments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command_ranges: Vec<u32>,
+    /// For a dynamic path, the matrix `[a, b, c, d, e, f]` taking its
+    /// vertices, tessellated untransformed, to display coordinates; see
+    /// `TessellateOptions::dynamic_paths`. None when the vertices are
+    /// already in display coordinates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<[f32; 6]>,
+    /// Alpha for the renderer to multiply into the mesh's color: the path's
+    /// `opacity` times its `fill-opacity` or `stroke-opacity`. None when
+    /// neither is set; `MeshAsset` colors have it applied already.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f32>,
+    /// What the mesh is painted with, for a mesh of a document: a solid
+    /// color or a linear gradient. None for meshes tessellated on their own
+    /// and for paints that draw nothing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paint: Option<MeshPaint>,
+    /// For a mesh of a document, its path's `fill_color` or `stroke_color`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Rgba>,
+    /// Whether the mesh is painted with `currentColor` that neither the
+    /// document's `color` nor `TessellateOptions::current_color` gave a
+    /// value, so a renderer can tint it. Its `paint` is then black.
+    #[serde(default)]
+    pub uses_current_color: bool,
+    /// Set on the empty mesh a single-path function returns without
+    /// tessellating, when its scale collapses the path to nothing. None for
+    /// a mesh that was tessellated, even if it came out empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degenerate: Option<DegenerateInput>,
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+impl TessellatedMesh {
+    /// The mesh returned for input too degenerate to tessellate.
+    fn degenerate(reason: DegenerateInput) -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            bounds: MeshBounds::default(),
+            paint_order: 0,
+            boundary_edges: Vec::new(),
+            source_commands: Vec::new(),
+            command_ranges: Vec::new(),
+            transform: None,
+            alpha: None,
+            paint: None,
+            color: None,
+            uses_current_color: false,
+            degenerate: Some(reason),
+        }
+    }
+}
+
+/// Why a tessellation returned nothing without trying, as opposed to
+/// tessellating and covering nothing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DegenerateInput {
+    /// The display size or scale is zero, too small to draw at or infinite,
+    /// as at the end of an animation that collapses an icon
+    DisplayScale,
+    /// The document's viewBox or size is zero or negative, which disables
+    /// rendering in SVG
+    NativeSize,
+}
+
+/// A display size no drawing can have: negative or NaN.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InvalidDisplaySize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl std::fmt::Display for InvalidDisplaySize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid display size {} x {}: sizes must be zero or more",
+            self.width, self.height
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct MeshBounds {
     pub min_x: f32,
     pub min_y: f32,
@@ -37,6 +195,7 @@ pub struct MeshBounds {
 }
 
 impl MeshBounds {
+    #[inline]
     fn expand(&mut self, x: f32, y: f32) {
         self.min_x = self.min_x.min(x);
         self.min_y = self.min_y.min(y);
@@ -44,6 +203,7 @@ impl MeshBounds {
         self.max_y = self.max_y.max(y);
     }
 
+    #[inline]
     fn new() -> Self {
         Self {
             min_x: f32::MAX,
@@ -53,6 +213,7 @@ impl MeshBounds {
         }
     }
 
+    #[inline]
     fn is_valid(&self) -> bool {
         self.min_x <= self.max_x && self.min_y <= self.max_y
     }
@@ -95,23 +256,524 @@ impl StrokeVertexConstructor<TessVertex> for VertexWithEdge {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ParsedPath {
+    pub id: Option<String>,
+    /// The `class` attribute as written.
+    #[serde(default)]
+    pub class: Option<String>,
     pub fill: Option<String>,
     pub stroke: Option<String>,
     pub stroke_width: Option<f32>,
     pub d: String,
+    /// Whether `d` was shortened or emptied by `ParseOptions::max_d_length`.
+    pub d_truncated: bool,
+    /// The `paint-order` attribute, which can paint the stroke first.
+    #[serde(default)]
+    pub paint_order: Option<String>,
+    /// The `stroke-alignment` attribute, which can draw the stroke inside or
+    /// outside the outline.
+    #[serde(default)]
+    pub stroke_alignment: Option<String>,
+    /// The `fill-rule` set on the shape or inherited from its groups.
+    #[serde(default)]
+    pub fill_rule: Option<String>,
+    /// The `stroke-linecap` set on the shape or inherited from its groups.
+    #[serde(default)]
+    pub stroke_linecap: Option<String>,
+    /// The `stroke-linejoin` set on the shape or inherited from its groups.
+    #[serde(default)]
+    pub stroke_linejoin: Option<String>,
+    /// The `stroke-miterlimit` set on the shape or inherited from its groups.
+    #[serde(default)]
+    pub stroke_miterlimit: Option<f32>,
+    /// The `stroke-dasharray` set on the shape or inherited from its groups.
+    #[serde(default)]
+    pub stroke_dasharray: Option<String>,
+    /// The `stroke-dashoffset` set on the shape or inherited from its groups.
+    #[serde(default)]
+    pub stroke_dashoffset: Option<f32>,
+    /// The shape's `transform` attribute composed with those of its groups,
+    /// as `[a, b, c, d, e, f]`, or None when none has one that parses.
+    #[serde(default)]
+    pub transform: Option<[f32; 6]>,
+    /// The shape's `opacity` times its groups', or None when none sets it.
+    #[serde(default)]
+    pub opacity: Option<f32>,
+    /// The `fill-opacity` set on the shape or inherited from its groups.
+    #[serde(default)]
+    pub fill_opacity: Option<f32>,
+    /// The `stroke-opacity` set on the shape or inherited from its groups.
+    #[serde(default)]
+    pub stroke_opacity: Option<f32>,
+    /// The `color` set on the shape or inherited from its groups, which
+    /// `currentColor` paints take.
+    #[serde(default)]
+    pub color: Option<String>,
+    /// `fill` as straight RGBA in [0, 1], not premultiplied, before
+    /// opacity. Black when unset or invalid. None when it paints no plain color: `none`, a
+    /// `url()` paint, or `currentColor` left unresolved.
+    #[serde(default)]
+    pub fill_color: Option<Rgba>,
+    /// `stroke` as for `fill_color`, but None when unset, as an unset stroke
+    /// draws nothing.
+    #[serde(default)]
+    pub stroke_color: Option<Rgba>,
+    /// Whether the shape has `visibility` hidden, set on it or inherited.
+    /// Hidden shapes are not tessellated.
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+impl ParsedPath {
+    /// Whether the path's fill, or its stroke with `stroke`, is
+    /// `currentColor`.
+    pub fn uses_current_color(&self, stroke: bool) -> bool {
+        self.paint(stroke).is_some_and(is_current_color)
+    }
+
+    /// Replace `currentColor` fills and strokes with the path's `color`, or
+    /// with `current` when the document sets none. Left as written when
+    /// neither is given.
+    fn resolve_current_color(&mut self, current: Option<&str>) {
+        let color = self
+            .color
+            .as_deref()
+            .filter(|color| !is_current_color(color))
+            .or(current)
+            .map(str::to_string);
+        let Some(color) = color else {
+            return;
+        };
+        for paint in [&mut self.fill, &mut self.stroke] {
+            if paint.as_deref().is_some_and(is_current_color) {
+                *paint = Some(color.clone());
+            }
+        }
+        self.read_colors(&mut Vec::new());
+    }
+
+    /// Set `fill_color` and `stroke_color` from `fill` and `stroke`,
+    /// adding the values that are not colors to `invalid`.
+    fn read_colors(&mut self, invalid: &mut Vec<String>) {
+        const BLACK: Rgba = [0.0, 0.0, 0.0, 1.0];
+        let mut plain = |value: Option<&str>, unset| {
+            let Some(value) = value.map(str::trim) else {
+                return unset;
+            };
+            if value.eq_ignore_ascii_case("none")
+                || is_current_color(value)
+                || split_reference(value).is_some()
+            {
+                return None;
+            }
+            let color = color::parse_color(value);
+            if color.is_none() && !invalid.iter().any(|seen| seen == value) {
+                invalid.push(value.to_string());
+            }
+            Some(color.unwrap_or(BLACK))
+        };
+        self.fill_color = plain(self.fill.as_deref(), Some(BLACK));
+        self.stroke_color = plain(self.stroke.as_deref(), None);
+    }
+
+    /// The path's `fill_color`, or its `stroke_color` with `stroke`.
+    pub fn paint_color(&self, stroke: bool) -> Option<Rgba> {
+        if stroke {
+            self.stroke_color
+        } else {
+            self.fill_color
+        }
+    }
+
+    /// The path's `fill`, or its `stroke` with `stroke`.
+    fn paint(&self, stroke: bool) -> Option<&str> {
+        if stroke {
+            self.stroke.as_deref()
+        } else {
+            self.fill.as_deref()
+        }
+    }
+
+    /// The alpha of the path's fill, or of its stroke with `stroke`: its
+    /// `opacity` times `fill-opacity` or `stroke-opacity`. None when
+    /// neither is set.
+    pub fn paint_alpha(&self, stroke: bool) -> Option<f32> {
+        let paint = if stroke {
+            self.stroke_opacity
+        } else {
+            self.fill_opacity
+        };
+        match (self.opacity, paint) {
+            (Some(opacity), Some(paint)) => Some(opacity * paint),
+            (opacity, paint) => opacity.or(paint),
+        }
+    }
+
+    /// Whether `paint-order` puts the stroke before the fill. Paints it does
+    /// not list follow in their usual order, so `stroke` alone is enough.
+    pub fn stroke_first(&self) -> bool {
+        let Some(order) = self.paint_order.as_deref() else {
+            return false;
+        };
+        order
+            .split_whitespace()
+            .find(|keyword| *keyword == "fill" || *keyword == "stroke")
+            .is_some_and(|keyword| keyword == "stroke")
+    }
+
+    /// The side of the outline `stroke-alignment` puts the stroke on.
+    pub fn stroke_align(&self) -> StrokeAlign {
+        StrokeAlign::from_attr(self.stroke_alignment.as_deref())
+    }
+
+    /// How `fill-rule` decides what is inside the path.
+    pub fn winding(&self) -> FillRule {
+        FillRule::from_attr(self.fill_rule.as_deref())
+    }
+
+    /// The cap `stroke-linecap` puts on the ends of open subpaths.
+    pub fn line_cap(&self) -> LineCap {
+        LineCap::from_attr(self.stroke_linecap.as_deref())
+    }
+
+    /// The join `stroke-linejoin` puts at corners.
+    pub fn line_join(&self) -> LineJoin {
+        LineJoin::from_attr(self.stroke_linejoin.as_deref())
+    }
+
+    /// The dash lengths of `stroke-dasharray`, or None for `none` or a list
+    /// that does not parse, which draw a solid stroke.
+    pub fn dash_array(&self) -> Option<Vec<f32>> {
+        let value = self.stroke_dasharray.as_deref()?.trim();
+        if value == "none" {
+            return None;
+        }
+        value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|length| !length.is_empty())
+            .map(|length| {
+                let length = length.strip_suffix("px").unwrap_or(length);
+                length
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|length| length.is_finite())
+            })
+            .collect()
+    }
+
+    /// The path's dashes in `lyon_path`'s coordinates, which are `scale`
+    /// times its own, or None for a solid stroke.
+    fn dashed(
+        &self,
+        lyon_path: &lyon::path::Path,
+        scale: f32,
+        tolerance: f32,
+    ) -> Option<lyon::path::Path> {
+        let dashes: Vec<f32> = self
+            .dash_array()?
+            .iter()
+            .map(|length| length * scale)
+            .collect();
+        let offset = self.stroke_dashoffset.unwrap_or(0.0) * scale;
+        Some(PathMeasure::from_path(lyon_path, tolerance).dash(&dashes, offset))
+    }
+
+    fn stroke_style(&self) -> StrokeStyle {
+        StrokeStyle {
+            line_cap: self.line_cap(),
+            line_join: self.line_join(),
+            miter_limit: self.stroke_miterlimit,
+        }
+    }
+}
+
+/// How overlapping subpaths fill, after SVG's `fill-rule`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillRule {
+    /// Inside where the subpaths wind around a point a nonzero number of
+    /// times, so a ring wound one way throughout fills its hole
+    #[default]
+    NonZero,
+    /// Inside where a ray from a point crosses an odd number of edges, so
+    /// nested subpaths cut holes whatever their direction
+    EvenOdd,
 }
 
+impl FillRule {
+    /// Parse a `fill-rule` value, treating anything unknown as `nonzero`.
+    pub(crate) fn from_attr(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some("evenodd") => FillRule::EvenOdd,
+            _ => FillRule::NonZero,
+        }
+    }
+
+    fn fill_options(self, tolerance: f32) -> FillOptions {
+        let rule = match self {
+            FillRule::NonZero => lyon::tessellation::FillRule::NonZero,
+            FillRule::EvenOdd => lyon::tessellation::FillRule::EvenOdd,
+        };
+        FillOptions::default()
+            .with_tolerance(tolerance)
+            .with_fill_rule(rule)
+    }
+}
+
+/// The shape at the ends of open subpaths, after SVG's `stroke-linecap`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineCap {
+    /// Square, ending at the endpoint
+    #[default]
+    Butt,
+    /// A semicircle of half the stroke width past the endpoint, flattened
+    /// to the tolerance like curves
+    Round,
+    /// Square, half the stroke width past the endpoint
+    Square,
+}
+
+impl LineCap {
+    /// Parse a `stroke-linecap` value, treating anything unknown as `butt`.
+    pub(crate) fn from_attr(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some("round") => LineCap::Round,
+            Some("square") => LineCap::Square,
+            _ => LineCap::Butt,
+        }
+    }
+}
+
+/// The shape at corners, after SVG's `stroke-linejoin`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LineJoin {
+    /// The outer edges extended to meet at a point, beveled instead where
+    /// the point would reach past the miter limit
+    #[default]
+    Miter,
+    /// Like `Miter`, but cut off at the miter limit instead of beveled
+    MiterClip,
+    /// A circular arc of half the stroke width around the corner
+    Round,
+    /// The outer edges joined by a straight line
+    Bevel,
+}
+
+impl LineJoin {
+    /// Parse a `stroke-linejoin` value, treating anything unknown, and SVG
+    /// 2's `arcs`, as `miter`.
+    pub(crate) fn from_attr(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some("miter-clip") => LineJoin::MiterClip,
+            Some("round") => LineJoin::Round,
+            Some("bevel") => LineJoin::Bevel,
+            _ => LineJoin::Miter,
+        }
+    }
+}
+
+/// How a stroke is drawn at its ends and corners.
+#[derive(Clone, Copy, Default)]
+struct StrokeStyle {
+    line_cap: LineCap,
+    line_join: LineJoin,
+    /// The longest miter, in stroke widths. Values below 1, which SVG
+    /// rejects, keep lyon's default of 4, which is also SVG's.
+    miter_limit: Option<f32>,
+}
+
+impl StrokeStyle {
+    fn stroke_options(self, line_width: f32, tolerance: f32) -> StrokeOptions {
+        let cap = match self.line_cap {
+            LineCap::Butt => lyon::tessellation::LineCap::Butt,
+            LineCap::Round => lyon::tessellation::LineCap::Round,
+            LineCap::Square => lyon::tessellation::LineCap::Square,
+        };
+        let join = match self.line_join {
+            LineJoin::Miter => lyon::tessellation::LineJoin::Miter,
+            LineJoin::MiterClip => lyon::tessellation::LineJoin::MiterClip,
+            LineJoin::Round => lyon::tessellation::LineJoin::Round,
+            LineJoin::Bevel => lyon::tessellation::LineJoin::Bevel,
+        };
+        let options = StrokeOptions::default()
+            .with_line_width(line_width)
+            .with_tolerance(tolerance)
+            .with_line_cap(cap)
+            .with_line_join(join);
+        match self.miter_limit {
+            Some(limit) if limit.is_finite() && limit >= StrokeOptions::MINIMUM_MITER_LIMIT => {
+                options.with_miter_limit(limit)
+            }
+            _ => options,
+        }
+    }
+}
+
+/// What a single-path function draws.
+#[derive(Clone, Copy)]
+enum Paint {
+    Fill(FillRule),
+    Stroke(StrokeOptions),
+}
+
+/// Options for `parse_svg` and `parse_svg_summary`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParseOptions {
+    /// Decimal places kept for fractional numbers in `d` strings and
+    /// summary bounds. Integers and arc flags are left as written.
+    pub precision: Option<u32>,
+    /// Longest `d` string, in bytes, returned in full. Applied after
+    /// `precision`.
+    pub max_d_length: Option<usize>,
+    /// Empty over-long `d` strings instead of cutting them at `max_d_length`.
+    pub omit_long_d: bool,
+    /// Fail on the first missing argument or unparsable token in any `d`
+    /// instead of reading it as 0. Only `parse_svg` applies it.
+    pub strict: bool,
+    /// The user's language, such as `de` or `en-US`, that picks between
+    /// `<switch>` children by `systemLanguage`. A stream parser reads it
+    /// from `SvgStreamParser::set_language` instead.
+    pub language: Option<String>,
+    /// The color `currentColor` fills and strokes take where the document
+    /// sets no `color`, as the `color` of the element an icon is shown in.
+    /// Left as `currentColor` without one.
+    pub current_color: Option<String>,
+    /// The font size `em` lengths such as `stroke-width="0.5em"` are
+    /// relative to, in user units. 16 without one. A stream parser reads it
+    /// from `SvgStreamParser::set_font_size` instead.
+    pub font_size: Option<f32>,
+}
+
+/// What `parse_svg` would return, minus the path data, for deciding how to
+/// tessellate a document before paying to transfer it.
+///
+/// Per-path fields are parallel arrays indexed by path, which serializes to
+/// a fraction of the size of one object per path for map-sized documents.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SvgSummary {
+    pub width: f32,
+    pub height: f32,
+    pub view_box: Option<ViewBox>,
+    #[serde(default)]
+    pub preserve_aspect_ratio: PreserveAspectRatio,
+    pub path_count: usize,
+    pub command_count: usize,
+    /// Total length of all `d` strings in bytes.
+    pub d_bytes: usize,
+    pub ids: Vec<Option<String>>,
+    /// Flat path bounds: [min_x, min_y, max_x, max_y, ...] in user space,
+    /// covering points and control points, so curves may lie inside them.
+    pub bounds: Vec<f32>,
+    pub command_counts: Vec<usize>,
+    pub d_lengths: Vec<usize>,
+    pub stroked: Vec<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ParsedSvg {
     pub width: f32,
     pub height: f32,
     pub view_box: Option<ViewBox>,
+    /// How the root element fits its native size into a display of another
+    /// aspect ratio.
+    #[serde(default)]
+    pub preserve_aspect_ratio: PreserveAspectRatio,
     pub paths: Vec<ParsedPath>,
+    /// Suspicious path data that still parsed, such as numbers written with
+    /// a comma as the decimal separator.
+    #[serde(default)]
+    pub warnings: Vec<PathDataIssue>,
+    /// Problems outside path data, such as skipped `foreignObject` blocks
+    /// or references to paints the document does not define, one entry per
+    /// kind.
+    #[serde(default)]
+    pub document_warnings: Vec<String>,
+    /// The title, description and ARIA labels a host needs to label the
+    /// rendered image for screen readers.
+    #[serde(default)]
+    pub accessibility: SvgAccessibility,
+    /// `<pattern>` definitions with an id, whose shapes are not in `paths`.
+    /// Always empty without the `patterns` feature.
+    #[serde(default)]
+    pub patterns: Vec<SvgPattern>,
+    /// `<linearGradient>` and `<radialGradient>` definitions with an id. Always empty without the
+    /// `gradients` feature.
+    #[serde(default)]
+    pub gradients: Vec<SvgGradient>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl ParsedSvg {
+    /// Read the colors of every path, including those of patterns, warning
+    /// about values that are not colors.
+    fn read_colors(&mut self) {
+        let mut invalid = Vec::new();
+        let pattern_paths = self
+            .patterns
+            .iter_mut()
+            .flat_map(|pattern| &mut pattern.paths);
+        for path in self.paths.iter_mut().chain(pattern_paths) {
+            path.read_colors(&mut invalid);
+        }
+        if !invalid.is_empty() {
+            self.document_warnings.push(format!(
+                "drew invalid colors black: \"{}\"",
+                invalid.join("\", \"")
+            ));
+        }
+    }
+
+    /// Resolve the `currentColor` paints of every path, including those of
+    /// patterns; see `ParsedPath::resolve_current_color`.
+    fn resolve_current_color(&mut self, current: Option<&str>) {
+        let pattern_paths = self
+            .patterns
+            .iter_mut()
+            .flat_map(|pattern| &mut pattern.paths);
+        for path in self.paths.iter_mut().chain(pattern_paths) {
+            path.resolve_current_color(current);
+        }
+    }
+}
+
+/// A problem in one path's `d` data: a warning from `parse_svg`, or the
+/// error that stopped it in strict mode.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PathDataIssue {
+    /// Index of the path in `ParsedSvg::paths`.
+    pub path: usize,
+    /// Index of the offending token in the path data, when one token is to
+    /// blame.
+    pub token: Option<usize>,
+    /// Byte offset in `d` of the offending token, or the length of `d` when
+    /// the data ended early.
+    #[serde(default)]
+    pub offset: Option<usize>,
+    /// The offending token as written, or None when the data ended early.
+    #[serde(default)]
+    pub found: Option<String>,
+    /// The command whose arguments were being read.
+    #[serde(default)]
+    pub command: Option<char>,
+    pub message: String,
+    /// The surrounding path data, with the offending token in brackets.
+    pub context: String,
+}
+
+impl std::fmt::Display for PathDataIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Path {}", self.path)?;
+        if let Some(token) = self.token {
+            write!(f, ", token {}", token)?;
+        }
+        write!(f, ": {} (near \"{}\")", self.message, self.context)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ViewBox {
     pub x: f32,
     pub y: f32,
@@ -119,29 +781,284 @@ pub struct ViewBox {
     pub height: f32,
 }
 
+/// Options for `tessellate_svg`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TessellateOptions {
+    /// Snap horizontal and vertical lines to the pixel grid after scaling:
+    /// fills to whole pixels, odd-width strokes to half-pixel centers. Stroke
+    /// widths of snapped paths round to whole pixels, ties down. Curve control
+    /// points are never moved, though a curve starting at a snapped corner
+    /// starts at the snapped position.
+    pub snap_axis_aligned: bool,
+    /// Fill each mesh's `boundary_edges`, for outline and glow effects.
+    pub boundary_edges: bool,
+    /// Paths the host animates. Their meshes are tessellated without their
+    /// transform, at the display scale, and carry it as
+    /// `TessellatedMesh::transform` instead, so they stay valid while the
+    /// transform changes. They are not snapped. Other paths have their
+    /// transform baked in.
+    pub dynamic_paths: Vec<DynamicPath>,
+    /// Return the fills of paths filled with a `<pattern>` as
+    /// `SvgTessellation::pattern_fills`, with the tile and where it repeats,
+    /// instead of in `meshes`. Dynamic paths keep theirs in `meshes`. Needs
+    /// the `patterns` feature.
+    pub pattern_fills: bool,
+    /// The user's language, as for `ParseOptions::language`.
+    pub language: Option<String>,
+    /// The color of `currentColor`, as for `ParseOptions::current_color`.
+    /// Without one, meshes painted with `currentColor` are black and set
+    /// `TessellatedMesh::uses_current_color`.
+    pub current_color: Option<String>,
+    /// The font size of `em` lengths, as for `ParseOptions::font_size`.
+    pub font_size: Option<f32>,
+    /// Drop each path whose data has a missing argument or unparsable token,
+    /// reporting it in `SvgTessellation::errors`. Otherwise such data reads
+    /// as `parse_svg` reads it, with 0 for what is missing, and the path
+    /// draws with the problem in `SvgTessellation::warnings`.
+    pub strict: bool,
+}
+
+/// Options for `tessellate_stroke_with_options`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrokeWidthOptions {
+    /// Thickness across vertical segments in output units, after offset and
+    /// scale. Defaults to the stroke width times the larger scale.
+    pub stroke_width_x: Option<f32>,
+    /// Thickness across horizontal segments in output units. Defaults like
+    /// `stroke_width_x`.
+    pub stroke_width_y: Option<f32>,
+    /// The cap on the ends of open subpaths.
+    pub line_cap: LineCap,
+    /// The join at corners.
+    pub line_join: LineJoin,
+    /// The longest miter join, in stroke widths. Defaults to 4.
+    pub miter_limit: Option<f32>,
+}
+
+impl StrokeWidthOptions {
+    fn stroke_style(&self) -> StrokeStyle {
+        StrokeStyle {
+            line_cap: self.line_cap,
+            line_join: self.line_join,
+            miter_limit: self.miter_limit,
+        }
+    }
+}
+
+/// Options for `tessellate_dashed_stroke`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DashedStrokeOptions {
+    /// The cap on the ends of each dash.
+    pub line_cap: LineCap,
+    /// The join at corners within a dash.
+    pub line_join: LineJoin,
+    /// The longest miter join, in stroke widths. Defaults to 4.
+    pub miter_limit: Option<f32>,
+}
+
+impl DashedStrokeOptions {
+    fn stroke_style(&self) -> StrokeStyle {
+        StrokeStyle {
+            line_cap: self.line_cap,
+            line_join: self.line_join,
+            miter_limit: self.miter_limit,
+        }
+    }
+}
+
+/// Options for `tessellate_path_with_transform`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathPaintOptions {
+    /// Stroke at this width in path units instead of filling.
+    pub stroke_width: Option<f32>,
+    /// Which side of the outline the stroke covers. Inner and outer strokes
+    /// do not fill `source_commands`.
+    pub stroke_align: StrokeAlign,
+    /// Fill the mesh's `source_commands` and `command_ranges`, so a host can
+    /// map what it hits back to the path data.
+    pub source_commands: bool,
+    /// How a fill treats overlapping subpaths.
+    pub fill_rule: FillRule,
+    /// The cap on the ends of open subpaths of a stroke.
+    pub line_cap: LineCap,
+    /// The join at the corners of a stroke.
+    pub line_join: LineJoin,
+    /// The longest miter join of a stroke, in stroke widths. Defaults to 4.
+    pub miter_limit: Option<f32>,
+}
+
+impl PathPaintOptions {
+    fn stroke_style(&self) -> StrokeStyle {
+        StrokeStyle {
+            line_cap: self.line_cap,
+            line_join: self.line_join,
+            miter_limit: self.miter_limit,
+        }
+    }
+}
+
+/// A requested display size for multi-resolution tessellation.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DisplaySize {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// One entry of `tessellate_svg_lods`: what `tessellate_svg` returns at a
+/// display size, tagged with the size.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LodMeshSet {
+    pub display_width: f32,
+    pub display_height: f32,
+    pub tessellation: SvgTessellation,
+}
+
+/// Opaque handle to a coarse tessellation from `tessellate_svg_progressive`.
+/// It goes stale when cancelled or when the tessellator is reset, and is
+/// never reissued.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProgressiveId(u64);
+
+#[wasm_bindgen]
+impl ProgressiveId {
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A coarse tessellation waiting to be refined. Holds the parsed document
+/// so `refine` does not parse it again.
+struct ProgressiveTessellation {
+    parsed: ParsedSvg,
+    commands: Vec<SvgCommand>,
+    ranges: Vec<(usize, usize)>,
+    size: DisplaySize,
+    /// Path, paint and paint order of each coarse mesh, in order
+    layout: Vec<(usize, bool, u32)>,
+    coarse: Vec<PaintedMesh>,
+}
+
+/// Scratch memory held by a tessellator, from `memory_report`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MemoryReport {
+    /// Bytes currently reserved by the per-call arena.
+    pub arena_capacity_bytes: usize,
+    /// Largest arena reservation at the end of any call.
+    pub arena_peak_bytes: usize,
+    /// Number of public calls that have reset the arena.
+    pub arena_resets: u64,
+}
+
 #[wasm_bindgen]
 pub struct SvgTessellator {
+    /// Lyon's tessellators are reused for every path. They hold no state
+    /// from one call to the next beyond buffer capacity, so need no reset.
     fill_tessellator: FillTessellator,
-    stroke_tessellator: StrokeTessellator,
+    stroke_tessellator: Stroker,
+    arena: Arena<SvgCommand, TessVertex>,
+    /// Rewind emitted triangles to one orientation, see `TessellatedMesh`
+    consistent_winding: bool,
+    /// Coarse tessellations from `tessellate_svg_progressive` not yet
+    /// cancelled
+    progressive: HandleRegistry<ProgressiveTessellation>,
 }
 
 #[wasm_bindgen]
 impl SvgTessellator {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        Self {
-            fill_tessellator: FillTessellator::new(),
-            stroke_tessellator: StrokeTessellator::new(),
+        Self::with_arena(cfg!(feature = "arena"))
+    }
+
+    /// Report the scratch memory retained between calls.
+    #[wasm_bindgen]
+    pub fn memory_report(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.memory_report_native())
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Return the tessellator to the state of a new instance for reuse from
+    /// a pool: scratch memory is released, memory counters are zeroed and
+    /// progressive handles go stale. With `keep_config` set, settings such
+    /// as consistent winding are kept.
+    #[wasm_bindgen]
+    pub fn reset(&mut self, keep_config: bool) {
+        let consistent_winding = self.consistent_winding;
+        // A fresh registry would reissue handles the host may still hold
+        let mut progressive = std::mem::take(&mut self.progressive);
+        progressive.clear();
+        *self = Self {
+            progressive,
+            ..Self::with_arena(self.arena.retain)
+        };
+        if keep_config {
+            self.consistent_winding = consistent_winding;
         }
     }
 
+    /// Release scratch memory retained between calls, keeping settings and
+    /// counters. The next call reallocates what it needs.
+    #[wasm_bindgen]
+    pub fn shrink_to_fit(&mut self) {
+        self.arena.shrink_to_fit();
+        // Lyon's tessellators keep their internal buffers between calls, and
+        // fresh ones behave identically
+        self.fill_tessellator = FillTessellator::new();
+        self.stroke_tessellator = Stroker::new();
+    }
+
+    /// Enable or disable rewinding triangles to a single orientation and
+    /// dropping zero-area ones. On by default; renderers that do not cull
+    /// back faces can turn it off to skip the pass.
+    #[wasm_bindgen]
+    pub fn set_consistent_winding(&mut self, enabled: bool) {
+        self.consistent_winding = enabled;
+    }
+
+    /// Parse an SVG into paths. `options_js` is an optional `ParseOptions`
+    /// for capping the size of returned `d` strings or rejecting malformed
+    /// path data.
     #[wasm_bindgen]
-    pub fn parse_svg(&self, svg_content: &str) -> Result<JsValue, JsValue> {
-        let parsed = parse_svg_content(svg_content);
+    pub fn parse_svg(&self, svg_content: &str, options_js: JsValue) -> Result<JsValue, JsValue> {
+        let options = parse_options(options_js)?;
+        let parsed = self
+            .parse_svg_native(svg_content, &options)
+            .map_err(|e| JsValue::from_str(&format!("Invalid path data: {}", e)))?;
         serde_wasm_bindgen::to_value(&parsed)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
+    /// Summarize an SVG's size, path count and per-path bounds without
+    /// returning any path data. Only `precision` of `options_js` applies.
+    #[wasm_bindgen]
+    pub fn parse_svg_summary(
+        &mut self,
+        svg_content: &str,
+        options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options = parse_options(options_js)?;
+        let summary = self.parse_svg_summary_native(svg_content, &options);
+        serde_wasm_bindgen::to_value(&summary)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// A 64-bit hash of `svg_content` as 16 hex digits, for memoizing
+    /// parsing and tessellation. It covers the whole document, byte for
+    /// byte, and nothing else: key options and `set_consistent_winding`
+    /// alongside it.
+    #[wasm_bindgen]
+    pub fn hash_svg(&self, svg_content: &str) -> String {
+        content_hash::to_hex(self.hash_svg_native(svg_content))
+    }
+
+    /// Fill a path. `fill_rule` is `"nonzero"`, the default, or
+    /// `"evenodd"`.
     #[wasm_bindgen]
     pub fn tessellate_path(
         &mut self,
@@ -150,21 +1067,12 @@ impl SvgTessellator {
         offset_y: f32,
         scale_x: f32,
         scale_y: f32,
+        fill_rule: Option<String>,
     ) -> Result<JsValue, JsValue> {
-        let commands = parse_svg_path_d(path_d);
-        let path = build_lyon_path(&commands, offset_x, offset_y, scale_x, scale_y);
-
-        let mut buffers: VertexBuffers<TessVertex, u32> = VertexBuffers::new();
-
-        self.fill_tessellator
-            .tessellate_path(
-                &path,
-                &FillOptions::default().with_tolerance(0.1),
-                &mut BuffersBuilder::new(&mut buffers, VertexWithEdge),
-            )
+        let fill_rule = FillRule::from_attr(fill_rule.as_deref());
+        let mesh = self
+            .tessellate_path_native(path_d, offset_x, offset_y, scale_x, scale_y, fill_rule)
             .map_err(|e| JsValue::from_str(&format!("Tessellation error: {:?}", e)))?;
-
-        let mesh = build_mesh(buffers);
         serde_wasm_bindgen::to_value(&mesh)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
@@ -179,812 +1087,7131 @@ impl SvgTessellator {
         scale_x: f32,
         scale_y: f32,
     ) -> Result<JsValue, JsValue> {
-        let commands = parse_svg_path_d(path_d);
-        let path = build_lyon_path(&commands, offset_x, offset_y, scale_x, scale_y);
-
-        let mut buffers: VertexBuffers<TessVertex, u32> = VertexBuffers::new();
+        let mesh = self
+            .tessellate_stroke_native(path_d, stroke_width, offset_x, offset_y, scale_x, scale_y)
+            .map_err(|e| JsValue::from_str(&format!("Tessellation error: {:?}", e)))?;
+        serde_wasm_bindgen::to_value(&mesh)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-        self.stroke_tessellator
-            .tessellate_path(
-                &path,
-                &StrokeOptions::default()
-                    .with_line_width(stroke_width * scale_x.max(scale_y))
-                    .with_tolerance(0.1),
-                &mut BuffersBuilder::new(&mut buffers, VertexWithEdge),
-            )
+    /// `tessellate_stroke` with the placement given as `[offset_x, offset_y,
+    /// scale_x, scale_y]`. `options_js` is an optional `StrokeWidthOptions`,
+    /// for outputs stretched unevenly that need a different thickness along
+    /// each axis.
+    #[wasm_bindgen]
+    pub fn tessellate_stroke_with_options(
+        &mut self,
+        path_d: &str,
+        stroke_width: f32,
+        placement: &[f32],
+        options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let placement: [f32; 4] = placement.try_into().map_err(|_| {
+            JsValue::from_str(&format!(
+                "Placement must have 4 values (offset_x offset_y scale_x scale_y), got {}",
+                placement.len()
+            ))
+        })?;
+        let options: StrokeWidthOptions = if options_js.is_undefined() || options_js.is_null() {
+            StrokeWidthOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?
+        };
+        let mesh = self
+            .tessellate_stroke_with_options_native(path_d, stroke_width, placement, &options)
             .map_err(|e| JsValue::from_str(&format!("Tessellation error: {:?}", e)))?;
+        serde_wasm_bindgen::to_value(&mesh)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-        let mesh = build_mesh(buffers);
+    /// Tessellate a path placed by a full affine transform `matrix`, given
+    /// as `[a, b, c, d, e, f]` like SVG's `matrix()`. Vertices and bounds are
+    /// in transformed coordinates. `options_js` is an optional
+    /// `PathPaintOptions`; see `tessellate_path_with_transform_native` for
+    /// how stroke width is scaled.
+    #[wasm_bindgen]
+    pub fn tessellate_path_with_transform(
+        &mut self,
+        path_d: &str,
+        matrix: &[f32],
+        options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let matrix: [f32; 6] = matrix.try_into().map_err(|_| {
+            JsValue::from_str(&format!(
+                "Transform must have 6 values (a b c d e f), got {}",
+                matrix.len()
+            ))
+        })?;
+        let options: PathPaintOptions = if options_js.is_undefined() || options_js.is_null() {
+            PathPaintOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?
+        };
+        let mesh = self
+            .tessellate_path_with_transform_native(path_d, matrix, &options)
+            .map_err(|e| JsValue::from_str(&format!("Tessellation error: {:?}", e)))?;
         serde_wasm_bindgen::to_value(&mesh)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
+    /// Fill a path for a radial gradient, adding each vertex's exact distance
+    /// from the gradient center. `gradient_js` is an optional
+    /// `RadialGradientInput`; with a `refine_tolerance`, triangles are split
+    /// near the center until interpolated distances are that accurate.
+    #[cfg(feature = "gradients")]
     #[wasm_bindgen]
-    pub fn tessellate_svg(
+    pub fn tessellate_radial_gradient_fill(
         &mut self,
-        svg_content: &str,
-        display_width: f32,
-        display_height: f32,
+        path_d: &str,
+        gradient_js: JsValue,
     ) -> Result<JsValue, JsValue> {
-        let parsed = parse_svg_content(svg_content);
-        let native_width = parsed
-            .view_box
-            .as_ref()
-            .map(|v| v.width)
-            .unwrap_or(parsed.width);
-        let native_height = parsed
-            .view_box
-            .as_ref()
-            .map(|v| v.height)
-            .unwrap_or(parsed.height);
-
-        let scale_x = display_width / native_width;
-        let scale_y = display_height / native_height;
-
-        let mut all_meshes: Vec<TessellatedMesh> = Vec::new();
-
-        for path in &parsed.paths {
-            if path.fill.as_deref() != Some("none") {
-                let commands = parse_svg_path_d(&path.d);
-                let lyon_path = build_lyon_path(&commands, 0.0, 0.0, scale_x, scale_y);
-
-                let mut buffers: VertexBuffers<TessVertex, u32> = VertexBuffers::new();
-
-                if self
-                    .fill_tessellator
-                    .tessellate_path(
-                        &lyon_path,
-                        &FillOptions::default().with_tolerance(0.1),
-                        &mut BuffersBuilder::new(&mut buffers, VertexWithEdge),
-                    )
-                    .is_ok()
-                    && !buffers.vertices.is_empty()
-                {
-                    all_meshes.push(build_mesh(buffers));
-                }
-            }
+        let gradient: RadialGradientInput = if gradient_js.is_undefined() || gradient_js.is_null() {
+            RadialGradientInput::default()
+        } else {
+            serde_wasm_bindgen::from_value(gradient_js)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?
+        };
+        let mesh = self
+            .tessellate_radial_gradient_fill_native(path_d, &gradient)
+            .map_err(|e| JsValue::from_str(&format!("Tessellation error: {:?}", e)))?;
+        serde_wasm_bindgen::to_value(&mesh)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-            if let (Some(stroke), Some(stroke_width)) = (&path.stroke, path.stroke_width) {
-                if stroke != "none" {
-                    let commands = parse_svg_path_d(&path.d);
-                    let lyon_path = build_lyon_path(&commands, 0.0, 0.0, scale_x, scale_y);
+    #[cfg(not(feature = "gradients"))]
+    #[wasm_bindgen]
+    pub fn tessellate_radial_gradient_fill(
+        &mut self,
+        _path_d: &str,
+        _gradient_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        Err(feature_disabled("gradients"))
+    }
 
-                    let mut buffers: VertexBuffers<TessVertex, u32> = VertexBuffers::new();
+    /// Tessellate a polygon or polyline given as flat x, y pairs, such as a
+    /// Float32Array, without building and parsing a `d` string. A pair with a
+    /// NaN coordinate ends the current subpath. Fills when `fill` is set and
+    /// strokes with `stroke_width` otherwise; `closed` closes every subpath.
+    #[wasm_bindgen]
+    pub fn tessellate_polygon_f32(
+        &mut self,
+        points: &[f32],
+        closed: bool,
+        fill: bool,
+        stroke_width: f32,
+    ) -> Result<JsValue, JsValue> {
+        let mesh = self
+            .tessellate_points_native(points, closed, (!fill).then_some(stroke_width))
+            .map_err(|e| JsValue::from_str(&format!("Tessellation error: {:?}", e)))?;
+        serde_wasm_bindgen::to_value(&mesh)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-                    if self
-                        .stroke_tessellator
-                        .tessellate_path(
-                            &lyon_path,
-                            &StrokeOptions::default()
-                                .with_line_width(stroke_width * scale_x.max(scale_y))
-                                .with_tolerance(0.1),
-                            &mut BuffersBuilder::new(&mut buffers, VertexWithEdge),
-                        )
-                        .is_ok()
-                        && !buffers.vertices.is_empty()
-                    {
-                        all_meshes.push(build_mesh(buffers));
-                    }
-                }
-            }
-        }
+    /// Stroke an open polyline given as flat x, y pairs. Shorthand for
+    /// `tessellate_polygon_f32(points, false, false, stroke_width)`.
+    #[wasm_bindgen]
+    pub fn tessellate_polyline_f32(
+        &mut self,
+        points: &[f32],
+        stroke_width: f32,
+    ) -> Result<JsValue, JsValue> {
+        self.tessellate_polygon_f32(points, false, false, stroke_width)
+    }
 
-        serde_wasm_bindgen::to_value(&all_meshes)
+    /// Stroke a path with an SVG `stroke-dasharray` pattern, shifted by
+    /// `dash_offset` as in `stroke-dashoffset`. `options_js` is an optional
+    /// `DashedStrokeOptions`; with round or square caps, zero-length dashes
+    /// draw dots.
+    #[wasm_bindgen]
+    pub fn tessellate_dashed_stroke(
+        &mut self,
+        path_d: &str,
+        stroke_width: f32,
+        dashes: &[f32],
+        dash_offset: f32,
+        options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options: DashedStrokeOptions = if options_js.is_undefined() || options_js.is_null() {
+            DashedStrokeOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?
+        };
+        let mesh = self
+            .tessellate_dashed_stroke_native(path_d, stroke_width, dashes, dash_offset, &options)
+            .map_err(|e| JsValue::from_str(&format!("Tessellation error: {:?}", e)))?;
+        serde_wasm_bindgen::to_value(&mesh)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
-}
 
-impl Default for SvgTessellator {
-    fn default() -> Self {
-        Self::new()
+    /// The point `distance` along a path and its direction, or None for a
+    /// path with no length. Hosts sampling one path repeatedly should keep a
+    /// `PathMeasure` instead.
+    #[wasm_bindgen]
+    pub fn point_at_length(&mut self, path_d: &str, distance: f32) -> Option<PathSample> {
+        let path = self.build_single_path(path_d, 0.0, 0.0, 1.0, 1.0);
+        PathMeasure::from_path(&path, TESSELLATION_TOLERANCE).sample(distance)
     }
-}
 
-fn build_mesh(buffers: VertexBuffers<TessVertex, u32>) -> TessellatedMesh {
-    let mut bounds = MeshBounds::new();
-    // 3 floats per vertex: x, y, edge_dist
-    let mut vertices: Vec<f32> = Vec::with_capacity(buffers.vertices.len() * 3);
+    /// Parse one path's `d` strictly, as `ParseOptions::strict` does each
+    /// path of a document. Returns the `PathDataIssue` that stopped it, or
+    /// undefined when it parses.
+    #[wasm_bindgen]
+    pub fn check_path_d(&self, path_d: &str) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&parse_svg_path_d_checked(path_d).err())
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-    for v in &buffers.vertices {
-        vertices.push(v.x);
-        vertices.push(v.y);
-        vertices.push(v.edge_dist);
-        bounds.expand(v.x, v.y);
+    /// Tessellate every path of an SVG at a display size, fit into it as
+    /// the document's `preserveAspectRatio` asks. `options_js` is an
+    /// optional `TessellateOptions`. Returns an `SvgTessellation`: a path
+    /// that fails to tessellate, or to parse with `strict`, is reported in
+    /// `errors` and the rest of the document is still tessellated. Throws
+    /// on a negative or NaN display size.
+    #[wasm_bindgen]
+    pub fn tessellate_svg(
+        &mut self,
+        svg_content: &str,
+        display_width: f32,
+        display_height: f32,
+        options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options: TessellateOptions = if options_js.is_undefined() || options_js.is_null() {
+            TessellateOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?
+        };
+        let tessellation = self
+            .tessellate_svg_document(svg_content, display_width, display_height, &options)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_wasm_bindgen::to_value(&tessellation)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    if !bounds.is_valid() {
-        bounds = MeshBounds::default();
+    /// Tessellate one SVG at several display sizes, parsing it only once.
+    ///
+    /// `display_sizes_js` is an array of `{ width, height }` and
+    /// `options_js` an optional `TessellateOptions`. Each entry of the
+    /// returned array holds the `SvgTessellation` that `tessellate_svg`
+    /// returns for one size with the same options. Throws on a negative or
+    /// NaN display size.
+    #[wasm_bindgen]
+    pub fn tessellate_svg_lods(
+        &mut self,
+        svg_content: &str,
+        display_sizes_js: JsValue,
+        options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let sizes: Vec<DisplaySize> = serde_wasm_bindgen::from_value(display_sizes_js)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse display sizes: {}", e)))?;
+        let options: TessellateOptions = if options_js.is_undefined() || options_js.is_null() {
+            TessellateOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?
+        };
+
+        let lods = self
+            .tessellate_svg_lod_meshes(svg_content, &sizes, &options)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_wasm_bindgen::to_value(&lods)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    TessellatedMesh {
-        vertices,
-        indices: buffers.indices,
-        bounds,
+    /// Tessellate an SVG coarsely for a first paint, returning a handle
+    /// that holds the coarse meshes and can be passed to `refine`.
+    #[wasm_bindgen]
+    pub fn tessellate_svg_progressive(
+        &mut self,
+        svg_content: &str,
+        display_width: f32,
+        display_height: f32,
+    ) -> ProgressiveId {
+        self.tessellate_svg_progressive_native(svg_content, display_width, display_height)
     }
-}
 
-#[derive(Clone, Debug)]
-enum SvgCommand {
-    MoveTo {
-        x: f32,
-        y: f32,
-        relative: bool,
-    },
-    LineTo {
-        x: f32,
-        y: f32,
-        relative: bool,
-    },
-    HLineTo {
-        x: f32,
-        relative: bool,
-    },
-    VLineTo {
-        y: f32,
-        relative: bool,
-    },
-    CubicTo {
-        x1: f32,
-        y1: f32,
-        x2: f32,
-        y2: f32,
-        x: f32,
-        y: f32,
-        relative: bool,
-    },
-    SmoothCubicTo {
-        x2: f32,
-        y2: f32,
-        x: f32,
-        y: f32,
-        relative: bool,
-    },
-    QuadTo {
-        x1: f32,
-        y1: f32,
-        x: f32,
-        y: f32,
-        relative: bool,
-    },
-    SmoothQuadTo {
-        x: f32,
-        y: f32,
-        relative: bool,
-    },
-    ArcTo {
-        rx: f32,
-        ry: f32,
-        rotation: f32,
-        large_arc: bool,
-        sweep: bool,
-        x: f32,
-        y: f32,
-        relative: bool,
-    },
-    Close,
-}
+    /// Take the coarse meshes behind `handle` as `PaintedMesh[]`. Later
+    /// calls return an empty array.
+    #[wasm_bindgen]
+    pub fn take_coarse(&mut self, handle: &ProgressiveId) -> Result<JsValue, JsValue> {
+        let coarse = self
+            .take_coarse_native(handle)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_wasm_bindgen::to_value(&coarse)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-fn parse_svg_path_d(d: &str) -> Vec<SvgCommand> {
-    let mut commands = Vec::new();
-    let tokens = tokenize_svg_path(d);
-    if tokens.is_empty() {
-        return commands;
+    /// Tessellate the document behind `handle` at full detail. The result
+    /// has one mesh per coarse mesh, in the same order and with the same
+    /// path and paint, so buffers can be swapped in place.
+    #[wasm_bindgen]
+    pub fn refine(&mut self, handle: &ProgressiveId) -> Result<JsValue, JsValue> {
+        let meshes = self
+            .refine_native(handle)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_wasm_bindgen::to_value(&meshes)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    let mut i = 0;
-    let mut current_cmd = 'M';
+    /// Drop the document behind `handle`; the handle goes stale. Cancelling
+    /// a stale or unknown handle does nothing but count toward
+    /// `double_frees`.
+    #[wasm_bindgen]
+    pub fn cancel_progressive(&mut self, handle: &ProgressiveId) {
+        self.cancel_progressive_native(handle);
+    }
+
+    /// Number of cancels of handles that were already cancelled or never
+    /// issued, which point at a host bug.
+    #[wasm_bindgen]
+    pub fn double_frees(&self) -> u64 {
+        self.progressive.double_frees()
+    }
+
+    /// Rasterize a mesh on the CPU into RGBA8 pixels on a transparent
+    /// background. `color` is packed as 0xRRGGBBAA.
+    #[wasm_bindgen]
+    pub fn rasterize_mesh(
+        &self,
+        mesh_js: JsValue,
+        width: u32,
+        height: u32,
+        color: u32,
+    ) -> Result<Vec<u8>, JsValue> {
+        let mesh: TessellatedMesh = serde_wasm_bindgen::from_value(mesh_js)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse mesh: {}", e)))?;
+        Ok(rasterize_mesh(&mesh, width, height, color.to_be_bytes()))
+    }
+
+    /// Tessellate an SVG at `width` x `height` and rasterize it on the CPU
+    /// into RGBA8 pixels, compositing each mesh with its fill or stroke color
+    /// in the order `tessellate_svg` returns them.
+    #[wasm_bindgen]
+    pub fn rasterize_svg(&mut self, svg_content: &str, width: u32, height: u32) -> Vec<u8> {
+        let parsed = parse_svg_content(svg_content, None, None, None);
+        let (commands, ranges) = self.parse_path_commands(&parsed, false, &mut Vec::new());
+        let size = DisplaySize {
+            width: width as f32,
+            height: height as f32,
+        };
+        let (painted, _) = self.tessellate_parsed(
+            &parsed,
+            &commands,
+            &ranges,
+            size,
+            &TessellateOptions::default(),
+            TESSELLATION_TOLERANCE,
+        );
+        self.restore_commands(commands, ranges);
+        self.arena.reset();
 
-    let parse_number = |tokens: &[String], i: &mut usize| -> f32 {
-        if *i >= tokens.len() {
-            return 0.0;
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        for painted in &painted {
+            if let Some(color) = painted.color(&parsed, &ColorMap::new()) {
+                composite_mesh(&mut pixels, &painted.mesh, width, height, color);
+            }
         }
-        let val = tokens[*i].parse::<f32>().unwrap_or(0.0);
-        *i += 1;
-        val
-    };
+        pixels
+    }
 
-    let parse_flag = |tokens: &[String], i: &mut usize| -> bool {
-        if *i >= tokens.len() {
-            return false;
+    /// Decode a mesh asset blob written by the native `encode_meshes`,
+    /// returning an array of `{ mesh, color, stroke }`.
+    #[wasm_bindgen]
+    pub fn decode_meshes(&self, bytes: &[u8]) -> Result<JsValue, JsValue> {
+        let meshes = decode_meshes(bytes)
+            .map_err(|e| JsValue::from_str(&format!("Failed to decode meshes: {}", e)))?;
+        serde_wasm_bindgen::to_value(&meshes)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Load a scene bundle written by the native `compile_scene`.
+    #[wasm_bindgen]
+    pub fn load_scene(&self, bytes: &[u8]) -> Result<Scene, JsValue> {
+        Scene::decode(bytes)
+            .map_err(|e| JsValue::from_str(&format!("Failed to decode scene: {}", e)))
+    }
+
+    /// The `SceneEntry` compiled for `name` at the smallest size covering
+    /// `display_width` x `display_height`, or at its largest size when none
+    /// does. Undefined when the scene has no entry named `name`.
+    #[wasm_bindgen]
+    pub fn get_scene_mesh(
+        &self,
+        scene: &Scene,
+        name: &str,
+        display_width: f32,
+        display_height: f32,
+    ) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&scene.entry(name, display_width, display_height))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+}
+
+impl SvgTessellator {
+    fn with_arena(retain: bool) -> Self {
+        Self {
+            fill_tessellator: FillTessellator::new(),
+            stroke_tessellator: Stroker::new(),
+            arena: Arena::new(retain),
+            consistent_winding: true,
+            progressive: HandleRegistry::new(),
         }
-        let val = &tokens[*i];
-        *i += 1;
-        val == "1"
-    };
+    }
+
+    /// Native counterpart of `memory_report`.
+    pub fn memory_report_native(&self) -> MemoryReport {
+        MemoryReport {
+            arena_capacity_bytes: self.arena.capacity_bytes(),
+            arena_peak_bytes: self.arena.peak_bytes(),
+            arena_resets: self.arena.resets(),
+        }
+    }
+
+    /// Native counterpart of `hash_svg`.
+    pub fn hash_svg_native(&self, svg_content: &str) -> u64 {
+        content_hash::xxh64(svg_content.as_bytes())
+    }
 
-    let is_command = |token: &str| -> bool {
-        matches!(
-            token,
-            "M" | "m"
-                | "L"
-                | "l"
-                | "H"
-                | "h"
-                | "V"
-                | "v"
-                | "C"
-                | "c"
-                | "S"
-                | "s"
-                | "Q"
-                | "q"
-                | "T"
-                | "t"
-                | "A"
-                | "a"
-                | "Z"
-                | "z"
+    /// Native counterpart of `parse_svg`. Only fails in strict mode.
+    pub fn parse_svg_native(
+        &self,
+        svg_content: &str,
+        options: &ParseOptions,
+    ) -> Result<ParsedSvg, PathDataIssue> {
+        apply_parse_options(
+            parse_svg_content(
+                svg_content,
+                options.language.as_deref(),
+                None,
+                options.font_size,
+            ),
+            options,
         )
-    };
+    }
 
-    while i < tokens.len() {
-        let token = &tokens[i];
+    /// Native counterpart of `parse_svg_summary`.
+    pub fn parse_svg_summary_native(
+        &mut self,
+        svg_content: &str,
+        options: &ParseOptions,
+    ) -> SvgSummary {
+        let parsed = parse_svg_content(
+            svg_content,
+            options.language.as_deref(),
+            None,
+            options.font_size,
+        );
+        let (commands, ranges) = self.parse_path_commands(&parsed, false, &mut Vec::new());
 
-        if is_command(token) {
-            current_cmd = token.chars().next().unwrap();
-            i += 1;
-            if i >= tokens.len() && current_cmd.to_ascii_uppercase() != 'Z' {
-                break;
-            }
+        let mut bounds = Vec::with_capacity(ranges.len() * 4);
+        for &(start, end) in &ranges {
+            let path_bounds = command_bounds(&commands[start..end]);
+            bounds.extend(
+                [
+                    path_bounds.min_x,
+                    path_bounds.min_y,
+                    path_bounds.max_x,
+                    path_bounds.max_y,
+                ]
+                .map(|value| round_to_precision(value, options.precision)),
+            );
         }
+        let command_counts: Vec<usize> = ranges.iter().map(|(start, end)| end - start).collect();
+        self.restore_commands(commands, ranges);
+        self.arena.reset();
 
-        let relative = current_cmd.is_ascii_lowercase();
-        let cmd = current_cmd.to_ascii_uppercase();
+        let d_lengths: Vec<usize> = parsed.paths.iter().map(|path| path.d.len()).collect();
+        SvgSummary {
+            width: parsed.width,
+            height: parsed.height,
+            path_count: parsed.paths.len(),
+            command_count: command_counts.iter().sum(),
+            d_bytes: d_lengths.iter().sum(),
+            ids: parsed.paths.iter().map(|path| path.id.clone()).collect(),
+            bounds,
+            command_counts,
+            d_lengths,
+            stroked: parsed
+                .paths
+                .iter()
+                .map(|path| matches!(&path.stroke, Some(stroke) if stroke != "none"))
+                .collect(),
+            view_box: parsed.view_box,
+            preserve_aspect_ratio: parsed.preserve_aspect_ratio,
+        }
+    }
 
-        match cmd {
-            'M' => {
-                commands.push(SvgCommand::MoveTo {
-                    x: parse_number(&tokens, &mut i),
-                    y: parse_number(&tokens, &mut i),
-                    relative,
-                });
-                current_cmd = if relative { 'l' } else { 'L' };
-            }
-            'L' => {
-                commands.push(SvgCommand::LineTo {
-                    x: parse_number(&tokens, &mut i),
-                    y: parse_number(&tokens, &mut i),
-                    relative,
-                });
-            }
-            'H' => {
-                commands.push(SvgCommand::HLineTo {
-                    x: parse_number(&tokens, &mut i),
-                    relative,
-                });
-            }
-            'V' => {
-                commands.push(SvgCommand::VLineTo {
-                    y: parse_number(&tokens, &mut i),
-                    relative,
-                });
-            }
-            'C' => {
-                commands.push(SvgCommand::CubicTo {
-                    x1: parse_number(&tokens, &mut i),
-                    y1: parse_number(&tokens, &mut i),
-                    x2: parse_number(&tokens, &mut i),
-                    y2: parse_number(&tokens, &mut i),
-                    x: parse_number(&tokens, &mut i),
-                    y: parse_number(&tokens, &mut i),
-                    relative,
-                });
-            }
-            'S' => {
-                commands.push(SvgCommand::SmoothCubicTo {
-                    x2: parse_number(&tokens, &mut i),
-                    y2: parse_number(&tokens, &mut i),
-                    x: parse_number(&tokens, &mut i),
-                    y: parse_number(&tokens, &mut i),
-                    relative,
-                });
-            }
-            'Q' => {
-                commands.push(SvgCommand::QuadTo {
-                    x1: parse_number(&tokens, &mut i),
-                    y1: parse_number(&tokens, &mut i),
-                    x: parse_number(&tokens, &mut i),
-                    y: parse_number(&tokens, &mut i),
-                    relative,
-                });
-            }
-            'T' => {
-                commands.push(SvgCommand::SmoothQuadTo {
-                    x: parse_number(&tokens, &mut i),
-                    y: parse_number(&tokens, &mut i),
-                    relative,
-                });
-            }
-            'A' => {
-                commands.push(SvgCommand::ArcTo {
-                    rx: parse_number(&tokens, &mut i),
-                    ry: parse_number(&tokens, &mut i),
-                    rotation: parse_number(&tokens, &mut i),
-                    large_arc: parse_flag(&tokens, &mut i),
-                    sweep: parse_flag(&tokens, &mut i),
-                    x: parse_number(&tokens, &mut i),
-                    y: parse_number(&tokens, &mut i),
-                    relative,
-                });
-            }
-            'Z' => {
-                commands.push(SvgCommand::Close);
-            }
-            _ => {
-                i += 1;
-            }
+    /// Native counterpart of `tessellate_path`.
+    pub fn tessellate_path_native(
+        &mut self,
+        path_d: &str,
+        offset_x: f32,
+        offset_y: f32,
+        scale_x: f32,
+        scale_y: f32,
+        fill_rule: FillRule,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        if degenerate_placement(offset_x, offset_y, scale_x, scale_y)? {
+            return Ok(TessellatedMesh::degenerate(DegenerateInput::DisplayScale));
         }
+        let path = self.build_single_path(path_d, offset_x, offset_y, scale_x, scale_y);
+        self.paint_lyon_path(&path, Paint::Fill(fill_rule))
     }
 
-    commands
-}
+    /// Native counterpart of `tessellate_stroke`.
+    pub fn tessellate_stroke_native(
+        &mut self,
+        path_d: &str,
+        stroke_width: f32,
+        offset_x: f32,
+        offset_y: f32,
+        scale_x: f32,
+        scale_y: f32,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        self.tessellate_stroke_with_options_native(
+            path_d,
+            stroke_width,
+            [offset_x, offset_y, scale_x, scale_y],
+            &StrokeWidthOptions::default(),
+        )
+    }
 
-fn tokenize_svg_path(d: &str) -> Vec<String> {
-    let mut tokens = Vec::new();
-    let mut current = String::new();
-    let mut chars = d.chars().peekable();
+    /// Native counterpart of `tessellate_stroke_with_options`.
+    ///
+    /// With different widths per axis, the path is squashed vertically by
+    /// their ratio, stroked at `stroke_width_x`, and the stroke stretched
+    /// back, so horizontal segments come out `stroke_width_y` thick and
+    /// other directions in between, as drawn with an elliptical pen. Curves
+    /// are flattened at a tolerance divided by the stretch, so they keep
+    /// their smoothness once stretched.
+    pub fn tessellate_stroke_with_options_native(
+        &mut self,
+        path_d: &str,
+        stroke_width: f32,
+        placement: [f32; 4],
+        options: &StrokeWidthOptions,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        let [offset_x, offset_y, scale_x, scale_y] = placement;
+        if degenerate_placement(offset_x, offset_y, scale_x, scale_y)? {
+            return Ok(TessellatedMesh::degenerate(DegenerateInput::DisplayScale));
+        }
+        let path = self.build_single_path(path_d, offset_x, offset_y, scale_x, scale_y);
+        let uniform_width = stroke_width * scale_x.max(scale_y);
+        let width_x = options.stroke_width_x.unwrap_or(uniform_width);
+        let width_y = options.stroke_width_y.unwrap_or(uniform_width);
+        let stretch = width_y / width_x;
+        if width_x == width_y || !stretch.is_normal() {
+            let stroke = options
+                .stroke_style()
+                .stroke_options(width_x.max(width_y), TESSELLATION_TOLERANCE);
+            return self.paint_lyon_path(&path, Paint::Stroke(stroke));
+        }
 
-    while let Some(c) = chars.next() {
-        if c.is_ascii_alphabetic() {
-            if !current.is_empty() {
-                tokens.push(std::mem::take(&mut current));
-            }
-            tokens.push(c.to_string());
-        } else if c == '-' {
-            if !current.is_empty() {
-                tokens.push(std::mem::take(&mut current));
-            }
-            current.push(c);
-        } else if c == '.' {
-            if current.contains('.') {
-                tokens.push(std::mem::take(&mut current));
-            }
-            current.push(c);
-        } else if c.is_ascii_digit() {
-            current.push(c);
-        } else if c == ',' || c.is_whitespace() {
-            if !current.is_empty() {
-                tokens.push(std::mem::take(&mut current));
-            }
-        } else if c == 'e' || c == 'E' {
-            current.push(c);
-            if let Some(&next) = chars.peek() {
-                if next == '+' || next == '-' {
-                    current.push(chars.next().unwrap());
-                }
-            }
+        let squashed = path.transformed(&Transform::scale(1.0, 1.0 / stretch));
+        let mut buffers = self.take_buffers();
+        let result = self.stroke_tessellator.tessellate_path(
+            &squashed,
+            &options
+                .stroke_style()
+                .stroke_options(width_x, TESSELLATION_TOLERANCE / stretch.max(1.0)),
+            &mut BuffersBuilder::new(&mut buffers, VertexWithEdge),
+        );
+        for vertex in &mut buffers.vertices {
+            vertex.y *= stretch;
         }
+        let mesh = build_mesh(&buffers, self.consistent_winding);
+        self.restore_buffers(buffers);
+        self.arena.reset();
+        result?;
+        Ok(mesh?)
     }
 
-    if !current.is_empty() {
-        tokens.push(current);
+    /// Native counterpart of `tessellate_path_with_transform`. The path is
+    /// transformed before tessellating, so curves are flattened to the
+    /// tolerance in transformed coordinates. Strokes are widened by the
+    /// square root of the determinant, which is exact for rotation and
+    /// uniform scale. Under shear or non-uniform scale, where SVG would
+    /// vary the width with direction, the stroke keeps that one width.
+    pub fn tessellate_path_with_transform_native(
+        &mut self,
+        path_d: &str,
+        matrix: [f32; 6],
+        options: &PathPaintOptions,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        let [a, b, c, d, e, f] = matrix;
+        let transform = Transform::new(a, b, c, d, e, f);
+        let paint = match options.stroke_width {
+            Some(width) => Paint::Stroke(options.stroke_style().stroke_options(
+                width * transform.determinant().abs().sqrt(),
+                TESSELLATION_TOLERANCE,
+            )),
+            None => Paint::Fill(options.fill_rule),
+        };
+        let align = options.stroke_align;
+        if options.source_commands && align == StrokeAlign::Center {
+            return self.tessellate_sourced_path(path_d, &transform, paint);
+        }
+        let path = self
+            .build_single_path(path_d, 0.0, 0.0, 1.0, 1.0)
+            .transformed(&transform);
+        match paint {
+            Paint::Stroke(stroke) if align != StrokeAlign::Center => {
+                let mut buffers = self.take_buffers();
+                let result = self.stroke_aligned(&path, &stroke, align, &mut buffers);
+                let mesh = build_mesh(&buffers, self.consistent_winding);
+                self.restore_buffers(buffers);
+                self.arena.reset();
+                result?;
+                Ok(mesh?)
+            }
+            _ => self.paint_lyon_path(&path, paint),
+        }
     }
 
-    tokens
-}
+    /// Native counterpart of `tessellate_radial_gradient_fill`. An
+    /// `objectBoundingBox` gradient is placed on the mesh bounds.
+    #[cfg(feature = "gradients")]
+    pub fn tessellate_radial_gradient_fill_native(
+        &mut self,
+        path_d: &str,
+        gradient: &RadialGradientInput,
+    ) -> Result<RadialGradientMesh, TessellationError> {
+        let path = self.build_single_path(path_d, 0.0, 0.0, 1.0, 1.0);
+        let mesh = self.tessellate_lyon_path(&path, None)?;
+        Ok(RadialGradientMesh::new(mesh, gradient))
+    }
 
-fn build_lyon_path(
-    commands: &[SvgCommand],
-    offset_x: f32,
-    offset_y: f32,
-    scale_x: f32,
-    scale_y: f32,
-) -> lyon::path::Path {
-    use lyon::path::Path;
+    /// Native counterpart of `tessellate_polygon_f32`: fills when
+    /// `stroke_width` is None.
+    pub fn tessellate_points_native(
+        &mut self,
+        points: &[f32],
+        closed: bool,
+        stroke_width: Option<f32>,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        let path = build_point_path(points, closed);
+        self.tessellate_lyon_path(&path, stroke_width)
+    }
 
-    let mut builder = Path::builder();
-    let mut current_x = 0.0f32;
+    /// Native counterpart of `tessellate_dashed_stroke`.
+    pub fn tessellate_dashed_stroke_native(
+        &mut self,
+        path_d: &str,
+        stroke_width: f32,
+        dashes: &[f32],
+        dash_offset: f32,
+        options: &DashedStrokeOptions,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        let path = self.build_single_path(path_d, 0.0, 0.0, 1.0, 1.0);
+        let dashed =
+            PathMeasure::from_path(&path, TESSELLATION_TOLERANCE).dash(dashes, dash_offset);
+        let stroke = options
+            .stroke_style()
+            .stroke_options(stroke_width, TESSELLATION_TOLERANCE);
+        self.paint_lyon_path(&dashed, Paint::Stroke(stroke))
+    }
+
+    /// Fill `path` under the nonzero rule, or stroke it with butt caps when
+    /// given a line width.
+    fn tessellate_lyon_path(
+        &mut self,
+        path: &lyon::path::Path,
+        stroke_width: Option<f32>,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        let paint = match stroke_width {
+            Some(width) => {
+                Paint::Stroke(StrokeStyle::default().stroke_options(width, TESSELLATION_TOLERANCE))
+            }
+            None => Paint::Fill(FillRule::NonZero),
+        };
+        self.paint_lyon_path(path, paint)
+    }
+
+    fn paint_lyon_path(
+        &mut self,
+        path: &lyon::path::Path,
+        paint: Paint,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        let mut buffers = self.take_buffers();
+        let mut builder = BuffersBuilder::new(&mut buffers, VertexWithEdge);
+        let result = match paint {
+            Paint::Stroke(stroke) => {
+                self.stroke_tessellator
+                    .tessellate_path(path, &stroke, &mut builder)
+            }
+            Paint::Fill(fill_rule) => self.fill_tessellator.tessellate_path(
+                path,
+                &fill_rule.fill_options(TESSELLATION_TOLERANCE),
+                &mut builder,
+            ),
+        };
+        let mesh = build_mesh(&buffers, self.consistent_winding);
+        self.restore_buffers(buffers);
+        self.arena.reset();
+        result?;
+        Ok(mesh?)
+    }
+
+    /// Tessellate a path like `tessellate_lyon_path`, noting the command
+    /// behind each vertex. Sources need the tessellators to track endpoint
+    /// ids, so this path is kept apart from the common one.
+    fn tessellate_sourced_path(
+        &mut self,
+        path_d: &str,
+        transform: &Transform,
+        paint: Paint,
+    ) -> Result<TessellatedMesh, TessellationError> {
+        let (mut text, mut spans, mut commands) = (Vec::new(), Vec::new(), Vec::new());
+        let mut tokens = Vec::new();
+        // Lenient parsing never fails
+        let _ = parse_path_tokens(
+            path_d,
+            &mut text,
+            &mut spans,
+            &mut commands,
+            false,
+            Some(&mut tokens),
+        );
+        let mut builder = NoAttributes::wrap(SourcedBuilder::new());
+        append_commands(&mut builder, &commands, 0.0, 0.0, 1.0, 1.0);
+        let (path, sources) = builder.into_inner().build();
+        let path = path.transformed(transform);
+
+        let mut buffers = self.take_buffers();
+        let mut source_commands = Vec::new();
+        let constructor = SourcedVertex {
+            sources: &sources,
+            commands: &mut source_commands,
+        };
+        let mut builder = BuffersBuilder::new(&mut buffers, constructor);
+        let result = match paint {
+            Paint::Stroke(stroke) => self.stroke_tessellator.tessellate_with_ids(
+                path.id_iter(),
+                &path,
+                None,
+                &stroke,
+                &mut builder,
+            ),
+            Paint::Fill(fill_rule) => self.fill_tessellator.tessellate_with_ids(
+                path.id_iter(),
+                &path,
+                None,
+                &fill_rule.fill_options(TESSELLATION_TOLERANCE),
+                &mut builder,
+            ),
+        };
+        let mesh = build_mesh(&buffers, self.consistent_winding);
+        self.restore_buffers(buffers);
+        self.arena.reset();
+        result?;
+        let mut mesh = mesh?;
+        mesh.source_commands = source_commands;
+        mesh.command_ranges = command_ranges(path_d, &text, &spans, &tokens);
+        Ok(mesh)
+    }
+
+    /// Native counterpart of `tessellate_svg` with default options.
+    pub fn tessellate_svg_meshes(
+        &mut self,
+        svg_content: &str,
+        display_width: f32,
+        display_height: f32,
+    ) -> Vec<TessellatedMesh> {
+        self.tessellate_svg_meshes_with_options(
+            svg_content,
+            display_width,
+            display_height,
+            &TessellateOptions::default(),
+        )
+    }
+
+    /// The meshes of `tessellate_svg_document`, without its errors.
+    pub fn tessellate_svg_meshes_with_options(
+        &mut self,
+        svg_content: &str,
+        display_width: f32,
+        display_height: f32,
+        options: &TessellateOptions,
+    ) -> Vec<TessellatedMesh> {
+        let Ok(tessellation) =
+            self.tessellate_svg_document(svg_content, display_width, display_height, options)
+        else {
+            return Vec::new();
+        };
+        let meshes = tessellation.meshes.into_iter();
+        meshes.map(|entry| entry.mesh).collect()
+    }
+
+    /// Native counterpart of `tessellate_svg`. A display size of zero, or
+    /// one too small to draw at, returns no meshes and no errors, with
+    /// `degenerate` saying why; so does a document with a zero size or
+    /// viewBox. Negative and NaN sizes are errors.
+    pub fn tessellate_svg_document(
+        &mut self,
+        svg_content: &str,
+        display_width: f32,
+        display_height: f32,
+        options: &TessellateOptions,
+    ) -> Result<SvgTessellation, InvalidDisplaySize> {
+        let size = DisplaySize {
+            width: display_width,
+            height: display_height,
+        };
+        let mut lods = self.tessellate_svg_lod_meshes(svg_content, &[size], options)?;
+        Ok(lods.pop().expect("one size").tessellation)
+    }
+
+    /// Native counterpart of `tessellate_svg_lods`. Fails on the first
+    /// negative or NaN size, before tessellating any.
+    pub fn tessellate_svg_lod_meshes(
+        &mut self,
+        svg_content: &str,
+        sizes: &[DisplaySize],
+        options: &TessellateOptions,
+    ) -> Result<Vec<LodMeshSet>, InvalidDisplaySize> {
+        for size in sizes {
+            check_display_size(size.width, size.height)?;
+        }
+        let parsed = parse_svg_content(
+            svg_content,
+            options.language.as_deref(),
+            options.current_color.as_deref(),
+            options.font_size,
+        );
+        let mut issues = Vec::new();
+        let (commands, ranges) = self.parse_path_commands(&parsed, options.strict, &mut issues);
+        let (parse_errors, mut warnings): (Vec<PathError>, _) = if options.strict {
+            (
+                issues.into_iter().map(PathError::parse).collect(),
+                Vec::new(),
+            )
+        } else {
+            (Vec::new(), issues)
+        };
+        warnings.extend(comma_decimal_issues(&parsed));
+        warnings.sort_by_key(|issue| issue.path);
+
+        let lods = sizes
+            .iter()
+            .map(|&size| LodMeshSet {
+                display_width: size.width,
+                display_height: size.height,
+                tessellation: self.tessellate_at_size(
+                    &parsed,
+                    &commands,
+                    &ranges,
+                    size,
+                    options,
+                    &parse_errors,
+                    &warnings,
+                ),
+            })
+            .collect();
+        self.restore_commands(commands, ranges);
+        self.arena.reset();
+        Ok(lods)
+    }
+
+    /// The `SvgTessellation` of a parsed document at one display size,
+    /// given its parse errors and warnings.
+    #[allow(clippy::too_many_arguments)]
+    fn tessellate_at_size(
+        &mut self,
+        parsed: &ParsedSvg,
+        commands: &[SvgCommand],
+        ranges: &[(usize, usize)],
+        size: DisplaySize,
+        options: &TessellateOptions,
+        parse_errors: &[PathError],
+        warnings: &[PathDataIssue],
+    ) -> SvgTessellation {
+        if let Err(reason) = document_placement(parsed, size) {
+            return SvgTessellation {
+                meshes: Vec::new(),
+                width: parsed.width,
+                height: parsed.height,
+                errors: Vec::new(),
+                pattern_fills: Vec::new(),
+                warnings: Vec::new(),
+                document_warnings: parsed.document_warnings.clone(),
+                degenerate: Some(reason),
+            };
+        }
+        #[cfg_attr(not(feature = "patterns"), allow(unused_mut))]
+        let (mut painted, tessellation_errors) = self.tessellate_parsed(
+            parsed,
+            commands,
+            ranges,
+            size,
+            options,
+            TESSELLATION_TOLERANCE,
+        );
+        let mut errors = parse_errors.to_vec();
+        errors.extend(tessellation_errors);
+        #[cfg(feature = "patterns")]
+        let pattern_fills = if options.pattern_fills {
+            self.tessellate_pattern_fills(parsed, &mut painted, size, options, &mut errors)
+        } else {
+            Vec::new()
+        };
+        #[cfg(not(feature = "patterns"))]
+        let pattern_fills = Vec::new();
+        errors.sort_by_key(|error| error.path_index);
+        SvgTessellation {
+            meshes: painted
+                .into_iter()
+                .map(|painted| painted.entry(parsed))
+                .collect(),
+            width: parsed.width,
+            height: parsed.height,
+            errors,
+            pattern_fills,
+            warnings: warnings.to_vec(),
+            document_warnings: parsed.document_warnings.clone(),
+            degenerate: None,
+        }
+    }
+
+    /// Tessellate an SVG like `tessellate_svg` and attach each mesh's
+    /// resolved color, ready for `encode_meshes`.
+    pub fn tessellate_svg_assets(
+        &mut self,
+        svg_content: &str,
+        display_width: f32,
+        display_height: f32,
+        options: &TessellateOptions,
+    ) -> Vec<MeshAsset> {
+        self.tessellate_svg_assets_checked(svg_content, display_width, display_height, options)
+            .0
+    }
+
+    /// `tessellate_svg_assets`, also returning the paths that could not be
+    /// tessellated, and in strict mode those dropped for bad path data.
+    pub fn tessellate_svg_assets_checked(
+        &mut self,
+        svg_content: &str,
+        display_width: f32,
+        display_height: f32,
+        options: &TessellateOptions,
+    ) -> (Vec<MeshAsset>, Vec<PathError>) {
+        let size = DisplaySize {
+            width: display_width,
+            height: display_height,
+        };
+        self.tessellate_asset_lods(svg_content, &[size], options, &ColorMap::new())
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Pre-tessellate named SVGs at every size in `options` into one scene
+    /// bundle for `load_scene`, with fill and stroke colors resolved against
+    /// `theme`. Entries are written per input in order, one per size. SVG
+    /// bytes that are not UTF-8 are decoded lossily.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn compile_scene(
+        &mut self,
+        inputs: Vec<(String, Vec<u8>)>,
+        theme: ColorMap,
+        options: &SceneOptions,
+    ) -> Vec<u8> {
+        let mut entries = Vec::with_capacity(inputs.len() * options.sizes.len());
+        for (name, svg_bytes) in inputs {
+            let svg_content = String::from_utf8_lossy(&svg_bytes);
+            let lods = self.tessellate_asset_lods(
+                &svg_content,
+                &options.sizes,
+                &options.tessellate,
+                &theme,
+            );
+            for (size, (meshes, _)) in options.sizes.iter().zip(lods) {
+                entries.push(SceneEntry {
+                    name: name.clone(),
+                    display_width: size.width,
+                    display_height: size.height,
+                    meshes,
+                });
+            }
+        }
+        scene::encode_scene(&entries)
+    }
+
+    /// Colored meshes of an SVG at each of `sizes`, with the paths that
+    /// could not be tessellated, parsing it only once.
+    fn tessellate_asset_lods(
+        &mut self,
+        svg_content: &str,
+        sizes: &[DisplaySize],
+        options: &TessellateOptions,
+        theme: &ColorMap,
+    ) -> Vec<(Vec<MeshAsset>, Vec<PathError>)> {
+        let parsed = parse_svg_content(
+            svg_content,
+            options.language.as_deref(),
+            options.current_color.as_deref(),
+            options.font_size,
+        );
+        let mut issues = Vec::new();
+        let (commands, ranges) = self.parse_path_commands(&parsed, options.strict, &mut issues);
+        let parse_errors: Vec<PathError> = if options.strict {
+            issues.into_iter().map(PathError::parse).collect()
+        } else {
+            Vec::new()
+        };
+        let lods = sizes
+            .iter()
+            .map(|size| {
+                let (painted, tessellation_errors) = self.tessellate_parsed(
+                    &parsed,
+                    &commands,
+                    &ranges,
+                    *size,
+                    options,
+                    TESSELLATION_TOLERANCE,
+                );
+                let mut errors = parse_errors.clone();
+                errors.extend(tessellation_errors);
+                errors.sort_by_key(|error| error.path_index);
+                let assets = painted
+                    .into_iter()
+                    .map(|painted| MeshAsset {
+                        color: painted.color(&parsed, theme),
+                        stroke: painted.stroke,
+                        // The color has the paint and alpha applied
+                        mesh: TessellatedMesh {
+                            alpha: None,
+                            paint: None,
+                            color: None,
+                            uses_current_color: false,
+                            ..painted.mesh
+                        },
+                    })
+                    .collect();
+                (assets, errors)
+            })
+            .collect();
+        self.restore_commands(commands, ranges);
+        self.arena.reset();
+        lods
+    }
+
+    /// Native counterpart of `tessellate_svg_progressive`.
+    pub fn tessellate_svg_progressive_native(
+        &mut self,
+        svg_content: &str,
+        display_width: f32,
+        display_height: f32,
+    ) -> ProgressiveId {
+        let parsed = parse_svg_content(svg_content, None, None, None);
+        // The handle keeps the command lists, so they are not returned to
+        // the arena
+        let (commands, ranges) = self.parse_path_commands(&parsed, false, &mut Vec::new());
+        let size = DisplaySize {
+            width: display_width,
+            height: display_height,
+        };
+        let (coarse, _) = self.tessellate_parsed(
+            &parsed,
+            &commands,
+            &ranges,
+            size,
+            &TessellateOptions::default(),
+            PREVIEW_TOLERANCE,
+        );
+        self.arena.reset();
+        ProgressiveId(
+            self.progressive.insert(ProgressiveTessellation {
+                layout: coarse
+                    .iter()
+                    .map(|painted| (painted.path, painted.stroke, painted.mesh.paint_order))
+                    .collect(),
+                parsed,
+                commands,
+                ranges,
+                size,
+                coarse,
+            }),
+        )
+    }
+
+    /// Native counterpart of `take_coarse`.
+    pub fn take_coarse_native(
+        &mut self,
+        handle: &ProgressiveId,
+    ) -> Result<Vec<PaintedMesh>, StaleHandle> {
+        let progressive = self.progressive.get_mut(handle.0)?;
+        Ok(std::mem::take(&mut progressive.coarse))
+    }
+
+    /// Native counterpart of `cancel_progressive`. Returns whether the
+    /// handle was live.
+    pub fn cancel_progressive_native(&mut self, handle: &ProgressiveId) -> bool {
+        self.progressive.remove(handle.0).is_some()
+    }
+
+    /// Native counterpart of `refine`.
+    pub fn refine_native(
+        &mut self,
+        handle: &ProgressiveId,
+    ) -> Result<Vec<PaintedMesh>, StaleHandle> {
+        // Tessellating borrows the tessellator, so the registry is lent out
+        let registry = std::mem::take(&mut self.progressive);
+        let refined = registry
+            .get(handle.0)
+            .map(|progressive| self.refine_progressive(progressive));
+        self.progressive = registry;
+        refined
+    }
+
+    fn refine_progressive(&mut self, handle: &ProgressiveTessellation) -> Vec<PaintedMesh> {
+        let mut fine = self
+            .tessellate_parsed(
+                &handle.parsed,
+                &handle.commands,
+                &handle.ranges,
+                handle.size,
+                &TessellateOptions::default(),
+                TESSELLATION_TOLERANCE,
+            )
+            .0
+            .into_iter()
+            .peekable();
+        self.arena.reset();
+
+        // Both passes emit meshes in paint order, but a paint can tessellate
+        // to nothing in only one of them. Match the coarse layout exactly,
+        // with empty meshes for anything missing.
+        handle
+            .layout
+            .iter()
+            .map(|&(path, stroke, paint_order)| {
+                while fine
+                    .next_if(|painted| painted.mesh.paint_order < paint_order)
+                    .is_some()
+                {}
+                let mesh = fine
+                    .next_if(|painted| painted.mesh.paint_order == paint_order)
+                    .map(|painted| painted.mesh)
+                    .unwrap_or_else(|| TessellatedMesh {
+                        vertices: Vec::new(),
+                        indices: Vec::new(),
+                        bounds: MeshBounds::default(),
+                        paint_order,
+                        boundary_edges: Vec::new(),
+                        source_commands: Vec::new(),
+                        command_ranges: Vec::new(),
+                        transform: None,
+                        alpha: None,
+                        paint: None,
+                        color: None,
+                        uses_current_color: false,
+                        degenerate: None,
+                    });
+                PaintedMesh { path, stroke, mesh }
+            })
+            .collect()
+    }
+
+    /// Tessellate already-parsed paths at one display size.
+    ///
+    /// `ranges[i]` is the slice of `commands` holding the parsed `d` of
+    /// `parsed.paths[i]`, so callers tessellating the same document repeatedly
+    /// only tokenize it once. Curves are flattened to within `tolerance`
+    /// display pixels. A path that fails to fill or stroke, even by
+    /// panicking, is returned as an error and the others still tessellate.
+    fn tessellate_parsed(
+        &mut self,
+        parsed: &ParsedSvg,
+        commands: &[SvgCommand],
+        ranges: &[(usize, usize)],
+        size: DisplaySize,
+        options: &TessellateOptions,
+        tolerance: f32,
+    ) -> (Vec<PaintedMesh>, Vec<PathError>) {
+        let Ok(placement) = document_placement(parsed, size) else {
+            return (Vec::new(), Vec::new());
+        };
+        let DocumentPlacement {
+            scale_x,
+            scale_y,
+            offset_x,
+            offset_y,
+        } = placement;
+        let offset = Transform::translation(offset_x, offset_y);
+
+        let mut all_meshes: Vec<PaintedMesh> = Vec::new();
+        let mut errors = Vec::new();
+        let mut buffers = self.take_buffers();
+        // Dynamic paths are drawn transformed, so snapping them is moot
+        let dynamic_options = TessellateOptions {
+            snap_axis_aligned: false,
+            boundary_edges: options.boundary_edges,
+            dynamic_paths: Vec::new(),
+            pattern_fills: false,
+            language: None,
+            current_color: None,
+            font_size: None,
+            strict: options.strict,
+        };
+
+        for (index, (path, &(start, end))) in parsed.paths.iter().zip(ranges).enumerate() {
+            if path.hidden {
+                continue;
+            }
+            let dynamic = options
+                .dynamic_paths
+                .iter()
+                .find(|dynamic| dynamic.selects(index, path.id.as_deref()));
+            let matrix = dynamic
+                .and_then(|dynamic| dynamic.transform)
+                .or(path.transform);
+            if matrix.is_some_and(|matrix| !matrix.iter().all(|value| value.is_finite())) {
+                errors.push(PathError {
+                    path_index: index,
+                    stage: TessellationStage::Parse,
+                    message: "transform is not finite".to_string(),
+                    issue: None,
+                });
+                continue;
+            }
+            let mut lyon_path = build_lyon_path(&commands[start..end], 0.0, 0.0, scale_x, scale_y);
+            let mut stroke_scale = scale_x.max(scale_y);
+            // Gradients are placed on the path's box in its own user units
+            let references = [&path.fill, &path.stroke]
+                .into_iter()
+                .any(|paint| paint.as_deref().and_then(referenced_id).is_some());
+            let bounds = if references {
+                let scaled = lyon::algorithms::aabb::bounding_box(&lyon_path);
+                Box2D::new(
+                    point(scaled.min.x / scale_x, scaled.min.y / scale_y),
+                    point(scaled.max.x / scale_x, scaled.max.y / scale_y),
+                )
+            } else {
+                Box2D::zero()
+            };
+            let mut user_to_mesh = Transform::scale(scale_x, scale_y);
+            let display_matrix = path_transform::display_matrix(
+                matrix.unwrap_or(path_transform::IDENTITY),
+                scale_x,
+                scale_y,
+            );
+            let mesh_transform = dynamic.map(|_| {
+                let [a, b, c, d, e, f] = display_matrix;
+                [a, b, c, d, e + offset_x, f + offset_y]
+            });
+            let paint_options = match (dynamic, matrix) {
+                (Some(_), _) => &dynamic_options,
+                (None, Some(matrix)) => {
+                    lyon_path = lyon_path.transformed(&path_transform::to_lyon(display_matrix));
+                    user_to_mesh = user_to_mesh.then(&path_transform::to_lyon(display_matrix));
+                    // As `tessellate_path_with_transform_native` widens strokes
+                    stroke_scale *= path_transform::to_lyon(matrix).determinant().abs().sqrt();
+                    options
+                }
+                (None, None) => options,
+            };
+            // Aligning moves the tessellated path; a dynamic path's
+            // transform moves it instead
+            if dynamic.is_none() && (offset_x, offset_y) != (0.0, 0.0) {
+                lyon_path = lyon_path.transformed(&offset);
+                user_to_mesh = user_to_mesh.then(&offset);
+            }
+
+            let mut report = |stage, result: Result<Option<TessellatedMesh>, String>| {
+                result.unwrap_or_else(|message| {
+                    errors.push(PathError {
+                        path_index: index,
+                        stage,
+                        message,
+                        issue: None,
+                    });
+                    None
+                })
+            };
+            let fill = if path.fill.as_deref() != Some("none") {
+                let result = catch_path_panic(|| {
+                    self.fill_document_path(
+                        &mut buffers,
+                        &lyon_path,
+                        path.winding(),
+                        paint_options,
+                        tolerance,
+                    )
+                });
+                report(TessellationStage::Fill, result)
+            } else {
+                None
+            };
+            let stroke = match (&path.stroke, path.stroke_width) {
+                (Some(stroke), Some(width)) if stroke != "none" => {
+                    let dashed = path.dashed(&lyon_path, stroke_scale, tolerance);
+                    let result = catch_path_panic(|| {
+                        self.stroke_document_path(
+                            &mut buffers,
+                            dashed.as_ref().unwrap_or(&lyon_path),
+                            path.stroke_style()
+                                .stroke_options(width * stroke_scale, tolerance),
+                            path.stroke_align(),
+                            paint_options,
+                        )
+                    });
+                    report(TessellationStage::Stroke, result)
+                }
+                _ => None,
+            };
+
+            let paints = if path.stroke_first() {
+                [(true, stroke), (false, fill)]
+            } else {
+                [(false, fill), (true, stroke)]
+            };
+            for (slot, (stroke, mesh)) in paints.into_iter().enumerate() {
+                if let Some(mut mesh) = mesh {
+                    mesh.paint_order = (2 * index + slot) as u32;
+                    mesh.transform = mesh_transform;
+                    mesh.alpha = path.paint_alpha(stroke);
+                    mesh.uses_current_color = path.uses_current_color(stroke);
+                    mesh.color = path.paint_color(stroke);
+                    mesh.paint = gradient::mesh_paint(
+                        path.paint(stroke),
+                        &parsed.gradients,
+                        &bounds,
+                        &user_to_mesh,
+                    );
+                    all_meshes.push(PaintedMesh {
+                        path: index,
+                        stroke,
+                        mesh,
+                    });
+                }
+            }
+        }
+
+        self.restore_buffers(buffers);
+        (all_meshes, errors)
+    }
+
+    /// Move the fills of paths filled with a pattern out of `painted`, each
+    /// with its pattern's tile tessellated at the scale the path's bounds
+    /// and `size` give it, and the grid the tile repeats on. A pattern's
+    /// shapes that fail are reported against the path it fills.
+    #[cfg(feature = "patterns")]
+    fn tessellate_pattern_fills(
+        &mut self,
+        parsed: &ParsedSvg,
+        painted: &mut Vec<PaintedMesh>,
+        size: DisplaySize,
+        options: &TessellateOptions,
+        errors: &mut Vec<PathError>,
+    ) -> Vec<PatternFill> {
+        let Ok(placement) = document_placement(parsed, size) else {
+            return Vec::new();
+        };
+        // Tiles land at fractional offsets, so snapping their content is moot
+        let tile_options = TessellateOptions {
+            snap_axis_aligned: false,
+            boundary_edges: options.boundary_edges,
+            dynamic_paths: Vec::new(),
+            pattern_fills: false,
+            language: None,
+            current_color: None,
+            font_size: None,
+            strict: options.strict,
+        };
+
+        let mut fills = Vec::new();
+        let mut kept = Vec::with_capacity(painted.len());
+        for clip in painted.drain(..) {
+            let pattern = parsed.paths[clip.path]
+                .fill
+                .as_deref()
+                .and_then(referenced_id)
+                .and_then(|id| parsed.patterns.iter().find(|pattern| pattern.id == id))
+                .filter(|_| !clip.stroke && clip.mesh.transform.is_none());
+            match pattern {
+                Some(pattern) => fills.push((clip, pattern)),
+                None => kept.push(clip),
+            }
+        }
+        *painted = kept;
+
+        let mut pattern_fills = Vec::with_capacity(fills.len());
+        for (clip, pattern) in fills {
+            let layout = pattern.layout(&clip.mesh.bounds, placement);
+            let content = pattern.content();
+            let mut issues = Vec::new();
+            let (commands, ranges) =
+                self.parse_path_commands(&content, options.strict, &mut issues);
+            let mut tile_errors: Vec<PathError> = if options.strict {
+                issues.into_iter().map(PathError::parse).collect()
+            } else {
+                Vec::new()
+            };
+            let (tile, failures) = self.tessellate_parsed(
+                &content,
+                &commands,
+                &ranges,
+                DisplaySize {
+                    width: layout.content_scale_x,
+                    height: layout.content_scale_y,
+                },
+                &tile_options,
+                TESSELLATION_TOLERANCE,
+            );
+            self.restore_commands(commands, ranges);
+            tile_errors.extend(failures);
+            errors.extend(tile_errors.into_iter().map(|error| PathError {
+                path_index: clip.path,
+                stage: error.stage,
+                message: format!(
+                    "pattern {} path {}: {}",
+                    pattern.id, error.path_index, error.message
+                ),
+                issue: None,
+            }));
+            pattern_fills.push(PatternFill {
+                path: clip.path,
+                pattern: pattern.id.clone(),
+                clip: clip.mesh,
+                tile,
+                grid: layout.grid,
+            });
+        }
+        pattern_fills
+    }
+
+    /// Fill one path of a document, or None when nothing is covered.
+    fn fill_document_path(
+        &mut self,
+        buffers: &mut VertexBuffers<TessVertex, u32>,
+        path: &lyon::path::Path,
+        fill_rule: FillRule,
+        options: &TessellateOptions,
+        tolerance: f32,
+    ) -> Result<Option<TessellatedMesh>, String> {
+        buffers.clear();
+        let snapped = options
+            .snap_axis_aligned
+            .then(|| snap_axis_aligned(path, f32::round))
+            .flatten();
+
+        self.fill_tessellator
+            .tessellate_path(
+                snapped.as_ref().unwrap_or(path),
+                &fill_rule.fill_options(tolerance),
+                &mut BuffersBuilder::new(buffers, VertexWithEdge),
+            )
+            .map_err(|e| format!("{:?}", e))?;
+        document_mesh(buffers, self.consistent_winding, options)
+    }
+
+    /// Stroke one path of a document with a line width in display pixels
+    /// and an alignment, or None when nothing is covered.
+    fn stroke_document_path(
+        &mut self,
+        buffers: &mut VertexBuffers<TessVertex, u32>,
+        path: &lyon::path::Path,
+        stroke: StrokeOptions,
+        align: StrokeAlign,
+        options: &TessellateOptions,
+    ) -> Result<Option<TessellatedMesh>, String> {
+        buffers.clear();
+        let mut stroke = stroke;
+        let snapped = options
+            .snap_axis_aligned
+            .then(|| snap_stroke(path, stroke.line_width, align))
+            .flatten()
+            .map(|(snapped, snapped_width)| {
+                stroke.line_width = snapped_width;
+                snapped
+            });
+
+        self.stroke_aligned(snapped.as_ref().unwrap_or(path), &stroke, align, buffers)
+            .map_err(|e| format!("{:?}", e))?;
+        document_mesh(buffers, self.consistent_winding, options)
+    }
+
+    /// Stroke `path` into `buffers` on the `align` side of its outline. Inner
+    /// and outer strokes are cut from a stroke of twice the width by the
+    /// path's fill; see `stroke_align` for how that treats self-intersecting
+    /// paths.
+    fn stroke_aligned(
+        &mut self,
+        path: &lyon::path::Path,
+        options: &StrokeOptions,
+        align: StrokeAlign,
+        buffers: &mut VertexBuffers<TessVertex, u32>,
+    ) -> Result<(), TessellationError> {
+        if align == StrokeAlign::Center {
+            return self.stroke_tessellator.tessellate_path(
+                path,
+                options,
+                &mut BuffersBuilder::new(buffers, VertexWithEdge),
+            );
+        }
+        let mut doubled = VertexBuffers::new();
+        self.stroke_tessellator.tessellate_path(
+            path,
+            &options.with_line_width(options.line_width * 2.0),
+            &mut BuffersBuilder::new(&mut doubled, VertexWithEdge),
+        )?;
+        let mut fill = VertexBuffers::new();
+        self.fill_tessellator.tessellate_path(
+            path,
+            &FillOptions::default().with_tolerance(options.tolerance),
+            &mut BuffersBuilder::new(&mut fill, VertexWithEdge),
+        )?;
+        stroke_align::clip_stroke(&doubled, &fill, align, buffers);
+        Ok(())
+    }
+
+    /// Parse the `d` of every path into one command list in the arena,
+    /// pushing the issue of each path whose data is malformed to `issues`.
+    /// With `strict`, such a path gets no commands; otherwise it is read
+    /// leniently, with 0 for what is missing.
+    fn parse_path_commands(
+        &mut self,
+        parsed: &ParsedSvg,
+        strict: bool,
+        issues: &mut Vec<PathDataIssue>,
+    ) -> (Vec<SvgCommand>, Vec<(usize, usize)>) {
+        let mut text = self.arena.token_text.take();
+        let mut spans = self.arena.token_spans.take();
+        let mut commands = self.arena.commands.take();
+        let mut ranges = self.arena.path_ranges.take();
+
+        for (index, path) in parsed.paths.iter().enumerate() {
+            let start = commands.len();
+            if let Err(error) =
+                parse_svg_path_d(&path.d, &mut text, &mut spans, &mut commands, true)
+            {
+                commands.truncate(start);
+                issues.push(error.issue(index, &path.d, &text, &spans));
+                if !strict {
+                    // Lenient parsing never fails
+                    let _ = parse_svg_path_d(&path.d, &mut text, &mut spans, &mut commands, false);
+                }
+            }
+            ranges.push((start, commands.len()));
+        }
+
+        let retain = self.arena.retain;
+        self.arena.token_text.restore(text, retain);
+        self.arena.token_spans.restore(spans, retain);
+        (commands, ranges)
+    }
+
+    fn restore_commands(&mut self, commands: Vec<SvgCommand>, ranges: Vec<(usize, usize)>) {
+        let retain = self.arena.retain;
+        self.arena.commands.restore(commands, retain);
+        self.arena.path_ranges.restore(ranges, retain);
+    }
+
+    fn build_single_path(
+        &mut self,
+        path_d: &str,
+        offset_x: f32,
+        offset_y: f32,
+        scale_x: f32,
+        scale_y: f32,
+    ) -> lyon::path::Path {
+        let mut text = self.arena.token_text.take();
+        let mut spans = self.arena.token_spans.take();
+        let mut commands = self.arena.commands.take();
+
+        // Lenient parsing never fails
+        let _ = parse_svg_path_d(path_d, &mut text, &mut spans, &mut commands, false);
+        let path = build_lyon_path(&commands, offset_x, offset_y, scale_x, scale_y);
+
+        let retain = self.arena.retain;
+        self.arena.token_text.restore(text, retain);
+        self.arena.token_spans.restore(spans, retain);
+        self.arena.commands.restore(commands, retain);
+        path
+    }
+
+    fn take_buffers(&mut self) -> VertexBuffers<TessVertex, u32> {
+        VertexBuffers {
+            vertices: self.arena.vertices.take(),
+            indices: self.arena.indices.take(),
+        }
+    }
+
+    fn restore_buffers(&mut self, buffers: VertexBuffers<TessVertex, u32>) {
+        let retain = self.arena.retain;
+        self.arena.vertices.restore(buffers.vertices, retain);
+        self.arena.indices.restore(buffers.indices, retain);
+    }
+}
+
+impl Default for SvgTessellator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where tessellating a path failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TessellationStage {
+    Parse,
+    Fill,
+    Stroke,
+}
+
+/// A path of a document that could not be tessellated.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PathError {
+    /// Index into `ParsedSvg::paths`.
+    pub path_index: usize,
+    pub stage: TessellationStage,
+    pub message: String,
+    /// Where the path data stopped parsing, for `Parse` errors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<PathDataIssue>,
+}
+
+impl PathError {
+    /// The error of a path dropped in strict mode for `issue`.
+    fn parse(issue: PathDataIssue) -> Self {
+        PathError {
+            path_index: issue.path,
+            stage: TessellationStage::Parse,
+            message: format!("{} (near \"{}\")", issue.message, issue.context),
+            issue: Some(issue),
+        }
+    }
+}
+
+/// What `tessellate_svg` returns: the meshes of every path that tessellated,
+/// and why the others did not.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SvgTessellation {
+    /// Each mesh with the path and paint it came from, in paint order.
+    pub meshes: Vec<MeshEntry>,
+    /// The document's size in user units, as `ParsedSvg::width` and
+    /// `height`.
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+    pub errors: Vec<PathError>,
+    /// Fills to draw by tiling a pattern, when requested with
+    /// `TessellateOptions::pattern_fills`. Their clips take their place in
+    /// the paint order of `meshes`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pattern_fills: Vec<PatternFill>,
+    /// Suspicious path data that was still drawn, such as comma decimals,
+    /// and, without `TessellateOptions::strict`, data read leniently, such
+    /// as a curve cut short.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<PathDataIssue>,
+    /// The document's `ParsedSvg::document_warnings`, such as paint
+    /// references to elements it does not have, which draw black.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub document_warnings: Vec<String>,
+    /// Why nothing was tessellated, when the sizes left nothing to draw.
+    /// None when the paths were tessellated, even if they came out empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degenerate: Option<DegenerateInput>,
+}
+
+/// Run one path's tessellation, turning a panic into an error so it cannot
+/// take the rest of the document with it. Panics only unwind on native
+/// targets; wasm builds abort.
+fn catch_path_panic<T>(tessellate: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(tessellate)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(format!("panicked: {}", message))
+    })
+}
+
+/// Whether a mesh of a document is a path's fill or its stroke.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeshKind {
+    Fill,
+    Stroke,
+}
+
+/// A mesh of a document with the path it was tessellated from, for a host
+/// matching meshes to elements without parsing the document again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MeshEntry {
+    pub mesh: TessellatedMesh,
+    /// Index into `ParsedSvg::paths`.
+    pub path: usize,
+    pub kind: MeshKind,
+    /// The path's `fill` or `stroke` as written, which the mesh's `color`
+    /// and `paint` resolve.
+    pub paint: Option<String>,
+    /// For a stroke, its width in user units.
+    pub stroke_width: Option<f32>,
+    pub id: Option<String>,
+    pub class: Option<String>,
+}
+
+/// A mesh tagged with the path and paint it was tessellated from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaintedMesh {
+    /// Index into `ParsedSvg::paths`.
+    pub path: usize,
+    /// Whether this is the path's stroke rather than its fill.
+    pub stroke: bool,
+    pub mesh: TessellatedMesh,
+}
+
+impl PaintedMesh {
+    fn entry(self, parsed: &ParsedSvg) -> MeshEntry {
+        let path = &parsed.paths[self.path];
+        MeshEntry {
+            path: self.path,
+            kind: if self.stroke {
+                MeshKind::Stroke
+            } else {
+                MeshKind::Fill
+            },
+            paint: path.paint(self.stroke).map(str::to_string),
+            stroke_width: path.stroke_width.filter(|_| self.stroke),
+            id: path.id.clone(),
+            class: path.class.clone(),
+            mesh: self.mesh,
+        }
+    }
+
+    fn color(&self, parsed: &ParsedSvg, theme: &ColorMap) -> Option<[u8; 4]> {
+        let path = &parsed.paths[self.path];
+        let paint = path.paint(self.stroke);
+        let themed = paint.map(str::trim).and_then(|value| {
+            theme.get(value).or_else(|| {
+                value
+                    .eq_ignore_ascii_case("currentColor")
+                    .then(|| theme.get("currentColor"))
+                    .flatten()
+            })
+        });
+        let color = themed.copied().or_else(|| resolve_color(paint));
+        match (color, path.paint_alpha(self.stroke)) {
+            (Some([r, g, b, a]), Some(alpha)) => {
+                Some([r, g, b, (f32::from(a) * alpha).round() as u8])
+            }
+            (color, _) => color,
+        }
+    }
+}
+
+/// Copy tessellator output into an exactly sized mesh, leaving the buffers
+/// for reuse. Fails when a vertex is not finite; see `finite`.
+fn build_mesh(
+    buffers: &VertexBuffers<TessVertex, u32>,
+    consistent_winding: bool,
+) -> Result<TessellatedMesh, NonFiniteValue> {
+    let mut bounds = MeshBounds::new();
+    // 3 floats per vertex: x, y, edge_dist
+    let mut vertices: Vec<f32> = Vec::with_capacity(buffers.vertices.len() * 3);
+
+    for v in &buffers.vertices {
+        vertices.push(v.x);
+        vertices.push(v.y);
+        vertices.push(v.edge_dist);
+        bounds.expand(v.x, v.y);
+    }
+
+    if !bounds.is_valid() {
+        bounds = MeshBounds::default();
+    }
+
+    // lyon does not promise an orientation, and mixed winding drops
+    // triangles when the renderer culls back faces
+    let indices = if consistent_winding {
+        wound_indices(&buffers.vertices, &buffers.indices)
+    } else {
+        buffers.indices.clone()
+    };
+
+    let mut mesh = TessellatedMesh {
+        vertices,
+        indices,
+        bounds,
+        paint_order: 0,
+        boundary_edges: Vec::new(),
+        source_commands: Vec::new(),
+        command_ranges: Vec::new(),
+        transform: None,
+        alpha: None,
+        paint: None,
+        color: None,
+        uses_current_color: false,
+        degenerate: None,
+    };
+    finite::enforce_finite(&mut mesh)?;
+    Ok(mesh)
+}
+
+/// The single-path functions report lyon's errors, and lyon reports a
+/// non-finite input position the same way.
+impl From<NonFiniteValue> for TessellationError {
+    fn from(_: NonFiniteValue) -> Self {
+        TessellationError::UnsupportedParamater(UnsupportedParamater::PositionIsNaN)
+    }
+}
+
+/// The mesh for one paint of a document, or None when nothing is covered.
+fn document_mesh(
+    buffers: &VertexBuffers<TessVertex, u32>,
+    consistent_winding: bool,
+    options: &TessellateOptions,
+) -> Result<Option<TessellatedMesh>, String> {
+    if buffers.vertices.is_empty() {
+        return Ok(None);
+    }
+    let mut mesh = build_mesh(buffers, consistent_winding).map_err(|e| e.to_string())?;
+    if options.boundary_edges {
+        mesh.boundary_edges = boundary_edges(&mesh.indices);
+    }
+    Ok(Some(mesh))
+}
+
+/// Reorder every triangle to positive signed area, dropping zero-area ones.
+fn wound_indices(vertices: &[TessVertex], indices: &[u32]) -> Vec<u32> {
+    let mut wound = Vec::with_capacity(indices.len());
+    for triangle in indices.chunks_exact(3) {
+        let a = &vertices[triangle[0] as usize];
+        let b = &vertices[triangle[1] as usize];
+        let c = &vertices[triangle[2] as usize];
+        let area = signed_area(a.x, a.y, b.x, b.y, c.x, c.y);
+        if area > 0.0 {
+            wound.extend_from_slice(triangle);
+        } else if area < 0.0 {
+            wound.extend_from_slice(&[triangle[0], triangle[2], triangle[1]]);
+        }
+    }
+    wound
+}
+
+/// Twice the signed area of triangle abc, positive when counter-clockwise
+/// in a y-up plane.
+#[inline]
+fn signed_area(ax: f32, ay: f32, bx: f32, by: f32, cx: f32, cy: f32) -> f32 {
+    (bx - ax) * (cy - ay) - (by - ay) * (cx - ax)
+}
+
+/// Rasterize a mesh into a `width` x `height` RGBA8 buffer on a transparent
+/// background. Coverage is sampled once per pixel center, with the mesh's
+/// edge_dist channel scaling alpha. This is meant for thumbnails and tests,
+/// not for speed.
+pub fn rasterize_mesh(mesh: &TessellatedMesh, width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    composite_mesh(&mut pixels, mesh, width, height, color);
+    pixels
+}
+
+/// Blend a mesh over existing RGBA8 pixels (straight alpha, source-over).
+fn composite_mesh(
+    pixels: &mut [u8],
+    mesh: &TessellatedMesh,
+    width: u32,
+    height: u32,
+    color: [u8; 4],
+) {
+    let coverage = mesh_coverage(mesh, width, height);
+    for (pixel, &alpha) in pixels.chunks_exact_mut(4).zip(&coverage) {
+        if alpha > 0.0 {
+            blend_over(pixel, color, alpha);
+        }
+    }
+}
+
+/// Per-pixel coverage of a mesh. Triangles are combined with `max` rather
+/// than blended, so shared edges inside one mesh are not drawn twice.
+fn mesh_coverage(mesh: &TessellatedMesh, width: u32, height: u32) -> Vec<f32> {
+    let mut coverage = vec![0.0f32; width as usize * height as usize];
+    let vertex = |index: u32| {
+        let i = index as usize * 3;
+        RasterVertex {
+            x: mesh.vertices[i],
+            y: mesh.vertices[i + 1],
+            edge_dist: mesh.vertices[i + 2],
+        }
+    };
+
+    for triangle in mesh.indices.chunks_exact(3) {
+        let corners = [
+            vertex(triangle[0]),
+            vertex(triangle[1]),
+            vertex(triangle[2]),
+        ];
+        fill_triangle(&mut coverage, width, height, &corners);
+    }
+    coverage
+}
+
+#[derive(Clone, Copy)]
+struct RasterVertex {
+    x: f32,
+    y: f32,
+    edge_dist: f32,
+}
+
+/// Triangles with less area than this, in square pixels, cover no sample and
+/// would divide by nearly zero.
+const DEGENERATE_AREA: Tolerance = Tolerance::absolute(1e-6);
+
+/// Twice the signed area of the triangle (a, b, p).
+fn edge_function(a: &RasterVertex, b: &RasterVertex, px: f32, py: f32) -> f32 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+fn fill_triangle(coverage: &mut [f32], width: u32, height: u32, [a, b, c]: &[RasterVertex; 3]) {
+    let area = edge_function(a, b, c.x, c.y);
+    if DEGENERATE_AREA.is_zero(area) || !area.is_finite() {
+        return;
+    }
+
+    // Float to int casts saturate, so boxes fully off-canvas come out empty
+    let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+    let max_x = (a.x.max(b.x).max(c.x).ceil() as u32).min(width);
+    let max_y = (a.y.max(b.y).max(c.y).ceil() as u32).min(height);
+
+    for y in min_y..max_y {
+        let py = y as f32 + 0.5;
+        for x in min_x..max_x {
+            let px = x as f32 + 0.5;
+            // Dividing by the signed area makes the weights positive inside
+            // the triangle for either winding
+            let wa = edge_function(b, c, px, py) / area;
+            let wb = edge_function(c, a, px, py) / area;
+            let wc = edge_function(a, b, px, py) / area;
+            if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                continue;
+            }
+
+            let alpha = (wa * a.edge_dist + wb * b.edge_dist + wc * c.edge_dist).clamp(0.0, 1.0);
+            let cell = &mut coverage[(y * width + x) as usize];
+            *cell = cell.max(alpha);
+        }
+    }
+}
+
+fn blend_over(dst: &mut [u8], color: [u8; 4], coverage: f32) {
+    let src_a = color[3] as f32 / 255.0 * coverage;
+    let dst_a = dst[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    if out_a <= 0.0 {
+        return;
+    }
+
+    for (channel, &src) in dst[..3].iter_mut().zip(&color[..3]) {
+        let blended = (src as f32 * src_a + *channel as f32 * dst_a * (1.0 - src_a)) / out_a;
+        *channel = blended.round() as u8;
+    }
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+/// The id a `fill` or `stroke` value references, as in `url(#dots)`, and
+/// the fallback written after it, which may be empty.
+fn split_reference(value: &str) -> Option<(&str, &str)> {
+    let inner = value.trim().strip_prefix("url(")?;
+    let (reference, fallback) = inner.split_once(')')?;
+    Some((reference.trim().strip_prefix('#')?, fallback.trim()))
+}
+
+/// The id a `fill` or `stroke` value references, as in `url(#dots)`.
+fn referenced_id(value: &str) -> Option<&str> {
+    split_reference(value).map(|(id, _)| id)
+}
+
+/// The id an element's `href`, or legacy `xlink:href`, links to, as in
+/// `href="#arrow"`.
+fn href_id(tag: &str) -> Option<String> {
+    let href = extract_attr(tag, "href").or_else(|| extract_attr(tag, "xlink:href"))?;
+    Some(href.trim().strip_prefix('#')?.to_string())
+}
+
+/// Resolve a fill or stroke value to RGBA8, or None when nothing should be
+/// painted. Unset and invalid values use the SVG initial value, black; see
+/// `color` for what is read. An unresolved `currentColor` has no color to
+/// take, so is black as well. A reference draws in its fallback color, or
+/// black without one.
+fn resolve_color(value: Option<&str>) -> Option<[u8; 4]> {
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+    let Some(value) = value.map(str::trim) else {
+        return Some(BLACK);
+    };
+    if let Some((_, fallback)) = split_reference(value) {
+        return match fallback {
+            "" => Some(BLACK),
+            fallback => resolve_color(Some(fallback)),
+        };
+    }
+    if value.eq_ignore_ascii_case("none") || value.eq_ignore_ascii_case("transparent") {
+        return None;
+    }
+    Some(color::parse_color(value).map_or(BLACK, color::to_rgba8))
+}
+
+/// Flattening tolerance in output (device) units.
+///
+/// Paths are built already scaled to the display size, so a constant tolerance
+/// in output space adapts the curve detail to each size automatically.
+const TESSELLATION_TOLERANCE: f32 = 0.1;
+
+/// Tolerance of the first pass of `tessellate_svg_progressive`, cheap enough
+/// to draw right away.
+const PREVIEW_TOLERANCE: f32 = 2.0;
+
+/// Axis-aligned lines closer than this to exact, in device pixels, still snap.
+const SNAP_ALIGNMENT: Tolerance = Tolerance::absolute(1e-3);
+
+/// Snap a stroke's axis-aligned lines and its width together. A width rounded
+/// to an odd number of pixels is centered on half pixels so each edge lands
+/// on a pixel boundary; even widths center on whole pixels. Inner and outer
+/// strokes have one edge on the outline, which snaps to whole pixels.
+fn snap_stroke(
+    path: &lyon::path::Path,
+    line_width: f32,
+    align: StrokeAlign,
+) -> Option<(lyon::path::Path, f32)> {
+    // Ties round down so a hairline scaled by 1.5 stays one pixel wide
+    let width = (line_width - 0.5).ceil().max(1.0);
+    // Inner and outer strokes end on the outline, so it snaps like a fill
+    let snapped = if width % 2.0 == 1.0 && align == StrokeAlign::Center {
+        snap_axis_aligned(path, |v| v.floor() + 0.5)
+    } else {
+        snap_axis_aligned(path, f32::round)
+    };
+    snapped.map(|path| (path, width))
+}
+
+/// Snap the endpoints of horizontal and vertical lines in a transformed path:
+/// y for horizontal lines, x for vertical ones. Control points are never
+/// moved. Returns `None` when the path has no axis-aligned lines.
+fn snap_axis_aligned(
+    path: &lyon::path::Path,
+    snap: impl Fn(f32) -> f32,
+) -> Option<lyon::path::Path> {
+    use lyon::path::PathEvent;
+
+    enum Step {
+        Begin(usize),
+        Line(usize),
+        Quadratic(Point, usize),
+        Cubic(Point, Point, usize),
+        End(bool),
+    }
+
+    // Endpoints with per-axis snap flags; steps refer to them by index so a
+    // snapped corner moves for every segment that shares it
+    let mut anchors: Vec<(Point, bool, bool)> = Vec::new();
+    let mut steps = Vec::new();
+    let mut first = 0;
+    let mut snapped_any = false;
+
+    let mut mark = |anchors: &mut Vec<(Point, bool, bool)>, from: usize, to: usize| {
+        let (a, b) = (anchors[from].0, anchors[to].0);
+        let (dx, dy) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+        // A zero-length line, like the implicit close of a path that already
+        // returned to its start, has no direction
+        let vertical = SNAP_ALIGNMENT.is_zero(dx) && !SNAP_ALIGNMENT.is_zero(dy);
+        let horizontal = SNAP_ALIGNMENT.is_zero(dy) && !SNAP_ALIGNMENT.is_zero(dx);
+        for index in [from, to] {
+            anchors[index].1 |= vertical;
+            anchors[index].2 |= horizontal;
+        }
+        snapped_any |= vertical || horizontal;
+    };
+
+    for event in path.iter() {
+        let next = anchors.len();
+        match event {
+            PathEvent::Begin { at } => {
+                anchors.push((at, false, false));
+                first = next;
+                steps.push(Step::Begin(next));
+            }
+            PathEvent::Line { to, .. } => {
+                anchors.push((to, false, false));
+                mark(&mut anchors, next - 1, next);
+                steps.push(Step::Line(next));
+            }
+            PathEvent::Quadratic { ctrl, to, .. } => {
+                anchors.push((to, false, false));
+                steps.push(Step::Quadratic(ctrl, next));
+            }
+            PathEvent::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => {
+                anchors.push((to, false, false));
+                steps.push(Step::Cubic(ctrl1, ctrl2, next));
+            }
+            PathEvent::End { close, .. } => {
+                if close && next > first {
+                    mark(&mut anchors, next - 1, first);
+                }
+                steps.push(Step::End(close));
+            }
+        }
+    }
+
+    if !snapped_any {
+        return None;
+    }
+
+    let points: Vec<Point> = anchors
+        .iter()
+        .map(|&(point, snap_x, snap_y)| {
+            Point::new(
+                if snap_x { snap(point.x) } else { point.x },
+                if snap_y { snap(point.y) } else { point.y },
+            )
+        })
+        .collect();
+
+    let mut builder = lyon::path::Path::builder();
+    for step in steps {
+        match step {
+            Step::Begin(at) => {
+                builder.begin(points[at]);
+            }
+            Step::Line(to) => {
+                builder.line_to(points[to]);
+            }
+            Step::Quadratic(ctrl, to) => {
+                builder.quadratic_bezier_to(ctrl, points[to]);
+            }
+            Step::Cubic(ctrl1, ctrl2, to) => {
+                builder.cubic_bezier_to(ctrl1, ctrl2, points[to]);
+            }
+            Step::End(close) => builder.end(close),
+        }
+    }
+    Some(builder.build())
+}
+
+/// Bounds of the points and control points of parsed commands, in user space.
+fn command_bounds(commands: &[SvgCommand]) -> MeshBounds {
+    let path = build_lyon_path(commands, 0.0, 0.0, 1.0, 1.0);
+    if path.iter().next().is_none() {
+        return MeshBounds::default();
+    }
+    let rect = lyon::algorithms::aabb::fast_bounding_box(&path);
+    MeshBounds {
+        min_x: rect.min.x,
+        min_y: rect.min.y,
+        max_x: rect.max.x,
+        max_y: rect.max.y,
+    }
+}
+
+fn parse_options(options_js: JsValue) -> Result<ParseOptions, JsValue> {
+    if options_js.is_undefined() || options_js.is_null() {
+        return Ok(ParseOptions::default());
+    }
+    serde_wasm_bindgen::from_value(options_js)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))
+}
+
+/// The error a wasm export gives in a build without the cargo feature that
+/// implements it.
+#[cfg(not(feature = "gradients"))]
+fn feature_disabled(feature: &str) -> JsValue {
+    JsValue::from_str(&format!("built without the {} feature", feature))
+}
+
+fn round_to_precision(value: f32, precision: Option<u32>) -> f32 {
+    match precision {
+        Some(places) => {
+            let scale = 10f64.powi(places as i32);
+            ((value as f64 * scale).round() / scale) as f32
+        }
+        None => value,
+    }
+}
+
+/// Rewrite fractional numbers in path data to `precision` decimal places.
+/// Integers are copied as written. Arc flags are single characters that may
+/// be packed against the next number, as in `a1 1 0 011.5 2`, so arguments
+/// are counted per command to tell them apart.
+fn round_path_numbers(d: &str, precision: u32) -> String {
+    let bytes = d.as_bytes();
+    let scale = 10f64.powi(precision as i32);
+    let mut result = String::with_capacity(d.len());
+    let mut copied = 0;
+    let mut command = b'M';
+    let mut arg = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if byte.is_ascii_alphabetic() {
+            command = byte;
+            arg = 0;
+            i += 1;
+            continue;
+        }
+        let starts_number = byte.is_ascii_digit()
+            || byte == b'.'
+            || (matches!(byte, b'-' | b'+')
+                && bytes
+                    .get(i + 1)
+                    .is_some_and(|b| b.is_ascii_digit() || *b == b'.'));
+        if !starts_number {
+            i += 1;
+            continue;
+        }
+
+        // Arc arguments are rx ry rotation large-arc sweep x y
+        let is_flag = command.eq_ignore_ascii_case(&b'A') && matches!(arg % 7, 3 | 4);
+        let end = if is_flag { i + 1 } else { number_end(bytes, i) };
+        let token = &d[i..end];
+        if !is_flag && token.contains('.') {
+            if let Ok(value) = token.parse::<f64>() {
+                // Adding 0.0 turns a rounded -0 into 0
+                let rounded = format!("{}", (value * scale).round() / scale + 0.0);
+                result.push_str(&d[copied..i]);
+                result.push_str(&rounded);
+                // A number that rounds to an integer would otherwise merge
+                // with a following `.5`
+                if bytes.get(end) == Some(&b'.') && !rounded.contains('.') {
+                    result.push(' ');
+                }
+                copied = end;
+            }
+        }
+        arg += 1;
+        i = end;
+    }
+    result.push_str(&d[copied..]);
+    result
+}
+
+/// End of the number starting at `start`: an optional sign, digits with at
+/// most one decimal point, and an optional exponent.
+fn number_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+    if matches!(bytes[i], b'-' | b'+') {
+        i += 1;
+    }
+    let mut seen_dot = false;
+    while let Some(&byte) = bytes.get(i) {
+        match byte {
+            b'0'..=b'9' => {}
+            b'.' if !seen_dot => seen_dot = true,
+            _ => break,
+        }
+        i += 1;
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut exponent = i + 1;
+        if matches!(bytes.get(exponent), Some(b'-' | b'+')) {
+            exponent += 1;
+        }
+        if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+            i = exponent;
+            while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+/// The largest index at or below `index` that splits `text` on a char
+/// boundary. `str::floor_char_boundary` is not stable at our MSRV.
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    (0..=index.min(text.len()))
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
+/// The size of the SVG's user coordinate space (viewBox, or width/height).
+fn native_size(parsed: &ParsedSvg) -> (f32, f32) {
+    match parsed.view_box {
+        Some(ref vb) => (vb.width, vb.height),
+        None => (parsed.width, parsed.height),
+    }
+}
+
+/// Display sizes this small, in pixels, cover nothing visible.
+const DEGENERATE_DISPLAY_SIZE: Tolerance = Tolerance::absolute(1e-3);
+
+/// Where a document's user units land in a display: scaled on each axis,
+/// then offset to align the document as its `preserveAspectRatio` asks.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DocumentPlacement {
+    pub(crate) scale_x: f32,
+    pub(crate) scale_y: f32,
+    pub(crate) offset_x: f32,
+    pub(crate) offset_y: f32,
+}
+
+/// How a document's native size fits into `size`, or why there is nothing
+/// to draw at it. Sizes below zero or NaN count as too small; the public
+/// entry points reject them first.
+fn document_placement(
+    parsed: &ParsedSvg,
+    size: DisplaySize,
+) -> Result<DocumentPlacement, DegenerateInput> {
+    let drawable = |extent: f32| extent > 0.0 && !DEGENERATE_DISPLAY_SIZE.is_zero(extent);
+    if !(drawable(size.width) && drawable(size.height)) {
+        return Err(DegenerateInput::DisplayScale);
+    }
+    let (native_width, native_height) = native_size(parsed);
+    let positive = |extent: f32| extent.is_normal() && extent > 0.0;
+    if !(positive(native_width) && positive(native_height)) {
+        return Err(DegenerateInput::NativeSize);
+    }
+    let [scale_x, scale_y, offset_x, offset_y] = parsed
+        .preserve_aspect_ratio
+        .place((native_width, native_height), (size.width, size.height));
+    // Also catches an infinite display size, which scales or centers the
+    // document infinitely, and one so large or a native size so small that
+    // the scale overflows
+    let finite = offset_x.is_finite() && offset_y.is_finite();
+    if !(scale_x.is_normal() && scale_y.is_normal() && finite) {
+        return Err(DegenerateInput::DisplayScale);
+    }
+    Ok(DocumentPlacement {
+        scale_x,
+        scale_y,
+        offset_x,
+        offset_y,
+    })
+}
+
+/// Reject display sizes that are negative or NaN.
+fn check_display_size(width: f32, height: f32) -> Result<(), InvalidDisplaySize> {
+    if width.is_nan() || height.is_nan() || width < 0.0 || height < 0.0 {
+        return Err(InvalidDisplaySize { width, height });
+    }
+    Ok(())
+}
+
+/// Whether a single path's placement collapses it to nothing: a zero or
+/// subnormal scale on either axis. An offset or scale that is not finite
+/// cannot place it at all.
+fn degenerate_placement(
+    offset_x: f32,
+    offset_y: f32,
+    scale_x: f32,
+    scale_y: f32,
+) -> Result<bool, TessellationError> {
+    if [offset_x, offset_y, scale_x, scale_y]
+        .iter()
+        .any(|value| !value.is_finite())
+    {
+        return Err(TessellationError::UnsupportedParamater(
+            UnsupportedParamater::PositionIsNaN,
+        ));
+    }
+    Ok(!(scale_x.is_normal() && scale_y.is_normal()))
+}
+
+#[derive(Clone, Debug)]
+enum SvgCommand {
+    MoveTo {
+        x: f32,
+        y: f32,
+        relative: bool,
+    },
+    LineTo {
+        x: f32,
+        y: f32,
+        relative: bool,
+    },
+    HLineTo {
+        x: f32,
+        relative: bool,
+    },
+    VLineTo {
+        y: f32,
+        relative: bool,
+    },
+    CubicTo {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        x: f32,
+        y: f32,
+        relative: bool,
+    },
+    SmoothCubicTo {
+        x2: f32,
+        y2: f32,
+        x: f32,
+        y: f32,
+        relative: bool,
+    },
+    QuadTo {
+        x1: f32,
+        y1: f32,
+        x: f32,
+        y: f32,
+        relative: bool,
+    },
+    SmoothQuadTo {
+        x: f32,
+        y: f32,
+        relative: bool,
+    },
+    ArcTo {
+        rx: f32,
+        ry: f32,
+        rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        x: f32,
+        y: f32,
+        relative: bool,
+    },
+    Close,
+}
+
+/// Parse a path `d` attribute, appending its commands to `commands`.
+///
+/// `text` and `spans` are scratch buffers for the tokenizer. Lenient parsing
+/// reads missing or unparsable numbers as 0 and never fails. With `strict`,
+/// the first missing argument, unparsable number or bad arc flag is an
+/// error, leaving the commands before it in `commands`.
+fn parse_svg_path_d(
+    d: &str,
+    text: &mut Vec<u8>,
+    spans: &mut Vec<(usize, usize)>,
+    commands: &mut Vec<SvgCommand>,
+    strict: bool,
+) -> Result<(), TokenError> {
+    parse_path_tokens(d, text, spans, commands, strict, None)
+}
+
+/// Parse path data `d` strictly, as `ParseOptions::strict` checks each path
+/// of a document: the number of commands it holds, or the first missing
+/// argument, unparsable number or bad arc flag, with the command being read
+/// and where in `d` it stopped. The issue's `path` is the index of the path
+/// that failed to parse, which for `d` alone is 0. Empty data holds no
+/// commands.
+pub fn parse_svg_path_d_checked(d: &str) -> Result<usize, PathDataIssue> {
+    let (mut text, mut spans, mut commands) = (Vec::new(), Vec::new(), Vec::new());
+    parse_svg_path_d(d, &mut text, &mut spans, &mut commands, true)
+        .map_err(|error| error.issue(0, d, &text, &spans))?;
+    Ok(commands.len())
+}
+
+/// `parse_svg_path_d`, also pushing the range of tokens each command was
+/// read from to `command_tokens`.
+fn parse_path_tokens(
+    d: &str,
+    text: &mut Vec<u8>,
+    spans: &mut Vec<(usize, usize)>,
+    commands: &mut Vec<SvgCommand>,
+    strict: bool,
+    mut command_tokens: Option<&mut Vec<(usize, usize)>>,
+) -> Result<(), TokenError> {
+    tokenize_svg_path(d, text, spans);
+    if spans.is_empty() {
+        return Ok(());
+    }
+    let text: &[u8] = text;
+    let spans: &[(usize, usize)] = spans;
+
+    let mut i = 0;
+    let mut current_cmd = 'M';
+
+    let parse_number = |i: &mut usize, cmd: char| -> Result<f32, TokenError> {
+        if *i >= spans.len() {
+            if strict {
+                return Err(TokenError {
+                    token: *i,
+                    command: Some(cmd),
+                    message: format!("path data ends in the middle of '{}'", cmd),
+                });
+            }
+            return Ok(0.0);
+        }
+        let token = token_at(text, spans, *i);
+        let val = match token.parse::<f32>() {
+            Ok(val) => val,
+            Err(_) if strict => {
+                let message = if is_command(token) {
+                    format!("'{}' is missing arguments before '{}'", cmd, token)
+                } else {
+                    format!("cannot read '{}' as a number", token)
+                };
+                return Err(TokenError {
+                    token: *i,
+                    command: Some(cmd),
+                    message,
+                });
+            }
+            // A number with an exponent cut short, as in `1e`, reads as
+            // the number before it
+            Err(_) => token
+                .trim_end_matches(['e', 'E', '-', '+'])
+                .parse()
+                .unwrap_or(0.0),
+        };
+        *i += 1;
+        Ok(val)
+    };
+
+    let parse_flag = |i: &mut usize, cmd: char| -> Result<bool, TokenError> {
+        if *i >= spans.len() {
+            if strict {
+                return Err(TokenError {
+                    token: *i,
+                    command: Some(cmd),
+                    message: format!("path data ends in the middle of '{}'", cmd),
+                });
+            }
+            return Ok(false);
+        }
+        let val = token_at(text, spans, *i);
+        if strict && val != "0" && val != "1" {
+            return Err(TokenError {
+                token: *i,
+                command: Some(cmd),
+                message: format!("arc flag must be 0 or 1, found '{}'", val),
+            });
+        }
+        *i += 1;
+        Ok(val == "1")
+    };
+
+    while i < spans.len() {
+        let token = token_at(text, spans, i);
+        let first_token = i;
+        let command_count = commands.len();
+
+        if is_command(token) {
+            current_cmd = token.chars().next().unwrap();
+            i += 1;
+            if i >= spans.len() && !current_cmd.eq_ignore_ascii_case(&'Z') {
+                if strict {
+                    return Err(TokenError {
+                        token: i - 1,
+                        command: Some(current_cmd),
+                        message: format!("'{}' has no arguments", current_cmd),
+                    });
+                }
+                break;
+            }
+        } else if strict && i == 0 {
+            return Err(TokenError {
+                token: 0,
+                command: None,
+                message: "path data must start with a moveto".to_string(),
+            });
+        }
+
+        let relative = current_cmd.is_ascii_lowercase();
+        let cmd = current_cmd.to_ascii_uppercase();
+        let c = current_cmd;
+
+        match cmd {
+            'M' => {
+                commands.push(SvgCommand::MoveTo {
+                    x: parse_number(&mut i, c)?,
+                    y: parse_number(&mut i, c)?,
+                    relative,
+                });
+                current_cmd = if relative { 'l' } else { 'L' };
+            }
+            'L' => {
+                commands.push(SvgCommand::LineTo {
+                    x: parse_number(&mut i, c)?,
+                    y: parse_number(&mut i, c)?,
+                    relative,
+                });
+            }
+            'H' => {
+                commands.push(SvgCommand::HLineTo {
+                    x: parse_number(&mut i, c)?,
+                    relative,
+                });
+            }
+            'V' => {
+                commands.push(SvgCommand::VLineTo {
+                    y: parse_number(&mut i, c)?,
+                    relative,
+                });
+            }
+            'C' => {
+                commands.push(SvgCommand::CubicTo {
+                    x1: parse_number(&mut i, c)?,
+                    y1: parse_number(&mut i, c)?,
+                    x2: parse_number(&mut i, c)?,
+                    y2: parse_number(&mut i, c)?,
+                    x: parse_number(&mut i, c)?,
+                    y: parse_number(&mut i, c)?,
+                    relative,
+                });
+            }
+            'S' => {
+                commands.push(SvgCommand::SmoothCubicTo {
+                    x2: parse_number(&mut i, c)?,
+                    y2: parse_number(&mut i, c)?,
+                    x: parse_number(&mut i, c)?,
+                    y: parse_number(&mut i, c)?,
+                    relative,
+                });
+            }
+            'Q' => {
+                commands.push(SvgCommand::QuadTo {
+                    x1: parse_number(&mut i, c)?,
+                    y1: parse_number(&mut i, c)?,
+                    x: parse_number(&mut i, c)?,
+                    y: parse_number(&mut i, c)?,
+                    relative,
+                });
+            }
+            'T' => {
+                commands.push(SvgCommand::SmoothQuadTo {
+                    x: parse_number(&mut i, c)?,
+                    y: parse_number(&mut i, c)?,
+                    relative,
+                });
+            }
+            'A' => {
+                commands.push(SvgCommand::ArcTo {
+                    rx: parse_number(&mut i, c)?,
+                    ry: parse_number(&mut i, c)?,
+                    rotation: parse_number(&mut i, c)?,
+                    large_arc: parse_flag(&mut i, c)?,
+                    sweep: parse_flag(&mut i, c)?,
+                    x: parse_number(&mut i, c)?,
+                    y: parse_number(&mut i, c)?,
+                    relative,
+                });
+            }
+            'Z' => {
+                commands.push(SvgCommand::Close);
+                // Closepath takes no arguments; skip a stray number rather
+                // than closing again on every pass over it
+                if i < spans.len() && !is_command(token_at(text, spans, i)) {
+                    if strict {
+                        return Err(TokenError {
+                            token: i,
+                            command: Some(current_cmd),
+                            message: format!(
+                                "'{}' takes no arguments, found '{}'",
+                                current_cmd,
+                                token_at(text, spans, i)
+                            ),
+                        });
+                    }
+                    i += 1;
+                }
+            }
+            _ => {
+                i += 1;
+            }
+        }
+        let pushed = commands.len() > command_count;
+        if let Some(tokens) = command_tokens.as_deref_mut().filter(|_| pushed) {
+            tokens.push((first_token, i));
+        }
+    }
+    Ok(())
+}
+
+/// The byte offset in `d` of each byte of `text`, the tokens read from it.
+fn token_origins(d: &str, text: &[u8]) -> Vec<u32> {
+    // Token bytes are the ASCII characters of `d` the tokenizer kept, in
+    // order, and it skips only bytes it never keeps, so matching each byte
+    // to the next equal one in `d` finds where it came from.
+    let mut origins = Vec::with_capacity(text.len());
+    let mut source = d.bytes().enumerate();
+    for &byte in text {
+        if let Some((offset, _)) = source.find(|&(_, c)| c == byte) {
+            origins.push(offset as u32);
+        }
+    }
+    origins
+}
+
+/// Byte ranges in `d` of commands read from `command_tokens`, as flat
+/// `[start, end, ...]` pairs.
+fn command_ranges(
+    d: &str,
+    text: &[u8],
+    spans: &[(usize, usize)],
+    command_tokens: &[(usize, usize)],
+) -> Vec<u32> {
+    let origins = token_origins(d, text);
+    command_tokens
+        .iter()
+        .flat_map(|&(first, end)| {
+            let start = origins[spans[first].0];
+            let end = origins[spans[end - 1].1 - 1] + 1;
+            [start, end]
+        })
+        .collect()
+}
+
+/// Where and why strict path parsing stopped.
+#[derive(Debug)]
+struct TokenError {
+    token: usize,
+    /// The command whose arguments were being read
+    command: Option<char>,
+    message: String,
+}
+
+impl TokenError {
+    /// The issue of path `path`, whose data `d` was tokenized into `text`
+    /// and `spans` when parsing stopped at this error.
+    fn issue(self, path: usize, d: &str, text: &[u8], spans: &[(usize, usize)]) -> PathDataIssue {
+        let offset = match spans.get(self.token) {
+            Some(&(start, _)) => token_origins(d, text)[start] as usize,
+            None => d.len(),
+        };
+        PathDataIssue {
+            path,
+            token: Some(self.token),
+            offset: Some(offset),
+            found: (self.token < spans.len())
+                .then(|| token_at(text, spans, self.token).to_string()),
+            command: self.command,
+            message: self.message,
+            context: token_context(text, spans, self.token),
+        }
+    }
+}
+
+fn is_command(token: &str) -> bool {
+    matches!(
+        token,
+        "M" | "m"
+            | "L"
+            | "l"
+            | "H"
+            | "h"
+            | "V"
+            | "v"
+            | "C"
+            | "c"
+            | "S"
+            | "s"
+            | "Q"
+            | "q"
+            | "T"
+            | "t"
+            | "A"
+            | "a"
+            | "Z"
+            | "z"
+    )
+}
+
+/// A warning for each path of `parsed` whose data looks written with a
+/// comma as the decimal separator.
+fn comma_decimal_issues(parsed: &ParsedSvg) -> Vec<PathDataIssue> {
+    let suspicious = parsed.paths.iter().enumerate().filter_map(|(index, path)| {
+        let segment = comma_decimal_segment(&path.d)?;
+        Some(PathDataIssue {
+            path: index,
+            token: None,
+            offset: None,
+            found: None,
+            command: None,
+            message: "argument counts suggest ',' is used as the decimal separator".to_string(),
+            context: segment.trim().to_string(),
+        })
+    });
+    suspicious.collect()
+}
+
+/// The first command in `d` whose arguments hint that the data was written
+/// with a comma as the decimal separator, as in `L10 12,75` for 10 12.75.
+///
+/// `M3,5 6,25` is also valid data with comma-separated pairs, so a command
+/// only counts when its whitespace-separated groups fit its argument count
+/// but its comma-split numbers do not, or when an `H` or `V` argument holds
+/// a comma. It takes two such commands, and no `.` anywhere in `d`.
+fn comma_decimal_segment(d: &str) -> Option<&str> {
+    if d.contains('.') || !d.contains(',') {
+        return None;
+    }
+
+    let bytes = d.as_bytes();
+    let mut first = None;
+    let mut suspicious = 0;
+    let mut start = 0;
+    while start < bytes.len() {
+        // A segment runs from a command letter to the next one; an `e`
+        // after a digit is an exponent
+        let end = (start + 1..bytes.len())
+            .find(|&i| {
+                bytes[i].is_ascii_alphabetic()
+                    && !(matches!(bytes[i], b'e' | b'E') && bytes[i - 1].is_ascii_digit())
+            })
+            .unwrap_or(bytes.len());
+        let segment = &d[start..end];
+        start = end;
+
+        let arity = match segment.as_bytes()[0].to_ascii_uppercase() {
+            b'M' | b'L' | b'T' => 2,
+            b'H' | b'V' => 1,
+            b'S' | b'Q' => 4,
+            b'C' => 6,
+            b'A' => 7,
+            _ => continue,
+        };
+        let groups = segment[1..].split_whitespace().count();
+        let numbers: usize = segment[1..]
+            .split_whitespace()
+            .map(|group| group.split(',').filter(|part| !part.is_empty()).count())
+            .sum();
+        let fits_groups = groups > 0 && groups % arity == 0;
+        if (fits_groups && numbers % arity != 0) || (arity == 1 && numbers > groups) {
+            first.get_or_insert(segment);
+            suspicious += 1;
+        }
+    }
+
+    if suspicious >= 2 {
+        first
+    } else {
+        None
+    }
+}
+
+/// Tokens around `index`, with the token itself in brackets, for locating a
+/// problem in long path data. An index past the end marks the end.
+fn token_context(text: &[u8], spans: &[(usize, usize)], index: usize) -> String {
+    const RADIUS: usize = 3;
+    let mut parts = Vec::new();
+    for i in index.saturating_sub(RADIUS)..(index + RADIUS + 1).min(spans.len()) {
+        if i == index {
+            parts.push(format!("[{}]", token_at(text, spans, i)));
+        } else {
+            parts.push(token_at(text, spans, i).to_string());
+        }
+    }
+    if index >= spans.len() {
+        parts.push("[<end>]".to_string());
+    }
+    parts.join(" ")
+}
+
+fn token_at<'a>(text: &'a [u8], spans: &[(usize, usize)], i: usize) -> &'a str {
+    let (start, end) = spans[i];
+    // The tokenizer only emits ASCII, so this never fails
+    std::str::from_utf8(&text[start..end]).unwrap_or("")
+}
+
+/// Split a path `d` attribute into tokens, stored as byte ranges of `text`.
+/// A number is an optional sign, digits with at most one `.`, and an
+/// exponent when digits follow its `e`, so `.5.5` is two numbers and
+/// `1e2-3` is 100 and -3. An `e` without digits stays in its number, which
+/// then fails to read. Arc flags are one character each, so packed flags
+/// such as the `011` of `a1 1 0 011 1`, as minifiers write them, split into
+/// `0`, `1` and `1`. Letters are tokens of their own; anything else
+/// separates tokens.
+fn tokenize_svg_path(d: &str, text: &mut Vec<u8>, spans: &mut Vec<(usize, usize)>) {
+    text.clear();
+    spans.clear();
+    let bytes = d.as_bytes();
+    // The span of the last `A` or `a`, while its arguments are being read
+    let mut arc_command: Option<usize> = None;
+    let mut i = 0;
+
+    while let Some(&byte) = bytes.get(i) {
+        // Arguments repeat in sevens; the fourth and fifth are the flags
+        let at_flag =
+            arc_command.is_some_and(|command| matches!((spans.len() - command - 1) % 7, 3 | 4));
+        let end = if byte.is_ascii_alphabetic() {
+            arc_command = matches!(byte, b'A' | b'a').then_some(spans.len());
+            i + 1
+        } else if at_flag && matches!(byte, b'0' | b'1') {
+            i + 1
+        } else if byte.is_ascii_digit() || matches!(byte, b'.' | b'-' | b'+') {
+            dangling_exponent_end(bytes, number_end(bytes, i))
+        } else {
+            i += 1;
+            continue;
+        };
+        let start = text.len();
+        text.extend_from_slice(&bytes[i..end]);
+        spans.push((start, text.len()));
+        i = end;
+    }
+}
+
+/// Past the `e` and sign of an exponent with no digits at `end`, where a
+/// number read by `number_end` stopped, or `end` when there is none.
+fn dangling_exponent_end(bytes: &[u8], end: usize) -> usize {
+    if !matches!(bytes.get(end), Some(b'e' | b'E')) {
+        return end;
+    }
+    match bytes.get(end + 1) {
+        Some(b'-' | b'+') => end + 2,
+        _ => end + 1,
+    }
+}
+
+/// Build a path from a `d` attribute in user units, parsed leniently.
+fn path_from_d(d: &str) -> lyon::path::Path {
+    let (mut text, mut spans, mut commands) = (Vec::new(), Vec::new(), Vec::new());
+    // Lenient parsing never fails
+    let _ = parse_svg_path_d(d, &mut text, &mut spans, &mut commands, false);
+    build_lyon_path(&commands, 0.0, 0.0, 1.0, 1.0)
+}
+
+/// Build a path from flat x, y pairs, starting a new subpath after each pair
+/// with a NaN coordinate. A trailing odd value is ignored.
+fn build_point_path(points: &[f32], closed: bool) -> lyon::path::Path {
+    use lyon::path::path::BuilderImpl;
+
+    let pairs = points.len() / 2;
+    let mut builder = NoAttributes::wrap(BuilderImpl::with_capacity(pairs, pairs));
+    let mut in_subpath = false;
+    for pair in points.chunks_exact(2) {
+        let (x, y) = (pair[0], pair[1]);
+        if x.is_nan() || y.is_nan() {
+            if in_subpath {
+                builder.end(closed);
+                in_subpath = false;
+            }
+        } else if in_subpath {
+            builder.line_to(Point::new(x, y));
+        } else {
+            builder.begin(Point::new(x, y));
+            in_subpath = true;
+        }
+    }
+    if in_subpath {
+        builder.end(closed);
+    }
+    builder.build()
+}
+
+fn build_lyon_path(
+    commands: &[SvgCommand],
+    offset_x: f32,
+    offset_y: f32,
+    scale_x: f32,
+    scale_y: f32,
+) -> lyon::path::Path {
+    use lyon::path::path::BuilderImpl;
+
+    // Most commands add one endpoint and up to two control points
+    let mut builder = NoAttributes::wrap(BuilderImpl::with_capacity(
+        commands.len() * 3,
+        commands.len(),
+    ));
+    append_commands(&mut builder, commands, offset_x, offset_y, scale_x, scale_y);
+    builder.build()
+}
+
+/// Add parsed commands to `builder`, scaled and then offset.
+fn append_commands<B: PathBuilder + CommandTracker>(
+    builder: &mut NoAttributes<B>,
+    commands: &[SvgCommand],
+    offset_x: f32,
+    offset_y: f32,
+    scale_x: f32,
+    scale_y: f32,
+) {
+    let mut current_x = 0.0f32;
     let mut current_y = 0.0f32;
     let mut start_x = 0.0f32;
     let mut start_y = 0.0f32;
     let mut last_control_x = 0.0f32;
     let mut last_control_y = 0.0f32;
     let mut last_cmd_type: Option<char> = None;
+    // lyon needs every subpath ended exactly once, while SVG leaves them open
+    // at the next moveto and lets drawing continue after a closepath
+    let mut in_subpath = false;
+
+    for (index, cmd) in commands.iter().enumerate() {
+        builder.inner_mut().start_command(index);
+        let draws = !matches!(cmd, SvgCommand::MoveTo { .. } | SvgCommand::Close);
+        if draws && !in_subpath {
+            builder.begin(Point::new(
+                current_x * scale_x + offset_x,
+                current_y * scale_y + offset_y,
+            ));
+            in_subpath = true;
+        }
 
-    for cmd in commands {
         match cmd {
             SvgCommand::MoveTo { x, y, relative } => {
                 let (nx, ny) = if *relative {
                     (current_x + x, current_y + y)
                 } else {
-                    (*x, *y)
-                };
-                let px = nx * scale_x + offset_x;
-                let py = ny * scale_y + offset_y;
-                builder.begin(Point::new(px, py));
+                    (*x, *y)
+                };
+                let px = nx * scale_x + offset_x;
+                let py = ny * scale_y + offset_y;
+                if in_subpath {
+                    builder.end(false);
+                }
+                builder.begin(Point::new(px, py));
+                in_subpath = true;
+                current_x = nx;
+                current_y = ny;
+                start_x = nx;
+                start_y = ny;
+                last_cmd_type = Some('M');
+            }
+            SvgCommand::LineTo { x, y, relative } => {
+                let (nx, ny) = if *relative {
+                    (current_x + x, current_y + y)
+                } else {
+                    (*x, *y)
+                };
+                let px = nx * scale_x + offset_x;
+                let py = ny * scale_y + offset_y;
+                builder.line_to(Point::new(px, py));
+                current_x = nx;
+                current_y = ny;
+                last_cmd_type = Some('L');
+            }
+            SvgCommand::HLineTo { x, relative } => {
+                let nx = if *relative { current_x + x } else { *x };
+                let px = nx * scale_x + offset_x;
+                let py = current_y * scale_y + offset_y;
+                builder.line_to(Point::new(px, py));
+                current_x = nx;
+                last_cmd_type = Some('H');
+            }
+            SvgCommand::VLineTo { y, relative } => {
+                let ny = if *relative { current_y + y } else { *y };
+                let px = current_x * scale_x + offset_x;
+                let py = ny * scale_y + offset_y;
+                builder.line_to(Point::new(px, py));
+                current_y = ny;
+                last_cmd_type = Some('V');
+            }
+            SvgCommand::CubicTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+                relative,
+            } => {
+                let (nx1, ny1, nx2, ny2, nx, ny) = if *relative {
+                    (
+                        current_x + x1,
+                        current_y + y1,
+                        current_x + x2,
+                        current_y + y2,
+                        current_x + x,
+                        current_y + y,
+                    )
+                } else {
+                    (*x1, *y1, *x2, *y2, *x, *y)
+                };
+                builder.cubic_bezier_to(
+                    Point::new(nx1 * scale_x + offset_x, ny1 * scale_y + offset_y),
+                    Point::new(nx2 * scale_x + offset_x, ny2 * scale_y + offset_y),
+                    Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
+                );
+                last_control_x = nx2;
+                last_control_y = ny2;
+                current_x = nx;
+                current_y = ny;
+                last_cmd_type = Some('C');
+            }
+            SvgCommand::SmoothCubicTo {
+                x2,
+                y2,
+                x,
+                y,
+                relative,
+            } => {
+                let (cx1, cy1) = match last_cmd_type {
+                    Some('C') | Some('S') => (
+                        2.0 * current_x - last_control_x,
+                        2.0 * current_y - last_control_y,
+                    ),
+                    _ => (current_x, current_y),
+                };
+                let (nx2, ny2, nx, ny) = if *relative {
+                    (current_x + x2, current_y + y2, current_x + x, current_y + y)
+                } else {
+                    (*x2, *y2, *x, *y)
+                };
+                builder.cubic_bezier_to(
+                    Point::new(cx1 * scale_x + offset_x, cy1 * scale_y + offset_y),
+                    Point::new(nx2 * scale_x + offset_x, ny2 * scale_y + offset_y),
+                    Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
+                );
+                last_control_x = nx2;
+                last_control_y = ny2;
+                current_x = nx;
+                current_y = ny;
+                last_cmd_type = Some('S');
+            }
+            SvgCommand::QuadTo {
+                x1,
+                y1,
+                x,
+                y,
+                relative,
+            } => {
+                let (nx1, ny1, nx, ny) = if *relative {
+                    (current_x + x1, current_y + y1, current_x + x, current_y + y)
+                } else {
+                    (*x1, *y1, *x, *y)
+                };
+                builder.quadratic_bezier_to(
+                    Point::new(nx1 * scale_x + offset_x, ny1 * scale_y + offset_y),
+                    Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
+                );
+                last_control_x = nx1;
+                last_control_y = ny1;
+                current_x = nx;
+                current_y = ny;
+                last_cmd_type = Some('Q');
+            }
+            SvgCommand::SmoothQuadTo { x, y, relative } => {
+                let (cx, cy) = match last_cmd_type {
+                    Some('Q') | Some('T') => (
+                        2.0 * current_x - last_control_x,
+                        2.0 * current_y - last_control_y,
+                    ),
+                    _ => (current_x, current_y),
+                };
+                let (nx, ny) = if *relative {
+                    (current_x + x, current_y + y)
+                } else {
+                    (*x, *y)
+                };
+                builder.quadratic_bezier_to(
+                    Point::new(cx * scale_x + offset_x, cy * scale_y + offset_y),
+                    Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
+                );
+                last_control_x = cx;
+                last_control_y = cy;
+                current_x = nx;
+                current_y = ny;
+                last_cmd_type = Some('T');
+            }
+            SvgCommand::ArcTo {
+                rx,
+                ry,
+                rotation,
+                large_arc,
+                sweep,
+                x,
+                y,
+                relative,
+            } => {
+                let (nx, ny) = if *relative {
+                    (current_x + x, current_y + y)
+                } else {
+                    (*x, *y)
+                };
+
+                if *rx == 0.0 || *ry == 0.0 {
+                    builder.line_to(Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y));
+                } else {
+                    let arc = lyon::geom::SvgArc {
+                        from: Point::new(
+                            current_x * scale_x + offset_x,
+                            current_y * scale_y + offset_y,
+                        ),
+                        to: Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
+                        radii: lyon::math::Vector::new(rx * scale_x, ry * scale_y),
+                        x_rotation: lyon::geom::Angle::degrees(*rotation),
+                        flags: lyon::geom::ArcFlags {
+                            large_arc: *large_arc,
+                            sweep: *sweep,
+                        },
+                    };
+
+                    arc.for_each_quadratic_bezier(&mut |q| {
+                        builder.quadratic_bezier_to(q.ctrl, q.to);
+                    });
+                }
+
                 current_x = nx;
                 current_y = ny;
-                start_x = nx;
-                start_y = ny;
-                last_cmd_type = Some('M');
+                last_cmd_type = Some('A');
+            }
+            SvgCommand::Close => {
+                if in_subpath {
+                    builder.close();
+                    in_subpath = false;
+                }
+                current_x = start_x;
+                current_y = start_y;
+                last_cmd_type = Some('Z');
+            }
+        }
+    }
+
+    if in_subpath {
+        builder.end(false);
+    }
+}
+
+/// Check, round and truncate a parsed document's path data as `options`
+/// ask. Only fails in strict mode.
+fn apply_parse_options(
+    mut parsed: ParsedSvg,
+    options: &ParseOptions,
+) -> Result<ParsedSvg, PathDataIssue> {
+    parsed.resolve_current_color(options.current_color.as_deref());
+    // Check the data as written, before rounding or truncation
+    if options.strict {
+        let (mut text, mut spans, mut commands) = (Vec::new(), Vec::new(), Vec::new());
+        for (index, path) in parsed.paths.iter().enumerate() {
+            commands.clear();
+            if let Err(error) =
+                parse_svg_path_d(&path.d, &mut text, &mut spans, &mut commands, true)
+            {
+                return Err(error.issue(index, &path.d, &text, &spans));
+            }
+        }
+    }
+    let suspicions = comma_decimal_issues(&parsed);
+    parsed.warnings.extend(suspicions);
+    if let Some(precision) = options.precision {
+        for path in &mut parsed.paths {
+            path.d = round_path_numbers(&path.d, precision);
+        }
+    }
+    if let Some(max_length) = options.max_d_length {
+        for path in parsed
+            .paths
+            .iter_mut()
+            .filter(|path| path.d.len() > max_length)
+        {
+            let keep = if options.omit_long_d {
+                0
+            } else {
+                floor_char_boundary(&path.d, max_length)
+            };
+            path.d.truncate(keep);
+            path.d_truncated = true;
+        }
+    }
+    Ok(parsed)
+}
+
+/// The document in `svg_content`, with `<switch>` children picked for
+/// `language`, `currentColor` paints resolved against `current_color` and
+/// `em` lengths relative to `font_size`.
+fn parse_svg_content(
+    svg_content: &str,
+    language: Option<&str>,
+    current_color: Option<&str>,
+    font_size: Option<f32>,
+) -> ParsedSvg {
+    let mut parser = SvgStreamParser::new();
+    parser.set_language(language.map(str::to_string));
+    parser.set_font_size(font_size);
+    parser.feed(svg_content.as_bytes());
+    let mut parsed = parser.finish_native();
+    parsed.resolve_current_color(current_color);
+    parsed
+}
+
+fn is_current_color(value: &str) -> bool {
+    value.trim().eq_ignore_ascii_case("currentColor")
+}
+
+/// Elements read as paths.
+const SHAPE_TAGS: [&str; 7] = [
+    "path", "circle", "ellipse", "line", "rect", "polygon", "polyline",
+];
+
+/// The path drawn by a start tag of one of `SHAPE_TAGS`, or None when it is
+/// missing the attributes that place it. Paint the tag does not set is
+/// looked up in `sheet`.
+fn read_shape(shape: &str, tag: &str, sheet: &style::StyleSheet) -> Option<ParsedPath> {
+    let number = |name| extract_attr(tag, name).and_then(|s| s.parse::<f32>().ok());
+    let d = match shape {
+        "path" => extract_attr(tag, "d")?,
+        "circle" => {
+            let cx = number("cx").unwrap_or(0.0);
+            let cy = number("cy").unwrap_or(0.0);
+            let r = number("r")?;
+            ellipse_path(cx, cy, r, r)
+        }
+        "ellipse" => {
+            let cx = number("cx").unwrap_or(0.0);
+            let cy = number("cy").unwrap_or(0.0);
+            let (rx, ry) = (number("rx")?, number("ry")?);
+            // A zero radius disables rendering, as in browsers
+            if rx <= 0.0 || ry <= 0.0 {
+                return None;
+            }
+            ellipse_path(cx, cy, rx, ry)
+        }
+        "line" => {
+            let coordinate = |name| number(name).unwrap_or(0.0);
+            format!(
+                "M{},{} L{},{}",
+                coordinate("x1"),
+                coordinate("y1"),
+                coordinate("x2"),
+                coordinate("y2")
+            )
+        }
+        "rect" => {
+            let x = number("x").unwrap_or(0.0);
+            let y = number("y").unwrap_or(0.0);
+            let (w, h) = (number("width")?, number("height")?);
+            // A missing or negative radius takes the other's value
+            let radius = |name| number(name).filter(|r| *r >= 0.0);
+            let (rx, ry) = match (radius("rx"), radius("ry")) {
+                (Some(rx), Some(ry)) => (rx, ry),
+                (Some(r), None) | (None, Some(r)) => (r, r),
+                (None, None) => (0.0, 0.0),
+            };
+            let (rx, ry) = (rx.min(w / 2.0), ry.min(h / 2.0));
+            if rx > 0.0 && ry > 0.0 {
+                rounded_rect_path(x, y, w, h, rx, ry)
+            } else {
+                format!(
+                    "M{},{} L{},{} L{},{} L{},{} Z",
+                    x,
+                    y,
+                    x + w,
+                    y,
+                    x + w,
+                    y + h,
+                    x,
+                    y + h
+                )
+            }
+        }
+        // A polyline is a polygon left open
+        "polygon" | "polyline" => {
+            let points = read_points(&extract_attr(tag, "points")?);
+            if points.len() < 4 {
+                return None;
+            }
+            let mut d = format!("M{},{}", points[0], points[1]);
+            for i in (2..points.len()).step_by(2) {
+                if i + 1 < points.len() {
+                    d.push_str(&format!(" L{},{}", points[i], points[i + 1]));
+                }
+            }
+            if shape == "polygon" {
+                d.push_str(" Z");
+            }
+            d
+        }
+        _ => return None,
+    };
+    Some(ParsedPath {
+        id: extract_attr(tag, "id"),
+        class: extract_attr(tag, "class"),
+        d,
+        // A line encloses nothing, so only its stroke draws
+        fill: if shape == "line" {
+            Some("none".to_string())
+        } else {
+            sheet.property(tag, "fill")
+        },
+        stroke: sheet.property(tag, "stroke"),
+        stroke_width: sheet.length_property(tag, "stroke-width"),
+        d_truncated: false,
+        paint_order: extract_attr(tag, "paint-order"),
+        stroke_alignment: extract_attr(tag, "stroke-alignment"),
+        fill_rule: sheet.property(tag, "fill-rule"),
+        stroke_linecap: sheet.property(tag, "stroke-linecap"),
+        stroke_linejoin: sheet.property(tag, "stroke-linejoin"),
+        stroke_miterlimit: sheet.number_property(tag, "stroke-miterlimit"),
+        stroke_dasharray: sheet.property(tag, "stroke-dasharray"),
+        stroke_dashoffset: sheet.number_property(tag, "stroke-dashoffset"),
+        transform: extract_attr(tag, "transform")
+            .as_deref()
+            .and_then(path_transform::parse_transform),
+        opacity: sheet
+            .number_property(tag, "opacity")
+            .and_then(inherit::clamp_opacity),
+        fill_opacity: sheet
+            .number_property(tag, "fill-opacity")
+            .and_then(inherit::clamp_opacity),
+        stroke_opacity: sheet
+            .number_property(tag, "stroke-opacity")
+            .and_then(inherit::clamp_opacity),
+        color: sheet.property(tag, "color"),
+        fill_color: None,
+        stroke_color: None,
+        hidden: false,
+    })
+}
+
+/// The coordinates of a `points` attribute, read with the path number
+/// grammar so `10-5` is two numbers. Reading stops at the first token that
+/// is not a number, keeping the points before it as SVG does.
+fn read_points(value: &str) -> Vec<f32> {
+    let bytes = value.as_bytes();
+    let mut points = Vec::new();
+    let mut i = 0;
+    while let Some(&byte) = bytes.get(i) {
+        if byte.is_ascii_whitespace() || byte == b',' {
+            i += 1;
+            continue;
+        }
+        if !(byte.is_ascii_digit() || matches!(byte, b'.' | b'-' | b'+')) {
+            break;
+        }
+        let end = number_end(bytes, i);
+        match value[i..end].parse::<f32>() {
+            Ok(number) if number.is_finite() => points.push(number),
+            _ => break,
+        }
+        i = end;
+    }
+    points
+}
+
+/// Path data for an ellipse as four cubic beziers, one per quadrant, with
+/// control points at the kappa distance along each radius.
+#[rustfmt::skip]
+fn ellipse_path(cx: f32, cy: f32, rx: f32, ry: f32) -> String {
+    let (kx, ky) = (0.552_284_8 * rx, 0.552_284_8 * ry);
+    format!(
+        "M{},{} C{},{} {},{} {},{} C{},{} {},{} {},{} C{},{} {},{} {},{} C{},{} {},{} {},{} Z",
+        cx + rx, cy,
+        cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry,
+        cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy,
+        cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry,
+        cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy
+    )
+}
+
+/// Path data for a rectangle whose corners are quarter arcs of radii `rx`
+/// and `ry`, drawn clockwise from the end of the top-left corner.
+#[rustfmt::skip]
+fn rounded_rect_path(x: f32, y: f32, w: f32, h: f32, rx: f32, ry: f32) -> String {
+    let (right, bottom) = (x + w, y + h);
+    format!(
+        "M{},{} L{},{} A{},{} 0 0 1 {},{} L{},{} A{},{} 0 0 1 {},{} \
+         L{},{} A{},{} 0 0 1 {},{} L{},{} A{},{} 0 0 1 {},{} Z",
+        x + rx, y,
+        right - rx, y, rx, ry, right, y + ry,
+        right, bottom - ry, rx, ry, right - rx, bottom,
+        x + rx, bottom, rx, ry, x, bottom - ry,
+        x, y + ry, rx, ry, x + rx, y
+    )
+}
+
+/// Matches SVG elements by name, with or without a prefix bound to the SVG
+/// namespace, as in `<svg:path>` from documents that embed SVG in other XML.
+/// Elements in other namespaces, such as Inkscape's `<inkscape:path-effect>`,
+/// never match.
+#[derive(Default)]
+struct ElementTags {
+    /// Prefixes bound to the SVG namespace
+    prefixes: Vec<String>,
+}
+
+impl ElementTags {
+    /// Record the prefixes a start tag binds to the SVG namespace.
+    fn declare(&mut self, tag: &str) {
+        if !tag.contains("xmlns:") {
+            return;
+        }
+        for (name, value) in attributes(tag) {
+            let Some(prefix) = name.strip_prefix("xmlns:") else {
+                continue;
+            };
+            if value == "http://www.w3.org/2000/svg" && !self.prefixes.iter().any(|p| p == prefix) {
+                self.prefixes.push(prefix.to_string());
+            }
+        }
+    }
+
+    /// Whether the element name `name`, as written in a tag, is the SVG
+    /// element `tag`.
+    fn is_svg_element(&self, name: &str, tag: &str) -> bool {
+        match name.split_once(':') {
+            Some((prefix, local)) => local == tag && self.prefixes.iter().any(|p| p == prefix),
+            None => name == tag,
+        }
+    }
+}
+
+/// The value of the attribute named exactly `attr` in the start tag
+/// `element`, with character and entity references replaced.
+fn extract_attr(element: &str, attr: &str) -> Option<String> {
+    attributes(element)
+        .find(|(name, _)| *name == attr)
+        .map(|(_, value)| accessibility::decode_entities(value).into_owned())
+}
+
+/// The attributes of the start tag `element` in order, as name and value
+/// with references still written out. Values are read to their closing
+/// quote, so markup and other attributes written inside a value stay part
+/// of it. An unquoted value runs to whitespace, and an attribute without
+/// `=` has an empty value, as browsers read them.
+fn attributes(element: &str) -> impl Iterator<Item = (&str, &str)> {
+    let is_space = |c: char| c.is_ascii_whitespace();
+    let name_end = |c: char| c.is_ascii_whitespace() || matches!(c, '=' | '/' | '>');
+    let after_name = element.trim_start_matches('<');
+    let mut rest = after_name
+        .find(name_end)
+        .map_or("", |end| &after_name[end..]);
+    std::iter::from_fn(move || {
+        rest = rest.trim_start_matches(|c: char| is_space(c) || c == '/');
+        if rest.is_empty() || rest.starts_with('>') {
+            return None;
+        }
+        // A stray `=` is read as a one-character name, so the scan moves on
+        let end = rest.find(name_end).unwrap_or(rest.len()).max(1);
+        let name = &rest[..end];
+        rest = rest[end..].trim_start_matches(is_space);
+        let Some(value) = rest.strip_prefix('=') else {
+            return Some((name, ""));
+        };
+        let value = value.trim_start_matches(is_space);
+        let (value, after) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let inner = &value[1..];
+                let close = inner.find(quote).unwrap_or(inner.len());
+                (&inner[..close], inner.get(close + 1..).unwrap_or(""))
+            }
+            _ => {
+                let end = value
+                    .find(|c: char| is_space(c) || c == '>')
+                    .unwrap_or(value.len());
+                value.split_at(end)
+            }
+        };
+        rest = after;
+        Some((name, value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A document with `count` filled and stroked curved paths.
+    fn many_paths_svg(count: usize) -> String {
+        let mut svg = String::from(r#"<svg width="100" height="100" viewBox="0 0 100 100">"#);
+        for i in 0..count {
+            let x = (i % 10) as f32 * 10.0;
+            let y = (i / 10) as f32 * 10.0;
+            svg.push_str(&format!(
+                r##"<path d="M{},{} C{},{} {},{} {},{} Z" fill="#000" stroke="#f00" stroke-width="0.5"/>"##,
+                x,
+                y,
+                x + 8.0,
+                y,
+                x + 8.0,
+                y + 8.0,
+                x,
+                y + 8.0
+            ));
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    #[test]
+    fn test_lods_match_individual_calls() {
+        let svg = many_paths_svg(20);
+        let sizes = [
+            DisplaySize {
+                width: 16.0,
+                height: 16.0,
+            },
+            DisplaySize {
+                width: 64.0,
+                height: 32.0,
+            },
+            DisplaySize {
+                width: 512.0,
+                height: 512.0,
+            },
+        ];
+
+        let mut tessellator = SvgTessellator::new();
+        let options = TessellateOptions::default();
+        let lods = tessellator
+            .tessellate_svg_lod_meshes(&svg, &sizes, &options)
+            .unwrap();
+        assert_eq!(lods.len(), sizes.len());
+
+        for (lod, size) in lods.iter().zip(&sizes) {
+            assert_eq!(lod.display_width, size.width);
+            assert_eq!(lod.display_height, size.height);
+            let individual = tessellator
+                .tessellate_svg_document(&svg, size.width, size.height, &options)
+                .unwrap();
+            assert_eq!(lod.tessellation, individual);
+        }
+    }
+
+    #[test]
+    fn test_lods_apply_options_and_report_errors_per_size() {
+        let svg = r#"<svg viewBox="0 0 10 10"><path d="M0 0 L10 0 L10 10 Z"/><path d="M0 0 C1 1"/></svg>"#;
+        let sizes = [
+            DisplaySize {
+                width: 0.0,
+                height: 0.0,
+            },
+            DisplaySize {
+                width: 32.0,
+                height: 32.0,
+            },
+        ];
+        let options = TessellateOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        let mut tessellator = SvgTessellator::new();
+        let lods = tessellator
+            .tessellate_svg_lod_meshes(svg, &sizes, &options)
+            .unwrap();
+        assert!(lods[0].tessellation.degenerate.is_some());
+        assert!(lods[0].tessellation.meshes.is_empty());
+        assert_eq!(lods[1].tessellation.meshes.len(), 1);
+        assert_eq!(lods[1].tessellation.errors.len(), 1);
+        assert_eq!(lods[1].tessellation.errors[0].path_index, 1);
+    }
+
+    #[test]
+    fn test_lods_reject_an_invalid_size() {
+        let svg = many_paths_svg(2);
+        let sizes = [
+            DisplaySize {
+                width: 16.0,
+                height: 16.0,
+            },
+            DisplaySize {
+                width: -1.0,
+                height: 16.0,
+            },
+        ];
+        let mut tessellator = SvgTessellator::new();
+        assert!(tessellator
+            .tessellate_svg_lod_meshes(&svg, &sizes, &TessellateOptions::default())
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_progressive_refine_matches_coarse_layout() {
+        let svg = many_paths_svg(20);
+        let mut tessellator = SvgTessellator::new();
+        let handle = tessellator.tessellate_svg_progressive_native(&svg, 512.0, 512.0);
+        let coarse = tessellator.take_coarse_native(&handle).unwrap();
+        let fine = tessellator.refine_native(&handle).unwrap();
+
+        // One fill and one stroke per path, in the same order
+        assert_eq!(coarse.len(), 40);
+        assert_eq!(fine.len(), coarse.len());
+        for (coarse, fine) in coarse.iter().zip(&fine) {
+            assert_eq!((coarse.path, coarse.stroke), (fine.path, fine.stroke));
+        }
+
+        let vertex_count =
+            |meshes: &[PaintedMesh]| meshes.iter().map(|m| m.mesh.vertices.len()).sum::<usize>();
+        assert!(
+            vertex_count(&coarse) * 3 < vertex_count(&fine),
+            "coarse {} vs fine {}",
+            vertex_count(&coarse),
+            vertex_count(&fine)
+        );
+
+        // The refined pass is the regular tessellation
+        let direct = tessellator.tessellate_svg_meshes(&svg, 512.0, 512.0);
+        let refined: Vec<TessellatedMesh> = fine.into_iter().map(|m| m.mesh).collect();
+        assert_eq!(refined, direct);
+    }
+
+    #[test]
+    fn test_progressive_handles_go_stale_when_cancelled_or_reset() {
+        let svg = many_paths_svg(2);
+        let mut tessellator = SvgTessellator::new();
+        let cancelled = tessellator.tessellate_svg_progressive_native(&svg, 64.0, 64.0);
+        assert!(tessellator.cancel_progressive_native(&cancelled));
+        let stale = StaleHandle {
+            handle: cancelled.0,
+            freed: true,
+        };
+        assert_eq!(tessellator.refine_native(&cancelled), Err(stale));
+        assert_eq!(tessellator.take_coarse_native(&cancelled), Err(stale));
+
+        // The slot is reused, but the cancelled handle does not reach it
+        let live = tessellator.tessellate_svg_progressive_native(&svg, 64.0, 64.0);
+        assert_ne!(live, cancelled);
+        assert_eq!(tessellator.refine_native(&cancelled), Err(stale));
+        assert!(tessellator.refine_native(&live).is_ok());
+
+        assert!(!tessellator.cancel_progressive_native(&cancelled));
+        assert_eq!(tessellator.double_frees(), 1);
+
+        tessellator.reset(true);
+        assert!(tessellator.refine_native(&live).unwrap_err().freed);
+        let fresh = tessellator.tessellate_svg_progressive_native(&svg, 64.0, 64.0);
+        assert!(fresh != live && fresh != cancelled);
+    }
+
+    #[test]
+    fn test_reset_returns_to_baseline() {
+        let huge = many_paths_svg(2_000);
+        let small = many_paths_svg(3);
+        let baseline = SvgTessellator::new().tessellate_svg_meshes(&small, 100.0, 100.0);
+
+        let mut tessellator = SvgTessellator::with_arena(true);
+        tessellator.set_consistent_winding(false);
+        tessellator.tessellate_svg_meshes(&huge, 100.0, 100.0);
+        let grown = tessellator.memory_report_native();
+        assert!(grown.arena_capacity_bytes > 128 * 1024, "{:?}", grown);
+
+        // Lyon's tessellators carry nothing over from the huge document
+        tessellator.set_consistent_winding(true);
+        assert_eq!(
+            tessellator.tessellate_svg_meshes(&small, 100.0, 100.0),
+            baseline
+        );
+
+        tessellator.shrink_to_fit();
+        let shrunk = tessellator.memory_report_native();
+        assert_eq!(shrunk.arena_capacity_bytes, 0);
+        assert_eq!(shrunk.arena_resets, grown.arena_resets + 1);
+
+        tessellator.set_consistent_winding(false);
+        tessellator.tessellate_svg_meshes(&huge, 100.0, 100.0);
+        tessellator.reset(true);
+        let report = tessellator.memory_report_native();
+        assert_eq!(report.arena_capacity_bytes, 0);
+        assert_eq!(report.arena_peak_bytes, 0);
+        assert_eq!(report.arena_resets, 0);
+        assert!(!tessellator.consistent_winding);
+
+        tessellator.reset(false);
+        assert!(tessellator.consistent_winding);
+        assert_eq!(
+            tessellator.tessellate_svg_meshes(&small, 100.0, 100.0),
+            baseline
+        );
+        let report = tessellator.memory_report_native();
+        assert!(report.arena_capacity_bytes < grown.arena_capacity_bytes / 100);
+    }
+
+    #[test]
+    fn test_arena_matches_fresh_allocation() {
+        // Compact number forms exercise the tokenizer's span bookkeeping
+        let compact = r##"<svg viewBox="0 0 24 24"><path d="M2.5.5l3-4h-1.5.5v2a2 2 0 1 1-4 0s1,2 3,4q1 1 2 0t2 0z" fill="#000" stroke="#000" stroke-width="1"/><circle cx="12" cy="12" r="5"/></svg>"##;
+        let documents = [many_paths_svg(200), compact.to_string(), many_paths_svg(3)];
+
+        let mut reused = SvgTessellator::with_arena(true);
+        let mut fresh = SvgTessellator::with_arena(false);
+
+        for svg in documents.iter().chain(documents.iter().rev()) {
+            for size in [16.0, 100.0] {
+                assert_eq!(
+                    reused.tessellate_svg_meshes(svg, size, size),
+                    fresh.tessellate_svg_meshes(svg, size, size)
+                );
+            }
+        }
+
+        let reused_report = reused.memory_report_native();
+        assert_eq!(reused_report.arena_resets, 12);
+        assert!(reused_report.arena_capacity_bytes > 0);
+        assert!(reused_report.arena_peak_bytes >= reused_report.arena_capacity_bytes);
+        assert_eq!(fresh.memory_report_native().arena_capacity_bytes, 0);
+    }
+
+    #[test]
+    fn test_summary_of_large_document_stays_small() {
+        let mut svg = String::from(r#"<svg width="1000" height="1000" viewBox="0 0 1000 1000">"#);
+        for i in 0..5_000 {
+            let x = (i % 100) as f32 * 10.0 + 0.123_456;
+            let y = (i / 100) as f32 * 20.0 + 0.654_321;
+            svg.push_str(&format!(
+                r##"<path id="road-{}" d="M{},{} C{},{} {},{} {},{} L{},{} L{},{} Z" fill="#ccc" stroke="#fff" stroke-width="1"/>"##,
+                i, x, y, x + 3.3, y - 4.4, x + 6.6, y + 4.4, x + 9.9, y,
+                x + 9.9, y + 8.8, x, y + 8.8,
+            ));
+        }
+
+        let mut tessellator = SvgTessellator::new();
+        let options = ParseOptions {
+            precision: Some(2),
+            ..Default::default()
+        };
+        let summary = tessellator.parse_svg_summary_native(&svg, &options);
+        assert_eq!(summary.path_count, 5_000);
+        assert_eq!(summary.command_count, 5_000 * 5);
+        assert_eq!(summary.ids[7].as_deref(), Some("road-7"));
+        assert_eq!(summary.bounds.len(), 5_000 * 4);
+        assert!(summary.stroked.iter().all(|&stroked| stroked));
+
+        // Curve control points reach above the path's start
+        assert_eq!(summary.bounds[..4], [0.12, -3.75, 10.02, 9.45]);
+
+        let serialized = serde_json::to_string(&summary).unwrap().len();
+        assert!(serialized < 300 * 1024, "summary is {} bytes", serialized);
+        assert!(serialized < summary.d_bytes);
+    }
+
+    #[test]
+    fn test_parse_options_truncate_or_omit_long_d() {
+        let svg = r##"<svg viewBox="0 0 10 10">
+            <path d="M1 1 L2 2" fill="#000"/>
+            <path d="M1 1 L2 2 L3 3 L4 4 L5 5 Z" fill="#000"/>
+        </svg>"##;
+        let tessellator = SvgTessellator::new();
+
+        let truncated = tessellator
+            .parse_svg_native(
+                svg,
+                &ParseOptions {
+                    max_d_length: Some(10),
+                    omit_long_d: false,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(truncated.paths[0].d, "M1 1 L2 2");
+        assert!(!truncated.paths[0].d_truncated);
+        assert_eq!(truncated.paths[1].d, "M1 1 L2 2 ");
+        assert!(truncated.paths[1].d_truncated);
+
+        let omitted = tessellator
+            .parse_svg_native(
+                svg,
+                &ParseOptions {
+                    max_d_length: Some(10),
+                    omit_long_d: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(omitted.paths[0].d, "M1 1 L2 2");
+        assert_eq!(omitted.paths[1].d, "");
+        assert!(omitted.paths[1].d_truncated);
+
+        let rounded = tessellator
+            .parse_svg_native(
+                r#"<svg><path d="M0.123456-1.98765 L10 20 a1.005 1.005 0 011.5e-3.25 Z"/></svg>"#,
+                &ParseOptions {
+                    precision: Some(2),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(rounded.paths[0].d, "M0.12-1.99 L10 20 a1 1 0 010 0.25 Z");
+
+        assert_eq!(floor_char_boundary("a\u{e9}b", 2), 1);
+    }
+
+    /// Trimmed from an Inkscape 1.3 "Inkscape SVG" save.
+    const INKSCAPE_SVG: &str = r##"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<svg
+   width="32"
+   height="32"
+   viewBox="0 0 32 32"
+   version="1.1"
+   id="svg1"
+   inkscape:version="1.3.2 (091e20e, 2023-11-25)"
+   sodipodi:docname="badge.svg"
+   xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape"
+   xmlns:sodipodi="http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd"
+   xmlns:xlink="http://www.w3.org/1999/xlink"
+   xmlns="http://www.w3.org/2000/svg"
+   xmlns:svg="http://www.w3.org/2000/svg">
+  <sodipodi:namedview
+     id="namedview1"
+     pagecolor="#ffffff"
+     inkscape:window-width="1920"
+     inkscape:window-height="1011"
+     inkscape:current-layer="layer1" />
+  <defs
+     id="defs1">
+    <inkscape:path-effect
+       effect="bspline"
+       id="path-effect1"
+       is_visible="true"
+       lpeversion="1.3" />
+  </defs>
+  <g
+     inkscape:label="Layer 1"
+     inkscape:groupmode="layer"
+     id="layer1">
+    <rect
+       style="fill:#3366cc"
+       id="rect1"
+       width="24"
+       height="24"
+       x="4"
+       y="4"
+       fill="#3366cc"
+       inkscape:label="background" />
+    <svg:path
+       d="M 8,16 14,22 24,10"
+       fill="none"
+       stroke="#ffffff"
+       stroke-width="2"
+       id="path1"
+       inkscape:path-effect="#path-effect1"
+       inkscape:original-d="M 8,16 14,22 24,10"
+       sodipodi:nodetypes="ccc" />
+    <use
+       xlink:href="#path1"
+       id="use1" />
+  </g>
+</svg>"##;
+
+    /// A diagram with an HTML caption, whose inline icon must not be drawn.
+    const FOREIGN_OBJECT_SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
+  <path d="M0 0 H100 V100 H0 Z" fill="#eee"/>
+  <foreignObject x="10" y="60" width="80" height="30">
+    <div xmlns="http://www.w3.org/1999/xhtml">Caption
+      <svg viewBox="0 0 10 10"><path d="M0 0 L10 10" stroke="#f00" stroke-width="1"/></svg>
+      <rect width="5" height="5"/>
+    </div>
+  </foreignObject>
+  <foreignObject x="0" y="0" width="1" height="1"/>
+  <circle cx="50" cy="30" r="10" fill="#000"/>
+</svg>"##;
+
+    #[test]
+    fn test_namespaced_and_foreign_content() {
+        let tessellator = SvgTessellator::new();
+        let parsed = tessellator
+            .parse_svg_native(INKSCAPE_SVG, &ParseOptions::default())
+            .unwrap();
+        let ids: Vec<_> = parsed.paths.iter().map(|p| p.id.as_deref()).collect();
+        // The prefixed path is found, and drawn again by the use;
+        // path-effect and namedview are not paths
+        assert_eq!(ids, [Some("rect1"), Some("path1"), Some("use1")]);
+        assert_eq!(parsed.paths[1].d, "M 8,16 14,22 24,10");
+        assert_eq!(parsed.paths[1].stroke.as_deref(), Some("#ffffff"));
+        assert_eq!((parsed.width, parsed.height), (32.0, 32.0));
+        assert!(parsed.warnings.is_empty());
+        assert!(parsed.document_warnings.is_empty());
+
+        // Without a binding for the prefix, svg:path is not an SVG element
+        let unbound = tessellator
+            .parse_svg_native(
+                r#"<svg><svg:path d="M0 0 L1 1"/><path d="M2 2 L3 3"/></svg>"#,
+                &ParseOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(unbound.paths.len(), 1);
+        assert_eq!(unbound.paths[0].d, "M2 2 L3 3");
+
+        let parsed = tessellator
+            .parse_svg_native(FOREIGN_OBJECT_SVG, &ParseOptions::default())
+            .unwrap();
+        let ds: Vec<_> = parsed.paths.iter().map(|p| &p.d[..2]).collect();
+        assert_eq!(ds, ["M0", "M6"]);
+        assert_eq!(
+            parsed.document_warnings,
+            ["skipped the contents of 2 foreignObject element(s)"]
+        );
+    }
+
+    /// Exported by a tool writing 3.5 as "3,5" but 10 as "10".
+    const COMMA_DECIMAL_SVG: &str = r##"<svg viewBox="0 0 20 20">
+        <path d="M2 2 L18 2 L18 18 Z" fill="#000"/>
+        <path d="M3,5 6,25 L10 12,75 H4,5 C1,5 2 3,25 4 5,5 6,75 Z" fill="#000"/>
+    </svg>"##;
+
+    #[test]
+    fn test_comma_decimal_paths_warn() {
+        let tessellator = SvgTessellator::new();
+        let parsed = tessellator
+            .parse_svg_native(COMMA_DECIMAL_SVG, &ParseOptions::default())
+            .unwrap();
+        assert_eq!(parsed.paths.len(), 2);
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].path, 1);
+        assert_eq!(parsed.warnings[0].context, "L10 12,75");
+
+        // Tessellating still draws the path, and warns about it
+        let tessellation = SvgTessellator::new()
+            .tessellate_svg_document(COMMA_DECIMAL_SVG, 20.0, 20.0, &TessellateOptions::default())
+            .unwrap();
+        assert_eq!(tessellation.meshes.len(), 2);
+        assert!(tessellation.errors.is_empty());
+        let warnings: Vec<_> = tessellation
+            .warnings
+            .iter()
+            .map(|issue| (issue.path, issue.token, issue.message.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (1, Some(9), "'L' is missing arguments before 'H'"),
+                (
+                    1,
+                    None,
+                    "argument counts suggest ',' is used as the decimal separator"
+                ),
+            ]
+        );
+
+        // Comma-separated pairs are the common style and are left alone
+        let pairs = tessellator
+            .parse_svg_native(
+                r#"<svg><path d="M3,5 6,25 L10,12 C1,5 2,3 4,5 H4 V7 Z"/></svg>"#,
+                &ParseOptions::default(),
+            )
+            .unwrap();
+        assert!(pairs.warnings.is_empty());
+
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let error = tessellator
+            .parse_svg_native(COMMA_DECIMAL_SVG, &strict)
+            .unwrap_err();
+        assert_eq!(error.path, 1);
+        assert_eq!(error.token, Some(9));
+        assert_eq!(error.context, "10 12 75 [H] 4 5 C");
+        assert_eq!(error.message, "'L' is missing arguments before 'H'");
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_truncated_path() {
+        let svg = r#"<svg><path d="M0 0 L10 0 L10 10 C5 5 2"/></svg>"#;
+        let tessellator = SvgTessellator::new();
+
+        // Lenient parsing reads the missing numbers as 0
+        let lenient = tessellator
+            .parse_svg_native(svg, &ParseOptions::default())
+            .unwrap();
+        assert_eq!(lenient.paths[0].d, "M0 0 L10 0 L10 10 C5 5 2");
+        let (mut text, mut spans, mut commands) = (Vec::new(), Vec::new(), Vec::new());
+        parse_svg_path_d(
+            &lenient.paths[0].d,
+            &mut text,
+            &mut spans,
+            &mut commands,
+            false,
+        )
+        .unwrap();
+        assert_eq!(commands.len(), 4);
+
+        let error = tessellator
+            .parse_svg_native(
+                svg,
+                &ParseOptions {
+                    strict: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+        assert_eq!(error.token, Some(13));
+        assert_eq!(error.context, "5 5 2 [<end>]");
+        assert_eq!(
+            error.to_string(),
+            "Path 0, token 13: path data ends in the middle of 'C' (near \"5 5 2 [<end>]\")"
+        );
+
+        for (d, message) in [
+            ("M0 0 Lx 5", "cannot read 'x' as a number"),
+            (
+                "M0 0 A5 5 0 2 0 10 10",
+                "arc flag must be 0 or 1, found '2'",
+            ),
+            ("M0 0 Z 5", "'Z' takes no arguments, found '5'"),
+            ("0 0 L5 5", "path data must start with a moveto"),
+        ] {
+            commands.clear();
+            let error =
+                parse_svg_path_d(d, &mut text, &mut spans, &mut commands, true).unwrap_err();
+            assert_eq!(error.message, message, "{}", d);
+        }
+
+        // Exponents are numbers, not commands
+        commands.clear();
+        parse_svg_path_d(
+            "M1e1 2E-1 L1.5e+1 0",
+            &mut text,
+            &mut spans,
+            &mut commands,
+            true,
+        )
+        .unwrap();
+        assert!(matches!(
+            commands[..],
+            [
+                SvgCommand::MoveTo { x: 10.0, .. },
+                SvgCommand::LineTo {
+                    x: 15.0,
+                    y: 0.0,
+                    ..
+                }
+            ]
+        ));
+    }
+
+    /// A 1px-stroked rect, a filled rect and a circle in a 10x10 viewBox, for
+    /// display at 15x15 where every rect edge lands on a half or quarter pixel.
+    #[cfg(feature = "stroke")]
+    const SNAP_SVG: &str = r##"<svg viewBox="0 0 10 10">
+        <circle cx="7" cy="7" r="2" fill="#000"/>
+        <rect x="1" y="1" width="5" height="5" fill="none" stroke="#000" stroke-width="1"/>
+        <rect x="3" y="3" width="3" height="3" fill="#000"/>
+    </svg>"##;
+
+    #[cfg(feature = "stroke")]
+    fn on_pixel_grid(mesh: &TessellatedMesh) -> bool {
+        mesh.vertices
+            .chunks_exact(3)
+            .all(|v| v[0].fract().abs() < 1e-4 && v[1].fract().abs() < 1e-4)
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_snapped_stroke_covers_single_pixel_rows() {
+        let mut tessellator = SvgTessellator::new();
+        let snap = TessellateOptions {
+            snap_axis_aligned: true,
+            ..Default::default()
+        };
+        let snapped = tessellator.tessellate_svg_meshes_with_options(SNAP_SVG, 15.0, 15.0, &snap);
+        let plain = tessellator.tessellate_svg_meshes(SNAP_SVG, 15.0, 15.0);
+        assert_eq!(snapped.len(), 3);
+
+        // The rect from 1.5 to 9 snaps to half-pixel centers 1.5 and 9.5 with
+        // its 1.5px stroke narrowed to 1px, so every edge is a pixel boundary
+        let stroke = &snapped[1];
+        assert!(on_pixel_grid(stroke));
+        assert!(!on_pixel_grid(&plain[1]));
+        assert_eq!(
+            stroke.bounds,
+            MeshBounds {
+                min_x: 1.0,
+                min_y: 1.0,
+                max_x: 10.0,
+                max_y: 10.0,
+            }
+        );
+
+        let pixels = rasterize_mesh(stroke, 15, 15, [0, 0, 0, 255]);
+        let covered = pixels.chunks_exact(4).filter(|p| p[3] > 0).count();
+        assert_eq!(covered, 9 * 4 - 4);
+        for x in 1..10 {
+            assert_eq!(pixel(&pixels, 15, x, 1)[3], 255);
+            assert_eq!(pixel(&pixels, 15, x, 9)[3], 255);
+        }
+        for y in 2..9 {
+            assert_eq!(pixel(&pixels, 15, 2, y)[3], 0);
+        }
+
+        // Fills snap to whole pixels, and curves are left alone
+        assert!(on_pixel_grid(&snapped[2]));
+        assert_eq!(snapped[2].bounds.min_x, 5.0);
+        assert_eq!(snapped[0], plain[0]);
+    }
+
+    fn assert_positive_winding(mesh: &TessellatedMesh) {
+        let point = |index: u32| {
+            let i = index as usize * 3;
+            (mesh.vertices[i], mesh.vertices[i + 1])
+        };
+        for triangle in mesh.indices.chunks_exact(3) {
+            let (a, b, c) = (point(triangle[0]), point(triangle[1]), point(triangle[2]));
+            let area = signed_area(a.0, a.1, b.0, b.1, c.0, c.1);
+            assert!(area > 0.0, "triangle {:?} has area {}", triangle, area);
+        }
+    }
+
+    #[test]
+    fn test_meshes_have_consistent_winding() {
+        let shapes = r##"<svg viewBox="0 0 100 100">
+            <rect x="5" y="5" width="30" height="20" rx="4" fill="#000"/>
+            <circle cx="70" cy="20" r="15" fill="#000" stroke="#f00" stroke-width="3"/>
+            <polygon points="50,40 61,75 30,53 70,53 39,75" fill="#000"/>
+            <path d="M10 90 C20 50 40 130 60 90 S90 60 95 95 Z" fill="#000" stroke="#00f" stroke-width="2"/>
+            <path d="M70 80 a10 10 0 1 0 20 0 a10 10 0 1 0 -20 0 z" fill="#000"/>
+        </svg>"##;
+        let gear = include_str!("../../../assets/gear.svg");
+
+        let mut tessellator = SvgTessellator::new();
+        for (svg, size) in [(shapes, 100.0), (shapes, 13.0), (gear, 64.0)] {
+            let meshes = tessellator.tessellate_svg_meshes(svg, size, size);
+            assert!(!meshes.is_empty());
+            for mesh in &meshes {
+                assert!(!mesh.indices.is_empty());
+                assert_positive_winding(mesh);
+            }
+        }
+    }
+
+    #[test]
+    fn test_winding_pass_flips_and_drops_degenerate_triangles() {
+        let vertex = |x, y| TessVertex {
+            x,
+            y,
+            edge_dist: 1.0,
+        };
+        let buffers = VertexBuffers {
+            vertices: vec![
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(0.0, 1.0),
+                vertex(2.0, 0.0),
+            ],
+            // Positive, negative, then collinear
+            indices: vec![0, 1, 2, 0, 2, 1, 0, 1, 3],
+        };
+
+        let mesh = build_mesh(&buffers, true).unwrap();
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 1, 2]);
+        assert_positive_winding(&mesh);
+
+        let raw = build_mesh(&buffers, false).unwrap();
+        assert_eq!(raw.indices, buffers.indices);
+    }
+
+    fn rect_mesh(min: f32, max: f32, edge_dist: f32) -> TessellatedMesh {
+        TessellatedMesh {
+            vertices: vec![
+                min, min, edge_dist, max, min, edge_dist, max, max, edge_dist, min, max, edge_dist,
+            ],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            bounds: MeshBounds {
+                min_x: min,
+                min_y: min,
+                max_x: max,
+                max_y: max,
+            },
+            paint_order: 0,
+            boundary_edges: Vec::new(),
+            source_commands: Vec::new(),
+            command_ranges: Vec::new(),
+            transform: None,
+            alpha: None,
+            paint: None,
+            color: None,
+            uses_current_color: false,
+            degenerate: None,
+        }
+    }
+
+    fn pixel(pixels: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * width + x) * 4) as usize;
+        [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+    }
+
+    #[test]
+    fn test_rasterize_mesh_fills_pixel_centers() {
+        let pixels = rasterize_mesh(&rect_mesh(2.0, 6.0, 1.0), 8, 8, [255, 0, 0, 128]);
+
+        let covered = pixels.chunks_exact(4).filter(|p| p[3] > 0).count();
+        assert_eq!(covered, 16);
+        assert_eq!(pixel(&pixels, 8, 2, 2), [255, 0, 0, 128]);
+        assert_eq!(pixel(&pixels, 8, 5, 5), [255, 0, 0, 128]);
+        assert_eq!(pixel(&pixels, 8, 6, 6), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_rasterize_mesh_edge_dist_scales_alpha() {
+        let pixels = rasterize_mesh(&rect_mesh(0.0, 4.0, 0.5), 4, 4, [0, 0, 255, 255]);
+        assert_eq!(pixel(&pixels, 4, 1, 1), [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn test_resolve_color() {
+        assert_eq!(resolve_color(None), Some([0, 0, 0, 255]));
+        assert_eq!(resolve_color(Some("none")), None);
+        assert_eq!(resolve_color(Some("#f80")), Some([255, 136, 0, 255]));
+        assert_eq!(resolve_color(Some("#336699")), Some([51, 102, 153, 255]));
+        assert_eq!(resolve_color(Some("#33669980")), Some([51, 102, 153, 128]));
+        assert_eq!(resolve_color(Some("rgb(1, 2, 3)")), Some([1, 2, 3, 255]));
+        assert_eq!(resolve_color(Some("currentColor")), Some([0, 0, 0, 255]));
+        assert_eq!(resolve_color(Some("#zzz")), Some([0, 0, 0, 255]));
+    }
+
+    /// Compare RGBA8 pixels with `goldens/<name>.png`. Set
+    /// `GLADE_UPDATE_GOLDENS=1` to rewrite the golden instead.
+    fn assert_matches_golden(name: &str, pixels: &[u8], width: u32, height: u32) {
+        let path = format!("{}/goldens/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+
+        if std::env::var_os("GLADE_UPDATE_GOLDENS").is_some() {
+            let file = std::fs::File::create(&path).unwrap();
+            let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder
+                .write_header()
+                .unwrap()
+                .write_image_data(pixels)
+                .unwrap();
+            return;
+        }
+
+        let file = std::fs::File::open(&path)
+            .unwrap_or_else(|_| panic!("missing {}; run with GLADE_UPDATE_GOLDENS=1", path));
+        let mut reader = png::Decoder::new(file).read_info().unwrap();
+        let mut golden = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut golden).unwrap();
+        assert_eq!((info.width, info.height), (width, height));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+
+        // Float differences across platforms can flip a few edge samples, so
+        // allow small channel drift everywhere and a handful of outliers
+        let outliers = golden
+            .chunks_exact(4)
+            .zip(pixels.chunks_exact(4))
+            .filter(|(g, p)| g.iter().zip(p.iter()).any(|(a, b)| a.abs_diff(*b) > 2))
+            .count();
+        let allowed = (width * height) as usize / 200;
+        assert!(
+            outliers <= allowed,
+            "{}: {} pixels differ from the golden (allowed {})",
+            name,
+            outliers,
+            allowed
+        );
+    }
+
+    #[test]
+    fn test_golden_gear_icon() {
+        let svg = include_str!("../../../assets/gear.svg");
+        let pixels = SvgTessellator::new().rasterize_svg(svg, 64, 64);
+        assert_matches_golden("gear_64", &pixels, 64, 64);
+    }
+
+    #[test]
+    fn test_golden_overlapping_shapes() {
+        let svg = r##"<svg viewBox="0 0 32 32">
+            <rect x="2" y="2" width="18" height="18" fill="#e33"/>
+            <circle cx="20" cy="20" r="9" fill="#3366ccc0"/>
+            <polygon points="16,1 31,12 25,30" fill="rgb(40, 160, 80)"/>
+        </svg>"##;
+        let pixels = SvgTessellator::new().rasterize_svg(svg, 64, 64);
+        assert_matches_golden("shapes_64", &pixels, 64, 64);
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_golden_strokes_and_arcs() {
+        let svg = r##"<svg viewBox="0 0 32 32">
+            <path d="M4 28 Q16 -4 28 28 Z" fill="none" stroke="#222" stroke-width="2"/>
+            <path d="M6 16 a10 6 0 1 0 20 0 a10 6 0 1 0 -20 0 z" fill="#fc0" stroke="blue" stroke-width="1"/>
+        </svg>"##;
+        let pixels = SvgTessellator::new().rasterize_svg(svg, 64, 64);
+        assert_matches_golden("strokes_64", &pixels, 64, 64);
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_paint_order_follows_document_and_attribute() {
+        let svg = r##"<svg viewBox="0 0 32 32">
+            <rect x="2" y="2" width="18" height="18" fill="red"/>
+            <circle cx="20" cy="20" r="9" fill="blue" stroke="black" stroke-width="2"/>
+            <circle cx="8" cy="24" r="4" fill="green" stroke="black" stroke-width="1" paint-order="stroke fill"/>
+        </svg>"##;
+        let mut tessellator = SvgTessellator::new();
+        let parsed = tessellator
+            .parse_svg_native(svg, &ParseOptions::default())
+            .unwrap();
+        assert!(!parsed.paths[1].stroke_first());
+        assert!(parsed.paths[2].stroke_first());
+
+        let assets =
+            tessellator.tessellate_svg_assets(svg, 32.0, 32.0, &TessellateOptions::default());
+        let paints: Vec<_> = assets
+            .iter()
+            .map(|a| (a.color, a.stroke, a.mesh.paint_order))
+            .collect();
+        assert_eq!(
+            paints,
+            vec![
+                (Some([255, 0, 0, 255]), false, 0),
+                (Some([0, 0, 255, 255]), false, 2),
+                (Some([0, 0, 0, 255]), true, 3),
+                (Some([0, 0, 0, 255]), true, 4),
+                (Some([0, 128, 0, 255]), false, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assets_checked_reports_paths_dropped_in_strict_mode() {
+        let svg =
+            r#"<svg viewBox="0 0 10 10"><path d="M0 0 L10 0 L10 10 Z"/><path d="M0 0 L5"/></svg>"#;
+        let mut tessellator = SvgTessellator::new();
+        let (assets, errors) = tessellator.tessellate_svg_assets_checked(
+            svg,
+            10.0,
+            10.0,
+            &TessellateOptions::default(),
+        );
+        assert_eq!(assets.len(), 2);
+        assert!(errors.is_empty());
+
+        let strict = TessellateOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let (assets, errors) = tessellator.tessellate_svg_assets_checked(svg, 10.0, 10.0, &strict);
+        assert_eq!(assets.len(), 1);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path_index, 1);
+        assert_eq!(errors[0].stage, TessellationStage::Parse);
+    }
+
+    fn icon_assets() -> Vec<MeshAsset> {
+        let svg = r##"<svg viewBox="0 0 32 32">
+            <rect x="2" y="2" width="18" height="18" fill="#e33"/>
+            <circle cx="20" cy="20" r="9" fill="#36c" stroke="#3366ccc0" stroke-width="2"/>
+        </svg>"##;
+        SvgTessellator::new().tessellate_svg_assets(svg, 48.0, 48.0, &TessellateOptions::default())
+    }
+
+    #[cfg(feature = "stroke")]
+    fn vertex_bits(asset: &MeshAsset) -> Vec<u32> {
+        asset.mesh.vertices.iter().map(|v| v.to_bits()).collect()
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_mesh_assets_round_trip_bit_identical() {
+        let assets = icon_assets();
+        let paints: Vec<_> = assets
+            .iter()
+            .map(|a| (a.color, a.stroke, a.mesh.paint_order))
+            .collect();
+        assert_eq!(
+            paints,
+            vec![
+                (Some([0xee, 0x33, 0x33, 0xff]), false, 0),
+                (Some([0x33, 0x66, 0xcc, 0xff]), false, 2),
+                (Some([0x33, 0x66, 0xcc, 0xc0]), true, 3),
+            ]
+        );
+
+        let decoded = decode_meshes(&encode_meshes(&assets)).unwrap();
+        assert_eq!(decoded, assets);
+        for (decoded, original) in decoded.iter().zip(&assets) {
+            assert_eq!(vertex_bits(decoded), vertex_bits(original));
+        }
+        assert_eq!(decode_meshes(&encode_meshes(&[])).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_mesh_decoder_skips_unknown_trailing_fields() {
+        let assets = icon_assets();
+        let bytes = encode_meshes(&assets[..1]);
+
+        // Rewrite as a future file with four more bytes in each header and
+        // data after the last mesh
+        let mut future = bytes[..12].to_vec();
+        future[6..8].copy_from_slice(&16u16.to_le_bytes());
+        future.extend_from_slice(&[0xAB; 4]);
+        future.extend_from_slice(&40u16.to_le_bytes());
+        future.extend_from_slice(&(1u16 | 1 << 15).to_le_bytes());
+        future.extend_from_slice(&bytes[16..48]);
+        future.extend_from_slice(&[0xCD; 4]);
+        future.extend_from_slice(&bytes[48..]);
+        future.extend_from_slice(&[0xEF; 8]);
+
+        assert_eq!(decode_meshes(&future).unwrap(), assets[..1]);
+
+        // Headers from before paint order was stored read it from the index
+        let mut legacy = bytes[..12].to_vec();
+        legacy.extend_from_slice(&32u16.to_le_bytes());
+        legacy.extend_from_slice(&bytes[14..44]);
+        legacy.extend_from_slice(&bytes[48..]);
+        let mut expected = assets[..1].to_vec();
+        expected[0].mesh.paint_order = 0;
+        assert_eq!(decode_meshes(&legacy).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_mesh_decoder_reports_corruption() {
+        let bytes = encode_meshes(&icon_assets());
+        let corrupt = |offset: usize, patch: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[offset..offset + patch.len()].copy_from_slice(patch);
+            decode_meshes(&bytes).unwrap_err()
+        };
+
+        assert_eq!(corrupt(0, b"GLMZ"), MeshFormatError::BadMagic);
+        assert_eq!(
+            corrupt(4, &9u16.to_le_bytes()),
+            MeshFormatError::UnsupportedVersion(9)
+        );
+        assert_eq!(
+            corrupt(6, &4u16.to_le_bytes()),
+            MeshFormatError::HeaderTooShort {
+                offset: 0,
+                length: 4
             }
-            SvgCommand::LineTo { x, y, relative } => {
-                let (nx, ny) = if *relative {
-                    (current_x + x, current_y + y)
-                } else {
-                    (*x, *y)
-                };
-                let px = nx * scale_x + offset_x;
-                let py = ny * scale_y + offset_y;
-                builder.line_to(Point::new(px, py));
-                current_x = nx;
-                current_y = ny;
-                last_cmd_type = Some('L');
+        );
+        assert!(matches!(
+            corrupt(8, &u32::MAX.to_le_bytes()),
+            MeshFormatError::Truncated { .. }
+        ));
+        assert!(matches!(
+            corrupt(16, &u32::MAX.to_le_bytes()),
+            MeshFormatError::Truncated { .. }
+        ));
+        assert!(matches!(
+            decode_meshes(&bytes[..bytes.len() - 1]),
+            Err(MeshFormatError::Truncated { .. })
+        ));
+        assert!(matches!(
+            decode_meshes(&[]),
+            Err(MeshFormatError::Truncated { .. })
+        ));
+
+        let vertex_count = u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize;
+        let first_index = 12 + 36 + vertex_count * 12;
+        assert_eq!(
+            corrupt(first_index, &u32::MAX.to_le_bytes()),
+            MeshFormatError::IndexOutOfRange {
+                mesh: 0,
+                index: u32::MAX
             }
-            SvgCommand::HLineTo { x, relative } => {
-                let nx = if *relative { current_x + x } else { *x };
-                let px = nx * scale_x + offset_x;
-                let py = current_y * scale_y + offset_y;
-                builder.line_to(Point::new(px, py));
-                current_x = nx;
-                last_cmd_type = Some('H');
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_compiled_scene_loads_by_name_and_size() {
+        let icons = [
+            (
+                "dot",
+                r##"<svg viewBox="0 0 24 24"><circle cx="12" cy="12" r="8" fill="currentColor"/></svg>"##,
+            ),
+            (
+                "square",
+                r##"<svg viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16" fill="var(--accent)" stroke="currentColor" stroke-width="2"/></svg>"##,
+            ),
+            (
+                "bar",
+                r##"<svg viewBox="0 0 24 24"><rect x="2" y="10" width="20" height="4" fill="#36c"/></svg>"##,
+            ),
+        ];
+        let theme = ColorMap::from([
+            ("currentColor".to_string(), [10, 20, 30, 255]),
+            ("var(--accent)".to_string(), [200, 100, 0, 255]),
+        ]);
+        let options = SceneOptions {
+            sizes: vec![
+                DisplaySize {
+                    width: 16.0,
+                    height: 16.0,
+                },
+                DisplaySize {
+                    width: 48.0,
+                    height: 48.0,
+                },
+            ],
+            ..SceneOptions::default()
+        };
+        let inputs = icons
+            .iter()
+            .map(|(name, svg)| (name.to_string(), svg.as_bytes().to_vec()))
+            .collect();
+        let bytes = SvgTessellator::new().compile_scene(inputs, theme, &options);
+        let scene = Scene::decode(&bytes).unwrap();
+
+        let index: Vec<_> = scene
+            .entries()
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.display_width))
+            .collect();
+        assert_eq!(
+            index,
+            vec![
+                ("dot", 16.0),
+                ("dot", 48.0),
+                ("square", 16.0),
+                ("square", 48.0),
+                ("bar", 16.0),
+                ("bar", 48.0),
+            ]
+        );
+
+        let paints = |name: &str, size: f32| {
+            let entry = scene.entry(name, size, size).unwrap();
+            let paints: Vec<_> = entry.meshes.iter().map(|a| (a.color, a.stroke)).collect();
+            (entry.display_width, paints)
+        };
+        assert_eq!(
+            paints("dot", 12.0),
+            (16.0, vec![(Some([10, 20, 30, 255]), false)])
+        );
+        assert_eq!(
+            paints("square", 32.0),
+            (
+                48.0,
+                vec![
+                    (Some([200, 100, 0, 255]), false),
+                    (Some([10, 20, 30, 255]), true)
+                ]
+            )
+        );
+        // Larger than anything compiled falls back to the largest size
+        assert_eq!(
+            paints("bar", 96.0),
+            (48.0, vec![(Some([0x33, 0x66, 0xcc, 0xff]), false)])
+        );
+        assert!(scene.entry("missing", 16.0, 16.0).is_none());
+
+        // Meshes match tessellating each icon on its own at that size
+        let square = scene.entry("square", 48.0, 48.0).unwrap();
+        let alone: Vec<_> = SvgTessellator::new()
+            .tessellate_svg_meshes(icons[1].1, 48.0, 48.0)
+            .into_iter()
+            .map(|mesh| TessellatedMesh {
+                paint: None,
+                color: None,
+                uses_current_color: false,
+                ..mesh
+            })
+            .collect();
+        let meshes: Vec<_> = square.meshes.iter().map(|a| a.mesh.clone()).collect();
+        assert_eq!(meshes, alone);
+
+        assert!(matches!(
+            Scene::decode(&bytes[..20]),
+            Err(MeshFormatError::Truncated { .. })
+        ));
+        assert_eq!(
+            Scene::decode(&encode_meshes(&[])).unwrap_err(),
+            MeshFormatError::BadMagic
+        );
+    }
+
+    #[test]
+    fn test_degenerate_sizes_tessellate_finite() {
+        let documents = [
+            r##"<svg viewBox="0 0 24 24"><rect x="2" y="2" width="20" height="20" fill="red" stroke="blue" stroke-width="2"/></svg>"##,
+            r##"<svg viewBox="0 0 0 0"><rect x="2" y="2" width="20" height="20" stroke="blue"/></svg>"##,
+            r##"<svg width="0" height="0"><path d="M0 0 L0 0 L4 4" stroke="blue"/></svg>"##,
+        ];
+        let sizes = [(0.0, 0.0), (24.0, 0.0), (24.0, 24.0), (f32::INFINITY, 24.0)];
+        let mut tessellator = SvgTessellator::new();
+        for svg in documents {
+            for (width, height) in sizes {
+                let mut tessellation = tessellator
+                    .tessellate_svg_document(svg, width, height, &TessellateOptions::default())
+                    .unwrap();
+                assert_eq!(tessellation.errors, vec![]);
+                for entry in &mut tessellation.meshes {
+                    assert_eq!(finite::first_non_finite(&mut entry.mesh), None);
+                }
             }
-            SvgCommand::VLineTo { y, relative } => {
-                let ny = if *relative { current_y + y } else { *y };
-                let px = current_x * scale_x + offset_x;
-                let py = ny * scale_y + offset_y;
-                builder.line_to(Point::new(px, py));
-                current_y = ny;
-                last_cmd_type = Some('V');
+        }
+
+        let mut mesh = tessellator
+            .tessellate_path_native("M0 0 L10 0 L10 10 Z", 0.0, 0.0, 1.0, 1.0, FillRule::NonZero)
+            .unwrap();
+        mesh.vertices[4] = f32::INFINITY;
+        assert_eq!(
+            finite::first_non_finite(&mut mesh).unwrap().to_string(),
+            "Non-finite vertices[4]: inf"
+        );
+    }
+
+    /// A jagged chart line of `count` points, as flat x, y pairs.
+    #[cfg(feature = "stroke")]
+    fn chart_points(count: usize) -> Vec<f32> {
+        (0..count)
+            .flat_map(|i| {
+                let x = i as f32 * 0.37;
+                [x, 50.0 + (x * 1.3).sin() * 40.0 + (x * 7.1).cos() * 3.0]
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "stroke")]
+    fn points_to_d(points: &[f32]) -> String {
+        let mut d = String::new();
+        let mut command = 'M';
+        for pair in points.chunks_exact(2) {
+            if pair[0].is_nan() {
+                command = 'M';
+                continue;
             }
-            SvgCommand::CubicTo {
-                x1,
-                y1,
-                x2,
-                y2,
-                x,
-                y,
-                relative,
-            } => {
-                let (nx1, ny1, nx2, ny2, nx, ny) = if *relative {
-                    (
-                        current_x + x1,
-                        current_y + y1,
-                        current_x + x2,
-                        current_y + y2,
-                        current_x + x,
-                        current_y + y,
-                    )
-                } else {
-                    (*x1, *y1, *x2, *y2, *x, *y)
-                };
-                builder.cubic_bezier_to(
-                    Point::new(nx1 * scale_x + offset_x, ny1 * scale_y + offset_y),
-                    Point::new(nx2 * scale_x + offset_x, ny2 * scale_y + offset_y),
-                    Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
-                );
-                last_control_x = nx2;
-                last_control_y = ny2;
-                current_x = nx;
-                current_y = ny;
-                last_cmd_type = Some('C');
+            d.push_str(&format!("{}{} {} ", command, pair[0], pair[1]));
+            command = 'L';
+        }
+        d
+    }
+
+    fn mesh_area(mesh: &TessellatedMesh) -> f32 {
+        let point = |index: u32| {
+            let i = index as usize * 3;
+            (mesh.vertices[i], mesh.vertices[i + 1])
+        };
+        mesh.indices
+            .chunks_exact(3)
+            .map(|t| {
+                let (a, b, c) = (point(t[0]), point(t[1]), point(t[2]));
+                signed_area(a.0, a.1, b.0, b.1, c.0, c.1).abs() / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_transformed_path_rotates_bounds_and_keeps_area() {
+        let mut tessellator = SvgTessellator::new();
+        let (sin, cos) = std::f32::consts::FRAC_PI_4.sin_cos();
+        let rotate = [cos, sin, -sin, cos, 50.0, 20.0];
+        let square = "M0 0 L10 0 L10 10 L0 10 Z";
+
+        let fill = tessellator
+            .tessellate_path_with_transform_native(square, rotate, &PathPaintOptions::default())
+            .unwrap();
+        let diagonal = 10.0 * std::f32::consts::SQRT_2;
+        assert!((fill.bounds.max_x - fill.bounds.min_x - diagonal).abs() < 1e-3);
+        assert!((fill.bounds.max_y - fill.bounds.min_y - diagonal).abs() < 1e-3);
+        assert!((fill.bounds.min_y - 20.0).abs() < 1e-3, "{:?}", fill.bounds);
+        assert!((mesh_area(&fill) - 100.0).abs() < 1e-2);
+        assert_positive_winding(&fill);
+
+        // Rotation with uniform scale 2 doubles the stroke's length and width
+        let scale_rotate = rotate.map(|v| v * 2.0);
+        let stroke = tessellator
+            .tessellate_path_with_transform_native(
+                "M0 0 L10 0",
+                scale_rotate,
+                &PathPaintOptions {
+                    stroke_width: Some(1.0),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!((mesh_area(&stroke) - 40.0).abs() < 1e-2);
+    }
+
+    /// Chains of a `boundary_edges` result as (edge count, closed, length).
+    fn edge_loops(mesh: &TessellatedMesh) -> Vec<(usize, bool, f32)> {
+        let point = |index: u32| {
+            let i = index as usize * 3;
+            (mesh.vertices[i], mesh.vertices[i + 1])
+        };
+        let mut loops: Vec<(u32, u32, usize, f32)> = Vec::new();
+        for pair in mesh.boundary_edges.chunks_exact(2) {
+            let ((ax, ay), (bx, by)) = (point(pair[0]), point(pair[1]));
+            let length = (bx - ax).hypot(by - ay);
+            match loops.last_mut() {
+                Some((_, end, count, total)) if *end == pair[0] => {
+                    *end = pair[1];
+                    *count += 1;
+                    *total += length;
+                }
+                _ => loops.push((pair[0], pair[1], 1, length)),
             }
-            SvgCommand::SmoothCubicTo {
-                x2,
-                y2,
-                x,
-                y,
-                relative,
-            } => {
-                let (cx1, cy1) = match last_cmd_type {
-                    Some('C') | Some('S') => (
-                        2.0 * current_x - last_control_x,
-                        2.0 * current_y - last_control_y,
-                    ),
-                    _ => (current_x, current_y),
-                };
-                let (nx2, ny2, nx, ny) = if *relative {
-                    (current_x + x2, current_y + y2, current_x + x, current_y + y)
-                } else {
-                    (*x2, *y2, *x, *y)
-                };
-                builder.cubic_bezier_to(
-                    Point::new(cx1 * scale_x + offset_x, cy1 * scale_y + offset_y),
-                    Point::new(nx2 * scale_x + offset_x, ny2 * scale_y + offset_y),
-                    Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
-                );
-                last_control_x = nx2;
-                last_control_y = ny2;
-                current_x = nx;
-                current_y = ny;
-                last_cmd_type = Some('S');
+        }
+        loops
+            .into_iter()
+            .map(|(start, end, count, length)| (count, start == end, length))
+            .collect()
+    }
+
+    #[test]
+    fn test_boundary_edges_form_closed_loops() {
+        let mut tessellator = SvgTessellator::new();
+        let options = TessellateOptions {
+            boundary_edges: true,
+            ..Default::default()
+        };
+        let svg = r##"<svg viewBox="0 0 20 20">
+            <rect x="2" y="4" width="10" height="6" fill="#000"/>
+        </svg>"##;
+        let rect = &tessellator.tessellate_svg_meshes_with_options(svg, 20.0, 20.0, &options)[0];
+        let loops = edge_loops(rect);
+        assert_eq!(loops.len(), 1);
+        let (count, closed, length) = loops[0];
+        assert!(count >= 4 && closed);
+        assert!((length - 32.0).abs() < TESSELLATION_TOLERANCE);
+
+        let donut = r##"<svg viewBox="0 0 20 20">
+            <path d="M10 1 A9 9 0 1 1 10 19 A9 9 0 1 1 10 1 Z M10 5 A5 5 0 1 0 10 15 A5 5 0 1 0 10 5 Z" fill="#000"/>
+        </svg>"##;
+        let ring = &tessellator.tessellate_svg_meshes_with_options(donut, 20.0, 20.0, &options)[0];
+        let loops = edge_loops(ring);
+        assert_eq!(loops.len(), 2);
+        assert!(loops.iter().all(|&(_, closed, _)| closed));
+
+        let plain = tessellator.tessellate_svg_meshes(svg, 20.0, 20.0);
+        assert!(plain[0].boundary_edges.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_point_path_matches_path_d() {
+        let mut tessellator = SvgTessellator::new();
+        let points = chart_points(500);
+        let d = points_to_d(&points);
+
+        let from_points = tessellator.tessellate_points_native(&points, false, Some(2.0));
+        let from_d = tessellator.tessellate_stroke_native(&d, 2.0, 0.0, 0.0, 1.0, 1.0);
+        assert!(!from_points.as_ref().unwrap().indices.is_empty());
+        assert_eq!(from_points, from_d);
+
+        let closed_d = format!("{}Z", d);
+        let from_points = tessellator.tessellate_points_native(&points, true, None);
+        let from_d =
+            tessellator.tessellate_path_native(&closed_d, 0.0, 0.0, 1.0, 1.0, FillRule::NonZero);
+        assert!(!from_points.as_ref().unwrap().indices.is_empty());
+        assert_eq!(from_points, from_d);
+
+        // A NaN pair splits the line into two subpaths
+        let mut split = chart_points(40);
+        split.extend([f32::NAN, f32::NAN]);
+        split.extend(chart_points(40).iter().map(|v| v + 100.0));
+        split.push(7.0);
+        let from_points = tessellator.tessellate_points_native(&split, false, Some(1.5));
+        let from_d =
+            tessellator.tessellate_stroke_native(&points_to_d(&split), 1.5, 0.0, 0.0, 1.0, 1.0);
+        assert_eq!(from_points, from_d);
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_open_and_reopened_subpaths_tessellate() {
+        let mut tessellator = SvgTessellator::new();
+        for d in [
+            "M0 0 L10 0 M0 5 L10 5",
+            "M0 0 L10 0 L10 10 Z L0 10",
+            "M0 0 L10 0 Z Z",
+        ] {
+            let mesh = tessellator
+                .tessellate_stroke_native(d, 1.0, 0.0, 0.0, 1.0, 1.0)
+                .unwrap();
+            assert!(!mesh.indices.is_empty(), "{}", d);
+        }
+    }
+
+    #[test]
+    fn test_path_measure_line_and_circle() {
+        let line = PathMeasure::new("M0 0 L30 40", 0.0);
+        assert_eq!(line.length(), 50.0);
+        let mid = line.sample(25.0).unwrap();
+        assert_eq!((mid.x, mid.y), (15.0, 20.0));
+        assert_eq!(mid.tangent, 40f32.atan2(30.0));
+        assert_eq!(line.sample(-5.0).map(|s| (s.x, s.y)), Some((0.0, 0.0)));
+        assert_eq!(line.sample(99.0).map(|s| (s.x, s.y)), Some((30.0, 40.0)));
+        assert!(PathMeasure::new("M5 5", 0.0).sample(0.0).is_none());
+
+        // Clockwise on screen from the top, through the right side
+        let circle = PathMeasure::new("M50 0 A50 50 0 1 1 50 100 A50 50 0 1 1 50 0 Z", 0.01);
+        let circumference = 2.0 * std::f32::consts::PI * 50.0;
+        // Arcs are built from quadratics, which bulge slightly outward
+        let error = (circle.length() - circumference).abs() / circumference;
+        assert!(error < 0.002, "{}", circle.length());
+        let quarter = circle.sample(circle.length() / 4.0).unwrap();
+        assert!((quarter.x - 100.0).abs() < 0.1 && (quarter.y - 50.0).abs() < 0.1);
+        let half = circle.sample(circle.length() / 2.0).unwrap();
+        assert!((half.x - 50.0).abs() < 0.1 && (half.y - 100.0).abs() < 0.1);
+        // Heading left along the bottom
+        assert!(half.tangent.cos() < -0.999);
+    }
+
+    #[test]
+    fn test_path_measure_segments_round_trip() {
+        let d = "M0 0 C10 20 30 20 40 0 M50 0 L80 0 L80 30 Z";
+        let measure = PathMeasure::new(d, 0.05);
+        let length = measure.length();
+
+        let whole = measure.segment(0.0, length);
+        assert_eq!(whole.matches('M').count(), 2);
+        assert!(whole.ends_with('Z'));
+        let copy = PathMeasure::new(&whole, 0.05);
+        assert!((copy.length() - length).abs() < 1e-3);
+        for step in 0..=20 {
+            let distance = length * step as f32 / 20.0;
+            let (a, b) = (
+                measure.sample(distance).unwrap(),
+                copy.sample(distance).unwrap(),
+            );
+            assert!(
+                (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3,
+                "{}",
+                distance
+            );
+        }
+
+        // Pieces add back up, and a piece of a closed subpath stays open
+        let split = length * 0.8;
+        let head = PathMeasure::new(&measure.segment(0.0, split), 0.05);
+        let tail = measure.segment(split, length);
+        assert!(!tail.contains('Z'));
+        let tail = PathMeasure::new(&tail, 0.05);
+        assert!((head.length() + tail.length() - length).abs() < 1e-3);
+        let (a, b) = (measure.sample(split).unwrap(), tail.sample(0.0).unwrap());
+        assert!((a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_dashes_follow_pattern_per_subpath() {
+        let measure = PathMeasure::new("M0 0 L50 0 M0 10 L20 10", 0.0);
+        let dashes = |pattern: &[f32], offset: f32| {
+            let dashed = measure.dash(pattern, offset);
+            let d = PathMeasure::from_path(&dashed, 0.0).segment(0.0, f32::INFINITY);
+            (
+                d.matches('M').count(),
+                PathMeasure::from_path(&dashed, 0.0).length(),
+            )
+        };
+
+        // 0-10 15-25 30-40 45-50, then the pattern restarts: 0-10 15-20
+        assert_eq!(dashes(&[10.0, 5.0], 0.0), (6, 50.0));
+        // 0-5 10-20 25-35 40-50, then 0-5 10-20
+        assert_eq!(dashes(&[10.0, 5.0], 5.0), (6, 50.0));
+        // An odd pattern repeats: dash 10, gap 10
+        assert_eq!(dashes(&[10.0], 0.0), (4, 40.0));
+        // Invalid patterns draw the whole path
+        assert_eq!(dashes(&[5.0, -1.0], 0.0), (2, 70.0));
+        assert_eq!(dashes(&[0.0, 0.0], 0.0), (2, 70.0));
+
+        let mut tessellator = SvgTessellator::new();
+        let dashed = tessellator
+            .tessellate_dashed_stroke_native(
+                "M0 0 L50 0",
+                2.0,
+                &[10.0, 5.0],
+                0.0,
+                &DashedStrokeOptions::default(),
+            )
+            .unwrap();
+        let solid = tessellator
+            .tessellate_stroke_native("M0 0 L50 0", 2.0, 0.0, 0.0, 1.0, 1.0)
+            .unwrap();
+        assert_eq!(dashed.indices.len(), solid.indices.len() * 4);
+
+        let point = tessellator.point_at_length("M0 0 L30 40", 25.0).unwrap();
+        assert_eq!((point.x, point.y), (15.0, 20.0));
+    }
+
+    #[test]
+    fn test_source_commands_map_curve_back_to_its_command() {
+        let mut tessellator = SvgTessellator::new();
+        let d = "M0 0 L50 0 C80 0 100 20 100 50 L0 50 Z";
+        let identity = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        let options = PathPaintOptions {
+            source_commands: true,
+            ..Default::default()
+        };
+        let mesh = tessellator
+            .tessellate_path_with_transform_native(d, identity, &options)
+            .unwrap();
+
+        let range = |command: usize| {
+            let pair = &mesh.command_ranges[command * 2..command * 2 + 2];
+            &d[pair[0] as usize..pair[1] as usize]
+        };
+        assert_eq!(mesh.command_ranges.len(), 5 * 2);
+        assert_eq!(range(1), "L50 0");
+        assert_eq!(range(2), "C80 0 100 20 100 50");
+        assert_eq!(range(4), "Z");
+
+        assert_eq!(mesh.source_commands.len(), mesh.vertices.len() / 3);
+        // Only the curve has vertices right of the line's end, and the
+        // triangles along it have at least two of them
+        let on_curve = |v: &u32| mesh.vertices[*v as usize * 3] > 50.5;
+        let mut curve_triangles = 0;
+        for triangle in mesh.indices.chunks_exact(3) {
+            if triangle.iter().filter(|v| on_curve(v)).count() >= 2 {
+                curve_triangles += 1;
+                for v in triangle.iter().filter(|v| on_curve(v)) {
+                    assert_eq!(mesh.source_commands[*v as usize], 2);
+                }
             }
-            SvgCommand::QuadTo {
-                x1,
-                y1,
-                x,
-                y,
-                relative,
-            } => {
-                let (nx1, ny1, nx, ny) = if *relative {
-                    (current_x + x1, current_y + y1, current_x + x, current_y + y)
-                } else {
-                    (*x1, *y1, *x, *y)
-                };
-                builder.quadratic_bezier_to(
-                    Point::new(nx1 * scale_x + offset_x, ny1 * scale_y + offset_y),
-                    Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
-                );
-                last_control_x = nx1;
-                last_control_y = ny1;
-                current_x = nx;
-                current_y = ny;
-                last_cmd_type = Some('Q');
+        }
+        assert!(curve_triangles > 2, "{}", curve_triangles);
+
+        let plain = tessellator
+            .tessellate_path_with_transform_native(d, identity, &PathPaintOptions::default())
+            .unwrap();
+        assert!(plain.source_commands.is_empty() && plain.command_ranges.is_empty());
+        assert_eq!(plain.indices, mesh.indices);
+    }
+
+    #[test]
+    fn test_corrupt_path_is_reported_without_losing_the_rest() {
+        let mut tessellator = SvgTessellator::new();
+        let svg = r#"<svg width="100" height="100" viewBox="0 0 100 100">
+            <path d="M0 0 L40 0 L40 40 Z" fill="red"/>
+            <path d="M50 50 L90 oops L90 90 Z" fill="green"/>
+            <path d="M0 60 L40 60 L40 100 Z" fill="blue"/>
+        </svg>"#;
+
+        let strict = TessellateOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let result = tessellator
+            .tessellate_svg_document(svg, 100.0, 100.0, &strict)
+            .unwrap();
+        assert_eq!(result.meshes.len(), 2);
+        assert_eq!(
+            result
+                .meshes
+                .iter()
+                .map(|entry| entry.mesh.paint_order)
+                .collect::<Vec<_>>(),
+            vec![0, 4]
+        );
+        assert_eq!(result.errors.len(), 1);
+        let error = &result.errors[0];
+        assert_eq!(
+            (error.path_index, error.stage),
+            (1, TessellationStage::Parse)
+        );
+        assert!(error.message.starts_with("cannot read 'o' as a number"));
+        assert!(result.warnings.is_empty());
+
+        // A panicking path becomes an error instead of unwinding further
+        let panicked = catch_path_panic(|| -> Result<(), String> { panic!("lyon gave up") });
+        assert_eq!(panicked, Err("panicked: lyon gave up".to_string()));
+    }
+
+    #[test]
+    fn test_imperfect_paths_draw_by_default_and_drop_in_strict_mode() {
+        let mut tessellator = SvgTessellator::new();
+        let svg = r#"<svg width="20" height="20" viewBox="0 0 20 20">
+            <path d="M0 0 L10 0 L10 10 L0"/>
+            <path d="M0,0 h10 v10 h-10 z x"/>
+        </svg>"#;
+
+        let lenient = tessellator
+            .tessellate_svg_document(svg, 20.0, 20.0, &TessellateOptions::default())
+            .unwrap();
+        assert_eq!(lenient.meshes.len(), 2);
+        assert!(lenient.errors.is_empty());
+        let warned: Vec<_> = lenient.warnings.iter().map(|issue| issue.path).collect();
+        assert_eq!(warned, [0, 1]);
+
+        let strict = TessellateOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let strict = tessellator
+            .tessellate_svg_document(svg, 20.0, 20.0, &strict)
+            .unwrap();
+        assert!(strict.meshes.is_empty());
+        assert!(strict.warnings.is_empty());
+        let failed: Vec<_> = strict.errors.iter().map(|error| error.path_index).collect();
+        assert_eq!(failed, [0, 1]);
+    }
+
+    #[test]
+    #[cfg(feature = "gradients")]
+    fn test_radial_distances_match_analytic_distance_at_corners() {
+        let mut tessellator = SvgTessellator::new();
+        let d = "M0 0 L200 0 L200 100 L0 100 Z";
+
+        // Bounding box units stretch the unit square over the 200x100 box
+        let boxed = tessellator
+            .tessellate_radial_gradient_fill_native(d, &RadialGradientInput::default())
+            .unwrap();
+        assert_eq!(
+            boxed.gradient.to_gradient,
+            [0.005, 0.0, 0.0, 0.01, 0.0, 0.0]
+        );
+        assert_eq!((boxed.gradient.fx, boxed.gradient.fy), (0.5, 0.5));
+        for (vertex, distance) in boxed
+            .mesh
+            .vertices
+            .chunks_exact(3)
+            .zip(&boxed.radial_distances)
+        {
+            let expected = (vertex[0] / 200.0 - 0.5).hypot(vertex[1] / 100.0 - 0.5);
+            assert!((distance - expected).abs() < 1e-5);
+        }
+
+        let gradient = RadialGradientInput {
+            cx: 50.0,
+            cy: 50.0,
+            r: 40.0,
+            units: GradientUnits::UserSpaceOnUse,
+            refine_tolerance: Some(0.5),
+            ..Default::default()
+        };
+        let refined = tessellator
+            .tessellate_radial_gradient_fill_native(d, &gradient)
+            .unwrap();
+        let mesh = &refined.mesh;
+        let point = |v: u32| {
+            (
+                mesh.vertices[v as usize * 3],
+                mesh.vertices[v as usize * 3 + 1],
+            )
+        };
+        let analytic = |(x, y): (f32, f32)| (x - 50.0).hypot(y - 50.0);
+        let mut area = 0.0;
+        for triangle in mesh.indices.chunks_exact(3) {
+            for (&from, &to) in triangle.iter().zip(triangle.iter().cycle().skip(1)) {
+                let distance = refined.radial_distances[from as usize];
+                assert!((distance - analytic(point(from))).abs() < 1e-4);
+                // Interpolating along each edge stays within the tolerance
+                let ((ax, ay), (bx, by)) = (point(from), point(to));
+                let mid = analytic(((ax + bx) / 2.0, (ay + by) / 2.0));
+                let interpolated = (distance + refined.radial_distances[to as usize]) / 2.0;
+                assert!((interpolated - mid).abs() <= 0.5);
             }
-            SvgCommand::SmoothQuadTo { x, y, relative } => {
-                let (cx, cy) = match last_cmd_type {
-                    Some('Q') | Some('T') => (
-                        2.0 * current_x - last_control_x,
-                        2.0 * current_y - last_control_y,
-                    ),
-                    _ => (current_x, current_y),
-                };
-                let (nx, ny) = if *relative {
-                    (current_x + x, current_y + y)
-                } else {
-                    (*x, *y)
-                };
-                builder.quadratic_bezier_to(
-                    Point::new(cx * scale_x + offset_x, cy * scale_y + offset_y),
-                    Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
-                );
-                last_control_x = cx;
-                last_control_y = cy;
-                current_x = nx;
-                current_y = ny;
-                last_cmd_type = Some('T');
+            let ((ax, ay), (bx, by), (cx, cy)) =
+                (point(triangle[0]), point(triangle[1]), point(triangle[2]));
+            let twice = signed_area(ax, ay, bx, by, cx, cy);
+            assert!(twice > 0.0);
+            area += twice / 2.0;
+        }
+        // Splitting keeps the fill covered exactly once
+        assert!((area - 20_000.0).abs() < 1e-2, "{}", area);
+        assert!(mesh.vertices.len() > boxed.mesh.vertices.len());
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_inner_and_outer_strokes_stay_on_their_side() {
+        let mut tessellator = SvgTessellator::new();
+        let d = "M0 0 L100 0 L100 100 L0 100 Z";
+        let identity = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        let stroke = |tessellator: &mut SvgTessellator, stroke_align| {
+            let options = PathPaintOptions {
+                stroke_width: Some(10.0),
+                stroke_align,
+                ..Default::default()
+            };
+            tessellator
+                .tessellate_path_with_transform_native(d, identity, &options)
+                .unwrap()
+        };
+        let area = |mesh: &TessellatedMesh| -> f32 {
+            let point = |v: u32| {
+                (
+                    mesh.vertices[v as usize * 3],
+                    mesh.vertices[v as usize * 3 + 1],
+                )
+            };
+            mesh.indices
+                .chunks_exact(3)
+                .map(|t| {
+                    let ((ax, ay), (bx, by), (cx, cy)) = (point(t[0]), point(t[1]), point(t[2]));
+                    signed_area(ax, ay, bx, by, cx, cy) / 2.0
+                })
+                .sum()
+        };
+
+        let inner = stroke(&mut tessellator, StrokeAlign::Inner);
+        assert_eq!(
+            inner.bounds,
+            MeshBounds {
+                min_x: 0.0,
+                min_y: 0.0,
+                max_x: 100.0,
+                max_y: 100.0,
             }
-            SvgCommand::ArcTo {
-                rx,
-                ry,
-                rotation,
-                large_arc,
-                sweep,
-                x,
-                y,
-                relative,
-            } => {
-                let (nx, ny) = if *relative {
-                    (current_x + x, current_y + y)
-                } else {
-                    (*x, *y)
-                };
+        );
+        assert!((area(&inner) - (100.0 * 100.0 - 80.0 * 80.0)).abs() < 0.1);
+
+        let outer = stroke(&mut tessellator, StrokeAlign::Outer);
+        assert_eq!(
+            outer.bounds,
+            MeshBounds {
+                min_x: -10.0,
+                min_y: -10.0,
+                max_x: 110.0,
+                max_y: 110.0,
+            }
+        );
+        assert!((area(&outer) - (120.0 * 120.0 - 100.0 * 100.0)).abs() < 0.1);
+
+        // Center is the plain stroke
+        let center = stroke(&mut tessellator, StrokeAlign::Center);
+        let plain = tessellator
+            .tessellate_stroke_native(d, 10.0, 0.0, 0.0, 1.0, 1.0)
+            .unwrap();
+        assert_eq!(center, plain);
+
+        // Documents take the alignment from each path
+        let svg = r#"<svg width="100" height="100">
+            <path d="M0 0 L100 0 L100 100 L0 100 Z" fill="none" stroke="red"
+                stroke-width="10" stroke-alignment="inner"/>
+        </svg>"#;
+        let meshes = tessellator.tessellate_svg_meshes(svg, 100.0, 100.0);
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].bounds, inner.bounds);
+    }
+
+    #[test]
+    fn test_accessibility_metadata_is_decoded() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:dc="http://purl.org/dc/elements/1.1/"
+            viewBox="0 0 24 24" role="img" aria-label="Tom's &quot;save&quot; icon">
+            <metadata><rdf:RDF><cc:Work><dc:title>inkscape-export</dc:title></cc:Work></rdf:RDF></metadata>
+            <title>
+                Save &amp; close
+            </title>
+            <desc>Writes the file &lt;name&gt; to disk &#8212; caf&#xE9; <![CDATA[a & b]]></desc>
+            <g><title>Group title</title></g>
+            <path id="disk" aria-label="Floppy &amp; label" d="M0 0 L24 24" fill="#000"/>
+            <path d="M0 24 L24 0" fill="#000"/>
+            <rect id="frame" x="0" y="0" width="24" height="24" fill="none"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        let labels = &parsed.accessibility;
+        assert_eq!(labels.title.as_deref(), Some("Save & close"));
+        assert_eq!(
+            labels.desc.as_deref(),
+            Some("Writes the file <name> to disk \u{2014} caf\u{e9} a & b")
+        );
+        assert_eq!(labels.role.as_deref(), Some("img"));
+        assert_eq!(labels.aria_label.as_deref(), Some("Tom's \"save\" icon"));
+        assert!(!labels.aria_hidden);
+        assert_eq!(
+            labels.paths,
+            vec![
+                PathAccessibility {
+                    path: 0,
+                    id: Some("disk".to_string()),
+                    aria_label: Some("Floppy & label".to_string()),
+                },
+                PathAccessibility {
+                    path: 2,
+                    id: Some("frame".to_string()),
+                    aria_label: None,
+                },
+            ]
+        );
+
+        // Unknown entities are kept as written
+        let hidden = parse_svg_content(
+            r#"<svg aria-hidden="true" viewBox="0 0 24 24"><title>&nbsp;A &unknown; B</title></svg>"#,
+            None,
+            None,
+            None,
+        );
+        assert!(hidden.accessibility.aria_hidden);
+        assert_eq!(
+            hidden.accessibility.title.as_deref(),
+            Some("&nbsp;A &unknown; B")
+        );
+        assert_eq!(hidden.accessibility.desc, None);
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_stroke_widths_per_axis() {
+        // Extent across the stroke of vertices strictly within a span along
+        // the segment
+        fn thickness(mesh: &TessellatedMesh, along_x: bool, from: f32, to: f32) -> f32 {
+            let (along, across) = if along_x { (0, 1) } else { (1, 0) };
+            let values: Vec<f32> = mesh
+                .vertices
+                .chunks_exact(3)
+                .filter(|v| v[along] > from && v[along] < to)
+                .map(|v| v[across])
+                .collect();
+            assert!(!values.is_empty());
+            let max = values.iter().cloned().fold(f32::MIN, f32::max);
+            let min = values.iter().cloned().fold(f32::MAX, f32::min);
+            max - min
+        }
+
+        let mut tessellator = SvgTessellator::new();
+        // Down, then right, with a midpoint on each run so both have
+        // vertices inside the measured spans
+        let d = "M10 10 L10 50 L10 90 L50 90 L90 90";
+        let options = StrokeWidthOptions {
+            stroke_width_x: Some(4.0),
+            stroke_width_y: Some(10.0),
+            ..Default::default()
+        };
+        // Scaled 2x horizontally, so the vertical run sits at x = 20
+        let mesh = tessellator
+            .tessellate_stroke_with_options_native(d, 1.0, [0.0, 0.0, 2.0, 1.0], &options)
+            .unwrap();
+        assert!((thickness(&mesh, false, 20.0, 80.0) - 4.0).abs() < 1e-3);
+        assert!((thickness(&mesh, true, 40.0, 160.0) - 10.0).abs() < 1e-3);
+        assert!((mesh.bounds.min_x - 18.0).abs() < 1e-3);
+        assert!((mesh.bounds.max_y - 95.0).abs() < 1e-3);
+
+        // Equal widths stroke as before
+        let uniform = StrokeWidthOptions {
+            stroke_width_x: Some(3.0),
+            stroke_width_y: Some(3.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            tessellator
+                .tessellate_stroke_with_options_native(d, 1.5, [0.0, 0.0, 2.0, 1.0], &uniform)
+                .unwrap(),
+            tessellator
+                .tessellate_stroke_native(d, 1.5, 0.0, 0.0, 2.0, 1.0)
+                .unwrap()
+        );
+
+        // Curves are stroked squashed too: a circle's stroke reaches half of
+        // `stroke_width_y` past its top and bottom
+        let circle = "M50 0 A50 50 0 1 1 50 100 A50 50 0 1 1 50 0 Z";
+        let stretched = StrokeWidthOptions {
+            stroke_width_x: Some(1.0),
+            stroke_width_y: Some(8.0),
+            ..Default::default()
+        };
+        let mesh = tessellator
+            .tessellate_stroke_with_options_native(circle, 1.0, [0.0, 0.0, 1.0, 1.0], &stretched)
+            .unwrap();
+        assert!(mesh.bounds.max_y > 103.9 && mesh.bounds.max_y < 104.1);
+    }
+
+    #[test]
+    fn test_hash_svg_is_xxh64_of_the_document() {
+        let engine = SvgTessellator::new();
+        // Reference values from the xxHash test suite
+        assert_eq!(engine.hash_svg("").as_str(), "ef46db3751d8e999");
+        assert_eq!(engine.hash_svg("abc").as_str(), "44bc2cf5ad770999");
+        assert_eq!(
+            engine
+                .hash_svg("Nobody inspects the spammish repetition")
+                .as_str(),
+            "fbcea83c8a378bf1"
+        );
+
+        // Every byte counts, including those past the 32-byte stripes
+        let svg = r#"<svg viewBox="0 0 24 24"><path d="M0 0L24 24Z" fill="red"/></svg>"#;
+        let hash = engine.hash_svg_native(svg);
+        assert_eq!(engine.hash_svg_native(svg), hash);
+        for index in 0..svg.len() {
+            let mut changed = svg.as_bytes().to_vec();
+            changed[index] ^= 1;
+            let changed = String::from_utf8(changed).unwrap();
+            assert_ne!(engine.hash_svg_native(&changed), hash, "byte {}", index);
+        }
+        assert_ne!(engine.hash_svg_native(&svg[..svg.len() - 1]), hash);
+    }
 
-                if *rx == 0.0 || *ry == 0.0 {
-                    builder.line_to(Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y));
-                } else {
-                    let arc = lyon::geom::SvgArc {
-                        from: Point::new(
-                            current_x * scale_x + offset_x,
-                            current_y * scale_y + offset_y,
-                        ),
-                        to: Point::new(nx * scale_x + offset_x, ny * scale_y + offset_y),
-                        radii: lyon::math::Vector::new(rx * scale_x, ry * scale_y),
-                        x_rotation: lyon::geom::Angle::degrees(*rotation),
-                        flags: lyon::geom::ArcFlags {
-                            large_arc: *large_arc,
-                            sweep: *sweep,
-                        },
-                    };
+    #[test]
+    fn test_dynamic_path_mesh_is_independent_of_its_transform() {
+        let spinner = |angle: f32| {
+            format!(
+                r##"<svg viewBox="0 0 24 24">
+                    <rect width="24" height="24" fill="#eee"/>
+                    <rect id="arm" x="11" y="2" width="2" height="8" fill="#000"
+                        transform="rotate({} 12 12)"/>
+                </svg>"##,
+                angle
+            )
+        };
+        let options = TessellateOptions {
+            dynamic_paths: vec![DynamicPath {
+                path: PathRef::Id("arm".to_string()),
+                transform: None,
+            }],
+            ..Default::default()
+        };
+        let mut tessellator = SvgTessellator::new();
+        let first =
+            tessellator.tessellate_svg_meshes_with_options(&spinner(30.0), 48.0, 48.0, &options);
+        let second =
+            tessellator.tessellate_svg_meshes_with_options(&spinner(75.0), 48.0, 48.0, &options);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[1].vertices, second[1].vertices);
+        assert_eq!(first[1].indices, second[1].indices);
+        assert_ne!(first[1].transform, second[1].transform);
+        // Static paths keep their transform baked in
+        assert_eq!(first[0].transform, None);
 
-                    arc.for_each_quadratic_bezier(&mut |q| {
-                        builder.quadratic_bezier_to(q.ctrl, q.to);
-                    });
-                }
+        // Untransformed, the arm is its rect at double scale
+        let bounds = &first[1].bounds;
+        assert_eq!(
+            (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y),
+            (22.0, 4.0, 26.0, 20.0)
+        );
+        // Rotating about (12, 12) is rotating about (24, 24) on the display
+        let [a, b, c, d, e, f] = first[1].transform.unwrap();
+        let (sin, cos) = 30f32.to_radians().sin_cos();
+        for (actual, expected) in [a, b, c, d].iter().zip([cos, sin, -sin, cos]) {
+            assert!((actual - expected).abs() < 1e-5);
+        }
+        assert!((a * 24.0 + c * 24.0 + e - 24.0).abs() < 1e-4);
+        assert!((b * 24.0 + d * 24.0 + f - 24.0).abs() < 1e-4);
 
-                current_x = nx;
-                current_y = ny;
-                last_cmd_type = Some('A');
-            }
-            SvgCommand::Close => {
-                builder.close();
-                current_x = start_x;
-                current_y = start_y;
-                last_cmd_type = Some('Z');
+        // Applying the matrix gives the mesh a static path bakes
+        let baked = tessellator.tessellate_svg_meshes(&spinner(30.0), 48.0, 48.0);
+        let placed: Vec<f32> = first[1]
+            .vertices
+            .chunks_exact(3)
+            .flat_map(|v| [a * v[0] + c * v[1] + e, b * v[0] + d * v[1] + f, v[2]])
+            .collect();
+        assert_eq!(baked[1].transform, None);
+        assert_eq!(placed.len(), baked[1].vertices.len());
+        for (placed, baked) in placed.iter().zip(&baked[1].vertices) {
+            assert!((placed - baked).abs() < 1e-3, "{} vs {}", placed, baked);
+        }
+
+        // A host transform replaces the attribute, selected by index
+        let hosted = |transform: [f32; 6]| TessellateOptions {
+            dynamic_paths: vec![DynamicPath {
+                path: PathRef::Index(1),
+                transform: Some(transform),
+            }],
+            ..Default::default()
+        };
+        let moved = tessellator.tessellate_svg_meshes_with_options(
+            &spinner(30.0),
+            48.0,
+            48.0,
+            &hosted([1.0, 0.0, 0.0, 1.0, 3.0, 0.0]),
+        );
+        assert_eq!(moved[1].vertices, first[1].vertices);
+        assert_eq!(moved[1].transform, Some([1.0, 0.0, 0.0, 1.0, 6.0, 0.0]));
+        let nan = tessellator
+            .tessellate_svg_document(
+                &spinner(30.0),
+                48.0,
+                48.0,
+                &hosted([f32::NAN, 0.0, 0.0, 1.0, 0.0, 0.0]),
+            )
+            .unwrap();
+        assert_eq!(nan.meshes.len(), 1);
+        assert_eq!(nan.errors[0].path_index, 1);
+
+        // Lists apply right to left; malformed ones are ignored
+        let listed = parse_svg_content(
+            r#"<svg><path d="M0 0" transform="translate(1, 2) scale(2)"/>
+                <path d="M0 0" transform="rotate(oops)"/></svg>"#,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            listed.paths[0].transform,
+            Some([2.0, 0.0, 0.0, 2.0, 1.0, 2.0])
+        );
+        assert_eq!(listed.paths[1].transform, None);
+    }
+
+    #[test]
+    #[cfg(feature = "patterns")]
+    fn test_pattern_fill_tiles_cover_the_path_bounds() {
+        let svg = r#"<svg viewBox="0 0 100 100">
+            <defs>
+                <pattern id="hatch" patternUnits="userSpaceOnUse" x="3" width="10" height="8">
+                    <rect width="4" height="8" fill="red"/>
+                    <circle cx="7" cy="4" r="2" fill="blue"/>
+                </pattern>
+                <pattern id="checks" width="0.25" height="0.5">
+                    <rect width="5" height="5"/>
+                </pattern>
+            </defs>
+            <rect x="25" y="17" width="50" height="40" fill="url(#hatch)" stroke="black" stroke-width="1"/>
+            <rect x="10" y="70" width="50" height="20" fill="url(#checks)"/>
+            <path d="M80 80 L90 80 L90 90 Z" fill="url(#missing)"/>
+        </svg>"#;
+        let parsed = parse_svg_content(svg, None, None, None);
+        assert_eq!(parsed.paths.len(), 3);
+        assert_eq!(parsed.patterns.len(), 2);
+        let hatch = &parsed.patterns[0];
+        assert_eq!(hatch.id, "hatch");
+        assert_eq!(
+            (hatch.x, hatch.y, hatch.width, hatch.height),
+            (3.0, 0.0, 10.0, 8.0)
+        );
+        assert_eq!(hatch.pattern_units, PatternUnits::UserSpaceOnUse);
+        assert_eq!(hatch.pattern_content_units, PatternUnits::UserSpaceOnUse);
+        assert_eq!(hatch.paths.len(), 2);
+        assert_eq!(
+            parsed.patterns[1].pattern_units,
+            PatternUnits::ObjectBoundingBox
+        );
+
+        let mut tessellator = SvgTessellator::new();
+        let options = TessellateOptions {
+            pattern_fills: true,
+            ..Default::default()
+        };
+        let result = tessellator
+            .tessellate_svg_document(svg, 200.0, 200.0, &options)
+            .unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        // The stroke and the fill of the unknown pattern stay meshes
+        assert_eq!(
+            result.meshes.len(),
+            1 + usize::from(cfg!(feature = "stroke"))
+        );
+        assert_eq!(result.pattern_fills.len(), 2);
+
+        for fill in &result.pattern_fills {
+            let bounds = &fill.clip.bounds;
+            let grid = &fill.grid;
+            let axes = [
+                (
+                    grid.origin_x,
+                    grid.step_x,
+                    grid.columns,
+                    bounds.min_x,
+                    bounds.max_x,
+                ),
+                (
+                    grid.origin_y,
+                    grid.step_y,
+                    grid.rows,
+                    bounds.min_y,
+                    bounds.max_y,
+                ),
+            ];
+            for (origin, step, count, min, max) in axes {
+                assert!(count > 0);
+                assert!(origin <= min && origin + step > min, "{:?}", grid);
+                let last = origin + (count - 1) as f32 * step;
+                assert!(last < max && last + step >= max, "{:?}", grid);
             }
         }
+
+        // User space tiles repeat from the pattern's own origin
+        let hatched = &result.pattern_fills[0];
+        assert_eq!((hatched.path, hatched.pattern.as_str()), (0, "hatch"));
+        assert_eq!(hatched.clip.paint_order, 0);
+        assert_eq!(
+            hatched.grid,
+            TileGrid {
+                origin_x: 46.0,
+                origin_y: 32.0,
+                step_x: 20.0,
+                step_y: 16.0,
+                columns: 6,
+                rows: 6,
+            }
+        );
+        let standalone = tessellator.tessellate_svg_meshes(
+            r#"<svg width="10" height="8">
+                <rect width="4" height="8" fill="red"/>
+                <circle cx="7" cy="4" r="2" fill="blue"/>
+            </svg>"#,
+            20.0,
+            16.0,
+        );
+        let tile: Vec<&TessellatedMesh> = hatched.tile.iter().map(|tile| &tile.mesh).collect();
+        assert_eq!(tile, standalone.iter().collect::<Vec<_>>());
+
+        // Bounding box tiles divide the path's bounds
+        let checked = &result.pattern_fills[1];
+        assert_eq!(
+            checked.grid,
+            TileGrid {
+                origin_x: 20.0,
+                origin_y: 140.0,
+                step_x: 25.0,
+                step_y: 20.0,
+                columns: 4,
+                rows: 2,
+            }
+        );
+
+        // Without the option, pattern fills are meshes like any other
+        let plain = tessellator
+            .tessellate_svg_document(svg, 200.0, 200.0, &Default::default())
+            .unwrap();
+        assert_eq!(
+            plain.meshes.len(),
+            3 + usize::from(cfg!(feature = "stroke"))
+        );
+        assert!(plain.pattern_fills.is_empty());
     }
 
-    builder.build()
-}
+    #[test]
+    fn test_gear_mesh_matches_golden_numbers() {
+        let svg = include_str!("../../../assets/gear.svg");
+        let mut tessellator = SvgTessellator::new();
+        let tessellation = tessellator
+            .tessellate_svg_document(svg, 64.0, 64.0, &TessellateOptions::default())
+            .unwrap();
+        assert!(tessellation.errors.is_empty());
 
-fn parse_svg_content(svg_content: &str) -> ParsedSvg {
-    let mut result = ParsedSvg {
-        width: 24.0,
-        height: 24.0,
-        view_box: None,
-        paths: Vec::new(),
-    };
+        let mut values = Vec::new();
+        for (index, MeshEntry { mesh, .. }) in tessellation.meshes.iter().enumerate() {
+            let bounds = &mesh.bounds;
+            values.extend(
+                [
+                    ("vertices", (mesh.vertices.len() / 3) as f32),
+                    ("triangles", (mesh.indices.len() / 3) as f32),
+                    ("area", mesh_area(mesh)),
+                    ("bounds.min_x", bounds.min_x),
+                    ("bounds.min_y", bounds.min_y),
+                    ("bounds.max_x", bounds.max_x),
+                    ("bounds.max_y", bounds.max_y),
+                ]
+                .map(|(field, value)| (format!("mesh{}.{}", index, field), value)),
+            );
+        }
+        tolerance::assert_matches_goldens!("gear_64_mesh", &values);
+    }
+
+    #[test]
+    #[cfg(feature = "patterns")]
+    fn test_streamed_parse_matches_single_chunk_at_any_split() {
+        let svg = concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n<!-- drawn by <hand> -->\n",
+            r#"<s:svg xmlns:s="http://www.w3.org/2000/svg" role="img" width="120" height="80" viewBox="0 0 120 80">"#,
+            "<s:title>Caf\u{e9} &amp; <![CDATA[bar > baz]]></s:title>",
+            r#"<s:pattern id="dots" width="10" height="10"><s:circle cx="5" cy="5" r="2"/></s:pattern>"#,
+            r#"<s:foreignObject><div><path d="M0 0 L9 9"/></div></s:foreignObject>"#,
+            r#"<s:path id="label" aria-label="a > b" d="M0,0 L10,0 L10,10 Z" fill="url(#dots)"/>"#,
+            r##"<s:rect x="1" y="2" width="3" height="4" stroke-width="0.5" stroke="#000"/>"##,
+            r#"<s:polygon points="0,0 5,0 5,5" transform="translate(1 2)"/>"#,
+            "</s:svg>",
+        );
+        let whole = parse_svg_content(svg, None, None, None);
+        assert_eq!(whole.paths.len(), 3);
+        assert_eq!(whole.patterns.len(), 1);
+        assert_eq!(whole.patterns[0].paths.len(), 1);
+        assert_eq!(whole.document_warnings.len(), 1);
+        assert_eq!(
+            whole.accessibility.title.as_deref(),
+            Some("Café & bar > baz")
+        );
+        assert_eq!(
+            whole.accessibility.paths[0].aria_label.as_deref(),
+            Some("a > b")
+        );
+        assert_eq!((whole.width, whole.height), (120.0, 80.0));
 
-    if let Some(cap) = regex_match(svg_content, r#"\bwidth\s*=\s*["']?(\d+(?:\.\d+)?)"#) {
-        result.width = cap.parse().unwrap_or(24.0);
+        let streamed = |chunks: &[&[u8]]| {
+            let mut parser = SvgStreamParser::new();
+            for chunk in chunks {
+                parser.feed(chunk);
+            }
+            parser.finish_native()
+        };
+        // Every split point, which includes ones inside attribute values,
+        // between `<` and a tag name, inside `<!--`, `]]>` and `é`
+        let bytes = svg.as_bytes();
+        for split in 0..=bytes.len() {
+            let (head, tail) = bytes.split_at(split);
+            assert_eq!(streamed(&[head, tail]), whole, "split at byte {}", split);
+        }
+        let bytewise: Vec<&[u8]> = bytes.chunks(1).collect();
+        assert_eq!(streamed(&bytewise), whole);
     }
 
-    if let Some(cap) = regex_match(svg_content, r#"\bheight\s*=\s*["']?(\d+(?:\.\d+)?)"#) {
-        result.height = cap.parse().unwrap_or(24.0);
+    #[test]
+    fn test_degenerate_display_sizes_are_empty_or_rejected() {
+        let svg = r#"<svg width="10" height="10"><rect width="10" height="10"/></svg>"#;
+        let options = TessellateOptions::default();
+        let mut tessellator = SvgTessellator::new();
+
+        let drawn = tessellator
+            .tessellate_svg_document(svg, 20.0, 20.0, &options)
+            .unwrap();
+        assert_eq!(drawn.meshes.len(), 1);
+        assert_eq!(drawn.degenerate, None);
+
+        // Zero, near-zero and subnormal sizes are empty on purpose
+        for (width, height) in [(0.0, 20.0), (20.0, 1e-4), (f32::MIN_POSITIVE / 2.0, 20.0)] {
+            let result = tessellator
+                .tessellate_svg_document(svg, width, height, &options)
+                .unwrap();
+            assert!(result.meshes.is_empty(), "{} x {}", width, height);
+            assert!(result.errors.is_empty());
+            assert_eq!(result.degenerate, Some(DegenerateInput::DisplayScale));
+        }
+        let zero_view_box = r#"<svg viewBox="0 0 0 10"><rect width="10" height="10"/></svg>"#;
+        let result = tessellator
+            .tessellate_svg_document(zero_view_box, 20.0, 20.0, &options)
+            .unwrap();
+        assert_eq!(result.degenerate, Some(DegenerateInput::NativeSize));
+
+        for (width, height) in [(-1.0, 20.0), (20.0, -0.0001), (f32::NAN, 20.0)] {
+            let error = tessellator
+                .tessellate_svg_document(svg, width, height, &options)
+                .unwrap_err();
+            assert!(error.to_string().starts_with("Invalid display size"));
+        }
+        assert!(tessellator
+            .tessellate_svg_meshes_with_options(svg, -1.0, 20.0, &options)
+            .is_empty());
+
+        let square = "M0 0 L10 0 L10 10 Z";
+        let mesh = tessellator
+            .tessellate_path_native(square, 0.0, 0.0, 0.0, 1.0, FillRule::NonZero)
+            .unwrap();
+        assert!(mesh.vertices.is_empty());
+        assert_eq!(mesh.degenerate, Some(DegenerateInput::DisplayScale));
+        let mirrored = tessellator
+            .tessellate_path_native(square, 0.0, 0.0, -1.0, 1.0, FillRule::NonZero)
+            .unwrap();
+        assert!(!mirrored.vertices.is_empty());
+        assert_eq!(mirrored.degenerate, None);
+        assert!(tessellator
+            .tessellate_path_native(square, f32::NAN, 0.0, 1.0, 1.0, FillRule::NonZero)
+            .is_err());
+        let stroke = tessellator
+            .tessellate_stroke_with_options_native(
+                square,
+                1.0,
+                [0.0, 0.0, 1.0, f32::MIN_POSITIVE / 2.0],
+                &StrokeWidthOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(stroke.degenerate, Some(DegenerateInput::DisplayScale));
     }
 
-    if let Some(cap) = regex_match(svg_content, r#"\bviewBox\s*=\s*["']([^"']+)["']"#) {
-        let parts: Vec<f32> = cap
-            .split(|c: char| c.is_whitespace() || c == ',')
-            .filter(|s| !s.is_empty())
-            .filter_map(|s| s.parse().ok())
+    #[test]
+    fn test_hidden_content_and_switch_alternatives() {
+        let svg = r#"<svg viewBox="0 0 10 10">
+            <g display="none"><rect id="gone" width="5" height="5"/></g>
+            <rect id="styled" width="5" height="5" style="fill:red; display: none"/>
+            <g visibility="hidden">
+                <rect id="hidden" width="5" height="5"/>
+                <rect id="shown" width="5" height="5" visibility="visible"/>
+            </g>
+        </svg>"#;
+        let mut tessellator = SvgTessellator::new();
+        let parsed = tessellator
+            .parse_svg_native(svg, &ParseOptions::default())
+            .unwrap();
+        let kept: Vec<_> = parsed
+            .paths
+            .iter()
+            .map(|path| (path.id.as_deref().unwrap(), path.hidden))
             .collect();
-        if parts.len() == 4 {
-            result.view_box = Some(ViewBox {
-                x: parts[0],
-                y: parts[1],
-                width: parts[2],
-                height: parts[3],
-            });
+        assert_eq!(kept, [("hidden", true), ("shown", false)]);
+        let meshes = tessellator.tessellate_svg_meshes(svg, 10.0, 10.0);
+        assert_eq!(meshes.len(), 1);
+
+        let all_hidden = r#"<svg viewBox="0 0 10 10" visibility="hidden">
+            <rect width="5" height="5"/><rect display="none" width="5" height="5"/></svg>"#;
+        assert!(tessellator
+            .tessellate_svg_meshes(all_hidden, 10.0, 10.0)
+            .is_empty());
+
+        let switch = r#"<svg viewBox="0 0 10 10"><switch>
+            <foreignObject requiredExtensions="http://www.w3.org/1999/xhtml">
+                <path id="html" d="M0 0 L1 1"/></foreignObject>
+            <g systemLanguage="en, fr"><rect id="en" width="5" height="5"/></g>
+            <g systemLanguage="de"><rect id="de" width="5" height="5"/></g>
+            <rect id="fallback" width="5" height="5"/>
+        </switch><rect id="after" width="5" height="5"/></svg>"#;
+        let ids = |language: Option<&str>| -> Vec<String> {
+            let options = ParseOptions {
+                language: language.map(str::to_string),
+                ..Default::default()
+            };
+            let parsed = tessellator.parse_svg_native(switch, &options).unwrap();
+            parsed
+                .paths
+                .into_iter()
+                .filter_map(|path| path.id)
+                .collect()
+        };
+        assert_eq!(ids(Some("de")), ["de", "after"]);
+        assert_eq!(ids(Some("DE-ch")), ["fallback", "after"]);
+        assert_eq!(ids(Some("fr")), ["en", "after"]);
+        assert_eq!(ids(None), ["fallback", "after"]);
+
+        let mut parser = SvgStreamParser::new();
+        parser.set_language(Some("de".to_string()));
+        parser.feed(switch.as_bytes());
+        let streamed = parser.finish_native();
+        assert_eq!(streamed.paths[0].id.as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn test_disabled_features_leave_fills_alone() {
+        let mut tessellator = SvgTessellator::new();
+        let stroke = tessellator
+            .tessellate_stroke_native("M0 0 L10 0", 2.0, 0.0, 0.0, 1.0, 1.0)
+            .unwrap();
+        assert_eq!(stroke.indices.is_empty(), !cfg!(feature = "stroke"));
+
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20">
+            <pattern id="dots" width="4" height="4"><circle cx="2" cy="2" r="1"/></pattern>
+            <rect width="10" height="10" fill="#f00" stroke="#000" stroke-width="2"/>
+            <rect x="10" width="10" height="10" fill="url(#dots)"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        assert_eq!(
+            parsed.patterns.len(),
+            usize::from(cfg!(feature = "patterns"))
+        );
+        assert_eq!(parsed.paths.len(), 2);
+
+        let assets =
+            tessellator.tessellate_svg_assets(svg, 20.0, 20.0, &TessellateOptions::default());
+        let strokes = assets.iter().filter(|asset| asset.stroke).count();
+        let fills = assets.len() - strokes;
+        assert_eq!(fills, 2);
+        assert_eq!(strokes, usize::from(cfg!(feature = "stroke")));
+    }
+
+    #[test]
+    fn test_ellipse_elements_are_read_as_paths() {
+        let svg = r##"<svg width="100" height="100" viewBox="0 0 100 100">
+            <ellipse cx="50" cy="40" rx="30" ry="10" fill="#0f0" stroke="#000" stroke-width="2"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        assert_eq!(parsed.paths.len(), 1);
+        let path = &parsed.paths[0];
+        assert_eq!(path.fill.as_deref(), Some("#0f0"));
+        assert_eq!(path.stroke.as_deref(), Some("#000"));
+        assert_eq!(path.stroke_width, Some(2.0));
+
+        let svg = r#"<svg width="100" height="100" viewBox="0 0 100 100">
+            <ellipse cx="50" cy="40" rx="30" ry="10"/>
+        </svg>"#;
+        let mut tessellator = SvgTessellator::new();
+        let result = tessellator
+            .tessellate_svg_document(svg, 100.0, 100.0, &TessellateOptions::default())
+            .unwrap();
+        assert_eq!(result.meshes.len(), 1);
+        let bounds = &result.meshes[0].mesh.bounds;
+        let expected = [20.0, 30.0, 80.0, 50.0];
+        let actual = [bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y];
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
         }
+
+        // Missing centers default to the origin; a zero radius draws nothing
+        let origin = parse_svg_content(r#"<svg><ellipse rx="3" ry="2"/></svg>"#, None, None, None);
+        assert!(origin.paths[0].d.starts_with("M3,0 "));
+        let flat = parse_svg_content(r#"<svg><ellipse rx="3" ry="0"/></svg>"#, None, None, None);
+        assert!(flat.paths.is_empty());
+
+        // Circles default their centers the same way
+        let circle = parse_svg_content(r#"<svg><circle r="5"/></svg>"#, None, None, None);
+        let ellipse = parse_svg_content(r#"<svg><ellipse rx="5" ry="5"/></svg>"#, None, None, None);
+        assert_eq!(circle.paths.len(), 1);
+        assert_eq!(circle.paths[0].d, ellipse.paths[0].d);
     }
 
-    for path_match in find_all_paths(svg_content) {
-        if let Some(d) = extract_attr(&path_match, "d") {
-            let fill = extract_attr(&path_match, "fill");
-            let stroke = extract_attr(&path_match, "stroke");
-            let stroke_width =
-                extract_attr(&path_match, "stroke-width").and_then(|s| s.parse().ok());
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_line_elements_draw_only_their_stroke() {
+        let svg = r##"<svg width="20" height="20">
+            <line x1="2" y1="10" x2="18" y2="10" stroke="#000" stroke-width="2" fill="#f00"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        assert_eq!(parsed.paths.len(), 1);
+        assert_eq!(parsed.paths[0].d, "M2,10 L18,10");
+        assert_eq!(parsed.paths[0].fill.as_deref(), Some("none"));
 
-            result.paths.push(ParsedPath {
-                d,
-                fill,
-                stroke,
-                stroke_width,
-            });
+        let mut tessellator = SvgTessellator::new();
+        let options = TessellateOptions::default();
+        let assets = tessellator.tessellate_svg_assets(svg, 20.0, 20.0, &options);
+        assert_eq!(assets.len(), 1);
+        assert!(assets[0].stroke);
+        let bounds = &assets[0].mesh.bounds;
+        assert!((bounds.max_y - bounds.min_y - 2.0).abs() < 1e-3);
+
+        // Coincident endpoints and missing coordinates don't panic
+        let dot = r##"<svg width="20" height="20">
+            <line x1="5" y1="5" x2="5" y2="5" stroke="#000"/><line stroke="#000"/>
+        </svg>"##;
+        tessellator.tessellate_svg_assets(dot, 20.0, 20.0, &options);
+    }
+
+    /// The path read from a stroked `<polyline>` with `points`.
+    fn polyline(points: &str) -> ParsedPath {
+        let svg =
+            format!(r##"<svg><polyline points="{points}" stroke="#000" stroke-width="2"/></svg>"##);
+        parse_svg_content(&svg, None, None, None)
+            .paths
+            .pop()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_polyline_elements_stay_open() {
+        let path = polyline("0,0,10,0,10,10");
+        assert_eq!(path.d, "M0,0 L10,0 L10,10");
+        assert_eq!(path.fill, None);
+        assert_eq!(path.stroke.as_deref(), Some("#000"));
+        assert_eq!(path.stroke_width, Some(2.0));
+    }
+
+    #[test]
+    fn test_points_may_be_separated_by_any_whitespace() {
+        assert_eq!(polyline("0 0  10 0\n10 10").d, "M0,0 L10,0 L10,10");
+    }
+
+    #[test]
+    fn test_points_drop_an_odd_trailing_coordinate() {
+        assert_eq!(polyline("0,0 10,0 10,10 20").d, "M0,0 L10,0 L10,10");
+    }
+
+    #[test]
+    fn test_points_split_where_a_sign_starts_a_number() {
+        assert_eq!(polyline("0,0 10-5").d, "M0,0 L10,-5");
+    }
+
+    #[test]
+    fn test_points_stop_at_the_first_bad_token() {
+        assert_eq!(polyline("0,0 10,0 x 10,10").d, "M0,0 L10,0");
+    }
+
+    #[test]
+    fn test_polygon_elements_close() {
+        let polygon = parse_svg_content(
+            r#"<svg><polygon points="0,0 10,0 10,10"/></svg>"#,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(polygon.paths[0].d, "M0,0 L10,0 L10,10 Z");
+    }
+
+    /// The `d` read from a 20x10 rect at (2, 4) with the `radii` attributes.
+    fn rect_d(radii: &str) -> String {
+        let svg = format!(r#"<svg><rect x="2" y="4" width="20" height="10" {radii}/></svg>"#);
+        parse_svg_content(&svg, None, None, None)
+            .paths
+            .pop()
+            .unwrap()
+            .d
+    }
+
+    /// Assert `radii` round the rect with arcs that parse and build back to
+    /// the rect's bounds.
+    fn assert_rounded_rect_round_trips(radii: &str) {
+        let d = rect_d(radii);
+        assert!(d.contains(" A"), "{d}");
+        let (mut text, mut spans, mut commands) = (Vec::new(), Vec::new(), Vec::new());
+        parse_svg_path_d(&d, &mut text, &mut spans, &mut commands, true).unwrap();
+        let path = build_lyon_path(&commands, 0.0, 0.0, 1.0, 1.0);
+        let bounds = lyon::algorithms::aabb::bounding_box(&path);
+        let actual = [bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y];
+        for (actual, expected) in actual.iter().zip([2.0, 4.0, 22.0, 14.0]) {
+            assert!(
+                (actual - expected).abs() < 1e-3,
+                "{d}: {actual} != {expected}"
+            );
         }
     }
 
-    for circle_match in find_all_circles(svg_content) {
-        if let (Some(cx_str), Some(cy_str), Some(r_str)) = (
-            extract_attr(&circle_match, "cx"),
-            extract_attr(&circle_match, "cy"),
-            extract_attr(&circle_match, "r"),
-        ) {
-            if let (Ok(cx), Ok(cy), Ok(r)) = (
-                cx_str.parse::<f32>(),
-                cy_str.parse::<f32>(),
-                r_str.parse::<f32>(),
-            ) {
-                let k = 0.5522847498;
-                let d = format!(
-                    "M{},{} C{},{} {},{} {},{} C{},{} {},{} {},{} C{},{} {},{} {},{} C{},{} {},{} {},{} Z",
-                    cx + r, cy,
-                    cx + r, cy + k * r, cx + k * r, cy + r, cx, cy + r,
-                    cx - k * r, cy + r, cx - r, cy + k * r, cx - r, cy,
-                    cx - r, cy - k * r, cx - k * r, cy - r, cx, cy - r,
-                    cx + k * r, cy - r, cx + r, cy - k * r, cx + r, cy
-                );
-                result.paths.push(ParsedPath {
-                    d,
-                    fill: extract_attr(&circle_match, "fill"),
-                    stroke: None,
-                    stroke_width: None,
-                });
-            }
+    const SHARP_RECT: &str = "M2,4 L22,4 L22,14 L2,14 Z";
+
+    #[test]
+    fn test_rect_rx_alone_sets_ry() {
+        assert_eq!(rect_d(r#"rx="3""#), rect_d(r#"rx="3" ry="3""#));
+    }
+
+    #[test]
+    fn test_rect_ry_alone_sets_rx() {
+        assert_eq!(rect_d(r#"ry="3""#), rect_d(r#"rx="3" ry="3""#));
+    }
+
+    #[test]
+    fn test_rect_rx_is_clamped_to_half_the_width() {
+        assert_eq!(rect_d(r#"rx="50" ry="2""#), rect_d(r#"rx="10" ry="2""#));
+    }
+
+    #[test]
+    fn test_rect_ry_is_clamped_to_half_the_height() {
+        assert_eq!(rect_d(r#"rx="4" ry="50""#), rect_d(r#"rx="4" ry="5""#));
+    }
+
+    #[test]
+    fn test_rect_without_radii_is_sharp() {
+        assert_eq!(rect_d(""), SHARP_RECT);
+    }
+
+    #[test]
+    fn test_rect_with_zero_rx_is_sharp() {
+        assert_eq!(rect_d(r#"rx="0""#), SHARP_RECT);
+    }
+
+    #[test]
+    fn test_rect_with_zero_rx_and_nonzero_ry_is_sharp() {
+        assert_eq!(rect_d(r#"rx="0" ry="3""#), SHARP_RECT);
+    }
+
+    #[test]
+    fn test_rect_rounded_by_rx_round_trips() {
+        assert_rounded_rect_round_trips(r#"rx="3""#);
+    }
+
+    #[test]
+    fn test_rect_rounded_by_ry_round_trips() {
+        assert_rounded_rect_round_trips(r#"ry="3""#);
+    }
+
+    #[test]
+    fn test_rect_with_clamped_radii_round_trips() {
+        assert_rounded_rect_round_trips(r#"rx="50""#);
+    }
+
+    /// Display bounds of a 10x10 rect at (5, 5) with `transform`, drawn at
+    /// twice its 50x50 viewBox.
+    fn transformed_rect_bounds(transform: &str) -> [f32; 4] {
+        let svg = format!(
+            r#"<svg width="50" height="50" viewBox="0 0 50 50">
+                <rect x="5" y="5" width="10" height="10" {transform}/>
+            </svg>"#
+        );
+        let mut tessellator = SvgTessellator::new();
+        let result = tessellator
+            .tessellate_svg_document(&svg, 100.0, 100.0, &TessellateOptions::default())
+            .unwrap();
+        let bounds = &result.meshes[0].mesh.bounds;
+        [bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y]
+    }
+
+    fn assert_bounds_near(actual: [f32; 4], expected: [f32; 4]) {
+        for (actual_edge, expected_edge) in actual.iter().zip(expected) {
+            assert!(
+                (actual_edge - expected_edge).abs() < 1e-3,
+                "{actual:?} vs {expected:?}"
+            );
         }
     }
 
-    for rect_match in find_all_rects(svg_content) {
-        let x: f32 = extract_attr(&rect_match, "x")
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(0.0);
-        let y: f32 = extract_attr(&rect_match, "y")
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(0.0);
+    #[test]
+    fn test_translate_moves_mesh_bounds_in_user_units() {
+        assert_eq!(transformed_rect_bounds(""), [10.0, 10.0, 30.0, 30.0]);
+        // The viewBox doubles user units, so 10 of them are 20 on screen
+        assert_bounds_near(
+            transformed_rect_bounds(r#"transform="translate(10,10)""#),
+            [30.0, 30.0, 50.0, 50.0],
+        );
+    }
 
-        if let (Some(w_str), Some(h_str)) = (
-            extract_attr(&rect_match, "width"),
-            extract_attr(&rect_match, "height"),
-        ) {
-            if let (Ok(w), Ok(h)) = (w_str.parse::<f32>(), h_str.parse::<f32>()) {
-                let d = format!(
-                    "M{},{} L{},{} L{},{} L{},{} Z",
-                    x,
-                    y,
-                    x + w,
-                    y,
-                    x + w,
-                    y + h,
-                    x,
-                    y + h
-                );
-                result.paths.push(ParsedPath {
-                    d,
-                    fill: extract_attr(&rect_match, "fill"),
-                    stroke: None,
-                    stroke_width: None,
-                });
-            }
+    #[test]
+    fn test_rotate_about_the_center_keeps_a_square_in_place() {
+        assert_bounds_near(
+            transformed_rect_bounds(r#"transform="rotate(90 10 10)""#),
+            [10.0, 10.0, 30.0, 30.0],
+        );
+    }
+
+    #[test]
+    fn test_translate_then_scale_scales_before_moving() {
+        // Items apply right to left: (5, 5)-(15, 15) scales to (10, 10)-(30, 30)
+        // and then moves 10 right
+        assert_bounds_near(
+            transformed_rect_bounds(r#"transform="translate(10 0) scale(2)""#),
+            [40.0, 20.0, 80.0, 60.0],
+        );
+    }
+
+    #[test]
+    fn test_scale_then_translate_scales_the_translation() {
+        // The rect moves to (15, 5)-(25, 15) and then doubles, offset and all
+        assert_bounds_near(
+            transformed_rect_bounds(r#"transform="scale(2) translate(10 0)""#),
+            [60.0, 20.0, 100.0, 60.0],
+        );
+    }
+
+    #[test]
+    fn test_skew_x_shifts_x_by_y() {
+        // At 45 degrees x gains y, so x spans 5 + 5 to 15 + 15
+        assert_bounds_near(
+            transformed_rect_bounds(r#"transform="skewX(45)""#),
+            [20.0, 10.0, 60.0, 30.0],
+        );
+    }
+
+    #[test]
+    fn test_skew_y_shifts_y_by_x() {
+        assert_bounds_near(
+            transformed_rect_bounds(r#"transform="skewY(45)""#),
+            [10.0, 20.0, 30.0, 60.0],
+        );
+    }
+
+    #[test]
+    fn test_matrix_takes_its_arguments_column_by_column() {
+        // x' = 2x + 3 and y' = x + y + 4; swapping b and c would give
+        // x' = 2x + y + 3 instead
+        assert_bounds_near(
+            transformed_rect_bounds(r#"transform="matrix(2 1 0 1 3 4)""#),
+            [26.0, 28.0, 66.0, 68.0],
+        );
+    }
+
+    #[test]
+    fn test_groups_pass_presentation_attributes_to_their_shapes() {
+        let svg = r##"<svg>
+            <g fill="red" stroke-width="2" opacity="0.5" transform="translate(10 0)">
+                <path id="inherits" d="M0 0 L1 0 L1 1 Z"/>
+                <g stroke="#000" fill="none" opacity="0.5" transform="scale(2)">
+                    <rect id="nested" width="1" height="1" fill="blue" transform="translate(0 3)"/>
+                </g>
+                <circle id="sibling" cx="0" cy="0" r="1" stroke-width="4"></circle>
+            </g>
+            <path id="outside" d="M0 0 L1 0 L1 1 Z"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        let [inherits, nested, sibling, outside] = &parsed.paths[..] else {
+            panic!("expected four paths, got {:?}", parsed.paths);
+        };
+        assert_eq!(inherits.fill.as_deref(), Some("red"));
+        assert_eq!(inherits.stroke_width, Some(2.0));
+        assert_eq!(inherits.opacity, Some(0.5));
+        assert_eq!(inherits.transform, Some([1.0, 0.0, 0.0, 1.0, 10.0, 0.0]));
+
+        // Own attributes win; transforms apply innermost first
+        assert_eq!(nested.fill.as_deref(), Some("blue"));
+        assert_eq!(nested.stroke.as_deref(), Some("#000"));
+        assert_eq!(nested.stroke_width, Some(2.0));
+        assert_eq!(nested.opacity, Some(0.25));
+        assert_eq!(nested.transform, Some([2.0, 0.0, 0.0, 2.0, 10.0, 6.0]));
+
+        assert_eq!(sibling.fill.as_deref(), Some("red"));
+        assert_eq!(sibling.stroke, None);
+        assert_eq!(sibling.stroke_width, Some(4.0));
+
+        assert_eq!(outside.fill, None);
+        assert_eq!(outside.opacity, None);
+        assert_eq!(outside.transform, None);
+
+        // Opacity scales the resolved color's alpha
+        let mut tessellator = SvgTessellator::new();
+        let assets = tessellator.tessellate_svg_assets(
+            r#"<svg width="4" height="4"><g opacity="0.5"><rect width="4" height="4" fill="red"/></g></svg>"#,
+            4.0,
+            4.0,
+            &TessellateOptions::default(),
+        );
+        assert_eq!(assets[0].color, Some([255, 0, 0, 128]));
+    }
+
+    #[test]
+    fn test_style_declarations_set_paint() {
+        let svg = r##"<svg>
+            <path d="M0 0 L1 1" style="fill: #ff0000 ;" stroke-width="1.5"/>
+            <path d="M0 0 L1 1" fill="red" stroke="blue" style="FILL:none;Stroke:none !important;;"/>
+            <g style="stroke-width:3"><path d="M0 0 L1 1" style="stroke:#000;opacity:0.5"/></g>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        let paint = |path: &ParsedPath| {
+            (
+                path.fill.clone(),
+                path.stroke.clone(),
+                path.stroke_width,
+                path.opacity,
+            )
+        };
+        assert_eq!(
+            paint(&parsed.paths[0]),
+            (Some("#ff0000".to_string()), None, Some(1.5), None)
+        );
+        // Declarations win over attributes
+        assert_eq!(
+            paint(&parsed.paths[1]),
+            (
+                Some("none".to_string()),
+                Some("none".to_string()),
+                None,
+                None
+            )
+        );
+        assert_eq!(
+            paint(&parsed.paths[2]),
+            (None, Some("#000".to_string()), Some(3.0), Some(0.5))
+        );
+    }
+
+    #[test]
+    fn test_style_sheet_class_rules() {
+        let svg = r##"<svg>
+            <defs><style><![CDATA[
+                /* exported */
+                .a{fill:#e91e63}.b { stroke: #000; stroke-width: 2; font-size: 3px }
+                path.c, .a .b { fill: #0f0 }
+                .d, .e { opacity: .5 }
+            ]]></style></defs>
+            <path d="M0 0 L1 1" class="a"/>
+            <path d="M0 0 L1 1" class="b d"/>
+            <path d="M0 0 L1 1" class="a" style="fill:#00f"/>
+            <path d="M0 0 L1 1" class="a c" fill="#00f"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        let paint = |index: usize| {
+            let path = &parsed.paths[index];
+            (
+                path.fill.as_deref(),
+                path.stroke.as_deref(),
+                path.stroke_width,
+                path.opacity,
+            )
+        };
+        assert_eq!(paint(0), (Some("#e91e63"), None, None, None));
+        assert_eq!(paint(1), (None, Some("#000"), Some(2.0), Some(0.5)));
+        // A style declaration beats a class rule, which beats an attribute
+        assert_eq!(paint(2).0, Some("#00f"));
+        assert_eq!(paint(3).0, Some("#e91e63"));
+    }
+
+    #[test]
+    fn test_opacities_set_mesh_alpha() {
+        let svg = r##"<svg width="10" height="10">
+            <g fill-opacity="0.8" stroke-opacity="0.5">
+                <rect width="10" height="10" fill="#f00" stroke="#000" stroke-width="1"
+                    opacity="0.5" fill-opacity="0.5"/>
+            </g>
+            <rect width="5" height="5" opacity="2" fill-opacity="-1"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        let [rect, clamped] = &parsed.paths[..] else {
+            panic!("expected two paths");
+        };
+        assert_eq!(rect.paint_alpha(false), Some(0.25));
+        assert_eq!(rect.paint_alpha(true), Some(0.25));
+        assert_eq!(rect.stroke_opacity, Some(0.5));
+        assert_eq!(
+            (clamped.opacity, clamped.fill_opacity),
+            (Some(1.0), Some(0.0))
+        );
+
+        let mut tessellator = SvgTessellator::new();
+        let result = tessellator
+            .tessellate_svg_document(svg, 10.0, 10.0, &TessellateOptions::default())
+            .unwrap();
+        let alphas: Vec<_> = result.meshes.iter().map(|entry| entry.mesh.alpha).collect();
+        if cfg!(feature = "stroke") {
+            assert_eq!(alphas, [Some(0.25), Some(0.25), Some(0.0)]);
+        } else {
+            assert_eq!(alphas, [Some(0.25), Some(0.0)]);
         }
+        let assets =
+            tessellator.tessellate_svg_assets(svg, 10.0, 10.0, &TessellateOptions::default());
+        assert_eq!(assets[0].color, Some([255, 0, 0, 64]));
     }
 
-    for polygon_match in find_all_polygons(svg_content) {
-        if let Some(points_str) = extract_attr(&polygon_match, "points") {
-            let points: Vec<f32> = points_str
-                .split(|c: char| c.is_whitespace() || c == ',')
-                .filter(|s| !s.is_empty())
-                .filter_map(|s| s.parse().ok())
-                .collect();
+    #[test]
+    fn test_evenodd_fill_rule_cuts_holes() {
+        /// Whether a triangle of `mesh` covers the point.
+        fn covers(mesh: &TessellatedMesh, x: f32, y: f32) -> bool {
+            let vertex = |index: u32| {
+                let at = index as usize * 3;
+                (mesh.vertices[at], mesh.vertices[at + 1])
+            };
+            mesh.indices.chunks_exact(3).any(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|corner| vertex(triangle[corner]));
+                let side = |(x0, y0): (f32, f32), (x1, y1): (f32, f32)| {
+                    (x1 - x0) * (y - y0) - (y1 - y0) * (x - x0)
+                };
+                let sides = [side(a, b), side(b, c), side(c, a)];
+                sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0)
+            })
+        }
 
-            if points.len() >= 4 {
-                let mut d = format!("M{},{}", points[0], points[1]);
-                for i in (2..points.len()).step_by(2) {
-                    if i + 1 < points.len() {
-                        d.push_str(&format!(" L{},{}", points[i], points[i + 1]));
-                    }
-                }
-                d.push_str(" Z");
+        // Both squares wound the same way
+        let ring = "M0 0 L30 0 L30 30 L0 30 Z M10 10 L20 10 L20 20 L10 20 Z";
+        let mut tessellator = SvgTessellator::new();
+        for (rule, hole) in [(FillRule::NonZero, false), (FillRule::EvenOdd, true)] {
+            let mesh = tessellator
+                .tessellate_path_native(ring, 0.0, 0.0, 1.0, 1.0, rule)
+                .unwrap();
+            assert!(covers(&mesh, 5.0, 5.0));
+            assert_eq!(covers(&mesh, 15.0, 15.0), !hole, "{rule:?}");
 
-                result.paths.push(ParsedPath {
-                    d,
-                    fill: extract_attr(&polygon_match, "fill"),
-                    stroke: None,
-                    stroke_width: None,
-                });
+            let options = PathPaintOptions {
+                fill_rule: rule,
+                ..Default::default()
+            };
+            let transformed = tessellator
+                .tessellate_path_with_transform_native(ring, path_transform::IDENTITY, &options)
+                .unwrap();
+            assert_eq!(covers(&transformed, 15.0, 15.0), !hole, "{rule:?}");
+        }
+
+        let svg = format!(
+            r#"<svg width="30" height="30"><g fill-rule="evenodd"><path d="{ring}"/></g>
+                <path d="{ring}" style="fill-rule: nonzero"/></svg>"#
+        );
+        let result = tessellator
+            .tessellate_svg_document(&svg, 30.0, 30.0, &TessellateOptions::default())
+            .unwrap();
+        assert_eq!(
+            parse_svg_content(&svg, None, None, None).paths[0].winding(),
+            FillRule::EvenOdd
+        );
+        assert!(!covers(&result.meshes[0].mesh, 15.0, 15.0));
+        assert!(covers(&result.meshes[1].mesh, 15.0, 15.0));
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_line_caps_extend_open_strokes() {
+        let mut tessellator = SvgTessellator::new();
+        let mut stroke = |line_cap| {
+            let options = StrokeWidthOptions {
+                line_cap,
+                ..Default::default()
+            };
+            tessellator
+                .tessellate_stroke_with_options_native(
+                    "M10 10 L30 10",
+                    4.0,
+                    [0.0, 0.0, 1.0, 1.0],
+                    &options,
+                )
+                .unwrap()
+        };
+        let butt = stroke(LineCap::Butt);
+        let round = stroke(LineCap::Round);
+        let square = stroke(LineCap::Square);
+        assert!(round.vertices.len() > butt.vertices.len());
+        assert!((butt.bounds.min_x - 10.0).abs() < 1e-3);
+        assert!((butt.bounds.max_x - 30.0).abs() < 1e-3);
+        for mesh in [&round, &square] {
+            assert!((mesh.bounds.min_x - 8.0).abs() < 1e-2, "{:?}", mesh.bounds);
+            assert!((mesh.bounds.max_x - 32.0).abs() < 1e-2, "{:?}", mesh.bounds);
+        }
+
+        let options = PathPaintOptions {
+            stroke_width: Some(4.0),
+            line_cap: LineCap::Round,
+            ..Default::default()
+        };
+        let transformed = tessellator
+            .tessellate_path_with_transform_native(
+                "M10 10 L30 10",
+                path_transform::IDENTITY,
+                &options,
+            )
+            .unwrap();
+        assert_eq!(transformed.vertices.len(), round.vertices.len());
+
+        let svg = r##"<svg width="40" height="20">
+            <g stroke-linecap="round"><path d="M10 10 L30 10" stroke="#000" stroke-width="4" fill="none"/></g>
+        </svg>"##;
+        let result = tessellator
+            .tessellate_svg_document(svg, 40.0, 20.0, &TessellateOptions::default())
+            .unwrap();
+        assert_eq!(
+            parse_svg_content(svg, None, None, None).paths[0].line_cap(),
+            LineCap::Round
+        );
+        assert!((result.meshes[0].mesh.bounds.min_x - 8.0).abs() < 1e-2);
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_miter_limit_truncates_sharp_corners() {
+        // A 10 degree corner at (100, 0), whose miter reaches 1 / sin(5deg),
+        // about 11.5 stroke widths, past it
+        let corner = "M0 0 L100 0 L1.519 17.365";
+        let mut tessellator = SvgTessellator::new();
+        let mut stroke = |line_join, miter_limit| {
+            let options = StrokeWidthOptions {
+                line_join,
+                miter_limit,
+                ..Default::default()
+            };
+            tessellator
+                .tessellate_stroke_with_options_native(corner, 4.0, [0.0, 0.0, 1.0, 1.0], &options)
+                .unwrap()
+        };
+        let bevel = stroke(LineJoin::Bevel, None);
+        let limited = stroke(LineJoin::Miter, Some(4.0));
+        let unlimited = stroke(LineJoin::Miter, Some(20.0));
+        assert!(bevel.bounds.max_x < 103.0, "{:?}", bevel.bounds);
+        assert!(
+            (limited.bounds.max_x - bevel.bounds.max_x).abs() < 1e-2,
+            "{:?} {:?}",
+            limited.bounds,
+            bevel.bounds
+        );
+        assert!(unlimited.bounds.max_x > 120.0, "{:?}", unlimited.bounds);
+
+        let svg = r##"<svg width="140" height="40">
+            <g stroke-linejoin="miter" stroke-miterlimit="20">
+                <path d="M0 0 L100 0 L1.519 17.365" stroke="#000" stroke-width="4" fill="none"/>
+                <path d="M0 0 L100 0 L1.519 17.365" stroke="#000" stroke-width="4" fill="none" style="stroke-linejoin: bevel"/>
+            </g>
+        </svg>"##;
+        let paths = parse_svg_content(svg, None, None, None).paths;
+        assert_eq!(paths[0].stroke_miterlimit, Some(20.0));
+        assert_eq!(paths[1].line_join(), LineJoin::Bevel);
+        let result = tessellator
+            .tessellate_svg_document(svg, 140.0, 40.0, &TessellateOptions::default())
+            .unwrap();
+        assert!(result.meshes[0].mesh.bounds.max_x > 120.0);
+        assert!(result.meshes[1].mesh.bounds.max_x < 103.0);
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_dash_arrays_split_strokes() {
+        let mut tessellator = SvgTessellator::new();
+        // Dashes 10 long every 15 cover 35 of the 50: 0-10 15-25 30-40 45-50
+        let options = DashedStrokeOptions::default();
+        let dashed = tessellator
+            .tessellate_dashed_stroke_native("M0 0 L50 0", 2.0, &[10.0, 5.0], 0.0, &options)
+            .unwrap();
+        assert!(
+            (mesh_area(&dashed) / 2.0 - 35.0).abs() < 1e-2,
+            "{}",
+            mesh_area(&dashed)
+        );
+
+        // Zero-length dashes are dots under round caps, nothing under butt
+        let round = DashedStrokeOptions {
+            line_cap: LineCap::Round,
+            ..Default::default()
+        };
+        let dots = tessellator
+            .tessellate_dashed_stroke_native("M0 0 L40 0", 4.0, &[0.0, 10.0], 0.0, &round)
+            .unwrap();
+        // Dots at 0, 10, 20, 30 and 40, each a circle of radius 2
+        let dot = std::f32::consts::PI * 4.0;
+        assert!(mesh_area(&dots) > 5.0 * 0.95 * dot && mesh_area(&dots) < 5.0 * dot);
+        assert!((dots.bounds.min_x + 2.0).abs() < 1e-2 && (dots.bounds.max_x - 42.0).abs() < 1e-2);
+        let butt = tessellator
+            .tessellate_dashed_stroke_native("M0 0 L40 0", 4.0, &[0.0, 10.0], 0.0, &options)
+            .unwrap();
+        assert!(butt.indices.is_empty());
+
+        let svg = r##"<svg width="100" height="20" viewBox="0 0 50 10">
+            <g stroke-dasharray="10, 5" stroke-dashoffset="5">
+                <path d="M0 5 L50 5" stroke="#000" stroke-width="2" fill="none"/>
+                <path d="M0 5 L50 5" stroke="#000" stroke-width="2" fill="none" stroke-dasharray="none"/>
+            </g>
+        </svg>"##;
+        let paths = parse_svg_content(svg, None, None, None).paths;
+        assert_eq!(paths[0].dash_array(), Some(vec![10.0, 5.0]));
+        assert_eq!(paths[1].dash_array(), None);
+        let result = tessellator
+            .tessellate_svg_document(svg, 100.0, 20.0, &TessellateOptions::default())
+            .unwrap();
+        // Shifted by 5: 0-5 10-20 25-35 40-50, also 35 long, drawn at twice
+        // the size and width
+        let dashed = mesh_area(&result.meshes[0].mesh);
+        let solid = mesh_area(&result.meshes[1].mesh);
+        assert!((dashed / 4.0 - 70.0).abs() < 0.1, "{dashed}");
+        assert!((solid / 4.0 - 100.0).abs() < 0.1, "{solid}");
+    }
+
+    #[test]
+    #[cfg(feature = "gradients")]
+    fn test_linear_gradients_paint_meshes() {
+        let svg = r##"<svg width="80" height="80" viewBox="0 0 40 40" xmlns:xlink="http://www.w3.org/1999/xlink">
+            <defs>
+                <linearGradient id="across">
+                    <stop offset="0" stop-color="red"/>
+                    <stop offset="100%" style="stop-color: #0000ff; stop-opacity: 0.5"/>
+                </linearGradient>
+                <linearGradient id="diagonal" xlink:href="#across" x2="1" y2="1"/>
+            </defs>
+            <rect x="10" y="10" width="20" height="10" fill="url(#across)"/>
+            <rect x="10" y="10" width="20" height="10" fill="url(#diagonal)" transform="translate(5 0)"/>
+            <rect x="10" y="10" width="20" height="10" fill="url(#missing)"/>
+            <rect x="10" y="10" width="20" height="10" fill="url(#missing) blue"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        assert_eq!(parsed.gradients.len(), 2);
+        let result = SvgTessellator::new()
+            .tessellate_svg_document(svg, 80.0, 80.0, &TessellateOptions::default())
+            .unwrap();
+        assert_eq!(
+            result.document_warnings,
+            ["drew references to undefined paints black: #missing"]
+        );
+        let paints: Vec<_> = result
+            .meshes
+            .iter()
+            .map(|entry| entry.mesh.paint.clone())
+            .collect();
+        assert_eq!(
+            paints[2..],
+            [
+                Some(MeshPaint::Solid {
+                    color: [0, 0, 0, 255]
+                }),
+                Some(MeshPaint::Solid {
+                    color: [0, 0, 255, 255]
+                }),
+            ]
+        );
+
+        // Offset of display point (x, y) by projecting onto the axis
+        let offset = |paint: &Option<MeshPaint>, x: f32, y: f32| match paint {
+            Some(MeshPaint::LinearGradient { start, end, .. }) => {
+                let axis = (end[0] - start[0], end[1] - start[1]);
+                ((x - start[0]) * axis.0 + (y - start[1]) * axis.1)
+                    / (axis.0 * axis.0 + axis.1 * axis.1)
             }
+            paint => panic!("not a gradient: {:?}", paint),
+        };
+        let Some(MeshPaint::LinearGradient { start, end, stops }) = &paints[0] else {
+            panic!("not a gradient: {:?}", paints[0]);
+        };
+        assert_eq!(*start, [20.0, 20.0]);
+        assert!((end[0] - 60.0).abs() < 1e-4 && end[1] == 20.0, "{end:?}");
+        assert_eq!(stops[0].color, [255, 0, 0, 255]);
+        assert_eq!((stops[1].offset, stops[1].color), (1.0, [0, 0, 255, 128]));
+
+        // The diagonal of a 2:1 box, whose lines of equal offset are not
+        // perpendicular to it, inherits its stops through href
+        let Some(MeshPaint::LinearGradient { stops, .. }) = &paints[1] else {
+            panic!("not a gradient: {:?}", paints[1]);
+        };
+        assert_eq!(stops.len(), 2);
+        for ((x, y), expected) in [
+            ((30.0, 20.0), 0.0),
+            ((70.0, 40.0), 1.0),
+            ((70.0, 20.0), 0.5),
+            ((30.0, 40.0), 0.5),
+        ] {
+            assert!(
+                (offset(&paints[1], x, y) - expected).abs() < 1e-4,
+                "{x} {y}"
+            );
         }
     }
 
-    if let Some(ref vb) = result.view_box {
-        result.width = vb.width;
-        result.height = vb.height;
+    #[test]
+    #[cfg(feature = "gradients")]
+    fn test_radial_gradients_paint_meshes() {
+        let svg = r##"<svg width="80" height="40" viewBox="0 0 40 20">
+            <radialGradient id="glow" fx="0.25">
+                <stop offset="0" stop-color="white"/>
+                <stop offset="1" stop-color="black"/>
+            </radialGradient>
+            <radialGradient id="halo" href="#glow" gradientUnits="userSpaceOnUse" cx="30" cy="10" r="5"/>
+            <circle cx="10" cy="10" r="8" fill="url(#glow)"/>
+            <rect x="20" y="0" width="20" height="10" fill="url(#glow)"/>
+            <circle cx="30" cy="10" r="5" fill="url(#halo)"/>
+        </svg>"##;
+        let result = SvgTessellator::new()
+            .tessellate_svg_document(svg, 80.0, 40.0, &TessellateOptions::default())
+            .unwrap();
+        let radial = |index: usize| match &result.meshes[index].mesh.paint {
+            Some(MeshPaint::RadialGradient {
+                center,
+                focus,
+                radius_x,
+                radius_y,
+                stops,
+            }) => (*center, *focus, *radius_x, *radius_y, stops.len()),
+            paint => panic!("not a radial gradient: {:?}", paint),
+        };
+        let close =
+            |a: [f32; 2], b: [f32; 2]| (a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3;
+
+        // The circle's center, scaled by the viewBox; the focus defaults
+        // follow cx and cy unless given
+        let (center, focus, radius_x, radius_y, stops) = radial(0);
+        assert!(close(center, [20.0, 20.0]), "{center:?}");
+        assert!(close(focus, [12.0, 20.0]), "{focus:?}");
+        assert!(close(radius_x, [16.0, 0.0]) && close(radius_y, [0.0, 16.0]));
+        assert_eq!(stops, 2);
+
+        // A 2:1 bounding box stretches the circle into an ellipse
+        let (center, _, radius_x, radius_y, _) = radial(1);
+        assert!(close(center, [60.0, 10.0]), "{center:?}");
+        assert!(close(radius_x, [20.0, 0.0]) && close(radius_y, [0.0, 10.0]));
+
+        // User space geometry of its own, with fx and the stops through href
+        let (center, focus, radius_x, _, stops) = radial(2);
+        assert!(close(center, [60.0, 20.0]), "{center:?}");
+        assert!(close(focus, [0.5, 20.0]), "{focus:?}");
+        assert!(close(radius_x, [10.0, 0.0]));
+        assert_eq!(stops, 2);
     }
 
-    result
-}
+    #[test]
+    fn test_use_elements_copy_definitions() {
+        let svg = r##"<svg viewBox="0 0 40 40" xmlns:xlink="http://www.w3.org/1999/xlink">
+            <defs>
+                <path id="p1" d="M0 0 H4 V4 H0 Z"/>
+            </defs>
+            <use href="#p1" x="4" y="4" fill="red"/>
+            <g transform="translate(10 0)" fill="blue">
+                <use xlink:href="#p1" x="4" y="4"/>
+            </g>
+            <use id="again" href="#later" y="20"/>
+            <path id="later" d="M0 0 H4 V4 H0 Z" fill="green"/>
+            <use id="a" href="#b"/>
+            <use id="b" href="#a"/>
+            <use href="#missing"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        let fills: Vec<_> = parsed
+            .paths
+            .iter()
+            .map(|path| (path.id.as_deref(), path.fill.as_deref()))
+            .collect();
+        // The def itself does not draw, and the target's own fill wins
+        assert_eq!(
+            fills,
+            [
+                (None, Some("red")),
+                (None, Some("blue")),
+                (Some("again"), Some("green")),
+                (Some("later"), Some("green")),
+            ]
+        );
+        assert_eq!(
+            parsed.document_warnings,
+            [
+                "skipped use elements linking to undefined shapes: #missing",
+                "skipped use elements nested more than 16 deep, as in a cycle: #b, #a",
+            ]
+        );
 
-fn regex_match(text: &str, pattern: &str) -> Option<String> {
-    let re = regex_lite::Regex::new(pattern).ok()?;
-    let caps = re.captures(text)?;
-    caps.get(1).map(|m| m.as_str().to_string())
-}
+        let meshes = SvgTessellator::new().tessellate_svg_meshes(svg, 40.0, 40.0);
+        let corners: Vec<_> = meshes
+            .iter()
+            .map(|mesh| (mesh.bounds.min_x.round(), mesh.bounds.min_y.round()))
+            .collect();
+        assert_eq!(corners, [(4.0, 4.0), (14.0, 4.0), (0.0, 20.0), (0.0, 0.0)]);
 
-fn find_all_paths(svg_content: &str) -> Vec<String> {
-    find_all_elements(svg_content, "path")
-}
+        // Offsets read units like other lengths, and ignore what isn't finite
+        let svg = r##"<svg viewBox="0 0 100 100">
+            <defs><path id="p1" d="M0 0 H4 V4 H0 Z"/></defs>
+            <use href="#p1" x="5px" y="0.25in"/>
+            <use href="#p1" x="NaN" y="1e40"/>
+        </svg>"##;
+        let meshes = SvgTessellator::new().tessellate_svg_meshes(svg, 100.0, 100.0);
+        let corners: Vec<_> = meshes
+            .iter()
+            .map(|mesh| (mesh.bounds.min_x.round(), mesh.bounds.min_y.round()))
+            .collect();
+        assert_eq!(corners, [(5.0, 24.0), (0.0, 0.0)]);
+    }
 
-fn find_all_circles(svg_content: &str) -> Vec<String> {
-    find_all_elements(svg_content, "circle")
-}
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_current_color_takes_the_callers_color() {
+        let svg = r##"<svg viewBox="0 0 20 20">
+            <g fill="currentColor">
+                <rect width="8" height="8"/>
+                <rect x="10" width="8" height="8" style="fill: none; stroke: currentColor" stroke-width="2"/>
+            </g>
+            <g style="color: green">
+                <circle cx="10" cy="15" r="4" fill="currentColor"/>
+            </g>
+        </svg>"##;
+        let mut tessellator = SvgTessellator::new();
+        let mut tessellate = |current_color: Option<&str>| {
+            let options = TessellateOptions {
+                current_color: current_color.map(str::to_string),
+                ..TessellateOptions::default()
+            };
+            tessellator
+                .tessellate_svg_document(svg, 20.0, 20.0, &options)
+                .unwrap()
+                .meshes
+                .into_iter()
+                .map(|entry| entry.mesh)
+                .collect::<Vec<_>>()
+        };
+        let color = |mesh: &TessellatedMesh| match mesh.paint {
+            Some(MeshPaint::Solid { color }) => color,
+            ref paint => panic!("not solid: {:?}", paint),
+        };
+        let red = tessellate(Some("#ff0000"));
+        let blue = tessellate(Some("#0000ff"));
+        assert_eq!(red.len(), 3);
+        for (red, blue) in red.iter().zip(&blue) {
+            assert_eq!(
+                (&red.vertices, &red.indices),
+                (&blue.vertices, &blue.indices)
+            );
+            assert!(!red.uses_current_color);
+        }
+        // Inherited from a group and set in style alike; a `color` in the
+        // document wins over the caller's
+        let colors = |meshes: &[TessellatedMesh]| meshes.iter().map(color).collect::<Vec<_>>();
+        assert_eq!(
+            colors(&red),
+            [[255, 0, 0, 255], [255, 0, 0, 255], [0, 128, 0, 255]]
+        );
+        assert_eq!(
+            colors(&blue),
+            [[0, 0, 255, 255], [0, 0, 255, 255], [0, 128, 0, 255]]
+        );
 
-fn find_all_rects(svg_content: &str) -> Vec<String> {
-    find_all_elements(svg_content, "rect")
-}
+        // Without a color, meshes are flagged for the renderer to tint
+        let plain = tessellate(None);
+        let flags: Vec<_> = plain.iter().map(|mesh| mesh.uses_current_color).collect();
+        assert_eq!(flags, [true, true, false]);
+        assert_eq!(color(&plain[0]), [0, 0, 0, 255]);
 
-fn find_all_polygons(svg_content: &str) -> Vec<String> {
-    find_all_elements(svg_content, "polygon")
-}
+        let tessellator = SvgTessellator::new();
+        let parse = |current_color: Option<&str>| {
+            let options = ParseOptions {
+                current_color: current_color.map(str::to_string),
+                ..ParseOptions::default()
+            };
+            let parsed = tessellator.parse_svg_native(svg, &options).unwrap();
+            parsed.paths[1].stroke.clone()
+        };
+        assert_eq!(parse(Some("#0000ff")).as_deref(), Some("#0000ff"));
+        assert_eq!(parse(None).as_deref(), Some("currentColor"));
+    }
+
+    #[test]
+    fn test_colors_parse_to_straight_rgba() {
+        let close = |value: &str, expected: [f32; 4]| {
+            let color = parse_color(value).unwrap_or_else(|| panic!("{value} is a color"));
+            let near = color
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| (a - b).abs() < 1e-3);
+            assert!(near, "{value}: {color:?} is not {expected:?}");
+        };
+        close("#f80", [1.0, 0.533, 0.0, 1.0]);
+        close("#f808", [1.0, 0.533, 0.0, 0.533]);
+        close("#E91E63", [0.914, 0.118, 0.388, 1.0]);
+        close("rgb(100%, 50%, 0%)", [1.0, 0.5, 0.0, 1.0]);
+        close("rgba(10, 20, 30, 50%)", [0.039, 0.078, 0.118, 0.5]);
+        close("rgb(255 0 0 / 0.25)", [1.0, 0.0, 0.0, 0.25]);
+        close("hsl(120, 100%, 50%)", [0.0, 1.0, 0.0, 1.0]);
+        // Hues wrap around the color wheel
+        close("hsl(480, 100%, 50%)", [0.0, 1.0, 0.0, 1.0]);
+        close("hsla(-120deg, 100%, 25%, 0.5)", [0.0, 0.0, 0.5, 0.5]);
+        close("hsl(0.5turn 100% 50%)", [0.0, 1.0, 1.0, 1.0]);
+        close("RebeccaPurple", [0.4, 0.2, 0.6, 1.0]);
+        close("transparent", [0.0; 4]);
+        for invalid in [
+            "none",
+            "currentColor",
+            "#12345",
+            "rgb(1, 2)",
+            "hsl(x, 1%, 1%)",
+            "bluish",
+        ] {
+            assert_eq!(parse_color(invalid), None, "{invalid}");
+        }
+
+        let svg = r##"<svg viewBox="0 0 10 10">
+            <rect width="4" height="4" fill="hsl(240, 100%, 50%)" stroke="#0f08" stroke-width="1"/>
+            <rect x="5" width="4" height="4" fill="bluish"/>
+            <rect y="5" width="4" height="4" fill="none" stroke="url(#missing)"/>
+        </svg>"##;
+        let parsed = parse_svg_content(svg, None, None, None);
+        let colors: Vec<_> = parsed
+            .paths
+            .iter()
+            .map(|path| (path.fill_color, path.stroke_color))
+            .collect();
+        let green = 136.0 / 255.0;
+        assert_eq!(
+            colors,
+            [
+                (Some([0.0, 0.0, 1.0, 1.0]), Some([0.0, 1.0, 0.0, green])),
+                (Some([0.0, 0.0, 0.0, 1.0]), None),
+                (None, None),
+            ]
+        );
+        assert!(parsed
+            .document_warnings
+            .contains(&"drew invalid colors black: \"bluish\"".to_string()));
+
+        let meshes = SvgTessellator::new().tessellate_svg_meshes(svg, 10.0, 10.0);
+        assert_eq!(meshes[0].color, Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(
+            meshes[0].paint,
+            Some(MeshPaint::Solid {
+                color: [0, 0, 255, 255]
+            })
+        );
+    }
 
-fn find_all_elements(svg_content: &str, tag: &str) -> Vec<String> {
-    let mut results = Vec::new();
-    let pattern = format!(
-        r"<{}\b([^>]*)/?>([\s\S]*?)</{}>|<{}\b([^>]*)/?>",
-        tag, tag, tag
-    );
+    #[test]
+    fn test_attributes_are_read_as_xml() {
+        // Only the root element sets the document size, and `stroke-width`
+        // is not `width`
+        let parsed = parse_svg_content(
+            r#"<svg height="30"><path stroke-width="2" d="M0 0 L1 1"/><rect width="5" height="5"/></svg>"#,
+            None,
+            None,
+            None,
+        );
+        assert_eq!((parsed.width, parsed.height), (24.0, 30.0));
+        let parsed = parse_svg_content(
+            r#"<svg width="20px"><svg width="5"/></svg>"#,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(parsed.width, 20.0);
 
-    if let Ok(re) = regex_lite::Regex::new(&pattern) {
-        for cap in re.captures_iter(svg_content) {
-            if let Some(m) = cap.get(0) {
-                results.push(m.as_str().to_string());
+        // Attributes written inside another attribute's value stay part of
+        // it, and references in values are replaced
+        let svg = r#"<?xml-stylesheet href="data:text/css,path>*{fill:red}"?>
+            <!DOCTYPE svg [
+                <!ENTITY note "1 > 2 <path d='M0 0 L5 5'/>">
+                <!-- don't draw this: <path d="M0 0 L6 6"/> -->
+            ]>
+            <svg viewBox = '0 0 10 10'>
+                <path data-note="a fill='red' d='M0 0'" d="M0 0&#10;L1 1 L1&#x20;0Z"/>
+                <path fill="&#x23;00f" d="M2 2 L3 3 L3 2Z"/>
+            </svg>"#;
+        let parsed = parse_svg_content(svg, None, None, None);
+        assert_eq!(parsed.width, 10.0);
+        let paths: Vec<_> = parsed
+            .paths
+            .iter()
+            .map(|path| (path.d.as_str(), path.fill.as_deref()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("M0 0\nL1 1 L1 0Z", None),
+                ("M2 2 L3 3 L3 2Z", Some("#00f")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preserve_aspect_ratio_places_the_document() {
+        let bounds = |aspect: &str, width: f32, height: f32| {
+            let svg =
+                format!(r#"<svg viewBox="0 0 24 24"{aspect}><rect width="24" height="24"/></svg>"#);
+            let meshes = SvgTessellator::new().tessellate_svg_meshes(&svg, width, height);
+            let bounds = &meshes[0].bounds;
+            [bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y]
+        };
+        let near = |actual: [f32; 4], expected: [f32; 4]| {
+            let close = actual
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| (a - b).abs() < 1e-3);
+            assert!(close, "{actual:?} is not {expected:?}");
+        };
+        // Scaled by 50/24 on both axes and centered horizontally
+        near(bounds("", 100.0, 50.0), [25.0, 0.0, 75.0, 50.0]);
+        near(
+            bounds(r#" preserveAspectRatio="xMidYMid meet""#, 100.0, 50.0),
+            [25.0, 0.0, 75.0, 50.0],
+        );
+        near(
+            bounds(r#" preserveAspectRatio="xMaxYMin""#, 100.0, 50.0),
+            [50.0, 0.0, 100.0, 50.0],
+        );
+        // Covering the display overflows it vertically
+        near(
+            bounds(r#" preserveAspectRatio="xMinYMid slice""#, 100.0, 50.0),
+            [0.0, -25.0, 100.0, 75.0],
+        );
+        near(
+            bounds(r#" preserveAspectRatio="none""#, 100.0, 50.0),
+            [0.0, 0.0, 100.0, 50.0],
+        );
+        // An invalid value reads as the default
+        near(
+            bounds(r#" preserveAspectRatio="xMidYMiddle""#, 50.0, 100.0),
+            [0.0, 25.0, 50.0, 75.0],
+        );
+        let parsed = parse_svg_content(
+            r#"<svg viewBox="0 0 24 24" preserveAspectRatio="xMinYMax slice"/>"#,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            parsed.preserve_aspect_ratio,
+            PreserveAspectRatio {
+                align: Some([AspectAlign::Min, AspectAlign::Max]),
+                slice: true,
             }
-        }
+        );
+    }
+
+    #[test]
+    fn test_lengths_with_units() {
+        let size = |svg: &str| {
+            let parsed = parse_svg_content(svg, None, None, None);
+            (parsed.width, parsed.height)
+        };
+        // A percentage is of the viewBox, and ignored without one
+        assert_eq!(
+            size(r#"<svg width="100%" height="50%" viewBox="0 0 40 20"/>"#),
+            (40.0, 10.0)
+        );
+        assert_eq!(size(r#"<svg viewBox="0 0 40 20"/>"#), (40.0, 20.0));
+        let (width, height) = size(r#"<svg width="10mm" height="1in" viewBox="0 0 4 4"/>"#);
+        assert!((width - 37.795).abs() < 1e-3, "{width}");
+        assert_eq!(height, 96.0);
+        assert_eq!(size(r#"<svg width="100%" height="100%"/>"#), (24.0, 24.0));
+        let (width, height) = size(r#"<svg width="10mm" height="0.5in"/>"#);
+        assert!((width - 37.795).abs() < 1e-3, "{width}");
+        assert_eq!(height, 48.0);
+        assert_eq!(size(r#"<svg width="24px" height="1.5em"/>"#), (24.0, 24.0));
+
+        let svg = r##"<svg viewBox="0 0 24 24">
+            <path d="M0 0 L1 1" stroke="#000" stroke-width="0.5em"/>
+            <g stroke-width="3pt"><path d="M0 0 L1 1" stroke="#000"/></g>
+            <path d="M0 0 L1 1" stroke="#000" stroke-width="2furlongs"/>
+        </svg>"##;
+        let widths = |font_size| {
+            let options = ParseOptions {
+                font_size,
+                ..Default::default()
+            };
+            let parsed = SvgTessellator::new()
+                .parse_svg_native(svg, &options)
+                .unwrap();
+            parsed
+                .paths
+                .iter()
+                .map(|path| path.stroke_width)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(widths(None), [Some(8.0), Some(4.0), None]);
+        assert_eq!(widths(Some(20.0)), [Some(10.0), Some(4.0), None]);
+    }
+
+    #[test]
+    fn test_packed_arc_flags() {
+        let commands = |d: &str| {
+            let (mut text, mut spans, mut commands) = (Vec::new(), Vec::new(), Vec::new());
+            parse_svg_path_d(d, &mut text, &mut spans, &mut commands, true).unwrap();
+            format!("{commands:?}")
+        };
+        // Flags take one character each, and the rest of the token is the
+        // next coordinate
+        assert_eq!(
+            commands("M0 0 a1 1 0 011 1"),
+            commands("M0 0 a1 1 0 0 1 1 1")
+        );
+        assert_eq!(
+            commands("M0 0 a1,1,0,1,0,5,5"),
+            commands("M0 0 a1 1 0 10 5 5")
+        );
+        assert_eq!(
+            commands("M0 0 A2 2 0 10.5.5 A2 2 0 11-1 1"),
+            commands("M0 0 A2 2 0 1 0 0.5 0.5 A2 2 0 1 1 -1 1")
+        );
+        assert!(commands("M0 0 a1 1 0 011 1").contains(
+            "ArcTo { rx: 1.0, ry: 1.0, rotation: 0.0, large_arc: false, sweep: true, x: 1.0, y: 1.0"
+        ));
+        // Numbers elsewhere still read whole
+        assert_eq!(commands("M10 10 L011 1"), commands("M10 10 L11 1"));
+    }
+
+    /// The tokens `tokenize_svg_path` splits `d` into.
+    fn path_tokens(d: &str) -> Vec<String> {
+        let (mut text, mut spans) = (Vec::new(), Vec::new());
+        tokenize_svg_path(d, &mut text, &mut spans);
+        (0..spans.len())
+            .map(|i| token_at(&text, &spans, i).to_string())
+            .collect()
+    }
+
+    fn path_commands(d: &str, strict: bool) -> Result<Vec<SvgCommand>, TokenError> {
+        let (mut text, mut spans, mut commands) = (Vec::new(), Vec::new(), Vec::new());
+        parse_svg_path_d(d, &mut text, &mut spans, &mut commands, strict).map(|_| commands)
     }
 
-    if results.is_empty() {
-        let simple_pattern = format!(r"<{}\b[^>]*>", tag);
-        if let Ok(re) = regex_lite::Regex::new(&simple_pattern) {
-            for m in re.find_iter(svg_content) {
-                results.push(m.as_str().to_string());
+    /// The endpoints of a `d` made only of moves and lines, parsed strictly.
+    fn line_points(d: &str) -> Vec<(f32, f32)> {
+        let mut points = Vec::new();
+        for command in path_commands(d, true).unwrap() {
+            match command {
+                SvgCommand::MoveTo { x, y, .. } | SvgCommand::LineTo { x, y, .. } => {
+                    points.push((x, y))
+                }
+                other => panic!("{d}: unexpected {other:?}"),
             }
         }
+        points
     }
 
-    results
-}
+    #[test]
+    fn test_tokenize_splits_concatenated_decimals_and_signed_exponents() {
+        assert_eq!(
+            path_tokens("M.5.5L1e2-3e-2"),
+            ["M", ".5", ".5", "L", "1e2", "-3e-2"]
+        );
+    }
 
-fn extract_attr(element: &str, attr: &str) -> Option<String> {
-    let pattern = format!(r#"\b{}\s*=\s*["']([^"']*)["']"#, attr);
-    regex_match(element, &pattern)
+    #[test]
+    fn test_tokenize_keeps_plus_signs() {
+        assert_eq!(path_tokens("l+5+5"), ["l", "+5", "+5"]);
+    }
+
+    #[test]
+    fn test_tokenize_ends_an_exponent_at_a_comma() {
+        assert_eq!(path_tokens("M1e5,2"), ["M", "1e5", "2"]);
+    }
+
+    #[test]
+    fn test_tokenize_ends_an_exponent_at_a_decimal_point() {
+        assert_eq!(path_tokens("M1e2.5 3E+1"), ["M", "1e2", ".5", "3E+1"]);
+    }
+
+    #[test]
+    fn test_tokenize_keeps_a_dangling_exponent_in_its_number() {
+        assert_eq!(path_tokens("M1e,2"), ["M", "1e", "2"]);
+    }
+
+    #[test]
+    fn test_parse_reads_concatenated_decimals_and_signed_exponents() {
+        assert_eq!(line_points("M.5.5L1e2-3e-2"), [(0.5, 0.5), (100.0, -0.03)]);
+    }
+
+    #[test]
+    fn test_parse_reads_plus_signs() {
+        assert_eq!(line_points("M0 0 l+5+5"), [(0.0, 0.0), (5.0, 5.0)]);
+    }
+
+    #[test]
+    fn test_parse_reads_an_exponent_before_a_comma() {
+        assert_eq!(line_points("M1e5,2"), [(100000.0, 2.0)]);
+    }
+
+    #[test]
+    fn test_strict_parse_rejects_a_dangling_exponent() {
+        let error = path_commands("M1e,2", true).unwrap_err();
+        assert_eq!(error.message, "cannot read '1e' as a number");
+    }
+
+    #[test]
+    fn test_lenient_parse_reads_a_dangling_exponent_as_its_number() {
+        assert!(matches!(
+            path_commands("M1e,2", false).unwrap()[..],
+            [SvgCommand::MoveTo { x: 1.0, y: 2.0, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_dangling_exponents_never_build_nan_points() {
+        let path = path_from_d("M1e,2 L3e-,4");
+        assert!(path.iter().all(|event| {
+            let (from, to) = (event.from(), event.to());
+            from.x.is_finite() && from.y.is_finite() && to.x.is_finite() && to.y.is_finite()
+        }));
+    }
+
+    #[test]
+    fn test_checked_path_data_reports_where_it_stopped() {
+        // A truncated curve stops at the end of the data
+        let truncated = parse_svg_path_d_checked("M0 0 C5 5 2").unwrap_err();
+        assert_eq!(
+            (truncated.offset, truncated.found, truncated.command),
+            (Some(11), None, Some('C'))
+        );
+        assert_eq!(truncated.message, "path data ends in the middle of 'C'");
+
+        // A stray letter stops at the letter, in the command it interrupts
+        let stray = parse_svg_path_d_checked("M0 0 L10 x 20 20").unwrap_err();
+        assert_eq!(
+            (
+                stray.token,
+                stray.offset,
+                stray.found.as_deref(),
+                stray.command
+            ),
+            (Some(5), Some(9), Some("x"), Some('L'))
+        );
+        assert_eq!(stray.context, "0 L 10 [x] 20 20");
+
+        // Empty data holds nothing to draw, which is not an error
+        assert_eq!(parse_svg_path_d_checked(""), Ok(0));
+        assert_eq!(parse_svg_path_d_checked("M0 0 L1 1"), Ok(2));
+
+        // Tessellating strictly keeps going past paths that stop, and
+        // reports them
+        let svg = r#"<svg viewBox="0 0 24 24">
+            <path d="M0 0 C5 5 2"/>
+            <path d="M0 0 L10 x 20 20"/>
+            <path d=""/>
+            <path d="M0 0 L24 0 L24 24Z"/>
+        </svg>"#;
+        let strict = TessellateOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let tessellation = SvgTessellator::new()
+            .tessellate_svg_document(svg, 24.0, 24.0, &strict)
+            .unwrap();
+        assert_eq!(tessellation.meshes.len(), 1);
+        let errors: Vec<_> = tessellation
+            .errors
+            .iter()
+            .map(|error| {
+                let issue = error.issue.as_ref().unwrap();
+                (error.path_index, error.stage, issue.offset, issue.command)
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (0, TessellationStage::Parse, Some(11), Some('C')),
+                (1, TessellationStage::Parse, Some(9), Some('L')),
+            ]
+        );
+        assert!(tessellation.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_lenient_tessellation_draws_truncated_path_with_a_warning() {
+        // A curve cut short reads 0 for what is missing, and still draws
+        let svg = r#"<svg viewBox="0 0 24 24">
+            <path d="M0 0 L24 0 L24 24 C12 24 0"/>
+            <path d="M0 0 L24 0 L24 24Z"/>
+        </svg>"#;
+        let tessellation = SvgTessellator::new()
+            .tessellate_svg_document(svg, 24.0, 24.0, &TessellateOptions::default())
+            .unwrap();
+        assert_eq!(tessellation.meshes.len(), 2);
+        assert!(tessellation.errors.is_empty());
+        let warnings: Vec<_> = tessellation
+            .warnings
+            .iter()
+            .map(|issue| (issue.path, issue.offset, issue.command))
+            .collect();
+        assert_eq!(warnings, [(0, Some(26), Some('C'))]);
+        assert_eq!(
+            tessellation.warnings[0].message,
+            "path data ends in the middle of 'C'"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stroke")]
+    fn test_meshes_name_their_sources() {
+        let svg = r##"<svg viewBox="0 0 40 20">
+            <path d=""/>
+            <rect id="box" class="card raised" width="10" height="10" fill="#f00"
+                stroke="#00f" stroke-width="2" paint-order="stroke"/>
+            <path d="M20 0 L30 10" stroke="red" stroke-width="1"/>
+            <circle cx="35" cy="5" r="3" fill="none" stroke="#000" stroke-width="0.5"/>
+        </svg>"##;
+        let tessellation = SvgTessellator::new()
+            .tessellate_svg_document(svg, 80.0, 40.0, &TessellateOptions::default())
+            .unwrap();
+        assert_eq!((tessellation.width, tessellation.height), (40.0, 20.0));
+        let sources: Vec<_> = tessellation
+            .meshes
+            .iter()
+            .map(|entry| {
+                (
+                    entry.path,
+                    entry.kind,
+                    entry.paint.as_deref(),
+                    entry.stroke_width,
+                    entry.id.as_deref(),
+                )
+            })
+            .collect();
+        // The empty path draws nothing, so indices skip it, and the rect's
+        // stroke comes first as its paint-order asks
+        assert_eq!(
+            sources,
+            [
+                (1, MeshKind::Stroke, Some("#00f"), Some(2.0), Some("box")),
+                (1, MeshKind::Fill, Some("#f00"), None, Some("box")),
+                (2, MeshKind::Fill, None, None, None),
+                (2, MeshKind::Stroke, Some("red"), Some(1.0), None),
+                (3, MeshKind::Stroke, Some("#000"), Some(0.5), None),
+            ]
+        );
+        assert_eq!(tessellation.meshes[0].class.as_deref(), Some("card raised"));
+        assert_eq!(
+            tessellation.meshes[1].mesh.color,
+            Some([1.0, 0.0, 0.0, 1.0])
+        );
+    }
 }
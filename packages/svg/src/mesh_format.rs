@@ -0,0 +1,313 @@
+//! Binary asset format for pre-tessellated meshes.
+//!
+//! Lets a build step tessellate SVGs natively and ship the meshes, so the
+//! client draws them without parsing any SVG. All values are little-endian,
+//! and every section is a multiple of four bytes so payloads can be viewed
+//! in place as `Float32Array` and `Uint32Array`.
+//!
+//! ```text
+//! file header   magic "GLMS"      [u8; 4]
+//!               version           u16
+//!               header length     u16   bytes from the start of the file
+//!               mesh count        u32
+//! per mesh      record length     u16   bytes in this mesh header
+//!               flags             u16   bit 0: has color, bit 1: stroke
+//!               vertex count      u32   vertices, three f32 each
+//!               index count       u32
+//!               color             [u8; 4] RGBA, zero without a color
+//!               bounds            [f32; 4] min_x, min_y, max_x, max_y
+//!               paint order       u32   see `TessellatedMesh::paint_order`
+//!               vertices          [f32; vertex count * 3] x, y, edge_dist
+//!               indices           [u32; index count]
+//! ```
+//!
+//! Compatibility: fields are only ever appended to the two headers, and a
+//! reader skips whatever lies past the fields it knows by honoring the
+//! stored lengths, as it does bytes after the last mesh. Unknown flag bits
+//! are ignored. The version changes only when existing fields change
+//! meaning, and readers reject versions newer than their own. Mesh headers
+//! written before paint order was appended are 32 bytes long, and their
+//! meshes read with a paint order equal to their index.
+//!
+//! Boundary edges are not stored, since `boundary_edges` rebuilds them from
+//! the indices, and neither are source commands, which documents never
+//! carry.
+
+use crate::{MeshBounds, TessellatedMesh};
+use serde::{Deserialize, Serialize};
+
+pub const MESH_MAGIC: [u8; 4] = *b"GLMS";
+pub const MESH_FORMAT_VERSION: u16 = 1;
+
+const FILE_HEADER_LEN: u16 = 12;
+const MESH_HEADER_LEN: u16 = 36;
+/// Length of a mesh header without the paint order
+const MESH_HEADER_V1_LEN: u16 = 32;
+
+const FLAG_HAS_COLOR: u16 = 1;
+const FLAG_STROKE: u16 = 1 << 1;
+
+/// A mesh with the paint needed to draw it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MeshAsset {
+    pub mesh: TessellatedMesh,
+    /// Resolved RGBA color, or None for a transparent paint
+    pub color: Option<[u8; 4]>,
+    /// Whether the mesh is a stroke rather than a fill
+    pub stroke: bool,
+}
+
+/// Why a mesh blob could not be decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeshFormatError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// A header declares a length shorter than the fields it must hold
+    HeaderTooShort {
+        offset: usize,
+        length: usize,
+    },
+    /// The data ends before `needed` bytes starting at `offset`
+    Truncated {
+        offset: usize,
+        needed: usize,
+    },
+    IndexOutOfRange {
+        mesh: usize,
+        index: u32,
+    },
+    /// A scene entry's name at byte `offset` is not UTF-8
+    InvalidName {
+        offset: usize,
+    },
+    /// A scene entry's meshes are not the next ones in the mesh blob
+    MeshesOutOfRange {
+        entry: usize,
+    },
+}
+
+impl std::fmt::Display for MeshFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshFormatError::BadMagic => write!(f, "Not a mesh asset"),
+            MeshFormatError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported mesh format version {}", version)
+            }
+            MeshFormatError::HeaderTooShort { offset, length } => {
+                write!(f, "Header at byte {} is only {} bytes", offset, length)
+            }
+            MeshFormatError::Truncated { offset, needed } => {
+                write!(
+                    f,
+                    "Data truncated: needed {} bytes at byte {}",
+                    needed, offset
+                )
+            }
+            MeshFormatError::IndexOutOfRange { mesh, index } => {
+                write!(f, "Mesh {} references missing vertex {}", mesh, index)
+            }
+            MeshFormatError::InvalidName { offset } => {
+                write!(f, "Name at byte {} is not valid UTF-8", offset)
+            }
+            MeshFormatError::MeshesOutOfRange { entry } => {
+                write!(f, "Scene entry {} references missing meshes", entry)
+            }
+        }
+    }
+}
+
+/// Encode meshes into one blob, in order.
+pub fn encode_meshes(meshes: &[MeshAsset]) -> Vec<u8> {
+    let payload: usize = meshes
+        .iter()
+        .map(|asset| {
+            MESH_HEADER_LEN as usize + (asset.mesh.vertices.len() + asset.mesh.indices.len()) * 4
+        })
+        .sum();
+    let mut out = Vec::with_capacity(FILE_HEADER_LEN as usize + payload);
+
+    out.extend_from_slice(&MESH_MAGIC);
+    out.extend_from_slice(&MESH_FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&FILE_HEADER_LEN.to_le_bytes());
+    out.extend_from_slice(&(meshes.len() as u32).to_le_bytes());
+
+    for asset in meshes {
+        let mesh = &asset.mesh;
+        debug_assert_eq!(mesh.vertices.len() % 3, 0, "vertices are x, y, edge_dist");
+        let mut flags = 0;
+        if asset.color.is_some() {
+            flags |= FLAG_HAS_COLOR;
+        }
+        if asset.stroke {
+            flags |= FLAG_STROKE;
+        }
+
+        out.extend_from_slice(&MESH_HEADER_LEN.to_le_bytes());
+        out.extend_from_slice(&flags.to_le_bytes());
+        out.extend_from_slice(&((mesh.vertices.len() / 3) as u32).to_le_bytes());
+        out.extend_from_slice(&(mesh.indices.len() as u32).to_le_bytes());
+        out.extend_from_slice(&asset.color.unwrap_or_default());
+        for bound in [
+            mesh.bounds.min_x,
+            mesh.bounds.min_y,
+            mesh.bounds.max_x,
+            mesh.bounds.max_y,
+        ] {
+            out.extend_from_slice(&bound.to_le_bytes());
+        }
+        out.extend_from_slice(&mesh.paint_order.to_le_bytes());
+        for value in &mesh.vertices {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        for index in &mesh.indices {
+            out.extend_from_slice(&index.to_le_bytes());
+        }
+    }
+
+    out
+}
+
+/// Decode a blob written by `encode_meshes`. Never panics on malformed
+/// input; every inconsistency is reported as a `MeshFormatError`.
+pub fn decode_meshes(bytes: &[u8]) -> Result<Vec<MeshAsset>, MeshFormatError> {
+    let mut reader = Reader::new(bytes);
+
+    if reader.take(MESH_MAGIC.len())? != MESH_MAGIC {
+        return Err(MeshFormatError::BadMagic);
+    }
+    let version = reader.u16()?;
+    if version == 0 || version > MESH_FORMAT_VERSION {
+        return Err(MeshFormatError::UnsupportedVersion(version));
+    }
+    let header_len = reader.u16()? as usize;
+    if header_len < FILE_HEADER_LEN as usize {
+        return Err(MeshFormatError::HeaderTooShort {
+            offset: 0,
+            length: header_len,
+        });
+    }
+    let mesh_count = reader.u32()? as usize;
+    reader.skip_to(header_len)?;
+
+    // The count comes from untrusted data, so it only caps the reservation
+    let mut meshes = Vec::with_capacity(mesh_count.min(bytes.len() / MESH_HEADER_V1_LEN as usize));
+    for mesh_index in 0..mesh_count {
+        let header_start = reader.offset();
+        let record_len = reader.u16()? as usize;
+        if record_len < MESH_HEADER_V1_LEN as usize {
+            return Err(MeshFormatError::HeaderTooShort {
+                offset: header_start,
+                length: record_len,
+            });
+        }
+        let flags = reader.u16()?;
+        let vertex_count = reader.u32()? as usize;
+        let index_count = reader.u32()? as usize;
+        let color: [u8; 4] = reader.array()?;
+        let bounds = MeshBounds {
+            min_x: reader.f32()?,
+            min_y: reader.f32()?,
+            max_x: reader.f32()?,
+            max_y: reader.f32()?,
+        };
+        let paint_order = if record_len >= MESH_HEADER_LEN as usize {
+            reader.u32()?
+        } else {
+            mesh_index as u32
+        };
+        reader.skip_to(header_start + record_len)?;
+
+        let vertices = reader
+            .take(vertex_count.saturating_mul(12))?
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        let indices: Vec<u32> = reader
+            .take(index_count.saturating_mul(4))?
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        if let Some(&index) = indices
+            .iter()
+            .find(|&&index| index as usize >= vertex_count)
+        {
+            return Err(MeshFormatError::IndexOutOfRange {
+                mesh: mesh_index,
+                index,
+            });
+        }
+
+        meshes.push(MeshAsset {
+            mesh: TessellatedMesh {
+                vertices,
+                indices,
+                bounds,
+                paint_order,
+                boundary_edges: Vec::new(),
+                source_commands: Vec::new(),
+                command_ranges: Vec::new(),
+                transform: None,
+                alpha: None,
+                paint: None,
+                color: None,
+                uses_current_color: false,
+                degenerate: None,
+            },
+            color: (flags & FLAG_HAS_COLOR != 0).then_some(color),
+            stroke: flags & FLAG_STROKE != 0,
+        });
+    }
+
+    Ok(meshes)
+}
+
+/// Reads little-endian fields from the front of a blob, reporting where
+/// it ran out.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], MeshFormatError> {
+        let truncated = MeshFormatError::Truncated {
+            offset: self.offset,
+            needed: len,
+        };
+        let end = self.offset.checked_add(len).ok_or(truncated.clone())?;
+        let slice = self.bytes.get(self.offset..end).ok_or(truncated)?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    /// Skip unknown trailing header fields up to `end`.
+    pub(crate) fn skip_to(&mut self, end: usize) -> Result<(), MeshFormatError> {
+        self.take(end - self.offset).map(|_| ())
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], MeshFormatError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, MeshFormatError> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, MeshFormatError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn f32(&mut self) -> Result<f32, MeshFormatError> {
+        self.array().map(f32::from_le_bytes)
+    }
+}
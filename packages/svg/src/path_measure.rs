@@ -0,0 +1,294 @@
+//! Arc-length measurement over flattened paths.
+//!
+//! A path is flattened once into line segments with cumulative lengths;
+//! sampling then binary-searches that table, so animating along a path costs
+//! O(log n) per frame after O(n) construction. Dashed strokes and
+//! `point_at_length` are built on the same table.
+
+use crate::{path_from_d, TESSELLATION_TOLERANCE};
+use lyon::math::Point;
+use lyon::path::iterator::PathIterator;
+use lyon::path::{Path, PathEvent};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use tolerance::Tolerance;
+use wasm_bindgen::prelude::*;
+
+/// Segments this short have no direction to sample.
+const DEGENERATE_LENGTH: Tolerance = Tolerance::absolute(f32::EPSILON);
+
+struct Segment {
+    from: Point,
+    to: Point,
+    /// Distance along the path where this segment starts
+    start: f32,
+    length: f32,
+    subpath: usize,
+}
+
+struct Subpath {
+    /// Distance along the path where this subpath starts
+    start: f32,
+    end: f32,
+    closed: bool,
+}
+
+/// A point on a path with the direction of travel.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PathSample {
+    pub x: f32,
+    pub y: f32,
+    /// Direction of travel in radians, measured from the positive x axis
+    pub tangent: f32,
+}
+
+/// A path flattened for measuring distances along it. Moves between subpaths
+/// add no length, so distances skip the gap.
+#[wasm_bindgen]
+pub struct PathMeasure {
+    segments: Vec<Segment>,
+    subpaths: Vec<Subpath>,
+    length: f32,
+}
+
+#[wasm_bindgen]
+impl PathMeasure {
+    /// Measure a path `d` attribute, flattening curves to within
+    /// `tolerance` user units. A non-positive tolerance uses the
+    /// tessellator's.
+    #[wasm_bindgen(constructor)]
+    pub fn new(path_d: &str, tolerance: f32) -> Self {
+        Self::from_path(&path_from_d(path_d), tolerance)
+    }
+
+    /// Total length of all subpaths.
+    #[wasm_bindgen]
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// The point `distance` along the path, clamped to its ends, or None for
+    /// a path with no length.
+    #[wasm_bindgen]
+    pub fn sample(&self, distance: f32) -> Option<PathSample> {
+        let segment = &self.segments[self.segment_index(distance)?];
+        let t = ((distance - segment.start) / segment.length).clamp(0.0, 1.0);
+        let delta = segment.to - segment.from;
+        Some(PathSample {
+            x: segment.from.x + delta.x * t,
+            y: segment.from.y + delta.y * t,
+            tangent: delta.y.atan2(delta.x),
+        })
+    }
+
+    /// The part of the path between two distances as a `d` attribute of
+    /// line commands.
+    #[wasm_bindgen]
+    pub fn segment(&self, start: f32, end: f32) -> String {
+        path_to_d(&self.segment_native(start, end))
+    }
+}
+
+impl PathMeasure {
+    /// Measure an already built path.
+    pub fn from_path(path: &Path, tolerance: f32) -> Self {
+        let tolerance = if tolerance > 0.0 {
+            tolerance
+        } else {
+            TESSELLATION_TOLERANCE
+        };
+        let mut measure = Self {
+            segments: Vec::new(),
+            subpaths: Vec::new(),
+            length: 0.0,
+        };
+        for event in path.iter().flattened(tolerance) {
+            match event {
+                PathEvent::Begin { .. } => measure.subpaths.push(Subpath {
+                    start: measure.length,
+                    end: measure.length,
+                    closed: false,
+                }),
+                PathEvent::Line { from, to } => measure.push_segment(from, to),
+                PathEvent::End { last, first, close } => {
+                    if close {
+                        measure.push_segment(last, first);
+                    }
+                    if let Some(subpath) = measure.subpaths.last_mut() {
+                        subpath.closed = close;
+                    }
+                }
+                // Flattening leaves only lines
+                PathEvent::Quadratic { .. } | PathEvent::Cubic { .. } => {}
+            }
+        }
+        measure
+    }
+
+    /// Native counterpart of `segment`. A range covering a whole closed
+    /// subpath stays closed.
+    pub fn segment_native(&self, start: f32, end: f32) -> Path {
+        let mut builder = Path::builder();
+        self.append_range(&mut builder, start, end);
+        builder.build()
+    }
+
+    /// Split the path into dashes following an SVG `stroke-dasharray`,
+    /// restarting the pattern at each subpath. An odd-length pattern is
+    /// repeated to make it even, and a pattern with a negative or no total
+    /// length returns the whole path. A zero-length dash is a closed
+    /// subpath of one point, which strokes as a dot under round or square
+    /// caps and as nothing under butt caps.
+    pub fn dash(&self, pattern: &[f32], offset: f32) -> Path {
+        let total: f32 = pattern.iter().sum();
+        if pattern.iter().any(|&value| value < 0.0) || total <= 0.0 || !total.is_finite() {
+            return self.segment_native(0.0, self.length);
+        }
+        let pattern: Vec<f32> = if pattern.len() % 2 == 1 {
+            pattern.iter().chain(pattern).copied().collect()
+        } else {
+            pattern.to_vec()
+        };
+        let phase = offset.rem_euclid(pattern.iter().sum());
+
+        let mut builder = Path::builder();
+        for subpath in &self.subpaths {
+            // Find where the offset falls in the pattern
+            let mut index = 0;
+            let mut remaining = pattern[0];
+            let mut phase = phase;
+            // A zero-length dash at the very start is drawn, not passed
+            while phase > 0.0 && phase >= remaining {
+                phase -= remaining;
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+            remaining -= phase;
+
+            let mut position = subpath.start;
+            while position < subpath.end {
+                let dash_end = (position + remaining).min(subpath.end);
+                if index % 2 == 0 && dash_end > position {
+                    self.append_range(&mut builder, position, dash_end);
+                } else if index % 2 == 0 {
+                    self.append_dot(&mut builder, position);
+                }
+                position = dash_end;
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+            if index % 2 == 0 && remaining == 0.0 && subpath.end > subpath.start {
+                self.append_dot(&mut builder, subpath.end);
+            }
+        }
+        builder.build()
+    }
+
+    fn push_segment(&mut self, from: Point, to: Point) {
+        let length = (to - from).length();
+        if DEGENERATE_LENGTH.is_zero(length) || !length.is_finite() {
+            return;
+        }
+        self.segments.push(Segment {
+            from,
+            to,
+            start: self.length,
+            length,
+            subpath: self.subpaths.len().saturating_sub(1),
+        });
+        self.length += length;
+        if let Some(subpath) = self.subpaths.last_mut() {
+            subpath.end = self.length;
+        }
+    }
+
+    /// Index of the segment containing `distance`, clamped to the ends.
+    fn segment_index(&self, distance: f32) -> Option<usize> {
+        let last = self.segments.len().checked_sub(1)?;
+        Some(
+            self.segments
+                .partition_point(|segment| segment.start + segment.length < distance)
+                .min(last),
+        )
+    }
+
+    fn point_at(&self, index: usize, distance: f32) -> Point {
+        let segment = &self.segments[index];
+        let t = ((distance - segment.start) / segment.length).clamp(0.0, 1.0);
+        segment.from.lerp(segment.to, t)
+    }
+
+    /// Append a closed subpath of the one point `distance` along the path.
+    /// As with ranges, a distance where one subpath ends and the next
+    /// begins belongs to the next.
+    fn append_dot(&self, builder: &mut lyon::path::path::Builder, distance: f32) {
+        let Some(last) = self.segments.len().checked_sub(1) else {
+            return;
+        };
+        let index = self
+            .segments
+            .partition_point(|segment| segment.start + segment.length <= distance)
+            .min(last);
+        builder.begin(self.point_at(index, distance));
+        builder.end(true);
+    }
+
+    /// Append the path between two distances, starting a new subpath
+    /// wherever the source path does.
+    fn append_range(&self, builder: &mut lyon::path::path::Builder, start: f32, end: f32) {
+        let start = start.max(0.0);
+        let end = end.min(self.length);
+        let Some(last) = self.segments.len().checked_sub(1) else {
+            return;
+        };
+        if end <= start {
+            return;
+        }
+        // Unlike sampling, a range starting where one subpath ends and the
+        // next begins belongs to the next
+        let first = self
+            .segments
+            .partition_point(|segment| segment.start + segment.length <= start)
+            .min(last);
+
+        let covers = |subpath: usize| {
+            let whole = &self.subpaths[subpath];
+            whole.closed && start <= whole.start && end >= whole.end
+        };
+        let mut subpath = self.segments[first].subpath;
+        builder.begin(self.point_at(first, start));
+        for index in first..self.segments.len() {
+            let segment = &self.segments[index];
+            if segment.start >= end {
+                break;
+            }
+            if segment.subpath != subpath {
+                builder.end(covers(subpath));
+                subpath = segment.subpath;
+                builder.begin(segment.from);
+            }
+            builder.line_to(self.point_at(index, end.min(segment.start + segment.length)));
+        }
+        builder.end(covers(subpath));
+    }
+}
+
+/// Write a path of lines as a `d` attribute.
+fn path_to_d(path: &Path) -> String {
+    let mut d = String::new();
+    for event in path.iter() {
+        match event {
+            PathEvent::Begin { at } => {
+                let _ = write!(d, "M{} {} ", at.x, at.y);
+            }
+            PathEvent::Line { to, .. } => {
+                let _ = write!(d, "L{} {} ", to.x, to.y);
+            }
+            PathEvent::End { close: true, .. } => d.push_str("Z "),
+            _ => {}
+        }
+    }
+    d.truncate(d.trim_end().len());
+    d
+}
@@ -0,0 +1,102 @@
+//! Shape `transform` attributes, and the paths a host animates.
+//!
+//! A static path has its transform baked into the mesh, so it batches with
+//! everything else. A dynamic path is tessellated in its own space and its
+//! mesh carries the matrix instead, for the renderer to apply on the GPU:
+//! a spinner rotating one path updates the matrix each frame rather than
+//! re-tessellating.
+//!
+//! A shape's `transform` is composed with those of its groups while the
+//! document is read; see `inherit`.
+
+use lyon::math::Transform;
+use serde::{Deserialize, Serialize};
+
+/// A path of a document, by index into `ParsedSvg::paths` or by `id`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PathRef {
+    Index(usize),
+    Id(String),
+}
+
+/// A path to tessellate untransformed, for `TessellateOptions::dynamic_paths`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DynamicPath {
+    pub path: PathRef,
+    /// Initial transform as `[a, b, c, d, e, f]` in document units, used
+    /// instead of the path's `transform` attribute.
+    #[serde(default)]
+    pub transform: Option<[f32; 6]>,
+}
+
+impl DynamicPath {
+    /// Whether this entry selects the path at `index` with `id`.
+    pub(crate) fn selects(&self, index: usize, id: Option<&str>) -> bool {
+        match &self.path {
+            PathRef::Index(selected) => *selected == index,
+            PathRef::Id(selected) => id == Some(selected.as_str()),
+        }
+    }
+}
+
+pub(crate) const IDENTITY: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// `matrix` moved from document units to a display scaled by `scale_x`
+/// and `scale_y`: the matrix that takes a path tessellated at that scale to
+/// where the transformed path would be tessellated.
+pub(crate) fn display_matrix(matrix: [f32; 6], scale_x: f32, scale_y: f32) -> [f32; 6] {
+    let [a, b, c, d, e, f] = matrix;
+    // Scale^-1, then the matrix, then scale. A collapsed axis has nothing
+    // to draw, so the ratio there is arbitrary.
+    let (x_per_y, y_per_x) = if scale_x != 0.0 && scale_y != 0.0 {
+        (scale_x / scale_y, scale_y / scale_x)
+    } else {
+        (1.0, 1.0)
+    };
+    [a, b * y_per_x, c * x_per_y, d, e * scale_x, f * scale_y]
+}
+
+pub(crate) fn to_lyon(matrix: [f32; 6]) -> Transform {
+    let [a, b, c, d, e, f] = matrix;
+    Transform::new(a, b, c, d, e, f)
+}
+
+/// Parse a `transform` attribute: a list of `matrix`, `translate`,
+/// `scale`, `rotate`, `skewX` and `skewY`, applied right to left. None when
+/// any item is malformed or not finite, which SVG treats as no transform.
+pub(crate) fn parse_transform(value: &str) -> Option<[f32; 6]> {
+    let mut total = Transform::identity();
+    let mut rest = value.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    while !rest.is_empty() {
+        let open = rest.find('(')?;
+        let close = open + rest[open..].find(')')?;
+        let name = rest[..open].trim();
+        let args: Vec<f32> = rest[open + 1..close]
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| arg.parse().ok().filter(|value: &f32| value.is_finite()))
+            .collect::<Option<_>>()?;
+        total = transform_item(name, &args)?.then(&total);
+        rest = rest[close + 1..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    Some(total.to_array())
+}
+
+fn transform_item(name: &str, args: &[f32]) -> Option<Transform> {
+    let transform = match (name, args) {
+        ("matrix", &[a, b, c, d, e, f]) => Transform::new(a, b, c, d, e, f),
+        ("translate", &[x]) => Transform::translation(x, 0.0),
+        ("translate", &[x, y]) => Transform::translation(x, y),
+        ("scale", &[s]) => Transform::scale(s, s),
+        ("scale", &[x, y]) => Transform::scale(x, y),
+        ("rotate", &[angle]) => Transform::rotation(lyon::math::Angle::degrees(angle)),
+        ("rotate", &[angle, cx, cy]) => Transform::translation(-cx, -cy)
+            .then_rotate(lyon::math::Angle::degrees(angle))
+            .then_translate(lyon::math::vector(cx, cy)),
+        ("skewX", &[angle]) => Transform::new(1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0),
+        ("skewY", &[angle]) => Transform::new(1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0),
+        _ => return None,
+    };
+    Some(transform)
+}
@@ -0,0 +1,206 @@
+//! `<pattern>` fills, tiled by the renderer instead of the tessellator.
+//!
+//! A path filled with `url(#id)` comes back as its fill mesh, to clip with,
+//! the pattern's tile tessellated once, and the grid of positions the tile
+//! repeats at over the path's bounds. A renderer instances the tile across
+//! the grid and clips it to the fill, so hatching a large chart area costs
+//! one small mesh rather than thousands of repeated shapes.
+//!
+//! `x`, `y`, `width`, `height`, `patternUnits` and `patternContentUnits` are
+//! read; `viewBox`, `patternTransform` and `href` are not. Tiles are laid
+//! out on the display's axes, so a path's own rotation or skew moves its
+//! clip but not its pattern.
+
+#[cfg(feature = "patterns")]
+use crate::{extract_attr, DocumentPlacement, MeshBounds, ParsedSvg, PreserveAspectRatio};
+use crate::{PaintedMesh, ParsedPath, TessellatedMesh};
+use serde::{Deserialize, Serialize};
+
+/// Coordinate system of a pattern's tile or content, as SVG `patternUnits`
+/// and `patternContentUnits`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatternUnits {
+    /// Fractions of the filled path's bounding box
+    #[default]
+    ObjectBoundingBox,
+    /// The document's own coordinates
+    UserSpaceOnUse,
+}
+
+#[cfg(feature = "patterns")]
+impl PatternUnits {
+    fn from_attr(value: Option<&str>, default: Self) -> Self {
+        match value.map(str::trim) {
+            Some("userSpaceOnUse") => Self::UserSpaceOnUse,
+            Some("objectBoundingBox") => Self::ObjectBoundingBox,
+            _ => default,
+        }
+    }
+}
+
+/// A `<pattern>` definition. Its shapes are not paths of the document.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SvgPattern {
+    pub id: String,
+    /// The tile's rectangle, in `pattern_units`
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub pattern_units: PatternUnits,
+    pub pattern_content_units: PatternUnits,
+    /// The tile's shapes in paint order, in `pattern_content_units` from the
+    /// tile's top left corner
+    pub paths: Vec<ParsedPath>,
+}
+
+/// Where a tile repeats, in display pixels: `columns` by `rows` copies, the
+/// first with its top left corner at the origin, each `step_x` right of and
+/// `step_y` below the one before. The copies exactly cover the filled path's
+/// bounds: dropping the last row or column would leave part uncovered.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TileGrid {
+    pub origin_x: f32,
+    pub origin_y: f32,
+    pub step_x: f32,
+    pub step_y: f32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+/// A path filled with a pattern, from `TessellateOptions::pattern_fills`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PatternFill {
+    /// Index into `ParsedSvg::paths`
+    pub path: usize,
+    /// Id of the pattern in `ParsedSvg::patterns`
+    pub pattern: String,
+    /// The path's fill, which the tiles show through. It keeps the fill's
+    /// `paint_order`.
+    pub clip: TessellatedMesh,
+    /// The tile's meshes in display pixels from the tile's top left corner,
+    /// with `path` indexing the pattern's paths. Shapes may reach past the
+    /// tile, where SVG clips them to it.
+    pub tile: Vec<PaintedMesh>,
+    pub grid: TileGrid,
+}
+
+/// A pattern resolved against one filled path: the grid, and the scale its
+/// content is tessellated at.
+#[cfg(feature = "patterns")]
+pub(crate) struct TileLayout {
+    pub grid: TileGrid,
+    pub content_scale_x: f32,
+    pub content_scale_y: f32,
+}
+
+#[cfg(feature = "patterns")]
+impl SvgPattern {
+    /// Lay the tile out over `bounds`, the filled path's bounds in display
+    /// pixels, for a document drawn at `placement`.
+    pub(crate) fn layout(&self, bounds: &MeshBounds, placement: DocumentPlacement) -> TileLayout {
+        let box_width = bounds.max_x - bounds.min_x;
+        let box_height = bounds.max_y - bounds.min_y;
+        let DocumentPlacement {
+            scale_x,
+            scale_y,
+            offset_x,
+            offset_y,
+        } = placement;
+        let (unit_x, unit_y, base_x, base_y) = match self.pattern_units {
+            PatternUnits::ObjectBoundingBox => (box_width, box_height, bounds.min_x, bounds.min_y),
+            PatternUnits::UserSpaceOnUse => (scale_x, scale_y, offset_x, offset_y),
+        };
+        let (content_scale_x, content_scale_y) = match self.pattern_content_units {
+            PatternUnits::ObjectBoundingBox => (box_width, box_height),
+            PatternUnits::UserSpaceOnUse => (scale_x, scale_y),
+        };
+        let (origin_x, step_x, columns) = cover(
+            base_x + self.x * unit_x,
+            self.width * unit_x,
+            bounds.min_x,
+            bounds.max_x,
+        );
+        let (origin_y, step_y, rows) = cover(
+            base_y + self.y * unit_y,
+            self.height * unit_y,
+            bounds.min_y,
+            bounds.max_y,
+        );
+        TileLayout {
+            grid: TileGrid {
+                origin_x,
+                origin_y,
+                step_x,
+                step_y,
+                columns,
+                rows,
+            },
+            content_scale_x,
+            content_scale_y,
+        }
+    }
+
+    /// The tile's shapes as a document one unit square, for tessellating at
+    /// the content scale.
+    pub(crate) fn content(&self) -> ParsedSvg {
+        ParsedSvg {
+            width: 1.0,
+            height: 1.0,
+            view_box: None,
+            // Tiles are drawn at their content scale on each axis
+            preserve_aspect_ratio: PreserveAspectRatio::STRETCH,
+            paths: self.paths.clone(),
+            warnings: Vec::new(),
+            document_warnings: Vec::new(),
+            accessibility: Default::default(),
+            patterns: Vec::new(),
+            gradients: Vec::new(),
+        }
+    }
+}
+
+/// The first position and count of tiles `step` apart, repeating from
+/// `anchor`, that cover `min..max` along one axis. The count is zero when
+/// the tile is empty, which disables the fill in SVG.
+#[cfg(feature = "patterns")]
+fn cover(anchor: f32, step: f32, min: f32, max: f32) -> (f32, f32, u32) {
+    if !(step > 0.0 && step.is_finite() && anchor.is_finite() && min <= max) {
+        return (anchor, step, 0);
+    }
+    let first = ((min - anchor) / step).floor();
+    let origin = anchor + first * step;
+    // At least one tile, so a path with no extent on this axis is still
+    // covered where it lies
+    let count = ((max - origin) / step).ceil().max(1.0);
+    (origin, step, count as u32)
+}
+
+/// The pattern with start tag `open` and the shapes read inside it, or None
+/// when it has no id to be referenced by.
+#[cfg(feature = "patterns")]
+pub(crate) fn read_pattern(open: &str, paths: Vec<ParsedPath>) -> Option<SvgPattern> {
+    let number = |name| {
+        extract_attr(open, name)
+            .and_then(|value| value.trim().parse().ok())
+            .filter(|value: &f32| value.is_finite())
+            .unwrap_or(0.0)
+    };
+    Some(SvgPattern {
+        id: extract_attr(open, "id")?,
+        x: number("x"),
+        y: number("y"),
+        width: number("width"),
+        height: number("height"),
+        pattern_units: PatternUnits::from_attr(
+            extract_attr(open, "patternUnits").as_deref(),
+            PatternUnits::ObjectBoundingBox,
+        ),
+        pattern_content_units: PatternUnits::from_attr(
+            extract_attr(open, "patternContentUnits").as_deref(),
+            PatternUnits::UserSpaceOnUse,
+        ),
+        paths,
+    })
+}
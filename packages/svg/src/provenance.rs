@@ -0,0 +1,169 @@
+//! Which parsed path command each tessellated vertex came from.
+//!
+//! `SourcedBuilder` notes the command behind every endpoint as a path is
+//! built, and `SourcedVertex` looks up the endpoint or edge lyon reports as
+//! each vertex's source. An edge belongs to the command that drew to its
+//! end, except the edge closing a subpath, which belongs to its closepath.
+
+use crate::TessVertex;
+use lyon::math::Point;
+use lyon::path::builder::{Build, PathBuilder};
+use lyon::path::path::BuilderImpl;
+use lyon::path::{Attributes, EndpointId, Path};
+use lyon::tessellation::{
+    FillVertex, FillVertexConstructor, StrokeVertex, StrokeVertexConstructor, VertexSource,
+};
+use std::collections::HashMap;
+
+/// A path builder told which command it is building before each one.
+pub(crate) trait CommandTracker {
+    fn start_command(&mut self, index: usize);
+}
+
+impl CommandTracker for BuilderImpl {
+    fn start_command(&mut self, _index: usize) {}
+}
+
+/// The command behind each endpoint of a built path.
+pub(crate) struct PathSources {
+    /// Command index per point id; control points share their curve's
+    commands: Vec<u32>,
+    /// Closepath command of each closed subpath, by its first endpoint
+    closes: HashMap<EndpointId, u32>,
+}
+
+impl PathSources {
+    fn command(&self, source: VertexSource) -> u32 {
+        match source {
+            VertexSource::Endpoint { id } => self.commands[id.to_usize()],
+            // Edges run forward through the path except the closing one
+            VertexSource::Edge { from, to, .. } if to.0 < from.0 => self
+                .closes
+                .get(&to)
+                .copied()
+                .unwrap_or(self.commands[from.to_usize()]),
+            VertexSource::Edge { to, .. } => self.commands[to.to_usize()],
+        }
+    }
+}
+
+/// Builds a path while recording `PathSources`.
+pub(crate) struct SourcedBuilder {
+    inner: BuilderImpl,
+    command: u32,
+    sources: PathSources,
+    subpath_start: EndpointId,
+}
+
+impl SourcedBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: BuilderImpl::new(),
+            command: 0,
+            sources: PathSources {
+                commands: Vec::new(),
+                closes: HashMap::new(),
+            },
+            subpath_start: EndpointId::INVALID,
+        }
+    }
+
+    pub(crate) fn build(self) -> (Path, PathSources) {
+        (self.inner.build(), self.sources)
+    }
+
+    fn note(&mut self, id: EndpointId) -> EndpointId {
+        let commands = &mut self.sources.commands;
+        commands.resize(id.to_usize() + 1, self.command);
+        id
+    }
+}
+
+impl CommandTracker for SourcedBuilder {
+    fn start_command(&mut self, index: usize) {
+        self.command = index as u32;
+    }
+}
+
+impl PathBuilder for SourcedBuilder {
+    fn num_attributes(&self) -> usize {
+        0
+    }
+
+    fn begin(&mut self, at: Point, custom_attributes: Attributes) -> EndpointId {
+        let id = self.inner.begin(at, custom_attributes);
+        self.subpath_start = id;
+        self.note(id)
+    }
+
+    fn end(&mut self, close: bool) {
+        if close {
+            self.sources.closes.insert(self.subpath_start, self.command);
+        }
+        self.inner.end(close);
+    }
+
+    fn line_to(&mut self, to: Point, custom_attributes: Attributes) -> EndpointId {
+        let id = self.inner.line_to(to, custom_attributes);
+        self.note(id)
+    }
+
+    fn quadratic_bezier_to(
+        &mut self,
+        ctrl: Point,
+        to: Point,
+        custom_attributes: Attributes,
+    ) -> EndpointId {
+        let id = self.inner.quadratic_bezier_to(ctrl, to, custom_attributes);
+        self.note(id)
+    }
+
+    fn cubic_bezier_to(
+        &mut self,
+        ctrl1: Point,
+        ctrl2: Point,
+        to: Point,
+        custom_attributes: Attributes,
+    ) -> EndpointId {
+        let id = self
+            .inner
+            .cubic_bezier_to(ctrl1, ctrl2, to, custom_attributes);
+        self.note(id)
+    }
+}
+
+/// Vertex constructor that records each vertex's source command, in
+/// output order.
+pub(crate) struct SourcedVertex<'a> {
+    pub(crate) sources: &'a PathSources,
+    pub(crate) commands: &'a mut Vec<u32>,
+}
+
+impl FillVertexConstructor<TessVertex> for SourcedVertex<'_> {
+    fn new_vertex(&mut self, vertex: FillVertex) -> TessVertex {
+        // A vertex at an intersection has several sources; take the first
+        let command = vertex
+            .sources()
+            .next()
+            .map_or(0, |source| self.sources.command(source));
+        self.commands.push(command);
+        let pos = vertex.position();
+        TessVertex {
+            x: pos.x,
+            y: pos.y,
+            edge_dist: 1.0,
+        }
+    }
+}
+
+impl StrokeVertexConstructor<TessVertex> for SourcedVertex<'_> {
+    fn new_vertex(&mut self, vertex: StrokeVertex) -> TessVertex {
+        self.commands.push(self.sources.command(vertex.source()));
+        let pos = vertex.position();
+        TessVertex {
+            x: pos.x,
+            y: pos.y,
+            edge_dist: 1.0,
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Per-vertex radial coordinates for paths filled with a radial gradient.
+//!
+//! Interpolating bounding-box UVs across a large triangle bends the rings of
+//! a radial gradient into polygons. Instead each vertex carries its exact
+//! distance from the gradient center, and edges are split where linearly
+//! interpolating that distance would miss by more than a tolerance. The
+//! distance curves most near the center, so that is where triangles shrink.
+//! Splits are decided per edge, so neighbouring triangles agree and the
+//! refined mesh has no T-junctions.
+
+use crate::{GradientUnits, MeshBounds, TessellatedMesh};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Most rounds of edge splitting, each halving the edges it splits.
+const MAX_REFINE_ROUNDS: usize = 8;
+
+/// A radial gradient's geometry, with SVG `<radialGradient>` defaults.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RadialGradientInput {
+    pub cx: f32,
+    pub cy: f32,
+    pub r: f32,
+    /// Focal point; defaults to the center
+    pub fx: Option<f32>,
+    pub fy: Option<f32>,
+    pub units: GradientUnits,
+    /// Split edges until interpolating the distance along them errs by at
+    /// most this much, in gradient units. No refinement when None.
+    pub refine_tolerance: Option<f32>,
+}
+
+impl Default for RadialGradientInput {
+    fn default() -> Self {
+        Self {
+            cx: 0.5,
+            cy: 0.5,
+            r: 0.5,
+            fx: None,
+            fy: None,
+            units: GradientUnits::ObjectBoundingBox,
+            refine_tolerance: None,
+        }
+    }
+}
+
+/// Gradient parameters resolved against the filled path, so a renderer can
+/// shade the mesh without parsing the gradient again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedRadialGradient {
+    pub cx: f32,
+    pub cy: f32,
+    pub r: f32,
+    pub fx: f32,
+    pub fy: f32,
+    pub units: GradientUnits,
+    /// Maps mesh coordinates to gradient units, as `[a, b, c, d, e, f]`
+    /// like SVG's `matrix()`. The identity for `userSpaceOnUse`.
+    pub to_gradient: [f32; 6],
+}
+
+impl ResolvedRadialGradient {
+    pub(crate) fn new(input: &RadialGradientInput, bounds: &MeshBounds) -> Self {
+        let to_gradient = match input.units {
+            GradientUnits::UserSpaceOnUse => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            GradientUnits::ObjectBoundingBox => {
+                // A degenerate box collapses to its origin instead of
+                // dividing by zero
+                let scale = |size: f32| if size > 0.0 { size.recip() } else { 0.0 };
+                let sx = scale(bounds.max_x - bounds.min_x);
+                let sy = scale(bounds.max_y - bounds.min_y);
+                [sx, 0.0, 0.0, sy, -bounds.min_x * sx, -bounds.min_y * sy]
+            }
+        };
+        Self {
+            cx: input.cx,
+            cy: input.cy,
+            r: input.r,
+            fx: input.fx.unwrap_or(input.cx),
+            fy: input.fy.unwrap_or(input.cy),
+            units: input.units,
+            to_gradient,
+        }
+    }
+
+    /// Distance of mesh point `(x, y)` from the center, in gradient units.
+    pub fn distance(&self, x: f32, y: f32) -> f32 {
+        let [a, b, c, d, e, f] = self.to_gradient;
+        let u = a * x + c * y + e;
+        let v = b * x + d * y + f;
+        (u - self.cx).hypot(v - self.cy)
+    }
+}
+
+/// A filled path with a radial coordinate per vertex.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RadialGradientMesh {
+    pub mesh: TessellatedMesh,
+    /// Distance of each vertex from the gradient center in gradient units,
+    /// parallel to the mesh's vertices
+    pub radial_distances: Vec<f32>,
+    pub gradient: ResolvedRadialGradient,
+}
+
+impl RadialGradientMesh {
+    pub(crate) fn new(mut mesh: TessellatedMesh, input: &RadialGradientInput) -> Self {
+        let gradient = ResolvedRadialGradient::new(input, &mesh.bounds);
+        if let Some(tolerance) = input.refine_tolerance.filter(|tolerance| *tolerance > 0.0) {
+            refine(&mut mesh, &gradient, tolerance);
+        }
+        let radial_distances = mesh
+            .vertices
+            .chunks_exact(3)
+            .map(|vertex| gradient.distance(vertex[0], vertex[1]))
+            .collect();
+        Self {
+            mesh,
+            radial_distances,
+            gradient,
+        }
+    }
+}
+
+/// Split edges whose midpoint distance differs from the average of their
+/// ends by more than `tolerance`, until none do or the rounds run out.
+fn refine(mesh: &mut TessellatedMesh, gradient: &ResolvedRadialGradient, tolerance: f32) {
+    for _ in 0..MAX_REFINE_ROUNDS {
+        let midpoints = split_edges(&mut mesh.vertices, &mesh.indices, gradient, tolerance);
+        if midpoints.is_empty() {
+            return;
+        }
+        let mut indices = Vec::with_capacity(mesh.indices.len() * 2);
+        for triangle in mesh.indices.chunks_exact(3) {
+            split_triangle(triangle, &midpoints, &mut indices);
+        }
+        mesh.indices = indices;
+    }
+}
+
+/// Add a midpoint vertex for every edge that needs one, keyed by the
+/// edge's sorted vertex pair.
+fn split_edges(
+    vertices: &mut Vec<f32>,
+    indices: &[u32],
+    gradient: &ResolvedRadialGradient,
+    tolerance: f32,
+) -> HashMap<(u32, u32), u32> {
+    let mut midpoints = HashMap::new();
+    let mut checked = HashSet::new();
+    for triangle in indices.chunks_exact(3) {
+        for (from, to) in [
+            (triangle[0], triangle[1]),
+            (triangle[1], triangle[2]),
+            (triangle[2], triangle[0]),
+        ] {
+            let key = (from.min(to), from.max(to));
+            if !checked.insert(key) {
+                continue;
+            }
+            let (a, b) = (key.0 as usize * 3, key.1 as usize * 3);
+            let mid_x = (vertices[a] + vertices[b]) * 0.5;
+            let mid_y = (vertices[a + 1] + vertices[b + 1]) * 0.5;
+            let interpolated = (gradient.distance(vertices[a], vertices[a + 1])
+                + gradient.distance(vertices[b], vertices[b + 1]))
+                * 0.5;
+            if (interpolated - gradient.distance(mid_x, mid_y)).abs() > tolerance {
+                midpoints.insert(key, (vertices.len() / 3) as u32);
+                vertices.extend([mid_x, mid_y, 1.0]);
+            }
+        }
+    }
+    midpoints
+}
+
+/// Replace a triangle by the triangles its split edges divide it into,
+/// keeping its winding.
+fn split_triangle(triangle: &[u32], midpoints: &HashMap<(u32, u32), u32>, indices: &mut Vec<u32>) {
+    let mid = |from: u32, to: u32| midpoints.get(&(from.min(to), from.max(to))).copied();
+    let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+    match (mid(a, b), mid(b, c), mid(c, a)) {
+        (None, None, None) => indices.extend([a, b, c]),
+        (Some(ab), Some(bc), Some(ca)) => {
+            indices.extend([a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca]);
+        }
+        (ab, bc, ca) => {
+            // Fan from a midpoint: no other ring vertex shares its edge's
+            // line with both of a pair, so no triangle is degenerate
+            let ring: Vec<u32> = [Some(a), ab, Some(b), bc, Some(c), ca]
+                .into_iter()
+                .flatten()
+                .collect();
+            let pivot = ring
+                .iter()
+                .position(|&vertex| vertex != a && vertex != b && vertex != c)
+                .unwrap_or(0);
+            for step in 1..ring.len() - 1 {
+                let from = ring[(pivot + step) % ring.len()];
+                let to = ring[(pivot + step + 1) % ring.len()];
+                indices.extend([ring[pivot], from, to]);
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Scene bundles: many named SVGs pre-tessellated at several sizes into one
+//! binary, so a runtime loads a whole icon set with a single call.
+//!
+//! A bundle is an index of entries followed by one mesh asset blob in the
+//! format of `mesh_format`, holding every entry's meshes back to back. All
+//! values are little-endian and every section is a multiple of four bytes.
+//!
+//! ```text
+//! file header   magic "GLSC"      [u8; 4]
+//!               version           u16
+//!               header length     u16   bytes from the start of the file
+//!               entry count       u32
+//!               meshes offset     u32   start of the mesh blob
+//! per entry     record length     u16   bytes in this entry header
+//!               flags             u16   none defined yet
+//!               name length       u32   bytes of UTF-8
+//!               display width     f32
+//!               display height    f32
+//!               first mesh        u32   index into the mesh blob
+//!               mesh count        u32
+//!               name              [u8; name length], zero-padded to four
+//! mesh blob     see `mesh_format`
+//! ```
+//!
+//! Compatibility follows `mesh_format`: fields are only appended to
+//! headers, readers honor the stored lengths, and the version changes only
+//! when existing fields change meaning.
+
+use crate::mesh_format::Reader;
+use crate::{decode_meshes, DisplaySize, MeshAsset, MeshFormatError, TessellateOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+pub const SCENE_MAGIC: [u8; 4] = *b"GLSC";
+pub const SCENE_FORMAT_VERSION: u16 = 1;
+
+const FILE_HEADER_LEN: u16 = 16;
+const ENTRY_HEADER_LEN: u16 = 24;
+
+/// Theme colors for `compile_scene`, keyed by paint value as written, such
+/// as `currentColor` or `var(--accent)`. Paints not listed resolve as usual,
+/// `currentColor` to black.
+pub type ColorMap = HashMap<String, [u8; 4]>;
+
+/// Options for `compile_scene`.
+#[derive(Clone, Debug, Default)]
+pub struct SceneOptions {
+    /// Display sizes every input is tessellated at.
+    pub sizes: Vec<DisplaySize>,
+    pub tessellate: TessellateOptions,
+}
+
+/// One input of a scene at one display size.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneEntry {
+    pub name: String,
+    pub display_width: f32,
+    pub display_height: f32,
+    pub meshes: Vec<MeshAsset>,
+}
+
+/// A loaded scene bundle, from `load_scene`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scene {
+    entries: Vec<SceneEntry>,
+}
+
+impl Scene {
+    /// Decode a bundle written by `compile_scene`. Never panics on malformed
+    /// input. Errors inside the mesh blob report offsets from its start.
+    pub fn decode(bytes: &[u8]) -> Result<Self, MeshFormatError> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(SCENE_MAGIC.len())? != SCENE_MAGIC {
+            return Err(MeshFormatError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version == 0 || version > SCENE_FORMAT_VERSION {
+            return Err(MeshFormatError::UnsupportedVersion(version));
+        }
+        let header_len = reader.u16()? as usize;
+        if header_len < FILE_HEADER_LEN as usize {
+            return Err(MeshFormatError::HeaderTooShort {
+                offset: 0,
+                length: header_len,
+            });
+        }
+        let entry_count = reader.u32()? as usize;
+        let meshes_offset = reader.u32()? as usize;
+        reader.skip_to(header_len)?;
+
+        let mut index =
+            Vec::with_capacity(entry_count.min(bytes.len() / ENTRY_HEADER_LEN as usize));
+        for _ in 0..entry_count {
+            let header_start = reader.offset();
+            let record_len = reader.u16()? as usize;
+            if record_len < ENTRY_HEADER_LEN as usize {
+                return Err(MeshFormatError::HeaderTooShort {
+                    offset: header_start,
+                    length: record_len,
+                });
+            }
+            let _flags = reader.u16()?;
+            let name_len = reader.u32()? as usize;
+            let display_width = reader.f32()?;
+            let display_height = reader.f32()?;
+            let first_mesh = reader.u32()? as usize;
+            let mesh_count = reader.u32()? as usize;
+            reader.skip_to(header_start + record_len)?;
+
+            let name_start = reader.offset();
+            let name = std::str::from_utf8(reader.take(name_len)?)
+                .map_err(|_| MeshFormatError::InvalidName { offset: name_start })?
+                .to_string();
+            reader.take(padding(name_len))?;
+            index.push((name, display_width, display_height, first_mesh, mesh_count));
+        }
+
+        let blob = bytes
+            .get(meshes_offset..)
+            .ok_or(MeshFormatError::Truncated {
+                offset: meshes_offset,
+                needed: 0,
+            })?;
+        let mut meshes = decode_meshes(blob)?.into_iter();
+        let mut next_mesh = 0;
+        let mut entries = Vec::with_capacity(index.len());
+        for (entry, (name, display_width, display_height, first_mesh, mesh_count)) in
+            index.into_iter().enumerate()
+        {
+            // Entries are written in mesh order, so each range must start
+            // where the previous one ended
+            if first_mesh != next_mesh || mesh_count > meshes.len() {
+                return Err(MeshFormatError::MeshesOutOfRange { entry });
+            }
+            next_mesh += mesh_count;
+            entries.push(SceneEntry {
+                name,
+                display_width,
+                display_height,
+                meshes: meshes.by_ref().take(mesh_count).collect(),
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn entries(&self) -> &[SceneEntry] {
+        &self.entries
+    }
+
+    /// Native counterpart of `get_scene_mesh`.
+    pub fn entry(
+        &self,
+        name: &str,
+        display_width: f32,
+        display_height: f32,
+    ) -> Option<&SceneEntry> {
+        let mut sizes: Vec<&SceneEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.name == name)
+            .collect();
+        sizes.sort_by(|a, b| {
+            (a.display_width * a.display_height).total_cmp(&(b.display_width * b.display_height))
+        });
+        sizes
+            .iter()
+            .find(|entry| {
+                entry.display_width >= display_width && entry.display_height >= display_height
+            })
+            .or(sizes.last())
+            .copied()
+    }
+}
+
+/// Encode entries into one bundle, in order.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn encode_scene(entries: &[SceneEntry]) -> Vec<u8> {
+    let index_len: usize = entries
+        .iter()
+        .map(|entry| ENTRY_HEADER_LEN as usize + entry.name.len() + padding(entry.name.len()))
+        .sum();
+    let meshes_offset = FILE_HEADER_LEN as usize + index_len;
+    let mut out = Vec::with_capacity(meshes_offset);
+
+    out.extend_from_slice(&SCENE_MAGIC);
+    out.extend_from_slice(&SCENE_FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&FILE_HEADER_LEN.to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(meshes_offset as u32).to_le_bytes());
+
+    let mut first_mesh = 0;
+    for entry in entries {
+        out.extend_from_slice(&ENTRY_HEADER_LEN.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&(entry.name.len() as u32).to_le_bytes());
+        out.extend_from_slice(&entry.display_width.to_le_bytes());
+        out.extend_from_slice(&entry.display_height.to_le_bytes());
+        out.extend_from_slice(&(first_mesh as u32).to_le_bytes());
+        out.extend_from_slice(&(entry.meshes.len() as u32).to_le_bytes());
+        out.extend_from_slice(entry.name.as_bytes());
+        out.resize(out.len() + padding(entry.name.len()), 0);
+        first_mesh += entry.meshes.len();
+    }
+
+    let meshes: Vec<MeshAsset> = entries
+        .iter()
+        .flat_map(|entry| entry.meshes.iter().cloned())
+        .collect();
+    out.extend(crate::encode_meshes(&meshes));
+    out
+}
+
+/// Zero bytes that bring `len` up to a multiple of four.
+fn padding(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
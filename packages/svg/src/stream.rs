@@ -0,0 +1,549 @@
+//! Incremental parsing for documents too large to hold as one string.
+//!
+//! Bytes are split into tokens as they arrive: tags, text, CDATA sections
+//! and other markup such as comments. Each token is read as soon as it is
+//! complete and then dropped, so memory stays proportional to the longest
+//! token, usually one shape's start tag with its path data, rather than to
+//! the document. `parse_svg` reads its string the same way, in one chunk.
+
+use crate::accessibility::{DocumentLabels, PathAccessibility};
+use crate::conditional::RenderScopes;
+use crate::definitions::Definitions;
+#[cfg(feature = "gradients")]
+use crate::gradient::resolve_gradients;
+use crate::gradient::GradientSource;
+use crate::inherit::PaintScopes;
+use crate::length::{parse_length, Length};
+#[cfg(feature = "patterns")]
+use crate::pattern::read_pattern;
+use crate::style::StyleSheet;
+use crate::{
+    apply_parse_options, extract_attr, parse_options, read_shape, split_reference, ElementTags,
+    ParsedPath, ParsedSvg, PreserveAspectRatio, SvgAccessibility, ViewBox, SHAPE_TAGS,
+};
+use wasm_bindgen::prelude::*;
+
+/// A complete piece of the document.
+enum Token<'a> {
+    /// Character data between markup, with references still written out
+    Text(&'a str),
+    /// The contents of a CDATA section
+    Cdata(&'a str),
+    /// A start or end tag, from `<` to `>`
+    Tag(&'a str),
+    /// A comment, declaration or processing instruction
+    Other,
+}
+
+#[derive(Clone, Copy)]
+enum TokenKind {
+    Text,
+    Cdata,
+    Tag,
+    Other,
+}
+
+/// Splits bytes into tokens, holding back only the token still being
+/// written. Tokens end at ASCII delimiters, so a UTF-8 sequence split across
+/// chunks is whole again by the time its token is read.
+#[derive(Default)]
+struct Tokenizer {
+    buffer: Vec<u8>,
+    /// Bytes of the buffer already searched for the end of its first token
+    scanned: usize,
+    /// The quote open at `scanned`, inside a tag
+    quote: Option<u8>,
+}
+
+impl Tokenizer {
+    /// Append `chunk` and pass each token it completes to `read`. With
+    /// `finished`, an unfinished token left at the end is read as text.
+    fn push(&mut self, chunk: &[u8], finished: bool, mut read: impl FnMut(Token<'_>)) {
+        self.buffer.extend_from_slice(chunk);
+        let mut start = 0;
+        while start < self.buffer.len() {
+            let (end, kind) = match self.token_end(start) {
+                Some(token) => token,
+                None if finished => (self.buffer.len(), TokenKind::Text),
+                None => break,
+            };
+            let source = String::from_utf8_lossy(&self.buffer[start..end]);
+            read(match kind {
+                TokenKind::Text => Token::Text(&source),
+                TokenKind::Cdata => Token::Cdata(&source["<![CDATA[".len()..source.len() - 3]),
+                TokenKind::Tag => Token::Tag(&source),
+                TokenKind::Other => Token::Other,
+            });
+            start = end;
+            self.scanned = end;
+            self.quote = None;
+        }
+        self.buffer.drain(..start);
+        self.scanned -= start;
+    }
+
+    /// Where the token starting at `start` ends and what it is, or None
+    /// while it is unfinished.
+    fn token_end(&mut self, start: usize) -> Option<(usize, TokenKind)> {
+        let token = &self.buffer[start..];
+        let scanned = self.scanned - start;
+        // A `<` before whitespace cannot start a tag
+        let markup = token[0] == b'<' && !token.get(1).is_some_and(u8::is_ascii_whitespace);
+        let found = if !markup {
+            // Text runs to the next `<`
+            let from = scanned.max(1);
+            find(&token[from..], b"<").map(|at| (from + at, TokenKind::Text))
+        } else if let Some((open, close, kind)) = [
+            ("<!--", "-->", TokenKind::Other),
+            ("<![CDATA[", "]]>", TokenKind::Cdata),
+        ]
+        .into_iter()
+        .find(|(open, _, _)| token.starts_with(open.as_bytes()))
+        {
+            // The close may have begun in the part already searched
+            let from = scanned.saturating_sub(close.len() - 1).max(open.len());
+            find(&token[from..], close.as_bytes()).map(|at| (from + at + close.len(), kind))
+        } else if ["<!--", "<![CDATA["]
+            .iter()
+            .any(|open| open.as_bytes().starts_with(token))
+        {
+            // Too short to tell a comment or CDATA section from a declaration
+            return None;
+        } else if token[1] == b'?' {
+            // A processing instruction's pseudo-attributes may hold `>`
+            let from = scanned.saturating_sub(1).max(2);
+            find(&token[from..], b"?>").map(|at| (from + at + 2, TokenKind::Other))
+        } else if token[1] == b'!' {
+            declaration_end(token).map(|end| (end, TokenKind::Other))
+        } else {
+            tag_end(token, scanned.max(1), &mut self.quote).map(|end| (end, TokenKind::Tag))
+        };
+        match found {
+            Some((end, kind)) => Some((start + end, kind)),
+            None => {
+                self.scanned = self.buffer.len();
+                None
+            }
+        }
+    }
+}
+
+/// End of the tag `token` after its closing `>`, searching from `from`.
+/// `quote` is the quote open at `from`, and is kept up to date so a search
+/// that runs out of bytes can resume. Attribute values may hold `>`.
+fn tag_end(token: &[u8], from: usize, quote: &mut Option<u8>) -> Option<usize> {
+    for (index, &byte) in token.iter().enumerate().skip(from) {
+        match *quote {
+            Some(open) if byte == open => *quote = None,
+            Some(_) => {}
+            None if byte == b'"' || byte == b'\'' => *quote = Some(byte),
+            None if byte == b'>' => return Some(index + 1),
+            None => {}
+        }
+    }
+    None
+}
+
+/// End of the declaration `token`, such as a DOCTYPE, after its closing
+/// `>`. An internal subset in brackets, its quoted literals and its
+/// comments may hold `>`.
+fn declaration_end(token: &[u8]) -> Option<usize> {
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut index = 2;
+    while let Some(&byte) = token.get(index) {
+        match quote {
+            Some(open) if byte == open => quote = None,
+            Some(_) => {}
+            None if depth > 0 && token[index..].starts_with(b"<!--") => {
+                index += 4 + find(&token[index + 4..], b"-->")? + 2;
+            }
+            None if byte == b'"' || byte == b'\'' => quote = Some(byte),
+            None if byte == b'[' => depth += 1,
+            None if byte == b']' => depth = depth.saturating_sub(1),
+            None if byte == b'>' && depth == 0 => return Some(index + 1),
+            None => {}
+        }
+        index += 1;
+    }
+    None
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The element name at the start of a tag's source after `<` or `</`.
+fn element_name(tag: &str) -> &str {
+    tag.split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .next()
+        .unwrap_or("")
+}
+
+/// An element name without its namespace prefix.
+fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+/// Reads tokens into a document as they arrive.
+struct DocumentReader {
+    parsed: ParsedSvg,
+    tags: ElementTags,
+    labels: DocumentLabels,
+    scopes: RenderScopes,
+    paints: PaintScopes,
+    sheet: StyleSheet,
+    /// The text so far of the `style` element being read
+    style_text: Option<String>,
+    /// The `width`, `height`, `viewBox` and `preserveAspectRatio` of the
+    /// root `svg` element, as written, once it has been read
+    size: Option<[Option<String>; 4]>,
+    /// Inside a `foreignObject`, whose HTML may hold elements named like ours
+    in_foreign_object: bool,
+    foreign_objects: usize,
+    /// The start tag and shapes so far of the `pattern` being read. A
+    /// pattern's shapes draw only where the pattern is used.
+    pattern: Option<(String, Vec<ParsedPath>)>,
+    /// The `linearGradient` or `radialGradient` being read, whose `stop` children are its own
+    gradient: Option<GradientSource>,
+    gradients: Vec<GradientSource>,
+    definitions: Definitions,
+}
+
+impl DocumentReader {
+    fn new() -> Self {
+        Self {
+            parsed: ParsedSvg {
+                width: 24.0,
+                height: 24.0,
+                view_box: None,
+                preserve_aspect_ratio: PreserveAspectRatio::default(),
+                paths: Vec::new(),
+                warnings: Vec::new(),
+                document_warnings: Vec::new(),
+                accessibility: SvgAccessibility::default(),
+                patterns: Vec::new(),
+                gradients: Vec::new(),
+            },
+            tags: ElementTags::default(),
+            labels: DocumentLabels::default(),
+            scopes: RenderScopes::default(),
+            paints: PaintScopes::default(),
+            sheet: StyleSheet::default(),
+            style_text: None,
+            size: Default::default(),
+            in_foreign_object: false,
+            foreign_objects: 0,
+            pattern: None,
+            gradient: None,
+            gradients: Vec::new(),
+            definitions: Definitions::default(),
+        }
+    }
+
+    fn read(&mut self, token: Token<'_>) {
+        let in_document =
+            !self.in_foreign_object && self.pattern.is_none() && self.gradient.is_none();
+        match token {
+            Token::Text(text) | Token::Cdata(text) if self.style_text.is_some() => {
+                self.style_text
+                    .iter_mut()
+                    .for_each(|css| css.push_str(text));
+            }
+            Token::Text(text) if in_document => self.labels.text(text),
+            Token::Cdata(text) if in_document => self.labels.cdata(text),
+            Token::Tag(tag) => match tag.strip_prefix("</") {
+                Some(end) => self.end_tag(element_name(end)),
+                None => self.start_tag(element_name(&tag[1..]), tag),
+            },
+            _ => {}
+        }
+    }
+
+    fn start_tag(&mut self, name: &str, tag: &str) {
+        if self.in_foreign_object {
+            return;
+        }
+        if local_name(name) == "foreignObject" {
+            // Still a candidate child of a `switch`
+            if self.pattern.is_none() {
+                self.scopes.start_tag(tag, false, false);
+                self.paints.start_tag(tag, &self.sheet);
+            }
+            self.foreign_objects += 1;
+            self.in_foreign_object = !tag.ends_with("/>");
+            return;
+        }
+        self.tags.declare(tag);
+        let shape = SHAPE_TAGS
+            .into_iter()
+            .find(|shape| self.tags.is_svg_element(name, shape))
+            .and_then(|shape| read_shape(shape, tag, &self.sheet));
+        if let Some((_, paths)) = self.pattern.as_mut() {
+            paths.extend(shape);
+            return;
+        }
+        if self.tags.is_svg_element(name, "pattern") {
+            self.pattern = Some((tag.to_string(), Vec::new()));
+            if tag.ends_with("/>") {
+                self.end_pattern();
+            }
+            return;
+        }
+        if let Some(gradient) = self.gradient.as_mut() {
+            if self.tags.is_svg_element(name, "stop") {
+                gradient.stop(tag, &self.sheet);
+            }
+            return;
+        }
+        let radial = self.tags.is_svg_element(name, "radialGradient");
+        if radial || self.tags.is_svg_element(name, "linearGradient") {
+            let gradient = GradientSource::new(tag, radial);
+            if tag.ends_with("/>") {
+                self.gradients.push(gradient);
+            } else {
+                self.gradient = Some(gradient);
+            }
+            return;
+        }
+        if self.size.is_none() && self.tags.is_svg_element(name, "svg") {
+            self.size = Some(
+                ["width", "height", "viewBox", "preserveAspectRatio"]
+                    .map(|name| extract_attr(tag, name)),
+            );
+        }
+        self.labels.start_tag(name, tag, &self.tags);
+        let is_switch = self.tags.is_svg_element(name, "switch");
+        let is_use = self.tags.is_svg_element(name, "use");
+        if is_use {
+            self.definitions.link(tag);
+        }
+        let shape = shape.map(|mut path| {
+            self.definitions.shape(&path);
+            self.paints.inherit(&mut path);
+            path
+        });
+        let parent = is_use.then(|| self.paints.current());
+        self.paints.start_tag(tag, &self.sheet);
+        if self.tags.is_svg_element(name, "style") && !tag.ends_with("/>") {
+            self.style_text = Some(String::new());
+        }
+        let is_defs = self.tags.is_svg_element(name, "defs");
+        let Some(hidden) = self.scopes.start_tag(tag, is_switch, is_defs) else {
+            return;
+        };
+        if let Some(parent) = parent {
+            let index = self.parsed.paths.len();
+            self.definitions.draw(tag, parent, index, hidden);
+        }
+        if let Some(mut path) = shape {
+            path.hidden = hidden;
+            let index = self.parsed.paths.len();
+            self.parsed
+                .accessibility
+                .paths
+                .extend(PathAccessibility::read(tag, index));
+            self.parsed.paths.push(path);
+        }
+    }
+
+    fn end_tag(&mut self, name: &str) {
+        if self.in_foreign_object {
+            self.in_foreign_object = local_name(name) != "foreignObject";
+            if !self.in_foreign_object && self.pattern.is_none() {
+                self.scopes.end_tag();
+                self.paints.end_tag();
+            }
+        } else if self.pattern.is_some() {
+            if self.tags.is_svg_element(name, "pattern") {
+                self.end_pattern();
+            }
+        } else if self.gradient.is_some() {
+            if self.tags.is_svg_element(name, "linearGradient")
+                || self.tags.is_svg_element(name, "radialGradient")
+            {
+                self.gradients.extend(self.gradient.take());
+            }
+        } else {
+            if let Some(css) = self.style_text.take() {
+                self.sheet.read(&css);
+            }
+            self.labels.end_tag();
+            self.scopes.end_tag();
+            self.paints.end_tag();
+        }
+    }
+
+    /// Without the `patterns` feature a pattern's shapes are dropped, along
+    /// with the pattern.
+    fn end_pattern(&mut self) {
+        #[cfg(feature = "patterns")]
+        if let Some((open, paths)) = self.pattern.take() {
+            self.parsed.patterns.extend(read_pattern(&open, paths));
+        }
+        #[cfg(not(feature = "patterns"))]
+        self.pattern.take();
+    }
+
+    /// The document read, ending any element still open.
+    fn finish(mut self) -> ParsedSvg {
+        self.end_pattern();
+        self.gradients.extend(self.gradient.take());
+        let mut parsed = self.parsed;
+        self.definitions.expand(&mut parsed, &self.sheet);
+        parsed.read_colors();
+        // Without the `gradients` feature, gradients are dropped and the
+        // paths that use them draw black
+        #[cfg(feature = "gradients")]
+        {
+            parsed.gradients = resolve_gradients(&self.gradients);
+        }
+        if let Some(warning) = missing_references(&parsed) {
+            parsed.document_warnings.push(warning);
+        }
+        if self.foreign_objects > 0 {
+            parsed.document_warnings.push(format!(
+                "skipped the contents of {} foreignObject element(s)",
+                self.foreign_objects
+            ));
+        }
+        parsed.accessibility = SvgAccessibility {
+            paths: std::mem::take(&mut parsed.accessibility.paths),
+            ..self.labels.finish()
+        };
+
+        let [width, height, view_box, aspect] = self.size.unwrap_or_default();
+        parsed.view_box = view_box.and_then(|view_box| {
+            let parts: Vec<f32> = view_box
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|s| !s.is_empty())
+                .filter_map(|s| s.parse().ok())
+                .collect();
+            (parts.len() == 4).then(|| ViewBox {
+                x: parts[0],
+                y: parts[1],
+                width: parts[2],
+                height: parts[3],
+            })
+        });
+        let font_size = self.sheet.font_size();
+        // A percentage is of the viewBox, and ignored without one
+        let length =
+            |value: Option<String>, of: Option<f32>| match parse_length(&value?, font_size)? {
+                Length::Units(units) => Some(units),
+                Length::Percent(percent) => Some(of? * percent / 100.0),
+            };
+        // The viewBox sizes the document only where a length is absent
+        let view_box = parsed.view_box.as_ref();
+        let view_width = view_box.map(|vb| vb.width);
+        let view_height = view_box.map(|vb| vb.height);
+        if let Some(width) = length(width, view_width).or(view_width) {
+            parsed.width = width;
+        }
+        if let Some(height) = length(height, view_height).or(view_height) {
+            parsed.height = height;
+        }
+        if let Some(aspect) = aspect {
+            parsed.preserve_aspect_ratio = PreserveAspectRatio::parse(&aspect);
+        }
+        parsed
+    }
+}
+
+/// Parses a document fed in chunks, for files too large to pass as one
+/// string. Feed its bytes in order, then call `finish`.
+#[wasm_bindgen]
+pub struct SvgStreamParser {
+    tokenizer: Tokenizer,
+    reader: DocumentReader,
+}
+
+impl Default for SvgStreamParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl SvgStreamParser {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            tokenizer: Tokenizer::default(),
+            reader: DocumentReader::new(),
+        }
+    }
+
+    /// Match `systemLanguage` in `<switch>` children against `language`,
+    /// such as `de` or `en-US`, for elements fed after the call. Without a
+    /// language, children that list one are never picked.
+    #[wasm_bindgen]
+    pub fn set_language(&mut self, language: Option<String>) {
+        self.reader.scopes.set_language(language);
+    }
+
+    /// Read `em` lengths as `font_size` user units, for elements fed after
+    /// the call. Without a font size, or with one that is not positive,
+    /// they are 16.
+    #[wasm_bindgen]
+    pub fn set_font_size(&mut self, font_size: Option<f32>) {
+        self.reader.sheet.set_font_size(font_size);
+    }
+
+    /// Read the next chunk of the document's UTF-8 bytes. Chunks may split
+    /// it anywhere, even inside a tag or a character. Invalid UTF-8 reads
+    /// as U+FFFD.
+    #[wasm_bindgen]
+    pub fn feed(&mut self, chunk: &[u8]) {
+        let reader = &mut self.reader;
+        self.tokenizer
+            .push(chunk, false, |token| reader.read(token));
+    }
+
+    /// Read the rest of the document and return it as `parse_svg` would
+    /// with `options_js`. Consumes the parser.
+    #[wasm_bindgen]
+    pub fn finish(self, options_js: JsValue) -> Result<JsValue, JsValue> {
+        let options = parse_options(options_js)?;
+        let parsed = apply_parse_options(self.finish_native(), &options)
+            .map_err(|e| JsValue::from_str(&format!("Invalid path data: {}", e)))?;
+        serde_wasm_bindgen::to_value(&parsed)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+}
+
+impl SvgStreamParser {
+    /// Native counterpart of `finish`, returning the document before any
+    /// `ParseOptions` apply.
+    pub fn finish_native(mut self) -> ParsedSvg {
+        let reader = &mut self.reader;
+        self.tokenizer.push(&[], true, |token| reader.read(token));
+        self.reader.finish()
+    }
+}
+
+/// A warning listing the ids that `fill` and `stroke` values reference but
+/// the document does not define, and that have no fallback color, so draw
+/// black.
+fn missing_references(parsed: &ParsedSvg) -> Option<String> {
+    let mut missing: Vec<&str> = Vec::new();
+    let paints = parsed
+        .paths
+        .iter()
+        .flat_map(|path| [&path.fill, &path.stroke]);
+    for (id, fallback) in paints.filter_map(|paint| split_reference(paint.as_deref()?)) {
+        let defined = parsed.gradients.iter().any(|gradient| gradient.id == id)
+            || parsed.patterns.iter().any(|pattern| pattern.id == id);
+        if fallback.is_empty() && !defined && !missing.contains(&id) {
+            missing.push(id);
+        }
+    }
+    (!missing.is_empty()).then(|| {
+        format!(
+            "drew references to undefined paints black: #{}",
+            missing.join(", #")
+        )
+    })
+}
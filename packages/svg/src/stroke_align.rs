@@ -0,0 +1,250 @@
+//! Inner and outer strokes, which SVG renderers and lyon only draw centered.
+//!
+//! The path is stroked at twice the width and each stroke triangle is
+//! clipped against the path's fill triangles: kept where it overlaps them
+//! for an inner stroke, and kept where it does not for an outer one. What
+//! remains is the band of the requested width on one side of the outline,
+//! joins included, without offsetting any curves.
+//!
+//! "Inside" is wherever the fill would paint, so the result follows the
+//! fill rule rather than the direction of the path. On a self-intersecting
+//! path, regions the fill rule leaves empty count as outside, and an inner
+//! stroke along the edges around them paints into the filled side only.
+//! Open subpaths are closed the way the fill closes them.
+
+use crate::{signed_area, TessVertex};
+use lyon::tessellation::VertexBuffers;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tolerance::Tolerance;
+
+/// Which side of the outline a stroke covers, after the SVG Strokes draft's
+/// `stroke-alignment`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrokeAlign {
+    /// Half the width on each side, as SVG strokes
+    #[default]
+    Center,
+    /// The whole width inside the fill
+    Inner,
+    /// The whole width outside the fill
+    Outer,
+}
+
+impl StrokeAlign {
+    /// Parse a `stroke-alignment` value, treating anything unknown as
+    /// `center` like other unsupported presentation attributes.
+    pub(crate) fn from_attr(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some("inner") => StrokeAlign::Inner,
+            Some("outer") => StrokeAlign::Outer,
+            _ => StrokeAlign::Center,
+        }
+    }
+}
+
+type Point = [f32; 2];
+
+/// Triangles smaller than this, in squared path units, are slivers left by
+/// clipping along a shared edge and are dropped.
+const MIN_AREA: Tolerance = Tolerance::absolute(1e-6);
+
+/// A fill triangle wound so its interior is left of every edge, with its
+/// bounding box for cheap rejection.
+struct Clipper {
+    corners: [Point; 3],
+    min: Point,
+    max: Point,
+}
+
+impl Clipper {
+    fn new(a: Point, b: Point, c: Point) -> Option<Self> {
+        let area = signed_area(a[0], a[1], b[0], b[1], c[0], c[1]);
+        let corners = if area > 0.0 {
+            [a, b, c]
+        } else if area < 0.0 {
+            [a, c, b]
+        } else {
+            return None;
+        };
+        let (min, max) = bounds(&corners);
+        Some(Self { corners, min, max })
+    }
+
+    fn overlaps(&self, polygon: &[Point]) -> bool {
+        let (min, max) = bounds(polygon);
+        min[0] < self.max[0] && max[0] > self.min[0] && min[1] < self.max[1] && max[1] > self.min[1]
+    }
+
+    fn edge(&self, index: usize) -> (Point, Point) {
+        (self.corners[index], self.corners[(index + 1) % 3])
+    }
+}
+
+/// Append to `out` the parts of `stroke`'s triangles on the `align` side of
+/// `fill`. `stroke` must be twice the requested width.
+pub(crate) fn clip_stroke(
+    stroke: &VertexBuffers<TessVertex, u32>,
+    fill: &VertexBuffers<TessVertex, u32>,
+    align: StrokeAlign,
+    out: &mut VertexBuffers<TessVertex, u32>,
+) {
+    let point = |vertices: &[TessVertex], index: u32| {
+        let vertex = &vertices[index as usize];
+        [vertex.x, vertex.y]
+    };
+    let clippers: Vec<Clipper> = fill
+        .indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            Clipper::new(
+                point(&fill.vertices, triangle[0]),
+                point(&fill.vertices, triangle[1]),
+                point(&fill.vertices, triangle[2]),
+            )
+        })
+        .collect();
+
+    let mut emitter = Emitter {
+        out,
+        shared: HashMap::new(),
+    };
+    for triangle in stroke.indices.chunks_exact(3) {
+        let polygon: Vec<Point> = triangle
+            .iter()
+            .map(|&index| point(&stroke.vertices, index))
+            .collect();
+        match align {
+            StrokeAlign::Center => emitter.polygon(&polygon),
+            StrokeAlign::Inner => {
+                // Fill triangles do not overlap, so the pieces do not either
+                for clipper in clippers.iter().filter(|clipper| clipper.overlaps(&polygon)) {
+                    emitter.polygon(&intersect(&polygon, clipper));
+                }
+            }
+            StrokeAlign::Outer => {
+                for piece in subtract(polygon, &clippers) {
+                    emitter.polygon(&piece);
+                }
+            }
+        }
+    }
+}
+
+/// The part of convex `polygon` inside `clipper`.
+fn intersect(polygon: &[Point], clipper: &Clipper) -> Vec<Point> {
+    let mut clipped = polygon.to_vec();
+    for edge in 0..3 {
+        let (from, to) = clipper.edge(edge);
+        clipped = clip_half_plane(&clipped, from, to, true);
+        if clipped.len() < 3 {
+            return Vec::new();
+        }
+    }
+    clipped
+}
+
+/// Convex pieces covering the part of `polygon` outside every clipper.
+fn subtract(polygon: Vec<Point>, clippers: &[Clipper]) -> Vec<Vec<Point>> {
+    let mut pieces = vec![polygon];
+    for clipper in clippers {
+        let mut outside = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            if !clipper.overlaps(&piece) {
+                outside.push(piece);
+                continue;
+            }
+            // Peel off the part beyond each edge in turn; what is left after
+            // all three is inside the clipper
+            let mut rest = piece;
+            for edge in 0..3 {
+                let (from, to) = clipper.edge(edge);
+                let beyond = clip_half_plane(&rest, from, to, false);
+                if beyond.len() >= 3 {
+                    outside.push(beyond);
+                }
+                rest = clip_half_plane(&rest, from, to, true);
+                if rest.len() < 3 {
+                    break;
+                }
+            }
+        }
+        pieces = outside;
+    }
+    pieces
+}
+
+/// Clip convex `polygon` to the left of the line `from`-`to`, or to the
+/// right when `left` is false. Points on the line are kept either way.
+fn clip_half_plane(polygon: &[Point], from: Point, to: Point, left: bool) -> Vec<Point> {
+    let side = |p: Point| {
+        let cross = signed_area(from[0], from[1], to[0], to[1], p[0], p[1]);
+        if left {
+            cross
+        } else {
+            -cross
+        }
+    };
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (index, &current) in polygon.iter().enumerate() {
+        let previous = polygon[(index + polygon.len() - 1) % polygon.len()];
+        let (current_side, previous_side) = (side(current), side(previous));
+        if (current_side >= 0.0) != (previous_side >= 0.0) {
+            let t = previous_side / (previous_side - current_side);
+            clipped.push([
+                previous[0] + (current[0] - previous[0]) * t,
+                previous[1] + (current[1] - previous[1]) * t,
+            ]);
+        }
+        if current_side >= 0.0 {
+            clipped.push(current);
+        }
+    }
+    clipped
+}
+
+fn bounds(points: &[Point]) -> (Point, Point) {
+    let mut min = [f32::MAX; 2];
+    let mut max = [f32::MIN; 2];
+    for point in points {
+        min = [min[0].min(point[0]), min[1].min(point[1])];
+        max = [max[0].max(point[0]), max[1].max(point[1])];
+    }
+    (min, max)
+}
+
+/// Writes clipped polygons as triangle fans, sharing vertices at identical
+/// positions so the mesh stays indexed.
+struct Emitter<'a> {
+    out: &'a mut VertexBuffers<TessVertex, u32>,
+    shared: HashMap<(u32, u32), u32>,
+}
+
+impl Emitter<'_> {
+    fn polygon(&mut self, polygon: &[Point]) {
+        for step in 1..polygon.len().saturating_sub(1) {
+            let [a, b, c] = [polygon[0], polygon[step], polygon[step + 1]];
+            if MIN_AREA.is_zero(signed_area(a[0], a[1], b[0], b[1], c[0], c[1])) {
+                continue;
+            }
+            let indices = [self.vertex(a), self.vertex(b), self.vertex(c)];
+            self.out.indices.extend(indices);
+        }
+    }
+
+    fn vertex(&mut self, point: Point) -> u32 {
+        let vertices = &mut self.out.vertices;
+        *self
+            .shared
+            .entry((point[0].to_bits(), point[1].to_bits()))
+            .or_insert_with(|| {
+                vertices.push(TessVertex {
+                    x: point[0],
+                    y: point[1],
+                    edge_dist: 1.0,
+                });
+                (vertices.len() - 1) as u32
+            })
+    }
+}
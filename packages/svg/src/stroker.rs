@@ -0,0 +1,46 @@
+//! The stroke tessellator, which is lyon's unless built without the `stroke`
+//! feature.
+//!
+//! Apps that only fill shapes can leave lyon's stroker out of the binary.
+//! The stand-in has the same methods and produces no geometry: a stroked
+//! path gives an empty mesh, and a document draws its fills alone.
+
+#[cfg(feature = "stroke")]
+pub(crate) use lyon::tessellation::StrokeTessellator as Stroker;
+
+#[cfg(not(feature = "stroke"))]
+pub(crate) use stub::Stroker;
+
+#[cfg(not(feature = "stroke"))]
+mod stub {
+    use lyon::path::{AttributeStore, IdEvent, PathSlice, PositionStore};
+    use lyon::tessellation::{StrokeGeometryBuilder, StrokeOptions, TessellationResult};
+
+    pub(crate) struct Stroker;
+
+    impl Stroker {
+        pub(crate) fn new() -> Self {
+            Stroker
+        }
+
+        pub(crate) fn tessellate_path<'l>(
+            &'l mut self,
+            _path: impl Into<PathSlice<'l>>,
+            _options: &'l StrokeOptions,
+            _builder: &'l mut dyn StrokeGeometryBuilder,
+        ) -> TessellationResult {
+            Ok(())
+        }
+
+        pub(crate) fn tessellate_with_ids(
+            &mut self,
+            _path: impl IntoIterator<Item = IdEvent>,
+            _positions: &impl PositionStore,
+            _custom_attributes: Option<&dyn AttributeStore>,
+            _options: &StrokeOptions,
+            _output: &mut dyn StrokeGeometryBuilder,
+        ) -> TessellationResult {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Presentation properties, which an element sets with an attribute, a
+//! rule for one of its classes in a `<style>` element, or a declaration in
+//! its `style`. Each wins over the ones before it.
+//!
+//! Style sheets are read for `.class` selectors alone, and for the paint
+//! properties in `SHEET_PROPERTIES`; other rules are skipped. Documents are
+//! read in one pass, so a sheet applies to the elements after it, which is
+//! where exporters put it.
+
+use crate::extract_attr;
+use crate::length::{parse_length, DEFAULT_FONT_SIZE};
+
+/// The properties read from style sheets.
+const SHEET_PROPERTIES: [&str; 15] = [
+    "fill",
+    "stroke",
+    "stroke-width",
+    "opacity",
+    "fill-opacity",
+    "stroke-opacity",
+    "fill-rule",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stop-color",
+    "stop-opacity",
+    "color",
+];
+
+/// The class rules of a document's `<style>` elements.
+#[derive(Default)]
+pub(crate) struct StyleSheet {
+    /// Class name and its declarations, in document order
+    rules: Vec<(String, Vec<(String, String)>)>,
+    /// What `em` lengths are relative to, when not `DEFAULT_FONT_SIZE`
+    font_size: Option<f32>,
+}
+
+impl StyleSheet {
+    /// Add the rules of a `<style>` element's text.
+    pub(crate) fn read(&mut self, css: &str) {
+        let css = strip_comments(css);
+        for rule in css.split('}') {
+            let Some((selectors, body)) = rule.split_once('{') else {
+                continue;
+            };
+            let declared: Vec<(String, String)> = declarations(body)
+                .filter(|(property, _)| SHEET_PROPERTIES.contains(&property.as_str()))
+                .map(|(property, value)| (property, value.to_string()))
+                .collect();
+            if declared.is_empty() {
+                continue;
+            }
+            for selector in selectors.split(',') {
+                let class = selector.trim().strip_prefix('.').filter(|class| {
+                    !class.is_empty()
+                        && class
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+                });
+                if let Some(class) = class {
+                    self.rules.push((class.to_string(), declared.clone()));
+                }
+            }
+        }
+    }
+
+    /// The value the sheet gives `name` on an element with start tag `tag`.
+    /// The last rule for any of its classes wins.
+    fn class_property(&self, tag: &str, name: &str) -> Option<String> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let classes = extract_attr(tag, "class")?;
+        let classes: Vec<&str> = classes.split_whitespace().collect();
+        self.rules
+            .iter()
+            .rev()
+            .filter(|(class, _)| classes.contains(&class.as_str()))
+            .flat_map(|(_, declared)| declared.iter().rev())
+            .find(|(property, _)| property == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// A presentation property of a start tag, from `style`, else a class
+    /// rule, else its attribute.
+    pub(crate) fn property(&self, tag: &str, name: &str) -> Option<String> {
+        declared(tag, name)
+            .or_else(|| self.class_property(tag, name))
+            .or_else(|| extract_attr(tag, name).map(|value| value.trim().to_string()))
+    }
+
+    /// A numeric presentation property, or None when it does not parse.
+    pub(crate) fn number_property(&self, tag: &str, name: &str) -> Option<f32> {
+        self.property(tag, name)
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// A length presentation property in user units, or None when it does
+    /// not parse. Percentages are not read.
+    pub(crate) fn length_property(&self, tag: &str, name: &str) -> Option<f32> {
+        self.property(tag, name)
+            .and_then(|value| parse_length(&value, self.font_size())?.units())
+    }
+
+    /// Read `em` lengths as `font_size` user units, or the default without
+    /// one.
+    pub(crate) fn set_font_size(&mut self, font_size: Option<f32>) {
+        self.font_size = font_size.filter(|size| size.is_finite() && *size > 0.0);
+    }
+
+    pub(crate) fn font_size(&self) -> f32 {
+        self.font_size.unwrap_or(DEFAULT_FONT_SIZE)
+    }
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// The declarations of a `style` attribute as `(property, value)`, in
+/// order. Property names are lowercased and `!important` is dropped; empty
+/// and malformed declarations are skipped.
+fn declarations(style: &str) -> impl Iterator<Item = (String, &str)> {
+    style.split(';').filter_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        let value = value.trim();
+        let value = value
+            .len()
+            .checked_sub("!important".len())
+            .filter(|at| value.is_char_boundary(*at))
+            .filter(|at| value[*at..].eq_ignore_ascii_case("!important"))
+            .map_or(value, |at| value[..at].trim_end());
+        let property = property.trim().to_ascii_lowercase();
+        (!property.is_empty() && !value.is_empty()).then_some((property, value))
+    })
+}
+
+/// The last declaration of `name` in a start tag's `style`.
+fn declared(tag: &str, name: &str) -> Option<String> {
+    extract_attr(tag, "style").and_then(|style| {
+        declarations(&style)
+            .filter(|(property, _)| property == name)
+            .last()
+            .map(|(_, value)| value.to_string())
+    })
+}
+
+/// A presentation property of a start tag, from `style` or else its
+/// attribute, without style sheets.
+pub(crate) fn property(tag: &str, name: &str) -> Option<String> {
+    StyleSheet::default().property(tag, name)
+}
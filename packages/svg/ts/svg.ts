@@ -8,7 +8,16 @@
 import { log } from "@glade/logging";
 import { base64ToBytes, formatBytes } from "@glade/utils";
 
-import { type InitOutput, initSync, SvgTessellator as WasmSvgTessellator } from "../pkg/svg";
+import {
+  type InitOutput,
+  initSync,
+  PathMeasure,
+  type PathSample,
+  type ProgressiveId,
+  type Scene,
+  SvgStreamParser,
+  SvgTessellator as WasmSvgTessellator,
+} from "../pkg/svg";
 import { SVG_WASM_BASE64 } from "./gen.embedded";
 
 export class SvgTessellator extends WasmSvgTessellator {
@@ -27,12 +36,71 @@ export function createSvgTessellator(): SvgTessellator {
   return new SvgTessellator(module);
 }
 
+/**
+ * Unless consistent winding is disabled, every triangle has positive signed
+ * area: counter-clockwise with y up, clockwise on a y-down screen. Zero-area
+ * triangles are dropped.
+ */
 export interface TessellatedMesh {
   vertices: Float32Array;
   indices: Uint32Array;
   bounds: MeshBounds;
+  /**
+   * Position in the document's painter's order: 2 * path index, plus 1 for
+   * the paint drawn second. Batch freely, but keep overlapping meshes in
+   * this order. Zero for meshes tessellated on their own.
+   */
+  paintOrder: number;
+  /**
+   * Edges used by only one triangle, as flat vertex index pairs chained into
+   * loops: a pair not starting where the previous one ended starts a new
+   * loop. Only filled when requested with TessellateOptions.boundaryEdges.
+   */
+  boundaryEdges?: Uint32Array;
+  /**
+   * Index of the parsed path command behind each vertex. Only filled when
+   * requested with PathPaintOptions.sourceCommands.
+   */
+  sourceCommands?: Uint32Array;
+  /**
+   * Range of each command in the path data as flat [start, end, ...] UTF-8
+   * byte offsets, which match string indices for ASCII path data.
+   */
+  commandRanges?: Uint32Array;
+  /**
+   * For a dynamic path, the matrix taking its untransformed vertices to
+   * display coordinates, for the renderer to apply. Absent when the vertices
+   * are already placed.
+   */
+  transform?: TransformMatrix;
+  /**
+   * Alpha to multiply into the mesh's color, from the path's opacity and
+   * fill or stroke opacity. Absent when neither is set.
+   */
+  alpha?: number;
+  /**
+   * A document mesh's solid color or gradient. Absent for meshes
+   * tessellated on their own and for paints that draw nothing.
+   */
+  paint?: MeshPaint;
+  /** A document mesh's path's fill_color or stroke_color */
+  color?: Rgba;
+  /**
+   * Painted with `currentColor` that neither the document's `color` nor
+   * TessellateOptions.currentColor gave a value; paint is then black, for the
+   * renderer to tint
+   */
+  usesCurrentColor: boolean;
+  /** Set when the mesh is empty because its scale left nothing to draw */
+  degenerate?: DegenerateInput;
 }
 
+/**
+ * Why a tessellation is empty on purpose: the display size or scale is zero
+ * or too small to draw at, or the document's own size or viewBox is zero.
+ */
+export type DegenerateInput = "display_scale" | "native_size";
+
 export interface MeshBounds {
   min_x: number;
   min_y: number;
@@ -41,10 +109,51 @@ export interface MeshBounds {
 }
 
 export interface ParsedPath {
+  id?: string;
+  /** The `class` attribute as written */
+  class?: string;
   fill?: string;
   stroke?: string;
   stroke_width?: number;
   d: string;
+  /** Whether `d` was shortened or emptied by `maxDLength` */
+  d_truncated: boolean;
+  /** The `paint-order` attribute, which can paint the stroke first */
+  paint_order?: string;
+  /** The `stroke-alignment` attribute: "center", "inner" or "outer" */
+  stroke_alignment?: string;
+  /** The `fill-rule` set on the shape or inherited: "nonzero" or "evenodd" */
+  fill_rule?: string;
+  /** The `stroke-linecap` set on the shape or inherited */
+  stroke_linecap?: string;
+  /** The `stroke-linejoin` set on the shape or inherited */
+  stroke_linejoin?: string;
+  /** The `stroke-miterlimit` set on the shape or inherited */
+  stroke_miterlimit?: number;
+  /** The `stroke-dasharray` set on the shape or inherited */
+  stroke_dasharray?: string;
+  /** The `stroke-dashoffset` set on the shape or inherited */
+  stroke_dashoffset?: number;
+  /** The shape's `transform` composed with its groups', when any parses */
+  transform?: TransformMatrix;
+  /** The shape's `opacity` times its groups', when any sets it */
+  opacity?: number;
+  /** The `fill-opacity` set on the shape or inherited */
+  fill_opacity?: number;
+  /** The `stroke-opacity` set on the shape or inherited */
+  stroke_opacity?: number;
+  /** The `color` set on the shape or inherited, which `currentColor` takes */
+  color?: string;
+  /**
+   * `fill` as straight (not premultiplied) RGBA in [0, 1], before opacity.
+   * Black when unset or invalid; absent for `none`, `url()` paints and
+   * unresolved `currentColor`.
+   */
+  fill_color?: Rgba;
+  /** `stroke` as for fill_color, but absent when unset */
+  stroke_color?: Rgba;
+  /** Whether the shape has `visibility` hidden; hidden shapes are not tessellated */
+  hidden: boolean;
 }
 
 export interface ViewBox {
@@ -54,43 +163,394 @@ export interface ViewBox {
   height: number;
 }
 
+/** Where a uniformly scaled document sits along one axis of the display */
+export type AspectAlign = "min" | "mid" | "max";
+
+/** The root element's `preserveAspectRatio`; the default is `xMidYMid meet` */
+export interface PreserveAspectRatio {
+  /** The x and y alignment; absent for `none`, which stretches each axis */
+  align?: [AspectAlign, AspectAlign];
+  /** Whether the document covers the display rather than fitting in it */
+  slice: boolean;
+}
+
+/**
+ * A problem in one path's `d` data: a parse warning, or the error thrown by
+ * parseSvg in strict mode.
+ */
+export interface PathDataIssue {
+  /** Index into `paths` */
+  path: number;
+  /** Index of the offending token, when one token is to blame */
+  token?: number;
+  /** Byte offset in `d` of the offending token, or its length when it ended early */
+  offset?: number;
+  /** The offending token as written; absent when the data ended early */
+  found?: string;
+  /** The command whose arguments were being read */
+  command?: string;
+  message: string;
+  /** Surrounding path data with the offending token in brackets */
+  context: string;
+}
+
 export interface ParsedSvg {
   width: number;
   height: number;
   view_box?: ViewBox;
+  preserve_aspect_ratio: PreserveAspectRatio;
   paths: ParsedPath[];
+  /** Suspicious path data that still parsed, such as comma decimals */
+  warnings: PathDataIssue[];
+  /**
+   * Problems outside path data, such as skipped foreignObject blocks or
+   * references to undefined paints
+   */
+  document_warnings: string[];
+  /** Title, description and ARIA labels for labeling the rendered image */
+  accessibility: SvgAccessibility;
+  /** `<pattern>` definitions with an id; their shapes are not in `paths` */
+  patterns: SvgPattern[];
+  /**
+   * `<linearGradient>` and `<radialGradient>` definitions with an id, with
+   * `href` followed
+   */
+  gradients: SvgGradient[];
+}
+
+/** A gradient's color at one offset along it */
+export interface GradientStop {
+  offset: number;
+  /** RGBA8, with stop-opacity applied */
+  color: [number, number, number, number];
 }
 
-interface RawMesh {
+/**
+ * A gradient's geometry, in its `units` before its `transform`: a linear
+ * axis, or a radial gradient from the focal point to the circle around the
+ * center
+ */
+export type GradientShape =
+  | { type: "linear"; x1: number; y1: number; x2: number; y2: number }
+  | { type: "radial"; cx: number; cy: number; r: number; fx: number; fy: number };
+
+export interface SvgGradient {
+  id: string;
+  shape: GradientShape;
+  units: GradientUnits;
+  /** gradientTransform */
+  transform: TransformMatrix;
+  /** Offsets are clamped to [0, 1] and never decrease */
+  stops: GradientStop[];
+}
+
+/**
+ * What a document mesh is painted with, before its alpha. Shade a gradient
+ * by projecting each point onto start-end: offset 0 at start, 1 at end,
+ * padded with the end stops past them. The ends are chosen so this is exact
+ * even when a transform skews the gradient. A radial gradient runs from
+ * offset 0 at focus to 1 on the ellipse of points
+ * `center + cos(a) * radius_x + sin(a) * radius_y`, which is a circle when
+ * the radii are perpendicular and of one length.
+ */
+export type MeshPaint =
+  | { type: "solid"; color: [number, number, number, number] }
+  | {
+      type: "linear_gradient";
+      start: [number, number];
+      end: [number, number];
+      stops: GradientStop[];
+    }
+  | {
+      type: "radial_gradient";
+      center: [number, number];
+      focus: [number, number];
+      radius_x: [number, number];
+      radius_y: [number, number];
+      stops: GradientStop[];
+    };
+
+export type PatternUnits = "objectBoundingBox" | "userSpaceOnUse";
+
+export interface SvgPattern {
+  id: string;
+  /** The tile's rectangle, in `pattern_units` */
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  pattern_units: PatternUnits;
+  pattern_content_units: PatternUnits;
+  /** The tile's shapes, from its top left corner */
+  paths: ParsedPath[];
+}
+
+/**
+ * Accessibility metadata of a document, with character references decoded
+ * and whitespace collapsed. Unset or blank fields are omitted.
+ */
+export interface SvgAccessibility {
+  /** Text of the root element's first `title` child */
+  title?: string;
+  /** Text of the root element's first `desc` child */
+  desc?: string;
+  /** The root element's `role`, such as "img" */
+  role?: string;
+  aria_label?: string;
+  /** Whether the root has aria-hidden="true", marking the image decorative */
+  aria_hidden: boolean;
+  /** Shapes with an `id` or `aria-label`, in path order */
+  paths: PathAccessibility[];
+}
+
+export interface PathAccessibility {
+  /** Index into `paths` */
+  path: number;
+  id?: string;
+  aria_label?: string;
+}
+
+export interface RawMesh {
   vertices: number[];
   indices: number[];
   bounds: MeshBounds;
+  paint_order?: number;
+  boundary_edges?: number[];
+  source_commands?: number[];
+  command_ranges?: number[];
+  transform?: TransformMatrix;
+  alpha?: number;
+  paint?: MeshPaint;
+  color?: Rgba;
+  uses_current_color?: boolean;
+  degenerate?: DegenerateInput;
 }
 
-function convertMesh(raw: RawMesh): TessellatedMesh {
+export function convertMesh(raw: RawMesh): TessellatedMesh {
   return {
     vertices: new Float32Array(raw.vertices),
     indices: new Uint32Array(raw.indices),
     bounds: raw.bounds,
+    paintOrder: raw.paint_order ?? 0,
+    boundaryEdges: raw.boundary_edges ? new Uint32Array(raw.boundary_edges) : undefined,
+    sourceCommands: raw.source_commands ? new Uint32Array(raw.source_commands) : undefined,
+    commandRanges: raw.command_ranges ? new Uint32Array(raw.command_ranges) : undefined,
+    transform: raw.transform,
+    alpha: raw.alpha,
+    paint: raw.paint,
+    color: raw.color,
+    usesCurrentColor: raw.uses_current_color ?? false,
+    degenerate: raw.degenerate,
   };
 }
 
-export function parseSvg(tessellator: SvgTessellator, svgContent: string): ParsedSvg {
-  return tessellator.parse_svg(svgContent) as ParsedSvg;
+/**
+ * Enable or disable rewinding triangles to one orientation. On by default;
+ * renderers that do not cull back faces can turn it off to skip the pass.
+ */
+export function setConsistentWinding(tessellator: SvgTessellator, enabled: boolean): void {
+  tessellator.set_consistent_winding(enabled);
+}
+
+export interface ParseOptions {
+  /**
+   * Decimal places kept for fractional numbers in `d` strings and summary
+   * bounds
+   */
+  precision?: number;
+  /** Longest `d` string, in bytes, returned in full */
+  maxDLength?: number;
+  /** Empty over-long `d` strings instead of cutting them */
+  omitLongD?: boolean;
+  /**
+   * Throw on the first missing argument or unparsable token in any `d`
+   * instead of reading it as 0. Only parseSvg applies it.
+   */
+  strict?: boolean;
+  /**
+   * The user's language, such as "de" or "en-US", picking between `<switch>`
+   * children by `systemLanguage`. Streamed documents take it from
+   * createSvgParser instead.
+   */
+  language?: string;
+  /**
+   * The color `currentColor` fills and strokes take where the document sets
+   * no `color`, such as the CSS color of the element an icon is shown in.
+   * Left as "currentColor" without one.
+   */
+  currentColor?: string;
+  /**
+   * The font size `em` lengths such as `stroke-width="0.5em"` are relative
+   * to, in user units; 16 without one. Streamed documents take it from
+   * createSvgParser instead.
+   */
+  fontSize?: number;
 }
 
+/**
+ * Per-path fields are parallel arrays indexed by path. `bounds` is flat
+ * [min_x, min_y, max_x, max_y, ...] covering points and control points.
+ */
+export interface SvgSummary {
+  width: number;
+  height: number;
+  view_box?: ViewBox;
+  preserve_aspect_ratio: PreserveAspectRatio;
+  path_count: number;
+  command_count: number;
+  d_bytes: number;
+  ids: Array<string | undefined>;
+  bounds: number[];
+  command_counts: number[];
+  d_lengths: number[];
+  stroked: boolean[];
+}
+
+function parseOptionsToWasm(options: ParseOptions): Record<string, unknown> {
+  // Only send set fields; serde rejects an explicit undefined for a
+  // non-optional field even when the struct has defaults
+  const wasmOptions: Record<string, unknown> = {};
+  if (options.precision !== undefined) {
+    wasmOptions.precision = options.precision;
+  }
+  if (options.maxDLength !== undefined) {
+    wasmOptions.max_d_length = options.maxDLength;
+  }
+  if (options.omitLongD !== undefined) {
+    wasmOptions.omit_long_d = options.omitLongD;
+  }
+  if (options.strict !== undefined) {
+    wasmOptions.strict = options.strict;
+  }
+  if (options.language !== undefined) {
+    wasmOptions.language = options.language;
+  }
+  if (options.currentColor !== undefined) {
+    wasmOptions.current_color = options.currentColor;
+  }
+  if (options.fontSize !== undefined) {
+    wasmOptions.font_size = options.fontSize;
+  }
+  return wasmOptions;
+}
+
+export function parseSvg(
+  tessellator: SvgTessellator,
+  svgContent: string,
+  options: ParseOptions = {}
+): ParsedSvg {
+  return tessellator.parse_svg(svgContent, parseOptionsToWasm(options)) as ParsedSvg;
+}
+
+/**
+ * Parse one path's `d` strictly, as ParseOptions.strict does each path of a
+ * document. Returns the issue that stopped it, or undefined when it parses.
+ */
+export function checkPathData(tessellator: SvgTessellator, d: string): PathDataIssue | undefined {
+  return tessellator.check_path_d(d) as PathDataIssue | undefined;
+}
+
+/**
+ * Summarize an SVG's size, path count and per-path bounds without returning
+ * any path data, for deciding where to tessellate it. Only `precision`
+ * applies.
+ */
+export function parseSvgSummary(
+  tessellator: SvgTessellator,
+  svgContent: string,
+  options: ParseOptions = {}
+): SvgSummary {
+  return tessellator.parse_svg_summary(svgContent, parseOptionsToWasm(options)) as SvgSummary;
+}
+
+/**
+ * Start parsing a document that arrives in chunks, for files too large to
+ * hold as one string. Pass its UTF-8 bytes in order to parser.feed(chunk),
+ * split anywhere, then call finishSvgParser. `language` and `fontSize` are
+ * as for ParseOptions.
+ */
+export function createSvgParser(language?: string, fontSize?: number): SvgStreamParser {
+  const parser = new SvgStreamParser();
+  parser.set_language(language);
+  parser.set_font_size(fontSize);
+  return parser;
+}
+
+/**
+ * Read the rest of a streamed document, returning what parseSvg would for
+ * the whole string. Frees the parser.
+ */
+export function finishSvgParser(parser: SvgStreamParser, options: ParseOptions = {}): ParsedSvg {
+  return parser.finish(parseOptionsToWasm(options)) as ParsedSvg;
+}
+
+/**
+ * A 64-bit hash of an SVG document as 16 hex digits, for memoizing parsing
+ * and tessellation without hashing the string in JS. Covers the document
+ * only; key options alongside it.
+ */
+export function hashSvg(tessellator: SvgTessellator, svgContent: string): string {
+  return tessellator.hash_svg(svgContent);
+}
+
+/**
+ * How overlapping subpaths fill, as SVG's fill-rule: "evenodd" cuts holes
+ * where subpaths nest, whatever their direction.
+ */
+export type FillRule = "nonzero" | "evenodd";
+
 export function tessellatePath(
   tessellator: SvgTessellator,
   pathD: string,
   offsetX = 0,
   offsetY = 0,
   scaleX = 1,
-  scaleY = 1
+  scaleY = 1,
+  fillRule: FillRule = "nonzero"
 ): TessellatedMesh {
-  const raw = tessellator.tessellate_path(pathD, offsetX, offsetY, scaleX, scaleY) as RawMesh;
+  const raw = tessellator.tessellate_path(
+    pathD,
+    offsetX,
+    offsetY,
+    scaleX,
+    scaleY,
+    fillRule
+  ) as RawMesh;
   return convertMesh(raw);
 }
 
+/**
+ * Which side of the outline a stroke covers. Inner and outer strokes are cut
+ * from a stroke of twice the width by the path's fill, so on self-intersecting
+ * paths "inside" follows the fill rule, not the path direction.
+ */
+export type StrokeAlign = "center" | "inner" | "outer";
+
+/** The shape at the ends of open subpaths, as SVG's stroke-linecap. */
+export type LineCap = "butt" | "round" | "square";
+
+/** The shape at corners, as SVG's stroke-linejoin. */
+export type LineJoin = "miter" | "miter-clip" | "round" | "bevel";
+
+/**
+ * Stroke thickness per axis in output units, after offset and scale, for
+ * canvases stretched unevenly. Each defaults to the stroke width times the
+ * larger scale. Other directions get a thickness in between, as drawn with
+ * an elliptical pen.
+ */
+export interface StrokeWidthOptions {
+  /** Thickness across vertical segments */
+  strokeWidthX?: number;
+  /** Thickness across horizontal segments */
+  strokeWidthY?: number;
+  /** The cap on the ends of open subpaths, default "butt" */
+  lineCap?: LineCap;
+  /** The join at corners, default "miter" */
+  lineJoin?: LineJoin;
+  /** The longest miter join in stroke widths, default 4 */
+  miterLimit?: number;
+}
+
 export function tessellateStroke(
   tessellator: SvgTessellator,
   pathD: string,
@@ -98,8 +558,56 @@ export function tessellateStroke(
   offsetX = 0,
   offsetY = 0,
   scaleX = 1,
-  scaleY = 1
+  scaleY = 1,
+  strokeAlign: StrokeAlign = "center",
+  widths: StrokeWidthOptions = {}
 ): TessellatedMesh {
+  const perAxis = widths.strokeWidthX !== undefined || widths.strokeWidthY !== undefined;
+  if (perAxis && strokeAlign !== "center") {
+    throw new Error("Per-axis stroke widths only apply to centered strokes");
+  }
+  const styled =
+    widths.lineCap !== undefined ||
+    widths.lineJoin !== undefined ||
+    widths.miterLimit !== undefined;
+  if (perAxis || (styled && strokeAlign === "center")) {
+    const wasmOptions: {
+      stroke_width_x?: number;
+      stroke_width_y?: number;
+      line_cap?: LineCap;
+      line_join?: LineJoin;
+      miter_limit?: number;
+    } = { stroke_width_x: widths.strokeWidthX, stroke_width_y: widths.strokeWidthY };
+    if (widths.lineCap !== undefined) {
+      wasmOptions.line_cap = widths.lineCap;
+    }
+    if (widths.lineJoin !== undefined) {
+      wasmOptions.line_join = widths.lineJoin;
+    }
+    if (widths.miterLimit !== undefined) {
+      wasmOptions.miter_limit = widths.miterLimit;
+    }
+    const raw = tessellator.tessellate_stroke_with_options(
+      pathD,
+      strokeWidth,
+      new Float32Array([offsetX, offsetY, scaleX, scaleY]),
+      wasmOptions
+    ) as RawMesh;
+    return convertMesh(raw);
+  }
+  if (strokeAlign !== "center") {
+    // The transform path widens strokes by sqrt(|det|) where this one uses
+    // the larger scale, so pre-scale the width to match
+    const widen = Math.max(scaleX, scaleY) / Math.sqrt(Math.abs(scaleX * scaleY));
+    const matrix: TransformMatrix = [scaleX, 0, 0, scaleY, offsetX, offsetY];
+    return tessellatePathWithTransform(tessellator, pathD, matrix, {
+      strokeWidth: strokeWidth * widen,
+      strokeAlign,
+      lineCap: widths.lineCap,
+      lineJoin: widths.lineJoin,
+      miterLimit: widths.miterLimit,
+    });
+  }
   const raw = tessellator.tessellate_stroke(
     pathD,
     strokeWidth,
@@ -111,18 +619,772 @@ export function tessellateStroke(
   return convertMesh(raw);
 }
 
+/** An affine transform as SVG's matrix(a b c d e f). */
+export type TransformMatrix = [number, number, number, number, number, number];
+
+/** A color as straight (not premultiplied) RGBA, each channel in [0, 1]. */
+export type Rgba = [number, number, number, number];
+
+export interface PathPaintOptions {
+  /** Stroke at this width in path units instead of filling */
+  strokeWidth?: number;
+  /** Which side of the outline the stroke covers, default "center" */
+  strokeAlign?: StrokeAlign;
+  /**
+   * Fill the mesh's sourceCommands and commandRanges, for mapping hits back to
+   * the path data. Inner and outer strokes leave them empty.
+   */
+  sourceCommands?: boolean;
+  /** How a fill treats overlapping subpaths, default "nonzero" */
+  fillRule?: FillRule;
+  /** The cap on the ends of open subpaths of a stroke, default "butt" */
+  lineCap?: LineCap;
+  /** The join at the corners of a stroke, default "miter" */
+  lineJoin?: LineJoin;
+  /** The longest miter join of a stroke in stroke widths, default 4 */
+  miterLimit?: number;
+}
+
+/**
+ * Tessellate a path placed by a full affine transform, so rotated icons keep
+ * mesh-space bounds. Stroke width scales exactly under rotation and uniform
+ * scale; under shear or non-uniform scale it uses one averaged width.
+ */
+export function tessellatePathWithTransform(
+  tessellator: SvgTessellator,
+  pathD: string,
+  matrix: TransformMatrix,
+  options: PathPaintOptions = {}
+): TessellatedMesh {
+  const wasmOptions: {
+    stroke_width?: number;
+    stroke_align?: StrokeAlign;
+    source_commands?: boolean;
+    fill_rule?: FillRule;
+    line_cap?: LineCap;
+    line_join?: LineJoin;
+    miter_limit?: number;
+  } = {};
+  if (options.strokeWidth !== undefined) {
+    wasmOptions.stroke_width = options.strokeWidth;
+  }
+  if (options.strokeAlign !== undefined) {
+    wasmOptions.stroke_align = options.strokeAlign;
+  }
+  if (options.sourceCommands !== undefined) {
+    wasmOptions.source_commands = options.sourceCommands;
+  }
+  if (options.fillRule !== undefined) {
+    wasmOptions.fill_rule = options.fillRule;
+  }
+  if (options.lineCap !== undefined) {
+    wasmOptions.line_cap = options.lineCap;
+  }
+  if (options.lineJoin !== undefined) {
+    wasmOptions.line_join = options.lineJoin;
+  }
+  if (options.miterLimit !== undefined) {
+    wasmOptions.miter_limit = options.miterLimit;
+  }
+  const raw = tessellator.tessellate_path_with_transform(
+    pathD,
+    new Float32Array(matrix),
+    wasmOptions
+  ) as RawMesh;
+  return convertMesh(raw);
+}
+
+/** Coordinate system of a gradient's geometry, as SVG gradientUnits */
+export type GradientUnits = "objectBoundingBox" | "userSpaceOnUse";
+
+/**
+ * A radial gradient's geometry. Defaults follow SVG: center and radius 0.5
+ * in objectBoundingBox units, with the focal point at the center.
+ */
+export interface RadialGradientOptions {
+  cx?: number;
+  cy?: number;
+  r?: number;
+  fx?: number;
+  fy?: number;
+  units?: GradientUnits;
+  /**
+   * Split triangles until interpolating the radial distance along any edge
+   * errs by at most this much, in gradient units. No refinement when unset.
+   */
+  refineTolerance?: number;
+}
+
+/** Gradient parameters resolved against the filled path */
+export interface ResolvedRadialGradient {
+  cx: number;
+  cy: number;
+  r: number;
+  fx: number;
+  fy: number;
+  units: GradientUnits;
+  /** Maps mesh coordinates to gradient units, like SVG's matrix() */
+  toGradient: TransformMatrix;
+}
+
+export interface RadialGradientMesh {
+  mesh: TessellatedMesh;
+  /** Distance of each vertex from the gradient center in gradient units */
+  radialDistances: Float32Array;
+  gradient: ResolvedRadialGradient;
+}
+
+/**
+ * Fill a path for a radial gradient, with each vertex's exact distance from
+ * the gradient center, so rings stay round on paths much larger than the
+ * gradient. An objectBoundingBox gradient is placed on the mesh bounds.
+ * Throws in builds without the gradients feature.
+ */
+export function tessellateRadialGradientFill(
+  tessellator: SvgTessellator,
+  pathD: string,
+  gradient: RadialGradientOptions = {}
+): RadialGradientMesh {
+  // Only send set fields; serde rejects an explicit undefined for f32
+  const wasmGradient: Record<string, unknown> = {};
+  for (const key of ["cx", "cy", "r", "fx", "fy", "units"] as const) {
+    if (gradient[key] !== undefined) {
+      wasmGradient[key] = gradient[key];
+    }
+  }
+  if (gradient.refineTolerance !== undefined) {
+    wasmGradient.refine_tolerance = gradient.refineTolerance;
+  }
+  const raw = tessellator.tessellate_radial_gradient_fill(pathD, wasmGradient) as {
+    mesh: RawMesh;
+    radial_distances: number[];
+    gradient: Omit<ResolvedRadialGradient, "toGradient"> & { to_gradient: TransformMatrix };
+  };
+  const { to_gradient, ...resolved } = raw.gradient;
+  return {
+    mesh: convertMesh(raw.mesh),
+    radialDistances: new Float32Array(raw.radial_distances),
+    gradient: { ...resolved, toGradient: to_gradient },
+  };
+}
+
+export interface PolygonOptions {
+  /** Close every subpath */
+  closed?: boolean;
+  /** Fill the polygon; otherwise stroke it with strokeWidth */
+  fill?: boolean;
+  strokeWidth?: number;
+}
+
+/**
+ * Tessellate a polygon or polyline from flat x, y pairs without formatting
+ * an SVG path string. A pair with a NaN coordinate starts a new subpath.
+ */
+export function tessellatePolygon(
+  tessellator: SvgTessellator,
+  points: Float32Array,
+  options: PolygonOptions = {}
+): TessellatedMesh {
+  const raw = tessellator.tessellate_polygon_f32(
+    points,
+    options.closed ?? false,
+    options.fill ?? false,
+    options.strokeWidth ?? 1
+  ) as RawMesh;
+  return convertMesh(raw);
+}
+
+/**
+ * Stroke an open polyline from flat x, y pairs. A pair with a NaN coordinate
+ * starts a new subpath.
+ */
+export function tessellatePolyline(
+  tessellator: SvgTessellator,
+  points: Float32Array,
+  strokeWidth: number
+): TessellatedMesh {
+  const raw = tessellator.tessellate_polyline_f32(points, strokeWidth) as RawMesh;
+  return convertMesh(raw);
+}
+
+export interface DashedStrokeOptions {
+  /** The cap on the ends of each dash, default "butt" */
+  lineCap?: LineCap;
+  /** The join at corners within a dash, default "miter" */
+  lineJoin?: LineJoin;
+  /** The longest miter join in stroke widths, default 4 */
+  miterLimit?: number;
+}
+
+/**
+ * Stroke a path with an SVG stroke-dasharray pattern shifted by dashOffset.
+ * Odd-length patterns repeat; patterns with a negative entry or no total
+ * length draw a solid stroke. Zero-length dashes draw dots under round or
+ * square caps.
+ */
+export function tessellateDashedStroke(
+  tessellator: SvgTessellator,
+  pathD: string,
+  strokeWidth: number,
+  dashes: number[],
+  dashOffset = 0,
+  options: DashedStrokeOptions = {}
+): TessellatedMesh {
+  const wasmOptions: {
+    line_cap?: LineCap;
+    line_join?: LineJoin;
+    miter_limit?: number;
+  } = {};
+  if (options.lineCap !== undefined) {
+    wasmOptions.line_cap = options.lineCap;
+  }
+  if (options.lineJoin !== undefined) {
+    wasmOptions.line_join = options.lineJoin;
+  }
+  if (options.miterLimit !== undefined) {
+    wasmOptions.miter_limit = options.miterLimit;
+  }
+  const raw = tessellator.tessellate_dashed_stroke(
+    pathD,
+    strokeWidth,
+    new Float32Array(dashes),
+    dashOffset,
+    wasmOptions
+  ) as RawMesh;
+  return convertMesh(raw);
+}
+
+/**
+ * The point `distance` along a path with its direction in radians, or
+ * undefined for a path with no length. Use createPathMeasure to sample one
+ * path repeatedly.
+ */
+export function pointAtLength(
+  tessellator: SvgTessellator,
+  pathD: string,
+  distance: number
+): PathSample | undefined {
+  return tessellator.point_at_length(pathD, distance);
+}
+
+/**
+ * Measure a path once for sampling it many times, such as animating along it
+ * frame by frame; sampling does not re-measure. Requires the module set up by
+ * createSvgTessellator. A tolerance of 0 uses the tessellator's. Call free()
+ * when done.
+ */
+export function createPathMeasure(pathD: string, tolerance = 0): PathMeasure {
+  return new PathMeasure(pathD, tolerance);
+}
+
+export interface TessellateOptions {
+  /**
+   * Snap horizontal and vertical lines to the pixel grid after scaling:
+   * fills to whole pixels, odd-width strokes to half-pixel centers, with
+   * stroke widths rounded to whole pixels. Curves are not moved.
+   */
+  snapAxisAligned?: boolean;
+  /** Fill each mesh's boundaryEdges, for outline and glow effects */
+  boundaryEdges?: boolean;
+  /**
+   * Paths the host animates, by index or id. Their meshes are tessellated
+   * untransformed and carry the transform instead, so they stay valid while
+   * it changes; other paths have theirs baked in. `transform`, in document
+   * units, replaces the path's own attribute.
+   */
+  dynamicPaths?: DynamicPath[];
+  /**
+   * Return the fills of paths filled with a `<pattern>` as patternFills, with
+   * the tile and where it repeats, instead of in meshes
+   */
+  patternFills?: boolean;
+  /** The user's language, as for ParseOptions.language */
+  language?: string;
+  /**
+   * The color of `currentColor`, as for ParseOptions.currentColor. Without
+   * one, meshes painted with it are black and set usesCurrentColor.
+   */
+  currentColor?: string;
+  /** The font size of `em` lengths, as for ParseOptions.fontSize */
+  fontSize?: number;
+  /**
+   * Drop paths whose data has a missing argument or unparsable token,
+   * reporting them in errors. By default such data reads as 0 where it is
+   * missing and the path draws, with the problem in warnings.
+   */
+  strict?: boolean;
+}
+
+export interface DynamicPath {
+  path: number | string;
+  transform?: TransformMatrix;
+}
+
+export type TessellationStage = "parse" | "fill" | "stroke";
+
+/** A path of a document that could not be tessellated. */
+export interface PathError {
+  pathIndex: number;
+  stage: TessellationStage;
+  message: string;
+  /** Where the path data stopped parsing, for "parse" errors */
+  issue?: PathDataIssue;
+}
+
+/** Whether a mesh of a document is a path's fill or its stroke */
+export type MeshKind = "fill" | "stroke";
+
+/**
+ * A mesh of a document with the path it was tessellated from, for matching
+ * meshes to elements without parsing the document again.
+ */
+export interface MeshEntry {
+  mesh: TessellatedMesh;
+  /** Index into ParsedSvg.paths */
+  path: number;
+  kind: MeshKind;
+  /** The path's `fill` or `stroke` as written, which the mesh's color and paint resolve */
+  paint?: string;
+  /** For a stroke, its width in user units */
+  strokeWidth?: number;
+  id?: string;
+  class?: string;
+}
+
+/**
+ * The meshes of every path that tessellated, and why the others did not. One
+ * bad path never loses the rest of the document.
+ */
+export interface SvgTessellation {
+  /** Each mesh with the path and paint it came from, in paint order */
+  meshes: MeshEntry[];
+  /** The document's size in user units, as ParsedSvg.width and height */
+  width: number;
+  height: number;
+  errors: PathError[];
+  /** Only filled when requested with TessellateOptions.patternFills */
+  patternFills: PatternFill[];
+  /**
+   * Suspicious path data that was still drawn, such as comma decimals or,
+   * without strict, a curve cut short
+   */
+  warnings: PathDataIssue[];
+  /** The document's warnings, such as undefined paints drawn black */
+  documentWarnings: string[];
+  /**
+   * Set when nothing was tessellated because the sizes left nothing to
+   * draw, as opposed to paths that tessellated to nothing.
+   */
+  degenerate?: DegenerateInput;
+}
+
+/**
+ * Where a tile repeats, in display pixels: columns by rows copies, the first
+ * at the origin, exactly covering the filled path's bounds.
+ */
+export interface TileGrid {
+  originX: number;
+  originY: number;
+  stepX: number;
+  stepY: number;
+  columns: number;
+  rows: number;
+}
+
+/**
+ * A path filled with a pattern: instance the tile across the grid and clip
+ * it to the path's fill. Tile meshes are relative to the tile's top left
+ * corner and their `path` indexes the pattern's paths.
+ */
+export interface PatternFill {
+  path: number;
+  pattern: string;
+  clip: TessellatedMesh;
+  tile: PaintedMesh[];
+  grid: TileGrid;
+}
+
+interface RawPatternFill {
+  path: number;
+  pattern: string;
+  clip: RawMesh;
+  tile: RawPaintedMesh[];
+  grid: {
+    origin_x: number;
+    origin_y: number;
+    step_x: number;
+    step_y: number;
+    columns: number;
+    rows: number;
+  };
+}
+
+interface RawPathError {
+  path_index: number;
+  stage: TessellationStage;
+  message: string;
+  issue?: PathDataIssue;
+}
+
+interface RawMeshEntry {
+  mesh: RawMesh;
+  path: number;
+  kind: MeshKind;
+  paint?: string;
+  stroke_width?: number;
+  id?: string;
+  class?: string;
+}
+
+interface RawSvgTessellation {
+  meshes: RawMeshEntry[];
+  width: number;
+  height: number;
+  errors: RawPathError[];
+  pattern_fills?: RawPatternFill[];
+  warnings?: PathDataIssue[];
+  document_warnings?: string[];
+  degenerate?: DegenerateInput;
+}
+
+/**
+ * Tessellate a document at a display size, fit into it as the document's
+ * `preserveAspectRatio` asks. Throws on a negative or NaN size; a zero one
+ * returns nothing, with degenerate set.
+ */
 export function tessellateSvg(
   tessellator: SvgTessellator,
   svgContent: string,
   displayWidth: number,
-  displayHeight: number
-): TessellatedMesh[] {
-  const rawMeshes = tessellator.tessellate_svg(
+  displayHeight: number,
+  options: TessellateOptions = {}
+): SvgTessellation {
+  const raw = tessellator.tessellate_svg(
     svgContent,
     displayWidth,
-    displayHeight
-  ) as RawMesh[];
-  return rawMeshes.map(convertMesh);
+    displayHeight,
+    rawTessellateOptions(options)
+  ) as RawSvgTessellation;
+  return convertTessellation(raw);
+}
+
+function rawTessellateOptions(options: TessellateOptions) {
+  return {
+    snap_axis_aligned: options.snapAxisAligned ?? false,
+    boundary_edges: options.boundaryEdges ?? false,
+    dynamic_paths: options.dynamicPaths ?? [],
+    pattern_fills: options.patternFills ?? false,
+    language: options.language,
+    current_color: options.currentColor,
+    font_size: options.fontSize,
+    strict: options.strict ?? false,
+  };
+}
+
+function convertTessellation(raw: RawSvgTessellation): SvgTessellation {
+  return {
+    meshes: raw.meshes.map((entry) => ({
+      mesh: convertMesh(entry.mesh),
+      path: entry.path,
+      kind: entry.kind,
+      paint: entry.paint,
+      strokeWidth: entry.stroke_width,
+      id: entry.id,
+      class: entry.class,
+    })),
+    width: raw.width,
+    height: raw.height,
+    errors: raw.errors.map((error) => ({
+      pathIndex: error.path_index,
+      stage: error.stage,
+      message: error.message,
+      issue: error.issue,
+    })),
+    patternFills: (raw.pattern_fills ?? []).map((fill) => ({
+      path: fill.path,
+      pattern: fill.pattern,
+      clip: convertMesh(fill.clip),
+      tile: convertPaintedMeshes(fill.tile),
+      grid: {
+        originX: fill.grid.origin_x,
+        originY: fill.grid.origin_y,
+        stepX: fill.grid.step_x,
+        stepY: fill.grid.step_y,
+        columns: fill.grid.columns,
+        rows: fill.grid.rows,
+      },
+    })),
+    warnings: raw.warnings ?? [],
+    documentWarnings: raw.document_warnings ?? [],
+    degenerate: raw.degenerate,
+  };
+}
+
+export interface DisplaySize {
+  width: number;
+  height: number;
+}
+
+export interface LodMeshSet {
+  displayWidth: number;
+  displayHeight: number;
+  tessellation: SvgTessellation;
+}
+
+interface RawLodMeshSet {
+  display_width: number;
+  display_height: number;
+  tessellation: RawSvgTessellation;
+}
+
+/**
+ * Tessellate an SVG at several display sizes in one call. The document is
+ * parsed once; each entry's tessellation is what tessellateSvg returns for
+ * that size with the same options. Throws on a negative or NaN size.
+ */
+export function tessellateSvgLods(
+  tessellator: SvgTessellator,
+  svgContent: string,
+  displaySizes: DisplaySize[],
+  options: TessellateOptions = {}
+): LodMeshSet[] {
+  const rawLods = tessellator.tessellate_svg_lods(
+    svgContent,
+    displaySizes,
+    rawTessellateOptions(options)
+  ) as RawLodMeshSet[];
+  return rawLods.map((lod) => {
+    return {
+      displayWidth: lod.display_width,
+      displayHeight: lod.display_height,
+      tessellation: convertTessellation(lod.tessellation),
+    };
+  });
+}
+
+/**
+ * A mesh tagged with the index of the path it came from and whether it is
+ * that path's stroke.
+ */
+export interface PaintedMesh {
+  path: number;
+  stroke: boolean;
+  mesh: TessellatedMesh;
+}
+
+interface RawPaintedMesh {
+  path: number;
+  stroke: boolean;
+  mesh: RawMesh;
+}
+
+function convertPaintedMeshes(raw: RawPaintedMesh[]): PaintedMesh[] {
+  return raw.map((painted) => {
+    return { path: painted.path, stroke: painted.stroke, mesh: convertMesh(painted.mesh) };
+  });
+}
+
+/**
+ * Tessellate an SVG coarsely for a fast first paint. Pass the handle to
+ * refineTessellation for full detail, and cancel it with
+ * cancelTessellation when done.
+ */
+export function tessellateSvgProgressive(
+  tessellator: SvgTessellator,
+  svgContent: string,
+  displayWidth: number,
+  displayHeight: number
+): { coarse: PaintedMesh[]; handle: ProgressiveId } {
+  const handle = tessellator.tessellate_svg_progressive(svgContent, displayWidth, displayHeight);
+  const coarse = convertPaintedMeshes(tessellator.take_coarse(handle) as RawPaintedMesh[]);
+  return { coarse, handle };
+}
+
+/**
+ * Tessellate the document behind a progressive handle at full detail. The
+ * meshes line up one to one with the coarse ones. Throws if the handle was
+ * cancelled or the tessellator reset since.
+ */
+export function refineTessellation(
+  tessellator: SvgTessellator,
+  handle: ProgressiveId
+): PaintedMesh[] {
+  return convertPaintedMeshes(tessellator.refine(handle) as RawPaintedMesh[]);
+}
+
+/**
+ * Drop the document behind a progressive handle. Cancelling it again does
+ * nothing but count toward tessellator.double_frees().
+ */
+export function cancelTessellation(tessellator: SvgTessellator, handle: ProgressiveId): void {
+  tessellator.cancel_progressive(handle);
+}
+
+/**
+ * Return a pooled tessellator to the state of a new one: scratch memory is
+ * released and memory report counters are zeroed. With keepConfig, settings
+ * such as consistent winding survive.
+ */
+export function resetTessellator(tessellator: SvgTessellator, keepConfig = true): void {
+  tessellator.reset(keepConfig);
+}
+
+/**
+ * Release scratch memory retained between calls, keeping settings and
+ * counters.
+ */
+export function shrinkToFit(tessellator: SvgTessellator): void {
+  tessellator.shrink_to_fit();
+}
+
+export interface MemoryReport {
+  arenaCapacityBytes: number;
+  arenaPeakBytes: number;
+  arenaResets: number;
+}
+
+interface RawMemoryReport {
+  arena_capacity_bytes: number;
+  arena_peak_bytes: number;
+  arena_resets: number;
+}
+
+/**
+ * Report the scratch memory the tessellator retains between calls.
+ */
+export function memoryReport(tessellator: SvgTessellator): MemoryReport {
+  const raw = tessellator.memory_report() as RawMemoryReport;
+  return {
+    arenaCapacityBytes: raw.arena_capacity_bytes,
+    arenaPeakBytes: raw.arena_peak_bytes,
+    arenaResets: raw.arena_resets,
+  };
+}
+
+/**
+ * Rasterize a mesh on the CPU into RGBA8 pixels, for environments without a
+ * GPU such as tests and thumbnail generation. `color` is packed 0xRRGGBBAA.
+ */
+export function rasterizeMesh(
+  tessellator: SvgTessellator,
+  mesh: TessellatedMesh,
+  width: number,
+  height: number,
+  color: number
+): Uint8Array {
+  const raw: RawMesh = {
+    vertices: Array.from(mesh.vertices),
+    indices: Array.from(mesh.indices),
+    bounds: mesh.bounds,
+    paint_order: mesh.paintOrder,
+  };
+  return tessellator.rasterize_mesh(raw, width, height, color);
+}
+
+/**
+ * Tessellate and rasterize a whole SVG on the CPU into RGBA8 pixels, painting
+ * each path with its own fill and stroke colors.
+ */
+export function rasterizeSvg(
+  tessellator: SvgTessellator,
+  svgContent: string,
+  width: number,
+  height: number
+): Uint8Array {
+  return tessellator.rasterize_svg(svgContent, width, height);
+}
+
+/**
+ * A pre-tessellated mesh with its resolved RGBA color, null when the paint
+ * is transparent.
+ */
+export interface MeshAsset {
+  mesh: TessellatedMesh;
+  color: [number, number, number, number] | null;
+  stroke: boolean;
+}
+
+interface RawMeshAsset {
+  mesh: RawMesh;
+  color?: [number, number, number, number] | null;
+  stroke: boolean;
+}
+
+/**
+ * Decode a mesh asset blob produced at build time by the native
+ * encode_meshes, without parsing any SVG. Throws on corrupt or newer data.
+ */
+export function decodeMeshes(tessellator: SvgTessellator, bytes: Uint8Array): MeshAsset[] {
+  const rawAssets = tessellator.decode_meshes(bytes) as RawMeshAsset[];
+  return rawAssets.map(convertMeshAsset);
+}
+
+function convertMeshAsset(asset: RawMeshAsset): MeshAsset {
+  return {
+    mesh: convertMesh(asset.mesh),
+    color: asset.color ?? null,
+    stroke: asset.stroke,
+  };
+}
+
+/**
+ * Load a scene bundle compiled at build time by the native compile_scene.
+ * Throws on corrupt or newer data. Free the scene when done.
+ */
+export function loadScene(tessellator: SvgTessellator, bytes: Uint8Array): Scene {
+  return tessellator.load_scene(bytes);
+}
+
+/**
+ * One named SVG of a scene at one compiled display size, with its meshes and
+ * their theme-resolved colors.
+ */
+export interface SceneEntry {
+  name: string;
+  displayWidth: number;
+  displayHeight: number;
+  meshes: MeshAsset[];
+}
+
+interface RawSceneEntry {
+  name: string;
+  display_width: number;
+  display_height: number;
+  meshes: RawMeshAsset[];
+}
+
+/**
+ * Look up `name` in a loaded scene, at the smallest compiled size covering
+ * the display size, or the largest compiled size when none does. Returns
+ * null when the scene has no such name.
+ */
+export function getSceneMesh(
+  tessellator: SvgTessellator,
+  scene: Scene,
+  name: string,
+  displayWidth: number,
+  displayHeight: number
+): SceneEntry | null {
+  const raw = tessellator.get_scene_mesh(scene, name, displayWidth, displayHeight) as
+    | RawSceneEntry
+    | null
+    | undefined;
+  if (!raw) {
+    return null;
+  }
+  return {
+    name: raw.name,
+    displayWidth: raw.display_width,
+    displayHeight: raw.display_height,
+    meshes: raw.meshes.map(convertMeshAsset),
+  };
 }
 
-export type { InitOutput };
+export type {
+  InitOutput,
+  PathMeasure,
+  PathSample,
+  ProgressiveId,
+  Scene,
+  SvgStreamParser,
+};
@@ -0,0 +1,244 @@
+//! Float comparisons with explicit tolerances, shared by the layout,
+//! shaper and svg packages.
+//!
+//! Checks for touching edges, degenerate geometry and zero sizes go through
+//! a named `Tolerance` rather than an exact compare or an ad hoc epsilon.
+//! Taffy, lyon, cosmic-text and swash move their outputs slightly between
+//! versions, so tests compare results against golden files with the same
+//! tolerances; the `goldens` feature adds `assert_matches_goldens!` for
+//! them.
+
+/// How far apart two floats can be and still compare equal. The bounds are
+/// alternatives: values are close when any one of them holds, so
+/// `Tolerance { ulps: 4, ..Tolerance::absolute(0.01) }` accepts either.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tolerance {
+    /// Largest difference allowed regardless of magnitude
+    pub absolute: f32,
+    /// Largest difference allowed as a fraction of the larger magnitude
+    pub relative: f32,
+    /// Largest number of representable floats between the two
+    pub ulps: u32,
+}
+
+impl Tolerance {
+    pub const fn absolute(absolute: f32) -> Self {
+        Self {
+            absolute,
+            relative: 0.0,
+            ulps: 0,
+        }
+    }
+
+    /// Whether `a` and `b` are within the tolerance. NaN is never close to
+    /// anything, and an infinity only to itself.
+    pub fn eq(self, a: f32, b: f32) -> bool {
+        if a == b {
+            return true;
+        }
+        if !a.is_finite() || !b.is_finite() {
+            return false;
+        }
+        let difference = (a - b).abs();
+        difference <= self.absolute
+            || difference <= self.relative * a.abs().max(b.abs())
+            || ulps_between(a, b) <= self.ulps
+    }
+
+    /// Whether `value` is zero within the tolerance. Only the absolute and
+    /// ulps bounds matter here, since nothing is relatively close to zero.
+    pub fn is_zero(self, value: f32) -> bool {
+        self.eq(value, 0.0)
+    }
+}
+
+/// Number of representable floats between `a` and `b`, counting both zeros
+/// as one.
+fn ulps_between(a: f32, b: f32) -> u32 {
+    // Sign and magnitude to a line where adjacent floats are adjacent integers
+    let ordered = |value: f32| {
+        let magnitude = i64::from(value.to_bits() & 0x7fff_ffff);
+        if value.is_sign_negative() {
+            -magnitude
+        } else {
+            magnitude
+        }
+    };
+    u32::try_from((ordered(a) - ordered(b)).unsigned_abs()).unwrap_or(u32::MAX)
+}
+
+/// Expected numbers kept in `goldens/<name>.txt`, one `key = value ~
+/// tolerance` per line, so a dependency bump that moves outputs shows up
+/// as a diff of numbers to review. A tolerance is any of an absolute
+/// difference (`0.001`), a relative one (`0.01%`) and a count of ulps
+/// (`4ulps`), separated by `|`. Lines starting with `#` are comments.
+#[cfg(feature = "goldens")]
+#[doc(hidden)]
+pub mod golden {
+    use super::Tolerance;
+
+    /// Tolerance written for values new to a golden file.
+    const DEFAULT_TOLERANCE: &str = "0.001";
+
+    enum Line {
+        Text(String),
+        Entry {
+            key: String,
+            value: f32,
+            tolerance: String,
+        },
+    }
+
+    fn parse_tolerance(text: &str) -> Tolerance {
+        let mut tolerance = Tolerance::default();
+        for bound in text.split('|').map(str::trim) {
+            let parse = |number: &str| {
+                number
+                    .trim()
+                    .parse::<f32>()
+                    .unwrap_or_else(|_| panic!("bad tolerance {:?}", text))
+            };
+            if let Some(ulps) = bound.strip_suffix("ulps") {
+                tolerance.ulps = ulps
+                    .trim()
+                    .parse()
+                    .unwrap_or_else(|_| panic!("bad tolerance {:?}", text));
+            } else if let Some(percent) = bound.strip_suffix('%') {
+                tolerance.relative = parse(percent) / 100.0;
+            } else {
+                tolerance.absolute = parse(bound);
+            }
+        }
+        tolerance
+    }
+
+    fn parse(path: &str, text: &str) -> Vec<Line> {
+        text.lines()
+            .map(|line| {
+                let trimmed = line.trim();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    return Line::Text(line.to_string());
+                }
+                let entry = trimmed.split_once('=').and_then(|(key, rest)| {
+                    let (value, tolerance) = rest.split_once('~')?;
+                    Some(Line::Entry {
+                        key: key.trim().to_string(),
+                        value: value.trim().parse().ok()?,
+                        tolerance: tolerance.trim().to_string(),
+                    })
+                });
+                entry.unwrap_or_else(|| panic!("{}: bad line {:?}", path, line))
+            })
+            .collect()
+    }
+
+    fn lookup(values: &[(String, f32)], key: &str) -> Option<f32> {
+        values
+            .iter()
+            .find(|(other, _)| other == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// The file with each entry's value replaced, entries no longer produced
+    /// dropped and new ones appended.
+    fn rewrite(lines: &[Line], values: &[(String, f32)]) -> String {
+        let mut out = String::new();
+        for line in lines {
+            let text = match line {
+                Line::Text(text) => text.clone(),
+                Line::Entry { key, tolerance, .. } => match lookup(values, key) {
+                    Some(value) => format!("{} = {} ~ {}", key, value, tolerance),
+                    None => continue,
+                },
+            };
+            out.push_str(&text);
+            out.push('\n');
+        }
+        let known = |key: &str| {
+            lines
+                .iter()
+                .any(|line| matches!(line, Line::Entry { key: known, .. } if known == key))
+        };
+        for (key, value) in values.iter().filter(|(key, _)| !known(key)) {
+            out.push_str(&format!("{} = {} ~ {}\n", key, value, DEFAULT_TOLERANCE));
+        }
+        out
+    }
+
+    /// Called through `assert_matches_goldens!`, which names the calling
+    /// package's `goldens` directory.
+    pub fn assert_matches_goldens(directory: &str, name: &str, values: &[(String, f32)]) {
+        let path = format!("{}/{}.txt", directory, name);
+        for (index, (key, _)) in values.iter().enumerate() {
+            assert!(
+                values[..index].iter().all(|(other, _)| other != key),
+                "{}: {} is given twice",
+                name,
+                key
+            );
+        }
+        let existing = std::fs::read_to_string(&path).ok();
+        let lines = existing
+            .as_deref()
+            .map_or_else(Vec::new, |text| parse(&path, text));
+
+        if std::env::var_os("GLADE_UPDATE_GOLDENS").is_some() {
+            std::fs::create_dir_all(directory).unwrap();
+            std::fs::write(&path, rewrite(&lines, values)).unwrap();
+            return;
+        }
+
+        assert!(
+            existing.is_some(),
+            "missing {}; run with GLADE_UPDATE_GOLDENS=1",
+            path
+        );
+        let mut mismatches = Vec::new();
+        let mut expected_keys = Vec::new();
+        for line in &lines {
+            let Line::Entry {
+                key,
+                value,
+                tolerance,
+            } = line
+            else {
+                continue;
+            };
+            expected_keys.push(key.as_str());
+            match lookup(values, key) {
+                None => mismatches.push(format!("{}: no longer produced", key)),
+                Some(got) if !parse_tolerance(tolerance).eq(*value, got) => {
+                    mismatches.push(format!("{}: {} ~ {}, got {}", key, value, tolerance, got));
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, value) in values {
+            if !expected_keys.contains(&key.as_str()) {
+                mismatches.push(format!("{}: {} is not in the golden", key, value));
+            }
+        }
+        assert!(
+            mismatches.is_empty(),
+            "{} differs from {}; rerun with GLADE_UPDATE_GOLDENS=1 and review the diff:\n{}",
+            name,
+            path,
+            mismatches.join("\n")
+        );
+    }
+}
+
+/// Compare named values with `goldens/<name>.txt` in the calling package.
+/// Set `GLADE_UPDATE_GOLDENS=1` to rewrite the file's values instead,
+/// keeping its comments and each existing value's tolerance.
+#[cfg(feature = "goldens")]
+#[macro_export]
+macro_rules! assert_matches_goldens {
+    ($name:expr, $values:expr) => {
+        $crate::golden::assert_matches_goldens(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/goldens"),
+            $name,
+            $values,
+        )
+    };
+}
@@ -0,0 +1,59 @@
+// Collects criterion results for the wasm packages into a single JSON file
+// that consuming apps can diff in CI. Run after `cargo bench --workspace`.
+
+import { existsSync, readdirSync, readFileSync, statSync, writeFileSync } from "fs";
+import { join } from "path";
+
+const FRAME_BUDGET_NS = 1_000_000_000 / 60;
+
+const rootDir = join(import.meta.dirname, "..");
+const criterionDir = join(rootDir, "target", "criterion");
+const outputPath = join(rootDir, "bench.baseline.json");
+
+type BaselineEntry = {
+  meanNs: number;
+  medianNs: number;
+  frameBudgetPercent: number;
+};
+
+function findResultDirs(dir: string): string[] {
+  const found: string[] = [];
+  for (const name of readdirSync(dir)) {
+    const path = join(dir, name);
+    if (!statSync(path).isDirectory() || name === "report") {
+      continue;
+    }
+    if (name === "new") {
+      found.push(path);
+    } else {
+      found.push(...findResultDirs(path));
+    }
+  }
+  return found;
+}
+
+if (!existsSync(criterionDir)) {
+  console.error("No criterion results found; run `cargo bench --workspace` first");
+  process.exit(1);
+}
+
+const results: Record<string, BaselineEntry> = {};
+for (const dir of findResultDirs(criterionDir)) {
+  const benchmark = JSON.parse(readFileSync(join(dir, "benchmark.json"), "utf8"));
+  const estimates = JSON.parse(readFileSync(join(dir, "estimates.json"), "utf8"));
+  const meanNs = estimates.mean.point_estimate;
+  results[benchmark.full_id] = {
+    meanNs: Math.round(meanNs),
+    medianNs: Math.round(estimates.median.point_estimate),
+    frameBudgetPercent: Math.round((meanNs / FRAME_BUDGET_NS) * 10_000) / 100,
+  };
+}
+
+const sorted = Object.fromEntries(
+  Object.entries(results).sort(([a], [b]) => a.localeCompare(b))
+);
+writeFileSync(
+  outputPath,
+  JSON.stringify({ frameBudgetNs: Math.round(FRAME_BUDGET_NS), results: sorted }, null, 2) + "\n"
+);
+console.log(`Wrote ${Object.keys(sorted).length} results to bench.baseline.json`);
@@ -0,0 +1,42 @@
+#!/usr/bin/env bash
+
+# Compare the size of each wasm package built with its default features
+# against its minimal profile, the package's build.minimal script, after
+# wasm-bindgen and wasm-opt, as they ship. Needs the wasm32-unknown-unknown target, and
+# wasm-bindgen and wasm-opt on the PATH; the wasm-bindgen CLI must match the
+# crate version in Cargo.lock.
+
+set -euo pipefail
+
+ROOT="$(cd "$(dirname "$0")/.." && pwd)"
+OUT="$ROOT/target/wasm-sizes"
+# The wasm features rustc enables for wasm32-unknown-unknown by default
+WASM_FEATURES="--enable-bulk-memory --enable-multivalue --enable-mutable-globals \
+--enable-nontrapping-float-to-int --enable-reference-types --enable-sign-ext"
+WASM_OPT_FLAGS="${WASM_OPT_FLAGS:--Oz $WASM_FEATURES}"
+
+rm -rf "$OUT"
+mkdir -p "$OUT"
+
+printf "%-8s %-8s %12s %12s\n" "package" "profile" "bytes" "gzip"
+for package in layout shaper svg; do
+    for profile in default minimal; do
+        features=()
+        if [[ "$profile" == "minimal" ]]; then
+            features=(--no-default-features)
+            if [[ "$package" != "shaper" ]]; then
+                features+=(--features arena)
+            fi
+        fi
+        cargo build --quiet --release --target wasm32-unknown-unknown \
+            --manifest-path "$ROOT/Cargo.toml" -p "$package" --lib "${features[@]}"
+        dir="$OUT/$package-$profile"
+        wasm-bindgen --target web --out-dir "$dir" \
+            "$ROOT/target/wasm32-unknown-unknown/release/$package.wasm"
+        wasm="$dir/${package}_bg.wasm"
+        # shellcheck disable=SC2086
+        wasm-opt $WASM_OPT_FLAGS "$wasm" -o "$wasm"
+        printf "%-8s %-8s %12d %12d\n" "$package" "$profile" \
+            "$(wc -c < "$wasm")" "$(gzip -9c "$wasm" | wc -c)"
+    done
+done